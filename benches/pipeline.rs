@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+use pilipili_strm::{
+    core::config::Config,
+    infrastructure::fs::{DirScanner, SyncPlan},
+    test_util::VirtualLibrary
+};
+
+/// Shows in the generated benchmark library.
+const BENCH_SHOWS: usize = 20;
+
+/// Episodes per show in the generated benchmark library.
+const BENCH_EPISODES: usize = 50;
+
+fn bench_library() -> VirtualLibrary {
+    VirtualLibrary::builder()
+        .with_shows(BENCH_SHOWS)
+        .with_episodes(BENCH_EPISODES)
+        .with_sidecar_chance(0.5)
+        .build()
+}
+
+fn scan(c: &mut Criterion) {
+    let library = bench_library();
+    let dir = tempfile::tempdir().expect("temp dir");
+    library.write_to(dir.path()).expect("write library");
+
+    let mut group = c.benchmark_group("scan");
+    group.throughput(Throughput::Elements(library.files().len() as u64));
+    group.bench_function("dir_scanner", |b| b.iter(|| DirScanner::scan(dir.path()).expect("scan")));
+    group.finish();
+}
+
+fn filter(c: &mut Criterion) {
+    let library = bench_library();
+    let config = Config::from_toml(r#"
+        [[libraries]]
+        name = "bench"
+        source = "/bench"
+        exclude_suffixes = ["nfo", "jpg"]
+        exclude_regex = "(?i)sample|trailer"
+
+        [[libraries.destinations]]
+        path = "/srv/bench"
+    "#).expect("bench config");
+    let config = config.library("bench").expect("bench library");
+
+    let mut group = c.benchmark_group("filter");
+    group.throughput(Throughput::Elements(library.files().len() as u64));
+    group.bench_function("matches_filters", |b| {
+        b.iter(|| {
+            library.files()
+                .iter()
+                .filter(|file| config.matches_filters(Path::new(&file.path)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn plan(c: &mut Criterion) {
+    let output: String = bench_library()
+        .files()
+        .iter()
+        .map(|file| format!(">f+++++++++ {}\n", file.path.display()))
+        .collect();
+
+    let mut group = c.benchmark_group("plan");
+    group.throughput(Throughput::Bytes(output.len() as u64));
+    group.bench_function("from_itemized_output", |b| {
+        b.iter_batched(|| output.clone(), |output| SyncPlan::from_itemized_output(&output), BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scan, filter, plan);
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+//! Generates `.strm` files for a media tree without syncing them anywhere.
+//!
+//! The target mirrors the source, with a `.strm` file per media file and
+//! copies of the `.nfo` files, posters and subtitles next to them.
+//! Unchanged files aren't rewritten, so running it again is cheap.
+//!
+//! ```text
+//! cargo run --example generate_only -- /mnt/media/movies /srv/strm/movies
+//! ```
+use std::{env, path::Path};
+
+use pilipili_strm::core::strm::{StrmGenerator, StrmLayout};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let (Some(source), Some(target)) = (args.next(), args.next()) else {
+        return Err("Usage: generate_only SOURCE TARGET".into());
+    };
+
+    let generator = StrmGenerator::new(&source, &target).with_layout(StrmLayout::Mirror);
+    let written = generator.generate()?;
+    // Removes the .strm files of media deleted from the source
+    let pruned = generator.prune_orphans(Path::new(""))?;
+
+    println!("Wrote {} files to {}, removed {} orphans", written.len(), target, pruned.len());
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Sends a notification through the Telegram bot of a configuration
+//! file, e.g. from a script running its own syncs.
+//!
+//! The message is rendered from the configured templates, and queued for
+//! a later retry if Telegram can't be reached.
+//!
+//! ```text
+//! cargo run --example notify_only -- config.toml movies 3
+//! ```
+use std::{env, fs};
+
+use pilipili_strm::core::{
+    config::Config,
+    notification::{NotificationKind, Notifier}
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [path, library, count] = args.as_slice() else {
+        return Err("Usage: notify_only CONFIG LIBRARY COUNT".into());
+    };
+
+    Config::apply_toml(&fs::read_to_string(path)?)?;
+    let notifier = Notifier::from_config(&Config::get()).ok_or("No Telegram bot configured")?;
+    notifier.notify(
+        NotificationKind::SyncCompleted,
+        &[("library", library), ("count", count), ("duration", "0s")],
+    )?;
+    Ok(())
+}
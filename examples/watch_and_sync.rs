@@ -0,0 +1,44 @@
+//! Watches every library of a configuration file and syncs it to its
+//! destinations whenever its source changes.
+//!
+//! Sources are usually trees of `.strm` files generated next to the media,
+//! see `generate_only`, and destinations the media server's library.
+//!
+//! ```text
+//! cargo run --example watch_and_sync -- config.toml
+//! ```
+use std::{env, fs};
+
+use pilipili_strm::{
+    core::{config::Config, library::LibrarySync},
+    infrastructure::{
+        fs::FileWatchable,
+        logger::{LogLevel, LoggerBuilder}
+    }
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    LoggerBuilder::default().with_level(LogLevel::Info).init();
+
+    let path = env::args().nth(1).ok_or("Usage: watch_and_sync CONFIG")?;
+    Config::apply_toml(&fs::read_to_string(path)?)?;
+    let config = Config::get();
+
+    let mut watchers = Vec::new();
+    for library in &config.libraries {
+        let sync = LibrarySync::new(library.clone());
+        // Catch up on changes made while nothing was watching
+        if let Err(e) = sync.sync() {
+            eprintln!("Initial sync of '{}' failed: {:#}", sync.name(), e);
+        }
+        watchers.push(sync.watch()?);
+    }
+
+    println!("Watching {} libraries, press Ctrl+C to stop", watchers.len());
+    tokio::signal::ctrl_c().await?;
+    for watcher in watchers.iter_mut() {
+        watcher.stop();
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::infrastructure::network::{encode_url_path, HttpMethod, NetworkTarget, NetworkTask};
+
+/// Timeout for listing a single directory.
+const ALIST_LIST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Response code Alist reports for successful requests.
+pub(crate) const ALIST_SUCCESS_CODE: i64 = 200;
+
+/// An Alist server and the token used to access it.
+#[derive(Debug, Clone)]
+pub struct AlistEndpoint {
+
+    /// Base URL of the Alist server (e.g. `http://127.0.0.1:5244`)
+    pub base_url: String,
+
+    /// Token sent in the `Authorization` header, if the server requires one
+    pub token: Option<String>,
+}
+
+impl AlistEndpoint {
+
+    /// Creates an endpoint for anonymous access.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            token: None,
+        }
+    }
+
+    /// Sets the token sent in the `Authorization` header.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Returns the direct link of a file, which Alist redirects to the storage.
+    ///
+    /// # Arguments
+    /// * `path` - Absolute path of the file on the server
+    /// * `sign` - The entry's signature, empty if the server doesn't sign links
+    pub fn direct_link(&self, path: &str, sign: &str) -> String {
+        let link = format!("{}/d/{}", self.base_url.trim_end_matches('/'), encode_url_path(path));
+        if sign.is_empty() {
+            link
+        } else {
+            format!("{}?sign={}", link, sign)
+        }
+    }
+}
+
+/// Requests of the Alist API.
+#[derive(Debug, Clone)]
+pub enum AlistAPI {
+
+    /// List the entries of a directory
+    ///
+    /// `refresh` asks Alist to bypass its own listing cache.
+    List { endpoint: AlistEndpoint, path: String, refresh: bool },
+}
+
+impl AlistAPI {
+
+    /// Returns the server the request is sent to.
+    fn endpoint(&self) -> &AlistEndpoint {
+        match self {
+            AlistAPI::List { endpoint, .. } => endpoint,
+        }
+    }
+}
+
+impl NetworkTarget for AlistAPI {
+
+    fn base_url(&self) -> String {
+        self.endpoint().base_url.clone()
+    }
+
+    fn path(&self) -> String {
+        match self {
+            AlistAPI::List { .. } => "api/fs/list".to_string(),
+        }
+    }
+
+    fn method(&self) -> HttpMethod {
+        HttpMethod::Post
+    }
+
+    fn task(&self) -> NetworkTask {
+        match self {
+            AlistAPI::List { path, refresh, .. } => NetworkTask::RequestJson(json!({
+                "path": path,
+                "password": "",
+                "page": 1,
+                "per_page": 0,
+                "refresh": refresh,
+            })),
+        }
+    }
+
+    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
+        let mut headers = vec![("content-type", "application/json".to_string())];
+        if let Some(token) = &self.endpoint().token {
+            headers.push(("authorization", token.clone()));
+        }
+        Some(headers)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(ALIST_LIST_TIMEOUT)
+    }
+}
+
+/// Envelope of every Alist API response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlistResponse<T> {
+
+    /// Alist status code, `200` on success
+    pub code: i64,
+
+    /// Human-readable status message
+    #[serde(default)]
+    pub message: String,
+
+    /// Payload of successful requests
+    pub data: Option<T>,
+}
+
+/// Payload of a directory listing.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlistListing {
+
+    /// Entries of the directory, `null` for empty directories
+    #[serde(default)]
+    pub content: Option<Vec<AlistEntry>>,
+}
+
+/// A file or directory in an Alist listing.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AlistEntry {
+
+    /// File name without the directory
+    pub name: String,
+
+    /// File size in bytes, zero for directories
+    #[serde(default)]
+    pub size: u64,
+
+    /// Whether the entry is a directory
+    #[serde(default)]
+    pub is_dir: bool,
+
+    /// Last modification time as reported by the storage (RFC 3339)
+    #[serde(default)]
+    pub modified: String,
+
+    /// Signature required by direct links when the server signs them, empty otherwise
+    #[serde(default)]
+    pub sign: String,
+}
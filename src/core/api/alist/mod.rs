@@ -0,0 +1,8 @@
+//! Alist file listing API.
+//!
+//! This module describes the requests used to list directories of an
+//! Alist server, so remote sources can be polled for changes.
+//! 
+pub mod alist_api;
+
+pub use alist_api::*;
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crate::infrastructure::network::{HttpMethod, NetworkTarget, NetworkTask};
+
+/// Timeout for downloading a single image.
+const ARTWORK_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Requests downloading artwork.
+#[derive(Debug, Clone)]
+pub enum ArtworkAPI {
+
+    /// Download an image from an absolute URL
+    Download { url: String },
+}
+
+impl ArtworkAPI {
+
+    /// Splits the URL into its origin and the rest.
+    fn split_url(&self) -> (&str, &str) {
+        let ArtworkAPI::Download { url } = self;
+        let path_start = url
+            .split_once("://")
+            .and_then(|(scheme, rest)| rest.find('/').map(|index| scheme.len() + 3 + index))
+            .unwrap_or(url.len());
+        url.split_at(path_start)
+    }
+}
+
+impl NetworkTarget for ArtworkAPI {
+
+    fn base_url(&self) -> String {
+        self.split_url().0.to_string()
+    }
+
+    fn path(&self) -> String {
+        self.split_url().1.to_string()
+    }
+
+    fn method(&self) -> HttpMethod {
+        HttpMethod::Get
+    }
+
+    fn task(&self) -> NetworkTask {
+        NetworkTask::RequestPlain
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(ARTWORK_DOWNLOAD_TIMEOUT)
+    }
+}
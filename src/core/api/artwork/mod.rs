@@ -0,0 +1,8 @@
+//! Artwork download API.
+//!
+//! This module describes the request used to download posters and other
+//! artwork from TMDB, Emby or any other server by URL.
+//! 
+pub mod artwork_api;
+
+pub use artwork_api::*;
@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::Duration
+};
+
+use serde_json::json;
 
 use crate::core::config::Config;
 use crate::infrastructure::network::{
@@ -7,8 +12,15 @@ use crate::infrastructure::network::{
     NetworkTask
 };
 
+/// Timeout for Emby API calls.
+const EMBY_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub enum EmbyAPI {
     GetUser { user_id: String },
+
+    /// Reports changed folders, so Emby rescans only those
+    /// (`paths` as the server sees them).
+    MediaUpdated { paths: Vec<String> },
 }
 
 impl NetworkTarget for EmbyAPI {
@@ -22,11 +34,15 @@ impl NetworkTarget for EmbyAPI {
             EmbyAPI::GetUser { user_id, .. } => {
                 format!("emby/Users/{}", user_id)
             }
+            EmbyAPI::MediaUpdated { .. } => "emby/Library/Media/Updated".to_string(),
         }
     }
 
     fn method(&self) -> HttpMethod {
-        HttpMethod::Get
+        match self {
+            EmbyAPI::GetUser { .. } => HttpMethod::Get,
+            EmbyAPI::MediaUpdated { .. } => HttpMethod::Post,
+        }
     }
 
     fn task(&self) -> NetworkTask {
@@ -37,16 +53,30 @@ impl NetworkTarget for EmbyAPI {
                 params.insert("api_key".to_string(), api_key);
                 NetworkTask::RequestParameters(params)
             }
+            EmbyAPI::MediaUpdated { paths } => {
+                let updates: Vec<_> = paths
+                    .iter()
+                    .map(|path| json!({ "Path": path, "UpdateType": "Modified" }))
+                    .collect();
+                NetworkTask::RequestJson(json!({ "Updates": updates }))
+            }
         }
     }
 
     fn headers(&self) -> Option<Vec<(&'static str, String)>> {
-        let base_url = Config::get().emby.base_url.clone();
+        let config = Config::get();
+        let base_url = config.emby.base_url.clone();
         Some(vec![
             ("accept", "application/json".to_string()),
+            // JSON requests carry no query parameters, so the key goes in a header
+            ("X-Emby-Token", config.emby.api_key.clone()),
             ("origin", base_url.clone()),
             ("referer", format!("{}/", base_url)),
             ("user-agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36".to_string()),
         ])
     }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(EMBY_DEFAULT_TIMEOUT)
+    }
 }
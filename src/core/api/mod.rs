@@ -1,5 +1,17 @@
+pub mod alist;
+pub mod artwork;
 pub mod emby;
+pub mod s3;
+pub mod strm;
 pub mod telegram;
+pub mod upload;
+pub mod webdav;
 
+pub use alist::*;
+pub use artwork::*;
 pub use emby::*;
-pub use telegram::*;
\ No newline at end of file
+pub use s3::*;
+pub use strm::*;
+pub use telegram::*;
+pub use upload::*;
+pub use webdav::*;
\ No newline at end of file
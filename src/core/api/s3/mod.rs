@@ -0,0 +1,10 @@
+//! S3-compatible object storage API.
+//!
+//! This module describes the requests used to list, upload and delete
+//! objects in S3, MinIO or R2 buckets, signed with AWS Signature Version 4.
+//! 
+pub mod s3_api;
+pub mod s3_signature;
+
+pub use s3_api::*;
+pub use s3_signature::*;
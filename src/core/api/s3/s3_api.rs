@@ -0,0 +1,287 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Error, Result};
+use quick_xml::{events::Event, Reader};
+use time::{Date, Month, OffsetDateTime, Time};
+
+use crate::infrastructure::network::{encode_url_path, HttpMethod, NetworkTarget, NetworkTask};
+use super::s3_signature::{S3CanonicalRequest, S3Credentials, S3Signature};
+
+/// Timeout for listing a single page of objects.
+const S3_LIST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Timeout for uploading a single object.
+const S3_PUT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Timeout for deleting a single object.
+const S3_DELETE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A bucket of an S3-compatible service and the credentials used to access it.
+///
+/// Objects are addressed path-style, as `{endpoint}/{bucket}/{key}`, which
+/// MinIO and R2 support without DNS for every bucket.
+#[derive(Debug, Clone)]
+pub struct S3Bucket {
+
+    /// Service URL, e.g. `https://s3.eu-west-1.amazonaws.com` or `http://minio:9000`
+    pub endpoint: String,
+
+    /// Bucket name
+    pub name: String,
+
+    /// Keys and region requests are signed with
+    pub credentials: S3Credentials,
+}
+
+impl S3Bucket {
+
+    /// Returns the `Host` header and the path of the endpoint, without a
+    /// trailing slash.
+    ///
+    /// Default ports are left out of the host, as HTTP clients do.
+    fn host_and_path(&self) -> (String, String) {
+        let (scheme, rest) = self.endpoint.split_once("://").unwrap_or(("https", &self.endpoint));
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = match (scheme.to_ascii_lowercase().as_str(), host.rsplit_once(':')) {
+            ("http", Some((name, "80"))) | ("https", Some((name, "443"))) => name,
+            _ => host,
+        };
+        let path = path.trim_end_matches('/');
+        let path = if path.is_empty() { String::new() } else { format!("/{}", path) };
+        (host.to_string(), path)
+    }
+}
+
+/// Requests to S3-compatible object storage.
+#[derive(Debug, Clone)]
+pub enum S3API {
+
+    /// List a page of the objects whose keys start with `prefix`
+    ListObjects {
+        bucket: S3Bucket,
+        prefix: String,
+        continuation_token: Option<String>,
+    },
+
+    /// Upload an object, replacing any object with the same key
+    PutObject { bucket: S3Bucket, key: String, data: Vec<u8> },
+
+    /// Delete an object
+    DeleteObject { bucket: S3Bucket, key: String },
+}
+
+impl S3API {
+
+    /// Returns the bucket the request is sent to.
+    fn bucket(&self) -> &S3Bucket {
+        match self {
+            S3API::ListObjects { bucket, .. }
+            | S3API::PutObject { bucket, .. }
+            | S3API::DeleteObject { bucket, .. } => bucket,
+        }
+    }
+
+    /// Returns the encoded path below the endpoint, without the query.
+    fn object_path(&self) -> String {
+        let bucket = encode_url_path(&self.bucket().name);
+        match self {
+            S3API::ListObjects { .. } => bucket,
+            S3API::PutObject { key, .. } | S3API::DeleteObject { key, .. } => {
+                format!("{}/{}", bucket, encode_url_path(key))
+            }
+        }
+    }
+
+    /// Returns the encoded query string, with parameters sorted by name
+    /// as signatures require.
+    fn query(&self) -> String {
+        match self {
+            S3API::ListObjects { prefix, continuation_token, .. } => {
+                let mut query = String::new();
+                if let Some(token) = continuation_token {
+                    query.push_str(&format!("continuation-token={}&", Self::encode_query_value(token)));
+                }
+                query.push_str(&format!("list-type=2&prefix={}", Self::encode_query_value(prefix)));
+                query
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Percent-encodes a query value, slashes included.
+    fn encode_query_value(value: &str) -> String {
+        value.split('/').map(encode_url_path).collect::<Vec<_>>().join("%2F")
+    }
+
+    /// Returns the body sent with the request.
+    fn payload(&self) -> &[u8] {
+        match self {
+            S3API::PutObject { data, .. } => data,
+            _ => &[],
+        }
+    }
+}
+
+impl NetworkTarget for S3API {
+
+    fn base_url(&self) -> String {
+        self.bucket().endpoint.clone()
+    }
+
+    fn path(&self) -> String {
+        // The query is encoded here, since signatures need the exact bytes sent
+        match self.query() {
+            query if query.is_empty() => self.object_path(),
+            query => format!("{}?{}", self.object_path(), query),
+        }
+    }
+
+    fn method(&self) -> HttpMethod {
+        match self {
+            S3API::ListObjects { .. } => HttpMethod::Get,
+            S3API::PutObject { .. } => HttpMethod::Put,
+            S3API::DeleteObject { .. } => HttpMethod::Delete,
+        }
+    }
+
+    fn task(&self) -> NetworkTask {
+        match self {
+            S3API::PutObject { data, .. } => NetworkTask::RequestBytes(data.clone()),
+            _ => NetworkTask::RequestPlain,
+        }
+    }
+
+    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
+        let bucket = self.bucket();
+        let (host, base_path) = bucket.host_and_path();
+        let uri = format!("{}/{}", base_path, self.object_path());
+        let query = self.query();
+        let payload_hash = S3Signature::payload_hash(self.payload());
+        let amz_date = S3Signature::amz_date(OffsetDateTime::now_utc());
+        let method = match self.method() {
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            _ => "GET",
+        };
+        let authorization = S3Signature::authorization(
+            &bucket.credentials,
+            &S3CanonicalRequest { method, host: &host, uri: &uri, query: &query, payload_hash: &payload_hash },
+            &amz_date,
+        );
+
+        let mut headers = vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            ("authorization", authorization),
+        ];
+        if let S3API::PutObject { .. } = self {
+            headers.push(("content-type", "application/octet-stream".to_string()));
+        }
+        Some(headers)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            S3API::ListObjects { .. } => Some(S3_LIST_TIMEOUT),
+            S3API::PutObject { .. } => Some(S3_PUT_TIMEOUT),
+            S3API::DeleteObject { .. } => Some(S3_DELETE_TIMEOUT),
+        }
+    }
+}
+
+/// An object in a bucket listing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3Object {
+
+    /// Full key of the object
+    pub key: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// Last modification time, e.g. `2024-05-01T12:00:00.000Z`
+    pub last_modified: Option<String>,
+
+    /// Entity tag, without quotes
+    pub etag: Option<String>,
+}
+
+impl S3Object {
+
+    /// Returns the last modification time, or `None` if it is missing or
+    /// not in the ISO 8601 UTC form S3 uses.
+    pub fn modified_at(&self) -> Option<SystemTime> {
+        let value = self.last_modified.as_deref()?.trim_end_matches('Z');
+        let (date, time) = value.split_once('T')?;
+        let time = time.split('.').next()?;
+        let number = |part: Option<&str>| part.and_then(|part| part.parse::<u32>().ok());
+
+        let mut date = date.split('-');
+        let year = number(date.next())?;
+        let month = Month::try_from(u8::try_from(number(date.next())?).ok()?).ok()?;
+        let day = u8::try_from(number(date.next())?).ok()?;
+        let mut time = time.split(':');
+        let (hour, minute, second) = (number(time.next())?, number(time.next())?, number(time.next())?);
+
+        let date = Date::from_calendar_date(i32::try_from(year).ok()?, month, day).ok()?;
+        let time = Time::from_hms(u8::try_from(hour).ok()?, u8::try_from(minute).ok()?, u8::try_from(second).ok()?).ok()?;
+        let timestamp = OffsetDateTime::new_utc(date, time).unix_timestamp();
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).ok()?))
+    }
+}
+
+/// A page of a `ListObjectsV2` response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3ObjectList {
+
+    /// Objects on this page
+    pub objects: Vec<S3Object>,
+
+    /// Token requesting the next page, `None` on the last one
+    pub next_continuation_token: Option<String>,
+}
+
+impl S3ObjectList {
+
+    /// Parses the body of a `ListObjectsV2` response.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the body isn't well-formed XML.
+    pub fn parse(body: &str) -> Result<S3ObjectList, Error> {
+        let mut reader = Reader::from_str(body);
+        let mut list = S3ObjectList::default();
+        let mut object: Option<S3Object> = None;
+        let mut element = String::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => {
+                    element = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                    if element == "Contents" {
+                        object = Some(S3Object::default());
+                    }
+                }
+                Event::Text(text) => {
+                    let text = text.unescape()?.trim().to_string();
+                    match (element.as_str(), object.as_mut()) {
+                        ("NextContinuationToken", None) => list.next_continuation_token = Some(text),
+                        ("Key", Some(object)) => object.key = text,
+                        ("Size", Some(object)) => object.size = text.parse().unwrap_or_default(),
+                        ("LastModified", Some(object)) => object.last_modified = Some(text),
+                        ("ETag", Some(object)) => object.etag = Some(text.trim_matches('"').to_string()),
+                        _ => {}
+                    }
+                }
+                Event::End(end) => {
+                    if end.local_name().as_ref() == b"Contents" {
+                        list.objects.extend(object.take());
+                    }
+                    element.clear();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(list)
+    }
+}
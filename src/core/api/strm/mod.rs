@@ -0,0 +1,8 @@
+//! Strm target API.
+//!
+//! This module describes the request used to check that a URL written
+//! into a `.strm` file can be reached.
+//! 
+pub mod strm_api;
+
+pub use strm_api::*;
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crate::infrastructure::network::{HttpMethod, NetworkTarget, NetworkTask};
+
+/// Timeout for checking a single URL.
+const STRM_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Requests checking the targets of `.strm` files.
+#[derive(Debug, Clone)]
+pub enum StrmAPI {
+
+    /// Check that an absolute URL answers a `HEAD` request
+    Probe { url: String },
+}
+
+impl StrmAPI {
+
+    /// Splits the URL into its origin and the rest.
+    fn split_url(&self) -> (&str, &str) {
+        let StrmAPI::Probe { url } = self;
+        let path_start = url
+            .split_once("://")
+            .and_then(|(scheme, rest)| rest.find('/').map(|index| scheme.len() + 3 + index))
+            .unwrap_or(url.len());
+        url.split_at(path_start)
+    }
+}
+
+impl NetworkTarget for StrmAPI {
+
+    fn base_url(&self) -> String {
+        self.split_url().0.to_string()
+    }
+
+    fn path(&self) -> String {
+        self.split_url().1.to_string()
+    }
+
+    fn method(&self) -> HttpMethod {
+        HttpMethod::Head
+    }
+
+    fn task(&self) -> NetworkTask {
+        NetworkTask::RequestPlain
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(STRM_PROBE_TIMEOUT)
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf
+};
+
+use crate::infrastructure::network::NetworkTask;
+
+/// Largest document the public Bot API server accepts, 50 MB.
+///
+/// Self-hosted servers in local mode accept up to 2000 MB.
+pub const TELEGRAM_DOCUMENT_MAX_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Represents a file sent as a document via Telegram API.
+///
+/// Documents are delivered as they are, without the compression applied
+/// to photos, which suits archives such as backups.
+#[derive(Debug, Clone)]
+pub struct DocumentMessage {
+
+    /// Local file to upload
+    pub document: PathBuf,
+
+    /// Optional caption for the document with MarkdownV2 formatting
+    pub caption: Option<String>,
+}
+
+impl DocumentMessage {
+
+    /// Creates a new document message from a file path.
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self { document: path.into(), caption: None }
+    }
+
+    /// Sets the caption for the document.
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// Converts the document message into a network task uploading the file.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The target chat ID for the message
+    pub fn into_task(self, chat_id: String) -> NetworkTask {
+        let mut fields = HashMap::new();
+        fields.insert("chat_id".to_string(), chat_id);
+        fields.insert("parse_mode".to_string(), "MarkdownV2".to_string());
+        if let Some(caption) = self.caption {
+            fields.insert("caption".to_string(), caption);
+        }
+
+        let files = vec![
+            (self.document.to_string_lossy().into_owned(), "document".to_string())
+        ];
+        NetworkTask::RequestMultipartWithFiles(fields, files)
+    }
+
+    /// Converts the document message into a network task for a local Bot API server.
+    ///
+    /// Servers running with `--local` read files directly from disk, so the
+    /// path is sent as a `file://` URI instead of being uploaded.
+    pub fn into_local_task(self, chat_id: String) -> NetworkTask {
+        let path = std::fs::canonicalize(&self.document).unwrap_or(self.document);
+        let mut fields = HashMap::new();
+        fields.insert("chat_id".to_string(), chat_id);
+        fields.insert("parse_mode".to_string(), "MarkdownV2".to_string());
+        fields.insert("document".to_string(), format!("file://{}", path.display()));
+        if let Some(caption) = self.caption {
+            fields.insert("caption".to_string(), caption);
+        }
+        NetworkTask::RequestMultipart(fields)
+    }
+}
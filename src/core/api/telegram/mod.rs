@@ -3,15 +3,20 @@
 //! This module provides a complete interface for building Telegram bots with:
 //! - Full API client implementation
 //! - Support for different message types
+//! - Webhook management for push-based updates
 //! - Response handling and error management
 //! - Markdown formatting utilities
 //! 
 pub mod telegram_api;
+pub mod document_message;
 pub mod photo_message;
 pub mod telegram_response;
 pub mod text_message;
+pub mod webhook;
 
 pub use telegram_api::*;
+pub use document_message::*;
 pub use photo_message::*;
 pub use telegram_response::*;
-pub use text_message::*;
\ No newline at end of file
+pub use text_message::*;
+pub use webhook::*;
\ No newline at end of file
@@ -1,12 +1,85 @@
 use std::{
     collections::HashMap,
-    path::PathBuf,
-    fmt::{Display, Formatter, Result as FmtResult}
+    fs,
+    path::{Path, PathBuf},
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Mutex,
+    time::SystemTime
 };
 
+use image::ImageFormat;
+use once_cell::sync::Lazy;
 use serde::Serialize;
+use tempfile::TempDir;
 
-use crate::infrastructure::network::NetworkTask;
+use crate::{
+    infrastructure::{
+        fs::{ImageHelper, ImageLimits},
+        network::NetworkTask
+    },
+    warn_log
+};
+
+/// Domain identifier for Telegram photo logs
+const TELEGRAM_PHOTO_LOGGER_DOMAIN: &str = "[TELEGRAM]";
+
+/// Prefix of the directory inside the temporary directory holding converted photos.
+const TELEGRAM_PHOTO_DIR_PREFIX: &str = "pilipili_strm-telegram-";
+
+/// Private directory converted photos are written to, created on first
+/// use with a random name and only accessible by the current user.
+static TELEGRAM_PHOTO_DIR: Lazy<Option<TempDir>> = Lazy::new(|| {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(TELEGRAM_PHOTO_DIR_PREFIX);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o700));
+    }
+    builder
+        .tempdir()
+        .map_err(|e| {
+            warn_log!(
+                TELEGRAM_PHOTO_LOGGER_DOMAIN,
+                format!("Failed to create a directory for converted photos: {}", e)
+            );
+        })
+        .ok()
+});
+
+/// Identifies a version of a photo file: its path, size and modification time.
+type PhotoVersion = (PathBuf, u64, Option<SystemTime>);
+
+/// Photos converted to fit Telegram's limits, keyed by the original's version.
+static CONVERTED_PHOTOS: Lazy<Mutex<HashMap<PhotoVersion, PathBuf>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// Constraints of photos sent with `sendPhoto`.
+pub const TELEGRAM_PHOTO_LIMITS: ImageLimits = ImageLimits {
+    max_bytes: 10 * 1024 * 1024,
+    max_dimension_sum: 10_000,
+    formats: &[ImageFormat::Jpeg, ImageFormat::Png],
+};
+
+/// Largest caption length accepted by `sendPhoto`, in UTF-16 code units.
+pub const TELEGRAM_CAPTION_MAX_LEN: usize = 1024;
+
+/// Appended to captions cut to fit the length limit.
+const TELEGRAM_CAPTION_ELLIPSIS: char = '…';
+
+/// How a caption longer than [`TELEGRAM_CAPTION_MAX_LEN`] is sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaptionOverflow {
+
+    /// The caption is cut to fit and ends with an ellipsis
+    #[default]
+    Truncate,
+
+    /// The photo is sent without caption, followed by the caption as a
+    /// text message replying to it
+    FollowUp,
+}
 
 /// Represents the input source for a photo message.
 ///
@@ -46,6 +119,10 @@ pub struct PhotoMessage {
     /// Optional caption for the photo with MarkdownV2 formatting
     #[serde(skip_serializing_if = "Option::is_none")]
     pub caption: Option<String>,
+
+    /// How a caption above the length limit is sent
+    #[serde(skip_serializing)]
+    pub caption_overflow: CaptionOverflow,
 }
 
 impl PhotoMessage {
@@ -59,10 +136,20 @@ impl PhotoMessage {
     /// A `NetworkTask` ready for execution by the network infrastructure.
     ///
     /// # Notes
-    /// - For file paths, creates a multipart request with file upload
+    /// - For file paths, creates a multipart request with file upload,
+    ///   converting images Telegram would reject first, see [`PhotoMessage::from_file`]
     /// - For URLs, creates a standard multipart request
     /// - Automatically sets parse mode to MarkdownV2
     pub fn into_task(self, chat_id: String) -> NetworkTask {
+        let photo = match self.photo {
+            PhotoInput::FilePath(path) => PhotoInput::FilePath(Self::prepare(&path)),
+            url => url,
+        };
+        Self { photo, ..self }.build_task(chat_id)
+    }
+
+    /// Builds the network task of the message as is.
+    fn build_task(self, chat_id: String) -> NetworkTask {
         let mut fields = HashMap::new();
         fields.insert("chat_id".to_string(), chat_id);
         fields.insert("parse_mode".to_string(), "MarkdownV2".to_string());
@@ -85,11 +172,75 @@ impl PhotoMessage {
         }
     }
 
+    /// Converts the photo message into a network task for a local Bot API server.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The target chat ID for the message
+    ///
+    /// # Notes
+    /// - Servers running with `--local` read files directly from disk, so file
+    ///   paths are sent as `file://` URIs instead of being uploaded
+    /// - Converted images are uploaded, as they are kept in a directory
+    ///   only this user can read
+    /// - URLs are sent unchanged
+    pub fn into_local_task(self, chat_id: String) -> NetworkTask {
+        let photo = match self.photo {
+            PhotoInput::FilePath(path) => {
+                let prepared = Self::prepare(&path);
+                if prepared != path {
+                    return Self { photo: PhotoInput::FilePath(prepared), ..self }.build_task(chat_id);
+                }
+                let path = fs::canonicalize(&path).unwrap_or(path);
+                PhotoInput::Url(format!("file://{}", path.display()))
+            }
+            url => url,
+        };
+
+        Self { photo, ..self }.build_task(chat_id)
+    }
+
     /// Creates a new photo message from a file path.
+    ///
+    /// Images Telegram would reject, such as WebP files or fanart above
+    /// 10 MB or 10000 pixels of width plus height, are converted to a JPEG
+    /// within [`TELEGRAM_PHOTO_LIMITS`] when the message is sent. The JPEG
+    /// is written to a private temporary directory and reused until the
+    /// original changes. If the conversion fails, the file is sent unchanged.
     pub fn from_file(path: impl Into<PathBuf>) -> Self {
         Self {
             photo: PhotoInput::FilePath(path.into()),
             caption: None,
+            caption_overflow: CaptionOverflow::default(),
+        }
+    }
+
+    /// Returns a photo Telegram accepts for a file, converting it on first
+    /// use, or the file itself if it fits or can't be converted.
+    fn prepare(path: &Path) -> PathBuf {
+        let Ok(metadata) = fs::metadata(path) else {
+            return path.to_path_buf();
+        };
+        let key = (path.to_path_buf(), metadata.len(), metadata.modified().ok());
+        let mut converted = CONVERTED_PHOTOS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(prepared) = converted.get(&key).filter(|prepared| prepared.exists()) {
+            return prepared.clone();
+        }
+        let Some(output_dir) = TELEGRAM_PHOTO_DIR.as_ref() else {
+            return path.to_path_buf();
+        };
+
+        match ImageHelper::fit(path, &TELEGRAM_PHOTO_LIMITS, output_dir.path()) {
+            Ok(prepared) => {
+                converted.insert(key, prepared.clone());
+                prepared
+            }
+            Err(e) => {
+                warn_log!(
+                    TELEGRAM_PHOTO_LOGGER_DOMAIN,
+                    format!("Sending {} unchanged: {:#}", path.display(), e)
+                );
+                path.to_path_buf()
+            }
         }
     }
 
@@ -98,6 +249,7 @@ impl PhotoMessage {
         Self {
             photo: PhotoInput::Url(url.into()),
             caption: None,
+            caption_overflow: CaptionOverflow::default(),
         }
     }
 
@@ -106,6 +258,183 @@ impl PhotoMessage {
         self.caption = Some(caption.into());
         self
     }
+
+    /// Sets how a caption above the length limit is sent.
+    pub fn with_caption_overflow(mut self, overflow: CaptionOverflow) -> Self {
+        self.caption_overflow = overflow;
+        self
+    }
+
+    /// Fits the caption into Telegram's length limit.
+    ///
+    /// # Returns
+    /// The message to send, and the text to send after it as a reply when
+    /// the caption overflows with [`CaptionOverflow::FollowUp`].
+    pub fn split_caption(mut self) -> (Self, Option<String>) {
+        let Some(caption) = self.caption.take() else {
+            return (self, None);
+        };
+        if caption.encode_utf16().count() <= TELEGRAM_CAPTION_MAX_LEN {
+            self.caption = Some(caption);
+            return (self, None);
+        }
+
+        match self.caption_overflow {
+            CaptionOverflow::Truncate => {
+                self.caption = Some(Self::truncate_caption(&caption, TELEGRAM_CAPTION_MAX_LEN));
+                (self, None)
+            }
+            CaptionOverflow::FollowUp => (self, Some(caption)),
+        }
+    }
+
+    /// Cuts a MarkdownV2 caption to at most `max_len` UTF-16 code units,
+    /// ellipsis included.
+    ///
+    /// The caption is split into visible characters and entity markers
+    /// first, so the cut never lands inside an escape sequence or a link
+    /// URL, and entities left open are closed after the ellipsis. The cut
+    /// falls on the last line break of the second half if there is one.
+    fn truncate_caption(caption: &str, max_len: usize) -> String {
+        let pieces = CaptionPiece::parse(caption);
+        let mut budget = max_len.saturating_sub(TELEGRAM_CAPTION_ELLIPSIS.len_utf16());
+        loop {
+            let mut used = 0;
+            let mut end = pieces
+                .iter()
+                .position(|piece| {
+                    used += piece.raw().encode_utf16().count();
+                    used > budget
+                })
+                .unwrap_or(pieces.len());
+            if let Some(line_end) = pieces[..end]
+                .iter()
+                .rposition(|piece| matches!(piece, CaptionPiece::Char(_, '\n')))
+                .filter(|index| *index >= end / 2)
+            {
+                end = line_end;
+            }
+
+            let kept: String = pieces[..end].iter().map(CaptionPiece::raw).collect();
+            let closing = CaptionPiece::closing(&pieces, end);
+            let len = kept.encode_utf16().count()
+                + TELEGRAM_CAPTION_ELLIPSIS.len_utf16()
+                + closing.encode_utf16().count();
+            if len <= max_len || budget == 0 {
+                return format!("{}{}{}", kept, TELEGRAM_CAPTION_ELLIPSIS, closing);
+            }
+            budget = budget.saturating_sub(len - max_len);
+        }
+    }
+}
+
+/// Part of a MarkdownV2 caption that truncation must keep whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptionPiece<'a> {
+
+    /// One visible character with its escaped source text
+    Char(&'a str, char),
+
+    /// Entity delimiter, or a whole `](url)` link target
+    Marker(&'a str),
+}
+
+impl<'a> CaptionPiece<'a> {
+
+    /// Delimiters of entities that are opened and closed by the same marker.
+    const TOGGLES: [&'static str; 7] = ["```", "__", "||", "`", "*", "_", "~"];
+
+    /// Source text of the piece.
+    fn raw(&self) -> &'a str {
+        match self {
+            CaptionPiece::Char(raw, _) | CaptionPiece::Marker(raw) => raw,
+        }
+    }
+
+    /// Splits a MarkdownV2 caption into characters and entity markers.
+    fn parse(caption: &'a str) -> Vec<Self> {
+        let mut pieces = Vec::new();
+        let mut code: Option<&str> = None;
+        let mut rest = caption;
+        while let Some(c) = rest.chars().next() {
+            let line_start = pieces.last().is_none_or(|piece| matches!(piece, CaptionPiece::Char(_, '\n')));
+            let (piece, len) = if c == '\\' && rest.len() > 1 {
+                let escaped = rest[1..].chars().next().unwrap_or('\\');
+                let len = 1 + escaped.len_utf8();
+                (CaptionPiece::Char(&rest[..len], escaped), len)
+            } else if let Some(marker) = code {
+                if rest.starts_with(marker) {
+                    code = None;
+                    (CaptionPiece::Marker(marker), marker.len())
+                } else {
+                    (CaptionPiece::Char(&rest[..c.len_utf8()], c), c.len_utf8())
+                }
+            } else if rest.starts_with("](") {
+                let len = Self::link_target_len(rest);
+                (CaptionPiece::Marker(&rest[..len]), len)
+            } else if let Some(marker) = Self::TOGGLES.into_iter().find(|marker| rest.starts_with(marker)) {
+                if marker.starts_with('`') {
+                    code = Some(marker);
+                }
+                (CaptionPiece::Marker(&rest[..marker.len()]), marker.len())
+            } else if c == '[' || (c == '>' && line_start) {
+                (CaptionPiece::Marker(&rest[..1]), 1)
+            } else {
+                (CaptionPiece::Char(&rest[..c.len_utf8()], c), c.len_utf8())
+            };
+            pieces.push(piece);
+            rest = &rest[len..];
+        }
+        pieces
+    }
+
+    /// Length of the `](url)` link target at the start of `text`.
+    fn link_target_len(text: &str) -> usize {
+        let mut escaped = false;
+        for (index, c) in text.char_indices().skip(2) {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                ')' => return index + 1,
+                _ => {}
+            }
+        }
+        text.len()
+    }
+
+    /// Markers closing the entities still open before `pieces[end]`,
+    /// innermost first.
+    ///
+    /// An open link is closed with its own target, found further on.
+    fn closing(pieces: &[Self], end: usize) -> String {
+        let mut open: Vec<&str> = Vec::new();
+        for piece in &pieces[..end] {
+            let CaptionPiece::Marker(marker) = piece else { continue };
+            if marker.starts_with("](") {
+                open.pop();
+            } else if *marker == "[" {
+                open.push(marker);
+            } else if *marker != ">" {
+                if open.last() == Some(marker) {
+                    open.pop();
+                } else {
+                    open.push(marker);
+                }
+            }
+        }
+
+        let mut targets = pieces[end..].iter().filter_map(|piece| match piece {
+            CaptionPiece::Marker(marker) if marker.starts_with("](") => Some(*marker),
+            _ => None,
+        });
+        open.iter()
+            .rev()
+            .map(|marker| match *marker {
+                "[" => targets.next().unwrap_or_default(),
+                marker => marker,
+            })
+            .collect()
+    }
 }
 
 impl Display for PhotoMessage {
@@ -1,13 +1,20 @@
+use std::time::Duration;
+
+use serde_json::json;
+
 use crate::{
     core::config::Config,
     infrastructure::network::{HttpMethod, NetworkTarget, NetworkTask}
 };
 
-use super::{PhotoMessage, TextMessage};
+use super::{DocumentMessage, PhotoMessage, TextMessage, WebhookConfig};
+
+
+/// Timeout for lightweight Telegram API calls such as sending text messages.
+const TELEGRAM_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
-/// The base URL for the Telegram API, used to construct requests to the Telegram Bot API.
-/// This constant provides the root address, to be concatenated with a bot token and specific endpoints.
-const TELEGRAM_API_BASE: &str = "https://api.telegram.org/bot";
+/// Timeout for Telegram API calls that upload files, which can take minutes on slow links.
+const TELEGRAM_UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Represents Telegram Bot API endpoints with their respective parameters.
 ///
@@ -21,16 +28,43 @@ pub enum TelegramAPI {
 
     /// Send a photo to a chat
     SendPhoto(PhotoMessage),
+
+    /// Send a file to a chat as a document
+    SendDocument(DocumentMessage),
+
+    /// Get basic info about a file and prepare it for downloading
+    GetFile { file_id: String },
+
+    /// Download a file using the `file_path` returned by `GetFile`
+    DownloadFile { file_path: String },
+
+    /// Register a webhook URL that receives updates
+    SetWebhook(WebhookConfig),
+
+    /// Remove the webhook and switch back to long polling
+    DeleteWebhook { drop_pending_updates: bool },
+
+    /// Get the current webhook status
+    GetWebhookInfo,
+
+    /// Get basic information about the bot, which checks the token
+    GetMe,
 }
 
 impl NetworkTarget for TelegramAPI {
 
     /// Gets the base URL for Telegram API requests.
     ///
-    /// Constructs the URL using the bot token from configuration.
+    /// Constructs the URL using the configured Bot API server (the public
+    /// server by default) and the bot token from configuration.
     fn base_url(&self) -> String {
-        let token = Config::get().telegram.bot_token.clone();
-        format!("{}{}", TELEGRAM_API_BASE, token)
+        let telegram = &Config::get().telegram;
+        match self {
+            TelegramAPI::DownloadFile { .. } => {
+                format!("{}/file/bot{}", telegram.api_base(), telegram.bot_token)
+            }
+            _ => format!("{}/bot{}", telegram.api_base(), telegram.bot_token),
+        }
     }
 
     /// Gets the API endpoint path for the specific operation.
@@ -38,12 +72,24 @@ impl NetworkTarget for TelegramAPI {
         match self {
             TelegramAPI::SendMessage(_) => "sendMessage".to_string(),
             TelegramAPI::SendPhoto(_) => "sendPhoto".to_string(),
+            TelegramAPI::SendDocument(_) => "sendDocument".to_string(),
+            TelegramAPI::GetFile { .. } => "getFile".to_string(),
+            TelegramAPI::DownloadFile { file_path } => file_path.clone(),
+            TelegramAPI::SetWebhook(_) => "setWebhook".to_string(),
+            TelegramAPI::DeleteWebhook { .. } => "deleteWebhook".to_string(),
+            TelegramAPI::GetWebhookInfo => "getWebhookInfo".to_string(),
+            TelegramAPI::GetMe => "getMe".to_string(),
         }
     }
 
-    /// Gets the HTTP method for the request (always POST for Telegram API).
+    /// Gets the HTTP method for the request.
+    ///
+    /// File downloads use GET, all other Bot API methods use POST.
     fn method(&self) -> HttpMethod {
-        HttpMethod::Post
+        match self {
+            TelegramAPI::DownloadFile { .. } => HttpMethod::Get,
+            _ => HttpMethod::Post,
+        }
     }
 
     /// Converts the API operation into a network task ready for execution.
@@ -55,9 +101,27 @@ impl NetworkTarget for TelegramAPI {
             TelegramAPI::SendMessage(params) => params
                 .clone()
                 .into_task(self.get_chat_id()),
+            TelegramAPI::SendPhoto(params) if Config::get().telegram.is_local() => params
+                .clone()
+                .into_local_task(self.get_chat_id()),
             TelegramAPI::SendPhoto(params) => params
                 .clone()
                 .into_task(self.get_chat_id()),
+            TelegramAPI::SendDocument(params) if Config::get().telegram.is_local() => params
+                .clone()
+                .into_local_task(self.get_chat_id()),
+            TelegramAPI::SendDocument(params) => params
+                .clone()
+                .into_task(self.get_chat_id()),
+            TelegramAPI::GetFile { file_id } => {
+                NetworkTask::RequestJson(json!({ "file_id": file_id }))
+            }
+            TelegramAPI::DownloadFile { .. } => NetworkTask::RequestPlain,
+            TelegramAPI::SetWebhook(params) => params.clone().into_task(),
+            TelegramAPI::DeleteWebhook { drop_pending_updates } => {
+                NetworkTask::RequestJson(json!({ "drop_pending_updates": drop_pending_updates }))
+            }
+            TelegramAPI::GetWebhookInfo | TelegramAPI::GetMe => NetworkTask::RequestPlain,
         }
     }
 
@@ -73,6 +137,25 @@ impl NetworkTarget for TelegramAPI {
             ("user-agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36".to_string()),
         ])
     }
+
+    /// Gets the timeout for the request.
+    ///
+    /// Photo and document uploads and file downloads get a longer budget than plain API calls.
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            TelegramAPI::SendMessage(_)
+            | TelegramAPI::GetFile { .. }
+            | TelegramAPI::SetWebhook(_)
+            | TelegramAPI::DeleteWebhook { .. }
+            | TelegramAPI::GetWebhookInfo
+            | TelegramAPI::GetMe => Some(TELEGRAM_DEFAULT_TIMEOUT),
+            TelegramAPI::SendPhoto(_)
+            | TelegramAPI::SendDocument(_)
+            | TelegramAPI::DownloadFile { .. } => {
+                Some(TELEGRAM_UPLOAD_TIMEOUT)
+            }
+        }
+    }
 }
 
 impl TelegramAPI {
@@ -19,6 +19,19 @@ pub struct TelegramResponse<T> {
     /// Human-readable description of the error if the request failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// HTTP-like status code of the error if the request failed
+    #[serde(default)]
+    pub error_code: Option<u16>,
+}
+
+impl<T> TelegramResponse<T> {
+
+    /// Returns `true` if a failed request may succeed when sent again,
+    /// i.e. it was rate limited or hit a server error.
+    pub fn is_retryable(&self) -> bool {
+        !self.ok && self.error_code.is_none_or(|code| code == 429 || code >= 500)
+    }
 }
 
 impl<T: Display> Display for TelegramResponse<T> {
@@ -50,6 +63,10 @@ pub struct MessageResult {
     /// Unique message identifier
     pub message_id: i64,
 
+    /// Sender of the message, empty for messages sent to channels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<User>,
+
     /// The chat this message was sent to
     pub chat: Chat,
 
@@ -133,4 +150,157 @@ impl Chat {
             (None, None) => format!("{} {}", self.chat_type, self.id),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Represents a file ready to be downloaded via Telegram API.
+///
+/// Returned by `getFile`. The `file_path` is relative to the download URL on the
+/// public server, and an absolute local path on servers running in local mode.
+#[derive(Debug, Deserialize)]
+pub struct FileResult {
+
+    /// Identifier for this file, which can be used to download or reuse the file
+    pub file_id: String,
+
+    /// Unique identifier for this file, stable over time and across bots
+    pub file_unique_id: String,
+
+    /// File size in bytes, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+
+    /// File path used to download the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+}
+
+impl Display for FileResult {
+
+    /// Formats the file result for display purposes.
+    ///
+    /// Shows the file ID, size, and path (if available).
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "FileID: {}", self.file_id)?;
+
+        if let Some(size) = self.file_size {
+            write!(f, ", Size: {}", size)?;
+        }
+
+        if let Some(path) = &self.file_path {
+            write!(f, ", Path: {}", path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents a Telegram user or bot.
+#[derive(Debug, Deserialize)]
+pub struct User {
+
+    /// Unique identifier for this user or bot
+    pub id: i64,
+
+    /// Whether this user is a bot
+    pub is_bot: bool,
+
+    /// User's or bot's first name
+    pub first_name: String,
+
+    /// User's or bot's username
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+impl Display for User {
+
+    /// Formats the user info for display purposes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "ID: {}, Name: {}", self.id, self.first_name)?;
+
+        if let Some(username) = &self.username {
+            write!(f, ", Username: @{}", username)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents an incoming update, received either through long polling or a webhook.
+#[derive(Debug, Deserialize)]
+pub struct Update {
+
+    /// Unique, sequentially increasing update identifier
+    pub update_id: i64,
+
+    /// New incoming message of any kind, if this update is a message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<MessageResult>,
+}
+
+impl Update {
+
+    /// Returns the ID of the user who sent the update, if any.
+    ///
+    /// This is the identity checked against the configured allowlist.
+    pub fn sender_id(&self) -> Option<i64> {
+        self.message
+            .as_ref()
+            .and_then(|message| message.from.as_ref())
+            .map(|user| user.id)
+    }
+}
+
+impl Display for Update {
+
+    /// Formats the update for display purposes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "UpdateID: {}", self.update_id)?;
+
+        if let Some(message) = &self.message {
+            write!(f, ", Message: {}", message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents the current status of a webhook.
+#[derive(Debug, Deserialize)]
+pub struct WebhookInfo {
+
+    /// Webhook URL, empty if no webhook is set up
+    pub url: String,
+
+    /// Whether a custom certificate was provided for the webhook
+    pub has_custom_certificate: bool,
+
+    /// Number of updates awaiting delivery
+    pub pending_update_count: u32,
+
+    /// Unix time of the most recent delivery error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_date: Option<i64>,
+
+    /// Error message of the most recent delivery error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_message: Option<String>,
+
+    /// Maximum number of simultaneous delivery connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+}
+
+impl Display for WebhookInfo {
+
+    /// Formats the webhook status for display purposes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "URL: {}, Pending: {}", self.url, self.pending_update_count)?;
+
+        if let Some(error) = &self.last_error_message {
+            write!(f, ", Last Error: {}", error)?;
+        }
+
+        Ok(())
+    }
+}
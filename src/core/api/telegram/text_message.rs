@@ -17,6 +17,22 @@ pub struct TextMessage {
     /// Optional inline keyboard or reply markup in JSON string format
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<String>,
+
+    /// Optional chat overriding the configured one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<String>,
+
+    /// Optional message this message replies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+}
+
+/// Describes the message a message replies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReplyParameters {
+
+    /// Identifier of the message replied to in the same chat
+    pub message_id: i64,
 }
 
 impl TextMessage {
@@ -26,6 +42,8 @@ impl TextMessage {
         Self {
             text: text.into(),
             reply_markup: None,
+            chat_id: None,
+            reply_parameters: None,
         }
     }
 
@@ -35,11 +53,23 @@ impl TextMessage {
         self
     }
 
+    /// Sends the message to a chat other than the configured one.
+    pub fn with_chat_id(mut self, chat_id: impl Into<String>) -> Self {
+        self.chat_id = Some(chat_id.into());
+        self
+    }
+
+    /// Sets the message this message replies to.
+    pub fn with_reply_to(mut self, message_id: i64) -> Self {
+        self.reply_parameters = Some(ReplyParameters { message_id });
+        self
+    }
+
     /// Converts the message to a JSON value with required Telegram API fields.
     ///
     /// Automatically adds:
     /// - `parse_mode: "MarkdownV2"`
-    /// - `chat_id` from parameter
+    /// - `chat_id` from parameter, unless the message sets its own
     pub fn to_json_value(&self, chat_id: String) -> Value {
         let mut value = serde_json::to_value(self)
             .expect("Failed to serialize TextMessage");
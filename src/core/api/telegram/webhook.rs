@@ -0,0 +1,117 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::infrastructure::network::NetworkTask;
+
+/// Represents the webhook settings registered via Telegram API.
+///
+/// Once a webhook is set, Telegram pushes updates to the given HTTPS URL
+/// instead of making them available through long polling.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookConfig {
+
+    /// HTTPS URL that receives the updates
+    pub url: String,
+
+    /// Secret sent back in the `X-Telegram-Bot-Api-Secret-Token` header of every update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<String>,
+
+    /// Maximum number of simultaneous HTTPS connections for update delivery (1-100)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+
+    /// Update types to receive (e.g. `message`, `callback_query`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_updates: Option<Vec<String>>,
+
+    /// Whether to drop all pending updates when setting the webhook
+    pub drop_pending_updates: bool,
+}
+
+impl WebhookConfig {
+
+    /// Creates a new webhook configuration for the given URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret_token: None,
+            max_connections: None,
+            allowed_updates: None,
+            drop_pending_updates: false,
+        }
+    }
+
+    /// Sets the secret token used to authenticate update deliveries.
+    pub fn with_secret_token(mut self, secret_token: impl Into<String>) -> Self {
+        self.secret_token = Some(secret_token.into());
+        self
+    }
+
+    /// Sets the maximum number of simultaneous delivery connections.
+    pub fn with_max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Restricts the update types delivered to the webhook.
+    pub fn with_allowed_updates(mut self, allowed_updates: Vec<&str>) -> Self {
+        self.allowed_updates = Some(
+            allowed_updates.into_iter().map(String::from).collect()
+        );
+        self
+    }
+
+    /// Drops all pending updates when the webhook is set.
+    pub fn with_drop_pending_updates(mut self, drop: bool) -> Self {
+        self.drop_pending_updates = drop;
+        self
+    }
+
+    /// Checks the secret token header of an incoming update delivery.
+    ///
+    /// # Arguments
+    /// * `header` - Value of the `X-Telegram-Bot-Api-Secret-Token` header, if present
+    ///
+    /// # Returns
+    /// `true` if no secret is configured or the header matches it.
+    ///
+    /// The comparison takes the same time wherever the header differs,
+    /// so the secret can't be guessed byte by byte from response times.
+    pub fn verify_secret_token(&self, header: Option<&str>) -> bool {
+        match (&self.secret_token, header) {
+            (Some(secret), Some(header)) => {
+                let (secret, header) = (secret.as_bytes(), header.as_bytes());
+                secret.len() == header.len()
+                    && secret.iter().zip(header).fold(0u8, |diff, (a, b)| std::hint::black_box(diff | (a ^ b))) == 0
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    /// Converts the webhook configuration to a JSON value.
+    pub fn to_json_value(&self) -> Value {
+        serde_json::to_value(self)
+            .expect("Failed to serialize WebhookConfig")
+    }
+
+    /// Converts the webhook configuration into a network task ready for sending.
+    pub fn into_task(self) -> NetworkTask {
+        NetworkTask::RequestJson(self.to_json_value())
+    }
+}
+
+impl Display for WebhookConfig {
+
+    /// Formats the webhook configuration for display, hiding the secret token.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "url={}", self.url)?;
+        if self.secret_token.is_some() {
+            write!(f, ", secret_token=***")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+//! Resumable HTTP upload API.
+//!
+//! This module describes the requests used to push files to an HTTP server
+//! in chunks, resuming interrupted uploads from the offset the server reports.
+//! 
+pub mod upload_api;
+
+pub use upload_api::*;
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use crate::infrastructure::network::{encode_url_path, HttpMethod, NetworkTarget, NetworkTask};
+
+/// Timeout for querying how much of a file the server already has.
+const UPLOAD_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeout for uploading a single chunk.
+const UPLOAD_CHUNK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Header a server can use to report the number of bytes it has received.
+pub const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+
+/// An HTTP endpoint files are uploaded to.
+///
+/// Files are addressed as `{base_url}/{relative path}`.
+#[derive(Debug, Clone)]
+pub struct UploadEndpoint {
+
+    /// Base URL of the upload endpoint
+    pub base_url: String,
+
+    /// Value of the `Authorization` header sent with every request
+    pub authorization: Option<String>,
+}
+
+impl UploadEndpoint {
+
+    /// Creates an endpoint without authorization.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            authorization: None,
+        }
+    }
+
+    /// Sets the value of the `Authorization` header (e.g. `Bearer <token>`).
+    pub fn with_authorization(mut self, authorization: impl Into<String>) -> Self {
+        self.authorization = Some(authorization.into());
+        self
+    }
+}
+
+/// Requests of the resumable upload protocol.
+///
+/// The protocol is plain HTTP:
+/// - `HEAD` on a file returns the bytes received so far, in `Upload-Offset`
+///   or `Content-Length`; `404` means nothing was received yet
+/// - `PUT` uploads one chunk, described by a `Content-Range` header
+#[derive(Debug, Clone)]
+pub enum UploadAPI {
+
+    /// Ask the server how many bytes of a file it already has
+    QueryOffset { endpoint: UploadEndpoint, path: String },
+
+    /// Upload the chunk of a file starting at `offset`
+    UploadChunk {
+        endpoint: UploadEndpoint,
+        path: String,
+        offset: u64,
+        total: u64,
+        data: Vec<u8>,
+    },
+}
+
+impl UploadAPI {
+
+    /// Returns the endpoint the request is sent to.
+    fn endpoint(&self) -> &UploadEndpoint {
+        match self {
+            UploadAPI::QueryOffset { endpoint, .. }
+            | UploadAPI::UploadChunk { endpoint, .. } => endpoint,
+        }
+    }
+}
+
+impl NetworkTarget for UploadAPI {
+
+    fn base_url(&self) -> String {
+        self.endpoint().base_url.clone()
+    }
+
+    fn path(&self) -> String {
+        match self {
+            UploadAPI::QueryOffset { path, .. }
+            | UploadAPI::UploadChunk { path, .. } => encode_url_path(path),
+        }
+    }
+
+    fn method(&self) -> HttpMethod {
+        match self {
+            UploadAPI::QueryOffset { .. } => HttpMethod::Head,
+            UploadAPI::UploadChunk { .. } => HttpMethod::Put,
+        }
+    }
+
+    fn task(&self) -> NetworkTask {
+        match self {
+            UploadAPI::QueryOffset { .. } => NetworkTask::RequestPlain,
+            UploadAPI::UploadChunk { data, .. } => NetworkTask::RequestBytes(data.clone()),
+        }
+    }
+
+    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
+        let mut headers = Vec::new();
+        if let Some(authorization) = &self.endpoint().authorization {
+            headers.push(("authorization", authorization.clone()));
+        }
+        if let UploadAPI::UploadChunk { offset, total, data, .. } = self {
+            headers.push(("content-type", "application/octet-stream".to_string()));
+            // An empty file has no byte range to describe
+            if !data.is_empty() {
+                let end = offset + data.len() as u64 - 1;
+                headers.push(("content-range", format!("bytes {}-{}/{}", offset, end, total)));
+            }
+        }
+        Some(headers)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            UploadAPI::QueryOffset { .. } => Some(UPLOAD_QUERY_TIMEOUT),
+            UploadAPI::UploadChunk { .. } => Some(UPLOAD_CHUNK_TIMEOUT),
+        }
+    }
+}
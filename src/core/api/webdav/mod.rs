@@ -0,0 +1,9 @@
+//! WebDAV collection listing API.
+//!
+//! This module describes the `PROPFIND` requests used to list collections
+//! of a WebDAV server with their change indicators, so remote sources can
+//! be polled for changes.
+//! 
+pub mod webdav_api;
+
+pub use webdav_api::*;
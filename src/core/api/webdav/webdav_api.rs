@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+use quick_xml::{events::Event, Reader};
+
+use crate::infrastructure::network::{decode_url_path, encode_url_path, HttpMethod, NetworkTarget, NetworkTask};
+
+/// Timeout for listing a single collection.
+const WEBDAV_PROPFIND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Properties requested for every listed resource.
+const WEBDAV_PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getetag/>
+    <cs:getctag/>
+    <d:getlastmodified/>
+    <d:getcontentlength/>
+  </d:prop>
+</d:propfind>"#;
+
+/// A WebDAV server and the credentials used to access it.
+#[derive(Debug, Clone)]
+pub struct WebDavEndpoint {
+
+    /// Base URL of the WebDAV root (e.g. `https://cloud.example.com/remote.php/dav/files/me`)
+    pub base_url: String,
+
+    /// Value of the `Authorization` header sent with every request
+    pub authorization: Option<String>,
+}
+
+impl WebDavEndpoint {
+
+    /// Creates an endpoint without authorization.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            authorization: None,
+        }
+    }
+
+    /// Sets the value of the `Authorization` header (e.g. `Basic <credentials>`).
+    pub fn with_authorization(mut self, authorization: impl Into<String>) -> Self {
+        self.authorization = Some(authorization.into());
+        self
+    }
+
+    /// Returns the URL of a resource.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the resource relative to the base URL
+    pub fn file_url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), encode_url_path(path))
+    }
+}
+
+/// Requests of the WebDAV protocol.
+#[derive(Debug, Clone)]
+pub enum WebDavAPI {
+
+    /// List a collection and its direct members (`PROPFIND` with `Depth: 1`)
+    ListCollection { endpoint: WebDavEndpoint, path: String },
+}
+
+impl WebDavAPI {
+
+    /// Returns the endpoint the request is sent to.
+    fn endpoint(&self) -> &WebDavEndpoint {
+        match self {
+            WebDavAPI::ListCollection { endpoint, .. } => endpoint,
+        }
+    }
+}
+
+impl NetworkTarget for WebDavAPI {
+
+    fn base_url(&self) -> String {
+        self.endpoint().base_url.clone()
+    }
+
+    fn path(&self) -> String {
+        match self {
+            // Collections are addressed with a trailing slash to avoid redirects
+            WebDavAPI::ListCollection { path, .. } => match encode_url_path(path) {
+                encoded if encoded.is_empty() => String::new(),
+                encoded => format!("{}/", encoded),
+            },
+        }
+    }
+
+    fn method(&self) -> HttpMethod {
+        HttpMethod::Propfind
+    }
+
+    fn task(&self) -> NetworkTask {
+        NetworkTask::RequestBytes(WEBDAV_PROPFIND_BODY.as_bytes().to_vec())
+    }
+
+    fn headers(&self) -> Option<Vec<(&'static str, String)>> {
+        let mut headers = vec![
+            ("depth", "1".to_string()),
+            ("content-type", "application/xml; charset=utf-8".to_string()),
+        ];
+        if let Some(authorization) = &self.endpoint().authorization {
+            headers.push(("authorization", authorization.clone()));
+        }
+        Some(headers)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        Some(WEBDAV_PROPFIND_TIMEOUT)
+    }
+}
+
+/// A resource in a WebDAV multistatus response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebDavEntry {
+
+    /// Decoded path of the resource on the server, without scheme and host
+    pub href: String,
+
+    /// Whether the resource is a collection
+    pub is_collection: bool,
+
+    /// Entity tag, which changes whenever the resource does
+    pub etag: Option<String>,
+
+    /// Collection tag some servers update whenever a member changes
+    pub ctag: Option<String>,
+
+    /// Last modification time as an HTTP date
+    pub last_modified: Option<String>,
+
+    /// Size in bytes, for non-collections
+    pub content_length: Option<u64>,
+}
+
+impl WebDavEntry {
+
+    /// Returns a string that changes whenever the resource does.
+    ///
+    /// Built from every change indicator the server reported, so servers
+    /// without ETags still work through modification times and sizes.
+    pub fn version(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.etag.as_deref().unwrap_or_default(),
+            self.ctag.as_deref().unwrap_or_default(),
+            self.last_modified.as_deref().unwrap_or_default(),
+            self.content_length.map(|length| length.to_string()).unwrap_or_default()
+        )
+    }
+
+    /// Parses the resources of a `207 Multi-Status` response body.
+    ///
+    /// Element namespaces are ignored, since servers disagree on prefixes.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the body isn't well-formed XML.
+    pub fn parse_multistatus(body: &str) -> Result<Vec<WebDavEntry>, Error> {
+        let mut reader = Reader::from_str(body);
+        let mut entries = Vec::new();
+        let mut entry: Option<WebDavEntry> = None;
+        let mut element = String::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => {
+                    element = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+                    match element.as_str() {
+                        "response" => entry = Some(WebDavEntry::default()),
+                        "collection" => entry.iter_mut().for_each(|entry| entry.is_collection = true),
+                        _ => {}
+                    }
+                }
+                Event::Empty(empty) if empty.local_name().as_ref() == b"collection" => {
+                    entry.iter_mut().for_each(|entry| entry.is_collection = true);
+                }
+                Event::Text(text) => {
+                    let Some(entry) = entry.as_mut() else {
+                        continue;
+                    };
+                    let text = text.unescape()?.trim().to_string();
+                    match element.as_str() {
+                        "href" => entry.href = Self::href_path(&text),
+                        "getetag" => entry.etag = Some(text),
+                        "getctag" => entry.ctag = Some(text),
+                        "getlastmodified" => entry.last_modified = Some(text),
+                        "getcontentlength" => entry.content_length = text.parse().ok(),
+                        _ => {}
+                    }
+                }
+                Event::End(end) => {
+                    if end.local_name().as_ref() == b"response" {
+                        entries.extend(entry.take());
+                    }
+                    element.clear();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Strips scheme and host from an href and decodes it.
+    fn href_path(href: &str) -> String {
+        let path = match href.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |index| &rest[index..]),
+            None => href,
+        };
+        decode_url_path(path)
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{self, File, OpenOptions},
+    path::{Component, Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use time::{macros::format_description, OffsetDateTime};
+
+use crate::{
+    debug_log,
+    infrastructure::fs::DirScanner,
+    warn_log
+};
+
+/// Domain identifier for backup logs
+const BACKUP_LOGGER_DOMAIN: &str = "[BACKUP]";
+
+/// Extension of backup archives.
+pub const BACKUP_EXTENSION: &str = "tar.zst";
+
+/// Prefix of the names of backups written by [`BackupArchive::timestamped`].
+const BACKUP_NAME_PREFIX: &str = "pilipili_strm-";
+
+/// Name of the configuration file inside an archive.
+const BACKUP_CONFIG_ENTRY: &str = "config.toml";
+
+/// Directory holding the state files inside an archive.
+const BACKUP_STATE_ENTRY: &str = "state";
+
+/// Directories of the state directory left out of backups: the artwork
+/// cache is downloaded again on demand, and backups don't nest.
+pub const BACKUP_EXCLUDED_DIRS: [&str; 2] = ["artwork", "backups"];
+
+/// Compression level of the archives, zstd's default.
+const BACKUP_COMPRESSION_LEVEL: i32 = 3;
+
+/// Files written to or read from an archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupSummary {
+
+    /// Whether the archive holds the configuration file
+    pub config: bool,
+
+    /// Number of state files
+    pub state_files: usize,
+
+    /// Size of the archive in bytes
+    pub bytes: u64,
+}
+
+impl Display for BackupSummary {
+
+    /// Formats the summary as a one-line description.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let config = if self.config { "configuration and " } else { "" };
+        write!(f, "{}{} state files, {} bytes compressed", config, self.state_files, self.bytes)
+    }
+}
+
+/// A `tar.zst` snapshot of the configuration file and the state directory.
+///
+/// The archive holds `config.toml` and the state files below `state/`,
+/// including the sync history and the notification queue. Caches that
+/// are rebuilt on demand are left out, see [`BACKUP_EXCLUDED_DIRS`].
+#[derive(Debug, Clone)]
+pub struct BackupArchive {
+
+    /// Path of the archive
+    path: PathBuf,
+}
+
+impl BackupArchive {
+
+    /// Creates a handle on the archive at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a handle on a new archive in `dir`, named after the current time.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the time can't be formatted.
+    pub fn timestamped(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let stamp = OffsetDateTime::now_utc()
+            .format(format_description!("[year][month][day]-[hour][minute][second]"))?;
+        Ok(Self::new(dir.as_ref().join(format!("{}{}.{}", BACKUP_NAME_PREFIX, stamp, BACKUP_EXTENSION))))
+    }
+
+    /// Returns the path of the archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the configuration file and state directory to the archive.
+    ///
+    /// The archive is written next to its final path and renamed once
+    /// complete, so an interrupted backup never replaces a good one. On
+    /// Unix it is only readable by its owner, since the configuration
+    /// holds credentials.
+    ///
+    /// # Arguments
+    /// * `config_path` - The configuration file, `None` if running on defaults
+    /// * `state_dir` - The state directory
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be read or the archive can't be written.
+    pub fn create(&self, config_path: Option<&Path>, state_dir: &Path) -> Result<BackupSummary, Error> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = self.path.with_extension("zst.partial");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(&partial)
+            .with_context(|| format!("Failed to create {}", partial.display()))?;
+        let mut builder = tar::Builder::new(zstd::Encoder::new(file, BACKUP_COMPRESSION_LEVEL)?);
+        let mut summary = BackupSummary::default();
+
+        let config_path = config_path.filter(|path| path.is_file());
+        if let Some(path) = config_path {
+            builder.append_path_with_name(path, BACKUP_CONFIG_ENTRY)
+                .with_context(|| format!("Failed to archive {}", path.display()))?;
+            summary.config = true;
+        }
+
+        if state_dir.is_dir() {
+            let config = config_path.and_then(|path| fs::canonicalize(path).ok());
+            for file in DirScanner::scan(state_dir)? {
+                let path = state_dir.join(&file.relative);
+                if Self::is_excluded(&file.relative)
+                    || path == self.path
+                    || path == partial
+                    || config.as_ref().is_some_and(|config| fs::canonicalize(&path).ok().as_ref() == Some(config)) {
+                    continue;
+                }
+                builder.append_path_with_name(&path, Path::new(BACKUP_STATE_ENTRY).join(&file.relative))
+                    .with_context(|| format!("Failed to archive {}", path.display()))?;
+                summary.state_files += 1;
+            }
+        }
+
+        builder.into_inner()?.finish()?;
+        fs::rename(&partial, &self.path)
+            .with_context(|| format!("Failed to move {} to {}", partial.display(), self.path.display()))?;
+        summary.bytes = fs::metadata(&self.path)?.len();
+        debug_log!(BACKUP_LOGGER_DOMAIN, format!("Wrote {}: {}", self.path.display(), summary));
+        Ok(summary)
+    }
+
+    /// Restores the configuration file and state files from the archive.
+    ///
+    /// Files in the archive replace the current ones; state files the
+    /// archive doesn't hold are left untouched. Stop any running watcher
+    /// first, since it rewrites its state files as it goes.
+    ///
+    /// # Arguments
+    /// * `config_path` - Where the configuration file is restored
+    /// * `state_dir` - The state directory the state files are restored to
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the archive can't be read, holds a path
+    /// outside its directories, or a file can't be written.
+    pub fn restore(&self, config_path: &Path, state_dir: &Path) -> Result<BackupSummary, Error> {
+        let file = File::open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let mut archive = tar::Archive::new(zstd::Decoder::new(file)?);
+        let mut summary = BackupSummary {
+            bytes: fs::metadata(&self.path)?.len(),
+            ..BackupSummary::default()
+        };
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.into_owned();
+            let target = if name == Path::new(BACKUP_CONFIG_ENTRY) {
+                if !entry.header().entry_type().is_file() {
+                    warn_log!(BACKUP_LOGGER_DOMAIN, format!("Skipping backup entry {}, it isn't a file", name.display()));
+                    continue;
+                }
+                summary.config = true;
+                config_path.to_path_buf()
+            } else if let Ok(relative) = name.strip_prefix(BACKUP_STATE_ENTRY) {
+                if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+                    return Err(anyhow!("Backup entry {} escapes the state directory", name.display()));
+                }
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                summary.state_files += 1;
+                state_dir.join(relative)
+            } else {
+                warn_log!(BACKUP_LOGGER_DOMAIN, format!("Skipping unknown backup entry {}", name.display()));
+                continue;
+            };
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&target)
+                .with_context(|| format!("Failed to restore {}", target.display()))?;
+        }
+
+        debug_log!(BACKUP_LOGGER_DOMAIN, format!("Restored {}: {}", self.path.display(), summary));
+        Ok(summary)
+    }
+
+    /// Deletes the oldest timestamped backups in `dir`, keeping the `keep` newest.
+    ///
+    /// # Returns
+    /// The deleted archives.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory can't be listed or an archive can't be deleted.
+    pub fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>, Error> {
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("Failed to list {}", dir.display()))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(BACKUP_NAME_PREFIX) && name.ends_with(&format!(".{}", BACKUP_EXTENSION))
+                    })
+            })
+            .collect();
+        // Timestamps sort chronologically, so the newest come last
+        backups.sort();
+
+        let expired = backups.len().saturating_sub(keep);
+        let removed: Vec<PathBuf> = backups.into_iter().take(expired).collect();
+        for path in &removed {
+            fs::remove_file(path).with_context(|| format!("Failed to delete {}", path.display()))?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns `true` if a state file is left out of backups.
+    fn is_excluded(relative: &Path) -> bool {
+        relative
+            .components()
+            .next()
+            .and_then(|component| component.as_os_str().to_str())
+            .is_some_and(|first| relative.components().count() > 1 && BACKUP_EXCLUDED_DIRS.contains(&first))
+    }
+}
@@ -0,0 +1,10 @@
+//! Backups of the configuration and persisted state.
+//!
+//! This module provides:
+//! - Compressed `tar.zst` snapshots of the configuration file and state directory
+//! - Restoring a snapshot in place of the current configuration and state
+//! - Rotation of the snapshots written on a schedule
+//! 
+pub mod backup_archive;
+
+pub use backup_archive::*;
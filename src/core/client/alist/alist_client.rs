@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf
+};
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::{
+    core::{
+        api::alist::{AlistAPI, AlistEndpoint, AlistEntry, AlistListing, AlistResponse, ALIST_SUCCESS_CODE},
+        strm::RemoteFile
+    },
+    infrastructure::network::{NetworkPlugin, NetworkProvider}
+};
+
+/// Client listing directory trees of an Alist server.
+///
+/// Construct using [`AlistClientBuilder`].
+pub struct AlistClient {
+
+    /// The network provider handling actual HTTP requests
+    provider: NetworkProvider,
+
+    /// Server the directories are listed from
+    endpoint: AlistEndpoint,
+
+    /// Whether Alist is asked to bypass its own listing cache
+    refresh: bool,
+}
+
+/// Builder for creating configured `AlistClient` instances.
+pub struct AlistClientBuilder {
+    endpoint: AlistEndpoint,
+    refresh: bool,
+    plugins: Vec<Box<dyn NetworkPlugin>>,
+}
+
+impl AlistClientBuilder {
+
+    /// Creates a builder for the given server.
+    pub fn new(endpoint: AlistEndpoint) -> Self {
+        Self {
+            endpoint,
+            refresh: false,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Asks Alist to list storages directly instead of serving its cache.
+    ///
+    /// Needed to notice changes promptly on storages Alist caches for long,
+    /// at the cost of one storage request per listed directory.
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Adds a network plugin to the client's configuration.
+    pub fn with_plugin(mut self, plugin: impl NetworkPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> AlistClient {
+        AlistClient {
+            provider: NetworkProvider::new(self.plugins),
+            endpoint: self.endpoint,
+            refresh: self.refresh,
+        }
+    }
+}
+
+impl AlistClient {
+
+    /// Creates a new `AlistClientBuilder` for the given server.
+    pub fn builder(endpoint: AlistEndpoint) -> AlistClientBuilder {
+        AlistClientBuilder::new(endpoint)
+    }
+
+    /// Lists the entries of a directory.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or Alist reports an error.
+    pub async fn list(&self, path: &str) -> Result<Vec<AlistEntry>, Error> {
+        let response = self.provider
+            .send_request(&AlistAPI::List {
+                endpoint: self.endpoint.clone(),
+                path: path.to_string(),
+                refresh: self.refresh,
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Listing '{}' failed with status {}", path, response.status()));
+        }
+
+        let response: AlistResponse<AlistListing> = response.json().await?;
+        if response.code != ALIST_SUCCESS_CODE {
+            return Err(anyhow!("Listing '{}' failed: {} ({})", path, response.message, response.code));
+        }
+        Ok(response.data.and_then(|listing| listing.content).unwrap_or_default())
+    }
+
+    /// Lists every file below a directory, one request per directory.
+    ///
+    /// # Returns
+    /// The files keyed by path relative to `root`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if any directory can't be listed.
+    pub async fn list_recursive(&self, root: &str) -> Result<BTreeMap<PathBuf, AlistEntry>, Error> {
+        let mut files = BTreeMap::new();
+        let mut pending = vec![PathBuf::new()];
+
+        while let Some(relative) = pending.pop() {
+            let path = Self::join(root, &relative);
+            for entry in self.list(&path).await? {
+                let child = relative.join(&entry.name);
+                if entry.is_dir {
+                    pending.push(child);
+                } else {
+                    files.insert(child, entry);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Lists every file below a directory with its direct link.
+    ///
+    /// # Returns
+    /// The files, sorted by path relative to `root`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if any directory can't be listed.
+    pub async fn list_files(&self, root: &str) -> Result<Vec<RemoteFile>, Error> {
+        Ok(self.list_recursive(root)
+            .await?
+            .into_iter()
+            .map(|(relative, entry)| RemoteFile {
+                url: Some(self.endpoint.direct_link(&Self::join(root, &relative), &entry.sign)),
+                size: entry.size,
+                relative,
+            })
+            .collect())
+    }
+
+    /// Joins a relative path onto an Alist directory path.
+    fn join(root: &str, relative: &std::path::Path) -> String {
+        let root = root.trim_end_matches('/');
+        let relative = relative.to_string_lossy();
+        match (root.is_empty(), relative.is_empty()) {
+            (true, true) => "/".to_string(),
+            (_, true) => root.to_string(),
+            (true, false) => format!("/{}", relative),
+            (false, false) => format!("{}/{}", root, relative),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use crate::infrastructure::fs::{PollFuture, RemotePoller, RemoteSnapshot};
+use super::alist_client::AlistClient;
+
+/// Polls a directory tree of an Alist server for changes.
+///
+/// Every poll lists the whole tree and compares file sizes and
+/// modification times with the previous listing.
+pub struct AlistPoller {
+
+    /// Client used to list the tree
+    client: AlistClient,
+
+    /// Alist path of the polled directory
+    root: String,
+
+    /// Files seen by the previous poll
+    snapshot: RemoteSnapshot,
+}
+
+impl AlistPoller {
+
+    /// Creates a poller for the directory `root` of an Alist server.
+    pub fn new(client: AlistClient, root: impl Into<String>) -> Self {
+        Self {
+            client,
+            root: root.into(),
+            snapshot: RemoteSnapshot::default(),
+        }
+    }
+}
+
+impl RemotePoller for AlistPoller {
+
+    fn poll(&mut self) -> PollFuture<'_> {
+        Box::pin(async move {
+            let listing = self.client
+                .list_recursive(&self.root)
+                .await?
+                .into_iter()
+                .map(|(path, entry)| (path, format!("{}:{}", entry.size, entry.modified)))
+                .collect();
+            Ok(self.snapshot.update(Path::new(""), listing))
+        })
+    }
+}
@@ -0,0 +1,10 @@
+//! Alist listing client.
+//!
+//! This module lists directory trees of an Alist server and polls them for
+//! changes, for sources that emit no filesystem notifications.
+//! 
+pub mod alist_client;
+pub mod alist_poller;
+
+pub use alist_client::*;
+pub use alist_poller::*;
@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    core::{
+        api::artwork::ArtworkAPI,
+        config::Config,
+        library::state_file::{load_state, lock_state, save_state}
+    },
+    debug_log,
+    infrastructure::network::NetworkProvider
+};
+
+/// Domain identifier for artwork cache logs
+const ARTWORK_LOGGER_DOMAIN: &str = "[ARTWORK]";
+
+/// Directory of the artwork cache inside the state directory.
+const ARTWORK_CACHE_DIR_NAME: &str = "artwork";
+
+/// File name of the cache index inside the cache directory.
+const ARTWORK_INDEX_FILE_NAME: &str = "index.json";
+
+/// Default size limit of the cached images (256 MiB).
+pub const ARTWORK_CACHE_DEFAULT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// A cached image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ArtworkBlob {
+
+    /// Size in bytes
+    size: u64,
+
+    /// Value of the use counter when the image was last used
+    last_used: u64,
+}
+
+/// Persisted index of the cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ArtworkIndex {
+
+    /// Cached images by SHA-256 of their content
+    blobs: BTreeMap<String, ArtworkBlob>,
+
+    /// Content hash of every downloaded URL
+    urls: BTreeMap<String, String>,
+
+    /// Counter incremented on every use, ordering images by recency
+    uses: u64,
+}
+
+/// Content-addressed cache of downloaded artwork.
+///
+/// Images are stored under the SHA-256 of their content, so a poster
+/// reachable through several URLs is stored once. Each URL remembers the
+/// content it resolved to, so it is only downloaded the first time. Once
+/// the cache grows past its size limit, the least recently used images
+/// are evicted.
+///
+/// The index is locked and merged with its copy on disk whenever it is
+/// saved, so several processes can share a cache directory.
+pub struct ArtworkCache {
+
+    /// Directory holding the index and the images
+    dir: PathBuf,
+
+    /// Size limit of the cached images in bytes
+    max_bytes: u64,
+
+    /// Index of cached images and URLs
+    index: ArtworkIndex,
+
+    /// The network provider downloading images
+    provider: NetworkProvider,
+}
+
+impl ArtworkCache {
+
+    /// Opens the cache in a directory, creating it on first use.
+    ///
+    /// Entries whose image was deleted from disk are forgotten.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the index exists but can't be read.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+        let mut index: ArtworkIndex = load_state(&dir.join(ARTWORK_INDEX_FILE_NAME))
+            .context("Failed to load the artwork cache index")?;
+        index.blobs.retain(|hash, _| Self::blob_path_in(&dir, hash).is_file());
+        let blobs = index.blobs.clone();
+        index.urls.retain(|_, hash| blobs.contains_key(hash));
+
+        Ok(Self {
+            dir,
+            max_bytes: ARTWORK_CACHE_DEFAULT_MAX_BYTES,
+            index,
+            provider: NetworkProvider::new(Vec::new()),
+        })
+    }
+
+    /// Returns the default cache directory inside the state directory.
+    pub fn default_dir(config: &Config) -> PathBuf {
+        config.state_dir().join(ARTWORK_CACHE_DIR_NAME)
+    }
+
+    /// Sets the size limit of the cached images in bytes.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns the number of cached images.
+    pub fn len(&self) -> usize {
+        self.index.blobs.len()
+    }
+
+    /// Returns `true` if no image is cached.
+    pub fn is_empty(&self) -> bool {
+        self.index.blobs.is_empty()
+    }
+
+    /// Returns the total size of the cached images in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.index.blobs.values().map(|blob| blob.size).sum()
+    }
+
+    /// Returns the cached image of a URL and marks it as recently used.
+    ///
+    /// # Returns
+    /// `None` if the URL was never downloaded or its image was evicted.
+    pub fn get(&mut self, url: &str) -> Option<PathBuf> {
+        let hash = self.index.urls.get(url)?.clone();
+        let path = self.blob_path(&hash);
+        if !path.is_file() {
+            self.index.blobs.remove(&hash);
+            self.index.urls.retain(|_, cached| *cached != hash);
+            return None;
+        }
+
+        self.touch(&hash);
+        if let Err(e) = self.save() {
+            debug_log!(ARTWORK_LOGGER_DOMAIN, format!("{:#}", e));
+        }
+        Some(path)
+    }
+
+    /// Stores the image downloaded from a URL.
+    ///
+    /// Images already cached under another URL aren't written again.
+    /// Least recently used images are evicted afterwards if the cache
+    /// exceeds its size limit, never the one just stored.
+    ///
+    /// # Returns
+    /// The path of the cached image.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the image or the index can't be written.
+    pub fn insert(&mut self, url: &str, bytes: &[u8]) -> Result<PathBuf, Error> {
+        let hash = format!("{:x}", Sha256::digest(bytes));
+        let path = self.blob_path(&hash);
+        if !path.is_file() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let partial = path.with_extension("partial");
+            fs::write(&partial, bytes)?;
+            fs::rename(&partial, &path)?;
+        }
+
+        self.index.blobs.insert(hash.clone(), ArtworkBlob { size: bytes.len() as u64, last_used: 0 });
+        self.index.urls.insert(url.to_string(), hash.clone());
+        self.touch(&hash);
+        self.evict(&hash);
+        self.save()?;
+        Ok(path)
+    }
+
+    /// Returns the cached image of a URL, downloading it on a miss.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the download fails or the image can't be stored.
+    pub async fn fetch(&mut self, url: &str) -> Result<PathBuf, Error> {
+        if let Some(path) = self.get(url) {
+            return Ok(path);
+        }
+
+        let response = self.provider
+            .send_request(&ArtworkAPI::Download { url: url.to_string() })
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Downloading '{}' failed with status {}", url, response.status()));
+        }
+        let bytes = response.bytes().await?;
+        debug_log!(
+            ARTWORK_LOGGER_DOMAIN,
+            format!("Downloaded {} ({} bytes)", url, bytes.len())
+        );
+        self.insert(url, &bytes)
+    }
+
+    /// Marks an image as the most recently used.
+    fn touch(&mut self, hash: &str) {
+        self.index.uses += 1;
+        if let Some(blob) = self.index.blobs.get_mut(hash) {
+            blob.last_used = self.index.uses;
+        }
+    }
+
+    /// Evicts least recently used images until the cache fits its limit.
+    fn evict(&mut self, keep: &str) {
+        let mut total = self.total_bytes();
+        let mut candidates: Vec<(String, u64, u64)> = self.index.blobs
+            .iter()
+            .filter(|(hash, _)| hash.as_str() != keep)
+            .map(|(hash, blob)| (hash.clone(), blob.size, blob.last_used))
+            .collect();
+        candidates.sort_by_key(|(_, _, last_used)| *last_used);
+
+        for (hash, size, _) in candidates {
+            if total <= self.max_bytes {
+                break;
+            }
+            let _ = fs::remove_file(self.blob_path(&hash));
+            self.index.blobs.remove(&hash);
+            self.index.urls.retain(|_, cached| *cached != hash);
+            total -= size;
+            debug_log!(ARTWORK_LOGGER_DOMAIN, format!("Evicted {} ({} bytes)", hash, size));
+        }
+    }
+
+    /// Writes the index, merged with the one written by other processes.
+    ///
+    /// Images and URLs known to either index are kept, each image with its
+    /// most recent use, unless its file was deleted in the meantime.
+    fn save(&mut self) -> Result<(), Error> {
+        let path = self.dir.join(ARTWORK_INDEX_FILE_NAME);
+        let _locked = lock_state(&path)?;
+        let stored: ArtworkIndex = load_state(&path)
+            .context("Failed to load the artwork cache index")?;
+
+        for (hash, blob) in stored.blobs {
+            let merged = self.index.blobs.entry(hash).or_insert_with(|| blob.clone());
+            merged.last_used = merged.last_used.max(blob.last_used);
+        }
+        for (url, hash) in stored.urls {
+            self.index.urls.entry(url).or_insert(hash);
+        }
+        self.index.uses = self.index.uses.max(stored.uses);
+        let dir = self.dir.clone();
+        self.index.blobs.retain(|hash, _| Self::blob_path_in(&dir, hash).is_file());
+        let blobs = &self.index.blobs;
+        self.index.urls.retain(|_, hash| blobs.contains_key(hash));
+
+        save_state(&self.index, &path)
+            .context("Failed to save the artwork cache index")
+    }
+
+    /// Returns the path of an image in this cache.
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        Self::blob_path_in(&self.dir, hash)
+    }
+
+    /// Returns the path of an image, sharded by the first two hex digits.
+    fn blob_path_in(dir: &Path, hash: &str) -> PathBuf {
+        dir.join(&hash[..2.min(hash.len())]).join(hash)
+    }
+}
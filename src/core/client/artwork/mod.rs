@@ -0,0 +1,8 @@
+//! Artwork cache.
+//!
+//! This module downloads artwork into a content-addressed local cache,
+//! so the same poster is fetched once for every episode of a show.
+//! 
+pub mod artwork_cache;
+
+pub use artwork_cache::*;
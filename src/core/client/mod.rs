@@ -1,3 +1,13 @@
+pub mod alist;
+pub mod artwork;
+pub mod s3;
 pub mod telegram;
+pub mod upload;
+pub mod webdav;
 
-pub use telegram::*;
\ No newline at end of file
+pub use alist::*;
+pub use artwork::*;
+pub use s3::*;
+pub use telegram::*;
+pub use upload::*;
+pub use webdav::*;
\ No newline at end of file
@@ -0,0 +1,8 @@
+//! S3-compatible object storage client.
+//!
+//! This module mirrors directory trees into a bucket prefix of S3, MinIO
+//! or R2, so libraries backed by object storage don't need rclone.
+//! 
+pub mod s3_client;
+
+pub use s3_client::*;
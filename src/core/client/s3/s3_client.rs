@@ -0,0 +1,311 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::Instant
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::{
+    core::api::s3::{S3Bucket, S3Object, S3ObjectList, S3API},
+    infrastructure::{
+        fs::{DirScanner, FileProgressTracker, ProgressSender, SyncAction, SyncPlan},
+        network::{NetworkPlugin, NetworkProvider}
+    },
+    debug_log,
+    info_log
+};
+
+/// Domain identifier for S3 logs
+const S3_LOGGER_DOMAIN: &str = "[S3]";
+
+/// Client mirroring directories into a bucket prefix.
+///
+/// Objects are compared with their source files by size and modification
+/// time, since S3 keeps no source timestamps: a file newer than its object
+/// is uploaded again. Construct using [`S3ClientBuilder`].
+pub struct S3Client {
+
+    /// The network provider handling actual HTTP requests
+    provider: NetworkProvider,
+
+    /// Bucket objects are written to
+    bucket: S3Bucket,
+
+    /// Key prefix the directory is mirrored below, without slashes at its ends
+    prefix: String,
+
+    /// Number of threads listing a directory before it is synced
+    scan_parallelism: usize,
+
+    /// Channel for typed progress events of the uploads
+    progress_sender: Option<ProgressSender>,
+}
+
+/// Builder for creating configured `S3Client` instances.
+pub struct S3ClientBuilder {
+    bucket: S3Bucket,
+    prefix: String,
+    scan_parallelism: usize,
+    progress_sender: Option<ProgressSender>,
+    plugins: Vec<Box<dyn NetworkPlugin>>,
+}
+
+impl S3ClientBuilder {
+
+    /// Creates a builder for the given bucket, mirroring into its root.
+    pub fn new(bucket: S3Bucket) -> Self {
+        Self {
+            bucket,
+            prefix: String::new(),
+            scan_parallelism: 1,
+            progress_sender: None,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Sets the key prefix the directory is mirrored below, e.g. `media/movies`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into().trim_matches('/').to_string();
+        self
+    }
+
+    /// Sets the number of threads listing a directory before it is synced.
+    ///
+    /// Zero is treated as one.
+    pub fn with_scan_parallelism(mut self, workers: usize) -> Self {
+        self.scan_parallelism = workers.max(1);
+        self
+    }
+
+    /// Sends a [`ProgressEvent`](crate::infrastructure::fs::ProgressEvent)
+    /// when an upload starts and is done (builder pattern). Objects are
+    /// put whole, so progress is only reported once they are.
+    pub fn with_progress_sender(mut self, sender: ProgressSender) -> Self {
+        self.progress_sender = Some(sender);
+        self
+    }
+
+    /// Adds a network plugin to the client's configuration.
+    pub fn with_plugin(mut self, plugin: impl NetworkPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Constructs the `S3Client`.
+    pub fn build(self) -> S3Client {
+        S3Client {
+            provider: NetworkProvider::new(self.plugins),
+            bucket: self.bucket,
+            prefix: self.prefix,
+            scan_parallelism: self.scan_parallelism,
+            progress_sender: self.progress_sender,
+        }
+    }
+}
+
+impl S3Client {
+
+    /// Creates a new `S3ClientBuilder` for the given bucket.
+    pub fn builder(bucket: S3Bucket) -> S3ClientBuilder {
+        S3ClientBuilder::new(bucket)
+    }
+
+    /// Lists every object below the prefix, following continuation tokens.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a request fails, the service answers with
+    /// an unexpected status or a response can't be parsed.
+    pub async fn list_objects(&self) -> Result<Vec<S3Object>, Error> {
+        let prefix = if self.prefix.is_empty() { String::new() } else { format!("{}/", self.prefix) };
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let response = self.provider
+                .send_request(&S3API::ListObjects {
+                    bucket: self.bucket.clone(),
+                    prefix: prefix.clone(),
+                    continuation_token: continuation_token.take(),
+                })
+                .await?;
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Listing bucket '{}' failed with status {}",
+                    self.bucket.name,
+                    response.status()
+                ));
+            }
+
+            let page = S3ObjectList::parse(&response.text().await?)
+                .with_context(|| format!("Invalid listing of bucket '{}'", self.bucket.name))?;
+            objects.extend(page.objects);
+            match page.next_continuation_token {
+                Some(token) => continuation_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Uploads an object, replacing any object with the same key.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or is rejected.
+    pub async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let response = self.provider
+            .send_request(&S3API::PutObject { bucket: self.bucket.clone(), key: key.to_string(), data })
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Uploading '{}' failed with status {}", key, response.status()));
+        }
+        Ok(())
+    }
+
+    /// Deletes an object; deleting a missing object succeeds.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or is rejected.
+    pub async fn delete_object(&self, key: &str) -> Result<(), Error> {
+        let response = self.provider
+            .send_request(&S3API::DeleteObject { bucket: self.bucket.clone(), key: key.to_string() })
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Deleting '{}' failed with status {}", key, response.status()));
+        }
+        Ok(())
+    }
+
+    /// Computes the changes that would mirror `source` into the prefix.
+    ///
+    /// Files passing `filter` that are missing from the bucket, differ in
+    /// size or are newer than their object are planned for upload. With
+    /// `delete`, objects whose source file is gone are planned for
+    /// deletion, unless `filter` excludes them. Paths are relative to the
+    /// prefix.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source can't be listed or the
+    /// bucket listing fails.
+    pub async fn plan(
+        &self,
+        source: impl AsRef<Path>,
+        filter: impl Fn(&Path) -> bool,
+        delete: bool,
+    ) -> Result<SyncPlan, Error> {
+        let objects: HashMap<String, S3Object> = self
+            .list_objects()
+            .await?
+            .into_iter()
+            .filter_map(|object| {
+                let relative = self.relative_key(&object.key)?.to_string();
+                Some((relative, object))
+            })
+            .collect();
+
+        let mut actions = Vec::new();
+        let mut local = HashSet::new();
+        for file in DirScanner::scan_parallel(source.as_ref(), self.scan_parallelism)? {
+            if !filter(&file.relative) {
+                continue;
+            }
+            let relative = file.relative.to_string_lossy().replace('\\', "/");
+            match objects.get(&relative) {
+                None => actions.push(SyncAction::Create(relative.clone())),
+                Some(object) => {
+                    let newer = file.modified.zip(object.modified_at()).is_some_and(|(local, remote)| local > remote);
+                    if object.size != file.size || newer {
+                        actions.push(SyncAction::Update(relative.clone()));
+                    }
+                }
+            }
+            local.insert(relative);
+        }
+
+        if delete {
+            let mut deleted: Vec<&String> = objects
+                .keys()
+                .filter(|relative| !local.contains(*relative) && filter(Path::new(relative)))
+                .collect();
+            deleted.sort();
+            actions.extend(deleted.into_iter().map(|relative| SyncAction::Delete(relative.clone())));
+        }
+        Ok(SyncPlan::new(actions))
+    }
+
+    /// Applies a plan computed by [`plan`](Self::plan).
+    ///
+    /// # Returns
+    /// The paths uploaded or deleted, relative to the prefix.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be read or a request fails.
+    pub async fn apply(&self, source: impl AsRef<Path>, plan: &SyncPlan) -> Result<Vec<String>, Error> {
+        let source = source.as_ref();
+        let mut changed = Vec::new();
+        let mut tracker = self.progress_sender.clone().map(FileProgressTracker::new);
+        for action in plan.actions() {
+            let key = self.key(action.path());
+            match action {
+                SyncAction::Create(path) | SyncAction::Update(path) => {
+                    let data = tokio::fs::read(source.join(path))
+                        .await
+                        .with_context(|| format!("Failed to read {}", source.join(path).display()))?;
+                    let size = data.len();
+                    if let Some(tracker) = tracker.as_mut() {
+                        tracker.started(path);
+                    }
+                    let started = Instant::now();
+                    self.put_object(&key, data).await?;
+                    if let Some(tracker) = tracker.as_mut() {
+                        let elapsed = started.elapsed().as_secs_f64();
+                        tracker.progress(100, if elapsed > 0.0 { size as f64 / elapsed } else { 0.0 });
+                        tracker.finish();
+                    }
+                    info_log!(S3_LOGGER_DOMAIN, format!("Uploaded '{}' ({} bytes)", key, size));
+                }
+                SyncAction::Delete(_) => {
+                    self.delete_object(&key).await?;
+                    debug_log!(S3_LOGGER_DOMAIN, format!("Deleted '{}'", key));
+                }
+                SyncAction::CreateDir(_) => continue,
+            }
+            changed.push(action.path().to_string());
+        }
+        Ok(changed)
+    }
+
+    /// Mirrors `source` into the prefix, see [`plan`](Self::plan).
+    ///
+    /// # Returns
+    /// The paths uploaded or deleted, relative to the prefix.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the plan can't be computed or applied.
+    pub async fn sync_dir(
+        &self,
+        source: impl AsRef<Path>,
+        filter: impl Fn(&Path) -> bool,
+        delete: bool,
+    ) -> Result<Vec<String>, Error> {
+        let plan = self.plan(source.as_ref(), filter, delete).await?;
+        self.apply(source, &plan).await
+    }
+
+    /// Returns the key of a path relative to the prefix.
+    fn key(&self, relative: &str) -> String {
+        match self.prefix.as_str() {
+            "" => relative.to_string(),
+            prefix => format!("{}/{}", prefix, relative),
+        }
+    }
+
+    /// Returns the path of a key relative to the prefix, or `None` for
+    /// keys outside it and folder placeholders.
+    fn relative_key<'a>(&self, key: &'a str) -> Option<&'a str> {
+        let relative = match self.prefix.as_str() {
+            "" => key,
+            prefix => key.strip_prefix(prefix)?.strip_prefix('/')?,
+        };
+        (!relative.is_empty() && !relative.ends_with('/')).then_some(relative)
+    }
+}
@@ -25,19 +25,25 @@ impl MarkdownV2Builder {
         self
     }
 
+    /// Appends already formatted MarkdownV2 content without escaping it.
+    pub fn raw(mut self, markdown: &str) -> Self {
+        self.text.push_str(markdown);
+        self
+    }
+
     /// Appends bold-formatted text (`*bold*`).
     pub fn bold(self, text: &str) -> Self {
-        self.text(&format!("*{}*", Self::escape(text)))
+        self.raw(&format!("*{}*", Self::escape(text)))
     }
 
     /// Appends italic-formatted text (`_italic_`).
     pub fn italic(self, text: &str) -> Self {
-        self.text(&format!("_{}_", Self::escape(text)))
+        self.raw(&format!("_{}_", Self::escape(text)))
     }
 
     /// Appends an inline link (`[text](url)`).
     pub fn link(self, text: &str, url: &str) -> Self {
-        self.text(&format!("[{}]({})", Self::escape(text), Self::escape(url)))
+        self.raw(&format!("[{}]({})", Self::escape(text), Self::escape(url)))
     }
 
     /// Finalizes and returns the built MarkdownV2 string.
@@ -49,7 +55,7 @@ impl MarkdownV2Builder {
     ///
     /// Telegram requires escaping these characters when they appear in regular text:
     /// `_ * [ ] ( ) ~ ` > # + - = | { } . !`
-    fn escape(text: &str) -> String {
+    pub fn escape(text: &str) -> String {
         const CHARS_TO_ESCAPE: &[char] = &[
             '_', '*', '[', ']', '(', ')', '~', '`',
             '>', '#', '+', '-', '=', '|', '{', '}', '.', '!'
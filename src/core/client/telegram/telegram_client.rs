@@ -1,7 +1,20 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+
 use crate::infrastructure::network::{NetworkProvider, NetworkPlugin};
-use crate::core::api::telegram::{
-    TextMessage, PhotoMessage, TelegramAPI, TelegramResponse, MessageResult
+use crate::core::{
+    api::telegram::{
+        TextMessage, PhotoMessage, PhotoInput, DocumentMessage, TelegramAPI, TelegramResponse, MessageResult, FileResult,
+        WebhookConfig, WebhookInfo, User, TELEGRAM_DOCUMENT_MAX_SIZE
+    },
+    client::artwork::ArtworkCache,
+    config::Config
 };
+use crate::{debug_log, warn_log};
+
+/// Domain identifier for Telegram client logs
+const TELEGRAM_CLIENT_LOGGER_DOMAIN: &str = "[TELEGRAM]";
 
 /// Telegram API client with configured network provider.
 ///
@@ -17,6 +30,7 @@ pub struct TelegramClient {
 ///
 /// Allows customization of the network stack through plugins before constructing
 /// the final client. By default creates a client with no plugins.
+#[derive(Default)]
 pub struct TelegramClientBuilder {
     plugins: Vec<Box<dyn NetworkPlugin>>,
 }
@@ -28,9 +42,7 @@ impl TelegramClientBuilder {
     /// Starts with an empty set of network plugins. You'll typically want to add
     /// at least one network implementation like `CurlPlugin`.
     pub fn new() -> Self {
-        Self {
-            plugins: Vec::new(),
-        }
+        Self::default()
     }
 
     /// Adds a network plugin to the client's configuration.
@@ -88,6 +100,16 @@ impl TelegramClient {
 
     /// Sends a photo to a Telegram chat.
     ///
+    /// Photos given by URL are downloaded through the [`ArtworkCache`] and
+    /// uploaded, so the same poster is only downloaded once and URLs only
+    /// reachable from this host, such as Emby images, work too. If the
+    /// download fails, Telegram fetches the URL itself.
+    ///
+    /// Captions above [`TELEGRAM_CAPTION_MAX_LEN`](crate::core::api::telegram::TELEGRAM_CAPTION_MAX_LEN)
+    /// are truncated or sent as a reply to the photo, depending on the
+    /// message's [`CaptionOverflow`](crate::core::api::telegram::CaptionOverflow).
+    /// A failed reply is logged, since the photo itself was delivered.
+    ///
     /// # Arguments
     /// * `params` - Photo message configuration including chat ID and image data
     ///
@@ -101,10 +123,232 @@ impl TelegramClient {
         &self,
         params: PhotoMessage,
     ) -> Result<TelegramResponse<MessageResult>, anyhow::Error> {
+        let (mut params, follow_up) = params.split_caption();
+        if let PhotoInput::Url(url) = &params.photo {
+            if let Some(path) = Self::cached_artwork(url).await {
+                params.photo = PhotoInput::FilePath(path);
+            }
+        }
         let response = self.provider
             .send_request(&TelegramAPI::SendPhoto(params))
             .await?;
         let result: TelegramResponse<MessageResult> = response.json().await?;
+
+        if let (Some(text), Some(photo)) = (follow_up, &result.result) {
+            let reply = TextMessage::new(text).with_reply_to(photo.message_id);
+            match self.send_message(reply).await {
+                Ok(response) if response.ok => {}
+                Ok(response) => {
+                    warn_log!(
+                        TELEGRAM_CLIENT_LOGGER_DOMAIN,
+                        format!("Failed to send the caption after the photo: {}", response)
+                    );
+                }
+                Err(e) => {
+                    warn_log!(
+                        TELEGRAM_CLIENT_LOGGER_DOMAIN,
+                        format!("Failed to send the caption after the photo: {:#}", e)
+                    );
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the cached image of a URL, downloading it on a miss.
+    async fn cached_artwork(url: &str) -> Option<PathBuf> {
+        let result = match ArtworkCache::open(ArtworkCache::default_dir(&Config::get())) {
+            Ok(mut cache) => cache.fetch(url).await,
+            Err(e) => Err(e),
+        };
+        result
+            .inspect_err(|e| {
+                debug_log!(
+                    TELEGRAM_CLIENT_LOGGER_DOMAIN,
+                    format!("Sending photo URL {} as is: {:#}", url, e)
+                );
+            })
+            .ok()
+    }
+
+    /// Sends a local file to a Telegram chat as a document.
+    ///
+    /// # Arguments
+    /// * `params` - Document message with the file to upload
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - The file can't be read, or exceeds the 50 MB limit of the public
+    ///   Bot API server (local mode servers have no such limit)
+    /// - Network request fails
+    /// - File upload fails
+    /// - Response parsing fails
+    pub async fn send_document(
+        &self,
+        params: DocumentMessage,
+    ) -> Result<TelegramResponse<MessageResult>, anyhow::Error> {
+        let size = std::fs::metadata(&params.document)
+            .map_err(|e| anyhow!("Failed to read {}: {}", params.document.display(), e))?
+            .len();
+        if size > TELEGRAM_DOCUMENT_MAX_SIZE && !Config::get().telegram.is_local() {
+            return Err(anyhow!(
+                "{} is {} bytes, over the {} byte limit of Telegram documents",
+                params.document.display(),
+                size,
+                TELEGRAM_DOCUMENT_MAX_SIZE
+            ));
+        }
+        let response = self.provider
+            .send_request(&TelegramAPI::SendDocument(params))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Gets information about a file stored on Telegram's servers.
+    ///
+    /// # Arguments
+    /// * `file_id` - Identifier of the file (e.g. from a received document)
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Network request fails
+    /// - Response parsing fails
+    pub async fn get_file(
+        &self,
+        file_id: impl Into<String>,
+    ) -> Result<TelegramResponse<FileResult>, anyhow::Error> {
+        let response = self.provider
+            .send_request(&TelegramAPI::GetFile { file_id: file_id.into() })
+            .await?;
+        let result: TelegramResponse<FileResult> = response.json().await?;
         Ok(result)
     }
-}
\ No newline at end of file
+
+    /// Downloads the content of a file returned by [`TelegramClient::get_file`].
+    ///
+    /// On servers running in local mode the file is read directly from disk,
+    /// since `file_path` is an absolute local path there.
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - The file has no `file_path`
+    /// - Network request fails or returns a non-success status
+    /// - Reading the local file fails
+    pub async fn download_file(&self, file: &FileResult) -> Result<Vec<u8>, anyhow::Error> {
+        let file_path = file.file_path
+            .clone()
+            .ok_or_else(|| anyhow!("File '{}' has no download path", file.file_id))?;
+
+        if Config::get().telegram.is_local() && Path::new(&file_path).is_absolute() {
+            return Ok(tokio::fs::read(&file_path).await?);
+        }
+
+        let response = self.provider
+            .send_request(&TelegramAPI::DownloadFile { file_path })
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Fetches a file by ID and writes its content to `destination`.
+    ///
+    /// # Arguments
+    /// * `file_id` - Identifier of the file to download
+    /// * `destination` - Local path the file content is written to
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Telegram API returns error
+    /// - Download fails
+    /// - Writing the destination file fails
+    pub async fn download_file_to(
+        &self,
+        file_id: impl Into<String>,
+        destination: impl AsRef<Path>,
+    ) -> Result<FileResult, anyhow::Error> {
+        let response = self.get_file(file_id).await?;
+        let file = match response.result {
+            Some(file) if response.ok => file,
+            _ => {
+                return Err(anyhow!(
+                    "getFile failed: {}",
+                    response.description.unwrap_or_default()
+                ));
+            }
+        };
+
+        let content = self.download_file(&file).await?;
+        tokio::fs::write(destination, content).await?;
+        Ok(file)
+    }
+
+    /// Registers a webhook that receives updates instead of long polling.
+    ///
+    /// # Arguments
+    /// * `params` - Webhook URL and delivery options
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Network request fails
+    /// - Response parsing fails
+    pub async fn set_webhook(
+        &self,
+        params: WebhookConfig,
+    ) -> Result<TelegramResponse<bool>, anyhow::Error> {
+        let response = self.provider
+            .send_request(&TelegramAPI::SetWebhook(params))
+            .await?;
+        let result: TelegramResponse<bool> = response.json().await?;
+        Ok(result)
+    }
+
+    /// Removes the webhook so updates can be fetched with long polling again.
+    ///
+    /// # Arguments
+    /// * `drop_pending_updates` - Whether to drop all pending updates
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Network request fails
+    /// - Response parsing fails
+    pub async fn delete_webhook(
+        &self,
+        drop_pending_updates: bool,
+    ) -> Result<TelegramResponse<bool>, anyhow::Error> {
+        let response = self.provider
+            .send_request(&TelegramAPI::DeleteWebhook { drop_pending_updates })
+            .await?;
+        let result: TelegramResponse<bool> = response.json().await?;
+        Ok(result)
+    }
+
+    /// Gets the current webhook status.
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Network request fails
+    /// - Response parsing fails
+    pub async fn get_webhook_info(
+        &self,
+    ) -> Result<TelegramResponse<WebhookInfo>, anyhow::Error> {
+        let response = self.provider
+            .send_request(&TelegramAPI::GetWebhookInfo)
+            .await?;
+        let result: TelegramResponse<WebhookInfo> = response.json().await?;
+        Ok(result)
+    }
+
+    /// Gets information about the bot, confirming the token is valid.
+    ///
+    /// # Errors
+    /// Returns `Err` if:
+    /// - Network request fails
+    /// - Response parsing fails
+    pub async fn get_me(&self) -> Result<TelegramResponse<User>, anyhow::Error> {
+        let response = self.provider
+            .send_request(&TelegramAPI::GetMe)
+            .await?;
+        let result: TelegramResponse<User> = response.json().await?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,9 @@
+//! Resumable HTTP upload client.
+//!
+//! This module uploads files and directory trees to an HTTP endpoint in
+//! chunks, so destinations that are app servers rather than filesystems can
+//! receive library content.
+//! 
+pub mod upload_client;
+
+pub use upload_client::*;
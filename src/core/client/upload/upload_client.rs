@@ -0,0 +1,370 @@
+use std::{
+    collections::BTreeMap,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Instant, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{
+    core::{
+        api::upload::{UploadAPI, UploadEndpoint, UPLOAD_OFFSET_HEADER},
+        library::state_file::{load_state, save_state}
+    },
+    infrastructure::{
+        fs::{DirScanner, FileProgressTracker, ProgressSender},
+        network::{NetworkPlugin, NetworkProvider}
+    },
+    debug_log,
+    info_log,
+    warn_log
+};
+
+/// Domain identifier for upload logs
+const UPLOAD_LOGGER_DOMAIN: &str = "[UPLOAD]";
+
+/// Default size of a single uploaded chunk (8 MiB).
+pub const UPLOAD_DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Version of a local file an upload was started for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct UploadedVersion {
+
+    /// Size of the file in bytes
+    size: u64,
+
+    /// Modification time of the file, in milliseconds since the Unix epoch
+    modified: u64,
+
+    /// Whether every byte was sent
+    complete: bool,
+}
+
+/// Uploads the client started, by remote path.
+///
+/// The server only reports how many bytes of a file it has, which can't
+/// tell a partial upload of the local file from another file of any size.
+/// The journal records which version of a local file each upload was
+/// started for, so only those are resumed or skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UploadJournal {
+
+    /// Version uploaded to each remote path
+    files: BTreeMap<String, UploadedVersion>,
+}
+
+/// Client uploading files to an HTTP endpoint in resumable chunks.
+///
+/// Before uploading a file, the client asks the server how many bytes it
+/// already has. An upload the client started for the same version of the
+/// file, by size and modification time, continues from there, so an
+/// interrupted upload doesn't start over; any other remote file is
+/// overwritten. Uploads are recorded in a journal, kept in memory unless
+/// a file is set for it. Construct using [`UploadClientBuilder`].
+pub struct UploadClient {
+
+    /// The network provider handling actual HTTP requests
+    provider: NetworkProvider,
+
+    /// Endpoint files are uploaded to
+    endpoint: UploadEndpoint,
+
+    /// Maximum number of bytes sent per request
+    chunk_size: u64,
+
+    /// Number of threads listing a directory before it is uploaded
+    scan_parallelism: usize,
+
+    /// File the journal is persisted to, if any
+    journal_path: Option<PathBuf>,
+
+    /// Uploads started by the client
+    journal: Mutex<UploadJournal>,
+
+    /// Channel for typed progress events of the uploads
+    progress_sender: Option<ProgressSender>,
+}
+
+/// Builder for creating configured `UploadClient` instances.
+pub struct UploadClientBuilder {
+    endpoint: UploadEndpoint,
+    chunk_size: u64,
+    scan_parallelism: usize,
+    journal_path: Option<PathBuf>,
+    progress_sender: Option<ProgressSender>,
+    plugins: Vec<Box<dyn NetworkPlugin>>,
+}
+
+impl UploadClientBuilder {
+
+    /// Creates a builder for the given endpoint with the default chunk size.
+    pub fn new(endpoint: UploadEndpoint) -> Self {
+        Self {
+            endpoint,
+            chunk_size: UPLOAD_DEFAULT_CHUNK_SIZE,
+            scan_parallelism: 1,
+            journal_path: None,
+            progress_sender: None,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of bytes sent per request.
+    ///
+    /// A chunk size of zero is replaced by the default.
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = if chunk_size == 0 { UPLOAD_DEFAULT_CHUNK_SIZE } else { chunk_size };
+        self
+    }
+
+    /// Sets the number of threads listing a directory before it is uploaded.
+    ///
+    /// Zero is treated as one.
+    pub fn with_scan_parallelism(mut self, workers: usize) -> Self {
+        self.scan_parallelism = workers.max(1);
+        self
+    }
+
+    /// Sets the file recording the uploads the client started, so they
+    /// are resumed or skipped across runs (builder pattern).
+    ///
+    /// Each endpoint needs its own journal, since entries are keyed by
+    /// remote path.
+    pub fn with_journal(mut self, path: impl Into<PathBuf>) -> Self {
+        self.journal_path = Some(path.into());
+        self
+    }
+
+    /// Sends a [`ProgressEvent`](crate::infrastructure::fs::ProgressEvent)
+    /// when an upload starts, after each chunk and when it is done
+    /// (builder pattern).
+    pub fn with_progress_sender(mut self, sender: ProgressSender) -> Self {
+        self.progress_sender = Some(sender);
+        self
+    }
+
+    /// Adds a network plugin to the client's configuration.
+    pub fn with_plugin(mut self, plugin: impl NetworkPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Constructs the `UploadClient`.
+    ///
+    /// An unreadable journal is discarded, which uploads every file again.
+    pub fn build(self) -> UploadClient {
+        let journal = self.journal_path
+            .as_deref()
+            .map(|path| load_state(path).unwrap_or_else(|e| {
+                warn_log!(
+                    UPLOAD_LOGGER_DOMAIN,
+                    format!("Failed to load upload journal {}: {}", path.display(), e)
+                );
+                UploadJournal::default()
+            }))
+            .unwrap_or_default();
+        UploadClient {
+            provider: NetworkProvider::new(self.plugins),
+            endpoint: self.endpoint,
+            chunk_size: self.chunk_size,
+            scan_parallelism: self.scan_parallelism,
+            journal_path: self.journal_path,
+            journal: Mutex::new(journal),
+            progress_sender: self.progress_sender,
+        }
+    }
+}
+
+impl UploadClient {
+
+    /// Creates a new `UploadClientBuilder` for the given endpoint.
+    pub fn builder(endpoint: UploadEndpoint) -> UploadClientBuilder {
+        UploadClientBuilder::new(endpoint)
+    }
+
+    /// Returns the number of bytes of `path` the server already has, or
+    /// `None` if the file doesn't exist there.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or the server answers
+    /// with an unexpected status.
+    pub async fn remote_offset(&self, path: &str) -> Result<Option<u64>, Error> {
+        let response = self.provider
+            .send_request(&UploadAPI::QueryOffset {
+                endpoint: self.endpoint.clone(),
+                path: path.to_string(),
+            })
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!("Querying '{}' failed with status {}", path, response.status()));
+        }
+
+        let headers = response.headers();
+        let offset = headers
+            .get(UPLOAD_OFFSET_HEADER)
+            .or_else(|| headers.get(reqwest::header::CONTENT_LENGTH))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        Ok(Some(offset))
+    }
+
+    /// Uploads a local file to `remote_path`, resuming a partial upload.
+    ///
+    /// The file is skipped if the client uploaded this version of it and
+    /// the server has all of it, and resumed if the client started
+    /// uploading this version; otherwise it is uploaded from the start.
+    ///
+    /// # Returns
+    /// The number of bytes sent, or `None` if the file was already uploaded.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read, a request fails
+    /// or the journal can't be saved.
+    pub async fn upload_file(
+        &self,
+        local_path: impl AsRef<Path>,
+        remote_path: &str,
+    ) -> Result<Option<u64>, Error> {
+        let mut file = tokio::fs::File::open(local_path.as_ref()).await?;
+        let metadata = file.metadata().await?;
+        let total = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_millis() as u64);
+        let version = UploadedVersion { size: total, modified, complete: false };
+
+        let started = self.journal
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .files
+            .get(remote_path)
+            .copied()
+            .filter(|uploaded| uploaded.size == total && uploaded.modified == modified);
+        let mut offset = match (self.remote_offset(remote_path).await?, started) {
+            (Some(offset), Some(uploaded)) if offset == total && uploaded.complete => {
+                debug_log!(UPLOAD_LOGGER_DOMAIN, format!("'{}' is already uploaded", remote_path));
+                return Ok(None);
+            }
+            (Some(offset), Some(_)) => offset,
+            _ => 0,
+        };
+        if offset >= total {
+            // The server has more than was sent, or the upload finished
+            // without being recorded; start over
+            offset = 0;
+        }
+        self.record(remote_path, version)?;
+
+        let start = offset;
+        let started_at = Instant::now();
+        let mut tracker = self.progress_sender.clone().map(FileProgressTracker::new);
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.started(remote_path);
+        }
+        file.seek(SeekFrom::Start(offset)).await?;
+        loop {
+            let length = self.chunk_size.min(total - offset);
+            let mut data = vec![0; length as usize];
+            file.read_exact(&mut data).await?;
+
+            let response = self.provider
+                .send_request(&UploadAPI::UploadChunk {
+                    endpoint: self.endpoint.clone(),
+                    path: remote_path.to_string(),
+                    offset,
+                    total,
+                    data,
+                })
+                .await?;
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Uploading '{}' at offset {} failed with status {}",
+                    remote_path,
+                    offset,
+                    response.status()
+                ));
+            }
+
+            offset += length;
+            if let Some(tracker) = tracker.as_mut() {
+                let percent = (offset * 100).checked_div(total).unwrap_or(100) as u8;
+                let elapsed = started_at.elapsed().as_secs_f64();
+                tracker.progress(percent, if elapsed > 0.0 { (offset - start) as f64 / elapsed } else { 0.0 });
+            }
+            if offset >= total {
+                break;
+            }
+        }
+
+        self.record(remote_path, UploadedVersion { complete: true, ..version })?;
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.finish();
+        }
+        Ok(Some(total - start))
+    }
+
+    /// Records the version of a file uploaded to a remote path, and saves
+    /// the journal if it is persisted.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the journal can't be saved.
+    fn record(&self, remote_path: &str, version: UploadedVersion) -> Result<(), Error> {
+        let mut journal = self.journal.lock().unwrap_or_else(|e| e.into_inner());
+        journal.files.insert(remote_path.to_string(), version);
+        if let Some(path) = &self.journal_path {
+            save_state(&*journal, path).with_context(|| format!("Failed to save upload journal {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Uploads every file below `source` that passes `filter`.
+    ///
+    /// Remote paths mirror the paths relative to `source`.
+    ///
+    /// # Returns
+    /// The remote paths of the files that needed uploading.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory can't be listed or any
+    /// upload fails.
+    pub async fn upload_dir(
+        &self,
+        source: impl AsRef<Path>,
+        filter: impl Fn(&Path) -> bool,
+    ) -> Result<Vec<String>, Error> {
+        let source = source.as_ref();
+        let mut uploaded = Vec::new();
+
+        for file in DirScanner::scan_parallel(source, self.scan_parallelism)? {
+            let relative = file.relative.as_path();
+            if !filter(relative) {
+                continue;
+            }
+            let path = source.join(relative);
+
+            let remote_path = relative.to_string_lossy().replace('\\', "/");
+            if let Some(sent) = self.upload_file(&path, &remote_path).await? {
+                info_log!(
+                    UPLOAD_LOGGER_DOMAIN,
+                    format!("Uploaded '{}' ({} bytes)", remote_path, sent)
+                );
+                uploaded.push(remote_path);
+            }
+        }
+
+        Ok(uploaded)
+    }
+}
@@ -0,0 +1,10 @@
+//! WebDAV listing client.
+//!
+//! This module lists collections of a WebDAV server and polls them for
+//! changes, descending only into collections whose tags changed.
+//! 
+pub mod webdav_client;
+pub mod webdav_poller;
+
+pub use webdav_client::*;
+pub use webdav_poller::*;
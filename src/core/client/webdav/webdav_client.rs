@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::{
+    core::{
+        api::webdav::{WebDavAPI, WebDavEndpoint, WebDavEntry},
+        strm::RemoteFile
+    },
+    infrastructure::network::{decode_url_path, NetworkPlugin, NetworkProvider}
+};
+
+/// Client listing collections of a WebDAV server.
+///
+/// Construct using [`WebDavClientBuilder`].
+pub struct WebDavClient {
+
+    /// The network provider handling actual HTTP requests
+    provider: NetworkProvider,
+
+    /// Server the collections are listed from
+    endpoint: WebDavEndpoint,
+}
+
+/// Builder for creating configured `WebDavClient` instances.
+pub struct WebDavClientBuilder {
+    endpoint: WebDavEndpoint,
+    plugins: Vec<Box<dyn NetworkPlugin>>,
+}
+
+impl WebDavClientBuilder {
+
+    /// Creates a builder for the given server.
+    pub fn new(endpoint: WebDavEndpoint) -> Self {
+        Self {
+            endpoint,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Adds a network plugin to the client's configuration.
+    pub fn with_plugin(mut self, plugin: impl NetworkPlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> WebDavClient {
+        WebDavClient {
+            provider: NetworkProvider::new(self.plugins),
+            endpoint: self.endpoint,
+        }
+    }
+}
+
+impl WebDavClient {
+
+    /// Creates a new `WebDavClientBuilder` for the given server.
+    pub fn builder(endpoint: WebDavEndpoint) -> WebDavClientBuilder {
+        WebDavClientBuilder::new(endpoint)
+    }
+
+    /// Lists a collection and its direct members.
+    ///
+    /// # Returns
+    /// Every resource keyed by its path relative to the endpoint, including
+    /// the collection itself.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or the response can't
+    /// be parsed.
+    pub async fn list(&self, path: &Path) -> Result<Vec<(PathBuf, WebDavEntry)>, Error> {
+        let response = self.provider
+            .send_request(&WebDavAPI::ListCollection {
+                endpoint: self.endpoint.clone(),
+                path: path.to_string_lossy().into_owned(),
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Listing '{}' failed with status {}",
+                path.display(),
+                response.status()
+            ));
+        }
+
+        let body = response.text().await?;
+        let root = self.root_path();
+        Ok(WebDavEntry::parse_multistatus(&body)?
+            .into_iter()
+            .filter_map(|entry| {
+                let relative = entry.href.strip_prefix(&root)?.trim_matches('/').to_string();
+                Some((PathBuf::from(relative), entry))
+            })
+            .collect())
+    }
+
+    /// Lists every file below a collection with its URL, one request per collection.
+    ///
+    /// # Returns
+    /// The files, sorted by path relative to `root`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if any collection can't be listed.
+    pub async fn list_files(&self, root: &Path) -> Result<Vec<RemoteFile>, Error> {
+        let root = PathBuf::from(root.to_string_lossy().trim_matches('/'));
+        let mut files = Vec::new();
+        let mut pending = vec![PathBuf::new()];
+
+        while let Some(collection) = pending.pop() {
+            for (path, entry) in self.list(&root.join(&collection)).await? {
+                let Ok(relative) = path.strip_prefix(&root).map(Path::to_path_buf) else {
+                    continue;
+                };
+                if relative == collection {
+                    continue;
+                }
+                if entry.is_collection {
+                    pending.push(relative);
+                } else {
+                    files.push(RemoteFile {
+                        url: Some(self.endpoint.file_url(&path.to_string_lossy())),
+                        size: entry.content_length.unwrap_or_default(),
+                        relative,
+                    });
+                }
+            }
+        }
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Ok(files)
+    }
+
+    /// Returns the decoded path of the endpoint on the server.
+    fn root_path(&self) -> String {
+        let base_url = &self.endpoint.base_url;
+        let path = match base_url.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("", |index| &rest[index..]),
+            None => base_url.as_str(),
+        };
+        decode_url_path(path.trim_end_matches('/'))
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf}
+};
+
+use crate::infrastructure::fs::{PollFuture, RemotePoller, RemoteSnapshot};
+use super::webdav_client::WebDavClient;
+
+/// Number of polls after which every collection is listed again,
+/// whatever its tags say.
+const WEBDAV_FULL_SCAN_EVERY: u32 = 10;
+
+/// Polls a collection tree of a WebDAV server for changes.
+///
+/// Each poll lists the polled collection and only descends into member
+/// collections whose ETag, ctag or modification time changed, so an
+/// unchanged tree costs a single request. Servers such as Nextcloud
+/// propagate changes to the tags of every parent collection; for servers
+/// that only update direct parents, every tenth poll walks the whole tree.
+pub struct WebDavPoller {
+
+    /// Client used to list collections
+    client: WebDavClient,
+
+    /// Path of the polled collection, relative to the endpoint
+    root: PathBuf,
+
+    /// Version of every collection seen by the previous poll, relative to `root`
+    collections: HashMap<PathBuf, String>,
+
+    /// Files seen by the previous poll
+    snapshot: RemoteSnapshot,
+
+    /// Number of polls since the last full walk
+    polls_since_full_scan: u32,
+}
+
+impl WebDavPoller {
+
+    /// Creates a poller for the collection `root` of a WebDAV server.
+    pub fn new(client: WebDavClient, root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref().to_string_lossy().trim_matches('/').to_string();
+        Self {
+            client,
+            root: PathBuf::from(root),
+            collections: HashMap::new(),
+            snapshot: RemoteSnapshot::default(),
+            polls_since_full_scan: 0,
+        }
+    }
+
+    /// Returns `true` if the subtree at `path` is known to be unchanged.
+    fn is_unchanged(&self, path: &Path, version: &str, full_scan: bool) -> bool {
+        !full_scan
+            && self.snapshot.has_baseline()
+            && self.collections.get(path).is_some_and(|previous| previous == version)
+    }
+}
+
+impl RemotePoller for WebDavPoller {
+
+    fn poll(&mut self) -> PollFuture<'_> {
+        Box::pin(async move {
+            let full_scan = self.polls_since_full_scan >= WEBDAV_FULL_SCAN_EVERY;
+            self.polls_since_full_scan = if full_scan { 0 } else { self.polls_since_full_scan + 1 };
+
+            let mut collections = HashMap::new();
+            let mut listing = BTreeMap::new();
+            let mut pending = vec![PathBuf::new()];
+
+            while let Some(collection) = pending.pop() {
+                for (path, entry) in self.client.list(&self.root.join(&collection)).await? {
+                    let Ok(relative) = path.strip_prefix(&self.root).map(Path::to_path_buf) else {
+                        continue;
+                    };
+                    let version = entry.version();
+
+                    if relative == collection {
+                        if relative.as_os_str().is_empty() && self.is_unchanged(&relative, &version, full_scan) {
+                            return Ok(Vec::new());
+                        }
+                        collections.insert(relative, version);
+                    } else if entry.is_collection {
+                        if self.is_unchanged(&relative, &version, full_scan) {
+                            collections.extend(
+                                self.collections
+                                    .iter()
+                                    .filter(|(path, _)| path.starts_with(&relative))
+                                    .map(|(path, version)| (path.clone(), version.clone()))
+                            );
+                            listing.extend(
+                                self.snapshot
+                                    .files_under(&relative)
+                                    .map(|(path, version)| (path.clone(), version.clone()))
+                            );
+                        } else {
+                            collections.insert(relative.clone(), version);
+                            pending.push(relative);
+                        }
+                    } else {
+                        listing.insert(relative, version);
+                    }
+                }
+            }
+
+            self.collections = collections;
+            Ok(self.snapshot.update(Path::new(""), listing))
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::fs::PathHelper;
+
+/// Directory inside the state directory holding scheduled backups by default.
+const BACKUP_DEFAULT_DIR_NAME: &str = "backups";
+
+/// Number of scheduled backups kept by default.
+const BACKUP_DEFAULT_KEEP: usize = 7;
+
+/// Scheduled backups of the configuration and state.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BackupConfig {
+
+    /// Seconds between two backups while watching, disabled when unset
+    pub interval_secs: Option<u64>,
+
+    /// Directory backups are written to (defaults to `backups` in the state directory)
+    pub dir: Option<String>,
+
+    /// Number of backups kept, older ones are deleted
+    pub keep: usize,
+
+    /// Whether each backup is also sent to the Telegram chat
+    pub send_to_telegram: bool,
+}
+
+impl Default for BackupConfig {
+
+    /// Keeps 7 backups in the state directory, without a schedule.
+    fn default() -> Self {
+        Self {
+            interval_secs: None,
+            dir: None,
+            keep: BACKUP_DEFAULT_KEEP,
+            send_to_telegram: false,
+        }
+    }
+}
+
+impl BackupConfig {
+
+    /// Returns the time between two backups, `None` if disabled.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Returns the directory backups are written to.
+    pub fn dir(&self, state_dir: &Path) -> PathBuf {
+        match &self.dir {
+            Some(dir) => PathHelper::expand_tilde(dir),
+            None => state_dir.join(BACKUP_DEFAULT_DIR_NAME),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::HashSet,
+    env,
+    fs,
+    path::PathBuf,
+    sync::{Arc, RwLock}
+};
+
+use anyhow::anyhow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    infrastructure::fs::{Collation, PathHelper},
+    warn_log
+};
+use super::{
+    backup_config::BackupConfig,
+    emby_config::EmbyConfig,
+    health_config::HealthConfig,
+    library_config::LibraryConfig,
+    notification_config::NotificationConfig,
+    telegram_config::TelegramConfig
+};
+
+/// Domain identifier for configuration logs
+const CONFIG_LOGGER_DOMAIN: &str = "[CONFIG]";
+
+/// Environment variable that overrides the configuration file location.
+pub const CONFIG_PATH_ENV: &str = "PILIPILI_STRM_CONFIG";
+
+/// Default configuration file name.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Application directory name under the user's configuration directory.
+const APP_DIR_NAME: &str = "pilipili_strm";
+
+/// Lazily loaded global configuration instance, replaceable at runtime.
+static CONFIG: Lazy<RwLock<Arc<Config>>> = Lazy::new(|| {
+    let config = Config::load();
+    Collation::set_current(config.collation);
+    RwLock::new(Arc::new(config))
+});
+
+/// Root application configuration.
+///
+/// Every section falls back to its default when missing from the file,
+/// so a partial configuration is always valid.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+
+    /// Telegram bot settings
+    pub telegram: TelegramConfig,
+
+    /// Emby server settings
+    pub emby: EmbyConfig,
+
+    /// Notification message settings
+    pub notification: NotificationConfig,
+
+    /// Media libraries, each synchronized independently
+    pub libraries: Vec<LibraryConfig>,
+
+    /// Scheduled health checks
+    pub health: HealthConfig,
+
+    /// Scheduled backups
+    pub backup: BackupConfig,
+
+    /// Read-only observer mode: every library runs as a dry run, so
+    /// changes are scanned, planned, logged and notified but destinations
+    /// are never written
+    pub observer: bool,
+
+    /// Simulated failures, see [`FaultInjector`](crate::infrastructure::chaos::FaultInjector)
+    #[cfg(feature = "chaos")]
+    pub chaos: crate::infrastructure::chaos::FaultRates,
+
+    /// Directory for persistent runtime state (defaults to the config directory)
+    pub state_dir: Option<String>,
+
+    /// Order of names in plans, digests and reports: `natural`, or
+    /// `chinese` to also compare Chinese numerals by value
+    pub collation: Collation,
+}
+
+impl Config {
+
+    /// Returns the global configuration instance.
+    ///
+    /// The configuration is loaded on first access and cached until it is
+    /// replaced with [`Config::apply`]. The returned snapshot stays valid
+    /// even if a new configuration is applied afterwards.
+    pub fn get() -> Arc<Config> {
+        CONFIG
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replaces the global configuration.
+    ///
+    /// Subsequent calls to [`Config::get`] return the new configuration, and
+    /// listings follow its collation.
+    pub fn apply(config: Config) {
+        Collation::set_current(config.collation);
+        *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+    }
+
+    /// Validates a TOML configuration and applies it if valid.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` and keeps the current configuration if the
+    /// content can't be parsed.
+    pub fn apply_toml(content: &str) -> Result<(), anyhow::Error> {
+        let config = Self::from_toml(content)?;
+        Self::apply(config);
+        Ok(())
+    }
+
+    /// Parses a configuration from a TOML string.
+    ///
+    /// In observer mode, every library is switched to a dry run. Builds
+    /// without the `chaos` feature ignore a `[chaos]` table with a warning,
+    /// as no failures can be simulated.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the content is not valid TOML,
+    /// doesn't match the configuration schema, declares the same
+    /// library name twice, has invalid library dependencies, an invalid
+    /// I/O priority or exclusion regex, a zero fallback poll interval, a
+    /// library both polling a remote server and scanning its source, a
+    /// `strm` target inside its library's source, invalid `strm`
+    /// settings, or chaos rates outside `0..=1`.
+    pub fn from_toml(content: &str) -> Result<Self, anyhow::Error> {
+        let mut config: Config = toml::from_str(content)?;
+        if config.observer {
+            for library in &mut config.libraries {
+                library.dry_run = true;
+            }
+        }
+
+        let mut names = HashSet::new();
+        for library in &config.libraries {
+            if !names.insert(library.name.as_str()) {
+                return Err(anyhow!("Duplicate library name '{}'", library.name));
+            }
+            if let Err(e) = library.io_priority.validate() {
+                return Err(anyhow!("Library '{}' has an invalid io_priority: {}", library.name, e));
+            }
+            if let Some(Err(e)) = library.exclude_regex.as_deref().map(Regex::new) {
+                return Err(anyhow!("Library '{}' has an invalid exclude_regex: {}", library.name, e));
+            }
+            if library.fallback_poll_secs == 0 {
+                return Err(anyhow!("Library '{}' needs a fallback_poll_secs above 0", library.name));
+            }
+            if library.remote_watch.is_some() && library.snapshot_watch.is_some() {
+                return Err(anyhow!("Library '{}' can't set both remote_watch and snapshot_watch", library.name));
+            }
+            if let Some(strm) = &library.strm {
+                let source = PathHelper::normalize(PathHelper::expand_tilde(&library.source));
+                if PathHelper::normalize(PathHelper::expand_tilde(&strm.target)).starts_with(&source) {
+                    return Err(anyhow!("Library '{}' can't write its strm target inside its source", library.name));
+                }
+                if let Err(e) = strm.validate() {
+                    return Err(anyhow!("Library '{}' has invalid strm settings: {}", library.name, e));
+                }
+            }
+        }
+
+        for library in &config.libraries {
+            if let Some(missing) = library.after.iter().find(|dep| !names.contains(dep.as_str())) {
+                return Err(anyhow!(
+                    "Library '{}' depends on unknown library '{}'",
+                    library.name,
+                    missing
+                ));
+            }
+        }
+
+        config.ordered_libraries(&[])?;
+
+        #[cfg(feature = "chaos")]
+        config.chaos.validate().map_err(|e| anyhow!("Invalid chaos settings: {}", e))?;
+        #[cfg(not(feature = "chaos"))]
+        if toml::from_str::<toml::Table>(content).is_ok_and(|file| file.contains_key("chaos")) {
+            warn_log!(
+                CONFIG_LOGGER_DOMAIN,
+                "Ignoring the [chaos] table, this build has no chaos feature to simulate failures with"
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Returns libraries in an order that honors their dependencies.
+    ///
+    /// Libraries listed in `after` come first; otherwise the declaration
+    /// order is kept. Dependencies only affect ordering and are not added
+    /// to the selection.
+    ///
+    /// # Arguments
+    /// * `names` - Libraries to include, or all libraries when empty
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a name is unknown or the dependencies
+    /// form a cycle.
+    pub fn ordered_libraries(&self, names: &[String]) -> Result<Vec<&LibraryConfig>, anyhow::Error> {
+        let selected: Vec<&LibraryConfig> = if names.is_empty() {
+            self.libraries.iter().collect()
+        } else {
+            names
+                .iter()
+                .map(|name| self.library(name).ok_or_else(|| anyhow!("Unknown library '{}'", name)))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut ordered: Vec<&LibraryConfig> = Vec::with_capacity(selected.len());
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut visiting: HashSet<&str> = HashSet::new();
+
+        for library in &self.libraries {
+            self.visit_library(library, &mut visiting, &mut done, &mut ordered)?;
+        }
+
+        ordered.retain(|library| selected.iter().any(|s| s.name == library.name));
+        Ok(ordered)
+    }
+
+    /// Depth-first visit used by [`Config::ordered_libraries`].
+    fn visit_library<'a>(
+        &'a self,
+        library: &'a LibraryConfig,
+        visiting: &mut HashSet<&'a str>,
+        done: &mut HashSet<&'a str>,
+        ordered: &mut Vec<&'a LibraryConfig>,
+    ) -> Result<(), anyhow::Error> {
+        if done.contains(library.name.as_str()) {
+            return Ok(());
+        }
+        if !visiting.insert(library.name.as_str()) {
+            return Err(anyhow!("Library '{}' has a circular dependency", library.name));
+        }
+
+        for dependency in &library.after {
+            if let Some(dependency) = self.library(dependency) {
+                self.visit_library(dependency, visiting, done, ordered)?;
+            }
+        }
+
+        visiting.remove(library.name.as_str());
+        done.insert(library.name.as_str());
+        ordered.push(library);
+        Ok(())
+    }
+
+    /// Finds a library by name.
+    pub fn library(&self, name: &str) -> Option<&LibraryConfig> {
+        self.libraries
+            .iter()
+            .find(|library| library.name == name)
+    }
+
+    /// Resolves the configuration file path.
+    ///
+    /// Uses the following order:
+    /// 1. `PILIPILI_STRM_CONFIG` environment variable
+    /// 2. `config.toml` in the current working directory
+    /// 3. `pilipili_strm/config.toml` in the user's configuration directory
+    pub fn config_path() -> Option<PathBuf> {
+        if let Ok(path) = env::var(CONFIG_PATH_ENV) {
+            return Some(PathHelper::expand_tilde(path));
+        }
+
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.exists() {
+            return Some(local);
+        }
+
+        PathHelper::config_dir()
+            .map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+            .filter(|path| path.exists())
+    }
+
+    /// Returns the configuration file path, or where it would be created
+    /// if there is none yet: the user's configuration directory.
+    pub fn config_path_or_default() -> PathBuf {
+        Self::config_path()
+            .or_else(|| PathHelper::config_dir().map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME)))
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+    }
+
+    /// Resolves the directory used for persistent runtime state.
+    ///
+    /// Uses `state_dir` when configured, otherwise `pilipili_strm` in the
+    /// user's configuration directory, falling back to the working directory.
+    pub fn state_dir(&self) -> PathBuf {
+        match &self.state_dir {
+            Some(dir) => PathHelper::expand_tilde(dir),
+            None => PathHelper::config_dir()
+                .map(|dir| dir.join(APP_DIR_NAME))
+                .unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+
+    /// Loads the configuration from disk, falling back to defaults.
+    fn load() -> Self {
+        let Some(path) = Self::config_path() else {
+            warn_log!(CONFIG_LOGGER_DOMAIN, "No configuration file found, using defaults.");
+            return Self::default();
+        };
+
+        let loaded = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Self::from_toml(&content));
+
+        match loaded {
+            Ok(config) => config,
+            Err(e) => {
+                warn_log!(
+                    CONFIG_LOGGER_DOMAIN,
+                    format!("Failed to load config {}: {}, using defaults.", path.display(), e)
+                );
+                Self::default()
+            }
+        }
+    }
+}
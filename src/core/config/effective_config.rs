@@ -0,0 +1,194 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use anyhow::{Error, Result};
+use toml::Value;
+
+use super::config::Config;
+
+/// Parts of key names whose values are masked, e.g. `bot_token` and
+/// `secret_access_key`, compared with the lowercased name.
+const SECRET_KEY_PATTERNS: [&str; 6] = ["token", "password", "secret", "api_key", "access_key", "authorization"];
+
+/// Text replacing a masked value.
+const MASKED_TEXT: &str = "********";
+
+/// Text replacing a masked value, as written in TOML.
+const MASKED_VALUE: &str = "\"********\"";
+
+/// Where the value of a setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSource {
+
+    /// Set in the configuration file
+    File,
+
+    /// Left out of the file, so the default applies
+    Default,
+
+    /// Set in the file but changed while loading, e.g. `dry_run` in
+    /// observer mode
+    Derived,
+}
+
+impl Display for ValueSource {
+
+    /// Formats the source as annotated in listings.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ValueSource::File => write!(f, "file"),
+            ValueSource::Default => write!(f, "default"),
+            ValueSource::Derived => write!(f, "derived"),
+        }
+    }
+}
+
+/// A setting of the effective configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveValue {
+
+    /// Dotted key, with indices of array entries, e.g. `libraries[0].name`
+    pub key: String,
+
+    /// Value as written in TOML, masked for secrets
+    pub value: String,
+
+    /// Where the value comes from
+    pub source: ValueSource,
+}
+
+/// The configuration as the application uses it, with file values merged
+/// into the defaults, secrets masked and the source of every value.
+///
+/// Useful to find why a setting doesn't apply, such as a filter set in
+/// the wrong table. Options left unset in the file and without a default
+/// are not listed.
+///
+/// # Examples
+/// ```
+/// use pilipili_strm::core::config::{EffectiveConfig, ValueSource};
+///
+/// let effective = EffectiveConfig::from_toml("[telegram]\nbot_token = \"123:abc\"")?;
+/// let token = effective.get("telegram.bot_token").unwrap();
+/// assert_eq!((token.value.as_str(), token.source), ("\"********\"", ValueSource::File));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EffectiveConfig {
+
+    /// Every setting, sorted by key
+    values: Vec<EffectiveValue>,
+}
+
+impl EffectiveConfig {
+
+    /// Loads a configuration file's content and resolves its effective values.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the content is not a valid configuration.
+    pub fn from_toml(content: &str) -> Result<Self, Error> {
+        let file: Value = toml::from_str(content)?;
+        Self::resolve(&Config::from_toml(content)?, &file)
+    }
+
+    /// Lists the values of a loaded configuration, comparing them with
+    /// the file it was loaded from.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the configuration can't be serialized.
+    pub fn resolve(config: &Config, file: &Value) -> Result<Self, Error> {
+        let mut effective = Self::default();
+        effective.flatten(String::new(), &Value::try_from(config)?, Some(file));
+        Ok(effective)
+    }
+
+    /// Returns a configuration file's settings as written, with secrets
+    /// masked like those of the effective configuration.
+    ///
+    /// Comments and formatting are not kept.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the content is not valid TOML.
+    pub fn mask_file(content: &str) -> Result<String, Error> {
+        let mut file: Value = toml::from_str(content)?;
+        Self::mask(&mut file);
+        Ok(toml::to_string_pretty(&file)?)
+    }
+
+    /// Returns every setting.
+    pub fn values(&self) -> &[EffectiveValue] {
+        &self.values
+    }
+
+    /// Returns the setting with a dotted key, e.g. `libraries[0].strict_mode`.
+    pub fn get(&self, key: &str) -> Option<&EffectiveValue> {
+        self.values.iter().find(|value| value.key == key)
+    }
+
+    /// Adds the settings below `key`, looking up the same key in the file.
+    fn flatten(&mut self, key: String, value: &Value, file: Option<&Value>) {
+        let child = |name: &str| if key.is_empty() { name.to_string() } else { format!("{}.{}", key, name) };
+        match value {
+            Value::Table(table) if !table.is_empty() => {
+                for (name, value) in table {
+                    self.flatten(child(name), value, file.and_then(|file| file.get(name)));
+                }
+            }
+            Value::Array(entries) if entries.iter().any(Value::is_table) => {
+                for (index, entry) in entries.iter().enumerate() {
+                    let file = file.and_then(|file| file.get(index));
+                    self.flatten(format!("{}[{}]", key, index), entry, file);
+                }
+            }
+            _ => {
+                let source = match file {
+                    None => ValueSource::Default,
+                    Some(file) if file.same_type(value) && file != value => ValueSource::Derived,
+                    Some(_) => ValueSource::File,
+                };
+                let secret = key.rsplit('.').next().is_some_and(Self::is_secret);
+                let value = if secret && value.as_str().is_none_or(|text| !text.is_empty()) {
+                    MASKED_VALUE.to_string()
+                } else {
+                    value.to_string()
+                };
+                self.values.push(EffectiveValue { key, value, source });
+            }
+        }
+    }
+
+    /// Masks the values of secret keys below a TOML value, leaving empty
+    /// strings visible so unset secrets can be told apart.
+    fn mask(value: &mut Value) {
+        match value {
+            Value::Table(table) => {
+                for (name, value) in table.iter_mut() {
+                    let secret = Self::is_secret(name) && !value.is_table() && !value.as_str().is_some_and(str::is_empty);
+                    if secret {
+                        *value = Value::String(MASKED_TEXT.to_string());
+                    } else {
+                        Self::mask(value);
+                    }
+                }
+            }
+            Value::Array(entries) => entries.iter_mut().for_each(Self::mask),
+            _ => {}
+        }
+    }
+
+    /// Returns `true` if the values of a key name are masked, in any case.
+    fn is_secret(name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        SECRET_KEY_PATTERNS.iter().any(|pattern| name.contains(pattern))
+    }
+}
+
+impl Display for EffectiveConfig {
+
+    /// Formats one `key = value  # source` line per setting.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for value in &self.values {
+            writeln!(f, "{} = {}  # {}", value.key, value.value, value.source)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Emby server configuration.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbyConfig {
+
+    /// Base URL of the Emby server (e.g. `http://127.0.0.1:8096`)
+    pub base_url: String,
+
+    /// API key used to authenticate requests
+    pub api_key: String,
+
+    /// Seconds a destination must go without writes before its changed
+    /// folders are reported to Emby, 0 to report them after every sync
+    pub refresh_settle_secs: u64,
+}
+
+impl Default for EmbyConfig {
+
+    /// Creates a configuration without server, refreshing after every sync.
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            api_key: String::new(),
+            refresh_settle_secs: 0,
+        }
+    }
+}
+
+impl EmbyConfig {
+
+    /// Returns how long a destination must go without writes before it is refreshed.
+    pub fn refresh_settle(&self) -> Duration {
+        Duration::from_secs(self.refresh_settle_secs)
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::fs::ByteSize;
+
+/// Periodic self-checks of the services and host conditions syncs depend on.
+///
+/// Host conditions, such as a full destination disk or a missing source
+/// mount, are the usual cause of failing syncs, so they are checked
+/// apart from sync runs and alerted on before the next sync fails.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HealthConfig {
+
+    /// Seconds between two rounds of checks while watching, disabled when unset
+    pub interval_secs: Option<u64>,
+
+    /// Free space every local and SSH destination must keep, e.g.
+    /// `"20GB"`; a library's `min_free_space` applies when unset
+    pub min_free_space: Option<ByteSize>,
+
+    /// Whether library sources must exist and hold files, which fails
+    /// when the volume they are mounted from is missing
+    pub check_sources: bool,
+
+    /// Size the state directory must stay below, e.g. `"500MB"`
+    pub max_state_size: Option<ByteSize>,
+}
+
+impl HealthConfig {
+
+    /// Returns the time between two rounds of checks, `None` if disabled.
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
@@ -0,0 +1,858 @@
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        api::{
+            s3::{S3Bucket, S3Credentials},
+            upload::UploadEndpoint
+        },
+        library::{listing_state::strm_checksums_path, SyncStrategy},
+        strm::{StrmGenerator, StrmLayout}
+    },
+    infrastructure::{
+        error::RetryClass,
+        fs::{
+            ByteSize, DirLocation, DirSyncConfig, IoPriority, MediaSizeLimits, MetadataPolicy, OverwritePolicy, PathHelper,
+            SshConfig, SyncTimeout, UncPath, WatchEventKind
+        }
+    }
+};
+use super::{
+    remote_watch_config::RemoteWatchConfig,
+    snapshot_watch_config::SnapshotWatchConfig,
+    strm_config::StrmConfig
+};
+
+/// Default debounce period between a filesystem change and the sync it triggers.
+const LIBRARY_DEFAULT_DEBOUNCE_SECS: u64 = 5;
+
+/// Default delay between two scans of the source when filesystem
+/// notifications are unavailable.
+const LIBRARY_DEFAULT_FALLBACK_POLL_SECS: u64 = 300;
+
+/// Default multiple of its estimated duration after which a running sync
+/// is reported as possibly stuck.
+const LIBRARY_DEFAULT_OVERRUN_FACTOR: f64 = 3.0;
+
+/// Default number of times a sync killed as stuck is retried.
+const LIBRARY_DEFAULT_HANG_RETRIES: u32 = 1;
+
+/// Default time a hook command may run before it is killed.
+const HOOK_DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Default number of consecutive failures that open a destination's circuit.
+const CIRCUIT_DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time an open circuit waits before probing the destination again.
+const CIRCUIT_DEFAULT_COOLDOWN_SECS: u64 = 300;
+
+/// Default number of times a sync failing transiently is retried, none
+/// unless enabled.
+const RETRY_DEFAULT_MAX_RETRIES: u32 = 0;
+
+/// Default delay before the first retry of a failed sync.
+const RETRY_DEFAULT_INITIAL_BACKOFF_SECS: u64 = 10;
+
+/// Default cap of the delay between two retries.
+const RETRY_DEFAULT_MAX_BACKOFF_SECS: u64 = 300;
+
+/// Default fraction by which retry delays are randomly shortened or
+/// lengthened, so destinations failing together don't retry in lockstep.
+const RETRY_DEFAULT_JITTER: f64 = 0.2;
+
+/// State of the generator drawing retry jitter.
+static RETRY_JITTER_STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Default region of S3 destinations, which MinIO also uses.
+const S3_DEFAULT_REGION: &str = "us-east-1";
+
+/// Environment variables holding S3 keys not set in the configuration.
+const S3_ACCESS_KEY_ID_ENV: &str = "AWS_ACCESS_KEY_ID";
+const S3_SECRET_ACCESS_KEY_ENV: &str = "AWS_SECRET_ACCESS_KEY";
+
+/// Commands run after a sync to a destination finished.
+///
+/// Commands are templates; `{library}`, `{destination}`, `{changed_count}`,
+/// `{changed_paths}` (space separated), `{changed_folders}` (the show and
+/// season folders holding them, likewise) and `{error}` are replaced
+/// before running them. Values are shell-quoted, so templates must not
+/// quote the variables themselves.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HookConfig {
+
+    /// Commands run on the destination host over SSH after a successful sync
+    pub on_success: Vec<String>,
+
+    /// Commands run on the destination host over SSH after a failed sync
+    pub on_failure: Vec<String>,
+
+    /// Commands run locally after a successful sync
+    pub local_on_success: Vec<String>,
+
+    /// Commands run locally after a failed sync
+    pub local_on_failure: Vec<String>,
+
+    /// Seconds each command may run, defaults to 60
+    pub timeout_secs: Option<u64>,
+}
+
+impl HookConfig {
+
+    /// Returns `true` if no command is configured.
+    pub fn is_empty(&self) -> bool {
+        self.on_success.is_empty()
+            && self.on_failure.is_empty()
+            && self.local_on_success.is_empty()
+            && self.local_on_failure.is_empty()
+    }
+
+    /// Returns the time each command may run.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(HOOK_DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// Circuit breaker protecting a destination that keeps failing.
+///
+/// After `failure_threshold` consecutive failures, syncs to the destination
+/// are skipped for `cooldown_secs`. The first sync after the cool-down
+/// probes the destination and closes the circuit if it succeeds; watched
+/// libraries whose syncs were skipped re-sync as soon as it ends.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+
+    /// Consecutive failures that open the circuit, `0` disables the breaker
+    pub failure_threshold: u32,
+
+    /// Seconds an open circuit skips syncs before probing again
+    pub cooldown_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+
+    /// Opens after 5 consecutive failures and probes every 5 minutes.
+    fn default() -> Self {
+        Self {
+            failure_threshold: CIRCUIT_DEFAULT_FAILURE_THRESHOLD,
+            cooldown_secs: CIRCUIT_DEFAULT_COOLDOWN_SECS,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+
+    /// Returns the time an open circuit skips syncs.
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+}
+
+/// Retries of a sync to a destination that failed transiently.
+///
+/// The delay doubles from `initial_backoff_secs` after each retry, up to
+/// `max_backoff_secs`, and is shortened or lengthened by up to `jitter`
+/// of itself. Syncs killed as stuck are retried as `hang_retries` says.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryConfig {
+
+    /// Times a failed sync is retried, `0`, the default, disables retries
+    pub max_retries: u32,
+
+    /// Seconds before the first retry
+    pub initial_backoff_secs: u64,
+
+    /// Most seconds between two retries
+    pub max_backoff_secs: u64,
+
+    /// Fraction of each delay it is randomly changed by, from `0` to `1`
+    pub jitter: f64,
+
+    /// Failures retried, `network` for connection failures and timeouts
+    /// and `server` for HTTP 429 and 5xx responses
+    pub retry_on: Vec<RetryClass>,
+}
+
+impl Default for RetryConfig {
+
+    /// Doesn't retry; with `max_retries` set, network and server failures
+    /// are retried after 10, 20, 40 seconds and so on.
+    fn default() -> Self {
+        Self {
+            max_retries: RETRY_DEFAULT_MAX_RETRIES,
+            initial_backoff_secs: RETRY_DEFAULT_INITIAL_BACKOFF_SECS,
+            max_backoff_secs: RETRY_DEFAULT_MAX_BACKOFF_SECS,
+            jitter: RETRY_DEFAULT_JITTER,
+            retry_on: vec![RetryClass::Network, RetryClass::Server],
+        }
+    }
+}
+
+impl RetryConfig {
+
+    /// Returns `true` if an error is of a class that is retried; syncs
+    /// killed for exceeding their transfer timeout never are.
+    pub fn is_retryable(&self, error: &anyhow::Error) -> bool {
+        !SyncTimeout::is_timeout(error)
+            && RetryClass::classify(error).is_some_and(|class| self.retry_on.contains(&class))
+    }
+
+    /// Returns the delay before a retry, without jitter.
+    ///
+    /// # Arguments
+    /// * `retry` - Number of the retry, starting at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u64.saturating_pow(retry.saturating_sub(1));
+        Duration::from_secs(self.initial_backoff_secs.saturating_mul(factor).min(self.max_backoff_secs))
+    }
+
+    /// Returns the delay before a retry, randomly changed by the jitter.
+    ///
+    /// # Arguments
+    /// * `retry` - Number of the retry, starting at 1
+    pub fn delay(&self, retry: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // splitmix64 over a counter seeded from the clock: no dependency needed
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or_default();
+        let mut z = seed.wrapping_add(RETRY_JITTER_STATE.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let random = (z >> 11) as f64 / (1u64 << 53) as f64;
+        self.backoff(retry).mul_f64(1.0 + jitter * (2.0 * random - 1.0))
+    }
+}
+
+/// Service and credentials of an `s3://bucket/prefix` destination.
+///
+/// Keys left out are read from `AWS_ACCESS_KEY_ID` and
+/// `AWS_SECRET_ACCESS_KEY`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct S3Config {
+
+    /// Service URL, e.g. `http://minio:9000` or
+    /// `https://<account>.r2.cloudflarestorage.com`; defaults to AWS in `region`
+    pub endpoint: Option<String>,
+
+    /// Region the bucket is in, defaults to `us-east-1`; R2 uses `auto`
+    pub region: Option<String>,
+
+    /// Access key ID
+    pub access_key_id: Option<String>,
+
+    /// Secret access key
+    pub secret_access_key: Option<String>,
+}
+
+/// A destination a library is synchronized to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DestinationConfig {
+
+    /// Destination directory path (local, or remote when `ssh` is set), an
+    /// `ssh://user@host:port/dir` URI, an SMB share path (`\\server\share\dir`),
+    /// an `http(s)://` URL files are uploaded to, or an `s3://bucket/prefix`
+    pub path: String,
+
+    /// SSH settings for remote destinations
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+
+    /// `Authorization` header sent to HTTP destinations
+    #[serde(default)]
+    pub authorization: Option<String>,
+
+    /// Bytes per request for HTTP destinations
+    #[serde(default)]
+    pub chunk_size: Option<u64>,
+
+    /// Commands run after each sync to this destination
+    #[serde(default)]
+    pub hooks: HookConfig,
+
+    /// Circuit breaker skipping syncs while the destination keeps failing
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Retries of syncs failing because of the network or the server
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// Path of this destination as the Emby server sees it; when set, the
+    /// show and season folders changed by each sync are reported to Emby,
+    /// which rescans only those
+    #[serde(default)]
+    pub emby_path: Option<String>,
+
+    /// Strategy used instead of the one selected from the address, e.g.
+    /// `native` to copy to a local destination without rsync, or
+    /// `robocopy` for SMB shares on Windows
+    #[serde(default)]
+    pub strategy: Option<SyncStrategy>,
+
+    /// Directory on the destination's filesystem, outside the destination,
+    /// partial files are written to before they are renamed into place
+    #[serde(default)]
+    pub temp_dir: Option<String>,
+
+    /// Service and credentials of `s3://` destinations
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+
+    /// Transfer rate per second to this destination, overriding that of
+    /// the library
+    #[serde(default)]
+    pub bandwidth_limit: Option<ByteSize>,
+}
+
+impl DestinationConfig {
+
+    /// Selects the strategy used to sync to this destination.
+    ///
+    /// The configured strategy takes precedence over the one selected from
+    /// the address. Local destinations default to the native strategy on
+    /// Windows, which ships without rsync.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if no strategy supports the destination
+    /// address, or the configured one doesn't.
+    pub fn strategy(&self) -> Result<SyncStrategy> {
+        let detected = SyncStrategy::for_destination(&self.path)?;
+        let is_local = detected == SyncStrategy::Rsync
+            && self.ssh_config().is_none()
+            && !self.path.to_ascii_lowercase().starts_with("rsync://");
+        match self.strategy {
+            None if cfg!(windows) && is_local => Ok(SyncStrategy::Native),
+            None => Ok(detected),
+            Some(strategy @ (SyncStrategy::Native | SyncStrategy::Robocopy)) if !is_local => {
+                Err(anyhow!("Strategy {} only supports local destinations, not '{}'", strategy, self.path))
+            }
+            Some(strategy @ (SyncStrategy::Native | SyncStrategy::Robocopy)) => Ok(strategy),
+            Some(strategy) if strategy != detected => {
+                Err(anyhow!("Strategy {} doesn't support '{}'", strategy, self.path))
+            }
+            Some(strategy) => Ok(strategy),
+        }
+    }
+
+    /// Returns the SSH settings of a remote destination, from its URI or `ssh` table.
+    pub fn ssh_config(&self) -> Option<SshConfig> {
+        DirLocation::parse(&self.path)
+            .ok()
+            .and_then(|location| location.ssh_config().cloned())
+            .or_else(|| self.ssh.clone())
+    }
+
+    /// Builds the upload endpoint of an HTTP destination.
+    pub fn to_upload_endpoint(&self) -> UploadEndpoint {
+        let endpoint = UploadEndpoint::new(&self.path);
+        match &self.authorization {
+            Some(authorization) => endpoint.with_authorization(authorization),
+            None => endpoint,
+        }
+    }
+
+    /// Returns the bucket of an `s3://bucket/prefix` destination.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the address names no bucket or the keys
+    /// are neither configured nor in the environment.
+    pub fn to_s3_bucket(&self) -> Result<S3Bucket> {
+        let (name, _) = self.s3_location()?;
+        let config = self.s3.clone().unwrap_or_default();
+        let key = |value: Option<String>, variable: &str| {
+            value
+                .or_else(|| std::env::var(variable).ok())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| anyhow!("No {} configured for '{}'", variable, self.path))
+        };
+        let region = config.region.unwrap_or_else(|| S3_DEFAULT_REGION.to_string());
+        Ok(S3Bucket {
+            endpoint: config.endpoint.unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region)),
+            name: name.to_string(),
+            credentials: S3Credentials {
+                access_key_id: key(config.access_key_id, S3_ACCESS_KEY_ID_ENV)?,
+                secret_access_key: key(config.secret_access_key, S3_SECRET_ACCESS_KEY_ENV)?,
+                region,
+            },
+        })
+    }
+
+    /// Returns the key prefix of an `s3://bucket/prefix` destination,
+    /// empty for the bucket root.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the address names no bucket.
+    pub fn s3_prefix(&self) -> Result<String> {
+        Ok(self.s3_location()?.1.trim_matches('/').to_string())
+    }
+
+    /// Splits an `s3://bucket/prefix` address into bucket and prefix.
+    fn s3_location(&self) -> Result<(&str, &str)> {
+        let location = self.path
+            .split_once("://")
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("s3"))
+            .map(|(_, location)| location)
+            .ok_or_else(|| anyhow!("'{}' is not an s3:// address", self.path))?;
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(anyhow!("'{}' names no bucket", self.path));
+        }
+        Ok((bucket, prefix))
+    }
+}
+
+/// A named media library with its own source, destinations and filters.
+///
+/// Each library runs as an independent pipeline, so operations such as
+/// syncing or watching can target a single library by name. A library
+/// needs at least one destination or a `strm` mirror tree.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LibraryConfig {
+
+    /// Unique library name used to address it (e.g. `movies`)
+    pub name: String,
+
+    /// Local source directory that is watched for changes
+    pub source: String,
+
+    /// Destinations the source, or its `.strm` mirror tree if any, is
+    /// synchronized to
+    #[serde(default)]
+    pub destinations: Vec<DestinationConfig>,
+
+    /// Local `.strm` mirror tree generated from the source before syncing
+    #[serde(default)]
+    pub strm: Option<StrmConfig>,
+
+    /// Whether files missing from the source are deleted at the destination
+    #[serde(default)]
+    pub strict_mode: bool,
+
+    /// Whether syncs only report the changes they would make, to preview
+    /// a strict-mode configuration safely
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// File suffixes to include (without leading dots)
+    #[serde(default)]
+    pub include_suffixes: Vec<String>,
+
+    /// File suffixes to exclude (without leading dots)
+    #[serde(default)]
+    pub exclude_suffixes: Vec<String>,
+
+    /// Regex pattern for excluding matching paths
+    #[serde(default)]
+    pub exclude_regex: Option<String>,
+
+    /// Suffixes of subtitle files synced along with their media files when
+    /// `include_suffixes` limits the sync to media, e.g. `["srt", "ass"]`
+    #[serde(default)]
+    pub subtitle_extensions: Vec<String>,
+
+    /// Size below which video files are skipped, e.g. `"200MB"`, to leave
+    /// out sample clips, trailers and placeholders
+    #[serde(default)]
+    pub min_video_size: Option<ByteSize>,
+
+    /// Size below which audio files are skipped, e.g. `"1MB"`
+    #[serde(default)]
+    pub min_audio_size: Option<ByteSize>,
+
+    /// Guard file that must exist for syncs to proceed
+    #[serde(default)]
+    pub guard_file: Option<String>,
+
+    /// Seconds to wait after the last change before syncing
+    #[serde(default = "LibraryConfig::default_debounce_secs")]
+    pub debounce_secs: u64,
+
+    /// Seconds between two scans of the source when filesystem
+    /// notifications are unavailable, e.g. because inotify watches ran out
+    #[serde(default = "LibraryConfig::default_fallback_poll_secs")]
+    pub fallback_poll_secs: u64,
+
+    /// Names of libraries that must be synced before this one
+    #[serde(default)]
+    pub after: Vec<String>,
+
+    /// Number of deletions above which a strict-mode sync needs confirmation
+    #[serde(default)]
+    pub confirm_deletions_above: Option<usize>,
+
+    /// Whether worker counts are chosen from latencies measured during the first runs
+    #[serde(default)]
+    pub auto_tune: bool,
+
+    /// Number of threads walking the source tree, overriding auto-tuning
+    #[serde(default)]
+    pub scan_parallelism: Option<usize>,
+
+    /// Number of destinations synced at once, overriding auto-tuning
+    #[serde(default)]
+    pub transfer_concurrency: Option<usize>,
+
+    /// Whether the source listing is cached so changes made while stopped
+    /// are found at startup without listing every directory again
+    #[serde(default)]
+    pub listing_cache: bool,
+
+    /// Remote server polled for changes instead of watching the source
+    #[serde(default)]
+    pub remote_watch: Option<RemoteWatchConfig>,
+
+    /// Periodic scans of the source detecting changes, instead of or
+    /// alongside filesystem notifications
+    #[serde(default)]
+    pub snapshot_watch: Option<SnapshotWatchConfig>,
+
+    /// Kinds of filesystem events that trigger a sync, all of them when
+    /// empty, e.g. `["create", "modify", "rename", "remove"]` to ignore
+    /// the access and metadata events some NFS servers emit constantly
+    #[serde(default)]
+    pub watch_events: Vec<WatchEventKind>,
+
+    /// CPU and disk priority of rsync processes and upload threads
+    #[serde(default)]
+    pub io_priority: IoPriority,
+
+    /// How files that already exist at a destination are treated; by
+    /// default rsync compares their size and modification time
+    #[serde(default)]
+    pub overwrite_policy: Option<OverwritePolicy>,
+
+    /// Whether `.nfo` files and artwork are copied, hard-linked or skipped
+    #[serde(default)]
+    pub metadata_policy: MetadataPolicy,
+
+    /// Multiple of the duration estimated from past runs after which a
+    /// running sync is reported as possibly stuck, 0 to never report it
+    #[serde(default = "LibraryConfig::default_overrun_factor")]
+    pub overrun_factor: f64,
+
+    /// Seconds rsync may go without output before it and its SSH
+    /// connections are killed as stuck, 0 to wait forever; off by
+    /// default, as rsync can be silent for long while it checksums or
+    /// lists large trees
+    #[serde(default)]
+    pub stall_timeout_secs: u64,
+
+    /// Seconds rsync may run in total before it and its SSH connections
+    /// are killed, 0 for no limit; a timed out sync isn't retried, as it
+    /// would transfer the same files from the start again
+    #[serde(default)]
+    pub transfer_timeout_secs: u64,
+
+    /// Times a sync killed as stuck is retried before it counts as failed
+    #[serde(default = "LibraryConfig::default_hang_retries")]
+    pub hang_retries: u32,
+
+    /// Whether transferred files are compared with their source by
+    /// checksum, failing the sync on any mismatch
+    #[serde(default)]
+    pub verify_transfers: bool,
+
+    /// Free space each destination and temporary directory must have for
+    /// a sync to start, e.g. `"10GB"`
+    #[serde(default)]
+    pub min_free_space: Option<ByteSize>,
+
+    /// Transfer rate per second rsync, native and robocopy syncs may not
+    /// exceed, e.g. `"2MB"`, so large syncs leave room on the uplink
+    #[serde(default)]
+    pub bandwidth_limit: Option<ByteSize>,
+
+    /// `exclude_regex` compiled on first use, `None` without one
+    #[serde(skip)]
+    exclude_pattern: OnceCell<Option<Regex>>,
+}
+
+impl LibraryConfig {
+
+    /// Returns the default debounce period in seconds.
+    fn default_debounce_secs() -> u64 {
+        LIBRARY_DEFAULT_DEBOUNCE_SECS
+    }
+
+    /// Returns the default delay between two fallback scans in seconds.
+    fn default_fallback_poll_secs() -> u64 {
+        LIBRARY_DEFAULT_FALLBACK_POLL_SECS
+    }
+
+    /// Returns the default overrun factor.
+    fn default_overrun_factor() -> f64 {
+        LIBRARY_DEFAULT_OVERRUN_FACTOR
+    }
+
+    /// Returns the default number of retries of a stuck sync.
+    fn default_hang_retries() -> u32 {
+        LIBRARY_DEFAULT_HANG_RETRIES
+    }
+
+    /// Returns the debounce period as a `Duration`.
+    pub fn debounce_time(&self) -> Duration {
+        Duration::from_secs(self.debounce_secs)
+    }
+
+    /// Returns the delay between two fallback scans as a `Duration`.
+    pub fn fallback_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.fallback_poll_secs)
+    }
+
+    /// Returns `true` if a sync deleting `deletions` files needs confirmation.
+    pub fn requires_confirmation(&self, deletions: usize) -> bool {
+        self.strict_mode && self.confirm_deletions_above.is_some_and(|limit| deletions > limit)
+    }
+
+    /// Builds one directory sync configuration per rsync destination.
+    ///
+    /// Destinations using other strategies are skipped, including native
+    /// and robocopy ones, which take theirs from
+    /// [`to_dir_sync_config`](Self::to_dir_sync_config).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the library has no destinations, a
+    /// destination is unsupported, or the exclusion regex is invalid.
+    pub fn to_dir_sync_configs(&self) -> Result<Vec<DirSyncConfig>> {
+        let mut configs = Vec::new();
+        for (destination, strategy) in self.destination_strategies()? {
+            if strategy == SyncStrategy::Rsync {
+                configs.push(self.to_dir_sync_config(destination)?);
+            }
+        }
+        Ok(configs)
+    }
+
+    /// Pairs every destination with the strategy used to sync to it.
+    ///
+    /// # Returns
+    /// No pairs for a generate-only library.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the library has neither destinations nor
+    /// a `strm` table, or a destination is unsupported. S3 destinations
+    /// upload each file in a single request held in memory, so they are
+    /// only supported for libraries syncing a `.strm` mirror tree rather
+    /// than their media files.
+    pub fn destination_strategies(&self) -> Result<Vec<(&DestinationConfig, SyncStrategy)>> {
+        if self.destinations.is_empty() && self.strm.is_none() {
+            return Err(anyhow!("Library '{}' has no destinations or strm target", self.name));
+        }
+
+        self.destinations
+            .iter()
+            .map(|destination| {
+                let strategy = destination.strategy()?;
+                if strategy == SyncStrategy::S3 && self.strm.is_none() {
+                    return Err(anyhow!(
+                        "S3 destination '{}' of library '{}' needs a strm table, only .strm mirror trees are uploaded to S3",
+                        destination.path,
+                        self.name
+                    ));
+                }
+                Ok((destination, strategy))
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the library only generates its `.strm` mirror
+    /// tree, without destinations to sync to.
+    pub fn is_generate_only(&self) -> bool {
+        self.destinations.is_empty() && self.strm.is_some()
+    }
+
+    /// Returns the directory synced to the destinations: the `.strm`
+    /// mirror tree if the library has one, the source otherwise.
+    pub fn sync_source(&self) -> String {
+        match &self.strm {
+            Some(strm) => strm.target.clone(),
+            None => self.source.clone(),
+        }
+    }
+
+    /// Returns `true` if a path relative to [`sync_source`](Self::sync_source)
+    /// is transferred to the destinations.
+    ///
+    /// Every file of a `.strm` mirror tree is, since it only holds what
+    /// was generated from the files passing the filters; otherwise see
+    /// [`matches_filters`](Self::matches_filters).
+    pub fn matches_sync_filters(&self, relative: &Path) -> bool {
+        self.strm.is_some() || self.matches_filters(relative)
+    }
+
+    /// Builds the generator of the library's `.strm` mirror tree.
+    ///
+    /// The generator takes the library's size limits, overwrite policy,
+    /// soft-delete settings and dry-run mode, and records the hashes of
+    /// the written contents in the state directory.
+    ///
+    /// # Returns
+    /// `None` if the library has no `strm` table.
+    pub fn to_strm_generator(&self) -> Option<StrmGenerator> {
+        let strm = self.strm.as_ref()?;
+        let layout = if strm.mirror { StrmLayout::Mirror } else { StrmLayout::StrmOnly };
+        let mut generator = StrmGenerator::new(
+            PathHelper::expand_tilde(&self.source),
+            PathHelper::expand_tilde(&strm.target)
+        )
+            .with_layout(layout)
+            .with_media_size_limits(self.media_size_limits())
+            .with_path_mappings(strm.path_mappings.clone())
+            .with_title_grouping(strm.title_grouping)
+            .with_signature_sniffing(strm.sniff_signatures)
+            .with_skipped_kinds(strm.skipped_kinds.clone())
+            .with_checksum_file(strm_checksums_path(self))
+            .with_dry_run(self.dry_run);
+        if let Some(template) = &strm.content_template {
+            generator = generator.with_content_template(template.clone());
+        }
+        if let Some(policy) = self.overwrite_policy {
+            generator = generator.with_overwrite_policy(policy);
+        }
+        if let Some(dir) = &strm.soft_delete_dir {
+            generator = generator.with_soft_delete_dir(PathHelper::expand_tilde(dir));
+        }
+        if let Some(retention) = strm.soft_delete_retention() {
+            generator = generator.with_soft_delete_retention(retention);
+        }
+        if let Some(dir) = &strm.staging_dir {
+            generator = generator.with_staging_dir(PathHelper::expand_tilde(dir));
+        }
+        // Patterns are checked when the configuration is loaded
+        if let Ok(rules) = strm.media_kind_rules() {
+            generator = generator.with_kind_rules(rules);
+        }
+        Some(generator)
+    }
+
+    /// Returns the minimum sizes of video and audio files.
+    pub fn media_size_limits(&self) -> MediaSizeLimits {
+        MediaSizeLimits {
+            min_video_size: self.min_video_size,
+            min_audio_size: self.min_audio_size,
+        }
+    }
+
+    /// Returns `true` if a path relative to the source passes the library's
+    /// suffix, regex and size filters.
+    ///
+    /// Sizes are read from the source; a file that can't be read, e.g.
+    /// because it was removed, passes the size filter.
+    pub fn matches_filters(&self, relative: &Path) -> bool {
+        let has_suffix = |suffixes: &[String]| {
+            relative.extension().is_some_and(|extension| {
+                suffixes.iter().any(|suffix| {
+                    extension.eq_ignore_ascii_case(suffix.trim_start_matches('.'))
+                })
+            })
+        };
+
+        if !self.include_suffixes.is_empty() {
+            if !has_suffix(&self.include_suffixes) && !has_suffix(&self.subtitle_extensions) {
+                return false;
+            }
+        } else if has_suffix(&self.exclude_suffixes) {
+            return false;
+        }
+
+        // Checked when the configuration is loaded
+        let exclude = self
+            .exclude_pattern
+            .get_or_init(|| self.exclude_regex.as_deref().and_then(|regex| Regex::new(regex).ok()));
+        if exclude.as_ref().is_some_and(|regex| regex.is_match(&relative.to_string_lossy())) {
+            return false;
+        }
+
+        let limits = self.media_size_limits();
+        limits.is_empty() || !fs::metadata(PathHelper::expand_tilde(&self.source).join(relative))
+            .is_ok_and(|metadata| limits.is_undersized(relative, metadata.len()))
+    }
+
+    /// Builds the rsync configuration for a single destination.
+    ///
+    /// The source is [`sync_source`](Self::sync_source); a `.strm` mirror
+    /// tree is synced without the suffix, regex and size filters, which
+    /// apply to media files it doesn't hold. The destination path is
+    /// parsed with [`DirLocation::parse`]; SSH settings from the URI take
+    /// precedence over the `ssh` table. SMB share paths are resolved to
+    /// the directory the share is mounted at.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the destination can't be parsed, an SMB
+    /// share isn't mounted, or the exclusion regex is invalid.
+    pub fn to_dir_sync_config(&self, destination: &DestinationConfig) -> Result<DirSyncConfig> {
+        let mut location = DirLocation::parse(&destination.path)?;
+        if location.ssh_config().is_none() {
+            location = match UncPath::parse(&destination.path) {
+                Some(unc) if destination.ssh.is_none() => {
+                    DirLocation::new(&unc.resolve()?.to_string_lossy(), true, None)
+                }
+                _ => location.with_ssh_config(destination.ssh.clone()),
+            };
+        }
+
+        let mut config = DirSyncConfig::builder()
+            .with_source(DirLocation::new(&self.sync_source(), true, None))
+            .with_destination(location)
+            .with_strict_mode(self.strict_mode)
+            .with_dry_run(self.dry_run)
+            .with_io_priority(self.io_priority)
+            .with_stall_timeout(Duration::from_secs(self.stall_timeout_secs))
+            .with_transfer_timeout(Duration::from_secs(self.transfer_timeout_secs))
+            .with_verify_transfers(self.verify_transfers);
+        if self.strm.is_none() {
+            config = config
+                .with_include_suffixes(self.include_suffixes.iter().map(String::as_str).collect())
+                .with_exclude_suffixes(self.exclude_suffixes.iter().map(String::as_str).collect())
+                .with_subtitle_extensions(self.subtitle_extensions.iter().map(String::as_str).collect());
+        }
+
+        if let Some(regex) = self.exclude_regex.as_ref().filter(|_| self.strm.is_none()) {
+            config = config.with_exclude_regex(regex)?;
+        }
+
+        if let Some(guard_file) = &self.guard_file {
+            config = config.with_guard_file(guard_file);
+        }
+
+        if let Some(policy) = self.overwrite_policy {
+            config = config.with_overwrite_policy(policy);
+        }
+        config = config.with_metadata_policy(self.metadata_policy);
+
+        if let Some(size) = self.min_video_size.filter(|_| self.strm.is_none()) {
+            config = config.with_min_video_size(size);
+        }
+
+        if let Some(size) = self.min_audio_size.filter(|_| self.strm.is_none()) {
+            config = config.with_min_audio_size(size);
+        }
+
+        if let Some(temp_dir) = &destination.temp_dir {
+            config = config.with_temp_dir(temp_dir);
+        }
+
+        if let Some(size) = self.min_free_space {
+            config = config.with_min_free_space(size);
+        }
+
+        if let Some(rate) = destination.bandwidth_limit.or(self.bandwidth_limit) {
+            config = config.with_bandwidth_limit(rate);
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,38 @@
+//! Application configuration loading and access.
+//!
+//! This module provides the global configuration used across the crate with:
+//! - TOML-based configuration files
+//! - Sensible defaults for every section
+//! - Named libraries, each with its own sync pipeline
+//! - Remote servers polled in place of unwatchable sources
+//! - Periodic scans of sources with unreliable notifications
+//! - Local `.strm` mirror trees generated from library sources
+//! - Scheduled health checks of external services
+//! - Scheduled backups of the configuration and state
+//! - Lazy, process-wide access through [`Config::get`]
+//! - The effective configuration with masked secrets and value sources
+//! 
+#[allow(clippy::module_inception)]
+pub mod config;
+pub mod backup_config;
+pub mod effective_config;
+pub mod emby_config;
+pub mod health_config;
+pub mod library_config;
+pub mod notification_config;
+pub mod remote_watch_config;
+pub mod snapshot_watch_config;
+pub mod strm_config;
+pub mod telegram_config;
+
+pub use config::*;
+pub use backup_config::*;
+pub use effective_config::*;
+pub use emby_config::*;
+pub use health_config::*;
+pub use library_config::*;
+pub use notification_config::*;
+pub use remote_watch_config::*;
+pub use snapshot_watch_config::*;
+pub use strm_config::*;
+pub use telegram_config::*;
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Language of the built-in notification templates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLanguage {
+
+    /// English
+    #[default]
+    En,
+
+    /// Simplified Chinese
+    Zh,
+}
+
+impl Display for NotificationLanguage {
+
+    /// Formats the language as its configuration code.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let language_str = match self {
+            NotificationLanguage::En => "en",
+            NotificationLanguage::Zh => "zh",
+        };
+        write!(f, "{}", language_str)
+    }
+}
+
+/// How often a digest report is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestPeriod {
+
+    /// Every 24 hours
+    Daily,
+
+    /// Every 7 days
+    Weekly,
+}
+
+impl DigestPeriod {
+
+    /// Returns the time span a digest covers.
+    pub fn duration(&self) -> Duration {
+        match self {
+            DigestPeriod::Daily => Duration::from_secs(24 * 60 * 60),
+            DigestPeriod::Weekly => Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl Display for DigestPeriod {
+
+    /// Formats the period as its configuration name.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let period_str = match self {
+            DigestPeriod::Daily => "daily",
+            DigestPeriod::Weekly => "weekly",
+        };
+        write!(f, "{}", period_str)
+    }
+}
+
+/// Notification message configuration.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+
+    /// Language of the built-in templates
+    pub language: NotificationLanguage,
+
+    /// TOML file overriding built-in templates, keyed by event
+    /// (e.g. `sync_failed = "..."`)
+    pub templates_file: Option<String>,
+
+    /// Per-event MarkdownV2 formats, taking precedence over all templates
+    /// (e.g. `sync_completed = "*{title}* season {season}: {count} new"`)
+    ///
+    /// Placeholder values are escaped automatically.
+    pub formats: HashMap<String, String>,
+
+    /// Sends a digest of sync activity on this schedule while watching
+    pub digest: Option<DigestPeriod>,
+
+    /// Directory where each digest is also written as a Markdown report
+    pub digest_report_dir: Option<String>,
+}
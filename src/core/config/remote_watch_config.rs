@@ -0,0 +1,110 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::api::{alist::AlistEndpoint, webdav::WebDavEndpoint};
+
+/// Default delay between two polls of a remote source.
+const REMOTE_WATCH_DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Kind of server a remote source is polled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteWatchKind {
+
+    /// An Alist server, listed through its API
+    Alist,
+
+    /// A WebDAV server such as Nextcloud or Alist's WebDAV endpoint
+    Webdav,
+}
+
+impl Display for RemoteWatchKind {
+
+    /// Formats the kind as written in the configuration.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RemoteWatchKind::Alist => write!(f, "alist"),
+            RemoteWatchKind::Webdav => write!(f, "webdav"),
+        }
+    }
+}
+
+/// Remote server polled for changes instead of watching the local source.
+///
+/// Useful when the source is a mount of remote storage, such as an rclone
+/// mount of Alist or Nextcloud, which emits no filesystem notifications.
+/// Changes are reported against the local source path.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteWatchConfig {
+
+    /// Kind of server polled
+    pub kind: RemoteWatchKind,
+
+    /// Base URL of the server (e.g. `http://127.0.0.1:5244`)
+    ///
+    /// For WebDAV this is the WebDAV root, such as `http://127.0.0.1:5244/dav`.
+    pub url: String,
+
+    /// Remote directory that corresponds to the library source
+    #[serde(default = "RemoteWatchConfig::default_path")]
+    pub path: String,
+
+    /// Token used to authenticate requests
+    ///
+    /// Sent as is in the `Authorization` header, so WebDAV servers expect
+    /// a complete value such as `Basic <credentials>`.
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// Seconds between two polls
+    #[serde(default = "RemoteWatchConfig::default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Whether an Alist server is asked to bypass its own listing cache
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+impl RemoteWatchConfig {
+
+    /// Returns the default remote directory, the server root.
+    fn default_path() -> String {
+        "/".to_string()
+    }
+
+    /// Returns the default poll interval in seconds.
+    fn default_interval_secs() -> u64 {
+        REMOTE_WATCH_DEFAULT_INTERVAL_SECS
+    }
+
+    /// Returns the delay between two polls.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    /// Builds the endpoint of an Alist server.
+    pub fn to_alist_endpoint(&self) -> AlistEndpoint {
+        let endpoint = AlistEndpoint::new(&self.url);
+        match &self.token {
+            Some(token) => endpoint.with_token(token),
+            None => endpoint,
+        }
+    }
+
+    /// Builds the endpoint of a WebDAV server.
+    pub fn to_webdav_endpoint(&self) -> WebDavEndpoint {
+        let endpoint = WebDavEndpoint::new(&self.url);
+        match &self.token {
+            Some(token) => endpoint.with_authorization(token),
+            None => endpoint,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Default delay between two scans of a library source.
+const SNAPSHOT_WATCH_DEFAULT_INTERVAL_SECS: u64 = 300;
+
+/// Periodic scans of the local source compared with the previous scan,
+/// for volumes whose filesystem notifications are unreliable.
+///
+/// Useful for NFS and SMB mounts, which report only the changes made by
+/// the host watching them. The last scan is kept in the state directory,
+/// so changes made while stopped are found by the first scan.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SnapshotWatchConfig {
+
+    /// Seconds between two scans
+    pub interval_secs: u64,
+
+    /// Whether filesystem notifications are still watched between scans,
+    /// rather than replaced by them
+    pub alongside_notify: bool,
+}
+
+impl Default for SnapshotWatchConfig {
+
+    /// Scans every 5 minutes instead of watching notifications.
+    fn default() -> Self {
+        Self {
+            interval_secs: SNAPSHOT_WATCH_DEFAULT_INTERVAL_SECS,
+            alongside_notify: false,
+        }
+    }
+}
+
+impl SnapshotWatchConfig {
+
+    /// Returns the delay between two scans.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
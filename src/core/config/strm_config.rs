@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::strm::{PathMappings, StrmContentTemplate, SwapMode},
+    infrastructure::fs::{MediaKind, MediaKindRule, MediaKindRules, PathHelper}
+};
+
+/// Longest retention of soft-deleted files, 100 years.
+const STRM_MAX_SOFT_DELETE_RETENTION_DAYS: u64 = 36_500;
+
+/// Local `.strm` mirror tree a library keeps up to date from its source.
+///
+/// Generated before any destination is synced, and synced to the
+/// destinations instead of the source. The target can't be inside the
+/// source. A library with a `strm` table and no destinations is
+/// generate-only: its watcher and scans only refresh the mirror, and
+/// nothing is transferred.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StrmConfig {
+
+    /// Directory the `.strm` files are written to
+    pub target: String,
+
+    /// Whether companion files such as `.nfo`, posters and subtitles are
+    /// copied alongside, so the target is a complete library
+    #[serde(default)]
+    pub mirror: bool,
+
+    /// Content written into `.strm` files, e.g.
+    /// `"http://nas:8096/media/{relative_path_encoded}"`; the media
+    /// file's path by default
+    #[serde(default)]
+    pub content_template: Option<StrmContentTemplate>,
+
+    /// Prefix mappings applied to the media file's path, for media
+    /// servers mounting the source elsewhere
+    #[serde(default)]
+    pub path_mappings: PathMappings,
+
+    /// Whether disc folders and multi-part releases get a single `.strm` file
+    #[serde(default)]
+    pub title_grouping: bool,
+
+    /// Whether media files are recognized by their content as well as
+    /// their suffix, so mislabelled media is found and text renamed to
+    /// `.mkv` is skipped
+    #[serde(default)]
+    pub sniff_signatures: bool,
+
+    /// Kinds of media files that get no `.strm` file, e.g.
+    /// `["trailer", "sample"]`
+    #[serde(default)]
+    pub skipped_kinds: Vec<MediaKind>,
+
+    /// Rules classifying media files, tried before the default trailer,
+    /// sample and extras keywords
+    #[serde(default)]
+    pub kind_rules: Vec<MediaKindRule>,
+
+    /// Directory orphaned `.strm` files are moved to, in a subdirectory
+    /// per day, instead of being deleted
+    #[serde(default)]
+    pub soft_delete_dir: Option<String>,
+
+    /// Days soft-deleted files are kept before they are purged, 0 to
+    /// keep them forever
+    #[serde(default)]
+    pub soft_delete_retention_days: u64,
+
+    /// How each generation is staged aside and swapped into the target,
+    /// `rename` or `symlink`; unset to update the target in place. A
+    /// staged generation always regenerates the whole mirror
+    #[serde(default)]
+    pub swap: Option<SwapMode>,
+
+    /// Directory staged generations are written to, on the target's
+    /// filesystem; next to the target by default
+    #[serde(default)]
+    pub staging_dir: Option<String>,
+}
+
+impl StrmConfig {
+
+    /// Returns how long soft-deleted files are kept, `None` if forever
+    /// or too long to represent.
+    pub fn soft_delete_retention(&self) -> Option<Duration> {
+        (self.soft_delete_retention_days > 0)
+            .then(|| self.soft_delete_retention_days.checked_mul(24 * 60 * 60))
+            .flatten()
+            .map(Duration::from_secs)
+    }
+
+    /// Returns the configured kind rules followed by the default ones.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a rule's pattern is invalid.
+    pub fn media_kind_rules(&self) -> Result<MediaKindRules, Error> {
+        MediaKindRules::with_configured(&self.kind_rules)
+    }
+
+    /// Checks the retention period, the soft-delete and staging
+    /// directories and the kind rules.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the retention exceeds 100 years, the
+    /// soft-delete or staging directory is inside the target, or a kind
+    /// rule has no keyword and no valid pattern.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.soft_delete_retention_days > STRM_MAX_SOFT_DELETE_RETENTION_DAYS {
+            return Err(anyhow!(
+                "soft_delete_retention_days must be at most {}, got {}",
+                STRM_MAX_SOFT_DELETE_RETENTION_DAYS,
+                self.soft_delete_retention_days
+            ));
+        }
+        let target = PathHelper::normalize(PathHelper::expand_tilde(&self.target));
+        for (key, dir) in [("soft_delete_dir", &self.soft_delete_dir), ("staging_dir", &self.staging_dir)] {
+            if dir.as_ref().is_some_and(|dir| PathHelper::normalize(PathHelper::expand_tilde(dir)).starts_with(&target)) {
+                return Err(anyhow!("{} can't be inside the target", key));
+            }
+        }
+        if let Some(rule) = self.kind_rules.iter().find(|rule| rule.keywords.is_empty() && rule.pattern.is_none()) {
+            return Err(anyhow!("kind rule for {} needs keywords or a pattern", rule.kind));
+        }
+        self.media_kind_rules().map_err(|e| anyhow!("kind_rules has an invalid pattern: {}", e))?;
+        Ok(())
+    }
+}
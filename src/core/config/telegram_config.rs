@@ -0,0 +1,112 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The public Telegram Bot API server.
+pub const TELEGRAM_DEFAULT_API_BASE: &str = "https://api.telegram.org";
+
+/// Permission level of a Telegram user allowed to control the bot.
+///
+/// Roles are ordered, so a higher role includes every permission of the
+/// lower ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelegramRole {
+
+    /// Can run read-only commands such as status queries
+    Viewer,
+
+    /// Can run every command, including destructive ones like strict syncs
+    Admin,
+}
+
+impl Display for TelegramRole {
+
+    /// Formats the role for display purposes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let role_str = match self {
+            TelegramRole::Viewer => "viewer",
+            TelegramRole::Admin => "admin",
+        };
+        write!(f, "{}", role_str)
+    }
+}
+
+/// A Telegram user allowed to send commands to the bot.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TelegramUser {
+
+    /// Telegram user ID (not the username, which can change)
+    pub id: i64,
+
+    /// Role granted to the user
+    pub role: TelegramRole,
+}
+
+/// Telegram bot configuration.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelegramConfig {
+
+    /// Bot token issued by BotFather
+    pub bot_token: String,
+
+    /// Default chat ID that receives notifications
+    pub chat_id: String,
+
+    /// Base URL of a self-hosted Bot API server (e.g. `http://127.0.0.1:8081`)
+    ///
+    /// Uses the public `https://api.telegram.org` server when not set.
+    pub api_base: Option<String>,
+
+    /// Whether the self-hosted Bot API server runs with `--local`
+    ///
+    /// Local servers accept uploads by absolute file path and return
+    /// absolute paths from `getFile` instead of downloadable relative paths.
+    pub local_mode: bool,
+
+    /// Users allowed to send commands to the bot
+    ///
+    /// Anyone not listed is denied, so an empty list disables remote control.
+    pub users: Vec<TelegramUser>,
+}
+
+impl TelegramConfig {
+
+    /// Returns the Bot API server base URL without a trailing slash.
+    pub fn api_base(&self) -> String {
+        self.api_base
+            .as_deref()
+            .filter(|base| !base.trim().is_empty())
+            .unwrap_or(TELEGRAM_DEFAULT_API_BASE)
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// Returns `true` if requests go to a self-hosted server running in local mode.
+    pub fn is_local(&self) -> bool {
+        self.local_mode && self.api_base.is_some()
+    }
+
+    /// Returns the role granted to a user, or `None` if the user isn't allowed.
+    pub fn role_of(&self, user_id: i64) -> Option<TelegramRole> {
+        self.users
+            .iter()
+            .find(|user| user.id == user_id)
+            .map(|user| user.role)
+    }
+
+    /// Checks whether a user may run a command that requires `required`.
+    ///
+    /// # Arguments
+    /// * `user_id` - Telegram ID of the user sending the command
+    /// * `required` - Minimum role the command needs
+    pub fn authorize(&self, user_id: i64, required: TelegramRole) -> bool {
+        self.role_of(user_id)
+            .is_some_and(|role| role >= required)
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant}
+};
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::{Config, DestinationConfig, LibraryConfig},
+    infrastructure::fs::{DirLocation, FdUsage, PathHelper, SshRunner, FD_PER_SCAN_WORKER, FD_PER_TRANSFER},
+    info_log,
+    warn_log
+};
+use super::{
+    state_file::{load_state, lock_state, save_state},
+    sync_strategy::SyncStrategy
+};
+
+/// Domain identifier for auto-tuning logs
+const TUNING_LOGGER_DOMAIN: &str = "[TUNING]";
+
+/// File name of the persisted tuning state inside the state directory.
+const TUNING_STATE_FILE_NAME: &str = "tuning.json";
+
+/// Number of runs whose latencies are sampled before values are chosen.
+pub const TUNING_SAMPLE_RUNS: usize = 3;
+
+/// Number of metadata lookups timed per local latency probe.
+const TUNING_PROBE_COUNT: usize = 5;
+
+/// Upper bound for both scan parallelism and transfer concurrency.
+const TUNING_MAX_WORKERS: usize = 8;
+
+/// Upper bound for the number of destinations synced at once.
+const TUNING_MAX_TRANSFERS: usize = 4;
+
+/// Latency below which a source is treated as a fast local disk.
+const TUNING_FAST_LATENCY: Duration = Duration::from_millis(1);
+
+/// Latency at or above which a location is treated as network storage.
+const TUNING_SLOW_LATENCY: Duration = Duration::from_millis(10);
+
+/// Measures round-trip latency of source and destination locations.
+pub struct LatencyProbe;
+
+impl LatencyProbe {
+
+    /// Returns the median time of a metadata lookup on a local path.
+    ///
+    /// Returns `None` if the path can't be read.
+    pub fn local(path: impl AsRef<Path>) -> Option<Duration> {
+        let path = path.as_ref();
+        let mut samples = Vec::with_capacity(TUNING_PROBE_COUNT);
+        for _ in 0..TUNING_PROBE_COUNT {
+            let started = Instant::now();
+            fs::metadata(path).ok()?;
+            samples.push(started.elapsed());
+        }
+        samples.sort();
+        Some(samples[samples.len() / 2])
+    }
+
+    /// Returns the latency of a destination.
+    ///
+    /// Remote destinations are timed with one existence check over SSH.
+    /// HTTP, S3 and rclone destinations aren't probed and return `None`.
+    pub fn destination(destination: &DestinationConfig, strategy: SyncStrategy) -> Option<Duration> {
+        if matches!(strategy, SyncStrategy::HttpUpload | SyncStrategy::S3 | SyncStrategy::Rclone) {
+            return None;
+        }
+
+        match destination.ssh_config() {
+            Some(ssh_config) => {
+                let path = DirLocation::parse(&destination.path)
+                    .map(|location| location.get_path())
+                    .unwrap_or_else(|_| destination.path.clone());
+                let started = Instant::now();
+                SshRunner::new(ssh_config).path_exists(&path).ok()?;
+                Some(started.elapsed())
+            }
+            None => Self::local(PathHelper::expand_tilde(&destination.path)),
+        }
+    }
+}
+
+/// Tuning samples and chosen worker counts of one library.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryTuning {
+
+    /// Source latencies of the sampled runs, in microseconds
+    pub source_samples: Vec<u64>,
+
+    /// Slowest destination latency of the sampled runs, in microseconds
+    ///
+    /// Destinations that couldn't be probed count as slow.
+    pub destination_samples: Vec<u64>,
+
+    /// Number of destinations when the samples were taken
+    pub destinations: usize,
+
+    /// Chosen scan parallelism, set once enough runs were sampled
+    pub scan_parallelism: Option<usize>,
+
+    /// Chosen transfer concurrency, set once enough runs were sampled
+    pub transfer_concurrency: Option<usize>,
+}
+
+impl LibraryTuning {
+
+    /// Returns `true` once worker counts were chosen.
+    pub fn is_tuned(&self) -> bool {
+        self.scan_parallelism.is_some() && self.transfer_concurrency.is_some()
+    }
+
+    /// Adds the latencies of one run and chooses worker counts once
+    /// [`TUNING_SAMPLE_RUNS`] runs were sampled.
+    pub fn add_sample(&mut self, source: Duration, destination: Duration, destinations: usize) {
+        if self.is_tuned() {
+            return;
+        }
+
+        self.source_samples.push(source.as_micros() as u64);
+        self.destination_samples.push(destination.as_micros() as u64);
+        self.destinations = destinations;
+
+        if self.source_samples.len() >= TUNING_SAMPLE_RUNS {
+            let source = Duration::from_micros(median(&self.source_samples));
+            let destination = Duration::from_micros(median(&self.destination_samples));
+            self.scan_parallelism = Some(Self::scan_parallelism_for(source));
+            self.transfer_concurrency = Some(Self::transfer_concurrency_for(destination, destinations));
+        }
+    }
+
+    /// Chooses scan parallelism from the median source latency.
+    ///
+    /// Fast local disks gain nothing from parallel walks, while network
+    /// mounts hide their round trips behind more workers.
+    fn scan_parallelism_for(latency: Duration) -> usize {
+        if latency < TUNING_FAST_LATENCY {
+            1
+        } else if latency < TUNING_SLOW_LATENCY {
+            TUNING_MAX_WORKERS / 2
+        } else {
+            TUNING_MAX_WORKERS
+        }
+    }
+
+    /// Chooses how many destinations are synced at once from the median
+    /// latency of the slowest destination.
+    ///
+    /// Local destinations share the same disks and stay sequential.
+    fn transfer_concurrency_for(latency: Duration, destinations: usize) -> usize {
+        if latency < TUNING_SLOW_LATENCY {
+            1
+        } else {
+            destinations.clamp(1, TUNING_MAX_TRANSFERS)
+        }
+    }
+}
+
+/// Persisted auto-tuning state of all libraries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuningState {
+
+    /// Tuning of every library, keyed by library name
+    libraries: BTreeMap<String, LibraryTuning>,
+}
+
+impl TuningState {
+
+    /// Returns the default location of the tuning state file.
+    pub fn default_path() -> PathBuf {
+        Config::get().state_dir().join(TUNING_STATE_FILE_NAME)
+    }
+
+    /// Loads the tuning state from `path`.
+    ///
+    /// A missing file means no library was tuned yet.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file exists but can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        load_state(path.as_ref())
+    }
+
+    /// Loads the tuning state from the default location.
+    ///
+    /// Falls back to an empty state if the file is unreadable, which
+    /// restarts sampling instead of failing the sync.
+    pub fn current() -> Self {
+        let path = Self::default_path();
+        Self::load(&path).unwrap_or_else(|e| {
+            warn_log!(
+                TUNING_LOGGER_DOMAIN,
+                format!("Failed to load tuning state {}: {}", path.display(), e)
+            );
+            Self::default()
+        })
+    }
+
+    /// Writes the tuning state to `path`, creating parent directories as needed.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        save_state(self, path.as_ref())
+    }
+
+    /// Returns the tuning of a library, if it was sampled at least once.
+    pub fn library(&self, name: &str) -> Option<&LibraryTuning> {
+        self.libraries.get(name)
+    }
+
+    /// Returns the tuning of a library, creating an empty one if needed.
+    pub fn library_mut(&mut self, name: &str) -> &mut LibraryTuning {
+        self.libraries.entry(name.to_string()).or_default()
+    }
+
+    /// Probes the latencies of a library and persists them as one sample.
+    ///
+    /// Does nothing if auto-tuning is off or the library is already tuned.
+    /// The state file is locked and reloaded after probing, so concurrent
+    /// libraries don't drop each other's samples. Failures are logged,
+    /// since tuning never blocks a sync.
+    pub fn sample(config: &LibraryConfig) {
+        if !config.auto_tune {
+            return;
+        }
+        if Self::current().library(&config.name).is_some_and(LibraryTuning::is_tuned) {
+            return;
+        }
+
+        let Ok(destinations) = config.destination_strategies() else {
+            return;
+        };
+        let source = LatencyProbe::local(PathHelper::expand_tilde(&config.source))
+            .unwrap_or(TUNING_SLOW_LATENCY);
+        let destination = destinations
+            .iter()
+            .map(|(destination, strategy)| {
+                LatencyProbe::destination(destination, *strategy).unwrap_or(TUNING_SLOW_LATENCY)
+            })
+            .max()
+            .unwrap_or_default();
+
+        let path = Self::default_path();
+        let _lock = match lock_state(&path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                warn_log!(
+                    TUNING_LOGGER_DOMAIN,
+                    format!("Failed to lock tuning state {}: {}", path.display(), e)
+                );
+                return;
+            }
+        };
+        let mut state = Self::current();
+        let tuning = state.library_mut(&config.name);
+        if tuning.is_tuned() {
+            return;
+        }
+        tuning.add_sample(source, destination, destinations.len());
+        if tuning.is_tuned() {
+            info_log!(
+                TUNING_LOGGER_DOMAIN,
+                format!(
+                    "Library '{}' tuned to {} scan workers and {} concurrent transfers",
+                    config.name,
+                    tuning.scan_parallelism.unwrap_or(1),
+                    tuning.transfer_concurrency.unwrap_or(1)
+                )
+            );
+        }
+
+        if let Err(e) = state.save(&path) {
+            warn_log!(
+                TUNING_LOGGER_DOMAIN,
+                format!("Failed to save tuning state {}: {}", path.display(), e)
+            );
+        }
+    }
+}
+
+/// Worker counts a library runs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Concurrency {
+
+    /// Number of threads walking the source tree
+    pub scan_parallelism: usize,
+
+    /// Number of destinations synced at once
+    pub transfer_concurrency: usize,
+}
+
+impl Default for Concurrency {
+
+    /// Returns sequential scanning and transfers.
+    fn default() -> Self {
+        Self {
+            scan_parallelism: 1,
+            transfer_concurrency: 1,
+        }
+    }
+}
+
+impl Concurrency {
+
+    /// Resolves the worker counts of a library.
+    ///
+    /// Explicit configuration wins over tuned values, which are only used
+    /// when auto-tuning is on. Anything left unset runs sequentially.
+    pub fn resolve(config: &LibraryConfig, state: &TuningState) -> Self {
+        let tuning = config
+            .auto_tune
+            .then(|| state.library(&config.name))
+            .flatten();
+        let defaults = Self::default();
+
+        Self {
+            scan_parallelism: config
+                .scan_parallelism
+                .or_else(|| tuning.and_then(|tuning| tuning.scan_parallelism))
+                .unwrap_or(defaults.scan_parallelism)
+                .max(1),
+            transfer_concurrency: config
+                .transfer_concurrency
+                .or_else(|| tuning.and_then(|tuning| tuning.transfer_concurrency))
+                .unwrap_or(defaults.transfer_concurrency)
+                .max(1),
+        }
+    }
+
+    /// Reduces the worker counts until their file descriptors fit in the
+    /// descriptors still available.
+    ///
+    /// Scan workers are given up before transfers, and neither count drops
+    /// below one.
+    pub fn budgeted(self, usage: &FdUsage) -> Self {
+        let available = usage.available();
+        let mut budgeted = self;
+        loop {
+            let per_transfer = FD_PER_TRANSFER + budgeted.scan_parallelism as u64 * FD_PER_SCAN_WORKER;
+            if budgeted.transfer_concurrency as u64 * per_transfer <= available {
+                return budgeted;
+            }
+            if budgeted.scan_parallelism > 1 {
+                budgeted.scan_parallelism -= 1;
+            } else if budgeted.transfer_concurrency > 1 {
+                budgeted.transfer_concurrency -= 1;
+            } else {
+                return budgeted;
+            }
+        }
+    }
+
+    /// Resolves the worker counts of a library from the default tuning
+    /// state, within the file descriptors currently available.
+    pub fn current(config: &LibraryConfig) -> Self {
+        let concurrency = if config.auto_tune {
+            Self::resolve(config, &TuningState::current())
+        } else {
+            Self::resolve(config, &TuningState::default())
+        };
+
+        let Some(usage) = FdUsage::current() else {
+            return concurrency;
+        };
+        let budgeted = concurrency.budgeted(&usage);
+        if budgeted != concurrency {
+            warn_log!(
+                TUNING_LOGGER_DOMAIN,
+                format!(
+                    "Reduced workers of library '{}' to {} scan and {} transfer with {}",
+                    config.name, budgeted.scan_parallelism, budgeted.transfer_concurrency, usage
+                )
+            );
+        }
+        budgeted
+    }
+}
+
+/// Returns the median of a non-empty list of samples.
+fn median(samples: &[u64]) -> u64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+}
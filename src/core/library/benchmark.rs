@@ -0,0 +1,106 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    time::{Duration, Instant}
+};
+
+use anyhow::{Error, Result};
+
+use crate::{
+    core::config::LibraryConfig,
+    infrastructure::fs::{DirScanner, PathHelper}
+};
+use super::auto_tune::Concurrency;
+
+/// Throughput of the read-only stages of a library pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+
+    /// Number of files found in the source
+    pub files: usize,
+
+    /// Total size of the files found, in bytes
+    pub bytes: u64,
+
+    /// Number of files passing the library filters
+    pub matched: usize,
+
+    /// Time spent listing the source
+    pub scan_time: Duration,
+
+    /// Time spent applying the filters to every file
+    pub filter_time: Duration,
+}
+
+impl BenchmarkReport {
+
+    /// Returns the scan throughput in files per second.
+    pub fn scan_rate(&self) -> f64 {
+        Self::rate(self.files, self.scan_time)
+    }
+
+    /// Returns the filter throughput in files per second.
+    pub fn filter_rate(&self) -> f64 {
+        Self::rate(self.files, self.filter_time)
+    }
+
+    /// Returns `count` per second over `elapsed`.
+    fn rate(count: usize, elapsed: Duration) -> f64 {
+        match elapsed.as_secs_f64() {
+            secs if secs > 0.0 => count as f64 / secs,
+            _ => 0.0,
+        }
+    }
+}
+
+impl Display for BenchmarkReport {
+
+    /// Formats the report as one stage per line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(
+            f,
+            "scan:   {} files ({} bytes) in {:.2?}, {:.0} files/s",
+            self.files, self.bytes, self.scan_time, self.scan_rate()
+        )?;
+        write!(
+            f,
+            "filter: {} of {} files matched in {:.2?}, {:.0} files/s",
+            self.matched, self.files, self.filter_time, self.filter_rate()
+        )
+    }
+}
+
+/// Measures the read-only stages of a library against its real source.
+///
+/// Nothing is written and no destination is contacted, so it is safe to
+/// run against a live library. The source is scanned with the library's
+/// configured or tuned scan parallelism.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the source can't be listed.
+pub fn benchmark_library(config: &LibraryConfig) -> Result<BenchmarkReport, Error> {
+    let source = PathHelper::expand_tilde(&config.source);
+
+    let started = Instant::now();
+    let workers = Concurrency::current(config).scan_parallelism;
+    let files = DirScanner::scan_parallel(&source, workers)?;
+    let scan_time = started.elapsed();
+
+    let started = Instant::now();
+    let matched = files
+        .iter()
+        .filter(|file| config.matches_filters(&file.relative))
+        .count();
+    let filter_time = started.elapsed();
+
+    Ok(BenchmarkReport {
+        files: files.len(),
+        bytes: files.iter().map(|file| file.size).sum(),
+        matched,
+        scan_time,
+        filter_time,
+    })
+}
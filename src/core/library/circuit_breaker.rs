@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    sync::Mutex,
+    time::{Duration, Instant}
+};
+
+use once_cell::sync::Lazy;
+
+use crate::core::config::CircuitBreakerConfig;
+
+/// Circuit breakers keyed by destination path, shared by every library
+/// syncing into the same destination.
+static CIRCUIT_BREAKERS: Lazy<Mutex<HashMap<String, CircuitBreaker>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+
+    /// Syncs run normally
+    Closed,
+
+    /// Syncs are skipped until the cool-down has passed
+    Open {
+
+        /// Time left before the destination is probed again
+        remaining: Duration,
+    },
+
+    /// The cool-down has passed and the next sync probes the destination
+    HalfOpen,
+}
+
+impl Display for CircuitState {
+
+    /// Formats the state for logs.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open { remaining } => write!(f, "open for {}s", remaining.as_secs()),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Outcome of recording a sync result with a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitTransition {
+
+    /// The state didn't change in a way worth reporting
+    None,
+
+    /// The circuit just opened after the given number of consecutive failures
+    Opened { failures: u32 },
+
+    /// A probe succeeded and the circuit closed again
+    Recovered,
+}
+
+/// Stops syncing to a destination that keeps failing.
+///
+/// After `failure_threshold` consecutive failures the circuit opens and
+/// syncs are skipped for the cool-down. The first sync after it is let
+/// through as a probe: success closes the circuit, failure opens it for
+/// another cool-down without alerting again.
+///
+/// Libraries whose syncs were skipped or failed while the circuit was
+/// open are owed a re-sync. Watchers claim it with
+/// [`take_resync`](Self::take_resync) once the cool-down has passed, so
+/// the skipped changes reach the destination without waiting for another
+/// change to arrive.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+
+    /// Consecutive failures that open the circuit, `0` disables it
+    failure_threshold: u32,
+
+    /// Time syncs are skipped once the circuit is open
+    cooldown: Duration,
+
+    /// Consecutive failures so far
+    failures: u32,
+
+    /// When the circuit last opened, `None` while closed
+    opened_at: Option<Instant>,
+
+    /// Libraries owed a re-sync once the cool-down has passed
+    owed: BTreeSet<String>,
+}
+
+impl CircuitBreaker {
+
+    /// Creates a closed circuit breaker.
+    pub fn new(config: &CircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold,
+            cooldown: config.cooldown(),
+            failures: 0,
+            opened_at: None,
+            owed: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the state at `now`.
+    pub fn state(&self, now: Instant) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) => {
+                let elapsed = now.saturating_duration_since(opened_at);
+                if elapsed >= self.cooldown {
+                    CircuitState::HalfOpen
+                } else {
+                    CircuitState::Open { remaining: self.cooldown - elapsed }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if a sync may run at `now`.
+    pub fn allows(&self, now: Instant) -> bool {
+        !matches!(self.state(now), CircuitState::Open { .. })
+    }
+
+    /// Records that a sync of `library` was skipped or failed, owing it a
+    /// re-sync if the circuit is open.
+    pub fn defer(&mut self, library: &str) {
+        if self.opened_at.is_some() {
+            self.owed.insert(library.to_string());
+        }
+    }
+
+    /// Claims the re-sync owed to `library`, once the cool-down has passed
+    /// at `now`.
+    ///
+    /// # Returns
+    /// `true` if the library should sync again, which probes the
+    /// destination while the circuit is half-open.
+    pub fn take_resync(&mut self, library: &str, now: Instant) -> bool {
+        if !self.allows(now) {
+            return false;
+        }
+        self.owed.remove(library)
+    }
+
+    /// Records a successful sync.
+    pub fn record_success(&mut self) -> CircuitTransition {
+        self.failures = 0;
+        match self.opened_at.take() {
+            Some(_) => CircuitTransition::Recovered,
+            None => CircuitTransition::None,
+        }
+    }
+
+    /// Records a failed sync at `now`.
+    pub fn record_failure(&mut self, now: Instant) -> CircuitTransition {
+        self.failures = self.failures.saturating_add(1);
+        if self.failure_threshold == 0 {
+            return CircuitTransition::None;
+        }
+
+        match self.opened_at {
+            // A failed probe silently opens the circuit for another cool-down
+            Some(_) => {
+                self.opened_at = Some(now);
+                CircuitTransition::None
+            }
+            None if self.failures >= self.failure_threshold => {
+                self.opened_at = Some(now);
+                CircuitTransition::Opened { failures: self.failures }
+            }
+            None => CircuitTransition::None,
+        }
+    }
+
+    /// Opens the circuit at `now` without waiting for the failure threshold.
+    ///
+    /// Used for failures that will repeat until someone intervenes, such
+    /// as a read-only destination. Does nothing while the breaker is disabled.
+    pub fn trip(&mut self, now: Instant) -> CircuitTransition {
+        self.failures = self.failures.saturating_add(1);
+        if self.failure_threshold == 0 {
+            return CircuitTransition::None;
+        }
+
+        match self.opened_at.replace(now) {
+            Some(_) => CircuitTransition::None,
+            None => CircuitTransition::Opened { failures: self.failures },
+        }
+    }
+
+    /// Runs `f` with the shared circuit breaker of a destination.
+    ///
+    /// The breaker is created on first use, and picks up configuration
+    /// changes while it is closed.
+    pub fn with_destination<T>(
+        destination: &str,
+        config: &CircuitBreakerConfig,
+        f: impl FnOnce(&mut CircuitBreaker) -> T,
+    ) -> T {
+        let mut breakers = CIRCUIT_BREAKERS.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers
+            .entry(destination.to_string())
+            .or_insert_with(|| CircuitBreaker::new(config));
+        if breaker.opened_at.is_none() {
+            breaker.failure_threshold = config.failure_threshold;
+            breaker.cooldown = config.cooldown();
+        }
+        f(breaker)
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, Error, Result};
+use once_cell::sync::Lazy;
+
+use crate::{
+    core::{
+        client::TelegramClient,
+        config::Config
+    },
+    infrastructure::fs::{
+        shell_quote, ByteSize, DirLocation, DirScanner, DiskSpace, PathHelper, ReadOnlyDestination, SshConfig, SshRunner
+    }
+};
+use super::sync_strategy::SyncStrategy;
+
+/// Time the measured size of a state directory is reused for, since
+/// measuring it walks every file below it.
+const STATE_SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Last measured size of each state directory, with when it was measured.
+static STATE_SIZES: Lazy<Mutex<HashMap<PathBuf, (Instant, u64)>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// What a [`HealthCheck`] verifies.
+#[derive(Debug, Clone)]
+pub enum HealthProbe {
+
+    /// The Telegram bot token is accepted (`getMe`)
+    TelegramBot,
+
+    /// An SSH connection to the host can be opened
+    Ssh(SshConfig),
+
+    /// A temporary file can be created in the destination
+    WriteAccess(DirLocation),
+
+    /// The destination has at least this much free space
+    FreeSpace(DirLocation, ByteSize),
+
+    /// The library source exists and holds files
+    SourceMounted(PathBuf),
+
+    /// The library source on an SSH host exists and holds files
+    RemoteSourceMounted(SshConfig, String),
+
+    /// The files below the state directory stay below this size, measured
+    /// at most once an hour
+    StateSize(PathBuf, ByteSize),
+}
+
+/// A named self-check of a service syncs depend on.
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+
+    /// Name identifying the check in logs and notifications
+    pub name: String,
+
+    /// What the check verifies
+    pub probe: HealthProbe,
+}
+
+impl HealthCheck {
+
+    /// Creates the checks for the configured services: the Telegram bot,
+    /// every SSH host and every rsync destination not synced as a dry run.
+    ///
+    /// Host conditions are checked as configured in `[health]`: the free
+    /// space of every local and SSH destination, the sources of every
+    /// library, over SSH for remote ones, and the size of the state
+    /// directory.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a library destination is invalid.
+    pub fn from_config(config: &Config) -> Result<Vec<Self>, Error> {
+        let mut checks = Vec::new();
+        if !config.telegram.bot_token.is_empty() {
+            checks.push(Self { name: "telegram".to_string(), probe: HealthProbe::TelegramBot });
+        }
+
+        let mut hosts = HashSet::new();
+        for library in &config.libraries {
+            for (destination, strategy) in library.destination_strategies()? {
+                if matches!(strategy, SyncStrategy::HttpUpload | SyncStrategy::S3 | SyncStrategy::Rclone) {
+                    continue;
+                }
+                let location = library.to_dir_sync_config(destination)?.get_destination();
+                if let Some(ssh_config) = location.ssh_config() {
+                    let host = ssh_config.to_destination();
+                    if hosts.insert(host.clone()) {
+                        checks.push(Self { name: format!("ssh {}", host), probe: HealthProbe::Ssh(ssh_config.clone()) });
+                    }
+                }
+                if let Some(required) = config.health.min_free_space.or(library.min_free_space) {
+                    checks.push(Self {
+                        name: format!("free space {}", destination.path),
+                        probe: HealthProbe::FreeSpace(location.clone(), required),
+                    });
+                }
+                // Dry runs, as in observer mode, must not write to the destination
+                if !library.dry_run {
+                    checks.push(Self {
+                        name: format!("write {}", destination.path),
+                        probe: HealthProbe::WriteAccess(location),
+                    });
+                }
+            }
+            if config.health.check_sources && library.remote_watch.is_none() {
+                let source = DirLocation::parse(&library.source)?;
+                let probe = match source.ssh_config() {
+                    Some(ssh_config) => {
+                        let path = source.get_path();
+                        let remote_path = path.split_once(':').map_or(path.as_str(), |(_, path)| path);
+                        let remote_path = match remote_path.trim_end_matches('/') {
+                            "" => "/",
+                            trimmed => trimmed,
+                        };
+                        HealthProbe::RemoteSourceMounted(ssh_config.clone(), remote_path.to_string())
+                    }
+                    None => HealthProbe::SourceMounted(PathHelper::expand_tilde(&library.source)),
+                };
+                checks.push(Self { name: format!("source {}", library.name), probe });
+            }
+        }
+        if let Some(max) = config.health.max_state_size {
+            checks.push(Self { name: "state size".to_string(), probe: HealthProbe::StateSize(config.state_dir(), max) });
+        }
+        Ok(checks)
+    }
+
+    /// Runs the check, blocking until it completes.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` describing why the check failed.
+    pub fn run(&self) -> Result<(), Error> {
+        match &self.probe {
+            HealthProbe::TelegramBot => Self::check_telegram(),
+            HealthProbe::Ssh(ssh_config) => {
+                let output = SshRunner::new(ssh_config.clone()).run("true")?;
+                if !output.success() {
+                    return Err(anyhow!("SSH connection failed: {}", output.stderr.trim()));
+                }
+                Ok(())
+            }
+            HealthProbe::WriteAccess(location) => ReadOnlyDestination::check(location),
+            HealthProbe::FreeSpace(location, required) => DiskSpace::check(location, *required),
+            HealthProbe::SourceMounted(path) => Self::check_source(path),
+            HealthProbe::RemoteSourceMounted(ssh_config, path) => Self::check_remote_source(ssh_config, path),
+            HealthProbe::StateSize(dir, _) if !dir.exists() => Ok(()),
+            HealthProbe::StateSize(dir, max) => {
+                let size = Self::state_size(dir)?;
+                if size > max.bytes() {
+                    return Err(anyhow!("State directory '{}' holds {}, more than {}", dir.display(), ByteSize(size), max));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Fails if a source is missing or empty, as the mount point of a
+    /// missing volume is.
+    fn check_source(path: &Path) -> Result<(), Error> {
+        let mut entries = fs::read_dir(path)
+            .map_err(|e| anyhow!("Source '{}' is unavailable: {}", path.display(), e))?;
+        if entries.next().is_none() {
+            return Err(anyhow!("Source '{}' is empty, is its volume mounted?", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Fails if a source on an SSH host is missing or empty, like
+    /// [`check_source`](Self::check_source).
+    fn check_remote_source(ssh_config: &SshConfig, path: &str) -> Result<(), Error> {
+        let host = ssh_config.to_destination();
+        let script = format!(
+            "test -d {path} || exit 3; [ -n \"$(ls -A {path} | head -n 1)\" ] || exit 4",
+            path = shell_quote(path)
+        );
+        let output = SshRunner::new(ssh_config.clone()).run(&script)?;
+        match output.status {
+            Some(0) => Ok(()),
+            Some(3) => Err(anyhow!("Source '{}:{}' is unavailable: no such directory", host, path)),
+            Some(4) => Err(anyhow!("Source '{}:{}' is empty, is its volume mounted?", host, path)),
+            _ => Err(anyhow!("Checking source '{}:{}' failed: {}", host, path, output.stderr.trim())),
+        }
+    }
+
+    /// Returns the size of the files below a state directory, measuring it
+    /// again once [`STATE_SIZE_CHECK_INTERVAL`] has passed.
+    fn state_size(dir: &Path) -> Result<u64, Error> {
+        let mut sizes = STATE_SIZES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((measured, size)) = sizes.get(dir) {
+            if measured.elapsed() < STATE_SIZE_CHECK_INTERVAL {
+                return Ok(*size);
+            }
+        }
+        let size = DirScanner::scan(dir)?.iter().map(|file| file.size).sum();
+        sizes.insert(dir.to_path_buf(), (Instant::now(), size));
+        Ok(size)
+    }
+
+    /// Calls `getMe` from a dedicated thread with its own runtime.
+    fn check_telegram() -> Result<(), Error> {
+        thread::spawn(|| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let response = runtime.block_on(TelegramClient::builder().build().get_me())?;
+            if !response.ok {
+                return Err(anyhow!("Telegram rejected the bot token: {}", response));
+            }
+            Ok(())
+        })
+        .join()
+        .map_err(|_| anyhow!("Health check thread panicked"))?
+    }
+}
+
+/// Change of a check's status after a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthTransition {
+
+    /// The status didn't change
+    None,
+
+    /// The check was passing and now fails
+    Failed {
+
+        /// Error of the failed run
+        error: String,
+    },
+
+    /// The check was failing and now passes
+    Recovered,
+}
+
+/// Health of every check, so each failure is reported once.
+///
+/// A check starts healthy. Its first failure is reported as
+/// [`HealthTransition::Failed`]; later failures are not, until the check
+/// passes again and [`HealthTransition::Recovered`] is reported.
+#[derive(Debug, Clone, Default)]
+pub struct HealthMonitor {
+
+    /// Error of each failing check, keyed by check name
+    failing: BTreeMap<String, String>,
+}
+
+impl HealthMonitor {
+
+    /// Creates a monitor with every check healthy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the result of a check run.
+    pub fn record(&mut self, name: &str, result: &Result<(), Error>) -> HealthTransition {
+        match result {
+            Ok(()) if self.failing.remove(name).is_some() => HealthTransition::Recovered,
+            Ok(()) => HealthTransition::None,
+            Err(e) => {
+                let error = format!("{:#}", e);
+                match self.failing.insert(name.to_string(), error.clone()) {
+                    Some(_) => HealthTransition::None,
+                    None => HealthTransition::Failed { error },
+                }
+            }
+        }
+    }
+
+    /// Runs every check and records the results.
+    ///
+    /// # Returns
+    /// The checks whose status changed, with their transition.
+    pub fn run(&mut self, checks: &[HealthCheck]) -> Vec<(String, HealthTransition)> {
+        checks
+            .iter()
+            .map(|check| (check.name.clone(), self.record(&check.name, &check.run())))
+            .filter(|(_, transition)| *transition != HealthTransition::None)
+            .collect()
+    }
+
+    /// Returns `true` if no check is failing.
+    pub fn is_healthy(&self) -> bool {
+        self.failing.is_empty()
+    }
+
+    /// Returns the failing checks and their last error.
+    pub fn failing(&self) -> &BTreeMap<String, String> {
+        &self.failing
+    }
+}
+
+impl Display for HealthMonitor {
+
+    /// Formats the status as `healthy` or the list of failing checks.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.failing.is_empty() {
+            return write!(f, "healthy");
+        }
+        let failing: Vec<String> = self.failing
+            .iter()
+            .map(|(name, error)| format!("{}: {}", name, error))
+            .collect();
+        write!(f, "unhealthy ({})", failing.join("; "))
+    }
+}
@@ -0,0 +1,1355 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{mpsc, Arc, Mutex, Weak},
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use once_cell::sync::Lazy;
+use tracing::{info_span, Span};
+
+use crate::{
+    core::{
+        client::{
+            alist::{AlistClient, AlistPoller},
+            s3::S3Client,
+            upload::UploadClient,
+            webdav::{WebDavClient, WebDavPoller}
+        },
+        config::{Config, DestinationConfig, LibraryConfig, RemoteWatchConfig, RemoteWatchKind, SnapshotWatchConfig},
+        notification::{format_duration, ErrorThrottle, MediaInfo, NotificationKind, Notifier, ThrottleDecision},
+        strm::StrmGenerator
+    },
+    infrastructure::{
+        error::ErrorHint,
+        fs::{
+            ByteSize, DirLocation, DirSyncHelper, DiskSpace, EventFilter, FdUsage, FileWatchable, FileWatcher, IoPriority, MoveReplay,
+            NativeSync, NaturalOrder, PathHelper, ProgressReporter, ProgressSender, RcloneSync, ReadOnlyDestination,
+            RenameTracker, ResourceUsage, RobocopySync, SnapshotPoller, SyncHang, SyncPlan, SyncReport
+        },
+        logger::RunId
+    },
+    debug_log,
+    error_log,
+    info_log,
+    warn_log
+};
+use super::{
+    auto_tune::{Concurrency, TuningState},
+    circuit_breaker::{CircuitBreaker, CircuitState, CircuitTransition},
+    maintenance_state::MaintenanceState,
+    media_refresh::{AffectedFolders, EmbyRefreshQueue},
+    pause_state::PauseState,
+    sync_executor::{StrategyExecutor, SyncExecutor},
+    sync_estimate::SyncEstimate,
+    sync_history::{DestinationRun, SyncHistory, SyncRecord},
+    listing_state::{reconcile_listing, snapshot_path, strm_index_path, update_listing, upload_journal_path},
+    sync_hooks::{run_sync_hooks, HookContext},
+    sync_strategy::SyncStrategy
+};
+
+/// Domain identifier for library logs
+const LIBRARY_LOGGER_DOMAIN: &str = "[LIBRARY]";
+
+/// How often watchers check whether an open circuit finished its cool-down.
+const CIRCUIT_PROBE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Callback deciding whether a destructive plan for a destination may run.
+pub type ConfirmCallback = dyn Fn(&str, &SyncPlan) -> bool + Sync;
+
+/// Locks keyed by destination path, so two libraries never sync into the
+/// same destination at the same time.
+static DESTINATION_LOCKS: Lazy<Mutex<HashMap<String, Arc<Mutex<()>>>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+/// Serializes confirmations, so prompts of destinations synced at once
+/// never interleave.
+static CONFIRM_LOCK: Mutex<()> = Mutex::new(());
+
+/// Synchronization pipeline for a single named library.
+///
+/// Wraps a [`LibraryConfig`] and provides:
+/// - One-shot synchronization to all configured destinations, over rsync
+///   or resumable HTTP uploads
+/// - Generation of a local `.strm` mirror tree, alone or before syncing
+/// - Dry-run plans of what a synchronization would change
+/// - A filesystem watcher that synchronizes after changes settle
+///
+/// # Examples
+/// ```no_run
+/// use pilipili_strm::core::{config::Config, library::LibrarySync};
+///
+/// let config = Config::from_toml(r#"
+///     [[libraries]]
+///     name = "movies"
+///     source = "/srv/strm/movies"
+///
+///     [[libraries.destinations]]
+///     path = "/mnt/emby/movies"
+/// "#)?;
+/// let sync = LibrarySync::new(config.library("movies").unwrap().clone());
+/// for (destination, plan) in sync.plan()? {
+///     println!("{}:\n{}", destination, plan);
+/// }
+/// sync.sync()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// See `examples/watch_and_sync.rs` for a watcher.
+pub struct LibrarySync {
+
+    /// Configuration of the library
+    config: LibraryConfig,
+
+    /// Performs the transfer into each destination
+    executor: Arc<dyn SyncExecutor>,
+
+    /// Channel for the typed progress events of the transfers
+    progress_sender: Option<ProgressSender>,
+}
+
+impl LibrarySync {
+
+    /// Creates a new pipeline for the given library.
+    pub fn new(config: LibraryConfig) -> Self {
+        Self {
+            config,
+            executor: Arc::new(StrategyExecutor),
+            progress_sender: None,
+        }
+    }
+
+    /// Replaces the executor performing the transfers, e.g. with a recording
+    /// executor in tests.
+    pub fn with_executor(mut self, executor: Arc<dyn SyncExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Sends a [`ProgressEvent`](crate::infrastructure::fs::ProgressEvent)
+    /// as files of the library transfer, from every sync including those
+    /// of a watcher (builder pattern). robocopy destinations send none.
+    pub fn with_progress_sender(mut self, sender: ProgressSender) -> Self {
+        self.progress_sender = Some(sender);
+        self
+    }
+
+    /// Returns the library name.
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Returns the library configuration.
+    pub fn config(&self) -> &LibraryConfig {
+        &self.config
+    }
+
+    /// Synchronizes the library to all of its destinations.
+    ///
+    /// Every destination is attempted even if a previous one failed. Syncs
+    /// into a destination shared with another library wait for each other.
+    /// Syncs that would delete more files than `confirm_deletions_above`
+    /// allows are held back; use [`LibrarySync::sync_with_confirmation`]
+    /// to approve them.
+    ///
+    /// # Returns
+    /// The statistics of the transfers, added up across destinations.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the configuration is invalid or any
+    /// destination failed to synchronize.
+    pub fn sync(&self) -> Result<SyncReport, Error> {
+        Self::run_span(&self.config, &RunId::new()).in_scope(|| {
+            Self::sync_library(&self.config, self.executor.as_ref(), &Self::reject_deletions, self.progress_sender.as_ref())
+        })
+    }
+
+    /// Synchronizes the library, asking `confirm` before any sync whose
+    /// deletions exceed `confirm_deletions_above`.
+    ///
+    /// # Returns
+    /// The statistics of the transfers, added up across destinations.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the configuration is invalid, any
+    /// destination failed to synchronize, or a plan was rejected.
+    pub fn sync_with_confirmation(&self, confirm: &ConfirmCallback) -> Result<SyncReport, Error> {
+        Self::run_span(&self.config, &RunId::new()).in_scope(|| {
+            Self::sync_library(&self.config, self.executor.as_ref(), confirm, self.progress_sender.as_ref())
+        })
+    }
+
+    /// Computes what a sync would change in each destination, without executing it.
+    ///
+    /// # Returns
+    /// One `(destination, plan)` pair per destination whose strategy supports plans.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the configuration is invalid or any plan
+    /// can't be computed.
+    pub fn plan(&self) -> Result<Vec<(String, SyncPlan)>, Error> {
+        let mut plans = Vec::new();
+        for (destination, strategy) in self.config.destination_strategies()? {
+            if strategy == SyncStrategy::S3 {
+                let plan = Self::block_on_thread(&self.config, Self::s3_plan(&self.config, destination))?;
+                plans.push((destination.path.clone(), plan));
+                continue;
+            }
+            let sync_config = self.config.to_dir_sync_config(destination)?;
+            let path = sync_config.get_destination().get_path();
+            let plan = match strategy {
+                SyncStrategy::Rsync => DirSyncHelper::new(sync_config).plan()?,
+                SyncStrategy::Native => Self::block_on_thread(&self.config, NativeSync::new(sync_config).plan())?,
+                SyncStrategy::Robocopy => RobocopySync::new(sync_config).plan()?,
+                SyncStrategy::Rclone => RcloneSync::new(sync_config).plan()?,
+                SyncStrategy::HttpUpload | SyncStrategy::S3 => continue,
+            };
+            plans.push((path, plan));
+        }
+        Ok(plans)
+    }
+
+    /// Creates and starts a watcher that synchronizes the library on changes.
+    ///
+    /// Changes are skipped while the library is paused, and their destination
+    /// writes queued for later while maintenance mode is enabled. Both states
+    /// are re-read on every
+    /// change, so they can be toggled while watching. Syncs that change
+    /// files or fail are reported through the configured notifier.
+    ///
+    /// Libraries with a remote server poll it instead of watching the
+    /// source, and changes it reports go through the same debounce.
+    /// Libraries with snapshot watching likewise scan their source, in
+    /// place of or alongside its notifications.
+    ///
+    /// With the listing cache enabled, the source is compared with the
+    /// listing of the last successful sync in the background, and changes
+    /// made while the watcher was stopped are synced right away.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the configuration is invalid or the watcher
+    /// fails to start.
+    pub fn watch(&self) -> Result<FileWatcher, Error> {
+        // Validate up front so a broken library fails at startup, not on first change
+        self.config.to_dir_sync_configs()?;
+
+        let renames = RenameTracker::new(PathHelper::expand_tilde(&self.config.source));
+        let mut watcher = FileWatcher::new(&self.config.source, self.config.debounce_time())
+            .with_event_filter(EventFilter::new(self.config.watch_events.clone()))
+            .with_fallback_polling(self.config.fallback_poll_interval())
+            .with_rename_tracker(renames.clone());
+        if let Some(remote) = &self.config.remote_watch {
+            watcher = Self::with_remote_poller(watcher, remote);
+        } else if let Some(snapshot) = &self.config.snapshot_watch {
+            watcher = Self::with_snapshot_poller(watcher, &self.config, snapshot);
+        }
+        let config = self.config.clone();
+        let executor = self.executor.clone();
+        let progress = self.progress_sender.clone();
+        let notifier = Notifier::from_config(&Config::get());
+        let throttle = Arc::new(Mutex::new(ErrorThrottle::new()));
+        // Held by the callback, so probing stops once the watcher is dropped
+        let alive = Arc::new(());
+        watcher.set_callback({
+            let config = config.clone();
+            let executor = executor.clone();
+            let progress = progress.clone();
+            let notifier = notifier.clone();
+            let throttle = throttle.clone();
+            let renames = renames.clone();
+            let alive = alive.clone();
+            move |_| {
+                let _alive = &alive;
+                Self::sync_watched(&config, executor.as_ref(), progress.as_ref(), notifier.as_ref(), &throttle, &renames)
+            }
+        });
+        if config.destinations.iter().any(|destination| destination.circuit_breaker.failure_threshold > 0) {
+            let config = config.clone();
+            let executor = executor.clone();
+            let progress = progress.clone();
+            let notifier = notifier.clone();
+            let throttle = throttle.clone();
+            let renames = renames.clone();
+            let alive = Arc::downgrade(&alive);
+            thread::spawn(move || {
+                Self::probe_circuits(&config, executor.as_ref(), progress.as_ref(), notifier.as_ref(), &throttle, &renames, &alive)
+            });
+        }
+        drop(alive);
+        watcher.resume().map_err(|e| match ErrorHint::classify_message(&e) {
+            Some(hint) => anyhow!("{} (hint: {})", e, hint),
+            None => anyhow!(e),
+        })?;
+
+        info_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Watching library '{}' at {}", self.config.name, self.config.source)
+        );
+
+        if self.config.listing_cache {
+            std::thread::spawn(move || match reconcile_listing(&config) {
+                Ok(Some(diff)) if !diff.is_empty() => {
+                    Self::sync_watched(&config, executor.as_ref(), progress.as_ref(), notifier.as_ref(), &throttle, &renames);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!("Failed to reconcile library '{}': {:#}", config.name, e)
+                    );
+                }
+            });
+        }
+
+        Ok(watcher)
+    }
+
+    /// Re-syncs a watched library whenever a destination it was owed a
+    /// sync by finishes its cool-down, until the watcher is dropped.
+    ///
+    /// The re-sync probes the destination and brings it up to date with the
+    /// changes skipped while its circuit was open, without waiting for
+    /// another change to arrive.
+    fn probe_circuits(
+        config: &LibraryConfig,
+        executor: &dyn SyncExecutor,
+        progress: Option<&ProgressSender>,
+        notifier: Option<&Notifier>,
+        throttle: &Mutex<ErrorThrottle>,
+        renames: &RenameTracker,
+        alive: &Weak<()>,
+    ) {
+        loop {
+            thread::sleep(CIRCUIT_PROBE_CHECK_INTERVAL);
+            if alive.strong_count() == 0 {
+                return;
+            }
+            let now = Instant::now();
+            let due: Vec<&str> = config
+                .destinations
+                .iter()
+                .filter(|destination| {
+                    CircuitBreaker::with_destination(&destination.path, &destination.circuit_breaker, |breaker| {
+                        breaker.take_resync(&config.name, now)
+                    })
+                })
+                .map(|destination| destination.path.as_str())
+                .collect();
+            if due.is_empty() {
+                continue;
+            }
+            info_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Cool-down of {} ended, re-syncing library '{}'", due.join(", "), config.name)
+            );
+            Self::sync_watched(config, executor, progress, notifier, throttle, renames);
+        }
+    }
+
+    /// Makes a watcher scan the library's source for changes, instead of
+    /// or alongside its filesystem notifications.
+    fn with_snapshot_poller(
+        watcher: FileWatcher,
+        config: &LibraryConfig,
+        snapshot: &SnapshotWatchConfig,
+    ) -> FileWatcher {
+        info_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Scanning {} for changes every {}s", config.source, snapshot.interval_secs)
+        );
+        let poller = SnapshotPoller::new(PathHelper::expand_tilde(&config.source))
+            .with_state_path(snapshot_path(config));
+        watcher
+            .with_poller(poller, snapshot.interval())
+            .with_poll_alongside(snapshot.alongside_notify)
+    }
+
+    /// Makes a watcher poll the library's remote server instead of
+    /// watching the local source.
+    fn with_remote_poller(watcher: FileWatcher, remote: &RemoteWatchConfig) -> FileWatcher {
+        info_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Polling {} {} every {}s", remote.kind, remote.url, remote.interval_secs)
+        );
+        match remote.kind {
+            RemoteWatchKind::Alist => {
+                let client = AlistClient::builder(remote.to_alist_endpoint())
+                    .with_refresh(remote.refresh)
+                    .build();
+                watcher.with_poller(AlistPoller::new(client, &remote.path), remote.interval())
+            }
+            RemoteWatchKind::Webdav => {
+                let client = WebDavClient::builder(remote.to_webdav_endpoint()).build();
+                watcher.with_poller(WebDavPoller::new(client, &remote.path), remote.interval())
+            }
+        }
+    }
+
+    /// Synchronizes a watched library and reports the outcome, unless it is
+    /// paused.
+    ///
+    /// Failures of the same class as the previous error are collapsed by `throttle`
+    /// into occasional reminders with an occurrence count. Moves recorded
+    /// by `renames` are replayed at the destinations first.
+    fn sync_watched(
+        config: &LibraryConfig,
+        executor: &dyn SyncExecutor,
+        progress: Option<&ProgressSender>,
+        notifier: Option<&Notifier>,
+        throttle: &Mutex<ErrorThrottle>,
+        renames: &RenameTracker,
+    ) {
+        if PauseState::current().is_paused(&config.name) {
+            info_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Library '{}' is paused, skipping sync", config.name)
+            );
+            return;
+        }
+        let run_id = RunId::new();
+        let _run = Self::run_span(config, &run_id).entered();
+        let started = Instant::now();
+        // Taken before the sync, so a scan made while it runs waits for the next one
+        let pending = config
+            .snapshot_watch
+            .as_ref()
+            .and_then(|_| SnapshotPoller::take_pending(&snapshot_path(config)));
+        Self::replay_moves(config, renames);
+        let result = Self::sync_library(config, executor, &Self::reject_deletions, progress);
+        if let Some(pending) = pending.filter(|_| result.is_ok() && !config.dry_run) {
+            if let Err(e) = pending.commit() {
+                warn_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!("Failed to save the scan of library '{}': {}", config.name, e)
+                );
+            }
+        }
+        let mut vars = vec![
+            ("duration", format_duration(started.elapsed())),
+            ("run_id", run_id.to_string()),
+        ];
+        let mut throttle = throttle.lock().unwrap_or_else(|e| e.into_inner());
+        if result.is_ok() && throttle.clear(&config.name) {
+            info_log!(LIBRARY_LOGGER_DOMAIN, format!("Library '{}' recovered", config.name));
+        }
+        let kind = match result {
+            Ok(report) if report.changed_paths.is_empty() => return,
+            Ok(report) => {
+                let media = MediaInfo::from_paths(&report.changed_paths);
+                vars.push(("count", report.changed_paths.len().to_string()));
+                vars.push(("transferred", report.files_transferred.to_string()));
+                vars.push(("deleted", report.deleted.to_string()));
+                vars.push(("skipped", report.skipped.to_string()));
+                vars.push(("size", ByteSize(report.bytes).to_string()));
+                vars.push(("title", media.title.unwrap_or_else(|| config.name.clone())));
+                vars.push(("season", media.season.map(|season| season.to_string()).unwrap_or_default()));
+                if config.dry_run {
+                    NotificationKind::SyncObserved
+                } else {
+                    NotificationKind::SyncCompleted
+                }
+            }
+            Err(e) => {
+                error_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!("Library '{}' sync failed: {:#}", config.name, e)
+                );
+                let error = format!("{:#}", e);
+                let decision = throttle.record(&config.name, &error, Instant::now());
+                vars.push(("error", error));
+                match decision {
+                    ThrottleDecision::Notify => NotificationKind::SyncFailed,
+                    ThrottleDecision::NotifyRepeated { occurrences, window } => {
+                        vars.push(("occurrences", occurrences.to_string()));
+                        vars.push(("window", format_duration(window)));
+                        NotificationKind::SyncStillFailing
+                    }
+                    ThrottleDecision::Suppress => {
+                        debug_log!(
+                            LIBRARY_LOGGER_DOMAIN,
+                            format!("Library '{}' failed again, notification suppressed", config.name)
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+        drop(throttle);
+        if let Some(notifier) = notifier {
+            let mut vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (*k, v.as_str())).collect();
+            vars.push(("library", &config.name));
+            let sent = info_span!("notify", kind = %kind).in_scope(|| notifier.notify(kind, &vars));
+            if let Err(e) = sent {
+                warn_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!("Failed to send {} notification: {:#}", kind, e)
+                );
+            }
+        }
+    }
+
+    /// Replays the moves of a watched library's source in its `.strm`
+    /// mirror tree and at its local and SSH destinations, so the sync
+    /// that follows moves files instead of transferring them again.
+    ///
+    /// Removals and creations are paired by the size and modification
+    /// time the removed file had in the mirror's index or at a local
+    /// destination. Destinations
+    /// of a library with a mirror tree hold the mirror, whose moves are
+    /// transferred by the sync. Failures are logged, the sync transfers
+    /// whatever wasn't moved.
+    pub fn replay_moves(config: &LibraryConfig, renames: &RenameTracker) {
+        let Ok(destinations) = config.destination_strategies() else {
+            return;
+        };
+        let locations: Vec<_> = destinations
+            .iter()
+            .filter(|_| config.strm.is_none())
+            .filter(|(_, strategy)| {
+                matches!(strategy, SyncStrategy::Rsync | SyncStrategy::Native | SyncStrategy::Robocopy)
+            })
+            .filter_map(|(destination, _)| {
+                let location = config.to_dir_sync_config(destination).ok()?.get_destination();
+                Some((destination.path.as_str(), location))
+            })
+            .collect();
+        let generator = config.to_strm_generator().map(|generator| generator.with_index_file(strm_index_path(config)));
+        // Destinations are frozen in maintenance mode, the deferred sync transfers the moves instead
+        let locations = if locations.is_empty() || MaintenanceState::current().is_enabled() {
+            Vec::new()
+        } else {
+            locations
+        };
+        let moves = renames.take_renames(|relative| {
+            generator
+                .as_ref()
+                .and_then(|generator| generator.indexed_stamp(relative))
+                .or_else(|| locations.iter().find_map(|(_, location)| MoveReplay::file_stamp(location, relative)))
+        });
+        if moves.is_empty() {
+            return;
+        }
+        if config.dry_run {
+            info_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Dry run: not replaying {} moves of library '{}'", moves.len(), config.name)
+            );
+            return;
+        }
+
+        if let Some(generator) = &generator {
+            match generator.apply_renames(&moves).and_then(|moved| generator.save_checksums().map(|()| moved)) {
+                Ok(moved) if !moved.is_empty() => {
+                    debug_log!(LIBRARY_LOGGER_DOMAIN, format!("Moved {} paths in the strm mirror", moved.len()));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn_log!(LIBRARY_LOGGER_DOMAIN, format!("Failed to move strm files: {:#}", e));
+                }
+            }
+        }
+        for (path, location) in &locations {
+            let lock = Self::destination_lock(path);
+            let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+            match MoveReplay::apply(location, &moves) {
+                Ok(applied) if !applied.is_empty() => {
+                    info_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!(
+                            "Library '{}' moved {} paths at {} instead of transferring them",
+                            config.name,
+                            applied.len(),
+                            path
+                        )
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn_log!(LIBRARY_LOGGER_DOMAIN, format!("{}: failed to replay moves: {:#}", path, e));
+                }
+            }
+        }
+    }
+
+    /// Synchronizes a library to each destination, using the strategy
+    /// selected for the destination.
+    ///
+    /// The library's `.strm` mirror tree, if any, is generated first and
+    /// synced to the destinations instead of the source; a generate-only
+    /// library does nothing else.
+    ///
+    /// While maintenance mode is enabled, the mirror is still generated but
+    /// the library is queued instead of being synced to its destinations.
+    ///
+    /// Destinations are synced in batches of the library's transfer
+    /// concurrency, which is sequential unless configured or auto-tuned.
+    ///
+    /// The outcome is appended to the sync history, and a successful sync
+    /// refreshes the library's listing cache.
+    ///
+    /// # Returns
+    /// The statistics of the transfers added up, with the paths changed
+    /// in the mirror tree or any destination, each listed once.
+    fn sync_library(
+        config: &LibraryConfig,
+        executor: &dyn SyncExecutor,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        TuningState::sample(config);
+        if let Some(usage) = FdUsage::current().filter(FdUsage::is_near_exhaustion) {
+            warn_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Running low on file descriptors, {}", usage)
+            );
+        }
+        let concurrency = Concurrency::current(config);
+        let destinations = config.destination_strategies()?;
+
+        let mut failures = Vec::new();
+        let mut changed = BTreeSet::new();
+        let mut total = SyncReport::default();
+        let mut runs = Vec::new();
+        if let Some((strm, generator)) = config.strm.as_ref().zip(config.to_strm_generator()) {
+            let (result, elapsed) = Self::timed(|| Self::generate_strm(config, generator));
+            runs.push(DestinationRun {
+                destination: strm.target.clone(),
+                changed: result.as_ref().map_or(0, Vec::len),
+                duration_ms: elapsed.as_millis() as u64,
+                failed: result.is_err(),
+                resources: None,
+                report: None,
+            });
+            match result {
+                Ok(written) => changed.extend(written),
+                Err(e) => failures.push(format!("{}: {}", strm.target, ErrorHint::describe(&e))),
+            }
+        }
+        // Destinations are frozen in maintenance mode, while the mirror above stays up to date
+        let deferred = !destinations.is_empty() && !config.dry_run && MaintenanceState::current().is_enabled();
+        if deferred {
+            match Self::defer_library(&config.name) {
+                Ok(()) => {
+                    info_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!("Maintenance mode enabled, deferred sync of library '{}' to its destinations", config.name)
+                    );
+                }
+                Err(e) => failures.push(format!("maintenance mode: {:#}", e)),
+            }
+        }
+        let destinations = if deferred { &[][..] } else { destinations.as_slice() };
+        let span = Span::current();
+        for batch in destinations.chunks(concurrency.transfer_concurrency) {
+            let results: Vec<_> = if batch.len() == 1 {
+                batch
+                    .iter()
+                    .map(|(destination, strategy)| {
+                        Self::timed(|| {
+                            ResourceUsage::measure(|| Self::sync_destination(config, executor, destination, *strategy, confirm, progress))
+                        })
+                    })
+                    .collect()
+            } else {
+                std::thread::scope(|scope| {
+                    let handles: Vec<_> = batch
+                        .iter()
+                        .map(|(destination, strategy)| {
+                            let span = &span;
+                            scope.spawn(move || {
+                                let _library = span.enter();
+                                Self::timed(|| {
+                                    ResourceUsage::measure(|| {
+                                        Self::sync_destination(config, executor, destination, *strategy, confirm, progress)
+                                    })
+                                })
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| {
+                            handle
+                                .join()
+                                .unwrap_or_else(|_| ((Err(anyhow!("Sync thread panicked")), None), Default::default()))
+                        })
+                        .collect()
+                })
+            };
+
+            for ((destination, strategy), ((result, resources), elapsed)) in batch.iter().zip(results) {
+                if let Some(resources) = resources {
+                    debug_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!("Library '{}' -> {} used {}", config.name, destination.path, resources)
+                    );
+                    Self::record_resource_metrics(config, *strategy, &resources);
+                }
+                runs.push(DestinationRun {
+                    destination: destination.path.clone(),
+                    changed: result.as_ref().map_or(0, |report| report.changed_paths.len()),
+                    duration_ms: elapsed.as_millis() as u64,
+                    failed: result.is_err(),
+                    resources,
+                    report: result.as_ref().ok().map(|report| report.clone().with_changed_paths(Vec::new())),
+                });
+                match result {
+                    Ok(report) => {
+                        changed.extend(report.changed_paths.iter().cloned());
+                        total.merge(report);
+                    }
+                    Err(e) => failures.push(format!("{}: {}", destination.path, ErrorHint::describe(&e))),
+                }
+            }
+        }
+
+        let changed_count = changed.len();
+        let result = if failures.is_empty() {
+            // Dry runs and deferred syncs leave the destinations behind, so the
+            // changes must still show up after a restart
+            if !config.dry_run && !deferred {
+                update_listing(config);
+            }
+            let mut changed: Vec<String> = changed.into_iter().collect();
+            NaturalOrder::sort_by_key(&mut changed, String::as_str);
+            Ok(total.with_changed_paths(changed))
+        } else {
+            Err(anyhow!(
+                "Library '{}' failed to sync to {}",
+                config.name,
+                failures.join("; ")
+            ))
+        };
+        let error = result.as_ref().err().map(|e| e.to_string());
+        // Dry runs don't transfer anything, so their durations would skew estimates
+        let destinations = if config.dry_run { Vec::new() } else { runs };
+        SyncHistory::record(&SyncRecord { destinations, ..SyncRecord::new(&config.name, changed_count, error) });
+        result
+    }
+
+    /// Refreshes a library's `.strm` mirror tree from the source files
+    /// that changed since the last generation, or regenerates it aside
+    /// and swaps it into place if the library stages generations. The
+    /// staging directory must then have the library's minimum free space.
+    ///
+    /// # Returns
+    /// The files written, relative to the mirror.
+    fn generate_strm(config: &LibraryConfig, generator: StrmGenerator) -> Result<Vec<String>, Error> {
+        let written = match config.strm.as_ref().and_then(|strm| strm.swap.map(|mode| (strm, mode))) {
+            Some((strm, mode)) => {
+                if let Some(required) = config.min_free_space.filter(|_| !config.dry_run) {
+                    let target = PathHelper::expand_tilde(&strm.target);
+                    let staging = match &strm.staging_dir {
+                        Some(dir) => PathHelper::expand_tilde(dir),
+                        None => target.parent().map(|parent| parent.to_path_buf()).unwrap_or(target),
+                    };
+                    DiskSpace::check(&DirLocation::new(&staging.to_string_lossy(), true, None), required)?;
+                }
+                generator.generate_staged(mode)?
+            }
+            None => generator.with_index_file(strm_index_path(config)).generate_incremental()?,
+        };
+        Ok(written.iter().map(|path| path.to_string_lossy().into_owned()).collect())
+    }
+
+    /// Adds the resources a transfer used to the exported metrics, labeled
+    /// with the library and strategy.
+    #[cfg(feature = "otlp")]
+    fn record_resource_metrics(config: &LibraryConfig, strategy: SyncStrategy, resources: &ResourceUsage) {
+        use crate::infrastructure::logger::OtlpExporter;
+        use super::sync_history::{SYNC_CPU_TIME_METRIC, SYNC_READ_BYTES_METRIC, SYNC_WRITTEN_BYTES_METRIC};
+
+        let attributes = [("library", config.name.clone()), ("strategy", strategy.to_string())];
+        for (name, description, value) in [
+            (SYNC_CPU_TIME_METRIC, "CPU time of transfer processes in milliseconds", resources.cpu_time_ms),
+            (SYNC_READ_BYTES_METRIC, "Bytes read from storage by transfer processes", resources.read_bytes),
+            (SYNC_WRITTEN_BYTES_METRIC, "Bytes written to storage by transfer processes", resources.written_bytes),
+        ] {
+            OtlpExporter::add_to_counter(name, description, value, &attributes);
+        }
+    }
+
+    /// Metrics are only exported with the `otlp` feature.
+    #[cfg(not(feature = "otlp"))]
+    fn record_resource_metrics(_config: &LibraryConfig, _strategy: SyncStrategy, _resources: &ResourceUsage) {}
+
+    /// Runs `f`, returning its result and how long it took.
+    fn timed<T>(f: impl FnOnce() -> T) -> (T, std::time::Duration) {
+        let started = Instant::now();
+        let result = f();
+        (result, started.elapsed())
+    }
+
+    /// Warns if a sync to a destination runs much longer than its past
+    /// runs suggest, which often means a stuck transfer.
+    ///
+    /// The size of the run isn't known in advance, so it's compared with
+    /// the estimate of the largest past run. Nothing is watched without
+    /// history or with an overrun factor of 0.
+    ///
+    /// # Returns
+    /// A guard stopping the watch when dropped.
+    fn watch_overrun(config: &LibraryConfig, destination: &DestinationConfig) -> Option<mpsc::Sender<()>> {
+        if config.overrun_factor <= 0.0 {
+            return None;
+        }
+        let estimate = SyncEstimate::load(&config.name, &destination.path).ok().flatten()?;
+        let limit = estimate.longest().mul_f64(config.overrun_factor);
+        let (guard, finished) = mpsc::channel::<()>();
+        let (library, path, factor) = (config.name.clone(), destination.path.clone(), config.overrun_factor);
+        thread::spawn(move || {
+            if finished.recv_timeout(limit) == Err(mpsc::RecvTimeoutError::Timeout) {
+                warn_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!(
+                        "Sync of library '{}' to {} is still running after {}, {} times its estimate ({}), it may be stuck",
+                        library,
+                        path,
+                        format_duration(limit),
+                        factor,
+                        estimate
+                    )
+                );
+            }
+        });
+        Some(guard)
+    }
+
+    /// Runs a sync, retrying it up to `hang_retries` times while it gets
+    /// killed as stuck, and as the destination's retry policy says while
+    /// it fails transiently; other errors are returned right away.
+    fn retry_sync<T>(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        mut sync: impl FnMut() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let policy = &destination.retry;
+        let (mut hangs, mut retries) = (0, 0);
+        loop {
+            match sync() {
+                Err(e) if SyncHang::is_hang(&e) => {
+                    if hangs >= config.hang_retries {
+                        return Err(e);
+                    }
+                    hangs += 1;
+                    warn_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!("{}: {}, retrying ({}/{})", destination.path, e, hangs, config.hang_retries)
+                    );
+                }
+                Err(e) if retries < policy.max_retries && policy.is_retryable(&e) => {
+                    retries += 1;
+                    let delay = policy.delay(retries);
+                    warn_log!(
+                        LIBRARY_LOGGER_DOMAIN,
+                        format!(
+                            "{}: {:#}, retrying in {} ({}/{})",
+                            destination.path,
+                            e,
+                            format_duration(delay),
+                            retries,
+                            policy.max_retries
+                        )
+                    );
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Synchronizes the library to one destination and runs its hooks.
+    ///
+    /// # Returns
+    /// The statistics the executor reported, with the paths it changed.
+    fn sync_destination(
+        config: &LibraryConfig,
+        executor: &dyn SyncExecutor,
+        destination: &DestinationConfig,
+        strategy: SyncStrategy,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        let _sync = info_span!("sync", destination = %destination.path, strategy = %strategy).entered();
+        let lock = Self::destination_lock(&destination.path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if config.strict_mode && !strategy.capabilities().supports_delete {
+            warn_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!(
+                    "Strategy {} can't delete, strict mode has no effect on {}",
+                    strategy,
+                    destination.path
+                )
+            );
+        }
+
+        if config.verify_transfers && !strategy.capabilities().supports_verify {
+            warn_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Strategy {} can't verify transfers, {} is synced unverified", strategy, destination.path)
+            );
+        }
+
+        if config.dry_run && !strategy.capabilities().supports_plan {
+            warn_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Strategy {} can't preview changes, skipping {} in dry run", strategy, destination.path)
+            );
+            return Ok(SyncReport::default());
+        }
+
+        let breaker = &destination.circuit_breaker;
+        let state = CircuitBreaker::with_destination(&destination.path, breaker, |breaker| breaker.state(Instant::now()));
+        if let CircuitState::Open { remaining } = state {
+            info_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Skipping {}, probing again in {}", destination.path, format_duration(remaining))
+            );
+            CircuitBreaker::with_destination(&destination.path, breaker, |breaker| breaker.defer(&config.name));
+            return Err(anyhow!("Destination is unavailable, syncs are paused after repeated failures"));
+        }
+
+        #[cfg(feature = "chaos")]
+        let injected = crate::infrastructure::chaos::FaultInjector::before_sync(&destination.path);
+        #[cfg(not(feature = "chaos"))]
+        let injected: Result<(), Error> = Ok(());
+        let overrun_guard = Self::watch_overrun(config, destination);
+        let result = injected.and_then(|()| {
+            Self::retry_sync(config, destination, || executor.sync_destination(config, destination, strategy, confirm, progress))
+        });
+        drop(overrun_guard);
+        let transition = CircuitBreaker::with_destination(&destination.path, breaker, |breaker| match &result {
+            Ok(_) => breaker.record_success(),
+            // Writes to a read-only destination can't succeed until someone intervenes
+            Err(e) if ReadOnlyDestination::is_read_only(e) => {
+                let transition = breaker.trip(Instant::now());
+                breaker.defer(&config.name);
+                transition
+            }
+            Err(_) => {
+                let transition = breaker.record_failure(Instant::now());
+                breaker.defer(&config.name);
+                transition
+            }
+        });
+        Self::report_circuit(config, destination, transition, &result);
+
+        let mut context = HookContext {
+            library: config.name.clone(),
+            destination: destination.path.clone(),
+            ..HookContext::default()
+        };
+        match &result {
+            Ok(report) => {
+                info_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!(
+                        "Library '{}' synced {} paths to {} with {}",
+                        config.name,
+                        report.changed_paths.len(),
+                        destination.path,
+                        strategy
+                    )
+                );
+                context.changed_paths = report.changed_paths.clone();
+            }
+            Err(e) => context.error = Some(format!("{:#}", e)),
+        }
+
+        // Hooks may refresh media servers, which a dry run must not trigger
+        if !destination.hooks.is_empty() && !config.dry_run {
+            if let Err(e) = info_span!("hooks").in_scope(|| run_sync_hooks(destination, &context)) {
+                error_log!(LIBRARY_LOGGER_DOMAIN, format!("{}: {}", destination.path, e));
+            }
+        }
+        if let Some(emby_path) = destination.emby_path.as_deref().filter(|_| !config.dry_run) {
+            Self::refresh_emby(destination, emby_path, &context.changed_paths);
+        }
+        result
+    }
+
+    /// Reports the show and season folders a sync changed to Emby, logging
+    /// failures since the files are synced either way.
+    ///
+    /// With a settle window, the folders are queued instead and reported
+    /// once the destination went that long without writes, merged with
+    /// those of the syncs in between.
+    fn refresh_emby(destination: &DestinationConfig, emby_path: &str, changed_paths: &[String]) {
+        let folders = AffectedFolders::from_paths(changed_paths);
+        if folders.is_empty() {
+            return;
+        }
+        let settle = Config::get().emby.refresh_settle();
+        if !settle.is_zero() {
+            debug_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Emby refresh of {} deferred until it settles for {}s: {}", destination.path, settle.as_secs(), folders)
+            );
+            EmbyRefreshQueue::schedule(emby_path, &folders, settle);
+            return;
+        }
+        match info_span!("emby_refresh").in_scope(|| folders.refresh_emby(emby_path)) {
+            Ok(()) => {
+                info_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!("Asked Emby to rescan {} folders of {}: {}", folders.folders().len(), destination.path, folders)
+                );
+            }
+            Err(e) => {
+                warn_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!("Failed to refresh Emby for {}: {:#}", destination.path, e)
+                );
+            }
+        }
+    }
+
+    /// Logs and notifies when a destination's circuit opens or recovers.
+    fn report_circuit(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        transition: CircuitTransition,
+        result: &Result<SyncReport, Error>,
+    ) {
+        let (kind, mut vars) = match transition {
+            CircuitTransition::None => return,
+            CircuitTransition::Opened { failures } => {
+                let cooldown = format_duration(destination.circuit_breaker.cooldown());
+                let error = result.as_ref().err().map(|e| format!("{:#}", e)).unwrap_or_default();
+                error_log!(
+                    LIBRARY_LOGGER_DOMAIN,
+                    format!(
+                        "{} failed {} times in a row, pausing syncs to it for {}",
+                        destination.path, failures, cooldown
+                    )
+                );
+                let vars = vec![("failures", failures.to_string()), ("cooldown", cooldown), ("error", error)];
+                (NotificationKind::DestinationUnavailable, vars)
+            }
+            CircuitTransition::Recovered => {
+                info_log!(LIBRARY_LOGGER_DOMAIN, format!("{} recovered", destination.path));
+                (NotificationKind::DestinationRecovered, Vec::new())
+            }
+        };
+
+        let Some(notifier) = Notifier::from_config(&Config::get()) else {
+            return;
+        };
+        vars.push(("library", config.name.clone()));
+        vars.push(("destination", destination.path.clone()));
+        let vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        if let Err(e) = notifier.notify(kind, &vars) {
+            warn_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Failed to send {} notification: {:#}", kind, e)
+            );
+        }
+    }
+
+    /// Synchronizes the library source to a destination with rsync.
+    ///
+    /// # Returns
+    /// The statistics rsync printed, with the paths it reported as transferred.
+    pub(super) fn rsync_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        let mut helper = DirSyncHelper::new(config.to_dir_sync_config(destination)?);
+        if let Some(sender) = progress {
+            helper.set_progress_sender(sender.clone());
+        }
+        Self::confirm_plan(config, || helper.plan(), &destination.path, confirm)?;
+
+        let changed_paths = Arc::new(Mutex::new(Vec::new()));
+        let collector = changed_paths.clone();
+        helper.set_file_sync_callback(Box::new(move |path| {
+            collector.lock().unwrap_or_else(|e| e.into_inner()).push(path.to_string());
+        }));
+        let library = config.name.clone();
+        let target = destination.path.clone();
+        helper.set_progress_callback(ProgressReporter::new().into_callback(move |progress| {
+            debug_log!(
+                LIBRARY_LOGGER_DOMAIN,
+                format!("Library '{}' -> {}: {}", library, target, progress)
+            );
+        }));
+        let report = helper.sync_with_report()?;
+        info_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Library '{}' -> {}: {}", config.name, destination.path, report)
+        );
+        for error in &report.errors {
+            warn_log!(LIBRARY_LOGGER_DOMAIN, format!("{}: {}", destination.path, error));
+        }
+
+        let changed_paths = changed_paths.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        Ok(report.with_changed_paths(changed_paths))
+    }
+
+    /// Copies the library source to a local destination without rsync.
+    ///
+    /// Runs on a dedicated thread with its own runtime, like uploads.
+    ///
+    /// # Returns
+    /// The report of the paths created, updated or deleted.
+    pub(super) fn native_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        let mut native = NativeSync::new(config.to_dir_sync_config(destination)?);
+        if let Some(sender) = progress {
+            native = native.with_progress_sender(sender.clone());
+        }
+        Self::confirm_plan(config, || Self::block_on_thread(config, native.plan()), &destination.path, confirm)?;
+        let changed = Self::block_on_thread(config, native.sync())?;
+        debug_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Copied {} paths to {}", changed.len(), destination.path)
+        );
+        Ok(SyncReport::from_changed_paths(changed))
+    }
+
+    /// Copies the library source to a local or SMB destination with robocopy.
+    ///
+    /// # Returns
+    /// The report of the paths copied or deleted.
+    pub(super) fn robocopy_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        confirm: &ConfirmCallback,
+    ) -> Result<SyncReport, Error> {
+        let robocopy = RobocopySync::new(config.to_dir_sync_config(destination)?);
+        Self::confirm_plan(config, || robocopy.plan(), &destination.path, confirm)?;
+        let changed = robocopy.sync()?;
+        debug_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Copied {} paths to {}", changed.len(), destination.path)
+        );
+        Ok(SyncReport::from_changed_paths(changed))
+    }
+
+    /// Copies the library source to an rclone remote or WebDAV server with rclone.
+    ///
+    /// # Returns
+    /// The report of the paths copied or deleted.
+    pub(super) fn rclone_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        confirm: &ConfirmCallback,
+    ) -> Result<SyncReport, Error> {
+        let rclone = RcloneSync::new(config.to_dir_sync_config(destination)?);
+        Self::confirm_plan(config, || rclone.plan(), &destination.path, confirm)?;
+        let changed = rclone.sync()?;
+        debug_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Copied or deleted {} paths in {}", changed.len(), destination.path)
+        );
+        Ok(SyncReport::from_changed_paths(changed))
+    }
+
+    /// Runs a future to completion on a dedicated thread with its own
+    /// runtime, so it can be called both from watcher threads and from
+    /// within an async context. The library's I/O priority applies to the
+    /// thread and to the runtime's blocking pool.
+    fn block_on_thread<T: Send>(
+        config: &LibraryConfig,
+        future: impl Future<Output = Result<T, Error>> + Send,
+    ) -> Result<T, Error> {
+        let span = Span::current();
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _entered = span.enter();
+                    let io_priority = config.io_priority;
+                    Self::apply_io_priority(&io_priority);
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .on_thread_start(move || Self::apply_io_priority(&io_priority))
+                        .build()?
+                        .block_on(future)
+                })
+                .join()
+                .map_err(|_| anyhow!("Sync thread panicked"))?
+        })
+    }
+
+    /// Uploads the library source, or its `.strm` mirror tree, to an HTTP destination.
+    ///
+    /// Runs on a dedicated thread with its own runtime, so it can be called
+    /// both from watcher threads and from within an async context. The
+    /// library's I/O priority applies to that thread and to the runtime's
+    /// blocking pool.
+    ///
+    /// # Returns
+    /// The report of the paths that needed uploading.
+    pub(super) fn upload_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        let mut builder = UploadClient::builder(destination.to_upload_endpoint())
+            .with_scan_parallelism(Concurrency::current(config).scan_parallelism)
+            .with_journal(upload_journal_path(config, destination));
+        if let Some(chunk_size) = destination.chunk_size {
+            builder = builder.with_chunk_size(chunk_size);
+        }
+        if let Some(sender) = progress {
+            builder = builder.with_progress_sender(sender.clone());
+        }
+        let client = builder.build();
+        let uploaded = Self::block_on_thread(
+            config,
+            client.upload_dir(&config.sync_source(), |path| config.matches_sync_filters(path)),
+        )?;
+
+        debug_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Uploaded {} files to {}", uploaded.len(), destination.path)
+        );
+        Ok(SyncReport::from_changed_paths(uploaded))
+    }
+
+    /// Mirrors the library source, or its `.strm` mirror tree, into an S3 bucket prefix.
+    ///
+    /// Deletions are planned in strict mode only and confirmed like those
+    /// of the other strategies; dry runs log the plan without writing.
+    ///
+    /// # Returns
+    /// The report of the plan, with the paths uploaded or deleted relative
+    /// to the prefix.
+    pub(super) fn s3_library(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        let client = Self::s3_client(config, destination, progress)?;
+        let plan = Self::block_on_thread(
+            config,
+            client.plan(&config.sync_source(), |path| config.matches_sync_filters(path), config.strict_mode),
+        )?;
+        Self::confirm_plan(config, || Ok(plan.clone()), &destination.path, confirm)?;
+        if config.dry_run {
+            for action in plan.actions() {
+                info_log!(LIBRARY_LOGGER_DOMAIN, format!("Dry run: {}", action));
+            }
+            let paths = plan.actions().iter().map(|action| action.path().to_string()).collect();
+            return Ok(SyncReport::from_plan(&plan).with_changed_paths(paths));
+        }
+
+        let changed = Self::block_on_thread(config, client.apply(&config.sync_source(), &plan))?;
+        debug_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!("Uploaded or deleted {} objects in {}", changed.len(), destination.path)
+        );
+        Ok(SyncReport::from_plan(&plan).with_changed_paths(changed))
+    }
+
+    /// Computes the changes a sync to an S3 destination would make.
+    async fn s3_plan(config: &LibraryConfig, destination: &DestinationConfig) -> Result<SyncPlan, Error> {
+        Self::s3_client(config, destination, None)?
+            .plan(&config.sync_source(), |path| config.matches_sync_filters(path), config.strict_mode)
+            .await
+    }
+
+    /// Creates the client of an S3 destination, sending progress events to `progress`.
+    fn s3_client(
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        progress: Option<&ProgressSender>,
+    ) -> Result<S3Client, Error> {
+        let mut builder = S3Client::builder(destination.to_s3_bucket()?)
+            .with_prefix(destination.s3_prefix()?)
+            .with_scan_parallelism(Concurrency::current(config).scan_parallelism);
+        if let Some(sender) = progress {
+            builder = builder.with_progress_sender(sender.clone());
+        }
+        Ok(builder.build())
+    }
+
+    /// Lowers the priority of the calling thread, logging when the kernel refuses.
+    fn apply_io_priority(io_priority: &IoPriority) {
+        if io_priority.is_default() {
+            return;
+        }
+        if let Err(e) = io_priority.apply_to_current_thread() {
+            warn_log!(LIBRARY_LOGGER_DOMAIN, format!("{:#}", e));
+        }
+    }
+
+    /// Checks the planned deletions against the library's confirmation threshold.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the plan can't be computed or was rejected.
+    fn confirm_plan(
+        config: &LibraryConfig,
+        plan: impl FnOnce() -> Result<SyncPlan, Error>,
+        destination: &str,
+        confirm: &ConfirmCallback,
+    ) -> Result<(), Error> {
+        // Only strict mode deletes, so other syncs never need a plan, and dry runs delete nothing
+        if !config.strict_mode || config.confirm_deletions_above.is_none() || config.dry_run {
+            return Ok(());
+        }
+
+        let plan = plan()?;
+        let deletions = plan.deletions().len();
+        if !config.requires_confirmation(deletions) {
+            return Ok(());
+        }
+
+        let _prompt = CONFIRM_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if confirm(destination, &plan) {
+            return Ok(());
+        }
+        Err(anyhow!("{} deletions were not confirmed", deletions))
+    }
+
+    /// Confirmation callback for non-interactive syncs, which rejects every destructive plan.
+    fn reject_deletions(destination: &str, plan: &SyncPlan) -> bool {
+        warn_log!(
+            LIBRARY_LOGGER_DOMAIN,
+            format!(
+                "Sync to {} would delete {} files and needs confirmation, run 'sync' interactively",
+                destination,
+                plan.deletions().len()
+            )
+        );
+        false
+    }
+
+    /// Queues a library sync until maintenance mode ends.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the maintenance state can't be updated.
+    fn defer_library(name: &str) -> Result<(), Error> {
+        MaintenanceState::defer_persisted(MaintenanceState::default_path(), name)
+            .with_context(|| format!("Failed to defer sync of library '{}'", name))
+    }
+
+    /// Creates the span of a single library run.
+    ///
+    /// Every log line within the span carries the library name and the
+    /// run ID, so the stages of one run can be told apart from others.
+    fn run_span(config: &LibraryConfig, run_id: &RunId) -> Span {
+        info_span!("library", library = %config.name, run_id = %run_id)
+    }
+
+    /// Returns the lock guarding syncs into the given destination.
+    fn destination_lock(destination: &str) -> Arc<Mutex<()>> {
+        DESTINATION_LOCKS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(destination.to_string())
+            .or_default()
+            .clone()
+    }
+}
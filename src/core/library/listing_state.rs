@@ -0,0 +1,172 @@
+use std::{
+    path::PathBuf,
+    time::Duration
+};
+
+use anyhow::{Error, Result};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    core::config::{Config, DestinationConfig, LibraryConfig},
+    infrastructure::fs::{ListingCache, ListingDiff, PathHelper},
+    info_log,
+    warn_log
+};
+
+/// Domain identifier for listing cache logs
+const LISTING_LOGGER_DOMAIN: &str = "[LISTING]";
+
+/// Directory holding one listing cache per library inside the state directory.
+const LISTING_CACHE_DIR_NAME: &str = "listings";
+
+/// Directory holding the last scan of each library watched by scanning.
+const SNAPSHOT_DIR_NAME: &str = "snapshots";
+
+/// Directory holding the index and checksums of each library's `.strm` mirror tree.
+const STRM_INDEX_DIR_NAME: &str = "strm";
+
+/// Directory holding the upload journal of each HTTP destination.
+const UPLOAD_JOURNAL_DIR_NAME: &str = "uploads";
+
+/// Age after which a listing cache is rebuilt from a full scan.
+pub const LISTING_CACHE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Returns the location of a library's listing cache.
+pub fn listing_cache_path(config: &LibraryConfig) -> PathBuf {
+    Config::get()
+        .state_dir()
+        .join(LISTING_CACHE_DIR_NAME)
+        .join(format!("{}.cache", config.name))
+}
+
+/// Returns the location of the last scan of a library watched by scanning.
+///
+/// Kept apart from the listing cache, which records the source as of the
+/// last successful sync rather than the last scan.
+pub fn snapshot_path(config: &LibraryConfig) -> PathBuf {
+    Config::get()
+        .state_dir()
+        .join(SNAPSHOT_DIR_NAME)
+        .join(format!("{}.cache", config.name))
+}
+
+/// Returns the location of the index of the source files a library's
+/// `.strm` mirror tree was last generated from.
+pub fn strm_index_path(config: &LibraryConfig) -> PathBuf {
+    Config::get()
+        .state_dir()
+        .join(STRM_INDEX_DIR_NAME)
+        .join(format!("{}.index", config.name))
+}
+
+/// Returns the location of the hashes of the contents written into a
+/// library's `.strm` mirror tree.
+pub fn strm_checksums_path(config: &LibraryConfig) -> PathBuf {
+    Config::get()
+        .state_dir()
+        .join(STRM_INDEX_DIR_NAME)
+        .join(format!("{}.checksums", config.name))
+}
+
+/// Returns the location of the journal of uploads a library started to
+/// an HTTP destination.
+///
+/// Destination addresses aren't valid file names, so the journal is named
+/// after a hash of the address.
+pub fn upload_journal_path(config: &LibraryConfig, destination: &DestinationConfig) -> PathBuf {
+    let hash = format!("{:x}", Sha256::digest(destination.path.as_bytes()));
+    Config::get()
+        .state_dir()
+        .join(UPLOAD_JOURNAL_DIR_NAME)
+        .join(format!("{}-{}.json", config.name, &hash[..16]))
+}
+
+/// Compares a library's source with the listing cached after its last
+/// successful sync.
+///
+/// Only directories that changed are listed again. A cache older than
+/// [`LISTING_CACHE_MAX_AGE`] is compared against a full scan instead, and an
+/// unreadable cache is discarded. Without a usable cache the listing is
+/// built for next time and nothing is reported.
+///
+/// # Returns
+/// The changes that pass the library's filters, or `None` if there is no
+/// cache to compare with.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the source can't be listed.
+pub fn reconcile_listing(config: &LibraryConfig) -> Result<Option<ListingDiff>, Error> {
+    let path = listing_cache_path(config);
+    let source = PathHelper::expand_tilde(&config.source);
+
+    let cached = if path.exists() {
+        ListingCache::load(&path)
+            .inspect_err(|e| {
+                warn_log!(LISTING_LOGGER_DOMAIN, format!("{:#}, rebuilding it", e));
+            })
+            .ok()
+    } else {
+        None
+    };
+    let Some(cached) = cached else {
+        save_listing(config, &ListingCache::scan(&source)?);
+        return Ok(None);
+    };
+
+    let (refreshed, diff) = if cached.age() > LISTING_CACHE_MAX_AGE {
+        let refreshed = ListingCache::scan(&source)?;
+        let diff = cached.diff(&refreshed);
+        (refreshed, diff)
+    } else {
+        cached.refresh(&source)?
+    };
+
+    let diff = ListingDiff {
+        added: diff.added.into_iter().filter(|path| config.matches_filters(path)).collect(),
+        modified: diff.modified.into_iter().filter(|path| config.matches_filters(path)).collect(),
+        removed: diff.removed.into_iter().filter(|path| config.matches_filters(path)).collect(),
+    };
+    if diff.is_empty() {
+        save_listing(config, &refreshed);
+    } else {
+        info_log!(
+            LISTING_LOGGER_DOMAIN,
+            format!("Library '{}' changed since its last sync: {}", config.name, diff)
+        );
+    }
+    Ok(Some(diff))
+}
+
+/// Refreshes a library's listing cache after a successful sync.
+///
+/// Does nothing unless the library enables the listing cache. Failures
+/// are logged, since the cache only speeds up the next startup.
+pub fn update_listing(config: &LibraryConfig) {
+    if !config.listing_cache {
+        return;
+    }
+
+    let path = listing_cache_path(config);
+    let source = PathHelper::expand_tilde(&config.source);
+    let cached = ListingCache::load(&path).unwrap_or_default();
+    match cached.refresh(&source) {
+        Ok((refreshed, _)) => save_listing(config, &refreshed),
+        Err(e) => {
+            warn_log!(
+                LISTING_LOGGER_DOMAIN,
+                format!("Failed to refresh listing of library '{}': {:#}", config.name, e)
+            );
+        }
+    }
+}
+
+/// Writes a library's listing cache, logging failures.
+fn save_listing(config: &LibraryConfig, cache: &ListingCache) {
+    let path = listing_cache_path(config);
+    if let Err(e) = cache.save(&path) {
+        warn_log!(
+            LISTING_LOGGER_DOMAIN,
+            format!("Failed to save listing cache {}: {:#}", path.display(), e)
+        );
+    }
+}
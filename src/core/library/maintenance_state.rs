@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::Config,
+    warn_log
+};
+use super::state_file::{load_state, lock_state, save_state};
+
+/// Domain identifier for maintenance state logs
+const MAINTENANCE_LOGGER_DOMAIN: &str = "[MAINTENANCE]";
+
+/// File name of the persisted maintenance state inside the state directory.
+const MAINTENANCE_STATE_FILE_NAME: &str = "maintenance.json";
+
+/// Persisted maintenance mode with its queue of deferred library syncs.
+///
+/// While maintenance is enabled, destinations are frozen: changes are
+/// still detected locally, but the libraries needing a sync are queued
+/// instead of being written to their destinations. The queue is flushed
+/// when maintenance ends.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceState {
+
+    /// Whether maintenance mode is enabled
+    enabled: bool,
+
+    /// Names of libraries with deferred destination writes
+    pending: BTreeSet<String>,
+}
+
+impl MaintenanceState {
+
+    /// Returns the default location of the maintenance state file.
+    pub fn default_path() -> PathBuf {
+        Config::get().state_dir().join(MAINTENANCE_STATE_FILE_NAME)
+    }
+
+    /// Loads the maintenance state from `path`.
+    ///
+    /// A missing file means maintenance is disabled with nothing pending.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file exists but can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        load_state(path.as_ref())
+    }
+
+    /// Loads the maintenance state from the default location.
+    ///
+    /// Falls back to an enabled state if the file is unreadable, so
+    /// destinations stay frozen rather than being written during
+    /// maintenance.
+    pub fn current() -> Self {
+        let path = Self::default_path();
+        Self::load(&path).unwrap_or_else(|e| {
+            warn_log!(
+                MAINTENANCE_LOGGER_DOMAIN,
+                format!("Failed to load maintenance state {}, assuming maintenance mode: {}", path.display(), e)
+            );
+            Self { enabled: true, ..Self::default() }
+        })
+    }
+
+    /// Writes the maintenance state to `path`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        save_state(self, path.as_ref())
+    }
+
+    /// Enables or disables maintenance mode.
+    ///
+    /// Disabling keeps the pending queue so it can be flushed afterwards.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns `true` if maintenance mode is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Queues a library whose destination writes were deferred.
+    pub fn defer(&mut self, library: &str) {
+        self.pending.insert(library.to_string());
+    }
+
+    /// Removes a library from the queue once its sync succeeded.
+    pub fn complete(&mut self, library: &str) {
+        self.pending.remove(library);
+    }
+
+    /// Returns the names of libraries with deferred destination writes.
+    pub fn pending_libraries(&self) -> Vec<String> {
+        self.pending.iter().cloned().collect()
+    }
+
+    /// Queues a library in the persisted state at `path`.
+    ///
+    /// The file is locked and reloaded first, so concurrent watchers and
+    /// processes don't drop each other's entries.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the state can't be locked, read or written.
+    pub fn defer_persisted(path: impl AsRef<Path>, library: &str) -> Result<(), Error> {
+        let path = path.as_ref();
+        let _lock = lock_state(path)?;
+        let mut state = Self::load(path)?;
+        state.defer(library);
+        state.save(path)
+    }
+
+    /// Enables or disables maintenance mode in the persisted state at `path`,
+    /// locking it like [`defer_persisted`](Self::defer_persisted).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the state can't be locked, read or written.
+    pub fn set_enabled_persisted(path: impl AsRef<Path>, enabled: bool) -> Result<(), Error> {
+        let path = path.as_ref();
+        let _lock = lock_state(path)?;
+        let mut state = Self::load(path)?;
+        state.set_enabled(enabled);
+        state.save(path)
+    }
+
+    /// Removes a library from the queue of the persisted state at `path`,
+    /// locking it like [`defer_persisted`](Self::defer_persisted).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the state can't be locked, read or written.
+    pub fn complete_persisted(path: impl AsRef<Path>, library: &str) -> Result<(), Error> {
+        let path = path.as_ref();
+        let _lock = lock_state(path)?;
+        let mut state = Self::load(path)?;
+        state.complete(library);
+        state.save(path)
+    }
+}
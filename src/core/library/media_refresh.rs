@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::Path,
+    sync::{atomic::{AtomicBool, Ordering}, Mutex},
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, Error, Result};
+use once_cell::sync::Lazy;
+
+use crate::{
+    core::api::EmbyAPI,
+    info_log,
+    infrastructure::network::NetworkProvider,
+    warn_log
+};
+
+const MEDIA_REFRESH_LOGGER_DOMAIN: &str = "[MEDIA-REFRESH]";
+
+/// Longest time the refresh thread sleeps before looking for settled
+/// refreshes again.
+const REFRESH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Emby refreshes waiting for their destination to settle.
+static PENDING_EMBY_REFRESHES: Lazy<Mutex<PendingRefreshes>> = Lazy::new(|| {
+    Mutex::new(PendingRefreshes::default())
+});
+
+/// Whether the thread sending settled Emby refreshes was started.
+static EMBY_REFRESH_THREAD: AtomicBool = AtomicBool::new(false);
+
+/// Held while refreshes taken from the queue are sent, so a flush waits
+/// for those the refresh thread is sending.
+static EMBY_REFRESH_SENDING: Mutex<()> = Mutex::new(());
+
+/// Folders of a destination changed by a sync, one per show or season.
+///
+/// Each changed file contributes the folder holding it, so the episodes
+/// of a season collapse into the season folder. A folder inside another
+/// changed folder is covered by it and left out, so a new show is one
+/// folder however many seasons it brings. Files at the root of the
+/// destination contribute the root itself, as an empty path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AffectedFolders {
+
+    /// Folders relative to the destination, sorted, none inside another
+    folders: Vec<String>,
+}
+
+impl AffectedFolders {
+
+    /// Groups the paths changed by a sync into folders.
+    ///
+    /// # Arguments
+    /// * `changed_paths` - Paths relative to the destination; directories,
+    ///   ending with `/`, are skipped since their files are listed too
+    pub fn from_paths(changed_paths: &[String]) -> Self {
+        let parents: BTreeSet<String> = changed_paths
+            .iter()
+            .filter(|path| !path.is_empty() && !path.ends_with('/'))
+            .map(|path| {
+                Path::new(path)
+                    .parent()
+                    .map(|parent| parent.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default()
+            })
+            .collect();
+        Self::collapse(parents)
+    }
+
+    /// Adds the folders of another change, dropping those now covered.
+    pub fn merge(&mut self, other: &AffectedFolders) {
+        let folders: BTreeSet<String> = self.folders.drain(..).chain(other.folders.iter().cloned()).collect();
+        *self = Self::collapse(folders);
+    }
+
+    /// Returns the folders, relative to the destination.
+    pub fn folders(&self) -> &[String] {
+        &self.folders
+    }
+
+    /// Returns `true` if no folder changed.
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    /// Returns the folders below `root`, e.g. the destination as the
+    /// media server sees it.
+    pub fn resolve(&self, root: &str) -> Vec<String> {
+        let root = root.trim_end_matches('/');
+        self.folders
+            .iter()
+            .map(|folder| if folder.is_empty() { root.to_string() } else { format!("{}/{}", root, folder) })
+            .collect()
+    }
+
+    /// Asks Emby to rescan the folders, blocking until it accepted.
+    ///
+    /// Emby scans the reported folders instead of the whole library, so
+    /// the cost of the refresh follows the size of the change. The
+    /// request is sent from a dedicated thread with its own runtime, so
+    /// it can be called from watcher threads and async contexts alike.
+    ///
+    /// # Arguments
+    /// * `root` - The destination as the Emby server sees it
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the request fails or Emby rejects it.
+    pub fn refresh_emby(&self, root: &str) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let request = EmbyAPI::MediaUpdated { paths: self.resolve(root) };
+        thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    let response = runtime.block_on(NetworkProvider::new(Vec::new()).send_request(&request))?;
+                    if !response.status().is_success() {
+                        return Err(anyhow!("Emby rejected the refresh with status {}", response.status()));
+                    }
+                    Ok(())
+                })
+                .join()
+                .map_err(|_| anyhow!("Emby refresh thread panicked"))?
+        })
+    }
+
+    /// Keeps the folders not inside another one.
+    fn collapse(candidates: BTreeSet<String>) -> Self {
+        // Sorted order puts every folder right after the folders containing it
+        let mut folders: Vec<String> = Vec::new();
+        for candidate in candidates {
+            let covered = folders.iter().any(|folder| {
+                folder.is_empty() || candidate.strip_prefix(folder.as_str()).is_some_and(|rest| rest.starts_with('/'))
+            });
+            if !covered {
+                folders.push(candidate);
+            }
+        }
+        Self { folders }
+    }
+}
+
+impl Display for AffectedFolders {
+
+    /// Formats the folders as a comma separated list, `/` for the root.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let folders: Vec<&str> = self.folders
+            .iter()
+            .map(|folder| if folder.is_empty() { "/" } else { folder.as_str() })
+            .collect();
+        write!(f, "{}", folders.join(", "))
+    }
+}
+
+/// Refreshes waiting for their destination to settle, one per root.
+///
+/// A large batch lands in a destination over several syncs, each of which
+/// would otherwise trigger a scan. Refreshes of the same root are merged
+/// and every new write pushes the refresh back, so the media server scans
+/// once the batch has landed.
+#[derive(Debug, Default)]
+pub struct PendingRefreshes {
+
+    /// Changed folders and refresh deadline, keyed by root
+    pending: BTreeMap<String, (AffectedFolders, Instant)>,
+}
+
+impl PendingRefreshes {
+
+    /// Records folders changed below a root, delaying its refresh until
+    /// `settle` has passed without another change.
+    ///
+    /// # Arguments
+    /// * `root` - The destination as the media server sees it
+    /// * `folders` - Folders changed by the write
+    /// * `settle` - Time to wait for further writes
+    /// * `now` - Time of the write
+    pub fn add(&mut self, root: &str, folders: &AffectedFolders, settle: Duration, now: Instant) {
+        if folders.is_empty() {
+            return;
+        }
+        // `/media` and `/media/` are the same destination
+        let root = match root.trim_end_matches('/') {
+            "" => root,
+            trimmed => trimmed,
+        };
+        let (pending, deadline) = self.pending
+            .entry(root.to_string())
+            .or_insert_with(|| (AffectedFolders::default(), now));
+        pending.merge(folders);
+        *deadline = now + settle;
+    }
+
+    /// Removes and returns the refreshes whose root has settled by `now`.
+    pub fn take_settled(&mut self, now: Instant) -> Vec<(String, AffectedFolders)> {
+        let settled: Vec<String> = self.pending
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(root, _)| root.clone())
+            .collect();
+        settled
+            .into_iter()
+            .filter_map(|root| self.pending.remove(&root).map(|(folders, _)| (root, folders)))
+            .collect()
+    }
+
+    /// Removes and returns every refresh, settled or not.
+    pub fn take_all(&mut self) -> Vec<(String, AffectedFolders)> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|(root, (folders, _))| (root, folders))
+            .collect()
+    }
+
+    /// Returns the earliest refresh deadline.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|(_, deadline)| *deadline).min()
+    }
+
+    /// Returns the number of roots waiting for a refresh.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if no refresh is waiting.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Process-wide queue of Emby refreshes, sent once their destination settles.
+pub struct EmbyRefreshQueue;
+
+impl EmbyRefreshQueue {
+
+    /// Queues folders changed below a root; the refresh is sent by a
+    /// background thread once `settle` passed without further changes.
+    pub fn schedule(root: &str, folders: &AffectedFolders, settle: Duration) {
+        Self::update(|pending| pending.add(root, folders, settle, Instant::now()));
+        if !EMBY_REFRESH_THREAD.swap(true, Ordering::SeqCst) {
+            thread::spawn(Self::run);
+        }
+    }
+
+    /// Sends every queued refresh now, e.g. before the process exits.
+    ///
+    /// Returns once the refreshes the background thread already took from
+    /// the queue are sent too, so none is lost when the process exits.
+    pub fn flush() {
+        let _sending = EMBY_REFRESH_SENDING.lock().unwrap_or_else(|e| e.into_inner());
+        for (root, folders) in Self::update(PendingRefreshes::take_all) {
+            Self::send(&root, &folders);
+        }
+    }
+
+    /// Sends refreshes as their destinations settle.
+    fn run() {
+        loop {
+            {
+                let _sending = EMBY_REFRESH_SENDING.lock().unwrap_or_else(|e| e.into_inner());
+                for (root, folders) in Self::update(|pending| pending.take_settled(Instant::now())) {
+                    Self::send(&root, &folders);
+                }
+            }
+            let wait = Self::update(|pending| pending.next_deadline())
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(REFRESH_POLL_INTERVAL)
+                .min(REFRESH_POLL_INTERVAL);
+            thread::sleep(wait);
+        }
+    }
+
+    /// Asks Emby to rescan the folders, logging the outcome.
+    fn send(root: &str, folders: &AffectedFolders) {
+        match folders.refresh_emby(root) {
+            Ok(()) => {
+                info_log!(
+                    MEDIA_REFRESH_LOGGER_DOMAIN,
+                    format!("Asked Emby to rescan {} folders of {}: {}", folders.folders().len(), root, folders)
+                );
+            }
+            Err(e) => {
+                warn_log!(MEDIA_REFRESH_LOGGER_DOMAIN, format!("Failed to refresh Emby for {}: {:#}", root, e));
+            }
+        }
+    }
+
+    /// Runs `f` on the pending refreshes, tolerating a poisoned lock.
+    fn update<T>(f: impl FnOnce(&mut PendingRefreshes) -> T) -> T {
+        let mut pending = PENDING_EMBY_REFRESHES.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut pending)
+    }
+}
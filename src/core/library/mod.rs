@@ -0,0 +1,56 @@
+//! Per-library synchronization pipelines.
+//!
+//! This module turns library configurations into running pipelines with:
+//! - One-shot synchronization to every destination
+//! - Filesystem watching with debounced synchronization
+//! - Independent operation per named library
+//! - Persisted global and per-library pause switches
+//! - Maintenance mode that defers destination writes
+//! - Sync strategies selected from each destination's address
+//! - Replaceable sync executors for testing the orchestration
+//! - Post-sync hook commands, run locally or on the destination host
+//! - A history of sync outcomes for reports
+//! - Durations of syncs estimated from past runs, with warnings on overruns
+//! - Dry-run replays of scripted filesystem events
+//! - Read-only benchmarks against a library's source
+//! - Worker counts auto-tuned from measured IO latency
+//! - Cached source listings to catch up on changes made while stopped
+//! - Circuit breakers pausing syncs to destinations that keep failing
+//! - Scheduled health checks of Telegram, SSH hosts and destinations
+//! - Integrity checks and repairs of the persisted state files
+//! - Media server refreshes limited to the show and season folders a sync changed,
+//!   deferred until the destination settles
+//! 
+pub mod auto_tune;
+pub mod benchmark;
+pub mod circuit_breaker;
+pub mod health_check;
+pub mod library_sync;
+pub(crate) mod listing_state;
+pub mod maintenance_state;
+pub mod media_refresh;
+pub mod pause_state;
+pub mod simulation;
+pub mod state_doctor;
+pub(crate) mod state_file;
+pub mod sync_estimate;
+pub mod sync_executor;
+pub mod sync_history;
+pub mod sync_hooks;
+pub mod sync_strategy;
+
+pub use auto_tune::*;
+pub use benchmark::*;
+pub use circuit_breaker::*;
+pub use health_check::*;
+pub use library_sync::*;
+pub use maintenance_state::*;
+pub use media_refresh::*;
+pub use pause_state::*;
+pub use simulation::*;
+pub use state_doctor::*;
+pub use sync_estimate::*;
+pub use sync_executor::*;
+pub use sync_history::*;
+pub use sync_hooks::*;
+pub use sync_strategy::*;
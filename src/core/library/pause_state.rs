@@ -0,0 +1,112 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::Config,
+    warn_log
+};
+use super::state_file::{load_state, save_state};
+
+/// Domain identifier for pause state logs
+const PAUSE_LOGGER_DOMAIN: &str = "[PAUSE]";
+
+/// File name of the persisted pause state inside the state directory.
+const PAUSE_STATE_FILE_NAME: &str = "pause.json";
+
+/// Persisted pause switches for all libraries and for individual ones.
+///
+/// The state is stored as JSON in the state directory so it survives
+/// daemon restarts and can be toggled from a separate process.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PauseState {
+
+    /// When true, every library is paused
+    global: bool,
+
+    /// Names of individually paused libraries
+    libraries: BTreeSet<String>,
+}
+
+impl PauseState {
+
+    /// Returns the default location of the pause state file.
+    pub fn default_path() -> PathBuf {
+        Config::get().state_dir().join(PAUSE_STATE_FILE_NAME)
+    }
+
+    /// Loads the pause state from `path`.
+    ///
+    /// A missing file means nothing is paused.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file exists but can't be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        load_state(path.as_ref())
+    }
+
+    /// Loads the pause state from the default location.
+    ///
+    /// Falls back to an unpaused state if the file is unreadable, so a
+    /// corrupted state file never blocks syncing silently.
+    pub fn current() -> Self {
+        let path = Self::default_path();
+        Self::load(&path).unwrap_or_else(|e| {
+            warn_log!(
+                PAUSE_LOGGER_DOMAIN,
+                format!("Failed to load pause state {}: {}", path.display(), e)
+            );
+            Self::default()
+        })
+    }
+
+    /// Writes the pause state to `path`, creating parent directories as needed.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        save_state(self, path.as_ref())
+    }
+
+    /// Pauses a single library, or all libraries when `library` is `None`.
+    pub fn pause(&mut self, library: Option<&str>) {
+        match library {
+            Some(name) => {
+                self.libraries.insert(name.to_string());
+            }
+            None => self.global = true,
+        }
+    }
+
+    /// Resumes a single library, or lifts the global pause when `library` is `None`.
+    ///
+    /// Lifting the global pause keeps individually paused libraries paused.
+    pub fn resume(&mut self, library: Option<&str>) {
+        match library {
+            Some(name) => {
+                self.libraries.remove(name);
+            }
+            None => self.global = false,
+        }
+    }
+
+    /// Returns `true` if all libraries are paused.
+    pub fn is_globally_paused(&self) -> bool {
+        self.global
+    }
+
+    /// Returns `true` if the library is paused, either globally or individually.
+    pub fn is_paused(&self, library: &str) -> bool {
+        self.global || self.libraries.contains(library)
+    }
+
+    /// Returns the names of individually paused libraries.
+    pub fn paused_libraries(&self) -> Vec<String> {
+        self.libraries.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    fs,
+    path::Path,
+    time::Duration
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::Deserialize;
+
+use crate::core::config::LibraryConfig;
+use super::{
+    maintenance_state::MaintenanceState,
+    pause_state::PauseState,
+    sync_strategy::SyncStrategy
+};
+
+/// Kind of a simulated filesystem change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimulatedEventKind {
+
+    /// A file was created
+    Create,
+
+    /// A file was modified
+    Modify,
+
+    /// A file was removed
+    Remove,
+}
+
+impl Display for SimulatedEventKind {
+
+    /// Formats the kind as its scenario name.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let kind_str = match self {
+            SimulatedEventKind::Create => "create",
+            SimulatedEventKind::Modify => "modify",
+            SimulatedEventKind::Remove => "remove",
+        };
+        write!(f, "{}", kind_str)
+    }
+}
+
+/// A filesystem change at a point of a scenario.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SimulatedEvent {
+
+    /// Seconds since the start of the scenario
+    pub at: f64,
+
+    /// Changed path, absolute or relative to the library source
+    pub path: String,
+
+    /// Kind of change
+    #[serde(default = "SimulatedEvent::default_kind")]
+    pub kind: SimulatedEventKind,
+}
+
+impl SimulatedEvent {
+
+    /// Returns the default event kind.
+    fn default_kind() -> SimulatedEventKind {
+        SimulatedEventKind::Create
+    }
+}
+
+/// A sequence of filesystem changes to replay against a library.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Scenario {
+
+    /// Events in any order; they are replayed by time
+    pub events: Vec<SimulatedEvent>,
+}
+
+impl Scenario {
+
+    /// Loads a scenario file.
+    ///
+    /// `.jsonl` files are read as an event journal with one JSON event per
+    /// line; anything else is read as TOML with an `[[events]]` list.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+
+        if path.extension().is_some_and(|extension| extension == "jsonl") {
+            let events = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(index, line)| {
+                    serde_json::from_str(line)
+                        .with_context(|| format!("Invalid event on line {} of {}", index + 1, path.display()))
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(Self { events });
+        }
+
+        toml::from_str(&content).with_context(|| format!("Invalid scenario {}", path.display()))
+    }
+}
+
+/// What a live watcher would do when a debounce window closes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatedOutcome {
+
+    /// The library would be synchronized
+    Sync,
+
+    /// The library is paused, so the changes would be skipped
+    Paused,
+
+    /// Maintenance mode is enabled, so the sync would be deferred
+    Deferred,
+}
+
+/// A sync triggered by a settled batch of simulated changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedSync {
+
+    /// Time since the start of the scenario at which the sync would run
+    pub at: Duration,
+
+    /// What the watcher would do
+    pub outcome: SimulatedOutcome,
+
+    /// Changed paths that pass the library filters
+    pub paths: Vec<String>,
+
+    /// Changed paths the library filters leave out
+    pub filtered: Vec<String>,
+
+    /// Destinations with the strategy that would sync into each
+    pub destinations: Vec<(String, SyncStrategy)>,
+}
+
+impl Display for SimulatedSync {
+
+    /// Formats the sync as a multi-line plan.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let outcome = match self.outcome {
+            SimulatedOutcome::Sync => "sync",
+            SimulatedOutcome::Paused => "skip (paused)",
+            SimulatedOutcome::Deferred => "defer (maintenance)",
+        };
+        write!(f, "[{:.1}s] {}", self.at.as_secs_f64(), outcome)?;
+        for (destination, strategy) in &self.destinations {
+            write!(f, "\n  -> {} ({})", destination, strategy)?;
+        }
+        for path in &self.paths {
+            write!(f, "\n  + {}", path)?;
+        }
+        for path in &self.filtered {
+            write!(f, "\n  - {} (filtered)", path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays a scenario against a library without touching any files.
+///
+/// Events are grouped the way the watcher debounces them: a batch settles
+/// once no event arrives for the library's debounce period, and the sync
+/// then runs with the pause and maintenance states as they are now.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the library's destinations are invalid or an
+/// event has a negative or non-finite time.
+pub fn simulate(
+    config: &LibraryConfig,
+    scenario: &Scenario,
+    pause: &PauseState,
+    maintenance: &MaintenanceState,
+) -> Result<Vec<SimulatedSync>, Error> {
+    let destinations: Vec<(String, SyncStrategy)> = config
+        .destination_strategies()?
+        .into_iter()
+        .map(|(destination, strategy)| (destination.path.clone(), strategy))
+        .collect();
+    let outcome = if pause.is_paused(&config.name) {
+        SimulatedOutcome::Paused
+    } else if maintenance.is_enabled() {
+        SimulatedOutcome::Deferred
+    } else {
+        SimulatedOutcome::Sync
+    };
+
+    let mut events = scenario
+        .events
+        .iter()
+        .map(|event| {
+            Duration::try_from_secs_f64(event.at)
+                .map(|at| (at, event))
+                .map_err(|_| anyhow!("Invalid event time {} for {}", event.at, event.path))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    events.sort_by_key(|(at, _)| *at);
+
+    // The watcher never debounces for less than 2 seconds
+    let debounce = config.debounce_time().max(Duration::from_secs(2));
+    let mut syncs = Vec::new();
+    let mut batch: Vec<&SimulatedEvent> = Vec::new();
+    let mut last = Duration::ZERO;
+
+    for (at, event) in events {
+        if !batch.is_empty() && at - last >= debounce {
+            syncs.push(settle(config, &batch, last + debounce, outcome, &destinations));
+            batch.clear();
+        }
+        batch.push(event);
+        last = at;
+    }
+    if !batch.is_empty() {
+        syncs.push(settle(config, &batch, last + debounce, outcome, &destinations));
+    }
+
+    Ok(syncs)
+}
+
+/// Turns a settled batch of events into a simulated sync.
+fn settle(
+    config: &LibraryConfig,
+    batch: &[&SimulatedEvent],
+    at: Duration,
+    outcome: SimulatedOutcome,
+    destinations: &[(String, SyncStrategy)],
+) -> SimulatedSync {
+    let mut paths = Vec::new();
+    let mut filtered = Vec::new();
+    for event in batch {
+        let relative = Path::new(&event.path)
+            .strip_prefix(&config.source)
+            .unwrap_or(Path::new(&event.path));
+        let entry = format!("{} {}", event.kind, relative.display());
+        if config.matches_filters(relative) {
+            paths.push(entry);
+        } else {
+            filtered.push(entry);
+        }
+    }
+
+    SimulatedSync {
+        at,
+        outcome,
+        paths,
+        filtered,
+        destinations: destinations.to_vec(),
+    }
+}
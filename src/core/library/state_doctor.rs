@@ -0,0 +1,211 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Context, Error, Result};
+use serde_json::Value;
+
+use crate::{
+    infrastructure::fs::DirScanner,
+    warn_log
+};
+
+/// Domain identifier for state file checks
+const STATE_DOCTOR_LOGGER_DOMAIN: &str = "[STATE-DOCTOR]";
+
+/// Suffix appended to the copy of a damaged state file set aside by a repair.
+pub const STATE_CORRUPT_SUFFIX: &str = "corrupt";
+
+/// Problem found in a state file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateProblem {
+
+    /// A JSON file can't be parsed
+    Unreadable(String),
+
+    /// Lines of a JSON Lines file can't be parsed, 1-based
+    BadLines(Vec<usize>),
+}
+
+impl Display for StateProblem {
+
+    /// Formats the problem as a short description.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StateProblem::Unreadable(error) => write!(f, "unreadable: {}", error),
+            StateProblem::BadLines(lines) => {
+                let lines: Vec<String> = lines.iter().map(usize::to_string).collect();
+                write!(f, "unreadable lines {}", lines.join(", "))
+            }
+        }
+    }
+}
+
+/// A damaged state file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateIssue {
+
+    /// Path of the file, relative to the state directory
+    pub path: PathBuf,
+
+    /// What is wrong with it
+    pub problem: StateProblem,
+}
+
+impl Display for StateIssue {
+
+    /// Formats the issue as `<path>: <problem>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}: {}", self.path.display(), self.problem)
+    }
+}
+
+/// Result of [`StateDoctor::check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateReport {
+
+    /// Number of state files checked
+    pub checked: usize,
+
+    /// Damaged files, sorted by path
+    pub issues: Vec<StateIssue>,
+
+    /// Whether the damaged files were repaired
+    pub repaired: bool,
+}
+
+impl StateReport {
+
+    /// Returns `true` if no file is damaged.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for StateReport {
+
+    /// Formats the report as a summary line followed by one line per issue.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let outcome = if self.repaired { "repaired" } else { "damaged" };
+        write!(f, "{} state files checked, {} {}", self.checked, self.issues.len(), outcome)?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the JSON state files of the state directory.
+///
+/// State files are plain JSON (`.json`) or JSON Lines (`.jsonl`), read
+/// with defaults for missing fields, so older files load after an
+/// upgrade. A file damaged by a crash mid-write or a full disk can't be
+/// read though, and keeps the feature using it failing. Repairing sets a
+/// damaged `.json` file aside as `<name>.corrupt`, so its default is used
+/// from then on, and drops the unreadable lines of a `.jsonl` file after
+/// saving a copy the same way.
+pub struct StateDoctor {
+
+    /// Directory holding the state files
+    dir: PathBuf,
+}
+
+impl StateDoctor {
+
+    /// Creates a doctor for the state files below `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Checks every state file without changing anything.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the state directory or a file can't be read.
+    pub fn check(&self) -> Result<StateReport, Error> {
+        let mut report = StateReport::default();
+        if !self.dir.exists() {
+            return Ok(report);
+        }
+
+        for file in DirScanner::scan(&self.dir)? {
+            let path = self.dir.join(&file.relative);
+            let problem = match file.relative.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => Self::check_json(&path)?,
+                Some("jsonl") => Self::check_json_lines(&path)?,
+                _ => continue,
+            };
+            report.checked += 1;
+            if let Some(problem) = problem {
+                report.issues.push(StateIssue { path: file.relative, problem });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Checks every state file and repairs the damaged ones.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be read, set aside or rewritten.
+    pub fn repair(&self) -> Result<StateReport, Error> {
+        let mut report = self.check()?;
+        for issue in &report.issues {
+            let path = self.dir.join(&issue.path);
+            let backup = Self::backup_path(&path);
+            match &issue.problem {
+                StateProblem::Unreadable(_) => {
+                    fs::rename(&path, &backup)
+                        .with_context(|| format!("Failed to move {} to {}", path.display(), backup.display()))?;
+                }
+                StateProblem::BadLines(lines) => {
+                    fs::copy(&path, &backup)
+                        .with_context(|| format!("Failed to copy {} to {}", path.display(), backup.display()))?;
+                    let content = fs::read(&path)?;
+                    let kept: Vec<u8> = content
+                        .split(|byte| *byte == b'\n')
+                        .enumerate()
+                        .filter(|(index, line)| !line.is_empty() && !lines.contains(&(index + 1)))
+                        .flat_map(|(_, line)| line.iter().copied().chain([b'\n']))
+                        .collect();
+                    fs::write(&path, kept).with_context(|| format!("Failed to rewrite {}", path.display()))?;
+                }
+            }
+            warn_log!(
+                STATE_DOCTOR_LOGGER_DOMAIN,
+                format!("Repaired {}, the damaged copy is {}", path.display(), backup.display())
+            );
+        }
+        report.repaired = true;
+        Ok(report)
+    }
+
+    /// Checks that a file holds a single JSON document.
+    fn check_json(path: &Path) -> Result<Option<StateProblem>, Error> {
+        let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_slice::<Value>(&content)
+            .err()
+            .map(|e| StateProblem::Unreadable(e.to_string())))
+    }
+
+    /// Checks that every non-empty line of a file is a JSON document.
+    fn check_json_lines(path: &Path) -> Result<Option<StateProblem>, Error> {
+        let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let bad_lines: Vec<usize> = content
+            .split(|byte| *byte == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim_ascii().is_empty())
+            .filter(|(_, line)| serde_json::from_slice::<Value>(line).is_err())
+            .map(|(index, _)| index + 1)
+            .collect();
+        Ok((!bad_lines.is_empty()).then_some(StateProblem::BadLines(bad_lines)))
+    }
+
+    /// Returns the path a damaged file is set aside at.
+    fn backup_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(STATE_CORRUPT_SUFFIX);
+        path.with_file_name(name)
+    }
+}
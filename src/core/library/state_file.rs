@@ -0,0 +1,96 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Context, Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Loads a JSON state file, returning the default value if it doesn't exist.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the file exists but can't be read or parsed.
+pub(crate) fn load_state<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Error> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Writes a JSON state file, creating parent directories as needed.
+///
+/// The state is written to a temporary file next to it and renamed into
+/// place, so readers never see a partially written file.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the file can't be written.
+pub(crate) fn save_state<T: Serialize>(state: &T, path: &Path) -> Result<(), Error> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    file.write_all(serde_json::to_string_pretty(state)?.as_bytes())?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Exclusive lock on a state file, held by one process at a time and
+/// released when dropped.
+///
+/// The lock is taken on a `.lock` file next to the state file, which
+/// can then be replaced while locked. Locks are advisory and only taken
+/// on Unix; elsewhere they don't exclude anything.
+pub(crate) struct StateLock {
+
+    /// Open lock file, unlocked when closed
+    _file: File,
+}
+
+/// Locks a state file, waiting for other processes to release it.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the lock file can't be opened or locked.
+pub(crate) fn lock_state(path: &Path) -> Result<StateLock, Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock_path = lock_path(path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    lock_file(&file).with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(StateLock { _file: file })
+}
+
+/// Returns the lock file of a state file, e.g. `queue.json.lock`.
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Takes an exclusive lock on an open file, waiting for it.
+#[cfg(unix)]
+fn lock_file(file: &File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: flock only reads the descriptor, which `file` keeps open
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Takes an exclusive lock on an open file, waiting for it.
+#[cfg(not(unix))]
+fn lock_file(_file: &File) -> std::io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration
+};
+
+use anyhow::{Error, Result};
+
+use crate::core::notification::format_duration;
+
+use super::sync_history::{DestinationRun, SyncHistory, SyncRecord};
+
+/// Number of most recent runs an estimate is computed from.
+pub const SYNC_ESTIMATE_SAMPLES: usize = 50;
+
+/// Duration of a sync to a destination, estimated from its past runs.
+///
+/// Past durations are fitted to a fixed cost, covering the connection
+/// and the comparison of the trees, plus a cost per changed path. Runs
+/// that failed are left out, since they may have stopped early.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncEstimate {
+
+    /// Time a run takes whatever it changes
+    fixed: Duration,
+
+    /// Additional time per changed path
+    per_path: Duration,
+
+    /// Largest number of paths changed by one of the runs
+    max_changed: usize,
+
+    /// Number of runs the estimate is computed from
+    samples: usize,
+}
+
+impl SyncEstimate {
+
+    /// Fits an estimate to past runs.
+    ///
+    /// # Returns
+    /// `None` if no run succeeded.
+    pub fn from_runs<'a>(runs: impl IntoIterator<Item = &'a DestinationRun>) -> Option<Self> {
+        let points: Vec<(f64, f64)> = runs
+            .into_iter()
+            .filter(|run| !run.failed)
+            .map(|run| (run.changed as f64, run.duration_ms as f64))
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+
+        let count = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+        // Least squares, without negative costs when timings are noisy
+        let per_path = if variance > 0.0 { (covariance / variance).max(0.0) } else { 0.0 };
+        let fixed = (mean_y - per_path * mean_x).max(0.0);
+        Some(Self {
+            fixed: Duration::from_secs_f64(fixed / 1000.0),
+            per_path: Duration::from_secs_f64(per_path / 1000.0),
+            max_changed: points.iter().map(|(x, _)| *x as usize).max().unwrap_or_default(),
+            samples: points.len(),
+        })
+    }
+
+    /// Fits an estimate to the most recent runs of a library to a destination.
+    pub fn from_history(records: &[SyncRecord], library: &str, destination: &str) -> Option<Self> {
+        let runs: Vec<&DestinationRun> = records
+            .iter()
+            .rev()
+            .filter(|record| record.library == library)
+            .flat_map(|record| &record.destinations)
+            .filter(|run| run.destination == destination && !run.failed)
+            .take(SYNC_ESTIMATE_SAMPLES)
+            .collect();
+        Self::from_runs(runs)
+    }
+
+    /// Fits an estimate to the runs in the default sync history.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the history can't be read.
+    pub fn load(library: &str, destination: &str) -> Result<Option<Self>, Error> {
+        let records = SyncHistory::load_since(SyncHistory::default_path(), 0)?;
+        Ok(Self::from_history(&records, library, destination))
+    }
+
+    /// Returns the estimated duration of a run changing `paths` paths.
+    pub fn estimate(&self, paths: usize) -> Duration {
+        self.fixed + self.per_path.saturating_mul(u32::try_from(paths).unwrap_or(u32::MAX))
+    }
+
+    /// Returns the estimated duration of the largest run seen so far,
+    /// an upper bound for runs whose size isn't known in advance.
+    pub fn longest(&self) -> Duration {
+        self.estimate(self.max_changed)
+    }
+
+    /// Returns the number of runs the estimate is computed from.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+impl Display for SyncEstimate {
+
+    /// Formats the costs, e.g. `4s + 120ms per path from 12 runs`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} + {}ms per path from {} runs",
+            format_duration(self.fixed),
+            self.per_path.as_millis(),
+            self.samples
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Error, Result};
+
+use crate::{
+    core::config::{DestinationConfig, LibraryConfig},
+    infrastructure::fs::{ProgressSender, SyncReport}
+};
+use super::{
+    library_sync::{ConfirmCallback, LibrarySync},
+    sync_strategy::SyncStrategy
+};
+
+/// Performs the transfer of a library into a single destination.
+///
+/// [`LibrarySync`] handles everything around the transfer: destination
+/// locks, hooks, history and notifications. Replacing the executor with
+/// [`LibrarySync::with_executor`] lets that orchestration run without
+/// rsync or a network.
+pub trait SyncExecutor: Send + Sync {
+
+    /// Synchronizes the library into one destination.
+    ///
+    /// # Arguments
+    /// * `config` - Library being synchronized
+    /// * `destination` - Destination to write into
+    /// * `strategy` - Strategy selected for the destination
+    /// * `confirm` - Decides whether destructive plans may run
+    /// * `progress` - Channel for the typed progress events of the transfer, if any
+    ///
+    /// # Returns
+    /// The statistics of the transfer, with the paths it changed in the
+    /// destination.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the destination failed to synchronize.
+    fn sync_destination(
+        &self,
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        strategy: SyncStrategy,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error>;
+}
+
+/// Executor that transfers with the selected strategy: rsync, HTTP uploads,
+/// native copies, S3 uploads, robocopy or rclone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrategyExecutor;
+
+impl SyncExecutor for StrategyExecutor {
+
+    fn sync_destination(
+        &self,
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        strategy: SyncStrategy,
+        confirm: &ConfirmCallback,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        match strategy {
+            SyncStrategy::Rsync => LibrarySync::rsync_library(config, destination, confirm, progress),
+            SyncStrategy::HttpUpload => LibrarySync::upload_library(config, destination, progress),
+            SyncStrategy::Native => LibrarySync::native_library(config, destination, confirm, progress),
+            SyncStrategy::S3 => LibrarySync::s3_library(config, destination, confirm, progress),
+            // robocopy writes its log only once done, and rclone only logs
+            // finished files, so there is no progress to send
+            SyncStrategy::Robocopy => LibrarySync::robocopy_library(config, destination, confirm),
+            SyncStrategy::Rclone => LibrarySync::rclone_library(config, destination, confirm),
+        }
+    }
+}
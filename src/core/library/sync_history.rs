@@ -0,0 +1,152 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    core::config::Config,
+    infrastructure::fs::{ResourceUsage, SyncReport},
+    warn_log
+};
+
+/// Domain identifier for sync history logs
+const HISTORY_LOGGER_DOMAIN: &str = "[HISTORY]";
+
+/// File name of the sync history inside the state directory.
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// Name of the counter of CPU time spent by transfer processes, in milliseconds.
+pub const SYNC_CPU_TIME_METRIC: &str = "pilipili_strm.sync.cpu_time";
+
+/// Name of the counter of bytes read from storage by transfer processes.
+pub const SYNC_READ_BYTES_METRIC: &str = "pilipili_strm.sync.read_bytes";
+
+/// Name of the counter of bytes written to storage by transfer processes.
+pub const SYNC_WRITTEN_BYTES_METRIC: &str = "pilipili_strm.sync.written_bytes";
+
+/// Outcome of a single library sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncRecord {
+
+    /// Name of the synchronized library
+    pub library: String,
+
+    /// Unix timestamp of when the sync finished
+    pub timestamp: i64,
+
+    /// Number of paths changed across all destinations
+    pub changed: usize,
+
+    /// Error message if the sync failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Run of each destination, used to estimate future durations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub destinations: Vec<DestinationRun>,
+}
+
+/// Outcome of a library sync to a single destination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationRun {
+
+    /// Path of the destination
+    pub destination: String,
+
+    /// Number of paths changed in the destination
+    pub changed: usize,
+
+    /// Time the sync took, in milliseconds
+    pub duration_ms: u64,
+
+    /// Whether the sync to this destination failed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub failed: bool,
+
+    /// Resources used by the transfer processes, when the strategy runs any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+
+    /// Statistics of a successful transfer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<SyncReport>,
+}
+
+impl SyncRecord {
+
+    /// Creates a record for a sync that just finished.
+    pub fn new(library: &str, changed: usize, error: Option<String>) -> Self {
+        Self {
+            library: library.to_string(),
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            changed,
+            error,
+            destinations: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the sync failed.
+    pub fn is_failure(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// Append-only log of sync outcomes, stored as JSON lines.
+pub struct SyncHistory;
+
+impl SyncHistory {
+
+    /// Returns the default location of the sync history.
+    pub fn default_path() -> PathBuf {
+        Config::get().state_dir().join(HISTORY_FILE_NAME)
+    }
+
+    /// Appends a record to the history at `path`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn append(path: impl AsRef<Path>, record: &SyncRecord) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Appends a record to the default history, logging failures.
+    pub fn record(record: &SyncRecord) {
+        let path = Self::default_path();
+        if let Err(e) = Self::append(&path, record) {
+            warn_log!(
+                HISTORY_LOGGER_DOMAIN,
+                format!("Failed to write sync history {}: {}", path.display(), e)
+            );
+        }
+    }
+
+    /// Loads the records at `path` that finished at or after `since`.
+    ///
+    /// A missing file is an empty history. Corrupt lines, e.g. from an
+    /// interrupted write, are skipped.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file exists but can't be read.
+    pub fn load_since(path: impl AsRef<Path>, since: i64) -> Result<Vec<SyncRecord>, Error> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str::<SyncRecord>(line).ok())
+            .filter(|record| record.timestamp >= since)
+            .collect())
+    }
+}
@@ -0,0 +1,157 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::{
+    core::config::DestinationConfig,
+    infrastructure::fs::{run_with_timeout, shell_quote, CommandOutput, SshRunner},
+    info_log,
+    warn_log
+};
+use super::media_refresh::AffectedFolders;
+
+/// Domain identifier for hook logs
+const HOOK_LOGGER_DOMAIN: &str = "[HOOK]";
+
+/// Outcome of a sync, exposed to hook command templates.
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+
+    /// Name of the synced library
+    pub library: String,
+
+    /// Destination the library was synced to
+    pub destination: String,
+
+    /// Paths changed by the sync, relative to the destination
+    pub changed_paths: Vec<String>,
+
+    /// Error message if the sync failed
+    pub error: Option<String>,
+}
+
+impl HookContext {
+
+    /// Replaces the template variables in a hook command.
+    ///
+    /// Every value is shell-quoted, since names of libraries and media
+    /// files and error messages holding them may contain shell syntax.
+    /// The template is read once, so a value that looks like a variable
+    /// is kept as it is.
+    pub fn render(&self, template: &str) -> String {
+        let quote_all = |values: &[String], empty: &str| values
+            .iter()
+            .map(|value| shell_quote(if value.is_empty() { empty } else { value }))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let variable = &rest[start..];
+            let Some(end) = variable.find('}') else {
+                rest = variable;
+                break;
+            };
+            let value = match &variable[1..end] {
+                "library" => Some(shell_quote(&self.library)),
+                "destination" => Some(shell_quote(&self.destination)),
+                "changed_count" => Some(self.changed_paths.len().to_string()),
+                "changed_paths" => Some(quote_all(&self.changed_paths, "")),
+                "changed_folders" => Some(quote_all(AffectedFolders::from_paths(&self.changed_paths).folders(), ".")),
+                "error" => Some(shell_quote(self.error.as_deref().unwrap_or_default())),
+                _ => None,
+            };
+            match value {
+                Some(value) => {
+                    rendered.push_str(&value);
+                    rest = &variable[end + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &variable[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// Runs the post-sync hooks of a destination.
+///
+/// Remote commands run on the destination host through [`SshRunner`]; local
+/// commands run through `sh -c`. Every command is attempted and its output
+/// logged, even if an earlier one failed.
+///
+/// # Errors
+/// Returns `anyhow::Error` listing the commands that failed.
+pub fn run_sync_hooks(destination: &DestinationConfig, context: &HookContext) -> Result<(), Error> {
+    let hooks = &destination.hooks;
+    let succeeded = context.error.is_none();
+    let (remote, local) = if succeeded {
+        (&hooks.on_success, &hooks.local_on_success)
+    } else {
+        (&hooks.on_failure, &hooks.local_on_failure)
+    };
+
+    let mut failures = Vec::new();
+
+    if !remote.is_empty() {
+        match destination.ssh_config() {
+            Some(ssh_config) => {
+                let runner = SshRunner::new(ssh_config).with_timeout(hooks.timeout());
+                for template in remote {
+                    let command = context.render(template);
+                    let result = runner.run(&command);
+                    if let Some(failure) = log_hook_result(&command, result) {
+                        failures.push(failure);
+                    }
+                }
+            }
+            None => {
+                warn_log!(
+                    HOOK_LOGGER_DOMAIN,
+                    format!("Destination {} is not remote, skipping remote hooks", destination.path)
+                );
+            }
+        }
+    }
+
+    for template in local {
+        let command = context.render(template);
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(&command);
+        let result = run_with_timeout(cmd, hooks.timeout());
+        if let Some(failure) = log_hook_result(&command, result) {
+            failures.push(failure);
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Hooks failed: {}", failures.join("; ")))
+    }
+}
+
+/// Logs the output of a hook command, returning a failure description if it failed.
+fn log_hook_result(command: &str, result: Result<CommandOutput, Error>) -> Option<String> {
+    match result {
+        Ok(output) => {
+            info_log!(
+                HOOK_LOGGER_DOMAIN,
+                format!(
+                    "Hook '{}' exited with {:?}, stdout: {}, stderr: {}",
+                    command,
+                    output.status,
+                    output.stdout.trim(),
+                    output.stderr.trim()
+                )
+            );
+            (!output.success()).then(|| format!("'{}' exited with {:?}", command, output.status))
+        }
+        Err(e) => Some(format!("'{}': {}", command, e)),
+    }
+}
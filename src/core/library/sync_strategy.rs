@@ -0,0 +1,148 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::fs::RcloneSync;
+
+/// What a sync strategy is able to do, so callers can adapt to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StrategyCapabilities {
+
+    /// Files missing from the source can be deleted at the destination
+    pub supports_delete: bool,
+
+    /// Modification times are preserved at the destination
+    pub preserves_mtime: bool,
+
+    /// Progress is reported while transferring
+    pub supports_progress: bool,
+
+    /// A dry-run plan can be computed before syncing
+    pub supports_plan: bool,
+
+    /// Transferred files can be compared with their source by checksum
+    pub supports_verify: bool,
+}
+
+/// How files reach a destination, selected from the destination's address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum SyncStrategy {
+
+    /// rsync to a local path, SMB share, `ssh://` or `user@host:path`
+    /// remote, or `rsync://` daemon
+    Rsync,
+
+    /// Resumable chunked upload to an `http://` or `https://` endpoint
+    HttpUpload,
+
+    /// Built-in copy between local directories, needing no rsync; see
+    /// [`NativeSync`](crate::infrastructure::fs::NativeSync)
+    Native,
+
+    /// Object uploads to an `s3://bucket/prefix` of S3 or a compatible
+    /// service such as MinIO or R2, for libraries with a `.strm` mirror tree
+    S3,
+
+    /// `robocopy` to a local path or SMB share, for Windows hosts without
+    /// rsync; see [`RobocopySync`](crate::infrastructure::fs::RobocopySync)
+    Robocopy,
+
+    /// `rclone` to an `rclone:remote:path` remote or a `webdav://` or
+    /// `webdavs://` server; see [`RcloneSync`](crate::infrastructure::fs::RcloneSync)
+    Rclone,
+}
+
+impl SyncStrategy {
+
+    /// Selects the strategy for a destination address.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if no strategy supports the address scheme.
+    pub fn for_destination(path: &str) -> Result<Self, Error> {
+        if let Some((scheme, _)) = path.split_once("://") {
+            return match scheme.to_ascii_lowercase().as_str() {
+                "http" | "https" => Ok(SyncStrategy::HttpUpload),
+                "ssh" | "rsync" | "file" => Ok(SyncStrategy::Rsync),
+                "s3" => Ok(SyncStrategy::S3),
+                "webdav" | "webdavs" => Ok(SyncStrategy::Rclone),
+                _ => Err(anyhow!("No sync strategy supports '{}://' destinations", scheme)),
+            };
+        }
+        if RcloneSync::is_rclone_destination(path) {
+            return Ok(SyncStrategy::Rclone);
+        }
+        Ok(SyncStrategy::Rsync)
+    }
+
+    /// Returns what the strategy is able to do.
+    pub fn capabilities(&self) -> StrategyCapabilities {
+        match self {
+            SyncStrategy::Rsync => StrategyCapabilities {
+                supports_delete: true,
+                preserves_mtime: true,
+                supports_progress: true,
+                supports_plan: true,
+                supports_verify: true,
+            },
+            SyncStrategy::HttpUpload => StrategyCapabilities {
+                supports_delete: false,
+                preserves_mtime: false,
+                supports_progress: true,
+                supports_plan: false,
+                supports_verify: false,
+            },
+            SyncStrategy::Native => StrategyCapabilities {
+                supports_delete: true,
+                preserves_mtime: true,
+                supports_progress: true,
+                supports_plan: true,
+                supports_verify: true,
+            },
+            SyncStrategy::S3 => StrategyCapabilities {
+                supports_delete: true,
+                preserves_mtime: false,
+                supports_progress: true,
+                supports_plan: true,
+                supports_verify: false,
+            },
+            SyncStrategy::Robocopy => StrategyCapabilities {
+                supports_delete: true,
+                preserves_mtime: true,
+                supports_progress: false,
+                supports_plan: true,
+                supports_verify: false,
+            },
+            SyncStrategy::Rclone => StrategyCapabilities {
+                supports_delete: true,
+                preserves_mtime: true,
+                supports_progress: false,
+                supports_plan: true,
+                supports_verify: false,
+            },
+        }
+    }
+}
+
+impl Display for SyncStrategy {
+
+    /// Formats the strategy name.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            SyncStrategy::Rsync => "rsync",
+            SyncStrategy::HttpUpload => "http-upload",
+            SyncStrategy::Native => "native",
+            SyncStrategy::S3 => "s3",
+            SyncStrategy::Robocopy => "robocopy",
+            SyncStrategy::Rclone => "rclone",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{Error, Result};
+use time::{macros::format_description, OffsetDateTime};
+
+use crate::{
+    core::{
+        config::DigestPeriod,
+        library::{SyncHistory, SyncRecord}
+    },
+    infrastructure::fs::NaturalOrder
+};
+
+/// Number of distinct errors listed in a digest.
+const DIGEST_TOP_ERRORS: usize = 3;
+
+/// Summary of sync activity over a digest period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+
+    /// Period the digest covers
+    pub period: DigestPeriod,
+
+    /// Unix timestamp of the end of the period
+    pub until: i64,
+
+    /// Number of syncs that ran
+    pub syncs: usize,
+
+    /// Number of paths added or updated across all libraries
+    pub added: usize,
+
+    /// Number of failed syncs
+    pub failures: usize,
+
+    /// Paths added or updated per library
+    pub libraries: BTreeMap<String, usize>,
+
+    /// Most frequent error messages with their counts, most frequent first
+    pub top_errors: Vec<(String, usize)>,
+}
+
+impl Digest {
+
+    /// Summarizes sync records into a digest.
+    ///
+    /// # Arguments
+    /// * `period` - Period the records cover
+    /// * `until` - Unix timestamp of the end of the period
+    /// * `records` - Sync outcomes within the period
+    pub fn from_records(period: DigestPeriod, until: i64, records: &[SyncRecord]) -> Self {
+        let mut libraries = BTreeMap::new();
+        let mut errors: HashMap<&str, usize> = HashMap::new();
+        for record in records {
+            *libraries.entry(record.library.clone()).or_default() += record.changed;
+            if let Some(error) = &record.error {
+                *errors.entry(error.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut top_errors: Vec<(String, usize)> = errors
+            .into_iter()
+            .map(|(error, count)| (error.to_string(), count))
+            .collect();
+        top_errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| NaturalOrder::compare(&a.0, &b.0)));
+        top_errors.truncate(DIGEST_TOP_ERRORS);
+
+        Self {
+            period,
+            until,
+            syncs: records.len(),
+            added: records.iter().map(|record| record.changed).sum(),
+            failures: records.iter().filter(|record| record.is_failure()).count(),
+            libraries,
+            top_errors,
+        }
+    }
+
+    /// Summarizes the default sync history over the period ending now.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the history can't be read.
+    pub fn collect(period: DigestPeriod) -> Result<Self, Error> {
+        let until = OffsetDateTime::now_utc().unix_timestamp();
+        let since = until - period.duration().as_secs() as i64;
+        let records = SyncHistory::load_since(SyncHistory::default_path(), since)?;
+        Ok(Self::from_records(period, until, &records))
+    }
+
+    /// Returns the placeholder values for the digest notification.
+    pub fn vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("period", self.period.to_string()),
+            ("syncs", self.syncs.to_string()),
+            ("added", self.added.to_string()),
+            ("failures", self.failures.to_string()),
+            ("libraries", self.libraries_summary()),
+            ("top_errors", self.errors_summary()),
+        ]
+    }
+
+    /// Renders the digest as a Markdown report.
+    pub fn to_markdown(&self) -> String {
+        let mut report = format!("# Sync digest ({}) - {}\n\n", self.period, self.date());
+        let _ = writeln!(report, "- Syncs: {}", self.syncs);
+        let _ = writeln!(report, "- Items added: {}", self.added);
+        let _ = writeln!(report, "- Failures: {}", self.failures);
+
+        report.push_str("\n## Library growth\n\n");
+        if self.libraries.is_empty() {
+            report.push_str("No syncs ran.\n");
+        }
+        for (library, added) in self.sorted_libraries() {
+            let _ = writeln!(report, "- {}: +{}", library, added);
+        }
+
+        report.push_str("\n## Top errors\n\n");
+        if self.top_errors.is_empty() {
+            report.push_str("None.\n");
+        }
+        for (error, count) in &self.top_errors {
+            let _ = writeln!(report, "- {}x {}", count, error);
+        }
+        report
+    }
+
+    /// Writes the Markdown report into `dir` as `digest-<period>-<date>.md`.
+    ///
+    /// # Returns
+    /// Path of the written report.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the report can't be written.
+    pub fn write_report(&self, dir: &Path) -> Result<PathBuf, Error> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("digest-{}-{}.md", self.period, self.date()));
+        fs::write(&path, self.to_markdown())?;
+        Ok(path)
+    }
+
+    /// Returns the end date of the period as `YYYY-MM-DD`.
+    fn date(&self) -> String {
+        OffsetDateTime::from_unix_timestamp(self.until)
+            .ok()
+            .and_then(|date| date.format(format_description!("[year]-[month]-[day]")).ok())
+            .unwrap_or_else(|| self.until.to_string())
+    }
+
+    /// Formats library growth as `Anime +12, Movies +3`.
+    fn libraries_summary(&self) -> String {
+        if self.libraries.is_empty() {
+            return "-".to_string();
+        }
+        self.sorted_libraries()
+            .into_iter()
+            .map(|(library, added)| format!("{} +{}", library, added))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns the library growth in natural order of library names.
+    pub(crate) fn sorted_libraries(&self) -> Vec<(&String, &usize)> {
+        let mut libraries: Vec<(&String, &usize)> = self.libraries.iter().collect();
+        NaturalOrder::sort_by_key(&mut libraries, |(library, _)| library.as_str());
+        libraries
+    }
+
+    /// Formats the top errors as `2x timeout; 1x denied`.
+    fn errors_summary(&self) -> String {
+        if self.top_errors.is_empty() {
+            return "-".to_string();
+        }
+        self.top_errors
+            .iter()
+            .map(|(error, count)| format!("{}x {}", count, error))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    process,
+    sync::atomic::{AtomicU64, Ordering}
+};
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::{digest::Digest, template::NotificationKind};
+
+/// Version of the outbound event schema, following semantic versioning.
+///
+/// Adding an optional field or an event type bumps the minor version;
+/// consumers of the same major version keep working, since unknown fields
+/// are ignored and unknown types read as [`OutboundEvent::Unknown`].
+/// Renaming or removing a field, or changing its type, bumps the major
+/// version.
+pub const EVENT_SCHEMA_VERSION: &str = "1.0.0";
+
+/// Counter making event IDs created within the same nanosecond unique.
+static EVENT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An event as sent to outbound consumers such as webhooks and event
+/// streams, with the schema version it follows.
+///
+/// Serialized as:
+///
+/// ```json
+/// {
+///   "schema_version": "1.0.0",
+///   "id": "1866f0c2a4b1e000-2a1f-0",
+///   "timestamp": "2025-10-15T08:16:37.123456Z",
+///   "type": "sync_completed",
+///   "data": { "library": "anime", "count": 3, "title": "Frieren", "season": 1 }
+/// }
+/// ```
+///
+/// `id` is unique across processes and restarts, so consumers can drop
+/// events delivered twice, and `timestamp` is the UTC time the event was
+/// created. `type` is the key of the [`NotificationKind`], and `data` holds the
+/// fields of the event type, listed with each variant of
+/// [`OutboundEvent`]. Optional fields missing from an event are left out.
+///
+/// # Examples
+/// ```
+/// use pilipili_strm::core::notification::{EventEnvelope, NotificationKind, OutboundEvent};
+///
+/// let envelope = EventEnvelope::from_notification(
+///     NotificationKind::SyncFailed,
+///     &[("library", "anime"), ("error", "disk full")],
+/// );
+/// let json = envelope.to_json()?;
+/// assert_eq!(EventEnvelope::from_json(&json)?, envelope);
+/// assert!(matches!(envelope.event, OutboundEvent::SyncFailed(ref failure) if failure.error == "disk full"));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventEnvelope {
+
+    /// Version of the schema the event follows, e.g. `1.0.0`
+    pub schema_version: String,
+
+    /// Unique identifier of the event
+    pub id: String,
+
+    /// UTC time the event was created, in RFC 3339 format
+    pub timestamp: String,
+
+    /// Type and fields of the event
+    #[serde(flatten)]
+    pub event: OutboundEvent,
+}
+
+/// Type of an outbound event, with its fields under `data`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum OutboundEvent {
+
+    /// A library finished syncing changes
+    SyncCompleted(SyncEvent),
+
+    /// A library running as a dry run found changes it would sync
+    SyncObserved(SyncEvent),
+
+    /// A library failed to sync
+    SyncFailed(SyncFailureEvent),
+
+    /// A library keeps failing with the same kind of error
+    SyncStillFailing(SyncFailureEvent),
+
+    /// A destination kept failing and syncs to it are paused
+    DestinationUnavailable(DestinationEvent),
+
+    /// A paused destination succeeded again
+    DestinationRecovered(DestinationEvent),
+
+    /// The process panicked
+    Crashed(CrashEvent),
+
+    /// A periodic summary of sync activity
+    Digest(DigestEvent),
+
+    /// A scheduled health check started failing
+    HealthCheckFailed(HealthCheckEvent),
+
+    /// A failing health check passed again
+    HealthCheckRecovered(HealthCheckEvent),
+
+    /// A type added by a later schema version, read without its fields;
+    /// it can't be serialized
+    #[serde(skip)]
+    Unknown,
+}
+
+/// Fields of `sync_completed` and `sync_observed` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncEvent {
+
+    /// Name of the library
+    pub library: String,
+
+    /// Number of changed paths
+    pub count: u64,
+
+    /// Title of the changed media, if they belong to a single one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Season of the changed media, if they belong to a single one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season: Option<u32>,
+
+    /// How long the sync took, e.g. `1m 5s`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+
+    /// Identifier of the sync run, shared with its logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+
+    /// Number of files transferred to the destinations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transferred: Option<u64>,
+
+    /// Number of paths deleted from the destinations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<u64>,
+
+    /// Number of files that were already up to date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<u64>,
+
+    /// Size of the transferred files, e.g. `1.2 MiB`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+}
+
+/// Fields of `sync_failed` and `sync_still_failing` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncFailureEvent {
+
+    /// Name of the library
+    pub library: String,
+
+    /// Why the sync failed
+    pub error: String,
+
+    /// Times the error occurred since it was last reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occurrences: Option<u64>,
+
+    /// Time the occurrences span, e.g. `1h`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+
+    /// How long the sync took, e.g. `1m 5s`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+
+    /// Identifier of the sync run, shared with its logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+/// Fields of `destination_unavailable` and `destination_recovered` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DestinationEvent {
+
+    /// Name of the library
+    pub library: String,
+
+    /// Address of the destination
+    pub destination: String,
+
+    /// Consecutive failures that paused the destination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failures: Option<u64>,
+
+    /// Time until the destination is probed again, e.g. `5m`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown: Option<String>,
+
+    /// Last error of the destination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Fields of `crashed` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashEvent {
+
+    /// Panic message and location
+    pub error: String,
+}
+
+/// Fields of `digest` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestEvent {
+
+    /// Period summarized, e.g. `daily`
+    pub period: String,
+
+    /// Number of syncs in the period
+    pub syncs: u64,
+
+    /// Number of paths added in the period
+    pub added: u64,
+
+    /// Number of failed syncs in the period
+    pub failures: u64,
+
+    /// Paths added per library, in natural order of library names
+    pub libraries: Vec<DigestLibraryEvent>,
+
+    /// Most frequent errors of the period, most frequent first
+    pub top_errors: Vec<DigestErrorEvent>,
+}
+
+/// Growth of one library in a `digest` event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestLibraryEvent {
+
+    /// Name of the library
+    pub library: String,
+
+    /// Number of paths added in the period
+    pub added: u64,
+}
+
+/// A recurring error in a `digest` event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestErrorEvent {
+
+    /// Error message
+    pub error: String,
+
+    /// Number of syncs that failed with it
+    pub count: u64,
+}
+
+/// Fields of `health_check_failed` and `health_check_recovered` events.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckEvent {
+
+    /// Name of the check
+    pub check: String,
+
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl EventEnvelope {
+
+    /// Wraps an event with the current schema version, a new ID and the
+    /// current time.
+    pub fn new(event: OutboundEvent) -> Self {
+        let now = OffsetDateTime::now_utc();
+        let counter = EVENT_COUNTER.fetch_add(1, Ordering::Relaxed);
+        Self {
+            schema_version: EVENT_SCHEMA_VERSION.to_string(),
+            id: format!("{:x}-{:x}-{:x}", now.unix_timestamp_nanos(), process::id(), counter),
+            timestamp: now.format(&Rfc3339).unwrap_or_else(|_| now.unix_timestamp().to_string()),
+            event,
+        }
+    }
+
+    /// Builds the `digest` event of a digest, with its libraries and top
+    /// errors as lists.
+    pub fn from_digest(digest: &Digest) -> Self {
+        Self::new(OutboundEvent::Digest(DigestEvent {
+            period: digest.period.to_string(),
+            syncs: digest.syncs as u64,
+            added: digest.added as u64,
+            failures: digest.failures as u64,
+            libraries: digest.sorted_libraries()
+                .into_iter()
+                .map(|(library, added)| DigestLibraryEvent { library: library.clone(), added: *added as u64 })
+                .collect(),
+            top_errors: digest.top_errors
+                .iter()
+                .map(|(error, count)| DigestErrorEvent { error: error.clone(), count: *count as u64 })
+                .collect(),
+        }))
+    }
+
+    /// Builds the event of a notification from its template variables.
+    ///
+    /// Numbers that don't parse and empty values are left out, like
+    /// missing variables. Template variables only summarize the libraries
+    /// and errors of a digest, so their lists are left empty; use
+    /// [`EventEnvelope::from_digest`] instead.
+    pub fn from_notification(kind: NotificationKind, vars: &[(&str, &str)]) -> Self {
+        let text = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let number = |name: &str| text(name).and_then(|value| value.parse::<u64>().ok());
+
+        let sync = || SyncEvent {
+            library: text("library").unwrap_or_default(),
+            count: number("count").unwrap_or_default(),
+            title: text("title"),
+            season: text("season").and_then(|season| season.parse().ok()),
+            duration: text("duration"),
+            run_id: text("run_id"),
+            transferred: number("transferred"),
+            deleted: number("deleted"),
+            skipped: number("skipped"),
+            size: text("size"),
+        };
+        let failure = || SyncFailureEvent {
+            library: text("library").unwrap_or_default(),
+            error: text("error").unwrap_or_default(),
+            occurrences: number("occurrences"),
+            window: text("window"),
+            duration: text("duration"),
+            run_id: text("run_id"),
+        };
+        let destination = || DestinationEvent {
+            library: text("library").unwrap_or_default(),
+            destination: text("destination").unwrap_or_default(),
+            failures: number("failures"),
+            cooldown: text("cooldown"),
+            error: text("error"),
+        };
+        let health = || HealthCheckEvent {
+            check: text("check").unwrap_or_default(),
+            error: text("error"),
+        };
+
+        Self::new(match kind {
+            NotificationKind::SyncCompleted => OutboundEvent::SyncCompleted(sync()),
+            NotificationKind::SyncObserved => OutboundEvent::SyncObserved(sync()),
+            NotificationKind::SyncFailed => OutboundEvent::SyncFailed(failure()),
+            NotificationKind::SyncStillFailing => OutboundEvent::SyncStillFailing(failure()),
+            NotificationKind::DestinationUnavailable => OutboundEvent::DestinationUnavailable(destination()),
+            NotificationKind::DestinationRecovered => OutboundEvent::DestinationRecovered(destination()),
+            NotificationKind::Crashed => OutboundEvent::Crashed(CrashEvent { error: text("error").unwrap_or_default() }),
+            NotificationKind::Digest => OutboundEvent::Digest(DigestEvent {
+                period: text("period").unwrap_or_default(),
+                syncs: number("syncs").unwrap_or_default(),
+                added: number("added").unwrap_or_default(),
+                failures: number("failures").unwrap_or_default(),
+                ..DigestEvent::default()
+            }),
+            NotificationKind::HealthCheckFailed => OutboundEvent::HealthCheckFailed(health()),
+            NotificationKind::HealthCheckRecovered => OutboundEvent::HealthCheckRecovered(health()),
+        })
+    }
+
+    /// Serializes the event as JSON.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the event can't be serialized.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parses an event, accepting every version with the same major
+    /// version as [`EVENT_SCHEMA_VERSION`].
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the JSON is malformed or follows an
+    /// incompatible schema version.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(json)?;
+        let schema_version = value
+            .get("schema_version")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Event has no schema_version"))?
+            .to_string();
+        let major = |version: &str| version.split('.').next().map(str::to_string);
+        if major(&schema_version) != major(EVENT_SCHEMA_VERSION) {
+            return Err(anyhow!(
+                "Event schema version {} is incompatible with {}",
+                schema_version,
+                EVENT_SCHEMA_VERSION
+            ));
+        }
+
+        let known = value
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| NotificationKind::from_key(kind).is_some());
+        if !known {
+            let text = |name: &str| value.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+            return Ok(Self { schema_version, id: text("id"), timestamp: text("timestamp"), event: OutboundEvent::Unknown });
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    path::{Component, Path},
+    time::Duration
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Matches season markers such as `Season 2`, `season02` or `S02E05`.
+static SEASON_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:\bseason[ ._-]*(\d{1,3})|\bs(\d{1,3})e\d{1,4})").expect("valid season pattern")
+});
+
+/// Media details derived from the paths touched by a sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaInfo {
+
+    /// Show or movie folder shared by every changed path
+    pub title: Option<String>,
+
+    /// Season number, if every changed path with a season marker agrees
+    pub season: Option<u32>,
+}
+
+impl MediaInfo {
+
+    /// Derives media details from paths relative to the library root.
+    ///
+    /// The title is the top-level folder when all paths share it, since
+    /// libraries are laid out as `<Title>/<Season>/<Episode>`.
+    pub fn from_paths(paths: &[String]) -> Self {
+        let mut titles = paths.iter().filter_map(|path| {
+            let mut components = Path::new(path).components().filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            });
+            let first = components.next()?;
+            // A bare file in the library root has no folder to name it
+            components.next().map(|_| first)
+        });
+        let title = titles.next().filter(|first| titles.all(|title| title == *first));
+
+        let mut seasons = paths.iter().filter_map(|path| Self::season_of(path));
+        let season = seasons.next().filter(|first| seasons.all(|season| season == *first));
+
+        Self {
+            title: title.map(str::to_string),
+            season,
+        }
+    }
+
+    /// Extracts the last season marker from a path.
+    fn season_of(path: &str) -> Option<u32> {
+        SEASON_PATTERN
+            .captures_iter(path)
+            .last()
+            .and_then(|captures| captures.get(1).or_else(|| captures.get(2)))
+            .and_then(|number| number.as_str().parse().ok())
+    }
+}
+
+/// Formats a duration for messages, e.g. `1h 2m 5s` or `42s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m {}s", minutes, seconds),
+        _ => format!("{}h {}m {}s", hours, minutes, seconds),
+    }
+}
@@ -0,0 +1,27 @@
+//! User-facing notifications.
+//!
+//! This module turns crate events into notification messages with:
+//! - Built-in English and Chinese templates
+//! - User overrides loaded from a template file
+//! - Per-event MarkdownV2 formats with automatically escaped values
+//! - Delivery through the configured Telegram bot
+//! - Daily or weekly digests of sync activity
+//! - Throttled reminders for errors that keep recurring
+//! - A persistent outbound queue retrying undelivered messages in order
+//! - A versioned JSON schema of events for outbound consumers
+//! 
+pub mod digest;
+pub mod event_schema;
+pub mod media_info;
+pub mod notifier;
+pub mod queue;
+pub mod template;
+pub mod throttle;
+
+pub use digest::*;
+pub use event_schema::*;
+pub use media_info::*;
+pub use notifier::*;
+pub use queue::*;
+pub use template::*;
+pub use throttle::*;
@@ -0,0 +1,156 @@
+use std::thread;
+
+use anyhow::{anyhow, Error};
+
+use crate::{
+    core::{
+        api::TextMessage,
+        client::TelegramClient,
+        config::Config
+    },
+    warn_log
+};
+use super::{
+    queue::{FlushReport, NotificationQueue},
+    template::{NotificationKind, NotificationTemplates}
+};
+
+/// Domain identifier for notification logs
+const NOTIFICATION_LOGGER_DOMAIN: &str = "[NOTIFICATION]";
+
+/// Sends rendered notifications to the configured Telegram chat.
+///
+/// # Examples
+/// ```no_run
+/// use pilipili_strm::core::{
+///     config::Config,
+///     notification::{NotificationKind, Notifier}
+/// };
+///
+/// if let Some(notifier) = Notifier::from_config(&Config::get()) {
+///     notifier.notify(NotificationKind::SyncCompleted, &[("library", "movies"), ("count", "3")])?;
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Notifier {
+
+    /// Templates used to render messages
+    templates: NotificationTemplates,
+}
+
+impl Notifier {
+
+    /// Creates a notifier with the given templates.
+    pub fn new(templates: NotificationTemplates) -> Self {
+        Self { templates }
+    }
+
+    /// Creates a notifier from the configuration.
+    ///
+    /// Falls back to the built-in templates if the template file is broken,
+    /// so a bad override never silences notifications.
+    ///
+    /// # Returns
+    /// `None` if no Telegram bot is configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.telegram.bot_token.is_empty() {
+            return None;
+        }
+
+        let templates = NotificationTemplates::from_config(&config.notification)
+            .unwrap_or_else(|e| {
+                warn_log!(
+                    NOTIFICATION_LOGGER_DOMAIN,
+                    format!("{:#}, using built-in templates", e)
+                );
+                NotificationTemplates::builtin(config.notification.language)
+            });
+        Some(Self::new(templates))
+    }
+
+    /// Returns the templates used by the notifier.
+    pub fn templates(&self) -> &NotificationTemplates {
+        &self.templates
+    }
+
+    /// Renders a notification as escaped MarkdownV2 text.
+    pub fn message(&self, kind: NotificationKind, vars: &[(&str, &str)]) -> String {
+        self.templates.render_markdown(kind, vars)
+    }
+
+    /// Renders and sends a notification, blocking until it is delivered.
+    ///
+    /// The message goes through the [`NotificationQueue`], after any older
+    /// message still waiting there, so an undelivered message is retried by
+    /// later notifications and [`Notifier::retry`]. If the queue can't be
+    /// written, the message is sent directly.
+    ///
+    /// Sends from a dedicated thread with its own runtime, so it can be
+    /// called from watcher threads and async contexts alike. Panic hooks
+    /// should use [`Notifier::enqueue`] instead.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the message couldn't be sent yet.
+    pub fn notify(&self, kind: NotificationKind, vars: &[(&str, &str)]) -> Result<(), Error> {
+        let text = self.message(kind, vars);
+        let queue = NotificationQueue::new(NotificationQueue::default_path());
+        let id = match queue.enqueue(Config::get().telegram.chat_id.clone(), text.as_str()) {
+            Ok(id) => id,
+            Err(e) => {
+                warn_log!(NOTIFICATION_LOGGER_DOMAIN, format!("{:#}, sending without queue", e));
+                return Self::block_on(async move {
+                    TelegramClient::builder().build().send_message(TextMessage::new(text)).await?;
+                    Ok(())
+                });
+            }
+        };
+
+        let flushing = queue.clone();
+        let report = Self::block_on(async move {
+            flushing.flush(&TelegramClient::builder().build()).await
+        })?;
+        if queue.contains(id) {
+            return Err(anyhow!("Notification queued for retry, {} messages pending", report.pending));
+        }
+        Ok(())
+    }
+
+    /// Renders a notification and appends it to the [`NotificationQueue`]
+    /// without sending it.
+    ///
+    /// The message is delivered by the next flush, such as the periodic
+    /// [`Notifier::retry`] or the next run. Suited to panic hooks, which
+    /// shouldn't wait on the network.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the queue file can't be read or written.
+    pub fn enqueue(&self, kind: NotificationKind, vars: &[(&str, &str)]) -> Result<u64, Error> {
+        let text = self.message(kind, vars);
+        NotificationQueue::new(NotificationQueue::default_path())
+            .enqueue(Config::get().telegram.chat_id.clone(), text)
+    }
+
+    /// Sends the notifications still waiting in the queue, blocking until done.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the queue can't be read or written.
+    pub fn retry() -> Result<FlushReport, Error> {
+        let queue = NotificationQueue::new(NotificationQueue::default_path());
+        Self::block_on(async move { queue.flush(&TelegramClient::builder().build()).await })
+    }
+
+    /// Runs a future to completion on a dedicated thread with its own runtime.
+    fn block_on<T: Send + 'static>(
+        future: impl Future<Output = Result<T, Error>> + Send + 'static,
+    ) -> Result<T, Error> {
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(future)
+        })
+        .join()
+        .map_err(|_| anyhow!("Notification thread panicked"))?
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration
+};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        api::TextMessage,
+        client::TelegramClient,
+        config::Config,
+        library::state_file::{load_state, lock_state, save_state}
+    },
+    debug_log,
+    warn_log
+};
+
+/// Domain identifier for notification queue logs
+const NOTIFICATION_QUEUE_LOGGER_DOMAIN: &str = "[NOTIFICATION-QUEUE]";
+
+/// File name of the persisted queue inside the state directory.
+const NOTIFICATION_QUEUE_FILE_NAME: &str = "notification_queue.json";
+
+/// Extension of the file locked while a process flushes the queue.
+const NOTIFICATION_QUEUE_FLUSH_EXTENSION: &str = "flush";
+
+/// Name of the gauge reporting the number of unsent notifications.
+pub const NOTIFICATION_QUEUE_DEPTH_METRIC: &str = "pilipili_strm.notification.queue_depth";
+
+/// Interval at which unsent notifications are retried while watching.
+pub const NOTIFICATION_QUEUE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Serializes reads and writes of queue files within the process.
+static NOTIFICATION_QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes flushes within the process, so a message is never sent twice
+/// concurrently. Other processes are kept out by a lock file.
+static NOTIFICATION_FLUSH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// A notification waiting to be delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedMessage {
+
+    /// Identifier of the message within the queue
+    pub id: u64,
+
+    /// Chat the message is sent to
+    pub chat_id: String,
+
+    /// MarkdownV2 text of the message
+    pub text: String,
+
+    /// Number of failed delivery attempts
+    pub attempts: u32,
+
+    /// Error of the last failed attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Persisted content of the queue.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct QueueState {
+
+    /// Identifier given to the next message
+    next_id: u64,
+
+    /// Unsent messages, oldest first
+    messages: VecDeque<QueuedMessage>,
+}
+
+/// Outcome of [`NotificationQueue::flush`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlushReport {
+
+    /// Messages delivered
+    pub sent: usize,
+
+    /// Messages Telegram rejected for good, removed from the queue
+    pub dropped: usize,
+
+    /// Messages still waiting for a later attempt
+    pub pending: usize,
+}
+
+impl Display for FlushReport {
+
+    /// Formats the report as a one-line summary.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} sent, {} dropped, {} pending", self.sent, self.dropped, self.pending)
+    }
+}
+
+/// Outbound notifications persisted until Telegram accepts them.
+///
+/// Messages are appended to a JSON file in the state directory before
+/// being sent, so alerts raised while Telegram is unreachable survive
+/// restarts and are delivered by a later flush. Messages of a chat are
+/// delivered in the order they were queued: once one fails, the
+/// following messages of that chat wait for the next flush. Messages
+/// Telegram rejects for good, such as malformed MarkdownV2, are dropped
+/// so they don't hold back the queue forever.
+///
+/// The queue file is locked while it is changed and while it is flushed,
+/// so several processes sharing a state directory, e.g. `watch` and a
+/// `sync` started by hand, neither lose messages nor send them twice.
+#[derive(Debug, Clone)]
+pub struct NotificationQueue {
+
+    /// Path of the queue file
+    path: PathBuf,
+}
+
+impl NotificationQueue {
+
+    /// Creates a queue persisted at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the default location of the queue file.
+    pub fn default_path() -> PathBuf {
+        Config::get().state_dir().join(NOTIFICATION_QUEUE_FILE_NAME)
+    }
+
+    /// Returns the path of the queue file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the unsent messages, oldest first.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the queue file can't be read.
+    pub fn pending(&self) -> Result<Vec<QueuedMessage>, Error> {
+        Ok(self.update(|_| ())?.messages.into())
+    }
+
+    /// Returns the number of unsent messages, `0` if the queue can't be read.
+    pub fn depth(&self) -> usize {
+        self.pending().map_or(0, |messages| messages.len())
+    }
+
+    /// Appends a message to the queue.
+    ///
+    /// # Returns
+    /// The identifier of the queued message.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the queue file can't be read or written.
+    pub fn enqueue(&self, chat_id: impl Into<String>, text: impl Into<String>) -> Result<u64, Error> {
+        let mut id = 0;
+        self.update(|state| {
+            id = state.next_id;
+            state.next_id += 1;
+            state.messages.push_back(QueuedMessage {
+                id,
+                chat_id: chat_id.into(),
+                text: text.into(),
+                attempts: 0,
+                last_error: None,
+            });
+        })?;
+        Ok(id)
+    }
+
+    /// Returns `true` if a message is still waiting to be delivered.
+    pub fn contains(&self, id: u64) -> bool {
+        self.pending().is_ok_and(|messages| messages.iter().any(|message| message.id == id))
+    }
+
+    /// Sends the queued messages in order.
+    ///
+    /// Network errors, rate limiting and server errors keep the message
+    /// for a later flush and hold back the following messages of its chat.
+    /// The queue file is updated after each message, so a crash mid-flush
+    /// resends at most one message.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the queue file can't be read or written.
+    pub async fn flush(&self, client: &TelegramClient) -> Result<FlushReport, Error> {
+        let _flushing = NOTIFICATION_FLUSH_LOCK.lock().await;
+        let flush_path = self.path.with_extension(NOTIFICATION_QUEUE_FLUSH_EXTENSION);
+        let _flushing_process = tokio::task::spawn_blocking(move || lock_state(&flush_path)).await??;
+        let mut report = FlushReport::default();
+        let mut blocked_chats = HashSet::new();
+
+        for message in self.pending()? {
+            if blocked_chats.contains(&message.chat_id) {
+                report.pending += 1;
+                continue;
+            }
+
+            let text = TextMessage::new(message.text.as_str()).with_chat_id(message.chat_id.as_str());
+            let error = match client.send_message(text).await {
+                Ok(response) if response.ok => {
+                    report.sent += 1;
+                    None
+                }
+                Ok(response) if !response.is_retryable() => {
+                    warn_log!(
+                        NOTIFICATION_QUEUE_LOGGER_DOMAIN,
+                        format!("Dropping notification {} rejected by Telegram: {}", message.id, response)
+                    );
+                    report.dropped += 1;
+                    None
+                }
+                Ok(response) => Some(response.to_string()),
+                Err(e) => Some(format!("{:#}", e)),
+            };
+
+            let Some(error) = error else {
+                self.update(|state| state.messages.retain(|queued| queued.id != message.id))?;
+                continue;
+            };
+            debug_log!(
+                NOTIFICATION_QUEUE_LOGGER_DOMAIN,
+                format!("Notification {} not sent, retrying later: {}", message.id, error)
+            );
+            self.update(|state| {
+                if let Some(queued) = state.messages.iter_mut().find(|queued| queued.id == message.id) {
+                    queued.attempts += 1;
+                    queued.last_error = Some(error);
+                }
+            })?;
+            blocked_chats.insert(message.chat_id);
+            report.pending += 1;
+        }
+
+        debug_log!(NOTIFICATION_QUEUE_LOGGER_DOMAIN, format!("Flushed notification queue: {}", report));
+        Ok(report)
+    }
+
+    /// Applies a change to the persisted queue.
+    ///
+    /// # Returns
+    /// The queue after the change.
+    fn update(&self, change: impl FnOnce(&mut QueueState)) -> Result<QueueState, Error> {
+        let _guard = NOTIFICATION_QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let _locked = lock_state(&self.path)?;
+        let mut state: QueueState = load_state(&self.path)
+            .with_context(|| format!("Failed to load notification queue {}", self.path.display()))?;
+        let before = state.clone();
+        change(&mut state);
+        if state != before {
+            save_state(&state, &self.path)
+                .with_context(|| format!("Failed to save notification queue {}", self.path.display()))?;
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    fs,
+    path::Path
+};
+
+use anyhow::{Context, Error};
+
+use crate::core::{
+    client::MarkdownV2Builder,
+    config::{NotificationConfig, NotificationLanguage}
+};
+
+/// Event that can trigger a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NotificationKind {
+
+    /// A library finished syncing changes
+    /// (`{library}`, `{count}`, `{title}`, `{season}`, `{duration}`, `{run_id}`,
+    /// `{transferred}`, `{deleted}`, `{skipped}`, `{size}`)
+    SyncCompleted,
+
+    /// A library running as a dry run found changes it would sync
+    /// (`{library}`, `{count}`, `{title}`, `{season}`, `{duration}`, `{run_id}`,
+    /// `{transferred}`, `{deleted}`, `{skipped}`, `{size}`)
+    SyncObserved,
+
+    /// A library failed to sync (`{library}`, `{error}`, `{duration}`, `{run_id}`)
+    SyncFailed,
+
+    /// A library keeps failing with the same kind of error
+    /// (`{library}`, `{error}`, `{occurrences}`, `{window}`, `{duration}`, `{run_id}`)
+    SyncStillFailing,
+
+    /// A destination kept failing and syncs to it are paused
+    /// (`{library}`, `{destination}`, `{failures}`, `{cooldown}`, `{error}`)
+    DestinationUnavailable,
+
+    /// A paused destination succeeded again (`{library}`, `{destination}`)
+    DestinationRecovered,
+
+    /// The process panicked (`{error}`)
+    Crashed,
+
+    /// A periodic summary of sync activity
+    /// (`{period}`, `{syncs}`, `{added}`, `{failures}`, `{libraries}`, `{top_errors}`)
+    Digest,
+
+    /// A scheduled health check started failing (`{check}`, `{error}`)
+    HealthCheckFailed,
+
+    /// A failing health check passed again (`{check}`)
+    HealthCheckRecovered,
+}
+
+impl NotificationKind {
+
+    /// Every notification kind, in declaration order.
+    pub const ALL: [NotificationKind; 10] = [
+        NotificationKind::SyncCompleted,
+        NotificationKind::SyncObserved,
+        NotificationKind::SyncFailed,
+        NotificationKind::SyncStillFailing,
+        NotificationKind::DestinationUnavailable,
+        NotificationKind::DestinationRecovered,
+        NotificationKind::Crashed,
+        NotificationKind::Digest,
+        NotificationKind::HealthCheckFailed,
+        NotificationKind::HealthCheckRecovered,
+    ];
+
+    /// Returns the key identifying the kind in template files.
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotificationKind::SyncCompleted => "sync_completed",
+            NotificationKind::SyncObserved => "sync_observed",
+            NotificationKind::SyncFailed => "sync_failed",
+            NotificationKind::SyncStillFailing => "sync_still_failing",
+            NotificationKind::DestinationUnavailable => "destination_unavailable",
+            NotificationKind::DestinationRecovered => "destination_recovered",
+            NotificationKind::Crashed => "crashed",
+            NotificationKind::Digest => "digest",
+            NotificationKind::HealthCheckFailed => "health_check_failed",
+            NotificationKind::HealthCheckRecovered => "health_check_recovered",
+        }
+    }
+
+    /// Looks up a notification kind by its template key.
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    /// Returns the built-in template for a language.
+    fn builtin(&self, language: NotificationLanguage) -> &'static str {
+        match (self, language) {
+            (NotificationKind::SyncCompleted, NotificationLanguage::En) => "Library '{library}' synced {count} changes",
+            (NotificationKind::SyncCompleted, NotificationLanguage::Zh) => "媒体库「{library}」已同步 {count} 项变更",
+            (NotificationKind::SyncObserved, NotificationLanguage::En) => "Library '{library}' would sync {count} changes (dry run)",
+            (NotificationKind::SyncObserved, NotificationLanguage::Zh) => "媒体库「{library}」将同步 {count} 项变更（演练模式）",
+            (NotificationKind::SyncFailed, NotificationLanguage::En) => "Library '{library}' failed to sync: {error}",
+            (NotificationKind::SyncFailed, NotificationLanguage::Zh) => "媒体库「{library}」同步失败：{error}",
+            (NotificationKind::SyncStillFailing, NotificationLanguage::En) => "Library '{library}' is still failing, {occurrences} occurrences in the last {window}: {error}",
+            (NotificationKind::SyncStillFailing, NotificationLanguage::Zh) => "媒体库「{library}」仍在失败，过去 {window} 内发生 {occurrences} 次：{error}",
+            (NotificationKind::DestinationUnavailable, NotificationLanguage::En) => "Destination {destination} of library '{library}' failed {failures} times in a row, pausing syncs to it for {cooldown}: {error}",
+            (NotificationKind::DestinationUnavailable, NotificationLanguage::Zh) => "媒体库「{library}」的目标 {destination} 已连续失败 {failures} 次，暂停同步 {cooldown}：{error}",
+            (NotificationKind::DestinationRecovered, NotificationLanguage::En) => "Destination {destination} of library '{library}' is reachable again",
+            (NotificationKind::DestinationRecovered, NotificationLanguage::Zh) => "媒体库「{library}」的目标 {destination} 已恢复",
+            (NotificationKind::Crashed, NotificationLanguage::En) => "pilipili_strm crashed: {error}",
+            (NotificationKind::Crashed, NotificationLanguage::Zh) => "pilipili_strm 发生崩溃：{error}",
+            (NotificationKind::Digest, NotificationLanguage::En) => "Sync digest ({period}): {syncs} syncs, {added} items added, {failures} failures\nLibraries: {libraries}\nTop errors: {top_errors}",
+            (NotificationKind::Digest, NotificationLanguage::Zh) => "同步摘要（{period}）：同步 {syncs} 次，新增 {added} 项，失败 {failures} 次\n媒体库：{libraries}\n主要错误：{top_errors}",
+            (NotificationKind::HealthCheckFailed, NotificationLanguage::En) => "Health check {check} failed: {error}",
+            (NotificationKind::HealthCheckFailed, NotificationLanguage::Zh) => "健康检查 {check} 失败：{error}",
+            (NotificationKind::HealthCheckRecovered, NotificationLanguage::En) => "Health check {check} passes again",
+            (NotificationKind::HealthCheckRecovered, NotificationLanguage::Zh) => "健康检查 {check} 已恢复",
+        }
+    }
+}
+
+impl Display for NotificationKind {
+
+    /// Formats the kind as its template key.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// Syntax of the literal text of a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateSyntax {
+
+    /// Plain text, escaped as a whole when sent as MarkdownV2
+    Plain,
+
+    /// MarkdownV2 written by the user, only placeholder values are escaped
+    MarkdownV2,
+}
+
+/// A single message template.
+#[derive(Debug, Clone)]
+struct Template {
+
+    /// Template text with `{name}` placeholders
+    text: String,
+
+    /// Syntax of the text around the placeholders
+    syntax: TemplateSyntax,
+}
+
+/// Message templates for every notification kind.
+///
+/// Templates use `{name}` placeholders. Placeholders without a value are
+/// kept as written, so a typo stays visible in the message instead of
+/// silently disappearing. Built-in and file templates are plain text,
+/// while `formats` from the configuration are MarkdownV2.
+#[derive(Debug, Clone)]
+pub struct NotificationTemplates {
+
+    /// Templates keyed by notification kind
+    templates: HashMap<NotificationKind, Template>,
+}
+
+impl NotificationTemplates {
+
+    /// Creates the built-in templates for a language.
+    pub fn builtin(language: NotificationLanguage) -> Self {
+        let templates = NotificationKind::ALL
+            .iter()
+            .map(|kind| (*kind, Template {
+                text: kind.builtin(language).to_string(),
+                syntax: TemplateSyntax::Plain,
+            }))
+            .collect();
+        Self { templates }
+    }
+
+    /// Creates templates from the notification configuration.
+    ///
+    /// Starts from the built-in templates of the configured language,
+    /// applies the overrides from `templates_file`, if set, and finally
+    /// the per-event `formats`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the template file can't be read or parsed,
+    /// or a format names an unknown notification kind.
+    pub fn from_config(config: &NotificationConfig) -> Result<Self, Error> {
+        let mut templates = Self::builtin(config.language);
+        if let Some(path) = &config.templates_file {
+            templates.load_overrides(Path::new(path))?;
+        }
+        for (key, format) in &config.formats {
+            let kind = NotificationKind::from_key(key)
+                .with_context(|| format!("Unknown notification format '{}'", key))?;
+            templates.set(kind, format, TemplateSyntax::MarkdownV2);
+        }
+        Ok(templates)
+    }
+
+    /// Applies template overrides from a TOML file of `key = "template"` pairs.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read, isn't valid TOML,
+    /// or contains a key that isn't a notification kind.
+    pub fn load_overrides(&mut self, path: &Path) -> Result<(), Error> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read notification templates {}", path.display()))?;
+        let overrides: HashMap<String, String> = toml::from_str(&content)
+            .with_context(|| format!("Invalid notification templates {}", path.display()))?;
+
+        for (key, template) in overrides {
+            let kind = NotificationKind::from_key(&key)
+                .with_context(|| format!("Unknown notification template '{}' in {}", key, path.display()))?;
+            self.set(kind, &template, TemplateSyntax::Plain);
+        }
+        Ok(())
+    }
+
+    /// Replaces the template for a notification kind.
+    pub fn set(&mut self, kind: NotificationKind, text: &str, syntax: TemplateSyntax) {
+        self.templates.insert(kind, Template { text: text.to_string(), syntax });
+    }
+
+    /// Returns the template text for a notification kind.
+    pub fn template(&self, kind: NotificationKind) -> &str {
+        &self.templates[&kind].text
+    }
+
+    /// Returns the syntax of the template for a notification kind.
+    pub fn syntax(&self, kind: NotificationKind) -> TemplateSyntax {
+        self.templates[&kind].syntax
+    }
+
+    /// Renders the template of a notification kind with the values inserted as-is.
+    ///
+    /// # Arguments
+    /// * `kind` - Notification to render
+    /// * `vars` - Placeholder names and their values
+    pub fn render(&self, kind: NotificationKind, vars: &[(&str, &str)]) -> String {
+        render_template(self.template(kind), vars)
+    }
+
+    /// Renders the template of a notification kind as MarkdownV2.
+    ///
+    /// Plain templates are escaped as a whole. MarkdownV2 templates keep
+    /// their formatting and only the inserted values are escaped, so
+    /// titles and error messages can never break the markup.
+    pub fn render_markdown(&self, kind: NotificationKind, vars: &[(&str, &str)]) -> String {
+        match self.syntax(kind) {
+            TemplateSyntax::Plain => MarkdownV2Builder::new()
+                .text(&self.render(kind, vars))
+                .build(),
+            TemplateSyntax::MarkdownV2 => {
+                let escaped: Vec<(&str, String)> = vars
+                    .iter()
+                    .map(|(name, value)| (*name, MarkdownV2Builder::escape(value)))
+                    .collect();
+                let escaped: Vec<(&str, &str)> = escaped
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_str()))
+                    .collect();
+                render_template(self.template(kind), &escaped)
+            }
+        }
+    }
+}
+
+/// Replaces `{name}` placeholders in a template with their values.
+///
+/// Unknown placeholders and unmatched braces are kept as written.
+pub fn render_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| vars.iter().find(|(name, _)| *name == &after[..end]).map(|(_, value)| (end, value)));
+        match value {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
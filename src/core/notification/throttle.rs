@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant}
+};
+
+use crate::infrastructure::error::{ErrorHint, RetryClass};
+
+/// Default quiet periods after which a repeated error is reported again,
+/// each one used once before moving on to the next.
+const THROTTLE_DEFAULT_INTERVALS: [Duration; 4] = [
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(4 * 60 * 60),
+];
+
+/// What to do with an error reported to an [`ErrorThrottle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleDecision {
+
+    /// The error is new and should be reported as is
+    Notify,
+
+    /// The error kept recurring and a reminder is due
+    NotifyRepeated {
+
+        /// Number of occurrences since the last notification, including this one
+        occurrences: usize,
+
+        /// Time since the last notification
+        window: Duration,
+    },
+
+    /// The error was reported recently and should not be sent again yet
+    Suppress,
+}
+
+/// Tracking of the error currently repeating for one key.
+#[derive(Debug, Clone)]
+struct RepeatedError {
+
+    /// Class of the repeating error, see [`ErrorThrottle::error_class`]
+    class: String,
+
+    /// When the error was last notified
+    notified_at: Instant,
+
+    /// Occurrences since the last notification
+    occurrences: usize,
+
+    /// Number of reminders sent, selecting the next interval
+    reminders: usize,
+}
+
+/// Collapses notifications of errors of the same class that keep recurring.
+///
+/// The first occurrence of an error is reported right away. Further
+/// occurrences of an error of the same class are only counted, and a reminder with
+/// the count is due once a quiet period has passed. Quiet periods grow
+/// with every reminder (5 minutes, 15 minutes, 1 hour, then every 4 hours
+/// by default), so an error firing every debounce cycle costs a handful
+/// of messages a day instead of hundreds. An error of another class, or clearing
+/// the key after a success, starts over.
+#[derive(Debug, Clone)]
+pub struct ErrorThrottle {
+
+    /// Quiet periods before each reminder, the last one repeating
+    intervals: Vec<Duration>,
+
+    /// Repeating error per key
+    errors: HashMap<String, RepeatedError>,
+}
+
+impl Default for ErrorThrottle {
+
+    /// Creates a throttle with the default escalating intervals.
+    fn default() -> Self {
+        Self {
+            intervals: THROTTLE_DEFAULT_INTERVALS.to_vec(),
+            errors: HashMap::new(),
+        }
+    }
+}
+
+impl ErrorThrottle {
+
+    /// Creates a throttle with the default escalating intervals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the quiet periods before each reminder.
+    ///
+    /// The last interval repeats for every further reminder. An empty list
+    /// keeps the defaults.
+    pub fn with_intervals(mut self, intervals: Vec<Duration>) -> Self {
+        if !intervals.is_empty() {
+            self.intervals = intervals;
+        }
+        self
+    }
+
+    /// Records an occurrence of `error` for `key` and decides whether to report it.
+    ///
+    /// Errors are compared by class rather than by message, so failures
+    /// that only differ in a path or a count are collapsed together.
+    pub fn record(&mut self, key: &str, error: &str, now: Instant) -> ThrottleDecision {
+        let class = Self::error_class(error);
+        let repeated = match self.errors.get_mut(key) {
+            Some(repeated) if repeated.class == class => repeated,
+            _ => {
+                self.errors.insert(key.to_string(), RepeatedError {
+                    class,
+                    notified_at: now,
+                    occurrences: 0,
+                    reminders: 0,
+                });
+                return ThrottleDecision::Notify;
+            }
+        };
+
+        repeated.occurrences += 1;
+        let interval = self.intervals[repeated.reminders.min(self.intervals.len() - 1)];
+        let window = now.saturating_duration_since(repeated.notified_at);
+        if window < interval {
+            return ThrottleDecision::Suppress;
+        }
+
+        let occurrences = repeated.occurrences;
+        repeated.notified_at = now;
+        repeated.occurrences = 0;
+        repeated.reminders += 1;
+        ThrottleDecision::NotifyRepeated { occurrences, window }
+    }
+
+    /// Forgets the repeating error of `key`, typically after a success.
+    ///
+    /// # Returns
+    /// `true` if an error was being tracked.
+    pub fn clear(&mut self, key: &str) -> bool {
+        self.errors.remove(key).is_some()
+    }
+
+    /// Returns the class errors are collapsed by.
+    ///
+    /// This is the error's hint, or its retry class for transient failures.
+    /// Other errors are classed by their message with numbers and paths
+    /// masked out.
+    pub fn error_class(error: &str) -> String {
+        if let Some(hint) = ErrorHint::classify_message(error) {
+            return format!("hint:{:?}", hint);
+        }
+        if let Some(class) = RetryClass::classify_message(error) {
+            return format!("retry:{}", class);
+        }
+
+        error
+            .split_whitespace()
+            .map(|word| {
+                if word.contains('/') || word.contains('\\') {
+                    "<path>".to_string()
+                } else {
+                    word.chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).collect()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
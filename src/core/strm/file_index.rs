@@ -0,0 +1,215 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    core::library::state_file::{load_state, save_state},
+    infrastructure::fs::ScannedFile
+};
+
+/// Bytes hashed at the start and at the end of a file.
+const FILE_INDEX_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// What the index knows about a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// Last modification time in milliseconds since the Unix epoch
+    pub modified_ms: Option<u64>,
+
+    /// Hex SHA-256 of the size and the first and last 64 KiB
+    pub hash: String,
+}
+
+/// Source files that changed since they were indexed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexDiff {
+
+    /// Files that are new or whose content changed
+    pub changed: Vec<PathBuf>,
+
+    /// Indexed files that no longer exist
+    pub removed: Vec<PathBuf>,
+
+    /// Number of files whose content is unchanged
+    pub unchanged: usize,
+
+    /// Entries to record once the changes are processed
+    updates: Vec<(String, IndexedFile)>,
+}
+
+impl IndexDiff {
+
+    /// Returns `true` if no file changed or was removed.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Size, modification time and hash of every source file processed by an
+/// incremental generation, kept between runs.
+///
+/// A file whose size and modification time match its entry is unchanged
+/// without being read. Otherwise its hash is compared, so a file that was
+/// only touched, e.g. by a backup restoring it, isn't processed again.
+/// Media files can be huge and live on slow mounts, so the hash covers
+/// the size and the first and last 64 KiB rather than the whole content.
+///
+/// The index also holds a hash of the settings the files were processed
+/// with, so a generation with other settings processes every file again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileIndex {
+
+    /// Indexed files, keyed by path relative to the source
+    files: BTreeMap<String, IndexedFile>,
+
+    /// Hash of the settings the files were processed with, `None` if unknown
+    #[serde(default)]
+    settings: Option<String>,
+
+    /// True once an entry changed since the last load or save
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl FileIndex {
+
+    /// Loads an index, returning an empty one if the file doesn't exist.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        load_state(path).with_context(|| format!("Failed to load file index {}", path.display()))
+    }
+
+    /// Writes the index if it changed since it was loaded.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn save(&mut self, path: &Path) -> Result<(), Error> {
+        if !self.changed {
+            return Ok(());
+        }
+        save_state(self, path).with_context(|| format!("Failed to save file index {}", path.display()))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Compares scanned files with the index, leaving it untouched.
+    ///
+    /// # Arguments
+    /// * `root` - Directory the files were scanned from
+    /// * `files` - Every file below `root`
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file that may have changed can't be read.
+    pub fn diff(&self, root: &Path, files: &[ScannedFile]) -> Result<IndexDiff, Error> {
+        let mut diff = IndexDiff::default();
+        let mut seen = HashSet::new();
+        for file in files {
+            let key = Self::key(&file.relative);
+            let modified_ms = file.modified.and_then(Self::millis);
+            let indexed = self.files.get(&key);
+            seen.insert(key.clone());
+            if indexed.is_some_and(|indexed| indexed.size == file.size && indexed.modified_ms == modified_ms) {
+                diff.unchanged += 1;
+                continue;
+            }
+
+            let hash = Self::fingerprint(&root.join(&file.relative), file.size)?;
+            if indexed.is_some_and(|indexed| indexed.hash == hash) {
+                diff.unchanged += 1;
+            } else {
+                diff.changed.push(file.relative.clone());
+            }
+            diff.updates.push((key, IndexedFile { size: file.size, modified_ms, hash }));
+        }
+        diff.removed = self.files
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .map(PathBuf::from)
+            .collect();
+        Ok(diff)
+    }
+
+    /// Records the changes of a diff once they are processed.
+    pub fn apply(&mut self, diff: IndexDiff) {
+        for removed in &diff.removed {
+            self.changed |= self.files.remove(&Self::key(removed)).is_some();
+        }
+        for (key, file) in diff.updates {
+            self.files.insert(key, file);
+            self.changed = true;
+        }
+    }
+
+    /// Returns the hash of the settings the files were processed with.
+    pub fn settings(&self) -> Option<&str> {
+        self.settings.as_deref()
+    }
+
+    /// Records the hash of the settings the files were processed with.
+    pub fn set_settings(&mut self, settings: impl Into<String>) {
+        let settings = settings.into();
+        if self.settings.as_deref() != Some(settings.as_str()) {
+            self.settings = Some(settings);
+            self.changed = true;
+        }
+    }
+
+    /// Returns the entry of a file, given its path relative to the source.
+    pub fn get(&self, relative: &Path) -> Option<&IndexedFile> {
+        self.files.get(&Self::key(relative))
+    }
+
+    /// Returns the number of indexed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if no file is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the hex SHA-256 of a file's size and its first and last 64 KiB.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read.
+    pub fn fingerprint(path: &Path, size: u64) -> Result<String, Error> {
+        let mut file = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut hasher = Sha256::new();
+        hasher.update(size.to_le_bytes());
+
+        let mut sample = Vec::new();
+        (&mut file).take(FILE_INDEX_SAMPLE_BYTES).read_to_end(&mut sample)?;
+        if size > FILE_INDEX_SAMPLE_BYTES {
+            file.seek(SeekFrom::Start(size.saturating_sub(FILE_INDEX_SAMPLE_BYTES).max(FILE_INDEX_SAMPLE_BYTES)))?;
+            file.take(FILE_INDEX_SAMPLE_BYTES).read_to_end(&mut sample)?;
+        }
+        hasher.update(&sample);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Returns a time in milliseconds since the Unix epoch.
+    fn millis(time: SystemTime) -> Option<u64> {
+        let elapsed = time.duration_since(UNIX_EPOCH).ok()?;
+        u64::try_from(elapsed.as_millis()).ok()
+    }
+
+    /// Returns the key of a file, with `/` separators on every platform.
+    fn key(relative: &Path) -> String {
+        relative.to_string_lossy().replace('\\', "/")
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf}
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::strm_generator::STRM_EXTENSION;
+
+/// Matches part markers at the end of a file stem, such as `Movie CD1`,
+/// `Movie.part2` or `Movie - Disc 3`. The marker must follow a separator,
+/// so stems like `Apt2` or `Concept 2` aren't parts.
+static PART_STEM_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(.*?)[ ._-]+(?:cd|dvd|part|pt|disc|disk)[ ._-]*(\d{1,2})$").expect("valid part pattern")
+});
+
+/// Matches folders holding one part of a release, such as `CD1` or `Disc 2`.
+static PART_DIR_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:cd|dvd|part|pt|disc|disk)[ ._-]*(\d{1,2})$").expect("valid part folder pattern")
+});
+
+/// Disc structure copied from an optical disc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscFormat {
+
+    /// Blu-ray, stored below a `BDMV` folder
+    BluRay,
+
+    /// DVD, stored below a `VIDEO_TS` folder
+    Dvd,
+}
+
+impl DiscFormat {
+
+    /// Returns the format whose structure folder has this name, ignoring case.
+    pub fn from_dir_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("BDMV") {
+            Some(DiscFormat::BluRay)
+        } else if name.eq_ignore_ascii_case("VIDEO_TS") {
+            Some(DiscFormat::Dvd)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name of the structure folder.
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            DiscFormat::BluRay => "BDMV",
+            DiscFormat::Dvd => "VIDEO_TS",
+        }
+    }
+
+    /// Returns the file players open to play the disc.
+    pub fn index_file(&self) -> &'static str {
+        match self {
+            DiscFormat::BluRay => "index.bdmv",
+            DiscFormat::Dvd => "VIDEO_TS.IFO",
+        }
+    }
+
+    /// Returns the folder holding the disc structure a file belongs to,
+    /// and its format.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of a file, e.g. `Movie/BDMV/STREAM/00001.m2ts`
+    pub fn root_of(relative: &Path) -> Option<(PathBuf, DiscFormat)> {
+        let mut root = PathBuf::new();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            // The file itself is never the structure folder
+            components.peek()?;
+            if let Some(format) = component.as_os_str().to_str().and_then(Self::from_dir_name) {
+                return Some((root, format));
+            }
+            root.push(component);
+        }
+        None
+    }
+}
+
+/// How the media of a title is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaTitleKind {
+
+    /// A single media file
+    Single,
+
+    /// A folder copied from a disc, played from its index file
+    Disc(DiscFormat),
+
+    /// Media files split into numbered parts, played one after another
+    MultiPart,
+}
+
+/// A movie or episode, which may be stored as several files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaTitle {
+
+    /// Path of the title's `.strm` file, relative to the target
+    pub strm: PathBuf,
+
+    /// Media files to play, relative to the source, in playback order
+    pub parts: Vec<PathBuf>,
+
+    /// How the media is stored
+    pub kind: MediaTitleKind,
+}
+
+impl MediaTitle {
+
+    /// Creates the title of a single media file.
+    pub fn single(relative: &Path) -> Self {
+        Self {
+            strm: relative.with_extension(STRM_EXTENSION),
+            parts: vec![relative.to_path_buf()],
+            kind: MediaTitleKind::Single,
+        }
+    }
+
+    /// Groups files into titles.
+    ///
+    /// Files below a `BDMV` or `VIDEO_TS` folder form one title named
+    /// after the folder holding the disc structure, e.g.
+    /// `Movie/BDMV/STREAM/00001.m2ts` gives `Movie/Movie.strm` playing
+    /// `Movie/BDMV/index.bdmv`. Media files marked as parts, either in
+    /// their name (`Movie.cd1.avi`, `Movie - part2.mkv`) or their folder
+    /// (`Movie/CD1/movie.avi`), form one title per release once at least
+    /// two distinct parts are found. Every other media file is a title of
+    /// its own.
+    ///
+    /// # Arguments
+    /// * `files` - Paths relative to the source
+    /// * `is_media` - Returns `true` for media files
+    ///
+    /// # Returns
+    /// The titles, sorted by `.strm` path.
+    pub fn group(files: &[PathBuf], is_media: impl Fn(&Path) -> bool) -> Vec<MediaTitle> {
+        let mut discs: BTreeMap<PathBuf, DiscFormat> = BTreeMap::new();
+        let mut stacks: BTreeMap<PathBuf, Vec<(u32, PathBuf)>> = BTreeMap::new();
+        let mut titles = Vec::new();
+
+        for relative in files {
+            if let Some((root, format)) = DiscFormat::root_of(relative) {
+                discs.insert(root, format);
+            } else if !is_media(relative) {
+                continue;
+            } else if let Some((strm, number)) = Self::part_of(relative) {
+                stacks.entry(strm).or_default().push((number, relative.clone()));
+            } else {
+                titles.push(Self::single(relative));
+            }
+        }
+
+        for (root, format) in discs {
+            let name = root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| format.dir_name().to_string());
+            titles.push(Self {
+                strm: root.join(format!("{}.{}", name, STRM_EXTENSION)),
+                parts: vec![root.join(format.dir_name()).join(format.index_file())],
+                kind: MediaTitleKind::Disc(format),
+            });
+        }
+
+        for (strm, mut parts) in stacks {
+            parts.sort();
+            // A repeated number means the folders hold several episodes each, not parts
+            if parts.len() == 1 || parts.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                titles.extend(parts.iter().map(|(_, part)| Self::single(part)));
+                continue;
+            }
+            titles.push(Self {
+                strm,
+                parts: parts.into_iter().map(|(_, part)| part).collect(),
+                kind: MediaTitleKind::MultiPart,
+            });
+        }
+
+        titles.sort_by(|a, b| a.strm.cmp(&b.strm));
+        titles
+    }
+
+    /// Returns `true` if a folder with this name holds part of a title
+    /// named after its parent: a disc structure or a part folder.
+    pub fn is_title_dir(name: &str) -> bool {
+        DiscFormat::from_dir_name(name).is_some() || PART_DIR_PATTERN.is_match(name)
+    }
+
+    /// Returns the `.strm` path of the release a media file is a part of,
+    /// and the part number.
+    pub fn part_of(relative: &Path) -> Option<(PathBuf, u32)> {
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        let folder = parent.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if let Some(captures) = PART_DIR_PATTERN.captures(folder) {
+            let release = parent.parent().unwrap_or(Path::new(""));
+            let name = release
+                .file_name()
+                .or_else(|| relative.file_stem())?
+                .to_string_lossy()
+                .into_owned();
+            return Some((release.join(format!("{}.{}", name, STRM_EXTENSION)), captures[1].parse().ok()?));
+        }
+
+        let stem = relative.file_stem()?.to_str()?;
+        let captures = PART_STEM_PATTERN.captures(stem)?;
+        let name = captures[1].trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some((parent.join(format!("{}.{}", name, STRM_EXTENSION)), captures[2].parse().ok()?))
+    }
+}
@@ -0,0 +1,38 @@
+//! Generation of `.strm` files pointing media servers at media files.
+//!
+//! This module provides:
+//! - A generator writing one `.strm` file per media file of a source tree
+//! - Detection of disc folders and multi-part releases played as one title
+//! - Templates turning media paths into URLs written into `.strm` files
+//! - Prefix mappings for media servers that mount the library elsewhere
+//! - Full regenerations staged aside and swapped into place at once
+//! - Hashes of written contents so unchanged `.strm` files aren't rewritten
+//! - An index of processed source files for incremental generations
+//! - Reports of orphaned `.strm` files removed from the target
+//! - Validation of existing `.strm` files and their targets
+//! - Content style detection and template suggestions for imported `.strm` trees
+//! - `.strm` files for media stored only on rclone, Alist or WebDAV remotes
+//! 
+pub mod file_index;
+pub mod media_title;
+pub mod path_mapping;
+pub mod prune_report;
+pub mod remote_listing;
+pub mod staged_target;
+pub mod strm_checksums;
+pub mod strm_generator;
+pub mod strm_import;
+pub mod strm_template;
+pub mod strm_validator;
+
+pub use file_index::*;
+pub use media_title::*;
+pub use path_mapping::*;
+pub use prune_report::*;
+pub use remote_listing::*;
+pub use staged_target::*;
+pub use strm_checksums::*;
+pub use strm_generator::*;
+pub use strm_import::*;
+pub use strm_template::*;
+pub use strm_validator::*;
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::network::encode_url_path;
+
+/// Replaces a path prefix with another one.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PathMapping {
+
+    /// Prefix of local paths (e.g. `/mnt/media`)
+    pub from: String,
+
+    /// Prefix written instead (e.g. `smb://nas/media` or `/media`)
+    pub to: String,
+}
+
+impl PathMapping {
+
+    /// Creates a mapping from one prefix to another.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Returns the rest of `path` after the prefix, if the prefix matches
+    /// whole path components.
+    fn strip<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let from = self.from.trim_end_matches(['/', '\\']);
+        let rest = path.strip_prefix(from)?;
+        (rest.is_empty() || rest.starts_with(['/', '\\'])).then_some(rest)
+    }
+}
+
+/// Prefix mappings applied to the paths written into `.strm` files.
+///
+/// Needed when the media server mounts the library under a different
+/// root than this machine, e.g. `/mnt/media` here and `/media` inside an
+/// Emby container. The longest matching prefix wins, and prefixes only
+/// match whole components, so `/mnt/media` doesn't apply to `/mnt/media2`.
+/// When the replacement is a URL, backslashes in the rest of the path
+/// become forward slashes and each segment is percent-encoded, so names
+/// holding spaces, `#` or `?` stay part of the path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct PathMappings(Vec<PathMapping>);
+
+impl PathMappings {
+
+    /// Creates a table from a list of mappings.
+    pub fn new(mappings: Vec<PathMapping>) -> Self {
+        Self(mappings)
+    }
+
+    /// Adds a mapping (builder pattern).
+    pub fn with_mapping(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.0.push(PathMapping::new(from, to));
+        self
+    }
+
+    /// Returns `true` if no mapping is configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Applies the longest matching mapping to a path.
+    ///
+    /// Paths no mapping matches are returned unchanged.
+    pub fn apply(&self, path: &str) -> String {
+        let matched = self.0
+            .iter()
+            .filter_map(|mapping| mapping.strip(path).map(|rest| (mapping, rest)))
+            .max_by_key(|(mapping, _)| mapping.from.trim_end_matches(['/', '\\']).len());
+        let Some((mapping, rest)) = matched else {
+            return path.to_string();
+        };
+
+        let to = mapping.to.trim_end_matches(['/', '\\']);
+        if to.contains("://") {
+            let encoded = encode_url_path(&rest.replace('\\', "/"));
+            if encoded.is_empty() { to.to_string() } else { format!("{}/{}", to, encoded) }
+        } else {
+            format!("{}{}", to, rest)
+        }
+    }
+}
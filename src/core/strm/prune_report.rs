@@ -0,0 +1,39 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf
+};
+
+/// Result of [`StrmGenerator::prune_orphans`](super::StrmGenerator::prune_orphans).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+
+    /// `.strm` files removed, relative to the target
+    pub removed: Vec<PathBuf>,
+
+    /// Directory the files were moved to, `None` if they were deleted
+    pub soft_delete_dir: Option<PathBuf>,
+}
+
+impl PruneReport {
+
+    /// Returns the number of files removed.
+    pub fn len(&self) -> usize {
+        self.removed.len()
+    }
+
+    /// Returns `true` if no file was removed.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+    }
+}
+
+impl Display for PruneReport {
+
+    /// Formats the report as a one-line summary.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match &self.soft_delete_dir {
+            Some(dir) => write!(f, "Moved {} orphaned strm files to {}", self.len(), dir.display()),
+            None => write!(f, "Deleted {} orphaned strm files", self.len()),
+        }
+    }
+}
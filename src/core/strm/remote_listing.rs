@@ -0,0 +1,96 @@
+use std::{
+    path::PathBuf,
+    process::Command,
+    time::Duration
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::Deserialize;
+
+use crate::infrastructure::fs::run_with_timeout;
+
+/// Program listing rclone remotes.
+const RCLONE_PROGRAM: &str = "rclone";
+
+/// Time a recursive listing may take; large cloud drives list slowly.
+const RCLONE_LIST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A file found on a remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+
+    /// Path relative to the listed directory
+    pub relative: PathBuf,
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// URL streaming the file, `None` if the remote has no direct links
+    pub url: Option<String>,
+}
+
+/// An entry of `rclone lsjson` output.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RcloneEntry {
+
+    /// Path relative to the listed directory
+    path: String,
+
+    /// File size in bytes, `-1` if unknown
+    #[serde(default)]
+    size: i64,
+
+    /// Whether the entry is a directory
+    #[serde(default)]
+    is_dir: bool,
+}
+
+/// Recursive listing of a directory on an rclone remote.
+pub struct RcloneListing;
+
+impl RcloneListing {
+
+    /// Returns the rclone path of a directory on a remote, e.g. `gdrive:Media/Anime`.
+    pub fn remote_path(remote: &str, path: &str) -> String {
+        format!("{}:{}", remote.trim_end_matches(':'), path.trim_matches('/'))
+    }
+
+    /// Lists every file below a directory of a remote with `rclone lsjson -R`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if rclone can't be run, fails, or prints
+    /// output that can't be parsed.
+    pub fn list(remote: &str, path: &str) -> Result<Vec<RemoteFile>, Error> {
+        let remote_path = Self::remote_path(remote, path);
+        let mut command = Command::new(RCLONE_PROGRAM);
+        command.args(["lsjson", "-R", "--files-only", "--no-mimetype", "--no-modtime", &remote_path]);
+        let output = run_with_timeout(command, RCLONE_LIST_TIMEOUT)?;
+        if !output.success() {
+            return Err(anyhow!("Listing {} failed: {}", remote_path, output.stderr.trim()));
+        }
+        Self::parse(&output.stdout).with_context(|| format!("Failed to parse the listing of {}", remote_path))
+    }
+
+    /// Parses `rclone lsjson` output, skipping directories.
+    ///
+    /// # Returns
+    /// The files, sorted by relative path.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the output isn't a JSON listing.
+    pub fn parse(json: &str) -> Result<Vec<RemoteFile>, Error> {
+        let entries: Vec<RcloneEntry> = serde_json::from_str(json)?;
+        let mut files: Vec<RemoteFile> = entries
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| RemoteFile {
+                relative: PathBuf::from(entry.path),
+                size: entry.size.max(0) as u64,
+                url: None,
+            })
+            .collect();
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Ok(files)
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{infrastructure::fs::DiskSpace, warn_log};
+
+/// Domain identifier for staged regeneration logs
+const STAGED_TARGET_LOGGER_DOMAIN: &str = "[STRM]";
+
+/// How a staged regeneration replaces the target directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapMode {
+
+    /// The staging directory and the target are exchanged in a single
+    /// atomic rename on Linux. Elsewhere, or on filesystems that can't
+    /// exchange paths, the target is renamed away and the staging
+    /// directory renamed into its place back to back, so the target is
+    /// missing only for an instant.
+    #[default]
+    Rename,
+
+    /// The target is a symlink to the current release, replaced in a
+    /// single atomic rename by a symlink to the new one. A target that is
+    /// still a directory is turned into a symlink on the first swap.
+    /// Only available on Unix.
+    Symlink,
+}
+
+/// A directory a full regeneration is written to before it replaces the
+/// target.
+///
+/// The staging directory is created next to the target, or in a
+/// configured directory on the same filesystem, as
+/// `.<name>.staging-<timestamp>`, so the swap is a rename. Media servers keep reading the previous content
+/// until [`commit`](Self::commit) swaps it, however long the rebuild
+/// takes, and never see a half-written library. Anything in the target
+/// that wasn't regenerated is gone after the swap.
+#[derive(Debug)]
+pub struct StagedTarget {
+
+    /// Directory being replaced, or the symlink pointing at it
+    target: PathBuf,
+
+    /// Directory the regeneration is written to
+    staging: PathBuf,
+
+    /// How the staging directory replaces the target
+    mode: SwapMode,
+}
+
+impl StagedTarget {
+
+    /// Creates an empty staging directory next to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the target has no parent or the staging
+    /// directory can't be created.
+    pub fn begin(target: impl Into<PathBuf>, mode: SwapMode) -> Result<Self, Error> {
+        Self::begin_in(target, mode, None)
+    }
+
+    /// Creates an empty staging directory in `dir`, or next to the target
+    /// if `None`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the target has no parent, `dir` isn't on
+    /// the target's filesystem, or the staging directory can't be created.
+    pub fn begin_in(target: impl Into<PathBuf>, mode: SwapMode, dir: Option<&Path>) -> Result<Self, Error> {
+        let target = target.into();
+        let sibling = Self::sibling(&target, "staging")?;
+        let staging = match (dir, sibling.file_name()) {
+            (Some(dir), Some(name)) => {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create staging directory {}", dir.display()))?;
+                let parent = target.parent().unwrap_or(Path::new(""));
+                if !DiskSpace::same_filesystem(dir, parent)? {
+                    return Err(anyhow!(
+                        "Staging directory '{}' is not on the filesystem of '{}', so it can't be swapped into place",
+                        dir.display(),
+                        target.display()
+                    ));
+                }
+                dir.join(name)
+            }
+            _ => sibling,
+        };
+        fs::create_dir_all(&staging)
+            .with_context(|| format!("Failed to create staging directory {}", staging.display()))?;
+        Ok(Self { target, staging, mode })
+    }
+
+    /// Returns the directory the regeneration is written to.
+    pub fn staging(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Returns the directory being replaced.
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Swaps the staging directory into place and removes the previous content.
+    ///
+    /// In symlink mode, a release the symlink pointed at is only removed
+    /// if it was created by an earlier swap, next to the target; a
+    /// directory the symlink was pointed at by hand is left alone.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the swap fails; the previous content is
+    /// then left in place, or restored, and the staging directory kept.
+    pub fn commit(self) -> Result<(), Error> {
+        let previous = match self.mode {
+            SwapMode::Rename => self.swap_by_rename()?,
+            SwapMode::Symlink => self.swap_symlink()?,
+        };
+        if let Some(previous) = previous {
+            fs::remove_dir_all(&previous)
+                .with_context(|| format!("Failed to remove previous content {}", previous.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the staging directory, leaving the target untouched.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the staging directory can't be removed.
+    pub fn abort(self) -> Result<(), Error> {
+        fs::remove_dir_all(&self.staging)
+            .with_context(|| format!("Failed to remove staging directory {}", self.staging.display()))
+    }
+
+    /// Renames the target away and the staging directory into its place.
+    ///
+    /// # Returns
+    /// Where the previous content was moved, if there was any.
+    fn swap_by_rename(&self) -> Result<Option<PathBuf>, Error> {
+        if fs::symlink_metadata(&self.target).is_err() {
+            fs::rename(&self.staging, &self.target)
+                .with_context(|| format!("Failed to move {} to {}", self.staging.display(), self.target.display()))?;
+            return Ok(None);
+        }
+
+        if Self::exchange(&self.staging, &self.target)? {
+            return Ok(Some(self.staging.clone()));
+        }
+
+        let previous = Self::sibling(&self.target, "previous")?;
+        fs::rename(&self.target, &previous)
+            .with_context(|| format!("Failed to move {} away", self.target.display()))?;
+        if let Err(e) = fs::rename(&self.staging, &self.target) {
+            let _ = fs::rename(&previous, &self.target);
+            return Err(anyhow!("Failed to move {} to {}: {}", self.staging.display(), self.target.display(), e));
+        }
+        Ok(Some(previous))
+    }
+
+    /// Points the target symlink at the staging directory.
+    ///
+    /// # Returns
+    /// The release the symlink pointed at, or the directory the target
+    /// was, if there was any.
+    #[cfg(unix)]
+    fn swap_symlink(&self) -> Result<Option<PathBuf>, Error> {
+        let release = Self::sibling(&self.target, "release")?;
+        fs::rename(&self.staging, &release)
+            .with_context(|| format!("Failed to move {} to {}", self.staging.display(), release.display()))?;
+
+        let previous = match fs::symlink_metadata(&self.target) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&self.target)?;
+                let linked = if link.is_absolute() { link } else { self.parent()?.join(link) };
+                if self.is_release(&linked) {
+                    Some(linked)
+                } else {
+                    warn_log!(
+                        STAGED_TARGET_LOGGER_DOMAIN,
+                        format!("Keeping {}, which {} pointed at", linked.display(), self.target.display())
+                    );
+                    None
+                }
+            }
+            Ok(_) => {
+                // The first swap turns the directory into a symlink
+                let previous = Self::sibling(&self.target, "previous")?;
+                fs::rename(&self.target, &previous)
+                    .with_context(|| format!("Failed to move {} away", self.target.display()))?;
+                Some(previous)
+            }
+            Err(_) => None,
+        };
+
+        // Renaming a new symlink over the old one swaps them atomically
+        let link = Self::sibling(&self.target, "link")?;
+        let name = release.file_name().ok_or_else(|| anyhow!("Invalid release {}", release.display()))?;
+        std::os::unix::fs::symlink(name, &link)
+            .and_then(|()| fs::rename(&link, &self.target))
+            .with_context(|| format!("Failed to point {} at {}", self.target.display(), release.display()))?;
+        Ok(previous)
+    }
+
+    /// Symlinks aren't swapped outside Unix.
+    #[cfg(not(unix))]
+    fn swap_symlink(&self) -> Result<Option<PathBuf>, Error> {
+        Err(anyhow!("Symlink swaps are only supported on Unix"))
+    }
+
+    /// Returns `true` if a path is a release created by an earlier swap,
+    /// `.<name>.release-<timestamp>` next to the target.
+    #[cfg(unix)]
+    fn is_release(&self, path: &Path) -> bool {
+        let Some(name) = self.target.file_name() else {
+            return false;
+        };
+        let mut prefix = OsString::from(".");
+        prefix.push(name);
+        prefix.push(".release-");
+        path.parent() == self.target.parent()
+            && path.file_name().is_some_and(|file_name| {
+                file_name.as_encoded_bytes().starts_with(prefix.as_encoded_bytes())
+            })
+    }
+
+    /// Exchanges two paths in a single rename.
+    ///
+    /// # Returns
+    /// `false` if the kernel or filesystem can't exchange paths.
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn exchange(from: &Path, to: &Path) -> Result<bool, Error> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let from_c = CString::new(from.as_os_str().as_bytes())?;
+        let to_c = CString::new(to.as_os_str().as_bytes())?;
+        // SAFETY: both paths are NUL-terminated and outlive the call
+        let result = unsafe {
+            libc::renameat2(libc::AT_FDCWD, from_c.as_ptr(), libc::AT_FDCWD, to_c.as_ptr(), libc::RENAME_EXCHANGE)
+        };
+        if result == 0 {
+            return Ok(true);
+        }
+        let error = std::io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::EINVAL | libc::ENOSYS | libc::EOPNOTSUPP) => Ok(false),
+            _ => Err(anyhow!("Failed to exchange {} and {}: {}", from.display(), to.display(), error)),
+        }
+    }
+
+    /// Paths are only exchanged on Linux.
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    fn exchange(_from: &Path, _to: &Path) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Returns the directory holding the target.
+    fn parent(&self) -> Result<&Path, Error> {
+        self.target
+            .parent()
+            .ok_or_else(|| anyhow!("{} has no parent directory", self.target.display()))
+    }
+
+    /// Returns a hidden path next to the target, `.<name>.<label>-<timestamp>`.
+    fn sibling(target: &Path, label: &str) -> Result<PathBuf, Error> {
+        let name = target
+            .file_name()
+            .ok_or_else(|| anyhow!("{} has no directory name", target.display()))?;
+        let parent = target
+            .parent()
+            .ok_or_else(|| anyhow!("{} has no parent directory", target.display()))?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+
+        let mut sibling = OsString::from(".");
+        sibling.push(name);
+        sibling.push(format!(".{}-{}", label, timestamp));
+        Ok(parent.join(sibling))
+    }
+}
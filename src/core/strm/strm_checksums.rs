@@ -0,0 +1,152 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::UNIX_EPOCH
+};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::library::state_file::{load_state, lock_state, save_state};
+
+/// What was written into a `.strm` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedStrm {
+
+    /// Hex SHA-256 of the content
+    pub checksum: String,
+
+    /// Size of the file once written
+    pub size: u64,
+
+    /// Modification time of the file once written, in milliseconds since the Unix epoch
+    pub modified_ms: Option<u64>,
+}
+
+/// Hashes of the content written into `.strm` files, kept between runs.
+///
+/// The size and modification time of each file are recorded along with
+/// the hash. When the hash recorded for a file matches the content a run
+/// would write and the file's size and modification time are unchanged,
+/// it is left alone without being read, so repeated runs don't touch the
+/// target at all and media servers see no change. A file edited by hand
+/// or damaged since is read again and rewritten if its content differs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrmChecksums {
+
+    /// What was written into each file, keyed by path relative to the target
+    #[serde(default)]
+    files: BTreeMap<String, RecordedStrm>,
+
+    /// True once a hash changed since the last load or save
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl StrmChecksums {
+
+    /// Loads recorded hashes, returning none if the file doesn't exist.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        load_state(path).with_context(|| format!("Failed to load strm checksums {}", path.display()))
+    }
+
+    /// Writes the hashes if they changed since they were loaded, holding
+    /// a lock on the file so processes sharing it don't write it at once.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be locked or written.
+    pub fn save(&mut self, path: &Path) -> Result<(), Error> {
+        if !self.changed {
+            return Ok(());
+        }
+        let _lock = lock_state(path)?;
+        save_state(self, path).with_context(|| format!("Failed to save strm checksums {}", path.display()))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Returns the hex SHA-256 of a content.
+    pub fn checksum(content: &str) -> String {
+        format!("{:x}", Sha256::digest(content.as_bytes()))
+    }
+
+    /// Returns `true` if the hash recorded for a file is that of `content`.
+    pub fn matches(&self, strm: &Path, content: &str) -> bool {
+        self.files
+            .get(&Self::key(strm))
+            .is_some_and(|recorded| recorded.checksum == Self::checksum(content))
+    }
+
+    /// Returns `true` if the hash recorded for a file is that of `content`
+    /// and the file on disk still has the recorded size and modification time.
+    ///
+    /// # Arguments
+    /// * `strm` - Path of the file relative to the target
+    /// * `content` - Content the file should hold
+    /// * `file` - The file on disk
+    pub fn is_current(&self, strm: &Path, content: &str, file: &Path) -> bool {
+        let Some(recorded) = self.files.get(&Self::key(strm)) else {
+            return false;
+        };
+        recorded.checksum == Self::checksum(content)
+            && Self::stat(file).is_some_and(|(size, modified_ms)| size == recorded.size && modified_ms == recorded.modified_ms)
+    }
+
+    /// Records the content of a file along with its size and modification
+    /// time on disk, forgetting it if the file can't be read.
+    ///
+    /// # Arguments
+    /// * `strm` - Path of the file relative to the target
+    /// * `content` - Content the file holds
+    /// * `file` - The file on disk
+    pub fn record(&mut self, strm: &Path, content: &str, file: &Path) {
+        let Some((size, modified_ms)) = Self::stat(file) else {
+            self.remove(strm);
+            return;
+        };
+        let recorded = RecordedStrm { checksum: Self::checksum(content), size, modified_ms };
+        if self.files.get(&Self::key(strm)) != Some(&recorded) {
+            self.files.insert(Self::key(strm), recorded);
+            self.changed = true;
+        }
+    }
+
+    /// Forgets a removed file.
+    pub fn remove(&mut self, strm: &Path) {
+        if self.files.remove(&Self::key(strm)).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Returns the number of recorded files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if no file is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the size and modification time of a file, `None` if it
+    /// can't be read.
+    fn stat(file: &Path) -> Option<(u64, Option<u64>)> {
+        let metadata = fs::metadata(file).ok()?;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .and_then(|elapsed| u64::try_from(elapsed.as_millis()).ok());
+        Some((metadata.len(), modified_ms))
+    }
+
+    /// Returns the key of a file, with `/` separators on every platform.
+    fn key(strm: &Path) -> String {
+        strm.to_string_lossy().replace('\\', "/")
+    }
+}
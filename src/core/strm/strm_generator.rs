@@ -0,0 +1,1156 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use sha2::{Digest, Sha256};
+use time::{macros::format_description, Date, OffsetDateTime};
+
+use crate::{
+    core::client::{AlistClient, WebDavClient},
+    debug_log,
+    info_log,
+    infrastructure::fs::{
+        DirScanner, MediaDetector, MediaKind, MediaKindRules, MediaSizeLimits, OverwritePolicy, ScannedFile, SyncAction,
+        FileRename, FileStamp, AUDIO_SUFFIXES
+    },
+    warn_log
+};
+use super::{
+    file_index::FileIndex,
+    path_mapping::PathMappings,
+    prune_report::PruneReport,
+    media_title::{DiscFormat, MediaTitle, MediaTitleKind},
+    remote_listing::{RcloneListing, RemoteFile},
+    staged_target::{StagedTarget, SwapMode},
+    strm_checksums::StrmChecksums,
+    strm_template::StrmContentTemplate
+};
+
+/// Domain identifier for strm generation logs
+const STRM_LOGGER_DOMAIN: &str = "[STRM]";
+
+/// Extension of generated files.
+pub const STRM_EXTENSION: &str = "strm";
+
+/// Suffixes of the media files a `.strm` file is generated for by default.
+pub const STRM_DEFAULT_MEDIA_SUFFIXES: [&str; 14] = [
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "flv", "webm",
+    "ts", "m2ts", "mpg", "mpeg", "rmvb", "iso",
+];
+
+/// Suffixes of the companion files copied by default in [`StrmLayout::Mirror`].
+pub const STRM_DEFAULT_COMPANION_SUFFIXES: [&str; 12] = [
+    "nfo", "jpg", "jpeg", "png", "webp",
+    "srt", "ass", "ssa", "sub", "idx", "vtt", "sup",
+];
+
+/// What a [`StrmGenerator`] writes into the target directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StrmLayout {
+
+    /// Only `.strm` files, e.g. next to the media files when the target is the source
+    #[default]
+    StrmOnly,
+
+    /// The source tree with `.strm` files for media and copies of companion
+    /// files such as `.nfo`, posters and subtitles
+    Mirror,
+}
+
+/// Writes one `.strm` file per media file of a source tree.
+///
+/// Each media file `<source>/<dir>/<name>.<ext>` gets a file
+/// `<target>/<dir>/<name>.strm`. With [`StrmLayout::Mirror`], companion
+/// files such as `.nfo`, posters and subtitles are copied alongside, so
+/// the target is a complete library for the media server.
+///
+/// By default a `.strm` file contains the absolute path of the media
+/// file, rewritten by the [`PathMappings`]; a [`StrmContentTemplate`]
+/// can write a URL instead. By default, files
+/// whose content is already up to date aren't rewritten, so media servers
+/// don't rescan them; see [`OverwritePolicy`].
+///
+/// # Examples
+/// ```no_run
+/// use pilipili_strm::core::strm::{PathMappings, StrmGenerator, StrmLayout};
+///
+/// let generator = StrmGenerator::new("/mnt/media/movies", "/srv/strm/movies")
+///     .with_layout(StrmLayout::Mirror)
+///     .with_path_mappings(PathMappings::default().with_mapping("/mnt/media", "/media"));
+/// let written = generator.generate()?;
+/// println!("Wrote {} files", written.len());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct StrmGenerator {
+
+    /// Directory holding the media files
+    source: PathBuf,
+
+    /// Directory the `.strm` files are written to
+    target: PathBuf,
+
+    /// Recognizes media files by suffix, and by content when sniffing
+    detector: MediaDetector,
+
+    /// Minimum sizes below which media files get no `.strm` file
+    media_size_limits: MediaSizeLimits,
+
+    /// Kinds of media files that get no `.strm` file, such as trailers
+    skipped_kinds: Vec<MediaKind>,
+
+    /// What is written into the target
+    layout: StrmLayout,
+
+    /// Suffixes of companion files copied when mirroring, without leading dots
+    companion_suffixes: Vec<String>,
+
+    /// Template of the content, `None` to write the media file's path
+    content_template: Option<StrmContentTemplate>,
+
+    /// Prefix mappings applied to the media file's path
+    path_mappings: PathMappings,
+
+    /// Treatment of `.strm` and companion files that already exist
+    overwrite_policy: OverwritePolicy,
+
+    /// Directory orphaned `.strm` files are moved to, `None` to delete them
+    soft_delete_dir: Option<PathBuf>,
+
+    /// How long soft-deleted files are kept, `None` to keep them forever
+    soft_delete_retention: Option<Duration>,
+
+    /// Directory staged regenerations are written to, `None` for next to the target
+    staging_dir: Option<PathBuf>,
+
+    /// When true, disc folders and multi-part releases get a single `.strm` file
+    title_grouping: bool,
+
+    /// When true, actions are logged and reported instead of performed
+    dry_run: bool,
+
+    /// Hashes of the written contents and the file they are saved to
+    checksums: Option<(PathBuf, Arc<Mutex<StrmChecksums>>)>,
+
+    /// Index of the processed source files and the file it is saved to
+    index: Option<(PathBuf, Arc<Mutex<FileIndex>>)>,
+}
+
+impl StrmGenerator {
+
+    /// Creates a generator for the default media suffixes.
+    pub fn new(source: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+            detector: MediaDetector::new()
+                .with_video_suffixes(STRM_DEFAULT_MEDIA_SUFFIXES.to_vec())
+                .with_audio_suffixes(Vec::new()),
+            media_size_limits: MediaSizeLimits::default(),
+            skipped_kinds: Vec::new(),
+            layout: StrmLayout::default(),
+            companion_suffixes: STRM_DEFAULT_COMPANION_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect(),
+            content_template: None,
+            path_mappings: PathMappings::default(),
+            overwrite_policy: OverwritePolicy::default(),
+            soft_delete_dir: None,
+            soft_delete_retention: None,
+            staging_dir: None,
+            title_grouping: false,
+            dry_run: false,
+            checksums: None,
+            index: None,
+        }
+    }
+
+    /// Sets the suffixes of media files, trimming leading dots (builder pattern).
+    pub fn with_media_suffixes(mut self, suffixes: Vec<&str>) -> Self {
+        let (audio, video): (Vec<&str>, Vec<&str>) = suffixes.into_iter().partition(|suffix| {
+            AUDIO_SUFFIXES.iter().any(|audio| suffix.trim_start_matches('.').eq_ignore_ascii_case(audio))
+        });
+        self.detector = self.detector.with_video_suffixes(video).with_audio_suffixes(audio);
+        self
+    }
+
+    /// Enables or disables reading source files to confirm they are media
+    /// (builder pattern).
+    ///
+    /// Media with a wrong or missing suffix then gets a `.strm` file, and
+    /// text files with a media suffix don't; see [`MediaDetector`].
+    /// Remote listings are still matched by suffix.
+    pub fn with_signature_sniffing(mut self, sniff_signatures: bool) -> Self {
+        self.detector = self.detector.with_signature_sniffing(sniff_signatures);
+        self
+    }
+
+    /// Sets the minimum sizes below which media files, such as sample
+    /// clips and zero-byte placeholders, get no `.strm` file (builder pattern).
+    pub fn with_media_size_limits(mut self, limits: MediaSizeLimits) -> Self {
+        self.media_size_limits = limits;
+        self
+    }
+
+    /// Sets the rules classifying media files into features, trailers,
+    /// samples and extras (builder pattern).
+    ///
+    /// The rules see paths relative to the source or the listed remote
+    /// directory. See [`MediaKindRules`].
+    pub fn with_kind_rules(mut self, rules: MediaKindRules) -> Self {
+        self.detector = self.detector.with_kind_rules(rules);
+        self
+    }
+
+    /// Sets the kinds of media files that get no `.strm` file, e.g.
+    /// trailers and samples (builder pattern).
+    ///
+    /// `.strm` files left from an earlier run are pruned as orphans.
+    pub fn with_skipped_kinds(mut self, kinds: Vec<MediaKind>) -> Self {
+        self.skipped_kinds = kinds;
+        self
+    }
+
+    /// Sets what is written into the target (builder pattern).
+    pub fn with_layout(mut self, layout: StrmLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the suffixes of companion files copied when mirroring,
+    /// trimming leading dots (builder pattern).
+    pub fn with_companion_suffixes(mut self, suffixes: Vec<&str>) -> Self {
+        self.companion_suffixes = suffixes
+            .into_iter()
+            .map(|suffix| suffix.trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    /// Sets the template of the content written into `.strm` files (builder pattern).
+    pub fn with_content_template(mut self, template: StrmContentTemplate) -> Self {
+        self.content_template = Some(template);
+        self
+    }
+
+    /// Sets the prefix mappings applied to the paths written (builder pattern).
+    pub fn with_path_mappings(mut self, mappings: PathMappings) -> Self {
+        self.path_mappings = mappings;
+        self
+    }
+
+    /// Sets how existing files in the target are treated (builder pattern).
+    ///
+    /// The default rewrites `.strm` files whose content changed, so files
+    /// pointing at moved media are refreshed.
+    pub fn with_overwrite_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite_policy = policy;
+        self
+    }
+
+    /// Sets the directory orphaned `.strm` files are moved to instead of
+    /// being deleted (builder pattern).
+    ///
+    /// Files are moved into a subdirectory named after the day they were
+    /// removed, e.g. `2026-10-15/Anime/E01.strm`; a file removed twice
+    /// the same day gets a counter, e.g. `E01.1.strm`. A directory inside
+    /// the target isn't pruned itself.
+    pub fn with_soft_delete_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.soft_delete_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets how long soft-deleted files are kept (builder pattern).
+    ///
+    /// Older days are purged whenever files are generated or orphans are
+    /// pruned, see [`purge_soft_deleted`](Self::purge_soft_deleted).
+    pub fn with_soft_delete_retention(mut self, retention: Duration) -> Self {
+        self.soft_delete_retention = Some(retention);
+        self
+    }
+
+    /// Sets the directory staged regenerations are written to, which must
+    /// be on the target's filesystem (builder pattern).
+    ///
+    /// See [`generate_staged`](Self::generate_staged).
+    pub fn with_staging_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.staging_dir = Some(dir.into());
+        self
+    }
+
+    /// Enables or disables grouping of titles stored as several files (builder pattern).
+    ///
+    /// When enabled, a `BDMV` or `VIDEO_TS` folder gets one `.strm` file
+    /// playing the disc, and a release split into parts such as
+    /// `Movie.cd1.avi` and `Movie.cd2.avi` gets one `.strm` file playing
+    /// the parts in order, written as a Kodi `stack://` URL. See
+    /// [`MediaTitle::group`].
+    pub fn with_title_grouping(mut self, title_grouping: bool) -> Self {
+        self.title_grouping = title_grouping;
+        self
+    }
+
+    /// Records the hash of every written content in a state file (builder pattern).
+    ///
+    /// With [`OverwritePolicy::OverwriteIfChanged`], a `.strm` file whose
+    /// recorded hash matches the content to write, and whose size and
+    /// modification time are unchanged, is kept without being read, so
+    /// repeated runs leave the target untouched. A state file
+    /// that can't be loaded is logged and rebuilt. See [`StrmChecksums`].
+    pub fn with_checksum_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let checksums = StrmChecksums::load(&path).unwrap_or_else(|e| {
+            warn_log!(STRM_LOGGER_DOMAIN, format!("{:#}, rebuilding it", e));
+            StrmChecksums::default()
+        });
+        self.checksums = Some((path, Arc::new(Mutex::new(checksums))));
+        self
+    }
+
+    /// Records the source files processed by
+    /// [`generate_incremental`](Self::generate_incremental) in an index
+    /// file (builder pattern).
+    ///
+    /// An index that can't be loaded is logged and rebuilt, which makes
+    /// the next incremental generation a full one. See [`FileIndex`].
+    pub fn with_index_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let index = FileIndex::load(&path).unwrap_or_else(|e| {
+            warn_log!(STRM_LOGGER_DOMAIN, format!("{:#}, rebuilding it", e));
+            FileIndex::default()
+        });
+        self.index = Some((path, Arc::new(Mutex::new(index))));
+        self
+    }
+
+    /// Enables or disables dry-run mode (builder pattern).
+    ///
+    /// In dry-run mode every file that would be created, copied or removed
+    /// is logged and returned as usual, but the filesystem is left untouched.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns `true` if a path has one of the media suffixes.
+    pub fn is_media(&self, path: &Path) -> bool {
+        self.detector.type_by_suffix(path).is_some()
+    }
+
+    /// Returns `true` if a file of the source is media, reading it when
+    /// sniffing signatures.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the file relative to the source
+    pub fn is_media_file(&self, relative: &Path) -> bool {
+        self.detector.is_media_file(&self.source.join(relative))
+    }
+
+    /// Returns the kind of a media file, given its path relative to the source.
+    pub fn kind_of(&self, relative: &Path) -> MediaKind {
+        self.detector.kind_rules().kind_of_media(relative)
+    }
+
+    /// Returns `true` if a media file is of a kind that gets no `.strm` file.
+    fn is_skipped(&self, relative: &Path) -> bool {
+        !self.skipped_kinds.is_empty() && self.skipped_kinds.contains(&self.kind_of(relative))
+    }
+
+    /// Returns `true` if a path is a companion file copied when mirroring.
+    pub fn is_companion(&self, path: &Path) -> bool {
+        self.layout == StrmLayout::Mirror
+            && !self.is_media(path)
+            && Self::has_suffix(path, &self.companion_suffixes)
+    }
+
+    /// Returns `true` if a path has one of the suffixes, ignoring case.
+    fn has_suffix(path: &Path, suffixes: &[String]) -> bool {
+        path.extension().is_some_and(|extension| {
+            suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix))
+        })
+    }
+
+    /// Returns the `.strm` file generated for a media file.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the media file relative to the source
+    pub fn strm_path(&self, relative: &Path) -> PathBuf {
+        self.target.join(relative).with_extension(STRM_EXTENSION)
+    }
+
+    /// Returns the content of the `.strm` file of a media file.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the media file relative to the source
+    pub fn content(&self, relative: &Path) -> String {
+        match &self.content_template {
+            Some(template) => template.render(relative),
+            None => self.path_mappings.apply(&self.source.join(relative).to_string_lossy()),
+        }
+    }
+
+    /// Returns the content of the `.strm` file of a title.
+    ///
+    /// # Arguments
+    /// * `title` - The title
+    /// * `part_content` - Returns the content pointing at one of its parts,
+    ///   `None` if the part is unknown
+    fn title_content(title: &MediaTitle, part_content: impl Fn(&Path) -> Option<String>) -> Option<String> {
+        if title.kind != MediaTitleKind::MultiPart {
+            return part_content(title.parts.first()?);
+        }
+        // Kodi separates stacked parts with " , " and escapes commas by doubling them
+        let parts = title.parts
+            .iter()
+            .map(|part| part_content(part).map(|content| content.replace(',', ",,")))
+            .collect::<Option<Vec<String>>>()?;
+        Some(format!("stack://{}", parts.join(" , ")))
+    }
+
+    /// Writes the `.strm` files of every media file in the source.
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source can't be listed or a file
+    /// can't be written.
+    pub fn generate(&self) -> Result<Vec<PathBuf>, Error> {
+        self.generate_strm_for_dir(Path::new(""))
+    }
+
+    /// Regenerates the whole target in a staging directory, then swaps it
+    /// into place.
+    ///
+    /// Media servers keep the previous content until the regeneration is
+    /// complete, then see the new one at once; see [`StagedTarget`]. Files
+    /// in the target that weren't generated from the source are dropped.
+    /// On failure the staging directory is removed and the target left
+    /// untouched. In dry-run mode this is [`generate`](Self::generate).
+    ///
+    /// # Returns
+    /// Every file written, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source can't be listed, a file can't
+    /// be written or the swap fails.
+    pub fn generate_staged(&self, mode: SwapMode) -> Result<Vec<PathBuf>, Error> {
+        if self.dry_run {
+            return self.generate();
+        }
+
+        let staged = StagedTarget::begin_in(&self.target, mode, self.staging_dir.as_deref())?;
+        let mut generator = self.clone();
+        generator.target = staged.staging().to_path_buf();
+        let written = match generator.generate() {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = staged.abort();
+                return Err(e);
+            }
+        };
+        staged.commit()?;
+        info_log!(
+            STRM_LOGGER_DOMAIN,
+            format!("Swapped a regeneration of {} files into {}", written.len(), self.target.display())
+        );
+        Ok(written)
+    }
+
+    /// Writes the `.strm` files of the media files below a directory of
+    /// the source, copying companion files too when mirroring.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory relative to the source, or an absolute path inside it
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory is outside the source,
+    /// can't be listed, or a file can't be written.
+    pub fn generate_strm_for_dir(&self, dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let dir = Self::relative_dir(dir, &self.source)?;
+        let files = DirScanner::scan(self.source.join(dir))?;
+        let written = self.generate_scanned(dir, &files, None)?;
+
+        self.purge_expired()?;
+        self.save_checksums()?;
+        debug_log!(
+            STRM_LOGGER_DOMAIN,
+            format!("Wrote {} files to {}", written.len(), self.target.join(dir).display())
+        );
+        Ok(written)
+    }
+
+    /// Returns a directory relative to `root`, given relative to it or
+    /// as an absolute path inside it.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory is outside `root`.
+    fn relative_dir<'a>(dir: &'a Path, root: &Path) -> Result<&'a Path, Error> {
+        let relative = if dir.is_absolute() { dir.strip_prefix(root).ok() } else { Some(dir) };
+        relative
+            .filter(|relative| relative.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)))
+            .ok_or_else(|| anyhow!("{} is outside {}", dir.display(), root.display()))
+    }
+
+    /// Writes the `.strm` files of the media files that changed since the
+    /// last incremental generation, and removes those of deleted ones.
+    ///
+    /// Files are compared with the index set by
+    /// [`with_index_file`](Self::with_index_file), so unchanged media
+    /// isn't sniffed, rendered or compared with the target again, unless
+    /// its `.strm` or companion file is missing from the target. Titles
+    /// grouped from several files are always rewritten. Every file is
+    /// processed again when the settings shaping the output, such as the
+    /// content template, path mappings or layout, changed since the index
+    /// was written. The index is only updated once every change is
+    /// written, so a failed run is repeated in full. Without an index
+    /// this is [`generate`](Self::generate).
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source can't be listed, a changed
+    /// file can't be read, or a file can't be written or removed.
+    pub fn generate_incremental(&self) -> Result<Vec<PathBuf>, Error> {
+        let Some((index_path, index)) = &self.index else {
+            return self.generate();
+        };
+        let files = DirScanner::scan(&self.source)?;
+        let settings = self.settings_hash();
+        let (diff, full) = {
+            let index = index.lock().unwrap_or_else(|e| e.into_inner());
+            (index.diff(&self.source, &files)?, index.settings() != Some(settings.as_str()))
+        };
+        if full {
+            info_log!(
+                STRM_LOGGER_DOMAIN,
+                format!("Generation settings of {} changed, processing every file", self.target.display())
+            );
+        }
+
+        let changed: Option<HashSet<PathBuf>> = (!full).then(|| diff.changed.iter().cloned().collect());
+        let written = self.generate_scanned(Path::new(""), &files, changed.as_ref())?;
+        if full || !diff.removed.is_empty() {
+            self.prune_orphans(Path::new(""))?;
+        } else {
+            self.purge_expired()?;
+        }
+        self.save_checksums()?;
+        info_log!(
+            STRM_LOGGER_DOMAIN,
+            format!(
+                "{} source files changed, {} removed, {} unchanged; wrote {} files to {}",
+                diff.changed.len(),
+                diff.removed.len(),
+                diff.unchanged,
+                written.len(),
+                self.target.display()
+            )
+        );
+
+        if !self.dry_run {
+            let mut index = index.lock().unwrap_or_else(|e| e.into_inner());
+            index.apply(diff);
+            index.set_settings(settings);
+            index.save(index_path)?;
+        }
+        Ok(written)
+    }
+
+    /// Writes the `.strm` and companion files of scanned source files.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory the files were scanned from, relative to the source
+    /// * `files` - Files below `dir`
+    /// * `changed` - Paths relative to the source of the only files to
+    ///   write, `None` to write every file; titles are always written
+    fn generate_scanned(
+        &self,
+        dir: &Path,
+        files: &[ScannedFile],
+        changed: Option<&HashSet<PathBuf>>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut written = Vec::new();
+        let mut grouped = Vec::new();
+        for file in files {
+            let relative = dir.join(&file.relative);
+            if self.media_size_limits.is_undersized(&relative, file.size) {
+                continue;
+            }
+            let unchanged = changed.is_some_and(|changed| !changed.contains(&relative)) && !self.output_missing(&relative);
+            if unchanged && !self.title_grouping {
+                continue;
+            }
+            let is_media = self.is_media_file(&relative) && !self.is_skipped(&relative);
+            if self.title_grouping && (is_media || DiscFormat::root_of(&relative).is_some()) {
+                grouped.push(relative);
+            } else if unchanged {
+                continue;
+            } else if is_media {
+                if self.write(&relative)? {
+                    written.push(relative.with_extension(STRM_EXTENSION));
+                }
+            } else if self.is_companion(&relative) && self.copy_companion(&relative)? {
+                written.push(relative);
+            }
+        }
+        for title in MediaTitle::group(&grouped, |path| self.is_media_file(path) && !self.is_skipped(path)) {
+            let content = Self::title_content(&title, |part| Some(self.content(part))).unwrap_or_default();
+            if self.write_content(&title.strm, &content)? {
+                written.push(title.strm);
+            }
+        }
+        Ok(written)
+    }
+
+    /// Returns `true` if a source file has a suffix the target gets a
+    /// file for, which is missing from the target.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the file relative to the source
+    fn output_missing(&self, relative: &Path) -> bool {
+        if self.is_media(relative) {
+            !self.strm_path(relative).exists()
+        } else if self.is_companion(relative) {
+            !self.target.join(relative).exists()
+        } else {
+            false
+        }
+    }
+
+    /// Returns the hex SHA-256 of the settings shaping what is written
+    /// for each source file, recorded in the index.
+    fn settings_hash(&self) -> String {
+        let settings = format!(
+            "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+            self.source,
+            self.content_template,
+            self.path_mappings,
+            self.layout,
+            self.companion_suffixes,
+            self.title_grouping,
+            self.detector.sniffs_signatures(),
+            self.skipped_kinds,
+            self.detector.kind_rules()
+        );
+        format!("{:x}", Sha256::digest(settings.as_bytes()))
+    }
+
+    /// Returns the size and modification time a source file had when it
+    /// was last indexed, `None` without an index or if it wasn't indexed.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the file relative to the source
+    pub fn indexed_stamp(&self, relative: &Path) -> Option<FileStamp> {
+        let (_, index) = self.index.as_ref()?;
+        index
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(relative)
+            .map(|file| FileStamp::new(file.size, file.modified_ms))
+    }
+
+    /// Replays moves of the source in the target, so generation finds
+    /// the `.strm` and companion files in place.
+    ///
+    /// A moved media file moves its `.strm` file, unless titles are
+    /// grouped; a moved directory or companion file moves as is. Moves
+    /// whose previous path is missing in the target, or whose new path
+    /// exists there, are skipped.
+    ///
+    /// # Arguments
+    /// * `renames` - Moves with paths relative to the source
+    ///
+    /// # Returns
+    /// The files and directories moved, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file or directory can't be moved.
+    pub fn apply_renames(&self, renames: &[FileRename]) -> Result<Vec<PathBuf>, Error> {
+        let mut moved = Vec::new();
+        for rename in renames {
+            let (from, to) = if self.source.join(&rename.to).is_dir() || self.is_companion(&rename.to) {
+                (rename.from.clone(), rename.to.clone())
+            } else if self.is_media_file(&rename.to) && !self.title_grouping {
+                (rename.from.with_extension(STRM_EXTENSION), rename.to.with_extension(STRM_EXTENSION))
+            } else {
+                continue;
+            };
+            let (from_path, to_path) = (self.target.join(&from), self.target.join(&to));
+            if !from_path.exists() || to_path.exists() {
+                continue;
+            }
+            if self.dry_run {
+                info_log!(
+                    STRM_LOGGER_DOMAIN,
+                    format!("Dry run: move {} to {} in {}", from.display(), to.display(), self.target.display())
+                );
+                continue;
+            }
+
+            if let Some(parent) = to_path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::rename(&from_path, &to_path)
+                .with_context(|| format!("Failed to move {} to {}", from_path.display(), to_path.display()))?;
+            self.update_checksums(|checksums| checksums.remove(&from));
+            moved.push(to);
+        }
+        Ok(moved)
+    }
+
+    /// Removes the `.strm` files below a directory of the target whose
+    /// media file no longer exists in the source.
+    ///
+    /// A `.strm` file is orphaned when no media file with the same stem
+    /// is left in the matching source directory. Orphans are moved to the
+    /// soft-delete directory, below the day's subdirectory and keeping
+    /// their relative path, or deleted if none is set. Soft-deleted days
+    /// older than the retention period are purged afterwards.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory relative to the target, or an absolute path inside it
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory is outside the target,
+    /// can't be listed, or an orphan can't be removed.
+    pub fn prune_orphans(&self, dir: &Path) -> Result<PruneReport, Error> {
+        let dir = Self::relative_dir(dir, &self.target)?;
+        let mut media_stems: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+        let mut report = PruneReport {
+            removed: Vec::new(),
+            soft_delete_dir: self.soft_delete_dir.clone(),
+        };
+
+        let soft_deleted = self.soft_delete_dir.as_ref().and_then(|soft_delete_dir| soft_delete_dir.strip_prefix(&self.target).ok());
+        for file in DirScanner::scan(self.target.join(dir))? {
+            let relative = dir.join(&file.relative);
+            if soft_deleted.is_some_and(|soft_deleted| relative.starts_with(soft_deleted)) {
+                continue;
+            }
+            if !relative.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(STRM_EXTENSION)) {
+                continue;
+            }
+
+            let parent = relative.parent().unwrap_or(Path::new("")).to_path_buf();
+            let stems = media_stems
+                .entry(parent)
+                .or_insert_with_key(|parent| self.media_stems(&self.source.join(parent)));
+            let stem = relative.file_stem().unwrap_or_default().to_string_lossy();
+            if stems.contains(stem.as_ref()) {
+                continue;
+            }
+
+            self.remove_orphan(&relative)?;
+            report.removed.push(relative);
+        }
+
+        self.purge_expired()?;
+        self.save_checksums()?;
+        debug_log!(STRM_LOGGER_DOMAIN, format!("{} in {}", report, self.target.join(dir).display()));
+        Ok(report)
+    }
+
+    /// Returns the stems of the media files directly inside a directory.
+    ///
+    /// With title grouping, the names of the titles stored there are
+    /// included: releases split into parts, and the directory's own name
+    /// if it holds a disc structure or part folders.
+    fn media_stems(&self, dir: &Path) -> HashSet<String> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return HashSet::new();
+        };
+        let mut stems = HashSet::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let relative = path.strip_prefix(&self.source).unwrap_or(&path);
+            if path.is_file() && self.detector.is_media_file(&path) && !self.is_skipped(relative) {
+                stems.extend(path.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+                if let Some((strm, _)) = MediaTitle::part_of(&path).filter(|_| self.title_grouping) {
+                    stems.extend(strm.file_stem().map(|stem| stem.to_string_lossy().into_owned()));
+                }
+            } else if self.title_grouping
+                && path.is_dir()
+                && path.file_name().and_then(|name| name.to_str()).is_some_and(MediaTitle::is_title_dir) {
+                stems.extend(dir.file_name().map(|name| name.to_string_lossy().into_owned()));
+            }
+        }
+        stems
+    }
+
+    /// Moves an orphaned `.strm` file to the soft-delete directory, or deletes it.
+    fn remove_orphan(&self, relative: &Path) -> Result<(), Error> {
+        if self.dry_run {
+            self.report_dry_run(SyncAction::Delete(relative.to_string_lossy().into_owned()));
+            return Ok(());
+        }
+
+        self.update_checksums(|checksums| checksums.remove(relative));
+        let path = self.target.join(relative);
+        let Some(soft_delete_dir) = &self.soft_delete_dir else {
+            return fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()));
+        };
+
+        let moved = Self::unused_path(
+            soft_delete_dir.join(Self::soft_delete_day(OffsetDateTime::now_utc().date())).join(relative)
+        );
+        if let Some(parent) = moved.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        if fs::rename(&path, &moved).is_err() {
+            // Renaming fails across filesystems, so fall back to copying
+            fs::copy(&path, &moved)
+                .and_then(|_| fs::remove_file(&path))
+                .with_context(|| format!("Failed to move {} to {}", path.display(), moved.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Purges the soft-deleted days past the retention period, if one is
+    /// set, except in dry-run mode.
+    fn purge_expired(&self) -> Result<(), Error> {
+        if let Some(retention) = self.soft_delete_retention.filter(|_| !self.dry_run) {
+            self.purge_soft_deleted(retention)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the days of the soft-delete directory that are older than
+    /// `older_than`.
+    ///
+    /// A day is purged once its end is `older_than` in the past, so every
+    /// file is kept at least that long; a period too long to represent
+    /// purges nothing. Entries not named after a day, such as files
+    /// soft-deleted by earlier versions, are left alone.
+    ///
+    /// # Returns
+    /// The directories deleted, relative to the soft-delete directory.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory can't be listed or a day
+    /// can't be deleted.
+    pub fn purge_soft_deleted(&self, older_than: Duration) -> Result<Vec<PathBuf>, Error> {
+        let Some(soft_delete_dir) = self.soft_delete_dir.as_ref().filter(|dir| dir.is_dir()) else {
+            return Ok(Vec::new());
+        };
+        let now = OffsetDateTime::now_utc();
+        let mut purged = Vec::new();
+        let entries = fs::read_dir(soft_delete_dir)
+            .with_context(|| format!("Failed to list {}", soft_delete_dir.display()))?;
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let Ok(day) = Date::parse(&name, format_description!("[year]-[month]-[day]")) else {
+                continue;
+            };
+            let end = day.next_day().unwrap_or(day).midnight().assume_utc();
+            let expiry = time::Duration::try_from(older_than).ok().and_then(|retention| end.checked_add(retention));
+            if expiry.is_none_or(|expiry| expiry > now) {
+                continue;
+            }
+            if self.dry_run {
+                info_log!(STRM_LOGGER_DOMAIN, format!("Dry run: purge {}", path.display()));
+                continue;
+            }
+            fs::remove_dir_all(&path).with_context(|| format!("Failed to purge {}", path.display()))?;
+            purged.push(PathBuf::from(name));
+        }
+        if !purged.is_empty() {
+            info_log!(
+                STRM_LOGGER_DOMAIN,
+                format!("Purged {} days of soft-deleted files from {}", purged.len(), soft_delete_dir.display())
+            );
+        }
+        Ok(purged)
+    }
+
+    /// Returns a path that doesn't exist yet, adding a counter to the stem
+    /// of `path` if needed, e.g. `E01.1.strm`.
+    fn unused_path(path: PathBuf) -> PathBuf {
+        if !path.exists() {
+            return path;
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let extension = path.extension().map(|extension| format!(".{}", extension.to_string_lossy())).unwrap_or_default();
+        (1u32..)
+            .map(|counter| path.with_file_name(format!("{}.{}{}", stem, counter, extension)))
+            .find(|candidate| !candidate.exists())
+            .unwrap_or(path)
+    }
+
+    /// Returns the name of a day's soft-delete subdirectory, e.g. `2026-10-15`.
+    fn soft_delete_day(day: Date) -> String {
+        day.format(format_description!("[year]-[month]-[day]")).unwrap_or_default()
+    }
+
+    /// Copies a companion file to the same place in the target.
+    ///
+    /// Unless the policy always overwrites, a copy with the same size that
+    /// is at least as new as the original is kept.
+    ///
+    /// # Returns
+    /// `true` if the file was copied, `false` if it was kept or the target
+    /// is the source itself.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be copied.
+    fn copy_companion(&self, relative: &Path) -> Result<bool, Error> {
+        let from = self.source.join(relative);
+        let to = self.target.join(relative);
+        if from == to {
+            return Ok(false);
+        }
+
+        let source_metadata = fs::metadata(&from)?;
+        let keep = match (self.overwrite_policy, fs::metadata(&to)) {
+            (_, Err(_)) => false,
+            (OverwritePolicy::Skip, Ok(_)) => true,
+            (OverwritePolicy::Overwrite, Ok(_)) => false,
+            (OverwritePolicy::OverwriteIfChanged, Ok(copied)) => {
+                copied.len() == source_metadata.len()
+                    && copied.modified().ok() >= source_metadata.modified().ok()
+            }
+        };
+        if keep {
+            return Ok(false);
+        }
+        if self.dry_run {
+            let relative = relative.to_string_lossy().into_owned();
+            self.report_dry_run(if to.exists() {
+                SyncAction::Update(relative)
+            } else {
+                SyncAction::Create(relative)
+            });
+            return Ok(true);
+        }
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(&from, &to).with_context(|| format!("Failed to copy {}", from.display()))?;
+        Ok(true)
+    }
+
+    /// Writes the `.strm` file of a single media file, following the
+    /// overwrite policy if it already exists.
+    ///
+    /// The hash of the content is recorded but not saved, see
+    /// [`save_checksums`](Self::save_checksums).
+    ///
+    /// # Returns
+    /// `true` if the file was written.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file or its directory can't be written.
+    pub fn write(&self, relative: &Path) -> Result<bool, Error> {
+        self.write_content(&relative.with_extension(STRM_EXTENSION), &self.content(relative))
+    }
+
+    /// Writes `.strm` files for the media files below a directory of an
+    /// rclone remote, for media that only exists there.
+    ///
+    /// The remote is listed with `rclone lsjson -R`. Each `.strm` file
+    /// gets the rclone path of its media file, such as
+    /// `gdrive:Media/Anime/E01.mkv`, rewritten by the path mappings, e.g.
+    /// to the mount point or the `rclone serve` URL the media server
+    /// reads, or the rendered content template. Companion files aren't
+    /// copied since they aren't available locally.
+    ///
+    /// # Arguments
+    /// * `remote` - Name of the rclone remote, with or without the trailing colon
+    /// * `path` - Directory on the remote
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the remote can't be listed or a file
+    /// can't be written.
+    pub fn generate_from_remote(&self, remote: &str, path: &str) -> Result<Vec<PathBuf>, Error> {
+        let files = RcloneListing::list(remote, path)?;
+        self.generate_from_files(&RcloneListing::remote_path(remote, path), &files)
+    }
+
+    /// Writes `.strm` files pointing at the direct links of the media
+    /// files below a directory of an Alist server.
+    ///
+    /// Alist redirects direct links to the storage, so the media server
+    /// streams from the cloud drive without anything being mounted.
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a directory can't be listed or a file
+    /// can't be written.
+    pub async fn generate_from_alist(&self, client: &AlistClient, path: &str) -> Result<Vec<PathBuf>, Error> {
+        let files = client.list_files(path).await?;
+        self.generate_from_files(path, &files)
+    }
+
+    /// Writes `.strm` files pointing at the URLs of the media files below
+    /// a collection of a WebDAV server.
+    ///
+    /// The URLs carry no credentials, so the server must allow the media
+    /// server to read them, e.g. through a read-only share.
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a collection can't be listed or a file
+    /// can't be written.
+    pub async fn generate_from_webdav(&self, client: &WebDavClient, path: &Path) -> Result<Vec<PathBuf>, Error> {
+        let files = client.list_files(path).await?;
+        self.generate_from_files(&path.to_string_lossy(), &files)
+    }
+
+    /// Writes `.strm` files for the media files of a remote listing.
+    ///
+    /// Files with a URL get it, rewritten by the path mappings; others
+    /// get their remote path below `root`. A content template replaces
+    /// both. With title grouping, disc folders and multi-part releases
+    /// get a single file.
+    ///
+    /// # Arguments
+    /// * `root` - Remote path of the listed directory, e.g. `gdrive:Media`
+    /// * `files` - The files below it
+    ///
+    /// # Returns
+    /// The files created or updated, relative to the target.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be written.
+    pub fn generate_from_files(&self, root: &str, files: &[RemoteFile]) -> Result<Vec<PathBuf>, Error> {
+        let files: Vec<&RemoteFile> = files
+            .iter()
+            .filter(|file| !self.media_size_limits.is_undersized(&file.relative, file.size))
+            .filter(|file| !self.is_skipped(&file.relative))
+            .collect();
+        let contents: HashMap<&Path, String> = files
+            .iter()
+            .map(|file| (file.relative.as_path(), self.remote_content(root, file)))
+            .collect();
+        let titles = if self.title_grouping {
+            let relatives: Vec<PathBuf> = files.iter().map(|file| file.relative.clone()).collect();
+            MediaTitle::group(&relatives, |path| self.is_media(path))
+        } else {
+            files
+                .iter()
+                .filter(|file| self.is_media(&file.relative))
+                .map(|file| MediaTitle::single(&file.relative))
+                .collect()
+        };
+
+        let mut written = Vec::new();
+        for title in titles {
+            let Some(content) = Self::title_content(&title, |part| contents.get(part).cloned()) else {
+                // A disc structure listed without its index file can't be played
+                continue;
+            };
+            if self.write_content(&title.strm, &content)? {
+                written.push(title.strm);
+            }
+        }
+
+        self.save_checksums()?;
+        debug_log!(
+            STRM_LOGGER_DOMAIN,
+            format!("Wrote {} files from {} to {}", written.len(), root, self.target.display())
+        );
+        Ok(written)
+    }
+
+    /// Returns the content pointing at a remote file, see
+    /// [`generate_from_files`](Self::generate_from_files).
+    fn remote_content(&self, root: &str, file: &RemoteFile) -> String {
+        match (&self.content_template, &file.url) {
+            (Some(template), _) => template.render(&file.relative),
+            (None, Some(url)) => self.path_mappings.apply(url),
+            (None, None) => {
+                let relative = file.relative.to_string_lossy().replace('\\', "/");
+                let separator = if root.ends_with([':', '/']) { "" } else { "/" };
+                self.path_mappings.apply(&format!("{}{}{}", root, separator, relative))
+            }
+        }
+    }
+
+    /// Writes a `.strm` file with the given content, following the
+    /// overwrite policy if it already exists.
+    ///
+    /// # Arguments
+    /// * `strm` - Path of the `.strm` file relative to the target
+    /// * `content` - Content to write
+    fn write_content(&self, strm: &Path, content: &str) -> Result<bool, Error> {
+        let path = self.target.join(strm);
+        let keep = match self.overwrite_policy {
+            OverwritePolicy::Skip => path.exists(),
+            OverwritePolicy::Overwrite => false,
+            OverwritePolicy::OverwriteIfChanged => {
+                if self.update_checksums(|checksums| checksums.is_current(strm, content, &path)) == Some(true) {
+                    return Ok(false);
+                }
+                fs::read_to_string(&path).is_ok_and(|existing| existing == content)
+            }
+        };
+        if keep {
+            if self.overwrite_policy == OverwritePolicy::OverwriteIfChanged && !self.dry_run {
+                self.update_checksums(|checksums| checksums.record(strm, content, &path));
+            }
+            return Ok(false);
+        }
+        if self.dry_run {
+            let relative = strm.to_string_lossy().into_owned();
+            self.report_dry_run(if path.exists() {
+                SyncAction::Update(relative)
+            } else {
+                SyncAction::Create(relative)
+            });
+            return Ok(true);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        self.update_checksums(|checksums| checksums.record(strm, content, &path));
+        Ok(true)
+    }
+
+    /// Saves the hashes of the written contents, if they are recorded.
+    ///
+    /// The generation methods save them when they finish; callers of
+    /// [`write`](Self::write) save them once done.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the state file can't be written.
+    pub fn save_checksums(&self) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+        match &self.checksums {
+            Some((path, checksums)) => checksums.lock().unwrap_or_else(|e| e.into_inner()).save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs `f` on the recorded hashes, if any.
+    fn update_checksums<T>(&self, f: impl FnOnce(&mut StrmChecksums) -> T) -> Option<T> {
+        let (_, checksums) = self.checksums.as_ref()?;
+        Some(f(&mut checksums.lock().unwrap_or_else(|e| e.into_inner())))
+    }
+
+    /// Logs an action skipped in dry-run mode.
+    fn report_dry_run(&self, action: SyncAction) {
+        info_log!(STRM_LOGGER_DOMAIN, format!("Dry run: {} in {}", action, self.target.display()));
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::{
+    debug_log,
+    infrastructure::{
+        fs::DirScanner,
+        network::encode_url_path
+    }
+};
+use super::{
+    strm_generator::STRM_EXTENSION,
+    strm_template::StrmContentTemplate
+};
+
+/// Domain identifier for strm import logs
+const STRM_IMPORT_LOGGER_DOMAIN: &str = "[STRM-IMPORT]";
+
+/// How the content of a `.strm` file points at its media.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StrmStyle {
+
+    /// A path on a local or mounted filesystem
+    LocalPath,
+
+    /// An `http://` or `https://` URL
+    HttpUrl,
+
+    /// An Alist direct link signed with `?sign=`
+    AlistSign,
+
+    /// Anything else, such as `rtsp://` URLs or empty files
+    Other,
+}
+
+impl StrmStyle {
+
+    /// Detects the style of a `.strm` file's content from its first
+    /// non-empty line.
+    pub fn detect(content: &str) -> Self {
+        let Some(target) = content.lines().map(str::trim).find(|line| !line.is_empty()) else {
+            return StrmStyle::Other;
+        };
+        let lowercase = target.to_ascii_lowercase();
+        if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+            let signed = lowercase.contains("/d/") && (lowercase.contains("?sign=") || lowercase.contains("&sign="));
+            return if signed { StrmStyle::AlistSign } else { StrmStyle::HttpUrl };
+        }
+        if target.contains("://") {
+            return StrmStyle::Other;
+        }
+        StrmStyle::LocalPath
+    }
+}
+
+impl Display for StrmStyle {
+
+    /// Formats the style as listed in import reports.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StrmStyle::LocalPath => write!(f, "local path"),
+            StrmStyle::HttpUrl => write!(f, "HTTP URL"),
+            StrmStyle::AlistSign => write!(f, "Alist signed link"),
+            StrmStyle::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Result of [`StrmImporter::import`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrmImportReport {
+
+    /// Number of `.strm` files found
+    pub scanned: usize,
+
+    /// Number of files of each style
+    pub styles: BTreeMap<StrmStyle, usize>,
+
+    /// Template reproducing the content of most files, if any does
+    pub suggested_template: Option<String>,
+
+    /// Number of files the suggested template reproduces
+    pub matching: usize,
+
+    /// Number of files rewritten to the configured template
+    pub normalized: usize,
+}
+
+impl Display for StrmImportReport {
+
+    /// Formats the report as a summary line, one line per style and the
+    /// suggested template.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} strm files imported", self.scanned)?;
+        if self.normalized > 0 {
+            write!(f, ", {} normalized", self.normalized)?;
+        }
+        for (style, count) in &self.styles {
+            write!(f, "\n  {}: {}", style, count)?;
+        }
+        match &self.suggested_template {
+            Some(template) => write!(
+                f,
+                "\nSuggested content_template: \"{}\" (matches {} of {})",
+                template,
+                self.matching,
+                self.scanned
+            ),
+            None => write!(f, "\nNo content_template reproduces these files"),
+        }
+    }
+}
+
+/// Inspects an existing tree of `.strm` files, e.g. one written by
+/// another tool, before a library takes it over.
+///
+/// Each file's content style is detected, and the content is compared
+/// with its path to infer a `content_template` the generator would write
+/// the same content with. The signatures of Alist links can't be
+/// templated, so the suggestion for them is the unsigned link.
+///
+/// With normalization, files are rewritten to the configured template.
+/// A file's media path is needed to render it, so files whose content
+/// doesn't end with their own path, media extension included, are left
+/// as they are.
+pub struct StrmImporter {
+
+    /// Directory holding the `.strm` files
+    root: PathBuf,
+
+    /// Template files are rewritten to, if they are normalized
+    template: Option<StrmContentTemplate>,
+
+    /// Whether files are rewritten to the template
+    normalize: bool,
+}
+
+impl StrmImporter {
+
+    /// Creates an importer for the `.strm` files below `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            template: None,
+            normalize: false,
+        }
+    }
+
+    /// Sets the template configured for the files (builder pattern).
+    pub fn with_template(mut self, template: StrmContentTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Sets whether files are rewritten to the configured template
+    /// (builder pattern).
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Detects the style of every `.strm` file below the root, and
+    /// rewrites them if normalization is enabled.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if normalization is enabled without a
+    /// template, or the directory or a file can't be read or written.
+    pub fn import(&self) -> Result<StrmImportReport, Error> {
+        let template = match (&self.template, self.normalize) {
+            (None, true) => return Err(anyhow!("Normalizing strm files needs a content_template")),
+            (template, true) => template.as_ref(),
+            (_, false) => None,
+        };
+
+        let mut report = StrmImportReport::default();
+        let mut templates: Vec<(String, usize)> = Vec::new();
+        for file in DirScanner::scan(&self.root)? {
+            if !file.relative.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(STRM_EXTENSION)) {
+                continue;
+            }
+
+            report.scanned += 1;
+            let path = self.root.join(&file.relative);
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let style = StrmStyle::detect(&content);
+            *report.styles.entry(style).or_default() += 1;
+
+            let Some((inferred, media)) = Self::infer(&file.relative, &content, style) else {
+                debug_log!(STRM_IMPORT_LOGGER_DOMAIN, format!("{}: no template reproduces it", path.display()));
+                continue;
+            };
+            match templates.iter_mut().find(|(template, _)| *template == inferred) {
+                Some((_, count)) => *count += 1,
+                None => templates.push((inferred, 1)),
+            }
+            if let Some(template) = template {
+                let rendered = template.render(&media);
+                if content.trim() != rendered {
+                    fs::write(&path, &rendered).with_context(|| format!("Failed to write {}", path.display()))?;
+                    report.normalized += 1;
+                }
+            }
+        }
+
+        // The first template found wins ties, so the suggestion is stable
+        let best = templates.into_iter().rev().max_by_key(|(_, count)| *count);
+        if let Some((template, count)) = best {
+            report.suggested_template = Some(template);
+            report.matching = count;
+        }
+        Ok(report)
+    }
+
+    /// Infers the template that renders a file's content from its media
+    /// path, and that media path.
+    ///
+    /// The content must end with the `.strm` file's path, in plain or
+    /// percent-encoded form, followed by the media extension; queries
+    /// such as Alist signatures are dropped.
+    fn infer(relative: &Path, content: &str, style: StrmStyle) -> Option<(String, PathBuf)> {
+        let target = content.lines().map(str::trim).find(|line| !line.is_empty())?;
+        let target = match style {
+            StrmStyle::HttpUrl | StrmStyle::AlistSign => target.split_once('?').map_or(target, |(path, _)| path).to_string(),
+            StrmStyle::LocalPath => target.replace('\\', "/"),
+            StrmStyle::Other => target.to_string(),
+        };
+        let stem = relative.with_extension("").to_string_lossy().replace('\\', "/");
+        let (extension_start, extension) = target.rsplit_once('.').filter(|(_, extension)| {
+            !extension.is_empty() && !extension.contains('/')
+        })?;
+
+        // URLs prefer the encoded variable, which renders plain paths the same
+        let variants = match style {
+            StrmStyle::LocalPath => [(stem.clone(), "relative_path"), (encode_url_path(&stem), "relative_path_encoded")],
+            _ => [(encode_url_path(&stem), "relative_path_encoded"), (stem.clone(), "relative_path")],
+        };
+        variants.into_iter().find_map(|(path, variable)| {
+            let prefix = extension_start.strip_suffix(path.as_str())?;
+            let media = PathBuf::from(format!("{}.{}", stem, extension));
+            Some((format!("{}{{{}}}", prefix, variable), media))
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    path::{Component, Path}
+};
+
+use anyhow::{anyhow, Error, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::notification::render_template,
+    infrastructure::network::encode_url_path
+};
+
+/// Variables that can be used in a [`StrmContentTemplate`].
+pub const STRM_TEMPLATE_VARIABLES: [&str; 9] = [
+    "relative_path",
+    "relative_path_encoded",
+    "file_name",
+    "file_name_encoded",
+    "file_stem",
+    "file_stem_encoded",
+    "extension",
+    "parent",
+    "parent_encoded",
+];
+
+/// Template of the content written into `.strm` files.
+///
+/// Placeholders such as `{relative_path}` are replaced with the media
+/// file's path relative to the source, using `/` as separator on every
+/// platform. Each path variable has an `_encoded` variant with every
+/// segment percent-encoded, for use in URLs:
+///
+/// ```text
+/// http://nas:8096/media/{relative_path_encoded}
+/// ```
+///
+/// `{file_name}`, `{file_stem}`, `{extension}` and `{parent}` (the
+/// directory relative to the source) are available as well.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct StrmContentTemplate {
+
+    /// Template text with its placeholders
+    template: String,
+}
+
+impl TryFrom<String> for StrmContentTemplate {
+    type Error = Error;
+
+    fn try_from(template: String) -> Result<Self, Error> {
+        Self::parse(&template)
+    }
+}
+
+impl From<StrmContentTemplate> for String {
+
+    fn from(template: StrmContentTemplate) -> Self {
+        template.template
+    }
+}
+
+impl Display for StrmContentTemplate {
+
+    /// Formats the template as written.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.template)
+    }
+}
+
+impl StrmContentTemplate {
+
+    /// Parses a template.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the template uses an unknown variable,
+    /// which would otherwise end up verbatim in every `.strm` file.
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let placeholder = Regex::new(r"\{([A-Za-z_]+)\}")?;
+        if let Some(unknown) = placeholder
+            .captures_iter(template)
+            .map(|captures| captures[1].to_string())
+            .find(|name| !STRM_TEMPLATE_VARIABLES.contains(&name.as_str()))
+        {
+            return Err(anyhow!(
+                "Unknown strm template variable '{{{}}}', expected one of: {}",
+                unknown,
+                STRM_TEMPLATE_VARIABLES.join(", ")
+            ));
+        }
+
+        Ok(Self { template: template.to_string() })
+    }
+
+    /// Renders the content for a media file.
+    ///
+    /// # Arguments
+    /// * `relative` - Path of the media file relative to the source
+    pub fn render(&self, relative: &Path) -> String {
+        let relative_path = slash_path(relative);
+        let parent = relative.parent().map(slash_path).unwrap_or_default();
+        let file_name = relative
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_stem = relative
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = relative
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let vars = [
+            ("relative_path_encoded", encode_url_path(&relative_path)),
+            ("file_name_encoded", encode_url_path(&file_name)),
+            ("file_stem_encoded", encode_url_path(&file_stem)),
+            ("parent_encoded", encode_url_path(&parent)),
+            ("relative_path", relative_path),
+            ("file_name", file_name),
+            ("file_stem", file_stem),
+            ("extension", extension),
+            ("parent", parent),
+        ];
+        let vars: Vec<(&str, &str)> = vars.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        render_template(&self.template, &vars)
+    }
+}
+
+/// Joins the normal components of a relative path with `/`.
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,215 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::PathBuf
+};
+
+use anyhow::{Context, Error, Result};
+
+use crate::{
+    core::api::StrmAPI,
+    debug_log,
+    infrastructure::{
+        fs::{DirScanner, NaturalOrder},
+        network::NetworkProvider
+    }
+};
+use super::{
+    path_mapping::PathMappings,
+    strm_generator::STRM_EXTENSION
+};
+
+/// Domain identifier for strm validation logs
+const STRM_VALIDATOR_LOGGER_DOMAIN: &str = "[STRM-VALIDATOR]";
+
+/// Problem found in a `.strm` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StrmProblem {
+
+    /// The file has no target
+    Empty,
+
+    /// The content isn't valid UTF-8
+    InvalidEncoding,
+
+    /// The local file the `.strm` points to doesn't exist
+    MissingTarget(PathBuf),
+
+    /// The URL the `.strm` points to can't be reached
+    UnreachableUrl {
+
+        /// URL written in the file
+        url: String,
+
+        /// Why the request failed
+        error: String,
+    },
+}
+
+impl Display for StrmProblem {
+
+    /// Formats the problem as a short description.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StrmProblem::Empty => write!(f, "empty"),
+            StrmProblem::InvalidEncoding => write!(f, "not valid UTF-8"),
+            StrmProblem::MissingTarget(path) => write!(f, "target {} doesn't exist", path.display()),
+            StrmProblem::UnreachableUrl { url, error } => write!(f, "{} unreachable: {}", url, error),
+        }
+    }
+}
+
+/// A `.strm` file that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrmIssue {
+
+    /// Path of the file, relative to the validated directory
+    pub path: PathBuf,
+
+    /// What is wrong with it
+    pub problem: StrmProblem,
+}
+
+impl Display for StrmIssue {
+
+    /// Formats the issue as `<path>: <problem>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}: {}", self.path.display(), self.problem)
+    }
+}
+
+/// Result of [`StrmValidator::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+
+    /// Number of `.strm` files checked
+    pub checked: usize,
+
+    /// Files that failed validation, in natural order of their paths
+    pub issues: Vec<StrmIssue>,
+}
+
+impl ValidationReport {
+
+    /// Returns `true` if every file passed validation.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+
+    /// Formats the report as a summary line followed by one line per issue.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} strm files checked, {} invalid", self.checked, self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the `.strm` files below a directory.
+///
+/// Each file must contain a target on its first non-empty line, encoded
+/// as UTF-8. Local targets must exist; URLs are only requested when
+/// [`with_url_check`](Self::with_url_check) is enabled, since checking a
+/// large library sends one `HEAD` request per file. Other schemes, such
+/// as `rtsp://`, aren't checked.
+pub struct StrmValidator {
+
+    /// Directory holding the `.strm` files
+    root: PathBuf,
+
+    /// Mappings applied to local targets before checking them
+    path_mappings: PathMappings,
+
+    /// Provider used to check URLs, `None` to skip them
+    provider: Option<NetworkProvider>,
+}
+
+impl StrmValidator {
+
+    /// Creates a validator for the `.strm` files below `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            path_mappings: PathMappings::default(),
+            provider: None,
+        }
+    }
+
+    /// Sets the mappings applied to local targets before checking them,
+    /// e.g. from the media server's mount point back to this host's.
+    pub fn with_path_mappings(mut self, path_mappings: PathMappings) -> Self {
+        self.path_mappings = path_mappings;
+        self
+    }
+
+    /// Sets whether URL targets are checked with a `HEAD` request.
+    pub fn with_url_check(mut self, check_urls: bool) -> Self {
+        self.provider = check_urls.then(|| NetworkProvider::new(Vec::new()));
+        self
+    }
+
+    /// Checks every `.strm` file below the root.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the directory or a file can't be read.
+    pub async fn validate(&self) -> Result<ValidationReport, Error> {
+        let mut report = ValidationReport::default();
+        for file in DirScanner::scan(&self.root)? {
+            if !file.relative.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(STRM_EXTENSION)) {
+                continue;
+            }
+
+            report.checked += 1;
+            let path = self.root.join(&file.relative);
+            let content = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            if let Some(problem) = self.check(&content).await {
+                debug_log!(STRM_VALIDATOR_LOGGER_DOMAIN, format!("{}: {}", path.display(), problem));
+                report.issues.push(StrmIssue { path: file.relative, problem });
+            }
+        }
+        NaturalOrder::sort_by_key(&mut report.issues, |issue| issue.path.to_str().unwrap_or_default());
+        Ok(report)
+    }
+
+    /// Checks the content of a `.strm` file.
+    ///
+    /// # Returns
+    /// The problem found, `None` if the file is valid.
+    async fn check(&self, content: &[u8]) -> Option<StrmProblem> {
+        let Ok(content) = std::str::from_utf8(content) else {
+            return Some(StrmProblem::InvalidEncoding);
+        };
+        let Some(target) = content.lines().map(str::trim).find(|line| !line.is_empty()) else {
+            return Some(StrmProblem::Empty);
+        };
+
+        let lowercase = target.to_ascii_lowercase();
+        if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+            let provider = self.provider.as_ref()?;
+            let error = match provider.send_request(&StrmAPI::Probe { url: target.to_string() }).await {
+                Ok(response) if Self::is_reachable(response.status()) => return None,
+                Ok(response) => format!("status {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            return Some(StrmProblem::UnreachableUrl { url: target.to_string(), error });
+        }
+        if target.contains("://") {
+            return None;
+        }
+
+        let local = PathBuf::from(self.path_mappings.apply(target));
+        (!local.exists()).then_some(StrmProblem::MissingTarget(local))
+    }
+
+    /// Returns `true` if a status shows the URL is served.
+    ///
+    /// Some servers only implement `GET` and refuse `HEAD`, which still
+    /// shows the URL is reachable.
+    fn is_reachable(status: reqwest::StatusCode) -> bool {
+        status.is_success() || status.is_redirection() || status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+    }
+}
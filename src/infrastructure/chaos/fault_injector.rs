@@ -0,0 +1,144 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Error, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::warn_log;
+
+/// Domain identifier for failure injection logs
+const CHAOS_LOGGER_DOMAIN: &str = "[CHAOS]";
+
+/// Rates in use, replaced with [`FaultInjector::configure`].
+static RATES: Lazy<RwLock<FaultRates>> = Lazy::new(|| RwLock::new(FaultRates::default()));
+
+/// State of the random generator deciding which operations fail.
+static SEED: Lazy<AtomicU64> = Lazy::new(|| {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default();
+    AtomicU64::new(nanos)
+});
+
+/// How often each simulated failure happens, as probabilities from 0 to 1.
+///
+/// Every rate defaults to 0, so nothing is injected until configured.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FaultRates {
+
+    /// Probability that a sync strategy run fails before transferring
+    pub strategy_error_rate: f64,
+
+    /// Probability that a sync is delayed before transferring
+    pub slow_io_rate: f64,
+
+    /// Delay of a slowed down sync, in milliseconds
+    pub slow_io_delay_ms: u64,
+
+    /// Probability that a filesystem watcher event is dropped
+    pub dropped_event_rate: f64,
+}
+
+impl FaultRates {
+
+    /// Returns `true` if no failure is injected.
+    pub fn is_disabled(&self) -> bool {
+        self.strategy_error_rate <= 0.0 && self.slow_io_rate <= 0.0 && self.dropped_event_rate <= 0.0
+    }
+
+    /// Checks that every rate is a probability.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` naming the first rate outside `0..=1`.
+    pub fn validate(&self) -> Result<(), Error> {
+        let rates = [
+            ("strategy_error_rate", self.strategy_error_rate),
+            ("slow_io_rate", self.slow_io_rate),
+            ("dropped_event_rate", self.dropped_event_rate),
+        ];
+        match rates.iter().find(|(_, rate)| !(0.0..=1.0).contains(rate)) {
+            Some((name, rate)) => Err(anyhow!("{} must be between 0 and 1, got {}", name, rate)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Process-wide injection of simulated failures.
+///
+/// Sync and watcher code ask the injector before acting, so configuring
+/// rates makes a share of syncs fail or stall and a share of events
+/// vanish, as they would on a flaky network or an overloaded host. This
+/// verifies that alerts fire and that retries, circuit breakers and
+/// periodic rescans recover as configured.
+pub struct FaultInjector;
+
+impl FaultInjector {
+
+    /// Replaces the rates in use.
+    pub fn configure(rates: FaultRates) {
+        if !rates.is_disabled() {
+            warn_log!(CHAOS_LOGGER_DOMAIN, format!("Injecting simulated failures: {:?}", rates));
+        }
+        *RATES.write().unwrap_or_else(|e| e.into_inner()) = rates;
+    }
+
+    /// Returns the rates in use.
+    pub fn rates() -> FaultRates {
+        RATES.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Simulates the faults of a sync to `destination`: sleeps if slow
+    /// I/O is drawn, then fails if a strategy error is drawn.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` when a strategy error is injected.
+    pub fn before_sync(destination: &str) -> Result<(), Error> {
+        let rates = Self::rates();
+        if Self::roll(rates.slow_io_rate) {
+            warn_log!(
+                CHAOS_LOGGER_DOMAIN,
+                format!("Delaying sync to {} by {}ms", destination, rates.slow_io_delay_ms)
+            );
+            thread::sleep(Duration::from_millis(rates.slow_io_delay_ms));
+        }
+        if Self::roll(rates.strategy_error_rate) {
+            warn_log!(CHAOS_LOGGER_DOMAIN, format!("Failing sync to {}", destination));
+            return Err(anyhow!("Injected strategy failure for {}", destination));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a watcher event must be dropped.
+    pub fn drop_event() -> bool {
+        let dropped = Self::roll(Self::rates().dropped_event_rate);
+        if dropped {
+            warn_log!(CHAOS_LOGGER_DOMAIN, "Dropping watcher event");
+        }
+        dropped
+    }
+
+    /// Draws whether an event of probability `rate` happens.
+    fn roll(rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+        if rate >= 1.0 {
+            return true;
+        }
+        // splitmix64: statistically sound and needs no dependency
+        let mut z = SEED.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        ((z >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+}
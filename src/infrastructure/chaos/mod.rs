@@ -0,0 +1,10 @@
+//! Failure injection for testing alerting and retries (`chaos` feature).
+//!
+//! This module simulates operational failures at configurable rates:
+//! - Failed sync strategy runs
+//! - Slow I/O before transfers
+//! - Dropped filesystem watcher events
+//! 
+pub mod fault_injector;
+
+pub use fault_injector::*;
@@ -0,0 +1,125 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+/// A common failure with a known remediation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorHint {
+
+    /// `sshpass` isn't installed but password authentication is configured
+    SshpassMissing,
+
+    /// `rsync` isn't installed
+    RsyncMissing,
+
+    /// The OpenSSH client isn't installed
+    SshMissing,
+
+    /// The inotify watch limit is exhausted
+    InotifyLimit,
+
+    /// The process ran out of file descriptors
+    FdLimit,
+
+    /// The SSH server rejected the configured credentials
+    SshAuthFailed,
+
+    /// The remote host can't be reached
+    HostUnreachable,
+
+    /// A transfer was killed after making no progress or running too long
+    TransferStuck,
+
+    /// A disk ran out of space
+    DiskFull,
+
+    /// The destination filesystem is mounted read-only
+    ReadOnlyFilesystem,
+
+    /// The process lacks permission to access a path
+    PermissionDenied,
+}
+
+impl ErrorHint {
+
+    /// Classifies an error by inspecting its whole context chain.
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        Self::classify_message(&format!("{:#}", error))
+    }
+
+    /// Classifies an error message.
+    ///
+    /// More specific failures are checked first, so a missing `sshpass` isn't
+    /// reported as a missing `ssh` and an inotify limit isn't reported as a
+    /// full disk.
+    pub fn classify_message(message: &str) -> Option<Self> {
+        let message = message.to_lowercase();
+        let contains = |pattern: &str| message.contains(pattern);
+
+        if contains("failed to start sshpass") {
+            Some(ErrorHint::SshpassMissing)
+        } else if contains("failed to start rsync") || contains("rsync: command not found") {
+            Some(ErrorHint::RsyncMissing)
+        } else if contains("failed to start ssh") {
+            Some(ErrorHint::SshMissing)
+        } else if contains("file watch limit")
+            || (contains("failed to watch") && contains("os error 28")) {
+            Some(ErrorHint::InotifyLimit)
+        } else if contains("too many open files") || contains("os error 24") {
+            Some(ErrorHint::FdLimit)
+        } else if contains("permission denied (publickey") || contains("permission denied, please try again") {
+            Some(ErrorHint::SshAuthFailed)
+        } else if contains("connection refused")
+            || contains("no route to host")
+            || contains("could not resolve hostname")
+            || contains("connection timed out") {
+            Some(ErrorHint::HostUnreachable)
+        } else if contains("killed as stuck") || contains("and was killed") {
+            Some(ErrorHint::TransferStuck)
+        } else if contains("no space left on device") || contains("not enough free space") {
+            Some(ErrorHint::DiskFull)
+        } else if contains("read-only file system") {
+            Some(ErrorHint::ReadOnlyFilesystem)
+        } else if contains("permission denied") {
+            Some(ErrorHint::PermissionDenied)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the remediation shown to the user.
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            ErrorHint::SshpassMissing => "sshpass is not installed, install it or switch to key authentication",
+            ErrorHint::RsyncMissing => "rsync is not installed, install it on both the local and the remote host",
+            ErrorHint::SshMissing => "the OpenSSH client is not installed, install openssh-client",
+            ErrorHint::InotifyLimit => "the inotify watch limit is reached, raise fs.inotify.max_user_watches (e.g. sysctl fs.inotify.max_user_watches=524288)",
+            ErrorHint::FdLimit => "the open file limit is reached, raise it (e.g. ulimit -n 65536 or LimitNOFILE= in the systemd unit) or lower scan_parallelism and transfer_concurrency",
+            ErrorHint::SshAuthFailed => "the SSH server rejected the credentials, check the username, key path or password",
+            ErrorHint::HostUnreachable => "the remote host can't be reached, check the address, port and network",
+            ErrorHint::TransferStuck => "the transfer stopped making progress or ran too long, check the connection to the remote host or raise stall_timeout_secs or transfer_timeout_secs",
+            ErrorHint::DiskFull => "the disk is full, free up space at the destination or its temp_dir",
+            ErrorHint::ReadOnlyFilesystem => "the destination is mounted read-only, remount it read-write",
+            ErrorHint::PermissionDenied => "permission denied, check the ownership and permissions of the source and destination",
+        }
+    }
+
+    /// Formats an error followed by its remediation hint, if one applies.
+    pub fn describe(error: &anyhow::Error) -> String {
+        match Self::classify(error) {
+            Some(hint) => format!("{:#} (hint: {})", error, hint),
+            None => format!("{:#}", error),
+        }
+    }
+}
+
+impl Display for ErrorHint {
+
+    /// Formats the remediation.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.remediation())
+    }
+}
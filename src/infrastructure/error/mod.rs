@@ -0,0 +1,19 @@
+//! Error classification for user-facing messages.
+//!
+//! This module recognizes common operational failures and attaches
+//! remediation hints to them:
+//! - Missing external tools (rsync, ssh, sshpass)
+//! - Exhausted inotify watch limits
+//! - SSH authentication and connectivity problems
+//! - Full disks and permission errors
+//!
+//! It also classifies the transient failures worth retrying a sync after
+//! and installs a panic hook that reports crashes.
+//! 
+pub mod hint;
+pub mod panic_hook;
+pub mod retry;
+
+pub use hint::*;
+pub use panic_hook::*;
+pub use retry::*;
@@ -0,0 +1,148 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    thread
+};
+
+use anyhow::{Error, Result};
+use time::{macros::format_description, OffsetDateTime};
+
+use crate::error_log;
+
+/// Domain identifier for crash logs
+const CRASH_LOGGER_DOMAIN: &str = "[CRASH]";
+
+/// Callback informed about every panic, e.g. to send a notification.
+pub type CrashNotifier = Box<dyn Fn(&CrashReport) + Send + Sync + 'static>;
+
+/// Details of a panic, captured by the panic hook.
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+
+    /// Panic message
+    pub message: String,
+
+    /// Source location of the panic, if known
+    pub location: Option<String>,
+
+    /// Name of the panicking thread
+    pub thread: String,
+
+    /// UTC time of the panic
+    pub timestamp: OffsetDateTime,
+
+    /// Captured backtrace
+    pub backtrace: String,
+}
+
+impl CrashReport {
+
+    /// Captures the report for a panic, including a backtrace.
+    pub fn capture(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload.downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        Self {
+            message,
+            location: info.location().map(|location| location.to_string()),
+            thread: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            timestamp: OffsetDateTime::now_utc(),
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+
+    /// Writes the report to a new `crash-<timestamp>.log` file in `dir`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let stamp = self.timestamp
+            .format(format_description!("[year][month][day]-[hour][minute][second]"))?;
+        let path = dir.join(format!("crash-{}-{}.log", stamp, self.timestamp.nanosecond()));
+        fs::write(&path, format!("{}\n\nBacktrace:\n{}", self, self.backtrace))?;
+        Ok(path)
+    }
+}
+
+impl Display for CrashReport {
+
+    /// Formats a one-line summary without the backtrace.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "Thread '{}' panicked at {}: {}",
+            self.thread,
+            self.location.as_deref().unwrap_or("<unknown>"),
+            self.message
+        )
+    }
+}
+
+/// Installs a process-wide panic hook reporting crashes.
+///
+/// On every panic, the hook:
+/// - Logs the panic and its backtrace through the logger
+/// - Writes a crash report file, if a report directory is set
+/// - Calls the crash notifier, if one is set
+///
+/// The previous hook (by default, printing to stderr) still runs afterwards.
+#[derive(Default)]
+pub struct PanicHook {
+
+    /// Directory crash reports are written to
+    report_dir: Option<PathBuf>,
+
+    /// Callback informed about every panic
+    notifier: Option<CrashNotifier>,
+}
+
+impl PanicHook {
+
+    /// Creates a hook that only logs panics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the directory crash reports are written to (builder pattern).
+    pub fn with_report_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.report_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the callback informed about every panic (builder pattern).
+    pub fn with_notifier(mut self, notifier: CrashNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Installs the hook, replacing the current one but chaining to it.
+    pub fn install(self) {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let report = CrashReport::capture(info);
+            error_log!(CRASH_LOGGER_DOMAIN, format!("{}\n{}", report, report.backtrace));
+
+            if let Some(dir) = &self.report_dir {
+                let result = report.write_to(dir)
+                    .map(|path| format!("Crash report written to {}", path.display()))
+                    .unwrap_or_else(|e| format!("Failed to write crash report: {}", e));
+                error_log!(CRASH_LOGGER_DOMAIN, result);
+            }
+
+            if let Some(notifier) = &self.notifier {
+                notifier(&report);
+            }
+
+            previous(info);
+        }));
+    }
+}
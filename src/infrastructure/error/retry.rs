@@ -0,0 +1,90 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::network::NetworkError,
+    hint::ErrorHint
+};
+
+/// Exit codes rsync reports for failed connections and timeouts, as
+/// written in its error messages: socket I/O, timeout and daemon
+/// connection timeout. Protocol data stream errors (code 12) are left
+/// out, as a full disk or a crashed remote rsync also cause them.
+const RSYNC_NETWORK_ERROR_CODES: [&str; 3] = ["(code 10)", "(code 30)", "(code 35)"];
+
+/// A class of transient failure worth retrying a sync after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RetryClass {
+
+    /// Unreachable hosts, reset connections and timeouts of rsync, SSH
+    /// and HTTP requests
+    Network,
+
+    /// HTTP `429 Too Many Requests` and `5xx` responses of upload and S3
+    /// servers
+    Server,
+}
+
+impl RetryClass {
+
+    /// Classifies an error by inspecting its whole context chain.
+    ///
+    /// # Returns
+    /// `None` for errors retrying won't fix, such as missing tools,
+    /// rejected credentials or full disks.
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        let network = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<NetworkError>())
+            .any(|e| e.is_timeout() || e.is_connect());
+        if network {
+            return Some(RetryClass::Network);
+        }
+        Self::classify_message(&format!("{:#}", error))
+    }
+
+    /// Classifies an error message.
+    pub fn classify_message(message: &str) -> Option<Self> {
+        if ErrorHint::classify_message(message) == Some(ErrorHint::HostUnreachable) {
+            return Some(RetryClass::Network);
+        }
+
+        let message = message.to_lowercase();
+        let contains = |pattern: &str| message.contains(pattern);
+        if contains("connection reset")
+            || contains("broken pipe")
+            || contains("connection unexpectedly closed")
+            || contains("timed out")
+            || RSYNC_NETWORK_ERROR_CODES.iter().any(|code| contains(code)) {
+            Some(RetryClass::Network)
+        } else if Self::server_status(&message).is_some_and(|status| status == 429 || (500..600).contains(&status)) {
+            Some(RetryClass::Server)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the HTTP status of a `failed with status <code>` message.
+    fn server_status(message: &str) -> Option<u16> {
+        let (_, status) = message.split_once("failed with status ")?;
+        status.get(..3)?.parse().ok()
+    }
+}
+
+impl Display for RetryClass {
+
+    /// Formats the class as written in configuration files.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RetryClass::Network => write!(f, "network"),
+            RetryClass::Server => write!(f, "server"),
+        }
+    }
+}
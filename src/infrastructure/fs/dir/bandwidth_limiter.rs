@@ -0,0 +1,72 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant}
+};
+
+use super::super::file::ByteSize;
+
+/// Token bucket state shared by the transfers of a limiter.
+#[derive(Debug)]
+struct Bucket {
+
+    /// Bytes that may be sent without waiting; negative once transfers
+    /// have borrowed ahead of the rate
+    tokens: f64,
+
+    /// When the tokens were last refilled
+    refilled: Instant,
+}
+
+/// Token bucket limiting the transfer rate of native copies, like
+/// rsync's `--bwlimit`.
+///
+/// The bucket holds one second of transfer, so short bursts go through
+/// at full speed and long transfers settle at the rate. A chunk larger
+/// than the bucket is let through and paid back by the chunks after it.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+
+    /// Bytes per second
+    rate: f64,
+
+    /// Tokens available to transfers
+    bucket: Mutex<Bucket>,
+}
+
+impl BandwidthLimiter {
+
+    /// Creates a limiter allowing `rate` bytes per second.
+    ///
+    /// # Returns
+    /// `None` for a zero rate, which means unlimited.
+    pub fn new(rate: ByteSize) -> Option<Self> {
+        let rate = rate.bytes() as f64;
+        (rate > 0.0).then(|| Self {
+            rate,
+            bucket: Mutex::new(Bucket { tokens: rate, refilled: Instant::now() }),
+        })
+    }
+
+    /// Takes `bytes` from the bucket and returns how long the caller must
+    /// wait before sending them.
+    pub fn reserve(&self, bytes: u64) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate) - bytes as f64;
+        bucket.refilled = now;
+        if bucket.tokens < 0.0 {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Waits until `bytes` may be sent.
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = self.reserve(bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
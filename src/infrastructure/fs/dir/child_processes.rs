@@ -0,0 +1,110 @@
+use std::{
+    collections::HashSet,
+    process::Command,
+    sync::Mutex
+};
+
+use once_cell::sync::Lazy;
+
+use crate::debug_log;
+
+/// Domain identifier for child process logs
+const CHILD_PROCESSES_LOGGER_DOMAIN: &str = "[CHILD-PROCESSES]";
+
+/// Process group IDs of the transfers still running.
+static RUNNING_GROUPS: Lazy<Mutex<HashSet<u32>>> = Lazy::new(|| {
+    Mutex::new(HashSet::new())
+});
+
+/// Transfer processes started in their own process group, so that they
+/// and everything they spawned, such as SSH connections, can be killed
+/// together.
+///
+/// A process in its own group no longer receives the terminal's Ctrl+C,
+/// so the running groups are tracked and [`kill_all`](Self::kill_all)
+/// must be called on cancellation and shutdown to leave no orphaned
+/// transfers behind.
+pub struct ChildProcesses;
+
+impl ChildProcesses {
+
+    /// Starts a command in a new process group (`setpgid`).
+    #[cfg(unix)]
+    pub fn isolate(cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    /// Starts a command in a new process group, away from the console's
+    /// Ctrl+C.
+    #[cfg(windows)]
+    pub fn isolate(cmd: &mut Command) {
+        use std::os::windows::process::CommandExt;
+        /// `CREATE_NEW_PROCESS_GROUP` process creation flag.
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+
+    /// Process groups aren't supported on other platforms.
+    #[cfg(not(any(unix, windows)))]
+    pub fn isolate(_cmd: &mut Command) {}
+
+    /// Tracks a process started with [`isolate`](Self::isolate) until the
+    /// returned guard is dropped, which should happen once it was waited for.
+    pub fn track(pid: u32) -> TrackedGroup {
+        RUNNING_GROUPS.lock().unwrap_or_else(|e| e.into_inner()).insert(pid);
+        TrackedGroup { pid }
+    }
+
+    /// Kills every tracked process group.
+    ///
+    /// # Returns
+    /// The number of groups killed.
+    pub fn kill_all() -> usize {
+        let groups: Vec<u32> = RUNNING_GROUPS.lock().unwrap_or_else(|e| e.into_inner()).drain().collect();
+        for pid in &groups {
+            debug_log!(CHILD_PROCESSES_LOGGER_DOMAIN, format!("Killing process group {}", pid));
+            Self::kill_tree(*pid);
+        }
+        groups.len()
+    }
+
+    /// Returns the number of tracked process groups.
+    pub fn running() -> usize {
+        RUNNING_GROUPS.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Kills a process group (`killpg`).
+    #[cfg(unix)]
+    pub fn kill_tree(pid: u32) {
+        if let Ok(pid) = libc::pid_t::try_from(pid) {
+            unsafe {
+                libc::killpg(pid, libc::SIGKILL);
+            }
+        }
+    }
+
+    /// Kills a process and its descendants.
+    #[cfg(not(unix))]
+    pub fn kill_tree(pid: u32) {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .status();
+    }
+}
+
+/// A process group tracked by [`ChildProcesses`], forgotten when dropped.
+#[derive(Debug)]
+pub struct TrackedGroup {
+
+    /// ID of the group's leader
+    pid: u32,
+}
+
+impl Drop for TrackedGroup {
+
+    /// Stops tracking the group.
+    fn drop(&mut self) {
+        RUNNING_GROUPS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.pid);
+    }
+}
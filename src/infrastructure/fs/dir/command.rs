@@ -0,0 +1,89 @@
+use std::{
+    io::Read,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+/// Interval between checks whether a command has finished.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Result of an external command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandOutput {
+
+    /// Exit code of the command, `None` if it was killed by a signal
+    pub status: Option<i32>,
+
+    /// Captured standard output
+    pub stdout: String,
+
+    /// Captured standard error
+    pub stderr: String,
+}
+
+impl CommandOutput {
+
+    /// Returns `true` if the command exited with status 0.
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Runs a command, capturing its output and killing it after `timeout`.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the command can't be started or times out.
+pub(crate) fn run_with_timeout(mut cmd: Command, timeout: Duration) -> Result<CommandOutput, Error> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait_with_timeout(&mut child, timeout)?;
+
+    Ok(CommandOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Quotes a value for a POSIX shell.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Reads a pipe to the end on a separate thread.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+/// Waits for a process, killing it once `timeout` elapses.
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Option<i32>, Error> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status.code());
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("timed out after {:?}", timeout));
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    }
+}
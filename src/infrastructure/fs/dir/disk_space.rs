@@ -0,0 +1,200 @@
+use std::{
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::debug_log;
+use super::{
+    super::file::ByteSize,
+    command::shell_quote,
+    location::DirLocation,
+    ssh_runner::SshRunner,
+    sync_config::DirSyncConfig
+};
+
+/// Domain identifier for disk space logs
+const DISK_SPACE_LOGGER_DOMAIN: &str = "[DISK-SPACE]";
+
+/// Free space and filesystem checks run before a sync writes anything.
+pub struct DiskSpace;
+
+impl DiskSpace {
+
+    /// Checks that a sync can stage its files and has room to write them.
+    ///
+    /// The temporary directory, if configured, is created and must be on
+    /// the destination's filesystem; a relative one is relative to the
+    /// destination, as for rsync, so partial files can be renamed into
+    /// place instead of being copied a second time. The destination and
+    /// the temporary directory must both have the configured free space.
+    /// Remote destinations are checked over SSH; rsync daemon modules
+    /// aren't checked.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a directory has too little free space,
+    /// the temporary directory is on another filesystem or can't be
+    /// created, or a check itself failed.
+    pub fn preflight(config: &DirSyncConfig) -> Result<(), Error> {
+        let destination = config.get_destination();
+        let path = destination.get_path();
+        if path.starts_with("rsync://") {
+            return Ok(());
+        }
+        let temp_dir = config.get_resolved_temp_dir();
+        let min_free_space = config.get_min_free_space();
+
+        match destination.ssh_config() {
+            Some(ssh_config) => {
+                let runner = SshRunner::new(ssh_config.clone());
+                let remote_path = path.split_once(':').map_or(path.as_str(), |(_, path)| path);
+                if let Some(temp_dir) = &temp_dir {
+                    let created = runner.run(&format!("mkdir -p {}", shell_quote(temp_dir)))?;
+                    if !created.success() {
+                        return Err(anyhow!("Failed to create temporary directory '{}': {}", temp_dir, created.stderr.trim()));
+                    }
+                    if runner.mount_point(temp_dir)? != runner.mount_point(remote_path)? {
+                        return Err(Self::different_filesystems(temp_dir, &path));
+                    }
+                }
+                if let Some(required) = min_free_space {
+                    for dir in std::iter::once(remote_path).chain(temp_dir.as_deref()) {
+                        Self::require(dir, runner.free_space(dir)?, required)?;
+                    }
+                }
+            }
+            None => {
+                let path = PathBuf::from(&path);
+                if let Some(temp_dir) = &temp_dir {
+                    fs::create_dir_all(temp_dir)
+                        .with_context(|| format!("Failed to create temporary directory '{}'", temp_dir))?;
+                    if !Self::same_filesystem(Path::new(temp_dir), &path)? {
+                        return Err(Self::different_filesystems(temp_dir, &path.to_string_lossy()));
+                    }
+                }
+                if let Some(required) = min_free_space {
+                    for dir in std::iter::once(path.as_path()).chain(temp_dir.as_deref().map(Path::new)) {
+                        match Self::available(dir) {
+                            Ok(available) => Self::require(&dir.to_string_lossy(), available, required)?,
+                            Err(e) => {
+                                debug_log!(DISK_SPACE_LOGGER_DOMAIN, format!("Skipping free space check: {}", e));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a local or SSH location has the required free space.
+    ///
+    /// rsync daemon modules aren't checked.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the location has too little free space
+    /// or its free space can't be read.
+    pub fn check(location: &DirLocation, required: ByteSize) -> Result<(), Error> {
+        let path = location.get_path();
+        if path.starts_with("rsync://") {
+            return Ok(());
+        }
+        match location.ssh_config() {
+            Some(ssh_config) => {
+                let remote_path = path.split_once(':').map_or(path.as_str(), |(_, path)| path);
+                Self::require(remote_path, SshRunner::new(ssh_config.clone()).free_space(remote_path)?, required)
+            }
+            None => Self::require(&path, Self::available(Path::new(&path))?, required),
+        }
+    }
+
+    /// Returns the space in bytes available to unprivileged users on the
+    /// filesystem holding `path`, or its closest existing parent.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if no parent exists or `statvfs` fails.
+    #[cfg(unix)]
+    pub fn available(path: &Path) -> Result<u64, Error> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let dir = Self::existing_ancestor(path)?;
+        let c_path = CString::new(dir.as_os_str().as_bytes())?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Failed to read free space of {}", dir.display()));
+        }
+        #[allow(clippy::useless_conversion)]
+        Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+    }
+
+    /// Free space can't be read on this platform.
+    ///
+    /// # Errors
+    /// Always returns `anyhow::Error`.
+    #[cfg(not(unix))]
+    pub fn available(path: &Path) -> Result<u64, Error> {
+        Err(anyhow!("Free space of {} can't be read on this platform", path.display()))
+    }
+
+    /// Returns `true` if two paths, or their closest existing parents,
+    /// are on the same filesystem.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a path has no existing parent.
+    #[cfg(unix)]
+    pub fn same_filesystem(a: &Path, b: &Path) -> Result<bool, Error> {
+        use std::os::unix::fs::MetadataExt;
+
+        let device = |path: &Path| -> Result<u64, Error> {
+            let dir = Self::existing_ancestor(path)?;
+            Ok(fs::metadata(&dir).with_context(|| format!("Failed to read {}", dir.display()))?.dev())
+        };
+        Ok(device(a)? == device(b)?)
+    }
+
+    /// Returns `true` if two paths are on the same drive or share.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a path has no existing parent.
+    #[cfg(not(unix))]
+    pub fn same_filesystem(a: &Path, b: &Path) -> Result<bool, Error> {
+        let root = |path: &Path| -> Result<Option<std::ffi::OsString>, Error> {
+            let dir = fs::canonicalize(Self::existing_ancestor(path)?)?;
+            Ok(dir.components().next().map(|prefix| prefix.as_os_str().to_ascii_lowercase()))
+        };
+        Ok(root(a)? == root(b)?)
+    }
+
+    /// Fails if less than the required space is available.
+    fn require(dir: &str, available: u64, required: ByteSize) -> Result<(), Error> {
+        if available < required.bytes() {
+            return Err(anyhow!(
+                "Not enough free space in '{}': {} available, {} required",
+                dir,
+                ByteSize(available),
+                required
+            ));
+        }
+        Ok(())
+    }
+
+    /// Describes a temporary directory partial files can't be renamed from.
+    fn different_filesystems(temp_dir: &str, destination: &str) -> Error {
+        anyhow!(
+            "Temporary directory '{}' is not on the filesystem of destination '{}', so files can't be renamed into place",
+            temp_dir,
+            destination
+        )
+    }
+
+    /// Returns the closest existing directory of a path, the path itself
+    /// if it exists; relative paths end at the working directory.
+    fn existing_ancestor(path: &Path) -> Result<PathBuf, Error> {
+        path.ancestors()
+            .find(|dir| dir.as_os_str().is_empty() || dir.exists())
+            .map(|dir| if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir.to_path_buf() })
+            .ok_or_else(|| anyhow!("No parent of {} exists", path.display()))
+    }
+}
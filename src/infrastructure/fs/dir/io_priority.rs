@@ -0,0 +1,195 @@
+use std::{
+    env,
+    process::Command
+};
+
+use anyhow::{anyhow, Error, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::debug_log;
+
+/// Domain identifier for I/O priority logs
+const IO_PRIORITY_LOGGER_DOMAIN: &str = "[IO-PRIORITY]";
+
+/// Whether `ionice` can be found on the `PATH`.
+static IONICE_AVAILABLE: Lazy<bool> = Lazy::new(|| program_available("ionice"));
+
+/// Whether `nice` can be found on the `PATH`.
+static NICE_AVAILABLE: Lazy<bool> = Lazy::new(|| program_available("nice"));
+
+/// I/O scheduling class, as understood by `ionice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoClass {
+
+    /// Shares disk time with other processes according to the level
+    BestEffort,
+
+    /// Only gets disk time when no other process needs it
+    Idle,
+}
+
+impl IoClass {
+
+    /// Returns the class number used by `ionice` and `ioprio_set`.
+    fn number(&self) -> u32 {
+        match self {
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        }
+    }
+}
+
+/// CPU and disk priority of sync work.
+///
+/// Keeps full-library syncs from starving media playback on the same
+/// machine. External commands such as rsync are started through `nice`
+/// and `ionice`, which are skipped when not installed; native transfers
+/// lower the priority of the threads running them, which is only
+/// supported on Linux.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IoPriority {
+
+    /// Niceness added to the CPU priority, from -20 (highest) to 19 (lowest)
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class
+    pub io_class: Option<IoClass>,
+
+    /// Level within the best-effort class, from 0 (highest) to 7 (lowest)
+    pub io_level: Option<u8>,
+}
+
+impl IoPriority {
+
+    /// Sets the niceness (builder pattern).
+    pub fn with_nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Sets the I/O scheduling class (builder pattern).
+    pub fn with_io_class(mut self, io_class: IoClass) -> Self {
+        self.io_class = Some(io_class);
+        self
+    }
+
+    /// Sets the level within the best-effort class (builder pattern).
+    pub fn with_io_level(mut self, io_level: u8) -> Self {
+        self.io_level = Some(io_level);
+        self
+    }
+
+    /// Returns `true` if no priority is changed.
+    pub fn is_default(&self) -> bool {
+        self.nice.is_none() && self.io_class.is_none() && self.io_level.is_none()
+    }
+
+    /// Checks that the values are within the ranges the kernel accepts.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` describing the first invalid value.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(nice) = self.nice.filter(|nice| !(-20..=19).contains(nice)) {
+            return Err(anyhow!("nice must be between -20 and 19, got {}", nice));
+        }
+        if let Some(level) = self.io_level.filter(|level| *level > 7) {
+            return Err(anyhow!("io_level must be between 0 and 7, got {}", level));
+        }
+        if self.io_level.is_some() && self.io_class == Some(IoClass::Idle) {
+            return Err(anyhow!("io_level only applies to the best_effort io_class"));
+        }
+        Ok(())
+    }
+
+    /// Returns the I/O class, which defaults to best-effort when only a level is set.
+    fn effective_io_class(&self) -> Option<IoClass> {
+        self.io_class.or(self.io_level.map(|_| IoClass::BestEffort))
+    }
+
+    /// Creates a command running `program` with this priority.
+    ///
+    /// The program is prefixed with `ionice` and `nice` as needed, so
+    /// every process it starts inherits the priority.
+    pub fn command(&self, program: &str) -> Command {
+        let mut prefix: Vec<String> = Vec::new();
+
+        if let Some(class) = self.effective_io_class() {
+            if *IONICE_AVAILABLE {
+                prefix.extend(["ionice".to_string(), "-c".to_string(), class.number().to_string()]);
+                if let Some(level) = self.io_level {
+                    prefix.extend(["-n".to_string(), level.to_string()]);
+                }
+            } else {
+                debug_log!(IO_PRIORITY_LOGGER_DOMAIN, "ionice not found, running without I/O priority");
+            }
+        }
+
+        if let Some(nice) = self.nice {
+            if *NICE_AVAILABLE {
+                prefix.extend(["nice".to_string(), "-n".to_string(), nice.to_string()]);
+            } else {
+                debug_log!(IO_PRIORITY_LOGGER_DOMAIN, "nice not found, running without CPU priority");
+            }
+        }
+
+        let Some((wrapper, args)) = prefix.split_first() else {
+            return Command::new(program);
+        };
+        let mut cmd = Command::new(wrapper);
+        cmd.args(args).arg(program);
+        cmd
+    }
+
+    /// Applies this priority to the calling thread.
+    ///
+    /// Threads started afterwards by the calling thread inherit it, which
+    /// makes it suitable as a runtime's `on_thread_start` hook.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the kernel rejects the priority, such as
+    /// a negative niceness without privileges.
+    #[cfg(target_os = "linux")]
+    pub fn apply_to_current_thread(&self) -> Result<(), Error> {
+        // SAFETY: gettid has no preconditions
+        let tid = unsafe { libc::gettid() };
+
+        if let Some(nice) = self.nice {
+            // SAFETY: setpriority only reads its integer arguments
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+                return Err(anyhow!("Failed to set niceness {}: {}", nice, std::io::Error::last_os_error()));
+            }
+        }
+
+        if let Some(class) = self.effective_io_class() {
+            // IOPRIO_WHO_PROCESS, with the class in the top bits of the priority
+            let priority = (class.number() << 13) | u32::from(self.io_level.unwrap_or(4));
+            // SAFETY: ioprio_set only reads its integer arguments
+            if unsafe { libc::syscall(libc::SYS_ioprio_set, 1, tid, priority) } != 0 {
+                return Err(anyhow!("Failed to set I/O priority: {}", std::io::Error::last_os_error()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies this priority to the calling thread.
+    ///
+    /// Thread priorities are only supported on Linux, so this logs and
+    /// does nothing elsewhere.
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply_to_current_thread(&self) -> Result<(), Error> {
+        if !self.is_default() {
+            debug_log!(IO_PRIORITY_LOGGER_DOMAIN, "Thread priorities are only supported on Linux");
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if an executable named `program` is on the `PATH`.
+fn program_available(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
@@ -0,0 +1,388 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use super::scanner::{DirScanner, ScannedFile};
+
+/// Leading bytes identifying a listing cache file.
+const LISTING_CACHE_MAGIC: &[u8; 4] = b"PLLC";
+
+/// Version of the binary layout, bumped on incompatible changes.
+const LISTING_CACHE_VERSION: u8 = 1;
+
+/// Files added, modified or removed between two listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListingDiff {
+
+    /// Files only present in the newer listing
+    pub added: Vec<PathBuf>,
+
+    /// Files whose size or modification time changed
+    pub modified: Vec<PathBuf>,
+
+    /// Files only present in the older listing
+    pub removed: Vec<PathBuf>,
+}
+
+impl ListingDiff {
+
+    /// Returns `true` if the listings contain the same files.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns every changed path, sorted.
+    pub fn paths(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.added
+            .iter()
+            .chain(&self.modified)
+            .chain(&self.removed)
+            .map(PathBuf::as_path)
+            .collect();
+        paths.sort();
+        paths
+    }
+}
+
+impl Display for ListingDiff {
+
+    /// Formats the diff as counts per kind of change.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} added, {} modified, {} removed",
+            self.added.len(),
+            self.modified.len(),
+            self.removed.len()
+        )
+    }
+}
+
+/// Persisted result of a directory scan, used to avoid re-listing
+/// unchanged directories.
+///
+/// Besides every file, the cache records each directory's modification
+/// time. A directory whose time is unchanged has the same entries, so a
+/// [`ListingCache::refresh`] reuses its cached entries after a single
+/// metadata lookup instead of listing it and reading every file. On slow
+/// cloud mounts this turns a full listing into one round trip per directory.
+///
+/// Files rewritten in place don't touch their directory, so changes in
+/// size or modification time of existing files are only noticed once the
+/// directory changes or the cache is rebuilt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingCache {
+
+    /// When the cache was built from a full scan
+    created: SystemTime,
+
+    /// Modification time of every directory, relative to the root
+    dirs: BTreeMap<PathBuf, Option<SystemTime>>,
+
+    /// Every file, sorted by relative path
+    files: Vec<ScannedFile>,
+}
+
+impl Default for ListingCache {
+
+    /// Creates an empty cache, which makes the next refresh a full scan.
+    fn default() -> Self {
+        Self {
+            created: SystemTime::now(),
+            dirs: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl ListingCache {
+
+    /// Lists every file below `root` without reusing anything.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a directory or file can't be read.
+    pub fn scan(root: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::default().refresh(root).map(|(cache, _)| cache)
+    }
+
+    /// Returns the cached files, sorted by relative path.
+    pub fn files(&self) -> &[ScannedFile] {
+        &self.files
+    }
+
+    /// Returns how long ago the cache was built from a full scan.
+    pub fn age(&self) -> Duration {
+        self.created.elapsed().unwrap_or_default()
+    }
+
+    /// Lists `root` again, re-listing only directories that changed.
+    ///
+    /// A directory's modification time only changes when entries are
+    /// added, removed or renamed in it, so the files of an unchanged
+    /// directory aren't listed again but are still read, to find the
+    /// ones rewritten in place. The refreshed cache keeps the creation
+    /// time of this one, since its directory entries are carried over.
+    ///
+    /// # Returns
+    /// The refreshed cache and the files that changed since this one.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a directory or file can't be read.
+    pub fn refresh(&self, root: impl AsRef<Path>) -> Result<(Self, ListingDiff), Error> {
+        let root = root.as_ref();
+
+        let mut cached_files: HashMap<&Path, Vec<&ScannedFile>> = HashMap::new();
+        for file in &self.files {
+            let parent = file.relative.parent().unwrap_or(Path::new(""));
+            cached_files.entry(parent).or_default().push(file);
+        }
+        let mut cached_dirs: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        for dir in self.dirs.keys() {
+            if let Some(parent) = dir.parent() {
+                cached_dirs.entry(parent).or_default().push(dir);
+            }
+        }
+
+        let mut dirs = BTreeMap::new();
+        let mut files = Vec::new();
+        let mut pending = vec![PathBuf::new()];
+        while let Some(relative) = pending.pop() {
+            let path = root.join(&relative);
+            let modified = fs::metadata(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .modified()
+                .ok();
+
+            let unchanged = modified.is_some() && self.dirs.get(&relative) == Some(&modified);
+            if unchanged {
+                for file in cached_files.get(relative.as_path()).into_iter().flatten() {
+                    let path = root.join(&file.relative);
+                    match DirScanner::scanned_file(root, &path) {
+                        Ok(scanned) => files.extend(scanned),
+                        // Removed since the directory was read
+                        Err(_) if fs::symlink_metadata(&path).is_err() => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                let subdirs = cached_dirs.get(relative.as_path()).into_iter().flatten();
+                pending.extend(subdirs.map(|dir| dir.to_path_buf()));
+            } else {
+                let entries = fs::read_dir(&path)
+                    .with_context(|| format!("Failed to list {}", path.display()))?;
+                for entry in entries {
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        pending.push(relative.join(entry.file_name()));
+                    } else if let Some(file) = DirScanner::scanned_file(root, &entry.path())? {
+                        files.push(file);
+                    }
+                }
+            }
+            dirs.insert(relative, modified);
+        }
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        let refreshed = Self {
+            created: if self.dirs.is_empty() { SystemTime::now() } else { self.created },
+            dirs,
+            files,
+        };
+        let diff = self.diff(&refreshed);
+        Ok((refreshed, diff))
+    }
+
+    /// Compares the files of this cache with those of a newer one.
+    pub fn diff(&self, newer: &ListingCache) -> ListingDiff {
+        let older: BTreeMap<&Path, &ScannedFile> = self.files
+            .iter()
+            .map(|file| (file.relative.as_path(), file))
+            .collect();
+        let mut diff = ListingDiff::default();
+
+        for file in &newer.files {
+            match older.get(file.relative.as_path()) {
+                None => diff.added.push(file.relative.clone()),
+                Some(old) if old.size != file.size || old.modified != file.modified => {
+                    diff.modified.push(file.relative.clone());
+                }
+                Some(_) => {}
+            }
+        }
+
+        let current: BTreeMap<&Path, ()> = newer.files
+            .iter()
+            .map(|file| (file.relative.as_path(), ()))
+            .collect();
+        diff.removed = older
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .map(|path| path.to_path_buf())
+            .collect();
+        diff
+    }
+
+    /// Loads a cache written by [`ListingCache::save`].
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read, was written by an
+    /// incompatible version, or is truncated.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read listing cache {}", path.display()))?;
+        Self::decode(&bytes)
+            .with_context(|| format!("Invalid listing cache {}", path.display()))
+    }
+
+    /// Writes the cache in its compact binary form, creating parent
+    /// directories as needed.
+    ///
+    /// The file is replaced atomically, so a crash never leaves a
+    /// half-written cache behind.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        fs::write(&partial, self.encode())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Encodes the cache as length-prefixed little-endian records.
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(LISTING_CACHE_MAGIC);
+        bytes.push(LISTING_CACHE_VERSION);
+        encode_time(&mut bytes, Some(self.created));
+
+        bytes.extend_from_slice(&(self.dirs.len() as u32).to_le_bytes());
+        for (dir, modified) in &self.dirs {
+            encode_path(&mut bytes, dir);
+            encode_time(&mut bytes, *modified);
+        }
+
+        bytes.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for file in &self.files {
+            encode_path(&mut bytes, &file.relative);
+            bytes.extend_from_slice(&file.size.to_le_bytes());
+            encode_time(&mut bytes, file.modified);
+        }
+        bytes
+    }
+
+    /// Decodes a cache produced by [`ListingCache::encode`].
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes };
+        if reader.take(LISTING_CACHE_MAGIC.len())? != LISTING_CACHE_MAGIC {
+            return Err(anyhow!("Not a listing cache"));
+        }
+        let version = reader.take(1)?[0];
+        if version != LISTING_CACHE_VERSION {
+            return Err(anyhow!("Unsupported listing cache version {}", version));
+        }
+        let created = reader.time()?.unwrap_or(UNIX_EPOCH);
+
+        let mut dirs = BTreeMap::new();
+        for _ in 0..reader.u32()? {
+            let dir = reader.path()?;
+            dirs.insert(dir, reader.time()?);
+        }
+
+        let count = reader.u32()? as usize;
+        let mut files = Vec::with_capacity(count.min(bytes.len()));
+        for _ in 0..count {
+            files.push(ScannedFile {
+                relative: reader.path()?,
+                size: reader.u64()?,
+                modified: reader.time()?,
+            });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(anyhow!("Unexpected trailing bytes"));
+        }
+        Ok(Self { created, dirs, files })
+    }
+}
+
+/// Appends a path as a length-prefixed UTF-8 string.
+///
+/// Paths that aren't valid UTF-8 are stored lossily and simply show up as
+/// changed on the next refresh.
+fn encode_path(bytes: &mut Vec<u8>, path: &Path) {
+    let path = path.to_string_lossy();
+    bytes.extend_from_slice(&(path.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(path.as_bytes());
+}
+
+/// Appends an optional time as a presence flag, seconds and nanoseconds
+/// since the Unix epoch.
+fn encode_time(bytes: &mut Vec<u8>, time: Option<SystemTime>) {
+    match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(since_epoch) => {
+            bytes.push(1);
+            bytes.extend_from_slice(&since_epoch.as_secs().to_le_bytes());
+            bytes.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+        }
+        None => bytes.push(0),
+    }
+}
+
+/// Cursor over the bytes of an encoded cache.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+
+    /// Consumes the next `count` bytes.
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < count {
+            return Err(anyhow!("Listing cache is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Consumes a little-endian `u32`.
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    /// Consumes a little-endian `u64`.
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// Consumes a path written by [`encode_path`].
+    fn path(&mut self) -> Result<PathBuf, Error> {
+        let length = self.u32()? as usize;
+        Ok(PathBuf::from(std::str::from_utf8(self.take(length)?)?))
+    }
+
+    /// Consumes a time written by [`encode_time`].
+    fn time(&mut self) -> Result<Option<SystemTime>, Error> {
+        if self.take(1)?[0] == 0 {
+            return Ok(None);
+        }
+        let secs = self.u64()?;
+        let nanos = self.u32()?;
+        Ok(UNIX_EPOCH.checked_add(Duration::new(secs, nanos)))
+    }
+}
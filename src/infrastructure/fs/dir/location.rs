@@ -1,6 +1,10 @@
+use anyhow::{anyhow, Error, Result};
 use serde::Serialize;
 
-use super::ssh_config::SshConfig;
+use super::{
+    ssh_config::SshConfig,
+    unc_path::UncPath
+};
 
 /// Represents a filesystem location which could be either local or remote.
 ///
@@ -57,6 +61,146 @@ impl DirLocation {
         }
     }
 
+    /// Parses a location from a URI or path.
+    ///
+    /// Supported forms:
+    /// - `ssh://user@host:2222/data/media?key=~/.ssh/id_ed25519` for SSH
+    ///   destinations; credentials are referenced with the `key` (private
+    ///   key path) or `password_env` (environment variable holding the
+    ///   password) query parameters, never written inline;
+    ///   `control_persist=<secs>` enables connection multiplexing
+    /// - `user@host:/data/media` legacy SSH shorthand
+    /// - `rsync://host/module/path` for rsync daemons, kept as is
+    /// - `webdav://host/path` and `webdavs://host/path` for WebDAV servers
+    ///   synced with rclone, kept as is, like `rclone:remote:path`
+    /// - `file:///data/media` and plain local or UNC paths
+    ///
+    /// User names and paths are percent-decoded.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the scheme is unsupported, the URI is
+    /// malformed, or it contains an inline password.
+    pub fn parse(location: &str) -> Result<Self, Error> {
+        let Some((scheme, rest)) = location.split_once("://") else {
+            return Ok(Self::parse_shorthand(location));
+        };
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "ssh" => Self::parse_ssh_uri(rest),
+            "file" => Ok(DirLocation::new(&Self::percent_decode(rest)?, true, None)),
+            "rsync" | "webdav" | "webdavs" => Ok(DirLocation::new(location, true, None)),
+            _ => Err(anyhow!("Unsupported location scheme '{}://'", scheme)),
+        }
+    }
+
+    /// Parses the part of an `ssh://` URI following the scheme.
+    fn parse_ssh_uri(rest: &str) -> Result<Self, Error> {
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => return Err(anyhow!("SSH location '{}' has no path", rest)),
+        };
+        let (user, host_port) = match authority.rsplit_once('@') {
+            Some((user, host_port)) => (Some(user), host_port),
+            None => (None, authority),
+        };
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse::<u16>()
+                    .map_err(|_| anyhow!("Invalid SSH port '{}'", port))?;
+                (host, Some(port))
+            }
+            None => (host_port, None),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("SSH location '{}' has no host", rest));
+        }
+
+        let mut ssh_config = SshConfig::builder().with_ip(host.to_string());
+        if let Some(user) = user {
+            if user.contains(':') {
+                return Err(anyhow!(
+                    "Inline SSH passwords are not supported, reference one with 'password_env'"
+                ));
+            }
+            ssh_config = ssh_config.with_username(Self::percent_decode(user)?);
+        }
+        if let Some(port) = port {
+            ssh_config = ssh_config.with_port(port);
+        }
+
+        for (key, value) in query.unwrap_or_default().split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = Self::percent_decode(value)?;
+            ssh_config = match key {
+                "key" => ssh_config.with_key_path(value),
+                "password_env" => {
+                    let password = std::env::var(&value)
+                        .map_err(|_| anyhow!("Environment variable '{}' is not set", value))?;
+                    ssh_config.with_password(password)
+                }
+                "control_persist" => {
+                    let secs = value.parse()
+                        .map_err(|_| anyhow!("Invalid control_persist '{}'", value))?;
+                    ssh_config.with_control_persist(secs)
+                }
+                _ => return Err(anyhow!("Unknown SSH location parameter '{}'", key)),
+            };
+        }
+
+        Ok(DirLocation::new(&Self::percent_decode(path)?, true, Some(ssh_config)))
+    }
+
+    /// Parses a location without a scheme: `user@host:path` or a local path.
+    fn parse_shorthand(location: &str) -> Self {
+        let remote = location
+            .split_once(':')
+            .and_then(|(authority, path)| {
+                let (user, host) = authority.split_once('@')?;
+                let is_remote = !user.is_empty() && !host.is_empty() && !authority.contains(['/', '\\']);
+                is_remote.then_some((user, host, path))
+            });
+
+        match remote {
+            Some((user, host, path)) => {
+                let ssh_config = SshConfig::builder()
+                    .with_username(user.to_string())
+                    .with_ip(host.to_string());
+                DirLocation::new(path, true, Some(ssh_config))
+            }
+            None => DirLocation::new(location, true, None),
+        }
+    }
+
+    /// Decodes `%XX` escapes in a URI component.
+    fn percent_decode(value: &str) -> Result<String, Error> {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let byte = bytes.get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid percent-encoding in '{}'", value))?;
+                decoded.push(byte);
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(decoded).map_err(|_| anyhow!("'{}' doesn't decode to UTF-8", value))
+    }
+
+    /// Replaces the SSH configuration of this location (builder pattern).
+    pub fn with_ssh_config(mut self, ssh_config: Option<SshConfig>) -> Self {
+        self.ssh_config = ssh_config;
+        self
+    }
+
     /// Gets the formatted path string for this location.
     ///
     /// For local paths, returns the normalized path (with trimmed trailing slashes).
@@ -82,6 +226,14 @@ impl DirLocation {
         }
     }
 
+    /// Returns the SMB share location if the path is a UNC path (`\\server\share\dir`).
+    pub fn unc_path(&self) -> Option<UncPath> {
+        if self.ssh_config.is_some() {
+            return None;
+        }
+        UncPath::parse(&self.path)
+    }
+
     /// Returns a reference to the SSH configuration, if any.
     pub fn ssh_config(&self) -> Option<&SshConfig> {
         self.ssh_config.as_ref()
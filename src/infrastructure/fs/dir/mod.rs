@@ -3,15 +3,78 @@
 //! This module provides a complete solution for local and remote file synchronization with:
 //! - Cross-platform path handling
 //! - SSH configuration and authentication
+//! - Remote command execution over SSH
 //! - Flexible sync configuration
-//! - Progress tracking and reporting
+//! - Progress tracking and reporting, throttled with a smoothed ETA
+//! - Typed per-file progress events sent over a channel
+//! - Dry-run sync plans
+//! - Sync statistics parsed from rsync output
+//! - Native local synchronization without rsync
+//! - robocopy synchronization for SMB shares on Windows
+//! - rclone synchronization for rclone remotes and WebDAV servers
+//! - CPU and disk priorities for sync processes and threads
+//! - Read-only directory scans, with a persisted listing cache
+//! - SMB/CIFS network locations
+//! - Write access probes of destinations
+//! - Transfer processes tracked in their own process groups
+//! - Detection and killing of stuck transfers
+//! - Checksum verification of transferred files
+//! - CPU, memory and disk usage of transfer processes
+//! - Free space and temporary directory checks before syncs
+//! - Bandwidth limiting of native copies
+//! - Replays of source moves at destinations
+//! - rsync filter rules passed in files
 //! 
+pub mod bandwidth_limiter;
+pub mod child_processes;
+pub mod command;
+pub mod disk_space;
+pub mod io_priority;
+pub mod listing_cache;
 pub mod location;
+pub mod move_replay;
+pub mod native_sync;
+pub mod progress_event;
+pub mod progress_reporter;
+pub mod rclone_sync;
+pub mod resource_usage;
+pub mod robocopy_sync;
+pub mod rsync_filter;
+pub mod scanner;
 pub mod ssh_config;
+pub mod ssh_runner;
+pub mod stall_watchdog;
 pub mod sync_config;
 pub mod sync_helper;
+pub mod sync_plan;
+pub mod sync_report;
+pub mod transfer_verifier;
+pub mod unc_path;
+pub mod write_access;
 
+pub use bandwidth_limiter::*;
+pub use child_processes::*;
+pub use command::*;
+pub use disk_space::*;
+pub use io_priority::*;
+pub use listing_cache::*;
 pub use location::*;
+pub use move_replay::*;
+pub use native_sync::*;
+pub use progress_event::*;
+pub use progress_reporter::*;
+pub use rclone_sync::*;
+pub use resource_usage::*;
+pub use robocopy_sync::*;
+pub use rsync_filter::*;
+pub use scanner::*;
 pub use ssh_config::*;
+pub use ssh_runner::*;
+pub use stall_watchdog::*;
 pub use sync_config::*;
-pub use sync_helper::*;
\ No newline at end of file
+pub use sync_helper::*;
+pub use sync_plan::*;
+pub use sync_report::*;
+pub use transfer_verifier::*;
+pub use unc_path::*;
+pub use write_access::*;
\ No newline at end of file
@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::debug_log;
+use super::{
+    command::shell_quote,
+    location::DirLocation,
+    ssh_runner::SshRunner,
+    super::watcher::{FileRename, FileStamp}
+};
+
+/// Domain identifier for move replay logs
+const MOVE_REPLAY_LOGGER_DOMAIN: &str = "[MOVE-REPLAY]";
+
+/// Replays moves of the source at a destination, so the next sync finds
+/// the files in place instead of deleting and transferring them again.
+///
+/// Works on local paths and SMB shares, and over SSH. A move is skipped
+/// when its previous path is missing at the destination or its new path
+/// already exists there; the sync then transfers the file as usual.
+pub struct MoveReplay;
+
+impl MoveReplay {
+
+    /// Returns the size and modification time of a file at a local
+    /// destination, `None` if it's missing, not a regular file, or the
+    /// destination is remote.
+    ///
+    /// # Arguments
+    /// * `location` - Destination the file was synced to
+    /// * `relative` - Path of the file relative to the destination
+    pub fn file_stamp(location: &DirLocation, relative: &Path) -> Option<FileStamp> {
+        if location.ssh_config().is_some() {
+            return None;
+        }
+        let metadata = fs::metadata(Self::local_root(location)?.join(relative)).ok()?;
+        metadata.is_file().then(|| FileStamp::of(&metadata))
+    }
+
+    /// Replays moves at a destination, in order.
+    ///
+    /// rsync daemon modules can't be changed outside of a transfer, so
+    /// nothing is replayed there.
+    ///
+    /// # Returns
+    /// The moves replayed.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a move fails; those before it stay done.
+    pub fn apply(location: &DirLocation, renames: &[FileRename]) -> Result<Vec<FileRename>, Error> {
+        let mut applied = Vec::new();
+        if location.get_path().starts_with("rsync://") {
+            return Ok(applied);
+        }
+        for rename in renames {
+            let moved = match location.ssh_config() {
+                Some(ssh_config) => {
+                    let root = location.get_path();
+                    let root = root.split_once(':').map_or(root.as_str(), |(_, path)| path);
+                    Self::apply_remote(&SshRunner::new(ssh_config.clone()), Path::new(root), rename)?
+                }
+                None => match Self::local_root(location) {
+                    Some(root) => Self::apply_local(&root, rename)?,
+                    None => false,
+                },
+            };
+            if moved {
+                applied.push(rename.clone());
+            } else {
+                debug_log!(
+                    MOVE_REPLAY_LOGGER_DOMAIN,
+                    format!("Not replaying {} at {}", rename, location.get_path())
+                );
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Moves a file or directory below a local root.
+    ///
+    /// # Returns
+    /// `true` if it was moved, `false` if it was skipped.
+    fn apply_local(root: &Path, rename: &FileRename) -> Result<bool, Error> {
+        let (from, to) = (root.join(&rename.from), root.join(&rename.to));
+        if !from.exists() || to.exists() {
+            return Ok(false);
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::rename(&from, &to).with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+        Ok(true)
+    }
+
+    /// Moves a file or directory below a root on an SSH host.
+    ///
+    /// # Returns
+    /// `true` if it was moved, `false` if it was skipped.
+    fn apply_remote(runner: &SshRunner, root: &Path, rename: &FileRename) -> Result<bool, Error> {
+        let quote = |path: &Path| shell_quote(&path.to_string_lossy());
+        let (from, to) = (root.join(&rename.from), root.join(&rename.to));
+        let parent = to.parent().unwrap_or(root);
+        // Exit status 3 tells a skipped move from a failed one
+        let script = format!(
+            "[ -e {from} ] && [ ! -e {to} ] || exit 3; mkdir -p {parent} && mv {from} {to}",
+            from = quote(&from),
+            to = quote(&to),
+            parent = quote(parent)
+        );
+        let output = runner.run(&script)?;
+        match output.status {
+            Some(0) => Ok(true),
+            Some(3) => Ok(false),
+            _ => Err(anyhow!("Failed to move {}: {}", rename, output.stderr.trim())),
+        }
+    }
+
+    /// Returns the local directory of a destination.
+    fn local_root(location: &DirLocation) -> Option<PathBuf> {
+        match location.unc_path() {
+            Some(unc) => unc.resolve().ok(),
+            None => Some(PathBuf::from(location.get_path())),
+        }
+    }
+}
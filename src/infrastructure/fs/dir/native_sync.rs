@@ -0,0 +1,471 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Instant, SystemTime, UNIX_EPOCH}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt}
+};
+
+use crate::{info_log, debug_log, warn_log};
+use super::{
+    super::file::MetadataFiles,
+    bandwidth_limiter::BandwidthLimiter,
+    disk_space::DiskSpace,
+    progress_event::{FileProgressTracker, ProgressSender},
+    sync_config::{DirSyncConfig, MetadataPolicy, OverwritePolicy},
+    sync_plan::{SyncAction, SyncPlan},
+    transfer_verifier::TransferVerifier,
+    write_access::ReadOnlyDestination
+};
+
+/// Domain identifier for native sync logs
+const NATIVE_SYNC_LOGGER_DOMAIN: &str = "[NATIVE-SYNC]";
+
+/// Suffix of the temporary file a copy is written to before it replaces
+/// the destination file.
+const NATIVE_SYNC_PARTIAL_SUFFIX: &str = "partial";
+
+/// Sequence number of the partial files of this process, so copies of
+/// files with the same name into a shared temporary directory don't clash.
+static PARTIAL_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Size of the chunks copies are read in when the bandwidth is limited
+/// or progress is reported.
+const NATIVE_SYNC_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A file or directory found while walking a tree.
+#[derive(Debug, Clone, Copy)]
+struct WalkedEntry {
+
+    /// True for directories
+    is_dir: bool,
+
+    /// File size in bytes
+    size: u64,
+
+    /// Last modification time, if the filesystem reports one
+    modified: Option<SystemTime>,
+}
+
+/// Directory synchronization between local paths without external
+/// programs, for hosts without rsync such as stock Windows.
+///
+/// Files are compared like rsync compares them: by size and modification
+/// time, or as the overwrite policy says. Copies are written to the
+/// temporary directory, or next to their destination, and renamed into
+/// place with the source's modification time, no faster than the
+/// bandwidth limit. The suffix, regex, size and metadata filters of the
+/// configuration apply; in strict mode, destination files missing from
+/// the source are deleted, except those the filters exclude. Metadata
+/// files are copied rather than linked.
+pub struct NativeSync {
+
+    /// Configuration for the sync operation
+    config: DirSyncConfig,
+
+    /// Channel for typed progress events of the copies
+    progress_sender: Option<ProgressSender>,
+}
+
+impl NativeSync {
+
+    /// Creates a native sync with the given configuration.
+    pub fn new(config: DirSyncConfig) -> Self {
+        NativeSync { config, progress_sender: None }
+    }
+
+    /// Sends a [`ProgressEvent`](super::ProgressEvent) when a copy starts,
+    /// progresses and is done (builder pattern).
+    pub fn with_progress_sender(mut self, sender: ProgressSender) -> Self {
+        self.progress_sender = Some(sender);
+        self
+    }
+
+    /// Computes the changes a sync would make without executing them.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a location is remote or a tree can't be read.
+    pub async fn plan(&self) -> Result<SyncPlan, Error> {
+        let (source, destination) = self.roots()?;
+        Ok(SyncPlan::new(self.actions(&source, &destination).await?))
+    }
+
+    /// Synchronizes the source into the destination.
+    ///
+    /// In dry-run mode the changes are logged instead.
+    ///
+    /// # Returns
+    /// The paths created, updated or deleted, relative to the destination.
+    ///
+    /// # Errors
+    /// Returns [`ReadOnlyDestination`] if the destination can't be written,
+    /// or `anyhow::Error` if a location is remote, the guard file is
+    /// missing, a file can't be copied or deleted, or a verified copy
+    /// doesn't match its source.
+    pub async fn sync(&self) -> Result<Vec<String>, Error> {
+        let (source, destination) = self.roots()?;
+        if let Some(guard) = self.config.get_guard_file() {
+            if !Path::new(&guard).exists() {
+                return Err(anyhow!("Guard file '{}' does not exist, sync aborted.", guard));
+            }
+        }
+        if !fs::try_exists(&source).await.unwrap_or(false) {
+            return Err(anyhow!("Source path '{}' does not exist, sync aborted.", source.display()));
+        }
+
+        let actions = self.actions(&source, &destination).await?;
+        if self.config.get_dry_run() {
+            for action in &actions {
+                info_log!(NATIVE_SYNC_LOGGER_DOMAIN, format!("Dry run: {}", action));
+            }
+            return Ok(Self::changed_paths(&actions));
+        }
+
+        ReadOnlyDestination::check(&self.config.get_destination())?;
+        DiskSpace::preflight(&self.config)?;
+        fs::create_dir_all(&destination)
+            .await
+            .with_context(|| format!("Failed to create {}", destination.display()))?;
+        let temp_dir = self.config.get_resolved_temp_dir().map(PathBuf::from);
+        let limiter = self.config.get_bandwidth_limit().and_then(BandwidthLimiter::new);
+        let mut tracker = self.progress_sender.clone().map(FileProgressTracker::new);
+        let mut copied = Vec::new();
+        for action in &actions {
+            debug_log!(NATIVE_SYNC_LOGGER_DOMAIN, action.to_string());
+            match action {
+                SyncAction::CreateDir(path) => {
+                    let dir = destination.join(path);
+                    fs::create_dir_all(&dir)
+                        .await
+                        .with_context(|| format!("Failed to create {}", dir.display()))?;
+                }
+                SyncAction::Create(path) | SyncAction::Update(path) => {
+                    if let Some(tracker) = tracker.as_mut() {
+                        tracker.started(path);
+                    }
+                    let (from, to) = (source.join(path), destination.join(path));
+                    Self::copy_file(&from, &to, temp_dir.as_deref(), limiter.as_ref(), tracker.as_mut()).await?;
+                    if let Some(tracker) = tracker.as_mut() {
+                        tracker.finish();
+                    }
+                    copied.push(path.clone());
+                }
+                SyncAction::Delete(path) => Self::delete(&destination.join(path)).await?,
+            }
+        }
+
+        if self.config.get_verify_transfers() {
+            let report = tokio::task::spawn_blocking({
+                let (source, destination) = (source.clone(), destination.clone());
+                move || TransferVerifier::verify_local(&source, &destination, &copied)
+            })
+            .await??;
+            if !report.is_ok() {
+                return Err(anyhow!("Verification of {} failed, {}", destination.display(), report));
+            }
+        }
+        Ok(Self::changed_paths(&actions))
+    }
+
+    /// Returns the local source and destination directories.
+    fn roots(&self) -> Result<(PathBuf, PathBuf), Error> {
+        let source = self.config.get_source();
+        let destination = self.config.get_destination();
+        if source.ssh_config().is_some() || destination.ssh_config().is_some() {
+            return Err(anyhow!("Native sync only supports local directories"));
+        }
+        Ok((PathBuf::from(source.get_path()), PathBuf::from(destination.get_path())))
+    }
+
+    /// Compares both trees and lists the changes turning the destination
+    /// into a copy of the source: directories first, then files, then
+    /// deletions, deepest paths first.
+    async fn actions(&self, source: &Path, destination: &Path) -> Result<Vec<SyncAction>, Error> {
+        let source_entries = Self::walk(source).await?;
+        let destination_entries = if fs::try_exists(destination).await.unwrap_or(false) {
+            Self::walk(destination).await?
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        let mut synced = BTreeSet::new();
+        for (relative, entry) in &source_entries {
+            if !entry.is_dir && !self.passes_filters(relative, entry.size) {
+                continue;
+            }
+            synced.insert(relative);
+            let path = Self::action_path(relative);
+            match destination_entries.get(relative) {
+                None if entry.is_dir => dirs.push(SyncAction::CreateDir(format!("{}/", path))),
+                None => files.push(SyncAction::Create(path)),
+                Some(copied) if copied.is_dir != entry.is_dir => {
+                    return Err(anyhow!("{} is a file on one side and a directory on the other", path));
+                }
+                Some(_) if entry.is_dir => {}
+                Some(copied) => {
+                    if self.needs_update(&source.join(relative), &destination.join(relative), entry, copied).await? {
+                        files.push(SyncAction::Update(path));
+                    }
+                }
+            }
+        }
+
+        let mut deletions = Vec::new();
+        if self.config.get_strict_mode() {
+            // Files the filters exclude are protected, like rsync protects them,
+            // and so are the directories holding them. Children come before
+            // their parents in reverse order.
+            let mut kept_dirs = BTreeSet::new();
+            for (relative, entry) in destination_entries.iter().rev() {
+                let protected = !entry.is_dir && !self.passes_filters(relative, entry.size);
+                let kept = synced.contains(relative)
+                    || protected
+                    || source_entries.contains_key(relative)
+                    || kept_dirs.contains(relative.as_path());
+                if kept {
+                    kept_dirs.extend(relative.ancestors().skip(1));
+                } else {
+                    deletions.push(SyncAction::Delete(Self::action_path(relative)));
+                }
+            }
+        }
+
+        dirs.extend(files);
+        dirs.extend(deletions);
+        Ok(dirs)
+    }
+
+    /// Returns `true` if a source file is synced under the configured filters.
+    fn passes_filters(&self, relative: &Path, size: u64) -> bool {
+        let has_suffix = |suffixes: &[String]| {
+            relative.extension().is_some_and(|extension| {
+                suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix))
+            })
+        };
+
+        let include_suffixes = self.config.get_include_suffixes();
+        if !include_suffixes.is_empty() {
+            if !has_suffix(&include_suffixes) && !has_suffix(&self.config.get_subtitle_extensions()) {
+                return false;
+            }
+        } else if has_suffix(&self.config.get_exclude_suffixes()) {
+            return false;
+        }
+
+        if self.config.get_exclude_regex().is_some_and(|regex| regex.is_match(&Self::action_path(relative))) {
+            return false;
+        }
+        if self.config.get_metadata_policy() == MetadataPolicy::Skip && MetadataFiles::is_metadata(relative) {
+            return false;
+        }
+        !self.config.get_media_size_limits().is_undersized(relative, size)
+    }
+
+    /// Returns `true` if a destination file must be replaced by its source.
+    async fn needs_update(
+        &self,
+        from: &Path,
+        to: &Path,
+        source: &WalkedEntry,
+        copied: &WalkedEntry,
+    ) -> Result<bool, Error> {
+        match self.config.get_overwrite_policy() {
+            Some(OverwritePolicy::Skip) => Ok(false),
+            Some(OverwritePolicy::Overwrite) => Ok(true),
+            Some(OverwritePolicy::OverwriteIfChanged) => {
+                if source.size != copied.size {
+                    return Ok(true);
+                }
+                let (from, to) = (from.to_path_buf(), to.to_path_buf());
+                tokio::task::spawn_blocking(move || {
+                    Ok(TransferVerifier::checksum(&from)? != TransferVerifier::checksum(&to)?)
+                })
+                .await?
+            }
+            // Like rsync, times are compared to the second
+            None => Ok(source.size != copied.size
+                || Self::unix_secs(source.modified) != Self::unix_secs(copied.modified)),
+        }
+    }
+
+    /// Lists every file and directory below `root`, keyed by relative path.
+    ///
+    /// Symbolic links are followed for metadata but not traversed as
+    /// directories, so link cycles can't loop forever. Links whose target
+    /// is missing are skipped with a warning.
+    async fn walk(root: &Path) -> Result<BTreeMap<PathBuf, WalkedEntry>, Error> {
+        let mut entries = BTreeMap::new();
+        let mut pending = vec![PathBuf::new()];
+        while let Some(relative) = pending.pop() {
+            let dir = root.join(&relative);
+            let mut listing = fs::read_dir(&dir)
+                .await
+                .with_context(|| format!("Failed to list {}", dir.display()))?;
+            while let Some(entry) = listing.next_entry().await? {
+                let child = relative.join(entry.file_name());
+                if entry.file_type().await?.is_dir() {
+                    entries.insert(child.clone(), WalkedEntry { is_dir: true, size: 0, modified: None });
+                    pending.push(child);
+                    continue;
+                }
+
+                let path = entry.path();
+                let metadata = match fs::metadata(&path).await {
+                    Ok(metadata) => metadata,
+                    Err(e) if entry.file_type().await?.is_symlink() => {
+                        let msg = format!("Skipping dangling link {}: {}", path.display(), e);
+                        warn_log!(NATIVE_SYNC_LOGGER_DOMAIN, msg);
+                        continue;
+                    }
+                    Err(e) => return Err(Error::new(e).context(format!("Failed to read {}", path.display()))),
+                };
+                if metadata.is_file() && !Self::is_partial(&child) {
+                    entries.insert(child, WalkedEntry {
+                        is_dir: false,
+                        size: metadata.len(),
+                        modified: metadata.modified().ok(),
+                    });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Copies a file into the temporary directory, or next to its
+    /// destination, gives it the source's modification time, then renames
+    /// it into place.
+    ///
+    /// The partial file is named after the destination file, the process
+    /// and a sequence number, e.g. `.E01.mkv.4242-7.partial`, so concurrent
+    /// copies never write the same file.
+    async fn copy_file(
+        from: &Path,
+        to: &Path,
+        temp_dir: Option<&Path>,
+        limiter: Option<&BandwidthLimiter>,
+        tracker: Option<&mut FileProgressTracker>
+    ) -> Result<(), Error> {
+        let name = to.file_name().ok_or_else(|| anyhow!("Invalid destination {}", to.display()))?;
+        let partial_name = format!(
+            ".{}.{}-{}.{}",
+            name.to_string_lossy(),
+            std::process::id(),
+            PARTIAL_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            NATIVE_SYNC_PARTIAL_SUFFIX
+        );
+        let partial = match temp_dir {
+            Some(temp_dir) => temp_dir.join(partial_name),
+            None => to.with_file_name(partial_name),
+        };
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let copied = async {
+            if limiter.is_some() || tracker.is_some() {
+                Self::copy_chunked(from, &partial, limiter, tracker).await?;
+            } else {
+                fs::copy(from, &partial).await?;
+            }
+            if let Ok(modified) = fs::metadata(from).await?.modified() {
+                let file = fs::OpenOptions::new().write(true).open(&partial).await?.into_std().await;
+                tokio::task::spawn_blocking(move || file.set_modified(modified)).await??;
+            }
+            fs::rename(&partial, to).await?;
+            Ok::<(), Error>(())
+        };
+        if let Err(e) = copied.await {
+            let _ = fs::remove_file(&partial).await;
+            return Err(e.context(format!("Failed to copy {} to {}", from.display(), to.display())));
+        }
+        Ok(())
+    }
+
+    /// Copies a file in chunks, waiting for the limiter before each one
+    /// and reporting the progress after it.
+    async fn copy_chunked(
+        from: &Path,
+        to: &Path,
+        limiter: Option<&BandwidthLimiter>,
+        mut tracker: Option<&mut FileProgressTracker>
+    ) -> Result<(), Error> {
+        let mut reader = fs::File::open(from).await?;
+        let size = reader.metadata().await?.len();
+        let mut writer = fs::File::create(to).await?;
+        let mut buffer = vec![0; NATIVE_SYNC_CHUNK_SIZE];
+        let (started, mut written) = (Instant::now(), 0u64);
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            if let Some(limiter) = limiter {
+                limiter.acquire(read as u64).await;
+            }
+            writer.write_all(&buffer[..read]).await?;
+            written += read as u64;
+            if let Some(tracker) = tracker.as_deref_mut() {
+                let percent = (written * 100).checked_div(size).unwrap_or(100).min(100) as u8;
+                let elapsed = started.elapsed().as_secs_f64();
+                tracker.progress(percent, if elapsed > 0.0 { written as f64 / elapsed } else { 0.0 });
+            }
+        }
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Deletes a destination file or empty directory.
+    ///
+    /// Directories still holding files the filters protect are kept.
+    async fn delete(path: &Path) -> Result<(), Error> {
+        let metadata = fs::symlink_metadata(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let deleted = if metadata.is_dir() {
+            match fs::remove_dir(path).await {
+                Err(e) if e.kind() == ErrorKind::DirectoryNotEmpty => Ok(()),
+                result => result,
+            }
+        } else {
+            fs::remove_file(path).await
+        };
+        deleted.with_context(|| format!("Failed to delete {}", path.display()))
+    }
+
+    /// Returns the paths changed by the actions, without created directories.
+    fn changed_paths(actions: &[SyncAction]) -> Vec<String> {
+        actions
+            .iter()
+            .filter(|action| !matches!(action, SyncAction::CreateDir(_)))
+            .map(|action| action.path().to_string())
+            .collect()
+    }
+
+    /// Returns `true` if a file is a copy left behind by an interrupted sync.
+    fn is_partial(relative: &Path) -> bool {
+        relative.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with('.') && name.ends_with(&format!(".{}", NATIVE_SYNC_PARTIAL_SUFFIX))
+        })
+    }
+
+    /// Returns a relative path with `/` separators on every platform.
+    fn action_path(relative: &Path) -> String {
+        relative.to_string_lossy().replace('\\', "/")
+    }
+
+    /// Returns a time in whole seconds since the Unix epoch.
+    fn unix_secs(time: Option<SystemTime>) -> Option<u64> {
+        time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|elapsed| elapsed.as_secs())
+    }
+}
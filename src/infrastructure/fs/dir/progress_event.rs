@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use tokio::sync::mpsc::Sender;
+
+/// Channel typed progress events of a sync are sent to.
+///
+/// Events are sent without waiting, so blocking rsync threads need no
+/// runtime and a slow receiver never holds up a transfer; events that
+/// don't fit into the channel, or are sent after the receiver is
+/// dropped, are discarded.
+pub type ProgressSender = Sender<ProgressEvent>;
+
+/// Progress of a single file during a sync.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+
+    /// A file started transferring
+    FileStarted {
+
+        /// Path relative to the destination
+        path: String,
+    },
+
+    /// A file is transferring
+    FileProgress {
+
+        /// Path relative to the destination
+        path: String,
+
+        /// Completion from 0 to 100
+        percent: u8,
+
+        /// Transfer rate in bytes per second
+        bytes_per_sec: f64,
+    },
+
+    /// A file finished transferring
+    FileDone {
+
+        /// Path relative to the destination
+        path: String,
+    },
+}
+
+impl ProgressEvent {
+
+    /// Returns the path of the file the event is about.
+    pub fn path(&self) -> &str {
+        match self {
+            ProgressEvent::FileStarted { path }
+            | ProgressEvent::FileProgress { path, .. }
+            | ProgressEvent::FileDone { path } => path,
+        }
+    }
+}
+
+impl Display for ProgressEvent {
+
+    /// Formats the event as e.g. `show/ep1.mkv 45% 10.5 MB/s`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ProgressEvent::FileStarted { path } => write!(f, "{} started", path),
+            ProgressEvent::FileProgress { path, percent, bytes_per_sec } => {
+                write!(f, "{} {}% {:.1} MB/s", path, percent, bytes_per_sec / 1_000_000.0)
+            }
+            ProgressEvent::FileDone { path } => write!(f, "{} done", path),
+        }
+    }
+}
+
+/// Turns the files a transfer goes through into progress events.
+///
+/// Each started file finishes the previous one, and progress is
+/// attributed to the file started last. Only changes of the percentage
+/// are sent, so chunked copies don't flood the channel.
+#[derive(Debug)]
+pub struct FileProgressTracker {
+
+    /// Channel events are sent to
+    sender: ProgressSender,
+
+    /// File transferring and the last percentage sent for it
+    current: Option<(String, Option<u8>)>,
+}
+
+impl FileProgressTracker {
+
+    /// Creates a tracker sending events to `sender`.
+    pub fn new(sender: ProgressSender) -> Self {
+        Self { sender, current: None }
+    }
+
+    /// Finishes the current file, if any, and starts `path`.
+    pub fn started(&mut self, path: &str) {
+        self.finish();
+        self.send(ProgressEvent::FileStarted { path: path.to_string() });
+        self.current = Some((path.to_string(), None));
+    }
+
+    /// Reports progress of the current file, ignored before any file
+    /// started or while the percentage stays the same.
+    pub fn progress(&mut self, percent: u8, bytes_per_sec: f64) {
+        let Some((path, last)) = &mut self.current else {
+            return;
+        };
+        if *last == Some(percent) {
+            return;
+        }
+        *last = Some(percent);
+        let event = ProgressEvent::FileProgress { path: path.clone(), percent, bytes_per_sec };
+        self.send(event);
+    }
+
+    /// Finishes the current file, if any.
+    pub fn finish(&mut self) {
+        if let Some((path, _)) = self.current.take() {
+            self.send(ProgressEvent::FileDone { path });
+        }
+    }
+
+    /// Sends an event, dropping it if the channel is full or closed.
+    fn send(&self, event: ProgressEvent) {
+        let _ = self.sender.try_send(event);
+    }
+}
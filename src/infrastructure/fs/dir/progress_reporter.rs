@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    sync::Mutex,
+    time::{Duration, Instant}
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Minimum time between two reported updates by default.
+const PROGRESS_DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time span of transfer samples averaged into the rate by default.
+const PROGRESS_DEFAULT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Matches an rsync `--info=progress2` line, e.g.
+/// `  1,234,567  45%   10.50MB/s    0:00:12 (xfr#3, to-chk=10/20)`.
+static PROGRESS_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*([\d,]+)\s+(\d{1,3})%\s+([\d.]+)([kMGT]?B)/s")
+        .expect("valid progress pattern")
+});
+
+/// Smoothed state of a running transfer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncProgress {
+
+    /// Bytes transferred so far
+    pub transferred_bytes: u64,
+
+    /// Overall completion reported by rsync, from 0 to 100
+    pub percent: u8,
+
+    /// Transfer rate averaged over the sample window, in bytes per second
+    pub bytes_per_sec: f64,
+
+    /// Estimated time until completion, if it can be estimated
+    pub eta: Option<Duration>,
+}
+
+impl Display for SyncProgress {
+
+    /// Formats the progress as `45% 10.5 MB/s ETA 0:01:05`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}% {:.1} MB/s", self.percent, self.bytes_per_sec / 1_000_000.0)?;
+        if let Some(eta) = self.eta {
+            let secs = eta.as_secs();
+            write!(f, " ETA {}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns raw rsync progress output into throttled, smoothed updates.
+///
+/// rsync prints progress many times per second and its instantaneous rate
+/// jumps with every file boundary. The reporter:
+/// - Emits at most one update per interval, plus the final 100% update
+/// - Averages the rate over a sliding window of samples
+/// - Derives the ETA from the averaged rate instead of rsync's own estimate
+#[derive(Debug)]
+pub struct ProgressReporter {
+
+    /// Minimum time between two emitted updates
+    interval: Duration,
+
+    /// Time span of samples used for the averaged rate
+    window: Duration,
+
+    /// Recent `(time, transferred bytes)` samples, oldest first
+    samples: VecDeque<(Instant, u64)>,
+
+    /// When the last update was emitted
+    last_emit: Option<Instant>,
+}
+
+impl Default for ProgressReporter {
+
+    fn default() -> Self {
+        Self {
+            interval: PROGRESS_DEFAULT_INTERVAL,
+            window: PROGRESS_DEFAULT_WINDOW,
+            samples: VecDeque::new(),
+            last_emit: None,
+        }
+    }
+}
+
+impl ProgressReporter {
+
+    /// Creates a reporter with the default interval and window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum time between two emitted updates.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the time span of samples averaged into the rate.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Feeds a line of rsync progress output observed at `now`.
+    ///
+    /// Lines may contain several carriage-return separated updates; only
+    /// the latest one is used.
+    ///
+    /// # Returns
+    /// The smoothed progress if an update is due, `None` if the line isn't
+    /// progress output or the update is throttled.
+    pub fn update(&mut self, line: &str, now: Instant) -> Option<SyncProgress> {
+        let (transferred_bytes, percent, reported_rate) = line
+            .split('\r')
+            .rev()
+            .find_map(Self::parse_segment)?;
+
+        self.samples.push_back((now, transferred_bytes));
+        while self.samples.len() > 2
+            && self.samples.front().is_some_and(|(time, _)| now.duration_since(*time) > self.window)
+        {
+            self.samples.pop_front();
+        }
+
+        let due = self.last_emit.is_none_or(|last| now.duration_since(last) >= self.interval);
+        if !due && percent < 100 {
+            return None;
+        }
+        self.last_emit = Some(now);
+
+        let bytes_per_sec = self.average_rate().unwrap_or(reported_rate);
+        Some(SyncProgress {
+            transferred_bytes,
+            percent,
+            bytes_per_sec,
+            eta: Self::estimate_eta(transferred_bytes, percent, bytes_per_sec),
+        })
+    }
+
+    /// Parses one rsync `--info=progress2` update.
+    ///
+    /// # Returns
+    /// The bytes transferred, the completion from 0 to 100 and the rate
+    /// in bytes per second, or `None` if `segment` isn't an update.
+    pub(super) fn parse_segment(segment: &str) -> Option<(u64, u8, f64)> {
+        let captures = PROGRESS_PATTERN.captures(segment)?;
+        let transferred_bytes: u64 = captures[1].replace(',', "").parse().ok()?;
+        let percent: u8 = captures[2].parse::<u8>().ok()?.min(100);
+        let rate = captures[3].parse::<f64>().ok()? * Self::unit_scale(&captures[4]);
+        Some((transferred_bytes, percent, rate))
+    }
+
+    /// Wraps the reporter into a progress callback for [`super::DirSyncHelper`].
+    ///
+    /// # Arguments
+    /// * `on_progress` - Receives every update that isn't throttled
+    pub fn into_callback(
+        self,
+        on_progress: impl Fn(&SyncProgress) + Send + 'static,
+    ) -> Box<dyn Fn(&str) + Send + 'static> {
+        let reporter = Mutex::new(self);
+        Box::new(move |line| {
+            let progress = reporter
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .update(line, Instant::now());
+            if let Some(progress) = progress {
+                on_progress(&progress);
+            }
+        })
+    }
+
+    /// Returns the transfer rate across the sample window, if it spans any time.
+    fn average_rate(&self) -> Option<f64> {
+        let (first_time, first_bytes) = self.samples.front()?;
+        let (last_time, last_bytes) = self.samples.back()?;
+        let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+        (elapsed > 0.0).then(|| last_bytes.saturating_sub(*first_bytes) as f64 / elapsed)
+    }
+
+    /// Estimates the remaining time from the bytes transferred and the completion.
+    fn estimate_eta(transferred_bytes: u64, percent: u8, bytes_per_sec: f64) -> Option<Duration> {
+        if percent >= 100 {
+            return Some(Duration::ZERO);
+        }
+        if percent == 0 || bytes_per_sec <= 0.0 {
+            return None;
+        }
+        let total = transferred_bytes as f64 * 100.0 / f64::from(percent);
+        let remaining = (total - transferred_bytes as f64).max(0.0);
+        Some(Duration::from_secs_f64(remaining / bytes_per_sec))
+    }
+
+    /// Returns the byte multiplier of an rsync rate unit.
+    fn unit_scale(unit: &str) -> f64 {
+        match unit {
+            "kB" => 1e3,
+            "MB" => 1e6,
+            "GB" => 1e9,
+            "TB" => 1e12,
+            _ => 1.0,
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    process::Stdio
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use serde::Deserialize;
+
+use crate::{debug_log, info_log};
+use super::{
+    child_processes::ChildProcesses,
+    resource_usage::ResourceUsage,
+    stall_watchdog::{ActivityMonitor, StallWatchdog},
+    sync_config::DirSyncConfig,
+    sync_plan::{SyncAction, SyncPlan}
+};
+
+/// Domain identifier for rclone logs
+const RCLONE_LOGGER_DOMAIN: &str = "[RCLONE]";
+
+/// Program run for the transfer.
+const RCLONE_PROGRAM: &str = "rclone";
+
+/// Prefix of destinations naming a configured rclone remote, as in
+/// `rclone:media:movies`.
+const RCLONE_DESTINATION_PREFIX: &str = "rclone:";
+
+/// One line of rclone's `--use-json-log` output.
+#[derive(Debug, Deserialize)]
+struct RcloneLogLine {
+
+    /// Log level, e.g. `info` or `error`
+    #[serde(default)]
+    level: String,
+
+    /// Message, e.g. `Copied (new)`
+    #[serde(default)]
+    msg: String,
+
+    /// Path the message is about, relative to the source or destination
+    #[serde(default)]
+    object: Option<String>,
+}
+
+/// Directory synchronization with `rclone`, for `rclone:remote:path`
+/// destinations and WebDAV servers.
+///
+/// A `rclone:` destination names a remote of the user's rclone
+/// configuration, which holds its credentials. A `webdav://` or
+/// `webdavs://` destination becomes a remote of its own for the run,
+/// reached over HTTP or HTTPS; it may carry a user name, but a server
+/// needing a password has to be configured as an rclone remote.
+///
+/// rclone copies files whose size or modification time differ. In strict
+/// mode it runs `rclone sync`, deleting destination files missing from
+/// the source, otherwise `rclone copy`. The suffix filters of the
+/// configuration become filter rules; the regex and size filters can't be
+/// expressed and don't apply, so strict mode is refused along with them,
+/// as their files would be deleted.
+pub struct RcloneSync {
+
+    /// Configuration for the sync operation
+    config: DirSyncConfig,
+}
+
+impl RcloneSync {
+
+    /// Creates an rclone sync with the given configuration.
+    pub fn new(config: DirSyncConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns `true` if a destination is synced with rclone.
+    pub fn is_rclone_destination(destination: &str) -> bool {
+        let scheme = destination.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+        destination.starts_with(RCLONE_DESTINATION_PREFIX) || matches!(scheme.as_deref(), Some("webdav" | "webdavs"))
+    }
+
+    /// Returns the rclone remote of a destination, e.g. `media:movies` for
+    /// `rclone:media:movies/`, or a connection string such as
+    /// `:webdav,url='https://nas/dav/movies':` for `webdavs://nas/dav/movies`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the destination names no remote or has
+    /// no host, or a WebDAV address holds an inline password.
+    pub fn remote(destination: &str) -> Result<String, Error> {
+        if let Some(remote) = destination.strip_prefix(RCLONE_DESTINATION_PREFIX) {
+            return match remote.split_once(':') {
+                // A trailing slash would make `media:/` the root of some backends
+                Some((name, path)) if !name.is_empty() => Ok(format!("{}:{}", name, path.trim_end_matches('/'))),
+                _ => Err(anyhow!("rclone destination '{}' names no remote, e.g. rclone:media:movies", destination)),
+            };
+        }
+
+        let (scheme, rest) = destination
+            .split_once("://")
+            .ok_or_else(|| anyhow!("'{}' isn't an rclone or WebDAV destination", destination))?;
+        let protocol = match scheme.to_ascii_lowercase().as_str() {
+            "webdav" => "http",
+            "webdavs" => "https",
+            _ => return Err(anyhow!("'{}' isn't an rclone or WebDAV destination", destination)),
+        };
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, authority),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("WebDAV destination '{}' has no host", destination));
+        }
+        let mut remote = format!(":webdav,url={}", Self::quote(&format!("{}://{}/{}", protocol, host, path)));
+        if let Some(user) = user {
+            if user.contains(':') {
+                return Err(anyhow!(
+                    "Inline WebDAV passwords are not supported, configure the server as an rclone remote"
+                ));
+            }
+            remote.push_str(&format!(",user={}", Self::quote(user)));
+        }
+        remote.push(':');
+        Ok(remote)
+    }
+
+    /// Computes the changes a sync would make with `--dry-run`.
+    ///
+    /// rclone doesn't tell new files from changed ones in dry runs, so
+    /// both are planned as updates.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the filters can't be applied, rclone
+    /// can't be run or fails.
+    pub fn plan(&self) -> Result<SyncPlan, Error> {
+        self.run(true)
+    }
+
+    /// Synchronizes the source into the destination.
+    ///
+    /// In dry-run mode, the plan is logged instead and the destination is
+    /// left untouched.
+    ///
+    /// # Returns
+    /// The paths copied or deleted, relative to the destination.
+    ///
+    /// # Errors
+    /// Returns a [`SyncHang`](super::SyncHang) if rclone was killed as
+    /// stuck, a [`SyncTimeout`](super::SyncTimeout) if it ran too long, or
+    /// `anyhow::Error` if a check fails, rclone can't be run or it
+    /// reports a failure.
+    pub fn sync(&self) -> Result<Vec<String>, Error> {
+        if let Some(guard) = self.config.get_guard_file() {
+            if !Path::new(&guard).exists() {
+                return Err(anyhow!("Guard file '{}' does not exist, sync aborted.", guard));
+            }
+        }
+        let source = self.config.get_source().get_path();
+        if !Path::new(&source).exists() {
+            return Err(anyhow!("Source path '{}' does not exist, sync aborted.", source));
+        }
+
+        let dry_run = self.config.get_dry_run();
+        let plan = self.run(dry_run)?;
+        if dry_run {
+            for action in plan.actions() {
+                info_log!(RCLONE_LOGGER_DOMAIN, format!("Dry run: {}", action));
+            }
+        }
+        Ok(plan.actions().iter().map(|action| action.path().to_string()).collect())
+    }
+
+    /// Returns the arguments of an rclone run.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the destination names no rclone remote.
+    pub fn args(&self, dry_run: bool) -> Result<Vec<String>, Error> {
+        let command = if self.config.get_strict_mode() { "sync" } else { "copy" };
+        let mut args = vec![
+            command.to_string(),
+            self.config.get_source().get_path(),
+            Self::remote(&self.config.get_destination().get_path())?,
+            "--use-json-log".to_string(),
+            "--verbose".to_string(),
+        ];
+
+        // Subtitles are copied along with the included files, as with rsync
+        let include_suffixes = self.config.get_include_suffixes();
+        if include_suffixes.is_empty() {
+            for suffix in self.config.get_exclude_suffixes() {
+                args.extend(["--exclude".to_string(), format!("*.{}", suffix)]);
+            }
+        } else {
+            for suffix in include_suffixes.iter().chain(self.config.get_subtitle_extensions().iter()) {
+                args.extend(["--include".to_string(), format!("*.{}", suffix)]);
+            }
+        }
+        if let Some(rate) = self.config.get_bandwidth_limit() {
+            args.extend(["--bwlimit".to_string(), format!("{}B", rate.bytes())]);
+        }
+        if dry_run {
+            args.push("--dry-run".to_string());
+        }
+        Ok(args)
+    }
+
+    /// Parses rclone's JSON log into the actions it took or, with
+    /// `--dry-run`, would take. Lines that aren't JSON are ignored.
+    ///
+    /// # Returns
+    /// The plan, and the errors rclone logged for single files.
+    pub fn parse_log(log: &str) -> (SyncPlan, Vec<String>) {
+        let mut actions = Vec::new();
+        let mut errors = Vec::new();
+        for line in log.lines() {
+            let Ok(entry) = serde_json::from_str::<RcloneLogLine>(line.trim()) else {
+                continue;
+            };
+            let Some(object) = entry.object.filter(|object| !object.is_empty()) else {
+                if entry.level == "error" {
+                    errors.push(entry.msg);
+                }
+                continue;
+            };
+            if entry.level == "error" {
+                errors.push(format!("{}: {}", object, entry.msg));
+                continue;
+            }
+            let msg = entry.msg.as_str();
+            let action = match () {
+                _ if msg.starts_with("Copied (new)") => SyncAction::Create(object),
+                _ if msg.starts_with("Copied") => SyncAction::Update(object),
+                _ if msg.starts_with("Skipped copy as --dry-run") => SyncAction::Update(object),
+                _ if msg.starts_with("Deleted") || msg.starts_with("Skipped delete as --dry-run") => {
+                    SyncAction::Delete(object)
+                }
+                _ => continue,
+            };
+            actions.push(action);
+        }
+        (SyncPlan::new(actions), errors)
+    }
+
+    /// Runs rclone in its own process group and parses its log.
+    fn run(&self, dry_run: bool) -> Result<SyncPlan, Error> {
+        let size_filtered = !self.config.get_media_size_limits().is_empty();
+        if self.config.get_strict_mode() && (self.config.get_exclude_regex().is_some() || size_filtered) {
+            return Err(anyhow!(
+                "rclone can't apply the exclusion regex or size filters, and strict mode would delete the files they exclude"
+            ));
+        }
+
+        let mut cmd = self.config.get_io_priority().command(RCLONE_PROGRAM);
+        cmd.args(self.args(dry_run)?).stdout(Stdio::null()).stderr(Stdio::piped());
+        ChildProcesses::isolate(&mut cmd);
+        debug_log!(RCLONE_LOGGER_DOMAIN, format!("Running {:?}", cmd));
+
+        let mut child = cmd.spawn().with_context(|| format!("Failed to run {}, is it installed?", RCLONE_PROGRAM))?;
+        let tracked = ChildProcesses::track(child.id());
+        let stderr = child.stderr.take().ok_or_else(|| anyhow!("Failed to capture stderr"))?;
+
+        let monitor = ActivityMonitor::new();
+        let watchdog = StallWatchdog::watch_limits(
+            child.id(),
+            monitor.clone(),
+            self.config.get_stall_timeout(),
+            self.config.get_transfer_timeout()
+        );
+        let mut log = String::new();
+        for line in BufReader::new(monitor.reader(stderr)).lines().map_while(Result::ok) {
+            log.push_str(&line);
+            log.push('\n');
+        }
+        let exit_status = ResourceUsage::wait(&mut child)?;
+        drop(tracked);
+        if let Some(kill) = watchdog.and_then(StallWatchdog::finish) {
+            return Err(kill.into_error(RCLONE_PROGRAM));
+        }
+
+        let (plan, errors) = Self::parse_log(&log);
+        if !exit_status.success() {
+            return Err(anyhow!("rclone failed with {}: {}", exit_status, errors.join("; ")));
+        }
+        Ok(plan)
+    }
+
+    /// Quotes a value of an rclone connection string.
+    fn quote(value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
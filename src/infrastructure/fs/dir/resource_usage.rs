@@ -0,0 +1,151 @@
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::Result as IoResult,
+    process::{Child, ExitStatus},
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+
+thread_local! {
+    /// Usage of the child processes waited for on this thread, one entry
+    /// per running measurement, innermost last.
+    static MEASUREMENTS: RefCell<Vec<Option<ResourceUsage>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Resources consumed by a child process and the processes it waited for,
+/// e.g. rsync and its SSH connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+
+    /// User and system CPU time, in milliseconds
+    pub cpu_time_ms: u64,
+
+    /// Largest resident set size of a single process, in bytes
+    pub peak_rss_bytes: u64,
+
+    /// Bytes read from storage
+    pub read_bytes: u64,
+
+    /// Bytes written to storage
+    pub written_bytes: u64,
+}
+
+impl ResourceUsage {
+
+    /// Runs `f`, collecting the usage of the child processes waited for
+    /// with [`wait`](Self::wait) on the current thread meanwhile.
+    ///
+    /// # Returns
+    /// The result of `f` and the combined usage, `None` if no child
+    /// process was waited for.
+    pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<Self>) {
+        MEASUREMENTS.with(|measurements| measurements.borrow_mut().push(None));
+        let result = f();
+        let usage = MEASUREMENTS.with(|measurements| measurements.borrow_mut().pop().flatten());
+        // Nested measurements also count towards the enclosing one
+        if let Some(usage) = usage {
+            Self::record(usage);
+        }
+        (result, usage)
+    }
+
+    /// Waits for a child process to exit, recording its usage for
+    /// [`measure`](Self::measure).
+    ///
+    /// # Errors
+    /// Returns the I/O error of the underlying wait.
+    #[cfg(unix)]
+    pub fn wait(child: &mut Child) -> IoResult<ExitStatus> {
+        use std::os::unix::process::ExitStatusExt;
+
+        let pid = libc::pid_t::try_from(child.id()).map_err(std::io::Error::other)?;
+        let mut status = 0;
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        loop {
+            let waited = unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) };
+            if waited == pid {
+                break;
+            }
+            let error = std::io::Error::last_os_error();
+            match error.raw_os_error() {
+                Some(libc::EINTR) => continue,
+                // Already reaped, e.g. by try_wait, which keeps the status
+                Some(libc::ECHILD) => return child.wait(),
+                _ => return Err(error),
+            }
+        }
+        Self::record(Self::from_rusage(&rusage));
+        Ok(ExitStatus::from_raw(status))
+    }
+
+    /// Waits for a child process to exit. Usage isn't available on this
+    /// platform, so nothing is recorded.
+    ///
+    /// # Errors
+    /// Returns the I/O error of the underlying wait.
+    #[cfg(not(unix))]
+    pub fn wait(child: &mut Child) -> IoResult<ExitStatus> {
+        child.wait()
+    }
+
+    /// Adds the usage of another process, keeping the larger peak RSS.
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            cpu_time_ms: self.cpu_time_ms.saturating_add(other.cpu_time_ms),
+            peak_rss_bytes: self.peak_rss_bytes.max(other.peak_rss_bytes),
+            read_bytes: self.read_bytes.saturating_add(other.read_bytes),
+            written_bytes: self.written_bytes.saturating_add(other.written_bytes),
+        }
+    }
+
+    /// Returns the CPU time.
+    pub fn cpu_time(&self) -> Duration {
+        Duration::from_millis(self.cpu_time_ms)
+    }
+
+    /// Adds usage to the current measurement, if any.
+    fn record(usage: Self) {
+        MEASUREMENTS.with(|measurements| {
+            if let Some(total) = measurements.borrow_mut().last_mut() {
+                *total = Some(total.map_or(usage, |total| total.combine(usage)));
+            }
+        });
+    }
+
+    /// Converts the usage reported by `wait4`.
+    ///
+    /// Block counts are in 512-byte units, and the peak RSS is in KiB on
+    /// Linux but in bytes on macOS.
+    #[cfg(unix)]
+    fn from_rusage(rusage: &libc::rusage) -> Self {
+        let millis = |time: libc::timeval| {
+            u64::try_from(time.tv_sec).unwrap_or(0) * 1000 + u64::try_from(time.tv_usec).unwrap_or(0) / 1000
+        };
+        let max_rss = u64::try_from(rusage.ru_maxrss).unwrap_or(0);
+        let peak_rss_bytes = if cfg!(target_os = "macos") { max_rss } else { max_rss * 1024 };
+        Self {
+            cpu_time_ms: millis(rusage.ru_utime) + millis(rusage.ru_stime),
+            peak_rss_bytes,
+            read_bytes: u64::try_from(rusage.ru_inblock).unwrap_or(0) * 512,
+            written_bytes: u64::try_from(rusage.ru_oublock).unwrap_or(0) * 512,
+        }
+    }
+}
+
+impl Display for ResourceUsage {
+
+    /// Formats the usage, e.g. `cpu 1.25s, peak rss 12.0 MiB, read 0.0 MiB, written 3.5 MiB`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+        write!(
+            f,
+            "cpu {:.2}s, peak rss {:.1} MiB, read {:.1} MiB, written {:.1} MiB",
+            self.cpu_time().as_secs_f64(),
+            mib(self.peak_rss_bytes),
+            mib(self.read_bytes),
+            mib(self.written_bytes)
+        )
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU64, Ordering}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use crate::{debug_log, info_log};
+use super::{
+    child_processes::ChildProcesses,
+    disk_space::DiskSpace,
+    resource_usage::ResourceUsage,
+    stall_watchdog::{ActivityMonitor, StallWatchdog},
+    sync_config::DirSyncConfig,
+    sync_plan::{SyncAction, SyncPlan},
+    write_access::ReadOnlyDestination
+};
+
+/// Domain identifier for robocopy logs
+const ROBOCOPY_LOGGER_DOMAIN: &str = "[ROBOCOPY]";
+
+/// Program run for the transfer.
+const ROBOCOPY_PROGRAM: &str = "robocopy";
+
+/// Lowest exit code robocopy uses for failures; lower codes are bit masks
+/// of what was copied or found.
+const ROBOCOPY_FAILURE_EXIT_CODE: i32 = 8;
+
+/// Size of the blocks robocopy pauses between with `/IPG`.
+const ROBOCOPY_IPG_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Labels of the log lines robocopy writes for files and directories it
+/// leaves as they are.
+const ROBOCOPY_UNCHANGED_CLASSES: [&str; 5] = ["Same", "Tweaked", "lonely", "Mismatch", "attrib"];
+
+/// Sequence number of the log files of concurrent runs.
+static LOG_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Directory synchronization with `robocopy`, which ships with Windows,
+/// for SMB shares and local destinations on hosts without rsync.
+///
+/// robocopy copies files whose size or modification time differ, with a
+/// two-second tolerance for NAS filesystems, and retries failed copies
+/// briefly. A bandwidth limit becomes a pause between 64 KiB blocks,
+/// which keeps the rate below it. The suffix filters of the
+/// configuration become file patterns; the regex and size filters can't
+/// be expressed and don't apply. In strict mode, destination files
+/// missing from the source are purged, except those the suffix filters
+/// exclude, so strict mode is refused along with the regex or size
+/// filters, whose files would be purged.
+///
+/// Changes are read from robocopy's log, whose labels follow the display
+/// language of Windows. Only English labels are known, and a run whose
+/// log holds others fails rather than reporting no changes.
+pub struct RobocopySync {
+
+    /// Configuration for the sync operation
+    config: DirSyncConfig,
+}
+
+impl RobocopySync {
+
+    /// Creates a robocopy sync with the given configuration.
+    pub fn new(config: DirSyncConfig) -> Self {
+        Self { config }
+    }
+
+    /// Computes the changes a sync would make with `robocopy /L`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the filters can't be applied, robocopy
+    /// can't be run or fails, or its log can't be read.
+    pub fn plan(&self) -> Result<SyncPlan, Error> {
+        self.parse_log(&self.run(true)?)
+    }
+
+    /// Synchronizes the source into the destination.
+    ///
+    /// In dry-run mode, the plan is logged instead and the destination is
+    /// left untouched.
+    ///
+    /// # Returns
+    /// The paths copied or deleted, relative to the destination.
+    ///
+    /// # Errors
+    /// Returns a [`SyncHang`](super::SyncHang) if robocopy was killed as
+    /// stuck, a [`SyncTimeout`](super::SyncTimeout) if it ran too long, or
+    /// `anyhow::Error` if a check fails, robocopy can't be run or it
+    /// reports a failure.
+    pub fn sync(&self) -> Result<Vec<String>, Error> {
+        if let Some(guard) = self.config.get_guard_file() {
+            if !Path::new(&guard).exists() {
+                return Err(anyhow!("Guard file '{}' does not exist, sync aborted.", guard));
+            }
+        }
+        let source = self.config.get_source().get_path();
+        if !Path::new(&source).exists() {
+            return Err(anyhow!("Source path '{}' does not exist, sync aborted.", source));
+        }
+
+        let plan = if self.config.get_dry_run() {
+            let plan = self.plan()?;
+            for action in plan.actions() {
+                info_log!(ROBOCOPY_LOGGER_DOMAIN, format!("Dry run: {}", action));
+            }
+            plan
+        } else {
+            ReadOnlyDestination::check(&self.config.get_destination())?;
+            DiskSpace::preflight(&self.config)?;
+            self.parse_log(&self.run(false)?)?
+        };
+
+        Ok(plan
+            .actions()
+            .iter()
+            .filter(|action| !matches!(action, SyncAction::CreateDir(_)))
+            .map(|action| action.path().to_string())
+            .collect())
+    }
+
+    /// Returns the arguments of a robocopy run logging to `log`.
+    ///
+    /// # Arguments
+    /// * `list_only` - Whether robocopy only lists what it would do (`/L`)
+    /// * `log` - File robocopy writes its UTF-16 log to
+    pub fn args(&self, list_only: bool, log: &Path) -> Vec<String> {
+        let mut args = vec![
+            Self::dir_arg(&self.config.get_source().get_path()),
+            Self::dir_arg(&self.config.get_destination().get_path()),
+        ];
+
+        // Subtitles are copied along with the included files, as with rsync
+        let include_suffixes = self.config.get_include_suffixes();
+        if !include_suffixes.is_empty() {
+            let subtitles = self.config.get_subtitle_extensions();
+            args.extend(include_suffixes.iter().chain(subtitles.iter()).map(|suffix| format!("*.{}", suffix)));
+        }
+        args.extend(["/E", "/R:2", "/W:5", "/XJ", "/FFT", "/NP", "/NJH", "/NJS", "/FP", "/BYTES", "/TEE"].map(String::from));
+        args.push(format!("/UNILOG:{}", log.display()));
+        if self.config.get_strict_mode() {
+            args.push("/PURGE".to_string());
+        }
+        if let Some(rate) = self.config.get_bandwidth_limit() {
+            args.push(format!("/IPG:{}", (ROBOCOPY_IPG_BLOCK_SIZE * 1000).div_ceil(rate.bytes())));
+        }
+        if list_only {
+            args.push("/L".to_string());
+        }
+
+        let exclude_suffixes = self.config.get_exclude_suffixes();
+        if include_suffixes.is_empty() && !exclude_suffixes.is_empty() {
+            args.push("/XF".to_string());
+            args.extend(exclude_suffixes.iter().map(|suffix| format!("*.{}", suffix)));
+        }
+        args
+    }
+
+    /// Parses a robocopy log into the actions it took or, with `/L`,
+    /// would take.
+    ///
+    /// Extra destination files are only reported as deletions in strict
+    /// mode, since robocopy lists them either way but only purges them
+    /// with `/PURGE`. Attribute-only changes are ignored.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a line of a path below the source or
+    /// destination has a label that isn't known, such as those of
+    /// robocopy in other languages than English.
+    pub fn parse_log(&self, log: &str) -> Result<SyncPlan, Error> {
+        let source = self.config.get_source().get_path();
+        let destination = self.config.get_destination().get_path();
+        let strict = self.config.get_strict_mode();
+
+        let mut actions = Vec::new();
+        for line in log.lines() {
+            let mut fields = line.split('\t').map(str::trim).filter(|field| !field.is_empty());
+            let Some(path) = fields.next_back() else {
+                continue;
+            };
+            let Some(class) = fields.next() else {
+                continue;
+            };
+            let class = class.trim_end_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+            let relative = |root: &str| Self::relative_path(path, root);
+            let action = match class {
+                "New File" => relative(&source).map(SyncAction::Create),
+                "Newer" | "Older" | "Changed" | "Modified" => relative(&source).map(SyncAction::Update),
+                "New Dir" => relative(&source).map(SyncAction::CreateDir),
+                "*EXTRA File" | "*EXTRA Dir" if strict => relative(&destination).map(SyncAction::Delete),
+                "*EXTRA File" | "*EXTRA Dir" => None,
+                "" => None,
+                class if ROBOCOPY_UNCHANGED_CLASSES.contains(&class) => None,
+                class if relative(&source).is_some() || relative(&destination).is_some() => {
+                    return Err(anyhow!(
+                        "Unknown robocopy log label '{}' for {}, only English labels are supported",
+                        class,
+                        path
+                    ));
+                }
+                _ => None,
+            };
+            actions.extend(action);
+        }
+        Ok(SyncPlan::new(actions))
+    }
+
+    /// Runs robocopy in its own process group and returns its log.
+    fn run(&self, list_only: bool) -> Result<String, Error> {
+        let size_filtered = !self.config.get_media_size_limits().is_empty();
+        if self.config.get_strict_mode() && (self.config.get_exclude_regex().is_some() || size_filtered) {
+            return Err(anyhow!(
+                "robocopy can't apply the exclusion regex or size filters, and strict mode would purge the files they exclude"
+            ));
+        }
+        let log = std::env::temp_dir().join(format!(
+            "pilipili-robocopy-{}-{}.log",
+            std::process::id(),
+            LOG_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = self.run_logged(list_only, &log);
+        let _ = fs::remove_file(&log);
+        result
+    }
+
+    /// Runs robocopy logging to `log`, then reads the log.
+    fn run_logged(&self, list_only: bool, log: &Path) -> Result<String, Error> {
+        let mut cmd = Command::new(ROBOCOPY_PROGRAM);
+        cmd.args(self.args(list_only, log)).stdout(Stdio::piped()).stderr(Stdio::null());
+        ChildProcesses::isolate(&mut cmd);
+        debug_log!(ROBOCOPY_LOGGER_DOMAIN, format!("Running {:?}", cmd));
+
+        let mut child = cmd.spawn().with_context(|| format!("Failed to run {}, it ships with Windows only", ROBOCOPY_PROGRAM))?;
+        let tracked = ChildProcesses::track(child.id());
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("Failed to capture stdout"))?;
+
+        // The console copy of the log only tells the watchdog robocopy is alive
+        let monitor = ActivityMonitor::new();
+        let watchdog = StallWatchdog::watch_limits(
+            child.id(),
+            monitor.clone(),
+            self.config.get_stall_timeout(),
+            self.config.get_transfer_timeout()
+        );
+        let drained = io::copy(&mut monitor.reader(stdout), &mut io::sink());
+        let exit_status = ResourceUsage::wait(&mut child)?;
+        drop(tracked);
+        if let Some(kill) = watchdog.and_then(StallWatchdog::finish) {
+            return Err(kill.into_error(ROBOCOPY_PROGRAM));
+        }
+        drained?;
+
+        let output = Self::decode_utf16(&fs::read(log).with_context(|| format!("Failed to read {}", log.display()))?);
+        match exit_status.code() {
+            Some(code) if code < ROBOCOPY_FAILURE_EXIT_CODE => Ok(output),
+            _ => {
+                let errors: Vec<&str> = output.lines().filter(|line| line.contains("ERROR")).collect();
+                Err(anyhow!("robocopy failed with {}: {}", exit_status, errors.join("; ")))
+            }
+        }
+    }
+
+    /// Strips the trailing separator rsync paths carry, keeping that of a
+    /// drive root such as `D:\`, since `D:` means its working directory.
+    fn dir_arg(path: &str) -> String {
+        let trimmed = path.trim_end_matches(['\\', '/']);
+        match trimmed {
+            "" => path.to_string(),
+            drive if drive.ends_with(':') => format!("{}\\", drive),
+            dir => dir.to_string(),
+        }
+    }
+
+    /// Decodes a UTF-16LE log, skipping its byte order mark.
+    fn decode_utf16(bytes: &[u8]) -> String {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let text = String::from_utf16_lossy(&units);
+        text.strip_prefix('\u{feff}').map(str::to_string).unwrap_or(text)
+    }
+
+    /// Returns a path of the log relative to a root, with forward slashes,
+    /// or `None` for the root itself and paths outside it.
+    fn relative_path(path: &str, root: &str) -> Option<String> {
+        let root = root.trim_end_matches(['\\', '/']);
+        let prefix = path.get(..root.len()).filter(|prefix| prefix.eq_ignore_ascii_case(root))?;
+        let relative = path[prefix.len()..].trim_matches(['\\', '/']).replace('\\', "/");
+        (!relative.is_empty()).then_some(relative)
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::Write;
+
+use anyhow::{Context, Error, Result};
+use tempfile::NamedTempFile;
+
+use crate::warn_log;
+
+/// Domain identifier for rsync filter logs
+const RSYNC_FILTER_LOGGER_DOMAIN: &str = "[RSYNC-FILTER]";
+
+/// Filter rules passed to rsync in a file, merged with `--filter=merge`.
+///
+/// Rules for single paths, such as undersized media files, are written to
+/// a temporary file instead of the command line, which a large library
+/// would overflow. Paths are anchored at the transfer root and matched
+/// literally. Rules keep the order they are added in, since rsync applies
+/// the first rule matching a path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RsyncFilterFile {
+
+    /// Rules in rsync's short form, e.g. `- /show/sample.mkv`
+    rules: Vec<String>,
+}
+
+impl RsyncFilterFile {
+
+    /// Creates an empty set of rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule excluding a path relative to the transfer root.
+    ///
+    /// Paths holding line breaks can't be written as a rule and are
+    /// skipped with a warning.
+    pub fn exclude_path(&mut self, relative: &str) {
+        self.push_path('-', relative);
+    }
+
+    /// Adds a rule including a path relative to the transfer root.
+    ///
+    /// Paths holding line breaks can't be written as a rule and are
+    /// skipped with a warning.
+    pub fn include_path(&mut self, relative: &str) {
+        self.push_path('+', relative);
+    }
+
+    /// Adds a rule excluding the paths matching an rsync pattern.
+    pub fn exclude_pattern(&mut self, pattern: &str) {
+        self.rules.push(format!("- {}", pattern));
+    }
+
+    /// Returns the rules in the order they were added.
+    pub fn rules(&self) -> &[String] {
+        &self.rules
+    }
+
+    /// Returns `true` if no rule was added.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Writes the rules to a temporary file, removed when it is dropped.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be written.
+    pub fn write(&self) -> Result<NamedTempFile, Error> {
+        let mut file = tempfile::Builder::new()
+            .prefix("pilipili-rsync-")
+            .suffix(".rules")
+            .tempfile()
+            .context("Failed to create rsync filter file")?;
+        for rule in &self.rules {
+            writeln!(file, "{}", rule)?;
+        }
+        file.flush()?;
+        Ok(file)
+    }
+
+    /// Returns the argument merging the rules of a written file.
+    pub fn merge_arg(file: &NamedTempFile) -> String {
+        format!("--filter=merge {}", file.path().display())
+    }
+
+    /// Adds a rule of the given kind for a literal, anchored path.
+    fn push_path(&mut self, kind: char, relative: &str) {
+        if relative.contains(['\n', '\r']) {
+            warn_log!(
+                RSYNC_FILTER_LOGGER_DOMAIN,
+                format!("Can't write a filter rule for {:?}, it is left to the other rules", relative)
+            );
+            return;
+        }
+        self.rules.push(format!("{} /{}", kind, Self::escape(relative)));
+    }
+
+    /// Escapes a path so an rsync filter rule matches it literally.
+    ///
+    /// rsync only treats backslashes as escapes in patterns holding a
+    /// wildcard, so paths without one are returned unchanged.
+    fn escape(path: &str) -> String {
+        if !path.contains(['*', '?', '[']) {
+            return path.to_string();
+        }
+        path.chars().fold(String::with_capacity(path.len()), |mut escaped, c| {
+            if matches!(c, '*' | '?' | '[' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+    }
+}
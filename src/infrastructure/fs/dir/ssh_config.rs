@@ -1,4 +1,12 @@
-use serde::Serialize;
+use std::{
+    env,
+    fs,
+    path::{Path, PathBuf}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{infrastructure::fs::PathHelper, warn_log};
 
 /// Default SSH password authentication options with reduced security checks.
 ///
@@ -14,12 +22,27 @@ use serde::Serialize;
 /// - Cases where host key verification isn't practical
 pub const SSH_PASSWORD_OPTIONS: &str = "ssh -o StrictHostKeyChecking=no -o UserKnownHostsFile=/dev/null";
 
+/// Environment variable `sshpass -e` reads the password from, keeping it
+/// out of the command line that other users can list with `ps`.
+pub const SSHPASS_ENV: &str = "SSHPASS";
+
+/// Domain identifier for SSH configuration logs
+const SSH_LOGGER_DOMAIN: &str = "[SSH]";
+
+/// Name of the directory holding SSH control sockets, inside the user's
+/// runtime directory or the temp directory, followed by the user id.
+const SSH_CONTROL_DIR_NAME: &str = "pilipili_strm-ssh";
+
+/// Path of the directory holding SSH control sockets below the user's
+/// configuration directory, the default state directory.
+const SSH_CONTROL_STATE_DIR: &str = "pilipili_strm/ssh";
+
 /// Configuration for SSH connection parameters.
 ///
 /// This struct encapsulates all necessary parameters to establish an SSH connection,
 /// supporting both key-based and password authentication. It provides a builder pattern
 /// for convenient configuration and methods to generate appropriate connection strings.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SshConfig {
 
     /// SSH username (defaults to "root" if not specified)
@@ -35,7 +58,12 @@ pub struct SshConfig {
     port: Option<u16>,
 
     /// Path to private key file for authentication
-    key_path: Option<String>
+    key_path: Option<String>,
+
+    /// Seconds a shared master connection stays open after the last sync;
+    /// enables OpenSSH connection multiplexing when set
+    #[serde(default)]
+    control_persist_secs: Option<u64>
 }
 
 impl Default for SshConfig {
@@ -51,7 +79,8 @@ impl Default for SshConfig {
             password: None,
             ip: "127.0.0.1".to_string(),
             port: None,
-            key_path: None
+            key_path: None,
+            control_persist_secs: None
         }
     }
 }
@@ -102,6 +131,15 @@ impl SshConfig {
         self
     }
 
+    /// Enables connection multiplexing (builder pattern).
+    ///
+    /// Consecutive syncs to the same host reuse one master connection, which
+    /// stays open for `secs` seconds after the last one finishes.
+    pub fn with_control_persist(mut self, secs: u64) -> Self {
+        self.control_persist_secs = Some(secs);
+        self
+    }
+
     /// Gets the SSH username, defaults to "root" if not specified.
     pub fn get_username(&self) -> &str {
         self.username.as_deref().unwrap_or("root")
@@ -132,9 +170,138 @@ impl SshConfig {
 
     /// Generates rsync-compatible SSH arguments based on configuration.
     ///
-    /// Returns `None` if neither key nor password authentication is configured.
-    /// When both key and password are configured, the key takes precedence.
+    /// Returns `None` if neither key nor password authentication is configured,
+    /// the default port is used, and multiplexing is disabled. When both key
+    /// and password are configured, the key takes precedence.
     pub fn to_rsync_arg(&self) -> Option<String> {
+        let command = self.ssh_command()
+            .or_else(|| self.control_persist_secs.map(|_| format!("ssh -p {}", self.get_port())))?;
+
+        match self.control_persist_secs.map(Self::control_options).filter(|options| !options.is_empty()) {
+            Some(options) => Some(format!("{} {}", command, options.join(" "))),
+            None => Some(command),
+        }
+    }
+
+    /// Generates the options for running `ssh` directly, without the
+    /// program name or destination.
+    ///
+    /// Includes the port, private key and multiplexing options. Password
+    /// authentication additionally disables host key checks, matching
+    /// [`SSH_PASSWORD_OPTIONS`].
+    pub fn to_ssh_args(&self) -> Vec<String> {
+        let mut args = vec!["-p".to_string(), self.get_port().to_string()];
+        if let Some(key) = &self.key_path {
+            args.push("-i".to_string());
+            args.push(key.clone());
+        } else if self.password.is_some() {
+            args.extend(SSH_PASSWORD_OPTIONS.split_whitespace().skip(1).map(str::to_string));
+        }
+        if let Some(secs) = self.control_persist_secs {
+            args.extend(Self::control_options(secs));
+        }
+        args
+    }
+
+    /// Returns the `user@host` destination passed to `ssh`.
+    pub fn to_destination(&self) -> String {
+        format!("{}@{}", self.get_username(), self.get_ip())
+    }
+
+    /// Builds the OpenSSH options sharing one master connection per host.
+    ///
+    /// The control socket lives in a private directory of the user, see
+    /// [`control_dir`](Self::control_dir), and is named by OpenSSH's `%C`
+    /// hash of user, host and port. Without such a directory, connections
+    /// aren't multiplexed and no options are returned.
+    fn control_options(persist_secs: u64) -> Vec<String> {
+        let Some(dir) = Self::control_dir() else {
+            return Vec::new();
+        };
+        let control_path = dir.join("%C");
+        vec![
+            "-o".to_string(),
+            "ControlMaster=auto".to_string(),
+            "-o".to_string(),
+            format!("ControlPersist={}", persist_secs),
+            "-o".to_string(),
+            format!("ControlPath={}", control_path.display()),
+        ]
+    }
+
+    /// Returns the directory for control sockets, creating it if needed.
+    ///
+    /// Sockets of other users must not be reachable, so the directory is
+    /// private to the user: inside `$XDG_RUNTIME_DIR` when set, otherwise
+    /// below the user's configuration directory or, lacking one, a
+    /// per-user directory in the temp directory. A directory that is a
+    /// symbolic link, owned by someone else or can't be made private is
+    /// refused with a warning.
+    fn control_dir() -> Option<PathBuf> {
+        let dir = match (dirs::runtime_dir(), PathHelper::config_dir()) {
+            (Some(runtime), _) => runtime.join(SSH_CONTROL_DIR_NAME),
+            (None, Some(config)) => config.join(SSH_CONTROL_STATE_DIR),
+            (None, None) => env::temp_dir().join(format!("{}-{}", SSH_CONTROL_DIR_NAME, Self::user_id())),
+        };
+        match Self::create_private_dir(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) => {
+                warn_log!(
+                    SSH_LOGGER_DOMAIN,
+                    format!("Not multiplexing SSH connections, {} is unsafe: {}", dir.display(), e)
+                );
+                None
+            }
+        }
+    }
+
+    /// Creates a directory only the current user can access, checking an
+    /// existing one is theirs.
+    #[cfg(unix)]
+    fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+        match fs::DirBuilder::new().recursive(true).mode(0o700).create(dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        let metadata = fs::symlink_metadata(dir)?;
+        if !metadata.is_dir() {
+            return Err(Error::other("not a directory"));
+        }
+        if metadata.uid() != Self::user_id() {
+            return Err(Error::other("owned by another user"));
+        }
+        if metadata.mode() & 0o077 != 0 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    /// Creates the directory for control sockets, which OpenSSH doesn't
+    /// support outside Unix anyway.
+    #[cfg(not(unix))]
+    fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)
+    }
+
+    /// Returns the id of the user running the process.
+    #[cfg(unix)]
+    fn user_id() -> u32 {
+        // SAFETY: getuid has no preconditions and can't fail
+        unsafe { libc::getuid() }
+    }
+
+    /// Returns a placeholder user id, as there are none outside Unix.
+    #[cfg(not(unix))]
+    fn user_id() -> u32 {
+        0
+    }
+
+    /// Builds the base SSH command for the configured authentication.
+    fn ssh_command(&self) -> Option<String> {
         match (&self.key_path, &self.password) {
             (Some(key), None) => {
                 Some(format!(
@@ -160,7 +327,7 @@ impl SshConfig {
                     self.port.unwrap_or(22)
                 ))
             }
-            (None, None) => None,
+            (None, None) => self.port.map(|port| format!("ssh -p {}", port)),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,202 @@
+use std::{
+    path::PathBuf,
+    process::Command,
+    time::Duration
+};
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::debug_log;
+use super::{
+    command::{run_with_timeout, shell_quote, CommandOutput},
+    scanner::ScannedFile,
+    ssh_config::{SshConfig, SSHPASS_ENV}
+};
+
+/// Domain identifier for SSH runner logs
+const SSH_RUNNER_LOGGER_DOMAIN: &str = "[SSH-RUNNER]";
+
+/// Default time a remote command may run before it is killed.
+const SSH_RUNNER_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs commands on a remote host over SSH.
+///
+/// Uses the same authentication and multiplexing settings as rsync, so
+/// commands can share the master connection of previous syncs. Used for:
+/// - Remote existence checks before syncing
+/// - Free space and rsync version detection
+/// - Listings of remote sources
+/// - Arbitrary commands such as post-sync hooks
+pub struct SshRunner {
+
+    /// Connection settings of the remote host
+    config: SshConfig,
+
+    /// Maximum duration of a single command
+    timeout: Duration,
+}
+
+impl SshRunner {
+
+    /// Creates a runner for the given host with the default timeout.
+    pub fn new(config: SshConfig) -> Self {
+        Self {
+            config,
+            timeout: SSH_RUNNER_DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the maximum duration of a single command (builder pattern).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Builds the local process that runs `command` remotely.
+    ///
+    /// Password authentication is wrapped in `sshpass`, which reads the
+    /// password from the environment; otherwise `BatchMode` prevents `ssh`
+    /// from waiting for interactive input.
+    pub fn build_command(&self, command: &str) -> Command {
+        let mut cmd = match self.config.get_password() {
+            Some(password) if !password.is_empty() => {
+                let mut cmd = Command::new("sshpass");
+                cmd.arg("-e").arg("ssh").env(SSHPASS_ENV, password);
+                cmd
+            }
+            _ => {
+                let mut cmd = Command::new("ssh");
+                cmd.arg("-o").arg("BatchMode=yes");
+                cmd
+            }
+        };
+        cmd.args(self.config.to_ssh_args())
+            .arg(self.config.to_destination())
+            .arg("--")
+            .arg(command);
+        cmd
+    }
+
+    /// Runs a command remotely and captures its output.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if `ssh` can't be started or the command
+    /// exceeds the timeout, in which case it is killed.
+    pub fn run(&self, command: &str) -> Result<CommandOutput, Error> {
+        debug_log!(
+            SSH_RUNNER_LOGGER_DOMAIN,
+            format!("Running on {}: {}", self.config.to_destination(), command)
+        );
+
+        run_with_timeout(self.build_command(command), self.timeout).map_err(|e| {
+            anyhow!("Command '{}' on {} failed: {}", command, self.config.to_destination(), e)
+        })
+    }
+
+    /// Returns `true` if `path` exists on the remote host.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the check itself can't be run.
+    pub fn path_exists(&self, path: &str) -> Result<bool, Error> {
+        let output = self.run(&format!("test -e {}", shell_quote(path)))?;
+        match output.status {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(anyhow!("Checking '{}' failed: {}", path, output.stderr.trim())),
+        }
+    }
+
+    /// Returns the free space in bytes of the filesystem holding `path`,
+    /// or its closest existing parent.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if `df` fails or its output can't be parsed.
+    pub fn free_space(&self, path: &str) -> Result<u64, Error> {
+        // Fourth column: available 1K blocks
+        self.df_columns(path)?
+            .get(3)
+            .and_then(|blocks| blocks.parse::<u64>().ok())
+            .map(|blocks| blocks * 1024)
+            .ok_or_else(|| anyhow!("Unexpected df output for '{}'", path))
+    }
+
+    /// Returns the mount point of the filesystem holding `path`, or its
+    /// closest existing parent.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if `df` fails or its output can't be parsed.
+    pub fn mount_point(&self, path: &str) -> Result<String, Error> {
+        // Sixth column onwards: mounted on, which may contain spaces
+        let columns = self.df_columns(path)?;
+        if columns.len() < 6 {
+            return Err(anyhow!("Unexpected df output for '{}'", path));
+        }
+        Ok(columns[5..].join(" "))
+    }
+
+    /// Returns the columns of the POSIX `df` output for the closest
+    /// existing directory of `path`.
+    fn df_columns(&self, path: &str) -> Result<Vec<String>, Error> {
+        let script = format!(
+            "d={}; while [ ! -d \"$d\" ]; do d=$(dirname \"$d\"); done; df -Pk \"$d\"",
+            shell_quote(path)
+        );
+        let output = self.run(&script)?;
+        if !output.success() {
+            return Err(anyhow!("df failed: {}", output.stderr.trim()));
+        }
+        output.stdout
+            .lines()
+            .nth(1)
+            .map(|line| line.split_whitespace().map(str::to_string).collect())
+            .ok_or_else(|| anyhow!("Unexpected df output: {}", output.stdout.trim()))
+    }
+
+    /// Lists every file below `path` on the remote host, sorted by
+    /// relative path, like [`DirScanner::scan`](super::DirScanner::scan).
+    ///
+    /// Uses `find` and `stat`, which BusyBox provides as well. Names
+    /// holding line breaks aren't supported, and modification times
+    /// aren't listed.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the listing fails.
+    pub fn list_files(&self, path: &str) -> Result<Vec<ScannedFile>, Error> {
+        let script = format!("cd {} && find . -type f -exec stat -c '%s %n' {{}} +", shell_quote(path));
+        let output = self.run(&script)?;
+        if !output.success() {
+            return Err(anyhow!("Listing '{}' failed: {}", path, output.stderr.trim()));
+        }
+        let mut files: Vec<ScannedFile> = output.stdout
+            .lines()
+            .filter_map(|line| {
+                let (size, name) = line.split_once(' ')?;
+                Some(ScannedFile {
+                    relative: PathBuf::from(name.strip_prefix("./")?),
+                    size: size.parse().ok()?,
+                    modified: None,
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Ok(files)
+    }
+
+    /// Returns the rsync version installed on the remote host (e.g. `3.2.7`).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if rsync isn't installed or its output can't be parsed.
+    pub fn rsync_version(&self) -> Result<String, Error> {
+        let output = self.run("rsync --version")?;
+        if !output.success() {
+            return Err(anyhow!("rsync is not available: {}", output.stderr.trim()));
+        }
+        // First line looks like "rsync  version 3.2.7  protocol version 31"
+        output.stdout
+            .split_whitespace()
+            .skip_while(|word| *word != "version")
+            .nth(1)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Unexpected rsync --version output"))
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{Read, Result as IoResult},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+        Arc
+    },
+    thread,
+    time::{Duration, Instant}
+};
+
+use anyhow::Error;
+
+use crate::warn_log;
+use super::child_processes::ChildProcesses;
+
+/// Domain identifier for stall watchdog logs
+const STALL_WATCHDOG_LOGGER_DOMAIN: &str = "[STALL-WATCHDOG]";
+
+/// Longest interval between two checks of a watched process.
+const STALL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A transfer that was killed after producing no output for too long.
+///
+/// A wedged SSH connection leaves rsync waiting forever without printing
+/// anything, so callers can tell such a run apart from one that failed
+/// by downcasting, e.g. to retry it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncHang {
+
+    /// Program that stopped making progress
+    pub program: String,
+
+    /// Time the program went without output before it was killed
+    pub idle: Duration,
+}
+
+impl Display for SyncHang {
+
+    /// Formats the error with the program and how long it was idle.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} made no progress for {}s and was killed as stuck",
+            self.program,
+            self.idle.as_secs()
+        )
+    }
+}
+
+impl StdError for SyncHang {}
+
+impl SyncHang {
+
+    /// Returns `true` if the error, or any error in its context chain,
+    /// is a [`SyncHang`].
+    pub fn is_hang(error: &Error) -> bool {
+        error.chain().any(|cause| cause.downcast_ref::<SyncHang>().is_some())
+    }
+}
+
+/// A transfer that was killed for running longer than its time limit.
+///
+/// Syncs don't keep partially transferred files, so another attempt
+/// would start over and run into the same limit; it isn't retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncTimeout {
+
+    /// Program that ran too long
+    pub program: String,
+
+    /// Time the program ran before it was killed
+    pub elapsed: Duration,
+}
+
+impl Display for SyncTimeout {
+
+    /// Formats the error with the program and how long it ran.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} timed out after {}s and was killed", self.program, self.elapsed.as_secs())
+    }
+}
+
+impl StdError for SyncTimeout {}
+
+impl SyncTimeout {
+
+    /// Returns `true` if the error, or any error in its context chain,
+    /// is a [`SyncTimeout`].
+    pub fn is_timeout(error: &Error) -> bool {
+        error.chain().any(|cause| cause.downcast_ref::<SyncTimeout>().is_some())
+    }
+}
+
+/// Why a [`StallWatchdog`] killed a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogKill {
+
+    /// The process went without output for too long
+    Stalled {
+
+        /// Time without output
+        idle: Duration,
+    },
+
+    /// The process ran longer than its time limit
+    TimedOut {
+
+        /// Time the process ran
+        elapsed: Duration,
+    },
+}
+
+impl WatchdogKill {
+
+    /// Converts the kill into a [`SyncHang`] or [`SyncTimeout`] error of
+    /// the given program.
+    pub fn into_error(self, program: impl Into<String>) -> Error {
+        let program = program.into();
+        match self {
+            WatchdogKill::Stalled { idle } => SyncHang { program, idle }.into(),
+            WatchdogKill::TimedOut { elapsed } => SyncTimeout { program, elapsed }.into(),
+        }
+    }
+}
+
+/// Time of the last output of a process, shared by the threads reading
+/// its pipes and the watchdog.
+#[derive(Debug, Clone)]
+pub struct ActivityMonitor {
+
+    /// When monitoring started
+    started: Instant,
+
+    /// Milliseconds after `started` of the last activity
+    last_activity_ms: Arc<AtomicU64>,
+}
+
+impl Default for ActivityMonitor {
+
+    /// Creates a monitor whose last activity is now.
+    fn default() -> Self {
+        ActivityMonitor {
+            started: Instant::now(),
+            last_activity_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl ActivityMonitor {
+
+    /// Creates a monitor whose last activity is now.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records activity now.
+    pub fn touch(&self) {
+        let elapsed = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.last_activity_ms.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    /// Returns the time since the last activity.
+    pub fn idle(&self) -> Duration {
+        let last = Duration::from_millis(self.last_activity_ms.load(Ordering::Relaxed));
+        self.started.elapsed().saturating_sub(last)
+    }
+
+    /// Wraps a pipe so that every read returning data counts as activity.
+    ///
+    /// rsync rewrites its progress line with carriage returns, so reads
+    /// are tracked rather than lines.
+    pub fn reader<R: Read>(&self, inner: R) -> ActivityReader<R> {
+        ActivityReader { inner, monitor: self.clone() }
+    }
+}
+
+/// A reader recording activity on an [`ActivityMonitor`].
+pub struct ActivityReader<R> {
+
+    /// Wrapped pipe
+    inner: R,
+
+    /// Monitor touched whenever data is read
+    monitor: ActivityMonitor,
+}
+
+impl<R: Read> Read for ActivityReader<R> {
+
+    /// Reads from the wrapped pipe, recording activity if data arrived.
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.monitor.touch();
+        }
+        Ok(read)
+    }
+}
+
+/// Kills a process and its descendants once it goes without output for
+/// longer than a timeout, or runs longer than a time limit.
+///
+/// The process must have been started in its own process group with
+/// [`ChildProcesses::isolate`], so that SSH connections it opened are
+/// killed along with it.
+pub struct StallWatchdog {
+
+    /// Stops the watchdog when sent to or dropped
+    finished: mpsc::Sender<()>,
+
+    /// Watchdog thread, returning why it killed the process, if it did
+    handle: thread::JoinHandle<Option<WatchdogKill>>,
+}
+
+impl StallWatchdog {
+
+    /// Starts watching a process for stalls.
+    ///
+    /// # Arguments
+    /// * `pid` - ID of the process, the leader of its process group
+    /// * `monitor` - Monitor the readers of the process's pipes touch
+    /// * `timeout` - Time without activity after which the process is killed
+    pub fn watch(pid: u32, monitor: ActivityMonitor, timeout: Duration) -> Self {
+        Self::spawn(pid, monitor, Some(timeout), None)
+    }
+
+    /// Starts watching a process for stalls and for its total run time.
+    ///
+    /// # Arguments
+    /// * `pid` - ID of the process, the leader of its process group
+    /// * `monitor` - Monitor the readers of the process's pipes touch
+    /// * `stall_timeout` - Time without activity after which the process is killed
+    /// * `transfer_timeout` - Run time after which the process is killed
+    ///
+    /// # Returns
+    /// `None` if neither limit is set, so there is nothing to watch.
+    pub fn watch_limits(
+        pid: u32,
+        monitor: ActivityMonitor,
+        stall_timeout: Option<Duration>,
+        transfer_timeout: Option<Duration>,
+    ) -> Option<Self> {
+        (stall_timeout.is_some() || transfer_timeout.is_some())
+            .then(|| Self::spawn(pid, monitor, stall_timeout, transfer_timeout))
+    }
+
+    /// Starts the watchdog thread.
+    fn spawn(
+        pid: u32,
+        monitor: ActivityMonitor,
+        stall_timeout: Option<Duration>,
+        transfer_timeout: Option<Duration>,
+    ) -> Self {
+        let (finished, stopped) = mpsc::channel::<()>();
+        let shortest = stall_timeout.into_iter().chain(transfer_timeout).min().unwrap_or(STALL_POLL_INTERVAL);
+        let poll_interval = (shortest / 4).clamp(Duration::from_millis(10), STALL_POLL_INTERVAL);
+        let started = Instant::now();
+        let handle = thread::spawn(move || loop {
+            match stopped.recv_timeout(poll_interval) {
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let idle = monitor.idle();
+                    let elapsed = started.elapsed();
+                    let kill = if stall_timeout.is_some_and(|timeout| idle >= timeout) {
+                        warn_log!(
+                            STALL_WATCHDOG_LOGGER_DOMAIN,
+                            format!("Process {} made no progress for {}s, killing it", pid, idle.as_secs())
+                        );
+                        WatchdogKill::Stalled { idle }
+                    } else if transfer_timeout.is_some_and(|timeout| elapsed >= timeout) {
+                        warn_log!(
+                            STALL_WATCHDOG_LOGGER_DOMAIN,
+                            format!("Process {} ran for {}s, killing it", pid, elapsed.as_secs())
+                        );
+                        WatchdogKill::TimedOut { elapsed }
+                    } else {
+                        continue;
+                    };
+                    ChildProcesses::kill_tree(pid);
+                    return Some(kill);
+                }
+                _ => return None,
+            }
+        });
+        StallWatchdog { finished, handle }
+    }
+
+    /// Stops watching.
+    ///
+    /// # Returns
+    /// Why the watchdog killed the process, if it did.
+    pub fn finish(self) -> Option<WatchdogKill> {
+        let _ = self.finished.send(());
+        self.handle.join().unwrap_or_default()
+    }
+}
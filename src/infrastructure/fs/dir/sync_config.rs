@@ -1,16 +1,60 @@
-use std::fmt::{
-    Display,
-    Formatter,
-    Result as FmtResult,
-    Error
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult,
+        Error
+    },
+    time::Duration
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_regex;
 use regex::Regex;
 use anyhow::Result;
 
-use super::DirLocation;
+use super::{
+    super::file::{ByteSize, MediaSizeLimits},
+    DirLocation,
+    IoPriority
+};
+
+/// How files that already exist at the destination are treated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+
+    /// Existing files are never touched
+    Skip,
+
+    /// Existing files are always rewritten
+    Overwrite,
+
+    /// Existing files are rewritten when their content differs
+    #[default]
+    OverwriteIfChanged,
+}
+
+/// How metadata files, such as `.nfo` files and artwork, are synced.
+///
+/// See [`MetadataFiles`](crate::infrastructure::fs::MetadataFiles) for
+/// the files this applies to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataPolicy {
+
+    /// Metadata is copied like any other file
+    #[default]
+    Copy,
+
+    /// Metadata is hard-linked from the source, so a library and its
+    /// mirror on the same filesystem share it; files that can't be linked
+    /// are copied. Only local destinations are linked, remote ones get copies
+    Link,
+
+    /// Metadata isn't synced; copies already at the destination are kept
+    Skip,
+}
 
 /// Configuration for directory synchronization operations.
 ///
@@ -29,6 +73,9 @@ pub struct DirSyncConfig {
     /// When true, enables additional validation and safety checks
     strict_mode: bool,
 
+    /// When true, changes are reported instead of performed
+    dry_run: bool,
+
     /// List of file suffixes to explicitly include (without leading dots)
     include_suffixes: Vec<String>,
 
@@ -41,6 +88,47 @@ pub struct DirSyncConfig {
 
     /// Optional guard file that must be present to proceed with sync
     guard_file: Option<String>,
+
+    /// CPU and disk priority rsync runs with
+    io_priority: IoPriority,
+
+    /// Treatment of existing destination files, `None` for rsync's size
+    /// and modification time check
+    overwrite_policy: Option<OverwritePolicy>,
+
+    /// Minimum sizes below which video and audio files are skipped
+    media_size_limits: MediaSizeLimits,
+
+    /// Suffixes of subtitle files synced along with their media files
+    /// (without leading dots)
+    subtitle_extensions: Vec<String>,
+
+    /// How `.nfo` files and artwork are synced
+    metadata_policy: MetadataPolicy,
+
+    /// Time rsync may go without output before it is killed as stuck,
+    /// `None` to wait forever
+    stall_timeout: Option<Duration>,
+
+    /// Time rsync may run in total before it is killed, `None` for no limit
+    transfer_timeout: Option<Duration>,
+
+    /// When true, transferred files are compared with their source by
+    /// checksum after the sync
+    verify_transfers: bool,
+
+    /// Directory on the destination's host partial files are written to
+    /// before they are renamed into place, `None` for the directory of
+    /// each file
+    temp_dir: Option<String>,
+
+    /// Free space the destination and the temporary directory must have
+    /// for a sync to start, `None` to skip the check
+    min_free_space: Option<ByteSize>,
+
+    /// Transfer rate in bytes per second the sync may not exceed, `None`
+    /// for unlimited
+    bandwidth_limit: Option<ByteSize>,
 }
 
 impl Display for DirSyncConfig {
@@ -63,10 +151,22 @@ impl Default for DirSyncConfig {
             source: DirLocation::default(),
             destination: DirLocation::default(),
             strict_mode: false,
+            dry_run: false,
             include_suffixes: Vec::new(),
             exclude_suffixes: Vec::new(),
             exclude_regex: None,
             guard_file: None,
+            io_priority: IoPriority::default(),
+            overwrite_policy: None,
+            media_size_limits: MediaSizeLimits::default(),
+            subtitle_extensions: Vec::new(),
+            metadata_policy: MetadataPolicy::default(),
+            stall_timeout: None,
+            transfer_timeout: None,
+            verify_transfers: false,
+            temp_dir: None,
+            min_free_space: None,
+            bandwidth_limit: None,
         }
     }
 }
@@ -101,6 +201,13 @@ impl DirSyncConfig {
         self
     }
 
+    /// Enables or disables dry-run mode, which reports the changes a sync
+    /// would make without touching the destination (builder pattern).
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
     /// Sets included file suffixes, automatically trimming leading dots (builder pattern).
     pub fn with_include_suffixes(mut self, suffixes: Vec<&str>) -> Self {
         self.include_suffixes = suffixes.into_iter()
@@ -132,6 +239,91 @@ impl DirSyncConfig {
         self
     }
 
+    /// Sets the CPU and disk priority rsync runs with (builder pattern).
+    pub fn with_io_priority(mut self, io_priority: IoPriority) -> Self {
+        self.io_priority = io_priority;
+        self
+    }
+
+    /// Sets how existing destination files are treated (builder pattern).
+    pub fn with_overwrite_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite_policy = Some(policy);
+        self
+    }
+
+    /// Sets the size below which video files are skipped, e.g. sample clips (builder pattern).
+    pub fn with_min_video_size(mut self, size: ByteSize) -> Self {
+        self.media_size_limits.min_video_size = Some(size);
+        self
+    }
+
+    /// Sets the size below which audio files are skipped (builder pattern).
+    pub fn with_min_audio_size(mut self, size: ByteSize) -> Self {
+        self.media_size_limits.min_audio_size = Some(size);
+        self
+    }
+
+    /// Sets the suffixes of subtitle files synced along with their media
+    /// files, automatically trimming leading dots (builder pattern).
+    ///
+    /// When included suffixes limit the sync to media files, subtitles
+    /// such as `Movie.en.srt` are synced too if their media file is; see
+    /// [`SubtitleCompanions`](crate::infrastructure::fs::SubtitleCompanions).
+    pub fn with_subtitle_extensions(mut self, extensions: Vec<&str>) -> Self {
+        self.subtitle_extensions = extensions.into_iter()
+            .map(|s| String::from(s.trim_start_matches('.')))
+            .collect();
+        self
+    }
+
+    /// Sets how `.nfo` files and artwork are synced (builder pattern).
+    pub fn with_metadata_policy(mut self, policy: MetadataPolicy) -> Self {
+        self.metadata_policy = policy;
+        self
+    }
+
+    /// Sets the time rsync may go without output before it is killed as
+    /// stuck (builder pattern); a zero timeout waits forever.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        self
+    }
+
+    /// Sets the time rsync may run in total before it is killed, however
+    /// much output it prints (builder pattern); a zero timeout is no limit.
+    pub fn with_transfer_timeout(mut self, timeout: Duration) -> Self {
+        self.transfer_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        self
+    }
+
+    /// Enables or disables the checksum verification of transferred files
+    /// (builder pattern).
+    pub fn with_verify_transfers(mut self, verify: bool) -> Self {
+        self.verify_transfers = verify;
+        self
+    }
+
+    /// Sets the directory partial files are written to (builder pattern).
+    ///
+    /// A relative path is relative to the destination directory.
+    pub fn with_temp_dir(mut self, temp_dir: &str) -> Self {
+        self.temp_dir = Some(temp_dir.to_string());
+        self
+    }
+
+    /// Sets the free space required for a sync to start (builder pattern).
+    pub fn with_min_free_space(mut self, size: ByteSize) -> Self {
+        self.min_free_space = Some(size);
+        self
+    }
+
+    /// Sets the transfer rate per second the sync may not exceed (builder
+    /// pattern); a zero rate is unlimited.
+    pub fn with_bandwidth_limit(mut self, rate: ByteSize) -> Self {
+        self.bandwidth_limit = Some(rate).filter(|rate| rate.bytes() > 0);
+        self
+    }
+
     /// Gets a clone of the source directory location.
     pub fn get_source(&self) -> DirLocation {
         self.source.clone()
@@ -152,6 +344,11 @@ impl DirSyncConfig {
         self.strict_mode
     }
 
+    /// Checks if dry-run mode is enabled.
+    pub fn get_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Gets a clone of the included suffixes list.
     pub fn get_include_suffixes(&self) -> Vec<String> {
         self.include_suffixes.clone()
@@ -166,4 +363,77 @@ impl DirSyncConfig {
     pub fn get_exclude_regex(&self) -> Option<Regex> {
         self.exclude_regex.clone()
     }
-}
\ No newline at end of file
+
+    /// Gets the CPU and disk priority rsync runs with.
+    pub fn get_io_priority(&self) -> IoPriority {
+        self.io_priority
+    }
+
+    /// Gets the treatment of existing destination files, if set.
+    pub fn get_overwrite_policy(&self) -> Option<OverwritePolicy> {
+        self.overwrite_policy
+    }
+
+    /// Gets the minimum sizes of video and audio files.
+    pub fn get_media_size_limits(&self) -> MediaSizeLimits {
+        self.media_size_limits
+    }
+
+    /// Gets how `.nfo` files and artwork are synced.
+    pub fn get_metadata_policy(&self) -> MetadataPolicy {
+        self.metadata_policy
+    }
+
+    /// Gets a clone of the subtitle suffixes synced along with media files.
+    pub fn get_subtitle_extensions(&self) -> Vec<String> {
+        self.subtitle_extensions.clone()
+    }
+
+    /// Gets the time rsync may go without output, if limited.
+    pub fn get_stall_timeout(&self) -> Option<Duration> {
+        self.stall_timeout
+    }
+
+    /// Gets the time rsync may run in total, if limited.
+    pub fn get_transfer_timeout(&self) -> Option<Duration> {
+        self.transfer_timeout
+    }
+
+    /// Returns whether transferred files are verified by checksum.
+    pub fn get_verify_transfers(&self) -> bool {
+        self.verify_transfers
+    }
+
+    /// Gets a clone of the directory partial files are written to, if set.
+    pub fn get_temp_dir(&self) -> Option<String> {
+        self.temp_dir.clone()
+    }
+
+    /// Gets the directory partial files are written to, if set, with a
+    /// relative path resolved against the destination directory, as
+    /// rsync resolves `--temp-dir`.
+    ///
+    /// For SSH destinations, the returned path is on the remote host.
+    pub fn get_resolved_temp_dir(&self) -> Option<String> {
+        let temp_dir = self.temp_dir.as_deref()?;
+        if temp_dir.starts_with(['/', '\\']) || std::path::Path::new(temp_dir).is_absolute() {
+            return Some(temp_dir.to_string());
+        }
+        let destination = self.destination.get_path();
+        let dir = match self.destination.ssh_config() {
+            Some(_) => destination.split_once(':').map_or(destination.as_str(), |(_, path)| path),
+            None => destination.as_str(),
+        };
+        Some(std::path::Path::new(dir).join(temp_dir).to_string_lossy().into_owned())
+    }
+
+    /// Gets the free space required for a sync to start, if set.
+    pub fn get_min_free_space(&self) -> Option<ByteSize> {
+        self.min_free_space
+    }
+
+    /// Gets the transfer rate per second the sync may not exceed, if limited.
+    pub fn get_bandwidth_limit(&self) -> Option<ByteSize> {
+        self.bandwidth_limit
+    }
+}
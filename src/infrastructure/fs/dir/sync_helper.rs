@@ -1,20 +1,48 @@
 use std::{
+    collections::HashSet,
+    fs,
     process::{Command, Stdio},
-    io::{BufReader, BufRead},
-    path::Path
+    io::{BufReader, BufRead, Read},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant}
 };
-use anyhow::{Result, anyhow, Error};
+use anyhow::{Result, anyhow, Context, Error};
 use regex::Regex;
+use tempfile::NamedTempFile;
 
 use crate::{info_log, debug_log, warn_log};
 use super::{
-    sync_config::DirSyncConfig,
-    ssh_config::SSH_PASSWORD_OPTIONS
+    super::file::{MetadataFiles, SubtitleCompanions},
+    child_processes::ChildProcesses,
+    disk_space::DiskSpace,
+    progress_event::{FileProgressTracker, ProgressSender},
+    progress_reporter::ProgressReporter,
+    resource_usage::ResourceUsage,
+    rsync_filter::RsyncFilterFile,
+    scanner::{DirScanner, ScannedFile},
+    sync_config::{DirSyncConfig, MetadataPolicy, OverwritePolicy},
+    sync_plan::SyncPlan,
+    sync_report::SyncReport,
+    transfer_verifier::{TransferVerifier, VerificationReport},
+    ssh_config::{SSHPASS_ENV, SSH_PASSWORD_OPTIONS},
+    ssh_runner::SshRunner,
+    stall_watchdog::{ActivityMonitor, StallWatchdog},
+    write_access::ReadOnlyDestination
 };
 
 /// Domain identifier for file sync logs
 const DIR_SYNC_LOGGER_DOMAIN: &str = "[DIR-SYNC]";
 
+/// Prefix rsync gives files it removes from the destination.
+const RSYNC_DELETING_PREFIX: &str = "deleting ";
+
+/// First line of the statistics rsync prints with `--stats`.
+const RSYNC_STATS_HEADER: &str = "Number of files:";
+
+/// Time a listing of a remote source may take.
+const REMOTE_LISTING_TIMEOUT: Duration = Duration::from_secs(600);
+
 /// Callback type for progress updates
 type ProgressCallback = Box<dyn Fn(&str) + Send + 'static>;
 
@@ -27,7 +55,7 @@ type FileSyncCallback = Box<dyn Fn(&str) + Send + 'static>;
 /// - Pre-sync validation checks
 /// - Rsync command construction
 /// - Process execution and output handling
-/// - Progress and file sync callbacks
+/// - Progress and file sync callbacks, and typed progress events
 pub struct DirSyncHelper {
 
     /// Configuration for the sync operation
@@ -38,6 +66,9 @@ pub struct DirSyncHelper {
 
     /// Optional callback for file sync notifications
     file_sync_callback: Option<FileSyncCallback>,
+
+    /// Optional channel for typed progress events
+    progress_sender: Option<ProgressSender>,
 }
 
 impl DirSyncHelper {
@@ -48,6 +79,7 @@ impl DirSyncHelper {
             config,
             progress_callback: None,
             file_sync_callback: None,
+            progress_sender: None,
         }
     }
 
@@ -65,24 +97,76 @@ impl DirSyncHelper {
         self.file_sync_callback = Some(callback);
     }
 
+    /// Sets a channel receiving a [`ProgressEvent`](super::ProgressEvent)
+    /// when a file starts transferring, progresses and is done.
+    ///
+    /// rsync then reports the progress of each file instead of that of
+    /// the whole transfer, which the progress callback receives as well.
+    pub fn set_progress_sender(&mut self, sender: ProgressSender) {
+        self.progress_sender = Some(sender);
+    }
+
     /// Performs the directory synchronization.
     ///
     /// # Steps
     /// 1. Validates guard file (if configured)
     /// 2. Checks source directory existence
-    /// 3. Builds and executes rsync command
-    /// 4. Processes output with callbacks
+    /// 3. Checks the destination is writable
+    /// 4. Checks the free space and the temporary directory with
+    ///    [`DiskSpace::preflight`]
+    /// 5. Builds and executes rsync command in its own process group,
+    ///    killed by [`ChildProcesses::kill_all`] on shutdown
+    /// 6. Processes output with callbacks, killing rsync and the SSH
+    ///    connections it opened if it goes without output for longer
+    ///    than the stall timeout, or runs longer than the transfer timeout
+    /// 7. Verifies the transferred files by checksum, if enabled
+    ///
+    /// In dry-run mode, the [`SyncPlan`] is computed instead and each of its
+    /// actions (e.g. `delete show/ep1.mkv`) is logged and passed to the file
+    /// sync callback, leaving the destination untouched.
     ///
     /// # Errors
-    /// Returns `anyhow::Error` if any step fails or rsync returns non-zero status.
+    /// Returns a [`SyncHang`](super::SyncHang) if rsync was killed as
+    /// stuck, a [`SyncTimeout`](super::SyncTimeout) if it ran too long, or
+    /// `anyhow::Error` if any step fails or rsync returns non-zero status.
     pub fn sync(&self) -> Result<(), Error> {
+        self.sync_with_report().map(|_| ())
+    }
+
+    /// Performs the directory synchronization like [`DirSyncHelper::sync`]
+    /// and returns its statistics.
+    ///
+    /// # Returns
+    /// The statistics rsync printed, or those of the plan in dry-run mode.
+    ///
+    /// # Errors
+    /// Same as [`DirSyncHelper::sync`].
+    pub fn sync_with_report(&self) -> Result<SyncReport, Error> {
+        let started = Instant::now();
+        if self.config.get_dry_run() {
+            let plan = self.plan()?;
+            for action in plan.actions() {
+                info_log!(DIR_SYNC_LOGGER_DOMAIN, format!("Dry run: {}", action));
+                if let Some(ref cb) = self.file_sync_callback {
+                    cb(&action.to_string());
+                }
+            }
+            return Ok(SyncReport::from_plan(&plan).with_duration(started.elapsed()));
+        }
+
         self.check_guard_file()?;
         self.check_source_dir()?;
+        ReadOnlyDestination::check(&self.config.get_destination())?;
+        DiskSpace::preflight(&self.config)?;
 
-        let mut cmd = self.build_rsync_command()?;
+        // The filter file must outlive rsync
+        let (mut cmd, _filters) = self.build_rsync_command(false, false)?;
         cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        ChildProcesses::isolate(&mut cmd);
 
-        let mut child = cmd.spawn()?;
+        let program = cmd.get_program().to_string_lossy().into_owned();
+        let mut child = Self::spawn(&mut cmd)?;
+        let tracked = ChildProcesses::track(child.id());
         let stdout = child.stdout
             .take()
             .ok_or_else(|| anyhow!("Failed to capture stdout"))?;
@@ -90,14 +174,216 @@ impl DirSyncHelper {
             .take()
             .ok_or_else(|| anyhow!("Failed to capture stderr"))?;
 
-        self.process_output(stdout, stderr)?;
+        let monitor = ActivityMonitor::new();
+        let watchdog = StallWatchdog::watch_limits(
+            child.id(),
+            monitor.clone(),
+            self.config.get_stall_timeout(),
+            self.config.get_transfer_timeout()
+        );
+        let output = self.process_output(monitor.reader(stdout), monitor.reader(stderr));
+        let exit_status = ResourceUsage::wait(&mut child)?;
+        drop(tracked);
+        if let Some(kill) = watchdog.and_then(StallWatchdog::finish) {
+            return Err(kill.into_error(program));
+        }
 
-        let exit_status = child.wait()?;
+        let (stderr_output, transferred, stats) = output?;
         if !exit_status.success() {
-            return Err(anyhow!("rsync failed"));
+            return Err(anyhow!("rsync failed with {}: {}", exit_status, stderr_output.trim()));
         }
 
-        Ok(())
+        if self.links_metadata() {
+            let linked = self.link_metadata()?;
+            debug_log!(DIR_SYNC_LOGGER_DOMAIN, format!("Linked or removed {} metadata files", linked));
+        }
+
+        if self.config.get_verify_transfers() {
+            let report = self.verify(&transferred)?;
+            if !report.is_ok() {
+                return Err(anyhow!(
+                    "Verification of {} failed, {}",
+                    self.config.get_destination().get_path(),
+                    report
+                ));
+            }
+            info_log!(DIR_SYNC_LOGGER_DOMAIN, report.to_string());
+        }
+        Ok(SyncReport::parse_rsync(&stats, &stderr_output).with_duration(started.elapsed()))
+    }
+
+    /// Returns `true` if metadata files are linked instead of copied by rsync,
+    /// which needs both the source and the destination to be local.
+    fn links_metadata(&self) -> bool {
+        self.config.get_metadata_policy() == MetadataPolicy::Link
+            && self.config.get_source().ssh_config().is_none()
+            && self.config.get_destination().ssh_config().is_none()
+    }
+
+    /// Hard-links the metadata files of the source into the destination,
+    /// copying those that can't be linked, e.g. across filesystems.
+    ///
+    /// Files matching [`MetadataFiles::rsync_patterns`], which rsync was
+    /// told to exclude, and passing the suffix, regex and size filters are
+    /// linked, like rsync would have copied them; files already linked are
+    /// left alone. Excluded files are out of reach of `--delete`, so in
+    /// strict mode those left at the destination without a source are
+    /// removed here.
+    ///
+    /// # Returns
+    /// The number of files linked, copied or removed.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source or destination can't be
+    /// listed, or a file can neither be linked nor copied, or removed.
+    fn link_metadata(&self) -> Result<usize, Error> {
+        let source = PathBuf::from(self.config.get_source().get_path());
+        let destination = PathBuf::from(self.config.get_destination().get_path());
+        let include_suffixes = self.config.get_include_suffixes();
+        let exclude_suffixes = self.config.get_exclude_suffixes();
+        let exclude_regex = self.config.get_exclude_regex();
+        let media_size_limits = self.config.get_media_size_limits();
+        let has_suffix = |path: &Path, suffixes: &[String]| {
+            path.extension().is_some_and(|extension| {
+                suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix))
+            })
+        };
+        let linked_file = |file: &ScannedFile| {
+            let relative = &file.relative;
+            let filtered = if include_suffixes.is_empty() {
+                has_suffix(relative, &exclude_suffixes)
+            } else {
+                !has_suffix(relative, &include_suffixes)
+            };
+            MetadataFiles::matches_rsync_patterns(relative)
+                && !filtered
+                && !exclude_regex.as_ref().is_some_and(|regex| regex.is_match(&relative.to_string_lossy()))
+                && !media_size_limits.is_undersized(relative, file.size)
+        };
+
+        let mut changed = 0;
+        let mut linked = HashSet::new();
+        for file in DirScanner::scan(&source)?.into_iter().filter(|file| linked_file(file)) {
+            let relative = file.relative;
+            let (from, to) = (source.join(&relative), destination.join(&relative));
+            linked.insert(relative);
+            if Self::is_same_file(&from, &to) {
+                continue;
+            }
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if to.exists() {
+                fs::remove_file(&to)?;
+            }
+            fs::hard_link(&from, &to)
+                .or_else(|_| fs::copy(&from, &to).map(|_| ()))
+                .with_context(|| format!("Failed to link {} to {}", from.display(), to.display()))?;
+            changed += 1;
+        }
+
+        if self.config.get_strict_mode() {
+            for file in DirScanner::scan(&destination)? {
+                if linked.contains(&file.relative) || !linked_file(&file) {
+                    continue;
+                }
+                let stale = destination.join(&file.relative);
+                fs::remove_file(&stale).with_context(|| format!("Failed to remove {}", stale.display()))?;
+                debug_log!(DIR_SYNC_LOGGER_DOMAIN, format!("Removed stale metadata {}", stale.display()));
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Returns `true` if two paths are links to the same file.
+    #[cfg(unix)]
+    fn is_same_file(a: &Path, b: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    /// Hard links can't be told apart from copies here, so files are relinked.
+    #[cfg(not(unix))]
+    fn is_same_file(_a: &Path, _b: &Path) -> bool {
+        false
+    }
+
+    /// Spawns a command, naming the program in the error if it can't be started.
+    fn spawn(cmd: &mut Command) -> Result<std::process::Child, Error> {
+        let program = cmd.get_program().to_string_lossy().into_owned();
+        cmd.spawn().with_context(|| format!("Failed to start {}", program))
+    }
+
+    /// Computes the changes a sync would make without executing them.
+    ///
+    /// Runs rsync with `--dry-run --itemize-changes` using the same filters
+    /// and strict mode as [`DirSyncHelper::sync`]. Nothing is written to
+    /// the destination, not even a probe of its write access.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a pre-sync check fails or rsync returns
+    /// non-zero status.
+    pub fn plan(&self) -> Result<SyncPlan, Error> {
+        self.check_guard_file()?;
+        self.check_source_dir()?;
+
+        self.dry_run_plan(false)
+    }
+
+    /// Compares the files rsync transferred with their source by checksum.
+    ///
+    /// When both the source and the destination are local, each
+    /// transferred file is hashed on both sides. Otherwise rsync compares
+    /// every file by checksum in a dry run, and files it would still
+    /// transfer don't match.
+    ///
+    /// # Arguments
+    /// * `transferred` - Files as rsync listed them while syncing
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be read or the dry run fails.
+    pub fn verify(&self, transferred: &[String]) -> Result<VerificationReport, Error> {
+        let source = self.config.get_source();
+        let destination = self.config.get_destination();
+        if source.ssh_config().is_some() || destination.ssh_config().is_some() {
+            return Ok(VerificationReport::from_plan(&self.dry_run_plan(true)?));
+        }
+
+        // rsync lists paths below the source's name unless it ends with a slash
+        let source_path = source.get_path();
+        let source_root = if source_path.ends_with(['/', '\\']) {
+            PathBuf::from(&source_path)
+        } else {
+            Path::new(&source_path).parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let files: Vec<String> = transferred
+            .iter()
+            .filter(|path| !path.starts_with(RSYNC_DELETING_PREFIX) && !path.ends_with('/'))
+            .cloned()
+            .collect();
+        TransferVerifier::verify_local(&source_root, Path::new(&destination.get_path()), &files)
+    }
+
+    /// Runs rsync with `--dry-run --itemize-changes` and parses the changes.
+    ///
+    /// # Arguments
+    /// * `checksum` - Compare every file by content, whatever the overwrite policy
+    fn dry_run_plan(&self, checksum: bool) -> Result<SyncPlan, Error> {
+        let (mut cmd, _filters) = self.build_rsync_command(true, checksum)?;
+        let program = cmd.get_program().to_string_lossy().into_owned();
+        let output = cmd.output().with_context(|| format!("Failed to start {}", program))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "rsync dry run failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(SyncPlan::from_itemized_output(&String::from_utf8_lossy(&output.stdout)))
     }
 
     /// Validates the guard file if configured.
@@ -113,17 +399,28 @@ impl DirSyncHelper {
         Ok(())
     }
 
-    /// Validates the source directory exists (for local paths).
+    /// Validates the source directory exists.
+    ///
+    /// Remote sources are checked over SSH.
     ///
     /// # Errors
-    /// Returns error if source path doesn't exist (only for local paths).
+    /// Returns error if source path doesn't exist or the remote check fails.
     fn check_source_dir(&self) -> Result<(), Error> {
         if self.config.get_strict_mode() {
             return Ok(());
         }
-        let source_path = self.config.get_source().get_path();
-        if self.config.get_source().ssh_config().is_none() &&
-            !Path::new(&source_path).exists() {
+        let source = self.config.get_source();
+        let source_path = source.get_path();
+        let exists = match source.ssh_config() {
+            Some(ssh_config) => {
+                let remote_path = source_path
+                    .split_once(':')
+                    .map_or(source_path.as_str(), |(_, path)| path);
+                SshRunner::new(ssh_config.clone()).path_exists(remote_path)?
+            }
+            None => Path::new(&source_path).exists(),
+        };
+        if !exists {
             return Err(anyhow!("Source path '{}' does not exist, sync aborted.", source_path));
         }
         Ok(())
@@ -131,15 +428,21 @@ impl DirSyncHelper {
 
     /// Constructs the rsync command based on configuration.
     ///
+    /// # Arguments
+    /// * `dry_run` - Itemize the changes instead of performing them
+    /// * `checksum` - Compare every file by content, whatever the overwrite policy
+    ///
     /// # Returns
-    /// Configured `Command` ready for execution.
+    /// Configured `Command` ready for execution, and the file holding the
+    /// rules for single paths it merges, which must be kept until rsync
+    /// exits.
     ///
     /// # Notes
     /// - Handles both local and remote paths
     /// - Applies to include/exclude filters
     /// - Configures strict mode if enabled
     /// - Logs the final command for debugging
-    fn build_rsync_command(&self) -> Result<Command, Error> {
+    fn build_rsync_command(&self, dry_run: bool, checksum: bool) -> Result<(Command, Option<NamedTempFile>), Error> {
         // Get synchronization configuration by cloning from self
         let sync_config = self.config.clone();
 
@@ -150,6 +453,7 @@ impl DirSyncHelper {
         let include_suffixes = sync_config.get_include_suffixes();
         let exclude_suffixes = sync_config.get_exclude_suffixes();
         let exclude_regex = sync_config.get_exclude_regex();
+        let io_priority = sync_config.get_io_priority();
 
         // Check if SSH password authentication should be used
         let (use_sshpass, password) = dest_config.ssh_config()
@@ -157,25 +461,37 @@ impl DirSyncHelper {
             .map(|pwd| (!pwd.is_empty(), pwd))
             .unwrap_or((false, ""));
 
-        // Initialize the base command - either sshpass-wrapped rsync or direct rsync
+        // Initialize the base command - either sshpass-wrapped rsync or direct rsync,
+        // prefixed with nice/ionice when a priority is configured
         let mut cmd = if use_sshpass {
-            let mut sshpass_cmd = Command::new("sshpass");
+            // The password is passed in the environment, as arguments are visible in `ps`
+            let mut sshpass_cmd = io_priority.command("sshpass");
             sshpass_cmd
-                .arg("-p")
-                .arg(password)
-                .arg("rsync");
+                .arg("-e")
+                .arg("rsync")
+                .env(SSHPASS_ENV, password);
             sshpass_cmd
         } else {
-            Command::new("rsync")
+            io_priority.command("rsync")
         };
 
         // Add common rsync arguments:
         // -a: archive mode (recursive, preserve permissions, etc.)
-        // -v: verbose output
-        // --info=progress2: show progress information
-        cmd.arg("-a")
-            .arg("-v")
-            .arg("--info=progress2");
+        // -8: print non-ASCII names as is instead of escaping them
+        cmd.arg("-a").arg("-8");
+        if dry_run {
+            // --dry-run: report without changing anything
+            // --itemize-changes: print one change code per affected path
+            cmd.arg("--dry-run").arg("--itemize-changes");
+        } else {
+            // -v: verbose output
+            // --out-format=%n: print transferred names alone, without symlink targets
+            // --info=progress1: show the progress of each file, for progress events
+            // --info=progress2: show the progress of the whole transfer otherwise
+            // --stats: print transfer statistics at the end
+            let progress = if self.progress_sender.is_some() { "--info=progress1" } else { "--info=progress2" };
+            cmd.arg("-v").arg("--out-format=%n").arg(progress).arg("--stats");
+        }
 
         // Add SSH configuration if not using sshpass
         if !use_sshpass {
@@ -188,11 +504,95 @@ impl DirSyncHelper {
             cmd.arg("-e").arg(SSH_PASSWORD_OPTIONS);
         }
 
+        // Select how existing destination files are compared
+        let overwrite_policy = if checksum {
+            Some(OverwritePolicy::OverwriteIfChanged)
+        } else {
+            sync_config.get_overwrite_policy()
+        };
+        match overwrite_policy {
+            // --ignore-existing: never update files that exist at the destination
+            Some(OverwritePolicy::Skip) => { cmd.arg("--ignore-existing"); }
+            // --ignore-times: transfer every file even if size and time match
+            Some(OverwritePolicy::Overwrite) => { cmd.arg("--ignore-times"); }
+            // --checksum: compare content instead of size and time
+            Some(OverwritePolicy::OverwriteIfChanged) => { cmd.arg("--checksum"); }
+            None => {}
+        }
+
         // Add --delete flag if in strict mode (removes files in dest not present in source)
         if strict_mode {
             cmd.arg("--delete");
         }
 
+        // --temp-dir: write partial files there instead of next to each file
+        if let Some(temp_dir) = sync_config.get_resolved_temp_dir() {
+            cmd.arg(format!("--temp-dir={}", temp_dir));
+        }
+
+        // --bwlimit: cap the transfer rate, in KiB per second
+        if let Some(rate) = sync_config.get_bandwidth_limit() {
+            cmd.arg(format!("--bwlimit={}", (rate.bytes() / 1024).max(1)));
+        }
+
+        // Skip sample clips and placeholders; rsync's --min-size can't tell
+        // media from subtitles, so undersized files are excluded one by one,
+        // ahead of the suffix rules since the first matching rule wins
+        let media_size_limits = sync_config.get_media_size_limits();
+        let subtitle_extensions = sync_config.get_subtitle_extensions();
+        let find_subtitles = !subtitle_extensions.is_empty() && !include_suffixes.is_empty();
+        // Linked metadata is excluded here and linked once rsync is done
+        let exclude_metadata = sync_config.get_metadata_policy() == MetadataPolicy::Skip || self.links_metadata();
+        let remote_source = source_config.ssh_config().is_some();
+        let mut filters = RsyncFilterFile::new();
+        let mut synced_files = Vec::new();
+        // Metadata of a remote source, and linked metadata, is matched by
+        // name; linking leaves out the files the patterns don't match
+        let metadata_by_name = exclude_metadata && (remote_source || self.links_metadata());
+        let metadata_by_file = exclude_metadata && !metadata_by_name;
+        if !media_size_limits.is_empty() || (!remote_source && (find_subtitles || metadata_by_file)) {
+            for file in self.source_files()? {
+                let excluded = media_size_limits.is_undersized(&file.relative, file.size)
+                    || (metadata_by_file && MetadataFiles::is_metadata(&file.relative));
+                if excluded {
+                    filters.exclude_path(&file.relative.to_string_lossy().replace('\\', "/"));
+                } else {
+                    synced_files.push(file.relative);
+                }
+            }
+        }
+        if metadata_by_name {
+            for pattern in MetadataFiles::rsync_patterns() {
+                filters.exclude_pattern(&pattern);
+            }
+        }
+
+        // Include the subtitles of synced media files ahead of the suffix
+        // rules, which would exclude them; a remote source can't be
+        // scanned, so every subtitle is included there
+        if find_subtitles && !remote_source {
+            let is_media = |path: &Path| {
+                path.extension().is_some_and(|extension| {
+                    include_suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix))
+                })
+            };
+            for subtitle in SubtitleCompanions::find(&synced_files, &subtitle_extensions, is_media) {
+                filters.include_path(&subtitle.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        let filter_file = if filters.is_empty() {
+            None
+        } else {
+            let file = filters.write()?;
+            cmd.arg(RsyncFilterFile::merge_arg(&file));
+            Some(file)
+        };
+        if find_subtitles && remote_source {
+            for extension in &subtitle_extensions {
+                cmd.arg(format!("--include=*.{}", extension));
+            }
+        }
+
         // Handle file inclusion/exclusion patterns
         if !include_suffixes.is_empty() {
             // First include all directories
@@ -230,7 +630,25 @@ impl DirSyncHelper {
         self.print_sync_command(&mut cmd);
 
         // Return the constructed command
-        Ok(cmd)
+        Ok((cmd, filter_file))
+    }
+
+    /// Lists the files of the source, over SSH if it is remote.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the source can't be listed.
+    fn source_files(&self) -> Result<Vec<ScannedFile>, Error> {
+        let source = self.config.get_source();
+        let path = source.get_path();
+        match source.ssh_config() {
+            Some(ssh_config) => {
+                let remote_path = path.split_once(':').map_or(path.as_str(), |(_, path)| path);
+                SshRunner::new(ssh_config.clone())
+                    .with_timeout(REMOTE_LISTING_TIMEOUT)
+                    .list_files(remote_path)
+            }
+            None => DirScanner::scan(&path),
+        }
     }
 
     /// Formats and logs the rsync command being executed for debugging purposes.
@@ -275,24 +693,47 @@ impl DirSyncHelper {
     ///
     /// # Arguments
     /// * `stdout` - Child process stdout pipe
-    /// * `stderr` - Child process stderr pipe
+    /// * `stderr` - Child process stderr pipe, drained on a separate thread
+    ///   so that a full pipe can't block rsync
     ///
     /// # Behavior
     /// - Progress updates are sent to progress callback
     /// - File sync notifications are sent to file sync callback
+    /// - Both are sent as typed events to the progress channel
+    /// - The `--stats` summary, from `Number of files:` on, is collected
+    ///   apart from the listed files
     /// - Error output is logged
+    ///
+    /// # Returns
+    /// The collected error output, the files rsync listed and its statistics.
     fn process_output(
         &self,
-        stdout: std::process::ChildStdout,
-        stderr: std::process::ChildStderr,
-    ) -> Result<(), Error> {
+        stdout: impl Read,
+        stderr: impl Read + Send + 'static,
+    ) -> Result<(String, Vec<String>, String), Error> {
         let stdout_reader = BufReader::new(stdout);
-        let stderr_reader = BufReader::new(stderr);
-        let mut stderr_output = String::new();
+        let stderr_reader = thread::spawn(move || {
+            let mut stderr_output = String::new();
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                stderr_output.push_str(&line);
+                stderr_output.push('\n');
+            }
+            stderr_output
+        });
 
+        let mut synced_files = Vec::new();
+        let mut stats = String::new();
+        let mut tracker = self.progress_sender.clone().map(FileProgressTracker::new);
         for line in stdout_reader.lines() {
             let line = line?;
+            if let Some(tracker) = tracker.as_mut().filter(|_| stats.is_empty()) {
+                Self::track_progress(tracker, &line);
+            }
             match () {
+                _ if !stats.is_empty() || line.starts_with(RSYNC_STATS_HEADER) => {
+                    stats.push_str(&line);
+                    stats.push('\n');
+                }
                 _ if Self::check_file_sync_progress(&line) => {
                     // Progress information
                     if let Some(ref cb) = self.progress_callback {
@@ -304,23 +745,44 @@ impl DirSyncHelper {
                     if let Some(ref cb) = self.file_sync_callback {
                         cb(&line);
                     }
+                    synced_files.push(line);
                 }
                 _ => {}
             }
         }
 
-        // Collect stderr output
-        for line in stderr_reader.lines() {
-            stderr_output.push_str(&line?);
-            stderr_output.push('\n');
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.finish();
         }
 
+        // Collect stderr output
+        let stderr_output = stderr_reader
+            .join()
+            .map_err(|_| anyhow!("Failed to read rsync stderr"))?;
+
         // Log any stderr output
         if !stderr_output.is_empty() {
             info_log!(DIR_SYNC_LOGGER_DOMAIN, format!("Rsync stderr: {}", stderr_output.trim()));
         }
 
-        Ok(())
+        Ok((stderr_output, synced_files, stats))
+    }
+
+    /// Turns a line of rsync output into progress events.
+    ///
+    /// rsync separates its progress updates with carriage returns, and may
+    /// print the next file name on the same line, so each segment is
+    /// either an update of the current file or a file starting.
+    fn track_progress(tracker: &mut FileProgressTracker, line: &str) {
+        for segment in line.split('\r').filter(|segment| !segment.is_empty()) {
+            if let Some((_, percent, bytes_per_sec)) = ProgressReporter::parse_segment(segment) {
+                tracker.progress(percent, bytes_per_sec);
+            } else if Self::check_file_sync_line(segment)
+                && !segment.starts_with(RSYNC_DELETING_PREFIX)
+                && !segment.ends_with('/') {
+                tracker.started(segment);
+            }
+        }
     }
 
     /// Determines if a line from rsync output represents progress information.
@@ -333,7 +795,7 @@ impl DirSyncHelper {
     ///
     /// # Returns
     /// `true` if the line contains progress information, `false` otherwise
-    fn check_file_sync_progress(line: &String) -> bool {
+    fn check_file_sync_progress(line: &str) -> bool {
         (line.contains("to-chk") || line.contains("bytes/sec")) &&
             !(line.contains("sent") && line.contains("received"))
     }
@@ -347,12 +809,12 @@ impl DirSyncHelper {
     ///
     /// # Returns
     /// `true` if the line represents a file being transferred, `false` otherwise
-    fn check_file_sync_line(line: &String) -> bool {
-        !line.starts_with(" ") &&
-            !line.is_empty() &&
-            !line.starts_with("total size is") &&
-            !(line.contains("sent") && line.contains("received")) &&
-            !line.ends_with("sending incremental file list") &&
-            !line.ends_with("./")
+    fn check_file_sync_line(line: &str) -> bool {
+        !(line.starts_with(" ") ||
+            line.is_empty() ||
+            line.starts_with("total size is") ||
+            (line.contains("sent") && line.contains("received")) ||
+            line.ends_with("sending incremental file list") ||
+            line.ends_with("./"))
     }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+use serde::Serialize;
+
+use super::super::file::NaturalOrder;
+
+/// Prefix rsync uses in itemized output for files removed from the destination.
+const RSYNC_DELETING_PREFIX: &str = "*deleting";
+
+/// Width of the change code at the start of an itemized rsync line.
+const RSYNC_ITEMIZE_CODE_WIDTH: usize = 11;
+
+/// A single change a sync would make to the destination.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", content = "path", rename_all = "snake_case")]
+pub enum SyncAction {
+
+    /// A directory that doesn't exist in the destination yet
+    CreateDir(String),
+
+    /// A file that doesn't exist in the destination yet
+    Create(String),
+
+    /// A file whose content or attributes differ in the destination
+    Update(String),
+
+    /// A file or directory that would be removed from the destination
+    Delete(String),
+}
+
+impl SyncAction {
+
+    /// Returns the path affected by this action, relative to the destination.
+    pub fn path(&self) -> &str {
+        match self {
+            SyncAction::CreateDir(path)
+            | SyncAction::Create(path)
+            | SyncAction::Update(path)
+            | SyncAction::Delete(path) => path,
+        }
+    }
+
+    /// Returns `true` if this action removes data from the destination.
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, SyncAction::Delete(_))
+    }
+}
+
+impl Display for SyncAction {
+
+    /// Formats the action as `<verb> <path>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let verb = match self {
+            SyncAction::CreateDir(_) => "mkdir",
+            SyncAction::Create(_) => "create",
+            SyncAction::Update(_) => "update",
+            SyncAction::Delete(_) => "delete",
+        };
+        write!(f, "{} {}", verb, self.path())
+    }
+}
+
+/// The full list of changes a sync would make, computed without executing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SyncPlan {
+
+    /// Actions in the order rsync reported them
+    actions: Vec<SyncAction>,
+}
+
+impl SyncPlan {
+
+    /// Creates a plan from a list of actions.
+    pub fn new(actions: Vec<SyncAction>) -> Self {
+        Self { actions }
+    }
+
+    /// Parses the output of `rsync --dry-run --itemize-changes`.
+    ///
+    /// Attribute-only changes (lines starting with `.`) and non-itemized
+    /// lines are ignored.
+    pub fn from_itemized_output(output: &str) -> Self {
+        let actions = output
+            .lines()
+            .filter_map(Self::parse_itemized_line)
+            .collect();
+        Self { actions }
+    }
+
+    /// Returns all planned actions.
+    pub fn actions(&self) -> &[SyncAction] {
+        &self.actions
+    }
+
+    /// Returns `true` if the sync would change nothing.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Returns the actions that remove data from the destination.
+    pub fn deletions(&self) -> Vec<&SyncAction> {
+        self.actions.iter().filter(|action| action.is_destructive()).collect()
+    }
+
+    /// Parses a single itemized line such as `>f+++++++++ show/ep1.mkv`.
+    fn parse_itemized_line(line: &str) -> Option<SyncAction> {
+        if line.len() <= RSYNC_ITEMIZE_CODE_WIDTH + 1 || !line.is_char_boundary(RSYNC_ITEMIZE_CODE_WIDTH) {
+            return None;
+        }
+        let (code, rest) = line.split_at(RSYNC_ITEMIZE_CODE_WIDTH);
+        let path = rest.strip_prefix(' ')?.to_string();
+
+        if code.starts_with(RSYNC_DELETING_PREFIX) {
+            return Some(SyncAction::Delete(path));
+        }
+
+        let mut chars = code.chars();
+        let update_type = chars.next()?;
+        let file_type = chars.next()?;
+        let is_new = chars.all(|c| c == '+');
+
+        match (update_type, file_type) {
+            ('c', 'd') if is_new => Some(SyncAction::CreateDir(path)),
+            ('<' | '>' | 'c' | 'h', 'f' | 'L') if is_new => Some(SyncAction::Create(path)),
+            ('<' | '>' | 'c' | 'h', 'f' | 'L') => Some(SyncAction::Update(path)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for SyncPlan {
+
+    /// Formats the plan with one action per line, in natural order of
+    /// their paths so `E2` is listed before `E10`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.actions.is_empty() {
+            return write!(f, "nothing to do");
+        }
+        let mut actions: Vec<&SyncAction> = self.actions.iter().collect();
+        NaturalOrder::sort_by_key(&mut actions, |action| action.path());
+        let lines: Vec<String> = actions.iter().map(ToString::to_string).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
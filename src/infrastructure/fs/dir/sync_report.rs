@@ -0,0 +1,173 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::file::ByteSize,
+    sync_plan::{SyncAction, SyncPlan}
+};
+
+/// Statistics of a finished sync, for summaries and notifications.
+///
+/// The changed paths and the duration aren't serialized, as the sync
+/// history records their number and the time separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncReport {
+
+    /// Regular files created or updated at the destination
+    pub files_transferred: usize,
+
+    /// Size of the transferred files in bytes
+    pub bytes: u64,
+
+    /// Files and directories deleted from the destination
+    pub deleted: usize,
+
+    /// Regular files already up to date, which were left alone
+    pub skipped: usize,
+
+    /// Time the sync took
+    #[serde(skip)]
+    pub duration: Duration,
+
+    /// Errors and warnings reported for single files, which don't
+    /// necessarily fail the sync
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+
+    /// Paths created, updated or deleted, relative to the destination
+    #[serde(skip)]
+    pub changed_paths: Vec<String>,
+}
+
+impl SyncReport {
+
+    /// Parses the statistics rsync prints with `--stats`, and the file
+    /// errors among its error output.
+    ///
+    /// Numbers may hold thousands separators, as rsync prints them:
+    /// commas, or the dots and spaces of some locales.
+    /// Statistics missing from the output, such as the deleted files
+    /// before rsync 3.1, are left at zero.
+    ///
+    /// # Arguments
+    /// * `stats` - Standard output of rsync, or the part holding the statistics
+    /// * `stderr` - Error output of rsync
+    pub fn parse_rsync(stats: &str, stderr: &str) -> Self {
+        let field = |name: &str| {
+            stats
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(name)?.strip_prefix(':'))
+                .map(str::trim)
+        };
+        let number = |text: &str| -> u64 {
+            text.chars()
+                .filter(|c| !matches!(c, ',' | '.') && !c.is_whitespace())
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .unwrap_or_default()
+        };
+
+        let files_transferred = field("Number of regular files transferred").map(number).unwrap_or_default();
+        // "Number of files: 10 (reg: 8, dir: 2)"
+        let regular_files = field("Number of files")
+            .and_then(|value| value.split_once("reg: "))
+            .map(|(_, regular)| number(regular))
+            .unwrap_or(files_transferred);
+        let errors = stderr
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("rsync") || line.starts_with("file has vanished"))
+            .map(str::to_string)
+            .collect();
+
+        Self {
+            files_transferred: files_transferred as usize,
+            bytes: field("Total transferred file size").map(number).unwrap_or_default(),
+            deleted: field("Number of deleted files").map(number).unwrap_or_default() as usize,
+            skipped: regular_files.saturating_sub(files_transferred) as usize,
+            duration: Duration::ZERO,
+            errors,
+            changed_paths: Vec::new(),
+        }
+    }
+
+    /// Builds the report of a dry run from its plan, which knows no sizes.
+    pub fn from_plan(plan: &SyncPlan) -> Self {
+        let transferred = plan
+            .actions()
+            .iter()
+            .filter(|action| matches!(action, SyncAction::Create(_) | SyncAction::Update(_)))
+            .count();
+        Self {
+            files_transferred: transferred,
+            deleted: plan.deletions().len(),
+            ..Self::default()
+        }
+    }
+
+    /// Builds the report of a strategy that only tells the paths it
+    /// changed, each of which is counted as transferred.
+    pub fn from_changed_paths(changed_paths: Vec<String>) -> Self {
+        Self {
+            files_transferred: changed_paths.len(),
+            changed_paths,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the paths the sync changed (builder pattern).
+    pub fn with_changed_paths(mut self, changed_paths: Vec<String>) -> Self {
+        self.changed_paths = changed_paths;
+        self
+    }
+
+    /// Sets the time the sync took (builder pattern).
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Returns `true` if the sync changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.files_transferred == 0 && self.deleted == 0
+    }
+
+    /// Adds the statistics of another sync, e.g. to another destination.
+    /// Counts, durations, errors and changed paths add up.
+    pub fn merge(&mut self, other: SyncReport) {
+        self.files_transferred += other.files_transferred;
+        self.bytes += other.bytes;
+        self.deleted += other.deleted;
+        self.skipped += other.skipped;
+        self.duration += other.duration;
+        self.errors.extend(other.errors);
+        self.changed_paths.extend(other.changed_paths);
+    }
+}
+
+impl Display for SyncReport {
+
+    /// Formats the report as a one-line summary, e.g.
+    /// `3 files transferred (1.2 MiB), 1 deleted, 40 unchanged in 2.5s`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} files transferred ({}), {} deleted, {} unchanged in {:.1}s",
+            self.files_transferred,
+            ByteSize(self.bytes),
+            self.deleted,
+            self.skipped,
+            self.duration.as_secs_f64()
+        )?;
+        if !self.errors.is_empty() {
+            write!(f, ", {} errors", self.errors.len())?;
+        }
+        Ok(())
+    }
+}
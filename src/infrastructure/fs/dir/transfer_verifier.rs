@@ -0,0 +1,164 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{self, File},
+    io,
+    path::Path
+};
+
+use anyhow::{Context, Error, Result};
+use sha2::{Digest, Sha256};
+
+use super::sync_plan::{SyncAction, SyncPlan};
+
+/// Largest number of mismatches listed by a report's `Display`.
+const VERIFICATION_REPORT_MAX_LISTED: usize = 20;
+
+/// How a transferred file differs from its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchKind {
+
+    /// The file is missing from the destination
+    Missing,
+
+    /// The sizes differ
+    SizeDiffers,
+
+    /// The sizes match but the contents differ
+    ContentDiffers,
+}
+
+impl Display for MismatchKind {
+
+    /// Formats the mismatch as a short description.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let description = match self {
+            MismatchKind::Missing => "missing",
+            MismatchKind::SizeDiffers => "size differs",
+            MismatchKind::ContentDiffers => "checksum differs",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// A transferred file that doesn't match its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+
+    /// Path relative to the destination
+    pub path: String,
+
+    /// How the file differs
+    pub kind: MismatchKind,
+}
+
+/// Outcome of comparing transferred files with their source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+
+    /// Number of files compared, 0 when rsync compared them
+    pub checked: usize,
+
+    /// Files that don't match
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl VerificationReport {
+
+    /// Returns `true` if every compared file matches its source.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Builds a report from an `rsync --checksum` dry run, in which every
+    /// file still to be created or updated is a mismatch.
+    ///
+    /// Deletions and directories aren't transfers, so they are ignored.
+    /// rsync doesn't list matching files, so none are counted as checked.
+    pub fn from_plan(plan: &SyncPlan) -> Self {
+        let mismatches = plan
+            .actions()
+            .iter()
+            .filter_map(|action| match action {
+                SyncAction::Create(path) => Some(Mismatch { path: path.clone(), kind: MismatchKind::Missing }),
+                SyncAction::Update(path) => Some(Mismatch { path: path.clone(), kind: MismatchKind::ContentDiffers }),
+                SyncAction::CreateDir(_) | SyncAction::Delete(_) => None,
+            })
+            .collect();
+        Self { checked: 0, mismatches }
+    }
+}
+
+impl Display for VerificationReport {
+
+    /// Formats the counts followed by the first mismatches, e.g.
+    /// `2 of 10 files don't match: Show/E01.mkv (size differs), ...`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match (self.is_ok(), self.checked) {
+            (true, 0) => return write!(f, "All files verified"),
+            (true, checked) => return write!(f, "{} files verified", checked),
+            (false, 0) => write!(f, "{} files don't match: ", self.mismatches.len())?,
+            (false, checked) => write!(f, "{} of {} files don't match: ", self.mismatches.len(), checked)?,
+        }
+        let listed: Vec<String> = self.mismatches
+            .iter()
+            .take(VERIFICATION_REPORT_MAX_LISTED)
+            .map(|mismatch| format!("{} ({})", mismatch.path, mismatch.kind))
+            .collect();
+        write!(f, "{}", listed.join(", "))?;
+        if self.mismatches.len() > VERIFICATION_REPORT_MAX_LISTED {
+            write!(f, " and {} more", self.mismatches.len() - VERIFICATION_REPORT_MAX_LISTED)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares transferred files with their source by SHA-256.
+pub struct TransferVerifier;
+
+impl TransferVerifier {
+
+    /// Compares local files with their copies.
+    ///
+    /// # Arguments
+    /// * `source` - Directory the paths are relative to in the source
+    /// * `destination` - Directory the paths are relative to in the destination
+    /// * `paths` - Transferred files; directories and symlinks, which
+    ///   rsync copies as links, are skipped
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a source file can't be read.
+    pub fn verify_local(source: &Path, destination: &Path, paths: &[String]) -> Result<VerificationReport, Error> {
+        let mut report = VerificationReport::default();
+        for path in paths {
+            let from = source.join(path);
+            let metadata = fs::symlink_metadata(&from).with_context(|| format!("Failed to read {}", from.display()))?;
+            if !metadata.is_file() {
+                continue;
+            }
+            report.checked += 1;
+
+            let to = destination.join(path);
+            let kind = match fs::metadata(&to) {
+                Err(_) => Some(MismatchKind::Missing),
+                Ok(copied) if copied.len() != metadata.len() => Some(MismatchKind::SizeDiffers),
+                Ok(_) if Self::checksum(&from)? != Self::checksum(&to)? => Some(MismatchKind::ContentDiffers),
+                Ok(_) => None,
+            };
+            if let Some(kind) = kind {
+                report.mismatches.push(Mismatch { path: path.clone(), kind });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the SHA-256 of a file's content.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the file can't be read.
+    pub fn checksum(path: &Path) -> Result<[u8; 32], Error> {
+        let mut file = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(hasher.finalize().into())
+    }
+}
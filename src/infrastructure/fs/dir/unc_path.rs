@@ -0,0 +1,160 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::PathBuf
+};
+
+use anyhow::{anyhow, Error, Result};
+
+/// File listing mounted filesystems on Linux.
+const PROC_MOUNTS_PATH: &str = "/proc/mounts";
+
+/// Filesystem types used for SMB/CIFS mounts.
+const SMB_FS_TYPES: [&str; 3] = ["cifs", "smb3", "smbfs"];
+
+/// An SMB/CIFS network location such as `\\nas\media\movies`.
+///
+/// Both `\\server\share\path` and `//server/share/path` spellings are
+/// accepted. On Windows the UNC path is used as is; elsewhere it's resolved
+/// to the directory where the share is mounted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UncPath {
+
+    /// Name or address of the SMB server
+    server: String,
+
+    /// Name of the share on the server
+    share: String,
+
+    /// Path components inside the share
+    components: Vec<String>,
+}
+
+impl UncPath {
+
+    /// Parses a UNC path, returning `None` if `path` isn't one.
+    pub fn parse(path: &str) -> Option<Self> {
+        let rest = path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//"))?;
+        let mut parts = rest
+            .split(['\\', '/'])
+            .filter(|part| !part.is_empty())
+            .map(str::to_string);
+
+        let server = parts.next()?;
+        let share = parts.next()?;
+        Some(Self {
+            server,
+            share,
+            components: parts.collect(),
+        })
+    }
+
+    /// Returns the server name.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Returns the share name.
+    pub fn share(&self) -> &str {
+        &self.share
+    }
+
+    /// Resolves the location to a path the local filesystem can access.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the share isn't mounted.
+    #[cfg(windows)]
+    pub fn resolve(&self) -> Result<PathBuf, Error> {
+        Ok(PathBuf::from(self.to_string()))
+    }
+
+    /// Resolves the location to a path the local filesystem can access.
+    ///
+    /// Looks for a CIFS mount of the share in `/proc/mounts`, then for a
+    /// share mounted through GVfs (`gio mount smb://server/share`).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the share isn't mounted.
+    #[cfg(not(windows))]
+    pub fn resolve(&self) -> Result<PathBuf, Error> {
+        let mounts = fs::read_to_string(PROC_MOUNTS_PATH).unwrap_or_default();
+        self.find_mount(&mounts)
+            .or_else(|| self.find_gvfs_mount())
+            .ok_or_else(|| anyhow!(
+                "SMB share {} is not mounted, mount it first (e.g. `mount -t cifs //{}/{} <dir> -o credentials=<file>` or `gio mount smb://{}/{}`)",
+                self, self.server, self.share, self.server, self.share
+            ))
+    }
+
+    /// Finds the share in the content of a `/proc/mounts` style listing.
+    pub fn find_mount(&self, mounts: &str) -> Option<PathBuf> {
+        mounts.lines().find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = Self::unescape_mount_field(fields.next()?);
+            let mount_point = Self::unescape_mount_field(fields.next()?);
+            let fs_type = fields.next()?;
+
+            let mounted = Self::parse(&source)?;
+            let matches = SMB_FS_TYPES.contains(&fs_type)
+                && mounted.server.eq_ignore_ascii_case(&self.server)
+                && mounted.share.eq_ignore_ascii_case(&self.share);
+            if !matches {
+                return None;
+            }
+
+            // A mount of a sub-directory of the share only covers paths below it
+            let inner = self.components.strip_prefix(mounted.components.as_slice())?;
+            Some(inner.iter().fold(PathBuf::from(mount_point), |path, part| path.join(part)))
+        })
+    }
+
+    /// Finds the share among the user's GVfs mounts.
+    fn find_gvfs_mount(&self) -> Option<PathBuf> {
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok()?;
+        let root = PathBuf::from(runtime_dir).join("gvfs").join(format!(
+            "smb-share:server={},share={}",
+            self.server.to_lowercase(),
+            self.share.to_lowercase()
+        ));
+        if !root.is_dir() {
+            return None;
+        }
+        Some(self.components.iter().fold(root, |path, part| path.join(part)))
+    }
+
+    /// Decodes the octal escapes (e.g. `\040` for a space) used in `/proc/mounts`.
+    fn unescape_mount_field(field: &str) -> String {
+        let bytes = field.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = bytes.get(i + 1..i + 4)
+                .filter(|_| bytes[i] == b'\\')
+                .and_then(|digits| std::str::from_utf8(digits).ok())
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+            match escaped {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 4;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+}
+
+impl Display for UncPath {
+
+    /// Formats the location as `\\server\share\path`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, r"\\{}\{}", self.server, self.share)?;
+        for component in &self.components {
+            write!(f, r"\{}", component)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{self, OpenOptions},
+    io::ErrorKind,
+    path::Path,
+    process
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use super::{
+    command::shell_quote,
+    location::DirLocation,
+    ssh_runner::SshRunner
+};
+
+/// Name of the file created and removed to probe a directory.
+const WRITE_PROBE_FILE_NAME: &str = ".pilipili_strm-write-probe";
+
+/// Why a destination can't be written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnlyReason {
+
+    /// The filesystem is mounted read-only
+    ReadOnlyFilesystem,
+
+    /// The process lacks write permission
+    PermissionDenied,
+}
+
+/// A destination that can't be written to.
+///
+/// Returned before any transfer starts, so callers can tell a destination
+/// that will keep failing apart from a transient error by downcasting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOnlyDestination {
+
+    /// Destination path as configured
+    pub path: String,
+
+    /// Why writing failed
+    pub reason: ReadOnlyReason,
+}
+
+impl Display for ReadOnlyDestination {
+
+    /// Formats the error with the destination and the reason.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.reason {
+            ReadOnlyReason::ReadOnlyFilesystem => {
+                write!(f, "Destination '{}' is on a read-only file system", self.path)
+            }
+            ReadOnlyReason::PermissionDenied => {
+                write!(f, "Destination '{}' is not writable: permission denied", self.path)
+            }
+        }
+    }
+}
+
+impl StdError for ReadOnlyDestination {}
+
+impl ReadOnlyDestination {
+
+    /// Returns `true` if the error, or any error in its context chain,
+    /// is a [`ReadOnlyDestination`].
+    pub fn is_read_only(error: &Error) -> bool {
+        error.chain().any(|cause| cause.downcast_ref::<ReadOnlyDestination>().is_some())
+    }
+
+    /// Checks that files can be created in a destination.
+    ///
+    /// A probe file is created and removed in the destination, or in its
+    /// closest existing parent when the destination doesn't exist yet.
+    /// Remote destinations are probed over SSH; rsync daemon modules
+    /// aren't checked.
+    ///
+    /// # Errors
+    /// Returns a [`ReadOnlyDestination`] if the destination is read-only
+    /// or not writable, or `anyhow::Error` if the probe itself failed.
+    pub fn check(location: &DirLocation) -> Result<(), Error> {
+        let path = location.get_path();
+        if path.starts_with("rsync://") {
+            return Ok(());
+        }
+
+        let reason = match location.ssh_config() {
+            Some(ssh_config) => {
+                let remote_path = path.split_once(':').map_or(path.as_str(), |(_, path)| path);
+                Self::probe_remote(&SshRunner::new(ssh_config.clone()), remote_path)?
+            }
+            None => Self::probe_local(Path::new(&path))?,
+        };
+        match reason {
+            Some(reason) => Err(ReadOnlyDestination { path, reason }.into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Creates and removes a probe file in the closest existing directory.
+    fn probe_local(path: &Path) -> Result<Option<ReadOnlyReason>, Error> {
+        let Some(dir) = path.ancestors().find(|dir| dir.is_dir()) else {
+            return Ok(None);
+        };
+
+        let probe = dir.join(format!("{}-{}", WRITE_PROBE_FILE_NAME, process::id()));
+        let created = OpenOptions::new().write(true).create_new(true).open(&probe);
+        match created {
+            Ok(_) => {
+                let _ = fs::remove_file(&probe);
+                Ok(None)
+            }
+            Err(e) => match e.kind() {
+                ErrorKind::AlreadyExists => Ok(None),
+                ErrorKind::ReadOnlyFilesystem => Ok(Some(ReadOnlyReason::ReadOnlyFilesystem)),
+                ErrorKind::PermissionDenied => Ok(Some(ReadOnlyReason::PermissionDenied)),
+                _ => Err(e).with_context(|| format!("Failed to probe {} for write access", dir.display())),
+            },
+        }
+    }
+
+    /// Creates and removes a probe file on the remote host.
+    fn probe_remote(runner: &SshRunner, path: &str) -> Result<Option<ReadOnlyReason>, Error> {
+        let script = format!(
+            "d={}; while [ ! -d \"$d\" ]; do d=$(dirname \"$d\"); done; f=\"$d/{}-$$\"; touch \"$f\" && rm -f \"$f\"",
+            shell_quote(path),
+            WRITE_PROBE_FILE_NAME
+        );
+        let output = runner.run(&script)?;
+        if output.success() {
+            return Ok(None);
+        }
+        Self::classify(&output.stderr)
+            .map(Some)
+            .ok_or_else(|| anyhow!("Probing '{}' for write access failed: {}", path, output.stderr.trim()))
+    }
+
+    /// Recognizes the reason in the error output of a remote command.
+    fn classify(message: &str) -> Option<ReadOnlyReason> {
+        let message = message.to_lowercase();
+        if message.contains("read-only file system") {
+            Some(ReadOnlyReason::ReadOnlyFilesystem)
+        } else if message.contains("permission denied") {
+            Some(ReadOnlyReason::PermissionDenied)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr
+};
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Units accepted by [`ByteSize::parse`], longest first, with their multiplier.
+///
+/// `KB`, `MB`, ... are decimal and `KiB`, `MiB`, ... binary; the one-letter
+/// forms follow rsync and are binary too.
+const BYTE_SIZE_UNITS: [(&str, u64); 16] = [
+    ("kib", 1 << 10), ("mib", 1 << 20), ("gib", 1 << 30), ("tib", 1 << 40),
+    ("kb", 1_000), ("mb", 1_000_000), ("gb", 1_000_000_000), ("tb", 1_000_000_000_000),
+    ("k", 1 << 10), ("m", 1 << 20), ("g", 1 << 30), ("t", 1 << 40),
+    ("bytes", 1), ("byte", 1), ("b", 1), ("", 1),
+];
+
+/// A size in bytes, written in configuration files as a number of bytes
+/// or a human-readable string such as `"200MB"` or `"1.5 GiB"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+
+    /// Parses a size such as `"200MB"`, `"512 KiB"`, `"1.5G"` or `"1024"`.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the number or unit is invalid.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| anyhow!("Invalid size '{}', expected e.g. \"200MB\"", text))?;
+        let unit = unit.trim().to_ascii_lowercase();
+        let multiplier = BYTE_SIZE_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| anyhow!("Unknown unit '{}' in size '{}'", unit, text))?;
+        Ok(Self((number * multiplier as f64).round() as u64))
+    }
+
+    /// Returns the size in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+impl Display for ByteSize {
+
+    /// Formats the size with the largest binary unit it reaches, e.g. `200.0 MiB`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        let mut value = self.0 as f64;
+        let mut unit = None;
+        for name in UNITS {
+            if value < 1024.0 {
+                break;
+            }
+            value /= 1024.0;
+            unit = Some(name);
+        }
+        match unit {
+            Some(unit) => write!(f, "{:.1} {}", value, unit),
+            None => write!(f, "{} B", self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+
+    /// Reads a number of bytes or a human-readable size string.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bytes(bytes) => Ok(Self(bytes)),
+            Raw::Text(text) => Self::parse(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
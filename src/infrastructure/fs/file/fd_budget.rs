@@ -0,0 +1,155 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult
+};
+
+use anyhow::{anyhow, Error, Result};
+
+/// Descriptors kept free for logs, sockets, the watcher and notifications.
+const FD_RESERVED: u64 = 64;
+
+/// Descriptors a directory scan worker may hold at once.
+pub(crate) const FD_PER_SCAN_WORKER: u64 = 4;
+
+/// Descriptors a transfer may hold at once: child process pipes, SSH
+/// connections, HTTP sockets and the files being sent.
+pub(crate) const FD_PER_TRANSFER: u64 = 16;
+
+/// Highest soft limit requested when the hard limit is unlimited.
+const FD_RAISE_MAX: u64 = 1 << 20;
+
+/// Share of the limit in use from which descriptors are near exhaustion.
+const FD_NEAR_EXHAUSTION_PERCENT: u64 = 80;
+
+/// Limits on the number of open file descriptors of the process (`ulimit -n`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdLimit {
+
+    /// Limit enforced by the kernel, which the process may raise up to `hard`
+    pub soft: u64,
+
+    /// Ceiling the soft limit can be raised to without privileges
+    pub hard: u64,
+}
+
+impl FdLimit {
+
+    /// Returns the current limits, `None` where they can't be queried.
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)] // rlim_t isn't 64 bits on every platform
+    pub fn current() -> Option<Self> {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: getrlimit writes into the provided struct only
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+            return None;
+        }
+        Some(Self {
+            soft: limit.rlim_cur as u64,
+            hard: limit.rlim_max as u64,
+        })
+    }
+
+    /// Returns the current limits, `None` where they can't be queried.
+    #[cfg(not(unix))]
+    pub fn current() -> Option<Self> {
+        None
+    }
+
+    /// Raises the soft limit as far as the hard limit allows.
+    ///
+    /// Some systems refuse soft limits above a kernel maximum even when the
+    /// hard limit is unlimited, in which case the limit is left unchanged.
+    ///
+    /// # Returns
+    /// The limits in effect afterwards.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the limits can't be queried or set.
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)] // rlim_t isn't 64 bits on every platform
+    pub fn raise() -> Result<Self, Error> {
+        let current = Self::current().ok_or_else(|| anyhow!("Failed to query the open file limit"))?;
+        let target = current.hard.min(FD_RAISE_MAX);
+        if current.soft >= target {
+            return Ok(current);
+        }
+
+        let limit = libc::rlimit {
+            rlim_cur: target as libc::rlim_t,
+            rlim_max: current.hard as libc::rlim_t,
+        };
+        // SAFETY: setrlimit only reads the provided struct
+        if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) } != 0 {
+            return Err(anyhow!(
+                "Failed to raise the open file limit from {} to {}: {}",
+                current.soft,
+                target,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(Self { soft: target, ..current })
+    }
+
+    /// Raises the soft limit as far as the hard limit allows.
+    ///
+    /// # Errors
+    /// Always returns `anyhow::Error`, since limits can't be queried here.
+    #[cfg(not(unix))]
+    pub fn raise() -> Result<Self, Error> {
+        Err(anyhow!("Open file limits are not supported on this platform"))
+    }
+}
+
+/// Number of descriptors the process has open against its soft limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdUsage {
+
+    /// Descriptors currently open
+    pub open: u64,
+
+    /// Soft limit on open descriptors
+    pub limit: u64,
+}
+
+impl Display for FdUsage {
+
+    /// Formats the usage as "N of M file descriptors open (P%)".
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} of {} file descriptors open ({}%)", self.open, self.limit, self.percent())
+    }
+}
+
+impl FdUsage {
+
+    /// Measures the usage of the process.
+    ///
+    /// Open descriptors are counted from `/proc/self/fd` or `/dev/fd`, so
+    /// this returns `None` on systems without either.
+    pub fn current() -> Option<Self> {
+        let limit = FdLimit::current()?.soft;
+        let open = ["/proc/self/fd", "/dev/fd"]
+            .iter()
+            .find_map(|dir| std::fs::read_dir(dir).ok())?
+            // The listing holds a descriptor of its own
+            .count()
+            .saturating_sub(1) as u64;
+        Some(Self { open, limit })
+    }
+
+    /// Returns the share of the limit in use, in percent.
+    pub fn percent(&self) -> u64 {
+        (self.open * 100).checked_div(self.limit).unwrap_or(100)
+    }
+
+    /// Returns `true` once 80% of the limit is in use.
+    pub fn is_near_exhaustion(&self) -> bool {
+        self.percent() >= FD_NEAR_EXHAUSTION_PERCENT
+    }
+
+    /// Returns the descriptors that can still be opened, keeping a reserve
+    /// for the rest of the process.
+    pub fn available(&self) -> u64 {
+        self.limit.saturating_sub(self.open).saturating_sub(FD_RESERVED)
+    }
+}
@@ -58,7 +58,7 @@ impl FileHelper {
         // Create file and write original path
         match File::create(&new_file_path) {
             Ok(mut file) => {
-                if let Err(_) = writeln!(file, "{}", absolute_path.display()) {
+                if writeln!(file, "{}", absolute_path.display()).is_err() {
                     return None;
                 }
                 Some(new_file_path)
@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf}
+};
+
+use anyhow::{anyhow, Context, Error, Result};
+use image::{
+    codecs::jpeg::JpegEncoder,
+    imageops::FilterType,
+    DynamicImage,
+    ImageFormat,
+    ImageReader
+};
+use sha2::{Digest, Sha256};
+
+use crate::debug_log;
+
+/// Domain identifier for image processing logs
+const IMAGE_LOGGER_DOMAIN: &str = "[IMAGE]";
+
+/// JPEG qualities tried in order until the image fits the size limit.
+const IMAGE_JPEG_QUALITIES: [u8; 4] = [90, 80, 70, 60];
+
+/// Factor applied to both dimensions when no quality fits the size limit.
+const IMAGE_DOWNSCALE_FACTOR: f64 = 0.75;
+
+/// Constraints an image must satisfy to be accepted by a service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+
+    /// Largest file size in bytes
+    pub max_bytes: u64,
+
+    /// Largest sum of width and height in pixels
+    pub max_dimension_sum: u32,
+
+    /// Formats accepted as they are; anything else is converted to JPEG
+    pub formats: &'static [ImageFormat],
+}
+
+impl ImageLimits {
+
+    /// Returns `true` if an image of this format, size and dimensions
+    /// can be sent unchanged.
+    pub fn allows(&self, format: Option<ImageFormat>, bytes: u64, (width, height): (u32, u32)) -> bool {
+        format.is_some_and(|format| self.formats.contains(&format))
+            && bytes <= self.max_bytes
+            && width.saturating_add(height) <= self.max_dimension_sum
+    }
+}
+
+/// Provides utility methods for preparing images
+pub struct ImageHelper;
+
+impl ImageHelper {
+
+    /// Returns an image satisfying the limits, converting it if needed.
+    ///
+    /// Images that already fit are returned unchanged. Others are scaled
+    /// down to the dimension limit and re-encoded as JPEG, lowering the
+    /// quality and then the dimensions until the file fits the size
+    /// limit. Converted images are written to `output_dir`, named after
+    /// the original's path so repeated sends reuse the same file.
+    ///
+    /// # Arguments
+    /// * `path` - The image to prepare
+    /// * `limits` - The constraints to satisfy
+    /// * `output_dir` - Directory converted images are written to
+    ///
+    /// # Returns
+    /// The original path, or the path of the converted JPEG.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the image can't be read or decoded, or
+    /// the converted image can't be written.
+    pub fn fit(path: &Path, limits: &ImageLimits, output_dir: &Path) -> Result<PathBuf, Error> {
+        let bytes = fs::metadata(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        let reader = ImageReader::open(path)?.with_guessed_format()?;
+        let format = reader.format();
+        let dimensions = image::image_dimensions(path)
+            .with_context(|| format!("Failed to read the dimensions of {}", path.display()))?;
+        if limits.allows(format, bytes, dimensions) {
+            return Ok(path.to_path_buf());
+        }
+
+        let image = reader
+            .decode()
+            .with_context(|| format!("Failed to decode {}", path.display()))?;
+        let encoded = Self::encode_within(image, limits)?;
+
+        let hash = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+        let output = output_dir.join(format!("{}.jpg", &hash[..16]));
+        fs::create_dir_all(output_dir)
+            .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+        fs::write(&output, &encoded)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+
+        debug_log!(
+            IMAGE_LOGGER_DOMAIN,
+            format!(
+                "Converted {} ({} bytes, {}x{}) to {} ({} bytes)",
+                path.display(), bytes, dimensions.0, dimensions.1, output.display(), encoded.len()
+            )
+        );
+        Ok(output)
+    }
+
+    /// Encodes an image as JPEG within the dimension and size limits.
+    fn encode_within(image: DynamicImage, limits: &ImageLimits) -> Result<Vec<u8>, Error> {
+        let mut image = Self::scale_to_sum(image, limits.max_dimension_sum);
+        loop {
+            let rgb = image.to_rgb8();
+            for quality in IMAGE_JPEG_QUALITIES {
+                let mut encoded = Cursor::new(Vec::new());
+                JpegEncoder::new_with_quality(&mut encoded, quality).encode_image(&rgb)?;
+                let encoded = encoded.into_inner();
+                if encoded.len() as u64 <= limits.max_bytes {
+                    return Ok(encoded);
+                }
+            }
+
+            if image.width() <= 1 && image.height() <= 1 {
+                return Err(anyhow!("Image can't be compressed below {} bytes", limits.max_bytes));
+            }
+            let width = ((image.width() as f64 * IMAGE_DOWNSCALE_FACTOR) as u32).max(1);
+            let height = ((image.height() as f64 * IMAGE_DOWNSCALE_FACTOR) as u32).max(1);
+            image = image.resize_exact(width, height, FilterType::Triangle);
+        }
+    }
+
+    /// Scales an image down so its width and height add up to at most `max_sum`.
+    fn scale_to_sum(image: DynamicImage, max_sum: u32) -> DynamicImage {
+        let sum = image.width() as u64 + image.height() as u64;
+        if sum <= max_sum as u64 {
+            return image;
+        }
+
+        let scale = max_sum as f64 / sum as f64;
+        let width = ((image.width() as f64 * scale) as u32).max(1);
+        let height = ((image.height() as f64 * scale) as u32).max(1);
+        image.resize_exact(width, height, FilterType::Lanczos3)
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path
+};
+
+use super::{
+    media_kind::{MediaKind, MediaKindRules},
+    media_size::{AUDIO_SUFFIXES, VIDEO_SUFFIXES}
+};
+
+/// Bytes read from the start of a file to sniff its signature and to
+/// decide whether it's plain text.
+const SIGNATURE_SNIFF_LEN: usize = 512;
+
+/// Offset of the ISO 9660 signature, read only when the first bytes match
+/// no other signature.
+const ISO_SIGNATURE_OFFSET: u64 = 0x8001;
+
+/// Signature of ISO 9660 images.
+const ISO_SIGNATURE: &[u8] = b"CD001";
+
+/// Byte order marks of UTF-16 text, little and big endian.
+const UTF16_BOMS: [[u8; 2]; 2] = [[0xFF, 0xFE], [0xFE, 0xFF]];
+
+/// Type of media a file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+
+    /// Video, possibly with audio tracks
+    Video,
+
+    /// Audio only
+    Audio,
+}
+
+impl Display for MediaType {
+
+    /// Formats the type as a lowercase word.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MediaType::Video => write!(f, "video"),
+            MediaType::Audio => write!(f, "audio"),
+        }
+    }
+}
+
+/// Recognizes media files by suffix and, optionally, by content.
+///
+/// By default only the suffix is looked at. With signature sniffing, the
+/// first bytes of the file are compared with the signatures of common
+/// containers (Matroska, MP4, AVI, MPEG-TS, FLAC, MP3, ...), so media with
+/// a wrong or missing suffix is still recognized, and a text file renamed
+/// to `.mkv` is rejected. A file whose content matches no signature keeps
+/// the type of its suffix unless it is plain text.
+#[derive(Debug, Clone)]
+pub struct MediaDetector {
+
+    /// Suffixes of video files, without leading dots
+    video_suffixes: Vec<String>,
+
+    /// Suffixes of audio files, without leading dots
+    audio_suffixes: Vec<String>,
+
+    /// When true, the content of files is read to confirm their type
+    sniff_signatures: bool,
+
+    /// Rules telling trailers, samples and extras from features
+    kind_rules: MediaKindRules,
+}
+
+impl Default for MediaDetector {
+
+    /// Creates a detector for the default video and audio suffixes, without sniffing.
+    fn default() -> Self {
+        Self {
+            video_suffixes: VIDEO_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect(),
+            audio_suffixes: AUDIO_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect(),
+            sniff_signatures: false,
+            kind_rules: MediaKindRules::default(),
+        }
+    }
+}
+
+impl MediaDetector {
+
+    /// Creates a detector for the default video and audio suffixes, without sniffing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the suffixes of video files, trimming leading dots (builder pattern).
+    ///
+    /// With no video suffix, video signatures aren't recognized either.
+    pub fn with_video_suffixes(mut self, suffixes: Vec<&str>) -> Self {
+        self.video_suffixes = Self::trim_suffixes(suffixes);
+        self
+    }
+
+    /// Sets the suffixes of audio files, trimming leading dots (builder pattern).
+    ///
+    /// With no audio suffix, audio signatures aren't recognized either.
+    pub fn with_audio_suffixes(mut self, suffixes: Vec<&str>) -> Self {
+        self.audio_suffixes = Self::trim_suffixes(suffixes);
+        self
+    }
+
+    /// Enables or disables reading files to confirm their type (builder pattern).
+    pub fn with_signature_sniffing(mut self, sniff_signatures: bool) -> Self {
+        self.sniff_signatures = sniff_signatures;
+        self
+    }
+
+    /// Sets the rules classifying media files and subtitles (builder pattern).
+    pub fn with_kind_rules(mut self, kind_rules: MediaKindRules) -> Self {
+        self.kind_rules = kind_rules;
+        self
+    }
+
+    /// Returns the rules classifying media files and subtitles.
+    pub fn kind_rules(&self) -> &MediaKindRules {
+        &self.kind_rules
+    }
+
+    /// Returns `true` if files are read to confirm their type.
+    pub fn sniffs_signatures(&self) -> bool {
+        self.sniff_signatures
+    }
+
+    /// Returns the media type given by a path's suffix, ignoring case.
+    pub fn type_by_suffix(&self, path: &Path) -> Option<MediaType> {
+        let extension = path.extension()?;
+        let matches = |suffixes: &[String]| suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix));
+        if matches(&self.video_suffixes) {
+            Some(MediaType::Video)
+        } else if matches(&self.audio_suffixes) {
+            Some(MediaType::Audio)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the media type of a file.
+    ///
+    /// Without sniffing, only the suffix is looked at and the file isn't
+    /// read. With sniffing, a file that can't be read isn't media.
+    pub fn detect(&self, path: &Path) -> Option<MediaType> {
+        if !self.sniff_signatures {
+            return self.type_by_suffix(path);
+        }
+
+        let mut file = File::open(path).ok()?;
+        let header = Self::read_header(&mut file).ok()?;
+        let sniffed = match Self::sniff(&header) {
+            None if Self::is_text(&header) => return None,
+            None if Self::is_disc_image(&mut file) => Some(MediaType::Video),
+            sniffed => sniffed,
+        };
+        match sniffed {
+            Some(media_type) if self.is_enabled(media_type) => Some(media_type),
+            Some(_) => None,
+            None => self.type_by_suffix(path),
+        }
+    }
+
+    /// Returns `true` if a file is a video or audio file, see [`detect`](Self::detect).
+    pub fn is_media_file(&self, path: &Path) -> bool {
+        self.detect(path).is_some()
+    }
+
+    /// Returns the role of a file in its release.
+    ///
+    /// Subtitles are recognized by suffix. Media files, as found by
+    /// [`detect`](Self::detect), are classified by the kind rules; other
+    /// files are [`MediaKind::Other`]. The rules see the whole path, so
+    /// folders above the library, such as `/mnt/extras`, can match too;
+    /// use [`MediaKindRules::kind_of_media`] with a relative path then.
+    pub fn classify(&self, path: &Path) -> MediaKind {
+        if self.kind_rules.is_subtitle(path) {
+            MediaKind::Subtitle
+        } else if self.is_media_file(path) {
+            self.kind_rules.kind_of_media(path)
+        } else {
+            MediaKind::Other
+        }
+    }
+
+    /// Returns the media type whose container signature starts `header`.
+    ///
+    /// UTF-16 text is never media, although its byte order mark looks
+    /// like an MPEG audio frame sync.
+    pub fn sniff(header: &[u8]) -> Option<MediaType> {
+        let at = |offset: usize, signature: &[u8]| {
+            header.get(offset..offset + signature.len()) == Some(signature)
+        };
+
+        if UTF16_BOMS.iter().any(|bom| at(0, bom)) {
+            return None;
+        }
+        if at(0, &[0x1A, 0x45, 0xDF, 0xA3])
+            || at(0, &[0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11])
+            || at(0, b"FLV")
+            || at(0, b".RMF")
+            || at(0, &[0x00, 0x00, 0x01, 0xBA])
+            || at(0, &[0x00, 0x00, 0x01, 0xB3])
+            || (at(0, b"RIFF") && at(8, b"AVI "))
+            || (at(0, &[0x47]) && at(188, &[0x47]) && at(376, &[0x47]))
+            || (at(4, &[0x47]) && at(196, &[0x47]) && at(388, &[0x47]))
+            || at(ISO_SIGNATURE_OFFSET as usize, ISO_SIGNATURE) {
+            return Some(MediaType::Video);
+        }
+        if at(4, b"ftyp") {
+            let audio_brand = at(8, b"M4A ") || at(8, b"M4B ");
+            return Some(if audio_brand { MediaType::Audio } else { MediaType::Video });
+        }
+        if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") {
+            return Some(MediaType::Video);
+        }
+        if at(0, b"ID3")
+            || at(0, b"fLaC")
+            || at(0, b"OggS")
+            || at(0, b"MAC ")
+            || at(0, b"DSD ")
+            || (at(0, b"RIFF") && at(8, b"WAVE"))
+            // MPEG audio and ADTS frame sync
+            || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0) {
+            return Some(MediaType::Audio);
+        }
+        None
+    }
+
+    /// Returns `true` if a file's first bytes are plain text, UTF-16 text
+    /// with a byte order mark, or empty.
+    fn is_text(header: &[u8]) -> bool {
+        if UTF16_BOMS.iter().any(|bom| header.starts_with(bom)) {
+            return true;
+        }
+        let sample = &header[..header.len().min(SIGNATURE_SNIFF_LEN)];
+        let text = match std::str::from_utf8(sample) {
+            Ok(text) => text,
+            // The sample may end in the middle of a character
+            Err(e) if e.error_len().is_none() => std::str::from_utf8(&sample[..e.valid_up_to()]).unwrap_or_default(),
+            Err(_) => return false,
+        };
+        text.chars().all(|c| !c.is_control() || c.is_ascii_whitespace())
+    }
+
+    /// Returns `true` if signatures of a media type are recognized.
+    fn is_enabled(&self, media_type: MediaType) -> bool {
+        match media_type {
+            MediaType::Video => !self.video_suffixes.is_empty(),
+            MediaType::Audio => !self.audio_suffixes.is_empty(),
+        }
+    }
+
+    /// Reads the first bytes of a file.
+    fn read_header(file: &mut File) -> io::Result<Vec<u8>> {
+        let mut header = Vec::with_capacity(SIGNATURE_SNIFF_LEN);
+        file.take(SIGNATURE_SNIFF_LEN as u64).read_to_end(&mut header)?;
+        Ok(header)
+    }
+
+    /// Returns `true` if a file carries the ISO 9660 signature, reading
+    /// only its bytes.
+    fn is_disc_image(file: &mut File) -> bool {
+        let mut signature = [0u8; ISO_SIGNATURE.len()];
+        file.seek(SeekFrom::Start(ISO_SIGNATURE_OFFSET)).is_ok()
+            && file.read_exact(&mut signature).is_ok()
+            && signature == ISO_SIGNATURE
+    }
+
+    /// Trims leading dots from suffixes.
+    fn trim_suffixes(suffixes: Vec<&str>) -> Vec<String> {
+        suffixes
+            .into_iter()
+            .map(|suffix| suffix.trim_start_matches('.').to_string())
+            .collect()
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::Path
+};
+
+use anyhow::{Error, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Suffixes of subtitle files.
+pub const SUBTITLE_SUFFIXES: [&str; 7] = ["srt", "ass", "ssa", "sub", "idx", "vtt", "sup"];
+
+/// Keywords of sample clips shipped with releases.
+const SAMPLE_KEYWORDS: [&str; 2] = ["sample", "samples"];
+
+/// Keywords of trailers and teasers.
+const TRAILER_KEYWORDS: [&str; 4] = ["trailer", "trailers", "teaser", "teasers"];
+
+/// Keywords of bonus material, following the extras folders and `-suffix`
+/// file names of Emby, Jellyfin and Plex. Words like `interview` and
+/// `scene` end many titles, so file names only match with a dash.
+const EXTRA_KEYWORDS: [&str; 13] = [
+    "extra", "extras", "featurette", "featurettes", "behindthescenes", "behind the scenes",
+    "deleted", "deleted scenes", "interview", "interviews", "scene", "scenes", "bonus",
+];
+
+/// Role of a file in a release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+
+    /// The movie or episode itself
+    Feature,
+
+    /// A trailer or teaser
+    Trailer,
+
+    /// A short sample clip of the release
+    Sample,
+
+    /// Bonus material such as featurettes, interviews or deleted scenes
+    Extra,
+
+    /// A subtitle file
+    Subtitle,
+
+    /// Anything else, such as `.nfo` files and artwork
+    Other,
+}
+
+impl Display for MediaKind {
+
+    /// Formats the kind as a lowercase word.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MediaKind::Feature => write!(f, "feature"),
+            MediaKind::Trailer => write!(f, "trailer"),
+            MediaKind::Sample => write!(f, "sample"),
+            MediaKind::Extra => write!(f, "extra"),
+            MediaKind::Subtitle => write!(f, "subtitle"),
+            MediaKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Rule classifying media files, as written in the configuration.
+///
+/// Either `keywords` or `pattern` is given; keywords match like the
+/// default trailer and sample keywords, see [`MediaKindRules`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MediaKindRule {
+
+    /// Kind of the media files the rule matches
+    pub kind: MediaKind,
+
+    /// Folder names and file name endings matched, ignoring case
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Regex tested against the path relative to the source, with `/` separators
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// Rules telling trailers, samples and extras from features.
+///
+/// Each rule is a regex tested against the path of a media file, with
+/// `/` separators. Keyword rules match a folder named after a keyword,
+/// such as `Trailers/`, or a file name ending with one, such as
+/// `Movie-trailer.mkv` or `movie.sample.mkv`, ignoring case; a keyword
+/// in the middle of a title, as in `Trailer Park Boys`, doesn't match.
+/// Extras keywords only match file names ending with `-keyword`, so
+/// `The Interview (2014).mkv` stays a feature. The first matching rule
+/// wins, so samples are checked before trailers and trailers before
+/// extras by default.
+#[derive(Debug, Clone)]
+pub struct MediaKindRules {
+
+    /// Rules in the order they are tried
+    rules: Vec<(MediaKind, Regex)>,
+
+    /// Suffixes of subtitle files, without leading dots
+    subtitle_suffixes: Vec<String>,
+}
+
+impl Default for MediaKindRules {
+
+    /// Creates the default keyword rules and subtitle suffixes.
+    fn default() -> Self {
+        Self::empty()
+            .with_keywords(MediaKind::Sample, SAMPLE_KEYWORDS.to_vec())
+            .with_keywords(MediaKind::Trailer, TRAILER_KEYWORDS.to_vec())
+            .with_dash_keywords(MediaKind::Extra, EXTRA_KEYWORDS.to_vec())
+    }
+}
+
+impl MediaKindRules {
+
+    /// Creates the default keyword rules and subtitle suffixes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates rules classifying every media file as a feature, with the
+    /// default subtitle suffixes.
+    pub fn empty() -> Self {
+        Self {
+            rules: Vec::new(),
+            subtitle_suffixes: SUBTITLE_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect(),
+        }
+    }
+
+    /// Creates rules trying configured rules first, then the default
+    /// keyword rules.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a rule's pattern is invalid.
+    pub fn with_configured(configured: &[MediaKindRule]) -> Result<Self, Error> {
+        let mut rules = Self::empty();
+        for rule in configured {
+            rules = rules.with_keywords(rule.kind, rule.keywords.iter().map(String::as_str).collect());
+            if let Some(pattern) = &rule.pattern {
+                rules = rules.with_pattern(rule.kind, pattern)?;
+            }
+        }
+        let defaults = Self::default();
+        rules.rules.extend(defaults.rules);
+        Ok(rules)
+    }
+
+    /// Adds a rule matching folders named after a keyword and file names
+    /// ending with one after a space, dot or dash, ignoring case (builder pattern).
+    pub fn with_keywords(self, kind: MediaKind, keywords: Vec<&str>) -> Self {
+        self.with_keyword_rule(kind, keywords, "[ ._/-]")
+    }
+
+    /// Adds a rule matching folders named after a keyword and file names
+    /// ending with `-keyword`, ignoring case (builder pattern).
+    pub fn with_dash_keywords(self, kind: MediaKind, keywords: Vec<&str>) -> Self {
+        self.with_keyword_rule(kind, keywords, "-")
+    }
+
+    /// Adds a rule matching whole folder names, and file names ending with
+    /// a keyword after one of `separators`.
+    fn with_keyword_rule(mut self, kind: MediaKind, keywords: Vec<&str>, separators: &str) -> Self {
+        if keywords.is_empty() {
+            return self;
+        }
+        let keywords: Vec<String> = keywords.into_iter().map(regex::escape).collect();
+        let pattern = format!(
+            r"(?i)(?:^|/)(?:{0})/|(?:^|/|{1})(?:{0})(?:\.[^/.]*)?$",
+            keywords.join("|"),
+            separators
+        );
+        self.rules.push((kind, Regex::new(&pattern).expect("escaped keywords form a valid pattern")));
+        self
+    }
+
+    /// Adds a rule matching paths with a regex (builder pattern).
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the regex pattern is invalid.
+    pub fn with_pattern(mut self, kind: MediaKind, pattern: &str) -> Result<Self, Error> {
+        self.rules.push((kind, Regex::new(pattern)?));
+        Ok(self)
+    }
+
+    /// Sets the suffixes of subtitle files, trimming leading dots (builder pattern).
+    pub fn with_subtitle_suffixes(mut self, suffixes: Vec<&str>) -> Self {
+        self.subtitle_suffixes = suffixes
+            .into_iter()
+            .map(|suffix| suffix.trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    /// Returns `true` if a path has one of the subtitle suffixes, ignoring case.
+    pub fn is_subtitle(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|extension| {
+            self.subtitle_suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix))
+        })
+    }
+
+    /// Returns the kind given by the first rule matching a media file,
+    /// or [`MediaKind::Feature`] if none does.
+    pub fn kind_of_media(&self, path: &Path) -> MediaKind {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .find(|(_, rule)| rule.is_match(&path))
+            .map_or(MediaKind::Feature, |(kind, _)| *kind)
+    }
+}
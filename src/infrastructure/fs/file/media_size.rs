@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::byte_size::ByteSize;
+
+/// Suffixes of the video files a minimum size applies to.
+pub const VIDEO_SUFFIXES: [&str; 14] = [
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "flv", "webm",
+    "ts", "m2ts", "mpg", "mpeg", "rmvb", "iso",
+];
+
+/// Suffixes of the audio files a minimum size applies to.
+pub const AUDIO_SUFFIXES: [&str; 10] = [
+    "mp3", "flac", "m4a", "aac", "ogg", "opus", "wav", "wma", "ape", "dsf",
+];
+
+/// Minimum sizes below which media files are skipped.
+///
+/// Releases often ship sample clips and trailers next to the feature, and
+/// incomplete downloads leave zero-byte placeholders; neither should reach
+/// the media server. Files of other kinds, such as subtitles and `.nfo`
+/// files, are never skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaSizeLimits {
+
+    /// Minimum size of video files, `None` to keep them all
+    pub min_video_size: Option<ByteSize>,
+
+    /// Minimum size of audio files, `None` to keep them all
+    pub min_audio_size: Option<ByteSize>,
+}
+
+impl MediaSizeLimits {
+
+    /// Returns `true` if no minimum is set.
+    pub fn is_empty(&self) -> bool {
+        self.min_video_size.is_none() && self.min_audio_size.is_none()
+    }
+
+    /// Returns `true` if a file is a video or audio file smaller than the
+    /// minimum size of its kind.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file, whose suffix gives its kind
+    /// * `size` - Size of the file in bytes
+    pub fn is_undersized(&self, path: &Path, size: u64) -> bool {
+        let Some(extension) = path.extension() else {
+            return false;
+        };
+        let is_kind = |suffixes: &[&str]| suffixes.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix));
+        let minimum = if is_kind(&VIDEO_SUFFIXES) {
+            self.min_video_size
+        } else if is_kind(&AUDIO_SUFFIXES) {
+            self.min_audio_size
+        } else {
+            None
+        };
+        minimum.is_some_and(|minimum| size < minimum.bytes())
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+
+/// Suffix of Kodi-style metadata files.
+const NFO_SUFFIX: &str = "nfo";
+
+/// Suffixes of artwork images.
+const ARTWORK_SUFFIXES: [&str; 5] = ["jpg", "jpeg", "png", "webp", "tbn"];
+
+/// Names of artwork images, alone (`poster.jpg`) or after the media
+/// file's stem (`Movie-poster.jpg`).
+const ARTWORK_NAMES: [&str; 13] = [
+    "poster", "fanart", "folder", "banner", "landscape", "logo", "clearlogo",
+    "clearart", "disc", "discart", "thumb", "backdrop", "cover",
+];
+
+/// Prefix of season artwork, such as `season01-poster.jpg` or `season-specials.png`.
+const SEASON_ARTWORK_PREFIX: &str = "season";
+
+/// Recognizes the metadata files of Kodi-style libraries: `.nfo` files
+/// and artwork such as `poster.jpg`, `fanart.jpg`, `Movie-thumb.jpg` and
+/// `season01-poster.jpg`.
+pub struct MetadataFiles;
+
+impl MetadataFiles {
+
+    /// Returns `true` if a file is an `.nfo` file or artwork, ignoring case.
+    pub fn is_metadata(path: &Path) -> bool {
+        Self::matches(path, true)
+    }
+
+    /// Returns `true` if a file matches one of the
+    /// [`MetadataFiles::rsync_patterns`], which are case-sensitive.
+    pub fn matches_rsync_patterns(path: &Path) -> bool {
+        Self::matches(path, false)
+    }
+
+    /// Returns rsync filter patterns matching the metadata files.
+    ///
+    /// rsync patterns are case-sensitive, so only the lowercase names
+    /// Kodi, Emby and Jellyfin write are matched.
+    pub fn rsync_patterns() -> Vec<String> {
+        let mut patterns = vec![format!("*.{}", NFO_SUFFIX)];
+        for suffix in ARTWORK_SUFFIXES {
+            patterns.push(format!("{}*.{}", SEASON_ARTWORK_PREFIX, suffix));
+            for name in ARTWORK_NAMES {
+                patterns.push(format!("{}.{}", name, suffix));
+                patterns.push(format!("*-{}.{}", name, suffix));
+            }
+        }
+        patterns
+    }
+
+    /// Returns `true` if a file is an `.nfo` file or artwork.
+    fn matches(path: &Path, ignore_case: bool) -> bool {
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let has_suffix = |suffixes: &[&str]| {
+            suffixes.iter().any(|suffix| {
+                if ignore_case { extension.eq_ignore_ascii_case(suffix) } else { extension == *suffix }
+            })
+        };
+        if has_suffix(&[NFO_SUFFIX]) {
+            return true;
+        }
+        if !has_suffix(&ARTWORK_SUFFIXES) {
+            return false;
+        }
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let stem = if ignore_case { stem.to_lowercase() } else { stem.into_owned() };
+        stem.starts_with(SEASON_ARTWORK_PREFIX)
+            || ARTWORK_NAMES.iter().any(|name| {
+                stem == *name || stem.strip_suffix(name).is_some_and(|rest| rest.ends_with('-'))
+            })
+    }
+}
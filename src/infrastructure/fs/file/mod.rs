@@ -5,9 +5,35 @@
 //! - Path manipulation and normalization
 //! - File operations with consistent error handling
 //! - Cross-platform path separator handling
+//! - Open file descriptor limits and budgets
+//! - Image resizing and conversion to service limits
+//! - Human-readable byte sizes and minimum sizes of media files
+//! - Media detection by suffix and container signature
+//! - Classification of media files into features, trailers, samples and extras
+//! - Subtitle files belonging to media files
+//! - Kodi-style metadata files: `.nfo` files and artwork
+//! - Natural ordering of names for stable, human-friendly listings
 //! 
+pub mod byte_size;
+pub mod fd_budget;
 pub mod file_helper;
+pub mod image_helper;
+pub mod media_detector;
+pub mod media_kind;
+pub mod media_size;
+pub mod metadata_file;
+pub mod natural_order;
 pub mod path_helper;
+pub mod subtitle_companion;
 
+pub use byte_size::*;
+pub use fd_budget::*;
 pub use file_helper::*;
-pub use path_helper::*;
\ No newline at end of file
+pub use image_helper::*;
+pub use media_detector::*;
+pub use media_kind::*;
+pub use media_size::*;
+pub use metadata_file::*;
+pub use natural_order::*;
+pub use path_helper::*;
+pub use subtitle_companion::*;
\ No newline at end of file
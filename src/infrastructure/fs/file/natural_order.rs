@@ -0,0 +1,170 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter, Result as FmtResult},
+    iter::Peekable,
+    str::Chars,
+    sync::atomic::{AtomicU8, Ordering as AtomicOrdering}
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Collation used by [`NaturalOrder::compare`], set from the configuration.
+static DEFAULT_COLLATION: AtomicU8 = AtomicU8::new(Collation::Natural as u8);
+
+/// Chinese numerals read as digits.
+const CHINESE_DIGITS: [(char, u128); 12] = [
+    ('零', 0), ('〇', 0), ('一', 1), ('二', 2), ('两', 2), ('三', 3),
+    ('四', 4), ('五', 5), ('六', 6), ('七', 7), ('八', 8), ('九', 9),
+];
+
+/// Chinese numerals read as multipliers.
+const CHINESE_UNITS: [(char, u128); 4] = [('十', 10), ('百', 100), ('千', 1_000), ('万', 10_000)];
+
+/// How names are ordered in generated listings and reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Collation {
+
+    /// Numbers compare by value and letters ignore case, so `Episode 2`
+    /// comes before `Episode 10`
+    #[default]
+    Natural,
+
+    /// Like `Natural`, with Chinese numerals also compared by value, so
+    /// `第二集` comes before `第十集`
+    Chinese,
+}
+
+impl Collation {
+
+    /// Returns the collation used by listings and reports.
+    pub fn current() -> Self {
+        match DEFAULT_COLLATION.load(AtomicOrdering::Relaxed) {
+            value if value == Collation::Chinese as u8 => Collation::Chinese,
+            _ => Collation::Natural,
+        }
+    }
+
+    /// Sets the collation used by listings and reports.
+    pub fn set_current(collation: Self) {
+        DEFAULT_COLLATION.store(collation as u8, AtomicOrdering::Relaxed);
+    }
+}
+
+impl Display for Collation {
+
+    /// Formats the collation as its configuration name.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Collation::Natural => write!(f, "natural"),
+            Collation::Chinese => write!(f, "chinese"),
+        }
+    }
+}
+
+/// A run of a name compared as a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+
+    /// Digits, compared by value
+    Number(u128),
+
+    /// Any other character, compared case-insensitively
+    Text(char),
+}
+
+/// Human-friendly ordering of names and paths.
+///
+/// Numbers compare by value rather than digit by digit and letters ignore
+/// case, with numbers before letters. Names equal under these rules, such
+/// as `E01` and `e1`, fall back to their bytes, so the order is total and
+/// listings sorted with it are stable between runs.
+pub struct NaturalOrder;
+
+impl NaturalOrder {
+
+    /// Compares two names with the current [`Collation`].
+    pub fn compare(a: &str, b: &str) -> Ordering {
+        Self::compare_with(a, b, Collation::current())
+    }
+
+    /// Compares two names with the given collation.
+    pub fn compare_with(a: &str, b: &str, collation: Collation) -> Ordering {
+        let mut left = a.chars().peekable();
+        let mut right = b.chars().peekable();
+        loop {
+            match (Self::next_segment(&mut left, collation), Self::next_segment(&mut right, collation)) {
+                (None, None) => return a.cmp(b),
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(l), Some(r)) if l != r => return l.cmp(&r),
+                _ => {}
+            }
+        }
+    }
+
+    /// Sorts items by a name with the current [`Collation`].
+    pub fn sort_by_key<T>(items: &mut [T], key: impl Fn(&T) -> &str) {
+        let collation = Collation::current();
+        items.sort_by(|a, b| Self::compare_with(key(a), key(b), collation));
+    }
+
+    /// Reads the next segment of a name.
+    fn next_segment(chars: &mut Peekable<Chars<'_>>, collation: Collation) -> Option<Segment> {
+        let first = *chars.peek()?;
+        if first.is_ascii_digit() {
+            let mut value: u128 = 0;
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                value = value.saturating_mul(10).saturating_add(u128::from(digit));
+                chars.next();
+            }
+            return Some(Segment::Number(value));
+        }
+        if collation == Collation::Chinese && Self::is_chinese_numeral(first) {
+            let mut numeral = String::new();
+            while let Some(c) = chars.next_if(|c| Self::is_chinese_numeral(*c)) {
+                numeral.push(c);
+            }
+            return Some(Segment::Number(Self::chinese_value(&numeral)));
+        }
+        chars.next();
+        Some(Segment::Text(first.to_lowercase().next().unwrap_or(first)))
+    }
+
+    /// Returns `true` if a character is a Chinese digit or multiplier.
+    fn is_chinese_numeral(c: char) -> bool {
+        CHINESE_DIGITS.iter().chain(CHINESE_UNITS.iter()).any(|(numeral, _)| *numeral == c)
+    }
+
+    /// Returns the value of a Chinese numeral such as `十二`, `一百零五` or
+    /// `二〇二四`, the latter read digit by digit.
+    fn chinese_value(numeral: &str) -> u128 {
+        let digit = |c: char| CHINESE_DIGITS.iter().find(|(d, _)| *d == c).map(|(_, value)| *value);
+        let unit = |c: char| CHINESE_UNITS.iter().find(|(u, _)| *u == c).map(|(_, value)| *value);
+
+        if !numeral.chars().any(|c| unit(c).is_some()) {
+            return numeral
+                .chars()
+                .filter_map(digit)
+                .fold(0u128, |value, d| value.saturating_mul(10).saturating_add(d));
+        }
+
+        let (mut total, mut section, mut pending) = (0u128, 0u128, 0u128);
+        for c in numeral.chars() {
+            match (digit(c), unit(c)) {
+                (Some(d), _) => pending = d,
+                (_, Some(10_000)) => {
+                    total = total.saturating_add(section + pending).saturating_mul(10_000);
+                    (section, pending) = (0, 0);
+                }
+                // A leading 十 means ten, as in 十二
+                (_, Some(multiplier)) => {
+                    section = section.saturating_add(pending.max(1) * multiplier);
+                    pending = 0;
+                }
+                _ => {}
+            }
+        }
+        total.saturating_add(section).saturating_add(pending)
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     path::{Path, PathBuf},
     fs::{metadata},
-    io::{Error as IoError, ErrorKind as IoErrorKind},
+    io::Error as IoError,
 };
 
 use dirs;
@@ -206,7 +206,7 @@ impl PathHelper {
                 } else if metadata.is_dir() {
                     Ok(FileType::Directory)
                 } else {
-                    Err(IoError::new(IoErrorKind::Other, "Unknown file type"))
+                    Err(IoError::other("Unknown file type"))
                 }
             }
             Err(e) => Err(e),
@@ -0,0 +1,77 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf}
+};
+
+/// Separators allowed between a media file's stem and the language or
+/// flags of its subtitles, as in `Movie.en.srt` or `Movie_zh-Hans.ass`.
+const SUBTITLE_TAG_SEPARATORS: [char; 3] = ['.', '_', '-'];
+
+/// Finds the subtitle files that belong to media files.
+///
+/// A subtitle belongs to a media file in the same folder when its name,
+/// without the extension, is the media file's stem, optionally followed
+/// by language or flag tags: `Movie.mkv` owns `Movie.srt`, `Movie.en.srt`
+/// and `Movie.zh-Hans.forced.ass`.
+pub struct SubtitleCompanions;
+
+impl SubtitleCompanions {
+
+    /// Returns `true` if a subtitle file belongs to a media file.
+    pub fn belongs_to(subtitle: &Path, media: &Path) -> bool {
+        if subtitle.parent() != media.parent() {
+            return false;
+        }
+        let (Some(subtitle), Some(media)) = (subtitle.file_stem(), media.file_stem()) else {
+            return false;
+        };
+        Self::is_tagged_stem(&subtitle.to_string_lossy(), &media.to_string_lossy())
+    }
+
+    /// Returns the subtitle files belonging to one of the media files.
+    ///
+    /// # Arguments
+    /// * `files` - Paths of the files to search, e.g. relative to a source
+    /// * `extensions` - Suffixes of subtitle files, with or without leading dots
+    /// * `is_media` - Returns `true` for the media files subtitles may belong to
+    ///
+    /// # Returns
+    /// The subtitle files, in the order of `files`.
+    pub fn find(files: &[PathBuf], extensions: &[String], is_media: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+        let is_subtitle = |path: &Path| {
+            path.extension().is_some_and(|extension| {
+                extensions.iter().any(|suffix| extension.eq_ignore_ascii_case(suffix.trim_start_matches('.')))
+            })
+        };
+
+        let mut media_stems: HashMap<&Path, Vec<String>> = HashMap::new();
+        for file in files.iter().filter(|file| !is_subtitle(file) && is_media(file)) {
+            if let Some(stem) = file.file_stem() {
+                media_stems
+                    .entry(file.parent().unwrap_or(Path::new("")))
+                    .or_default()
+                    .push(stem.to_string_lossy().into_owned());
+            }
+        }
+
+        files
+            .iter()
+            .filter(|file| is_subtitle(file))
+            .filter(|file| {
+                let Some(stem) = file.file_stem().map(|stem| stem.to_string_lossy()) else {
+                    return false;
+                };
+                media_stems
+                    .get(file.parent().unwrap_or(Path::new("")))
+                    .is_some_and(|stems| stems.iter().any(|media| Self::is_tagged_stem(&stem, media)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns `true` if `stem` is `media` or `media` followed by tags.
+    fn is_tagged_stem(stem: &str, media: &str) -> bool {
+        stem.strip_prefix(media)
+            .is_some_and(|tags| tags.is_empty() || tags.starts_with(SUBTITLE_TAG_SEPARATORS))
+    }
+}
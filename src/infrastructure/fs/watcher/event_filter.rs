@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use notify::{event::{MetadataKind, ModifyKind}, EventKind};
+use serde::{Deserialize, Serialize};
+
+/// Kind of filesystem change a watcher can be limited to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum WatchEventKind {
+
+    /// A file or directory was created
+    Create,
+
+    /// The content of a file changed, or its modification time did
+    Modify,
+
+    /// Only permissions, ownership or access times changed
+    Metadata,
+
+    /// A file or directory was renamed or moved
+    Rename,
+
+    /// A file or directory was removed
+    Remove,
+
+    /// A file was opened, read or closed without changes
+    Access,
+
+    /// A change the platform doesn't describe further
+    Other,
+}
+
+impl WatchEventKind {
+
+    /// Returns the kind of a notify event.
+    ///
+    /// A changed modification time is a [`Modify`](Self::Modify): the
+    /// poll watcher and some platforms report writes only that way.
+    pub fn of(kind: &EventKind) -> Self {
+        match kind {
+            EventKind::Create(_) => WatchEventKind::Create,
+            EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime)) => WatchEventKind::Modify,
+            EventKind::Modify(ModifyKind::Metadata(_)) => WatchEventKind::Metadata,
+            EventKind::Modify(ModifyKind::Name(_)) => WatchEventKind::Rename,
+            EventKind::Modify(_) => WatchEventKind::Modify,
+            EventKind::Remove(_) => WatchEventKind::Remove,
+            EventKind::Access(_) => WatchEventKind::Access,
+            EventKind::Any | EventKind::Other => WatchEventKind::Other,
+        }
+    }
+}
+
+impl Display for WatchEventKind {
+
+    /// Formats the kind as written in configuration files.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            WatchEventKind::Create => "create",
+            WatchEventKind::Modify => "modify",
+            WatchEventKind::Metadata => "metadata",
+            WatchEventKind::Rename => "rename",
+            WatchEventKind::Remove => "remove",
+            WatchEventKind::Access => "access",
+            WatchEventKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Filter deciding which filesystem events a watcher processes, so
+/// constant access or metadata events, as some NFS servers emit, don't
+/// trigger syncs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+
+    /// Kinds of events processed, all of them when empty
+    kinds: Vec<WatchEventKind>,
+}
+
+impl EventFilter {
+
+    /// Creates a filter processing only the given kinds of events, or
+    /// every event when `kinds` is empty.
+    pub fn new(kinds: Vec<WatchEventKind>) -> Self {
+        Self { kinds }
+    }
+
+    /// Returns `true` if events of this kind are processed.
+    pub fn matches(&self, kind: &EventKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&WatchEventKind::of(kind))
+    }
+
+    /// Gets the kinds of events processed, empty for all of them.
+    pub fn get_kinds(&self) -> &[WatchEventKind] {
+        &self.kinds
+    }
+}
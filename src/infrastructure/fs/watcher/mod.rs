@@ -2,16 +2,29 @@
 //!
 //! This module provides a comprehensive solution for real-time file system monitoring with:
 //! - Cross-platform filesystem event notification
-//! - Configurable event filtering
+//! - Configurable event filtering, by kind of change
 //! - State management for monitoring lifecycle
 //! - Extensible callback system
+//! - Polling of remote sources behind the same event model
+//! - Polling of local paths where filesystem notifications are unavailable
+//! - Scan comparisons for volumes whose notifications are unreliable
+//! - Detection of moves, to replay them instead of transferring again
 //! 
 pub mod callback;
+pub mod event_filter;
+pub mod poller;
+pub mod rename_tracker;
+pub mod snapshot_poller;
 pub mod state;
 pub mod watchable;
+#[allow(clippy::module_inception)]
 pub mod watcher;
 
 pub use callback::*;
+pub use event_filter::*;
+pub use poller::*;
+pub use rename_tracker::*;
+pub use snapshot_poller::*;
 pub use state::*;
 pub use watchable::*;
 pub use watcher::*;
\ No newline at end of file
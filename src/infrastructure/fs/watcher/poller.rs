@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin
+};
+
+use anyhow::Error;
+use notify::{
+    event::{CreateKind, DataChange, ModifyKind, RemoveKind},
+    Event,
+    EventKind
+};
+
+/// Future returned by [`RemotePoller::poll`].
+pub type PollFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<Event>, Error>> + Send + 'a>>;
+
+/// A source that can't be watched with filesystem notifications and is
+/// polled for changes instead.
+///
+/// Pollers report changes as the same [`Event`]s a notify watcher emits,
+/// with paths relative to the polled root, so a [`FileWatcher`] can feed
+/// them through its usual debounce pipeline.
+///
+/// [`FileWatcher`]: super::FileWatcher
+pub trait RemotePoller: Send + 'static {
+
+    /// Lists the remote source and returns what changed since the last poll.
+    ///
+    /// The first poll only records a baseline and returns no events.
+    fn poll(&mut self) -> PollFuture<'_>;
+}
+
+/// Last known version of every file of a polled source.
+///
+/// A version is any string that changes when the file does, such as a
+/// size and modification time or an ETag.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteSnapshot {
+
+    /// Version of every file, keyed by path relative to the polled root,
+    /// or `None` before the first listing
+    files: Option<BTreeMap<PathBuf, String>>,
+}
+
+impl RemoteSnapshot {
+
+    /// Returns `true` once a baseline listing was recorded.
+    pub fn has_baseline(&self) -> bool {
+        self.files.is_some()
+    }
+
+    /// Returns the recorded version of every file below `prefix`.
+    pub fn files_under<'a>(&'a self, prefix: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a String)> + 'a {
+        self.files
+            .iter()
+            .flatten()
+            .filter(move |(path, _)| path.starts_with(prefix))
+    }
+
+    /// Replaces the files below `prefix` with a new listing.
+    ///
+    /// Pass an empty prefix to replace the whole snapshot. Files outside
+    /// the prefix are kept, which lets pollers re-list only the subtrees
+    /// that changed.
+    ///
+    /// # Returns
+    /// One event per file created, modified or removed below `prefix`,
+    /// or nothing if this is the first listing.
+    pub fn update(&mut self, prefix: &Path, listing: BTreeMap<PathBuf, String>) -> Vec<Event> {
+        let Some(files) = &mut self.files else {
+            self.files = Some(listing);
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        let removed: Vec<PathBuf> = files
+            .keys()
+            .filter(|path| path.starts_with(prefix) && !listing.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in removed {
+            files.remove(&path);
+            events.push(Event::new(EventKind::Remove(RemoveKind::File)).add_path(path));
+        }
+
+        for (path, version) in listing {
+            let kind = match files.get(&path) {
+                None => EventKind::Create(CreateKind::File),
+                Some(previous) if *previous != version => EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+                Some(_) => continue,
+            };
+            events.push(Event::new(kind).add_path(path.clone()));
+            files.insert(path, version);
+        }
+        events
+    }
+}
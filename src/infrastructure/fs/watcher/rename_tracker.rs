@@ -0,0 +1,297 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, UNIX_EPOCH}
+};
+
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event,
+    EventKind
+};
+
+/// Default time between a removal and a creation for them to be paired
+/// as a move.
+const RENAME_TRACKER_DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Most events of each kind kept until the renames are taken; older ones
+/// are dropped, and their files transferred again.
+const RENAME_TRACKER_MAX_EVENTS: usize = 10_000;
+
+/// Largest difference between modification times of the same file, as
+/// filesystems such as FAT and some SMB servers round them to 2 seconds.
+const RENAME_TRACKER_MTIME_TOLERANCE_MS: u64 = 2_000;
+
+/// Size and modification time identifying a file across a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// Last modification time in milliseconds since the Unix epoch, if known
+    pub modified_ms: Option<u64>,
+}
+
+impl FileStamp {
+
+    /// Creates a stamp from a size and a modification time.
+    pub fn new(size: u64, modified_ms: Option<u64>) -> Self {
+        Self { size, modified_ms }
+    }
+
+    /// Returns the stamp of a file's metadata.
+    pub fn of(metadata: &Metadata) -> Self {
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .and_then(|elapsed| u64::try_from(elapsed.as_millis()).ok());
+        Self::new(metadata.len(), modified_ms)
+    }
+
+    /// Returns `Some(true)` if both modification times are known and
+    /// agree, `Some(false)` if they differ, and `None` if one is unknown.
+    fn same_mtime(&self, other: &FileStamp) -> Option<bool> {
+        let (a, b) = (self.modified_ms?, other.modified_ms?);
+        Some(a.abs_diff(b) <= RENAME_TRACKER_MTIME_TOLERANCE_MS)
+    }
+}
+
+/// A file or directory moved within the watched tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRename {
+
+    /// Previous path, relative to the watched root
+    pub from: PathBuf,
+
+    /// New path, relative to the watched root
+    pub to: PathBuf,
+}
+
+impl Display for FileRename {
+
+    /// Formats the rename as `from -> to`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} -> {}", self.from.display(), self.to.display())
+    }
+}
+
+/// Events recorded since the renames were last taken.
+#[derive(Debug, Default)]
+struct TrackedEvents {
+
+    /// Renames the platform reported with both paths
+    renames: Vec<FileRename>,
+
+    /// Previous paths of renames whose new path is still to come, by
+    /// the cookie pairing both halves
+    pending_from: Vec<(usize, PathBuf)>,
+
+    /// Paths removed, with when
+    removed: Vec<(PathBuf, Instant)>,
+
+    /// Paths created, with when
+    created: Vec<(PathBuf, Instant)>,
+}
+
+impl TrackedEvents {
+
+    /// Drops the oldest events beyond [`RENAME_TRACKER_MAX_EVENTS`], so a
+    /// tracker whose renames aren't taken, e.g. while syncs are paused,
+    /// doesn't grow without end.
+    fn bound(&mut self) {
+        fn keep_latest<T>(events: &mut Vec<T>) {
+            if events.len() > RENAME_TRACKER_MAX_EVENTS {
+                events.drain(..events.len() - RENAME_TRACKER_MAX_EVENTS);
+            }
+        }
+        keep_latest(&mut self.renames);
+        keep_latest(&mut self.pending_from);
+        keep_latest(&mut self.removed);
+        keep_latest(&mut self.created);
+    }
+
+    /// Adds a rename, folding it into an earlier one of the same file.
+    fn push_rename(&mut self, from: PathBuf, to: PathBuf) {
+        if self.renames.iter().any(|rename| rename.from == from && rename.to == to) {
+            return;
+        }
+        match self.renames.iter_mut().find(|rename| rename.to == from) {
+            Some(earlier) if earlier.from == to => {
+                // Moved back where it was, nothing to replay
+                self.renames.retain(|rename| rename.to != from);
+            }
+            Some(earlier) => earlier.to = to,
+            None => self.renames.push(FileRename { from, to }),
+        }
+    }
+}
+
+/// Collects the moves happening in a watched tree, so they can be
+/// replayed at destinations instead of deleting and transferring again.
+///
+/// Renames the platform reports, such as inotify's paired `IN_MOVED_FROM`
+/// and `IN_MOVED_TO`, are taken as is. A removal followed by a creation
+/// within the pairing window, which is all pollers and moves across
+/// watches report, is a move if the created file has the size and the
+/// modification time the removed one had. Halves of a rename the
+/// platform can't tell apart, such as FSEvents' renames, are removals or
+/// creations depending on whether the path still exists.
+///
+/// Each kind of event is kept up to a limit until the renames are taken,
+/// the oldest being dropped first.
+///
+/// Cloning is cheap and clones share the recorded events, so the watcher
+/// records into the tracker its owner takes the renames from.
+#[derive(Debug, Clone)]
+pub struct RenameTracker {
+
+    /// Root the recorded paths are made relative to
+    root: PathBuf,
+
+    /// Longest time between a removal and a creation paired as a move
+    window: Duration,
+
+    /// Events recorded since the renames were last taken
+    events: Arc<Mutex<TrackedEvents>>,
+}
+
+impl RenameTracker {
+
+    /// Creates a tracker for the tree at `root`, the watched path.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            window: RENAME_TRACKER_DEFAULT_WINDOW,
+            events: Arc::new(Mutex::new(TrackedEvents::default())),
+        }
+    }
+
+    /// Sets the longest time between a removal and a creation for them
+    /// to be paired as a move (builder pattern).
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Records a filesystem event; events outside the root are ignored.
+    pub fn record(&self, event: &Event) {
+        let paths: Vec<PathBuf> = event.paths.iter().filter_map(|path| self.relative(path)).collect();
+        if paths.len() != event.paths.len() || paths.is_empty() {
+            return;
+        }
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+                events.push_rename(paths[0].clone(), paths[1].clone());
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => match event.attrs.tracker() {
+                Some(cookie) => events.pending_from.push((cookie, paths[0].clone())),
+                None => events.removed.push((paths[0].clone(), now)),
+            },
+            EventKind::Modify(ModifyKind::Name(RenameMode::Any | RenameMode::Other)) => {
+                for path in paths {
+                    if fs::symlink_metadata(self.root.join(&path)).is_ok() {
+                        events.created.push((path, now));
+                    } else {
+                        events.removed.push((path, now));
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let from = event.attrs.tracker().and_then(|cookie| {
+                    let index = events.pending_from.iter().position(|(pending, _)| *pending == cookie)?;
+                    Some(events.pending_from.remove(index).1)
+                });
+                match from {
+                    Some(from) => events.push_rename(from, paths[0].clone()),
+                    None => events.created.push((paths[0].clone(), now)),
+                }
+            }
+            EventKind::Remove(_) => events.removed.extend(paths.into_iter().map(|path| (path, now))),
+            EventKind::Create(_) => events.created.extend(paths.into_iter().map(|path| (path, now))),
+            _ => {}
+        }
+        events.bound();
+    }
+
+    /// Takes the moves recorded so far, clearing the tracker.
+    ///
+    /// Removals and creations are paired by stamp: a created file is a
+    /// move of a removed one reported within the window whose size, as
+    /// `removed_stamp` finds it, is the same and whose modification time
+    /// doesn't differ. A single removed file with the same modification
+    /// time is taken. Otherwise, such as when a modification time is
+    /// unknown, only the removed file with the same file name is taken,
+    /// and none if there's no such file.
+    ///
+    /// # Arguments
+    /// * `removed_stamp` - Stamp of a removed file given its relative
+    ///   path, such as that of its copy at a destination; `None` if
+    ///   unknown, which leaves the file unpaired
+    pub fn take_renames(&self, removed_stamp: impl Fn(&Path) -> Option<FileStamp>) -> Vec<FileRename> {
+        let events = std::mem::take(&mut *self.events.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut renames = events.renames;
+        let mut removed: Vec<(PathBuf, Instant, Option<FileStamp>)> = events.removed
+            .into_iter()
+            .filter(|(path, _)| !renames.iter().any(|rename| rename.from == *path))
+            .map(|(path, at)| {
+                let stamp = removed_stamp(&path);
+                (path, at, stamp)
+            })
+            .collect();
+
+        for (created, at) in events.created {
+            let Some(stamp) = fs::metadata(self.root.join(&created)).ok().filter(|m| m.is_file()).map(|m| FileStamp::of(&m)) else {
+                continue;
+            };
+            let candidates: Vec<(usize, Option<bool>)> = removed
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (removed_path, removed_at, removed_stamp))| {
+                    let removed_stamp = removed_stamp.filter(|removed_stamp| removed_stamp.size == stamp.size)?;
+                    let same_mtime = removed_stamp.same_mtime(&stamp);
+                    (same_mtime != Some(false)
+                        && *removed_path != created
+                        && Self::elapsed_between(*removed_at, at) <= self.window)
+                        .then_some((index, same_mtime))
+                })
+                .collect();
+            let confirmed: Vec<usize> = candidates
+                .iter()
+                .filter(|(_, same_mtime)| *same_mtime == Some(true))
+                .map(|(index, _)| *index)
+                .collect();
+            let index = match confirmed.as_slice() {
+                [index] => Some(*index),
+                _ => candidates
+                    .iter()
+                    .map(|(index, _)| *index)
+                    .filter(|index| confirmed.is_empty() || confirmed.contains(index))
+                    .find(|index| removed[*index].0.file_name() == created.file_name()),
+            };
+            if let Some(index) = index {
+                let (from, _, _) = removed.remove(index);
+                renames.push(FileRename { from, to: created });
+            }
+        }
+        renames
+    }
+
+    /// Returns a path relative to the root, `None` if it's outside.
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.root)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    }
+
+    /// Returns the time between two instants, in either order.
+    fn elapsed_between(a: Instant, b: Instant) -> Duration {
+        if a > b { a - b } else { b - a }
+    }
+}
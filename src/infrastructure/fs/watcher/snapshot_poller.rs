@@ -0,0 +1,193 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io,
+    path::{Path, PathBuf}
+};
+
+use anyhow::Error;
+use notify::{
+    event::{CreateKind, DataChange, ModifyKind, RemoveKind},
+    Event,
+    EventKind
+};
+
+use crate::warn_log;
+use super::{
+    poller::{PollFuture, RemotePoller},
+    super::dir::{ListingCache, ListingDiff}
+};
+
+/// Domain identifier for snapshot poller logs
+const SNAPSHOT_LOGGER_DOMAIN: &str = "[SNAPSHOT]";
+
+/// Poller detecting changes of a local tree by comparing scans, for
+/// volumes whose filesystem notifications are missing or unreliable,
+/// such as NFS and SMB mounts.
+///
+/// Every poll scans the whole tree and compares the size and modification
+/// time of each file with the previous scan. With a state file, the last
+/// synced scan is persisted, so the first poll after a restart reports
+/// what changed while stopped, or wasn't synced; without one, it only
+/// records a baseline. A scan with changes is written aside and only
+/// replaces the state file once [`PendingSnapshot::commit`] is called
+/// after the sync of its changes succeeded.
+pub struct SnapshotPoller {
+
+    /// Root of the scanned tree
+    root: PathBuf,
+
+    /// File the last scan is persisted to, if any
+    state_path: Option<PathBuf>,
+
+    /// Last scan, `None` before the first poll
+    snapshot: Option<ListingCache>,
+}
+
+impl SnapshotPoller {
+
+    /// Creates a poller scanning the tree at `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            state_path: None,
+            snapshot: None,
+        }
+    }
+
+    /// Persists the last scan to `path` (builder pattern).
+    pub fn with_state_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.state_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Scans the tree and returns the changes since the previous scan.
+    ///
+    /// A baseline is saved to the state file right away; a scan with
+    /// changes is saved aside, pending the sync of its changes.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the tree can't be scanned.
+    pub fn scan(&mut self) -> Result<Vec<Event>, Error> {
+        let previous = self.snapshot.take().or_else(|| self.load());
+        let current = ListingCache::scan(&self.root)?;
+        let diff = previous.as_ref().map(|previous| previous.diff(&current)).unwrap_or_default();
+
+        if let Some(path) = &self.state_path {
+            if previous.is_none() {
+                Self::save(&current, path);
+            } else if !diff.is_empty() {
+                Self::save(&current, &Self::suffixed(path, "pending"));
+            }
+        }
+        self.snapshot = Some(current);
+        Ok(Self::events(diff))
+    }
+
+    /// Takes the last scan with changes saved for a state file, so it
+    /// can be committed once a sync started afterwards succeeded.
+    ///
+    /// A scan taken by a sync that failed stays taken, and is replaced by
+    /// the next scan with changes; it's committed by the next sync that
+    /// succeeds otherwise.
+    ///
+    /// # Returns
+    /// The scan to commit, `None` if there's none.
+    pub fn take_pending(state_path: &Path) -> Option<PendingSnapshot> {
+        let taken = Self::suffixed(state_path, "syncing");
+        match fs::rename(Self::suffixed(state_path, "pending"), &taken) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                let msg = format!("Failed to take the pending scan of {}: {}", state_path.display(), e);
+                warn_log!(SNAPSHOT_LOGGER_DOMAIN, msg);
+            }
+        }
+        taken.exists().then(|| PendingSnapshot {
+            path: taken,
+            state_path: state_path.to_path_buf(),
+        })
+    }
+
+    /// Loads the persisted scan, discarding an unreadable one.
+    fn load(&self) -> Option<ListingCache> {
+        let path = self.state_path.as_ref().filter(|path| path.exists())?;
+        ListingCache::load(path)
+            .inspect_err(|e| {
+                warn_log!(SNAPSHOT_LOGGER_DOMAIN, format!("{:#}, scanning again", e));
+            })
+            .ok()
+    }
+
+    /// Persists a scan to a file.
+    fn save(snapshot: &ListingCache, path: &Path) {
+        if let Err(e) = snapshot.save(path) {
+            warn_log!(SNAPSHOT_LOGGER_DOMAIN, format!("Failed to save {}: {:#}", path.display(), e));
+        }
+    }
+
+    /// Returns a file next to a state file, e.g. `anime.cache.pending`.
+    fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = OsString::from(path.as_os_str());
+        name.push(".");
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    /// Converts a diff into one event per changed file.
+    fn events(diff: ListingDiff) -> Vec<Event> {
+        let created = diff.added
+            .into_iter()
+            .map(|path| Event::new(EventKind::Create(CreateKind::File)).add_path(path));
+        let modified = diff.modified
+            .into_iter()
+            .map(|path| Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Any))).add_path(path));
+        let removed = diff.removed
+            .into_iter()
+            .map(|path| Event::new(EventKind::Remove(RemoveKind::File)).add_path(path));
+        created.chain(modified).chain(removed).collect()
+    }
+}
+
+/// Scan with changes taken by a sync, see [`SnapshotPoller::take_pending`].
+#[derive(Debug)]
+pub struct PendingSnapshot {
+
+    /// File the scan was moved to when taken
+    path: PathBuf,
+
+    /// State file the scan replaces once committed
+    state_path: PathBuf,
+}
+
+impl PendingSnapshot {
+
+    /// Makes the scan the state file, once its changes are synced.
+    ///
+    /// # Errors
+    /// Returns `io::Error` if the scan can't be moved.
+    pub fn commit(self) -> io::Result<()> {
+        fs::rename(&self.path, &self.state_path)
+    }
+}
+
+impl RemotePoller for SnapshotPoller {
+
+    /// Scans the tree on a blocking thread.
+    fn poll(&mut self) -> PollFuture<'_> {
+        Box::pin(async move {
+            let mut poller = Self {
+                root: self.root.clone(),
+                state_path: self.state_path.clone(),
+                snapshot: self.snapshot.take(),
+            };
+            let (snapshot, events) = tokio::task::spawn_blocking(move || {
+                let events = poller.scan();
+                (poller.snapshot, events)
+            })
+            .await?;
+            self.snapshot = snapshot;
+            events
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -6,10 +7,10 @@ use std::{
     }
 };
 
-use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{Config, Event, EventHandler, EventKind, PollWatcher, RecursiveMode, Watcher};
 use tokio::{
     sync::mpsc::{channel, Receiver, Sender},
-    time::{sleep, Duration},
+    time::{sleep, sleep_until, Duration, Instant},
 };
 use tokio_stream::{
     StreamExt,
@@ -21,6 +22,9 @@ use crate::{error_log, info_log, warn_log};
 use super::{
     state::WatcherState,
     callback::FileWatcherCallback,
+    event_filter::EventFilter,
+    poller::RemotePoller,
+    rename_tracker::RenameTracker,
     watchable::FileWatchable,
     super::file::PathHelper,
 };
@@ -28,6 +32,10 @@ use super::{
 /// Domain identifier for file watcher logs
 const WATCHER_LOGGER_DOMAIN: &str = "[WATCHER]";
 
+/// Default delay between two scans of the watched path when filesystem
+/// notifications are unavailable; every scan walks the whole tree
+const WATCHER_FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(300);
+
 /// A robust filesystem watcher with debounce support and graceful shutdown
 ///
 /// This watcher provides:
@@ -36,13 +44,33 @@ const WATCHER_LOGGER_DOMAIN: &str = "[WATCHER]";
 /// - State management (Running/Paused/Stopped)
 /// - Automatic directory creation
 /// - Thread-safe operation
+/// - Polling of remote sources that emit no filesystem notifications
+/// - Polling of the watched path where filesystem notifications are
+///   unavailable, such as containers without inotify
 pub struct FileWatcher {
 
     /// The path being watched (expanded with tilde if needed)
     path: PathBuf,
 
-    /// Underlying notify watcher instance
-    watcher: Option<RecommendedWatcher>,
+    /// Underlying notify watcher instance, notification or polling based
+    watcher: Option<Box<dyn Watcher + Send>>,
+
+    /// Delay between two scans of the watched path, if it is polled
+    /// instead of relying on filesystem notifications
+    local_poll_interval: Option<Duration>,
+
+    /// Delay between two scans of the watched path when filesystem
+    /// notifications are unavailable
+    fallback_poll_interval: Duration,
+
+    /// Poller replacing filesystem notifications for remote sources
+    poller: Option<Box<dyn RemotePoller>>,
+
+    /// Delay between two polls of the remote source
+    poll_interval: Duration,
+
+    /// Whether filesystem notifications keep being watched while polling
+    poll_alongside: bool,
 
     /// Current operational state
     state: WatcherState,
@@ -50,6 +78,12 @@ pub struct FileWatcher {
     /// Callback for processing filesystem events
     callback: Option<FileWatcherCallback>,
 
+    /// Kinds of events that reach the callback
+    event_filter: EventFilter,
+
+    /// Tracker the moves among processed events are recorded into
+    rename_tracker: Option<RenameTracker>,
+
     /// Debounce period for event processing
     debounce_time: Duration,
 
@@ -62,6 +96,9 @@ pub struct FileWatcher {
     /// Handle to the async event processing task
     worker_handle: Option<tokio::task::JoinHandle<()>>,
 
+    /// Handle to the async remote polling task
+    poll_handle: Option<tokio::task::JoinHandle<()>>,
+
     /// Atomic flag for graceful shutdown
     should_exit: Arc<AtomicBool>,
 }
@@ -73,7 +110,7 @@ impl FileWatcher {
     /// # Arguments
     /// * `path` - Path to watch (supports tilde expansion)
     /// * `debounce_time` - Minimum delay between processing events 
-    /// (will be clamped to at least 2 seconds if lower value provided)
+    ///   (will be clamped to at least 2 seconds if lower value provided)
     ///
     /// # Notes
     /// - Watcher starts in Stopped state (call `resume()` to begin watching)
@@ -97,16 +134,121 @@ impl FileWatcher {
         Self {
             path,
             watcher: None,
+            local_poll_interval: None,
+            fallback_poll_interval: WATCHER_FALLBACK_POLL_INTERVAL,
+            poller: None,
+            poll_interval: Duration::ZERO,
+            poll_alongside: false,
             state: WatcherState::Stopped,
             callback: None,
+            event_filter: EventFilter::default(),
+            rename_tracker: None,
             debounce_time,
             event_tx,
             event_rx: Some(event_rx),
             worker_handle: None,
+            poll_handle: None,
             should_exit: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Polls a remote source instead of watching the local path
+    ///
+    /// # Arguments
+    /// * `poller` - Lists the remote source and reports what changed
+    /// * `interval` - Delay between two polls
+    ///
+    /// # Notes
+    /// - Paths reported by the poller are resolved against the watched path
+    /// - The local path isn't created, since it may be a mount of the remote
+    /// - Filesystem notifications aren't watched, see [`Self::with_poll_alongside`]
+    pub fn with_poller(mut self, poller: impl RemotePoller, interval: Duration) -> Self {
+        self.poller = Some(Box::new(poller));
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Keeps watching filesystem notifications while polling
+    ///
+    /// # Arguments
+    /// * `alongside` - Whether notifications and polls both report changes
+    ///
+    /// # Notes
+    /// - For local volumes whose notifications are unreliable rather than missing
+    /// - Has no effect without a poller
+    pub fn with_poll_alongside(mut self, alongside: bool) -> Self {
+        self.poll_alongside = alongside;
+        self
+    }
+
+    /// Limits the events that trigger the callback
+    ///
+    /// # Arguments
+    /// * `filter` - Kinds of events processed
+    ///
+    /// # Notes
+    /// - Filtered events don't restart the debounce window, so a stream of
+    ///   them can't hold back the callback
+    pub fn with_event_filter(mut self, filter: EventFilter) -> Self {
+        self.event_filter = filter;
+        self
+    }
+
+    /// Records the moves among processed events
+    ///
+    /// # Arguments
+    /// * `tracker` - Tracker sharing the recorded events with its clones
+    ///
+    /// # Notes
+    /// - Every event passing the filter is recorded, not only the last
+    ///   one of its debounce window
+    pub fn with_rename_tracker(mut self, tracker: RenameTracker) -> Self {
+        self.rename_tracker = Some(tracker);
+        self
+    }
+
+    /// Scans the watched path for changes instead of relying on
+    /// filesystem notifications
+    ///
+    /// # Arguments
+    /// * `interval` - Delay between two scans
+    ///
+    /// # Notes
+    /// - For filesystems that don't report changes, such as some network mounts
+    /// - Done automatically when filesystem notifications are unavailable
+    pub fn with_local_polling(mut self, interval: Duration) -> Self {
+        self.local_poll_interval = Some(interval);
+        self
+    }
+
+    /// Sets the delay between two scans of the watched path when
+    /// filesystem notifications are unavailable
+    ///
+    /// # Arguments
+    /// * `interval` - Delay between two scans, 5 minutes by default
+    pub fn with_fallback_polling(mut self, interval: Duration) -> Self {
+        self.fallback_poll_interval = interval;
+        self
+    }
+
+    /// Checks if the watched path is scanned instead of relying on
+    /// filesystem notifications
+    ///
+    /// # Returns
+    /// `true` once a polling watcher runs, whether requested or as a fallback
+    pub fn is_polling(&self) -> bool {
+        self.local_poll_interval.is_some() && self.watcher.is_some()
+    }
+
+    /// Returns a sender that injects events as if they came from the filesystem
+    ///
+    /// # Notes
+    /// - Lets simulations script events instead of touching real files
+    #[cfg(feature = "test-util")]
+    pub fn event_sender(&self) -> Sender<Event> {
+        self.event_tx.clone()
+    }
+
     /// Sets up Ctrl+C handler for graceful shutdown
     ///
     /// # Returns
@@ -147,6 +289,19 @@ impl FileWatcher {
             return Ok(());
         }
 
+        if self.poller.is_some() {
+            self.start_poller();
+            info_log!(
+                WATCHER_LOGGER_DOMAIN,
+                format!("Started polling source of: {}", self.path.display())
+            );
+            if !self.poll_alongside {
+                self.state = WatcherState::Running;
+                self.start_event_processor();
+                return Ok(());
+            }
+        }
+
         if !self.path.exists() {
             std::fs::create_dir_all(&self.path).map_err(|e| {
                 format!(
@@ -159,8 +314,62 @@ impl FileWatcher {
             info_log!(WATCHER_LOGGER_DOMAIN, msg);
         }
 
+        let watcher = match self.local_poll_interval {
+            Some(interval) => self.poll_watcher(interval)?,
+            None => self.notify_watcher().or_else(|e| {
+                let interval = self.fallback_poll_interval;
+                let msg = format!("{}, falling back to polling every {:?}", e, interval);
+                warn_log!(WATCHER_LOGGER_DOMAIN, msg);
+                self.local_poll_interval = Some(interval);
+                self.poll_watcher(interval)
+            })?,
+        };
+
+        self.watcher = Some(watcher);
+        self.state = WatcherState::Running;
+
+        info_log!(
+            WATCHER_LOGGER_DOMAIN,
+            format!("Started watching directory: {}", self.path.display())
+        );
+
+        self.start_event_processor();
+
+        Ok(())
+    }
+
+    /// Watches the path with filesystem notifications
+    ///
+    /// # Returns
+    /// - `Ok(watcher)` watching the path recursively
+    /// - `Err(String)` if notifications are unavailable or the path can't be watched,
+    ///   e.g. once the inotify watch limit is reached
+    fn notify_watcher(&self) -> Result<Box<dyn Watcher + Send>, String> {
+        let mut watcher = notify::recommended_watcher(self.event_handler())
+            .map_err(|e| format!("Failed to create watcher: {}", e))?;
+        watcher
+            .watch(&self.path, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch path {}: {}", self.path.display(), e))?;
+        Ok(Box::new(watcher))
+    }
+
+    /// Watches the path by scanning it for changes
+    ///
+    /// # Arguments
+    /// * `interval` - Delay between two scans
+    fn poll_watcher(&self, interval: Duration) -> Result<Box<dyn Watcher + Send>, String> {
+        let mut watcher = PollWatcher::new(self.event_handler(), Config::default().with_poll_interval(interval))
+            .map_err(|e| format!("Failed to create polling watcher: {}", e))?;
+        watcher
+            .watch(&self.path, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to poll path {}: {}", self.path.display(), e))?;
+        Ok(Box::new(watcher))
+    }
+
+    /// Returns the handler forwarding notify events into the event channel
+    fn event_handler(&self) -> impl EventHandler {
         let event_tx = self.event_tx.clone();
-        let mut watcher = notify::recommended_watcher(move |res| {
+        move |res: notify::Result<Event>| {
             match res {
                 Ok(event) => {
                     if let Err(e) = event_tx.blocking_send(event) {
@@ -173,24 +382,50 @@ impl FileWatcher {
                     error_log!(WATCHER_LOGGER_DOMAIN, msg);
                 }
             }
-        })
-            .map_err(|e| format!("Failed to create watcher: {}", e))?;
+        }
+    }
 
-        watcher
-            .watch(&self.path, RecursiveMode::Recursive)
-            .map_err(|e| format!("Failed to watch path {}: {}", self.path.display(), e))?;
+    /// Starts the async remote polling task
+    ///
+    /// # Notes
+    /// - Sends every reported change into the event channel
+    /// - Logs failed polls and keeps polling
+    /// - Checks for shutdown signal before each poll
+    fn start_poller(&mut self) {
+        let Some(mut poller) = self.poller.take() else {
+            return;
+        };
 
-        self.watcher = Some(watcher);
-        self.state = WatcherState::Running;
+        let root = self.path.clone();
+        let interval = self.poll_interval;
+        let event_tx = self.event_tx.clone();
+        let should_exit = self.should_exit.clone();
 
-        info_log!(
-            WATCHER_LOGGER_DOMAIN,
-            format!("Started watching directory: {}", self.path.display())
-        );
+        let handle = tokio::spawn(async move {
+            loop {
+                if should_exit.load(Ordering::Relaxed) {
+                    break;
+                }
 
-        self.start_event_processor();
+                match poller.poll().await {
+                    Ok(events) => {
+                        for mut event in events {
+                            event.paths = event.paths.iter().map(|path| root.join(path)).collect();
+                            if event_tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let msg = format!("Poll of {} failed: {:#}", root.display(), e);
+                        warn_log!(WATCHER_LOGGER_DOMAIN, msg);
+                    }
+                }
+                sleep(interval).await;
+            }
+        });
 
-        Ok(())
+        self.poll_handle = Some(handle);
     }
 
     /// Starts the async event processing task
@@ -198,6 +433,10 @@ impl FileWatcher {
     /// # Notes
     /// - Implements debounce logic
     /// - Only processes the last event in each debounce window
+    /// - Drops events the event filter excludes
+    /// - Records moves into the rename tracker, if any
+    /// - Keeps running if the callback panics
+    /// - Drops events at the configured rate with the `chaos` feature
     /// - Checks for shutdown signal periodically
     fn start_event_processor(&mut self) {
         if self.worker_handle.is_some() {
@@ -206,6 +445,8 @@ impl FileWatcher {
 
         let debounce_time = self.debounce_time;
         let callback = self.callback.clone();
+        let event_filter = self.event_filter.clone();
+        let rename_tracker = self.rename_tracker.clone();
         let event_rx = self.event_rx.take()
             .expect("Event receiver already taken");
         let should_exit = self.should_exit.clone();
@@ -213,17 +454,38 @@ impl FileWatcher {
         let handle = tokio::spawn(async move {
             let mut last_event = None;
             let mut stream = ReceiverStream::new(event_rx);
+            // Only processed events restart the window, filtered ones must not starve it
+            let mut deadline = Instant::now() + debounce_time;
 
             loop {
                 tokio::select! {
                     Some(event) = stream.next() => {
+                        if !event_filter.matches(&event.kind) {
+                            continue;
+                        }
+                        #[cfg(feature = "chaos")]
+                        if crate::infrastructure::chaos::FaultInjector::drop_event() {
+                            continue;
+                        }
+                        if let Some(tracker) = &rename_tracker {
+                            tracker.record(&event);
+                        }
                         last_event = Some(event);
+                        deadline = Instant::now() + debounce_time;
                     }
 
-                    _ = sleep(debounce_time) => {
+                    _ = sleep_until(deadline) => {
+                        deadline = Instant::now() + debounce_time;
                         if let Some(event) = &last_event {
                             if let Some(cb) = &callback {
-                                cb.0(event.kind);
+                                // A panicking callback must not take the watcher down with it
+                                let kind = event.kind;
+                                if panic::catch_unwind(AssertUnwindSafe(|| cb.0(kind))).is_err() {
+                                    error_log!(
+                                        WATCHER_LOGGER_DOMAIN,
+                                        "Callback panicked, continuing to watch"
+                                    );
+                                }
                             }
                             last_event = None;
                         }
@@ -244,7 +506,7 @@ impl FileWatchable for FileWatcher {
 
     /// Gets the current watcher state
     fn get_state(&self) -> WatcherState {
-        self.state.clone()
+        self.state
     }
     
     /// Resumes or starts watching
@@ -284,7 +546,7 @@ impl FileWatchable for FileWatcher {
     /// Stops watching and releases resources
     ///
     /// # Notes
-    /// - Aborts the event processing task
+    /// - Aborts the event processing and polling tasks
     /// - Drops the underlying watcher
     /// - Cannot be resumed after stopping
     fn stop(&mut self) {
@@ -292,6 +554,9 @@ impl FileWatchable for FileWatcher {
             self.state = WatcherState::Stopped;
             info_log!(WATCHER_LOGGER_DOMAIN, "Stopped watching.");
             self.watcher.take();
+            if let Some(handle) = self.poll_handle.take() {
+                handle.abort();
+            }
             if let Some(handle) = self.worker_handle.take() {
                 tokio::spawn(async move {
                     handle.abort();
@@ -9,7 +9,7 @@ use tracing_subscriber::{
     Registry
 };
 
-use super::{LogLevel, LogRotation};
+use super::{LogLevel, LogRotation, SpanTimingLayer};
 
 /// A builder for configuring and initializing a logging system
 ///
@@ -29,6 +29,10 @@ pub struct LoggerBuilder {
 
     /// Rotation strategy for log files
     rolling: LogRotation,
+
+    /// OTLP/HTTP collector base URL receiving traces and metrics
+    #[cfg(feature = "otlp")]
+    otlp_endpoint: Option<String>,
 }
 
 impl Default for LoggerBuilder {
@@ -44,6 +48,8 @@ impl Default for LoggerBuilder {
             directory: "logs".to_owned(),
             file_name_prefix: "".to_owned(),
             rolling: LogRotation::Daily,
+            #[cfg(feature = "otlp")]
+            otlp_endpoint: None,
         }
     }
 }
@@ -96,6 +102,21 @@ impl LoggerBuilder {
         self
     }
 
+    /// Exports spans and span durations to an OpenTelemetry collector
+    ///
+    /// # Arguments
+    /// * `endpoint` - OTLP/HTTP collector base URL (e.g. `http://localhost:4318`),
+    ///   or `None` to disable the export
+    ///
+    /// # Notes
+    /// - Call [`OtlpExporter::shutdown`](super::OtlpExporter::shutdown) before
+    ///   exiting to flush pending telemetry
+    #[cfg(feature = "otlp")]
+    pub fn with_otlp_endpoint(mut self, endpoint: Option<String>) -> Self {
+        self.otlp_endpoint = endpoint.filter(|endpoint| !endpoint.trim().is_empty());
+        self
+    }
+
     /// Initializes the global logger with the configured settings
     ///
     /// # Panics
@@ -109,6 +130,9 @@ impl LoggerBuilder {
     ///   - Compact format
     ///   - Precise timestamps
     ///   - No ANSI colors
+    /// - With the `otlp` feature, spans and their durations are exported
+    ///   when an endpoint is set; export failures are logged, not fatal
+    /// - Span durations are collected into [`SpanTimings`](super::SpanTimings)
     /// - Console logging includes:
     ///   - Compact format
     ///   - ANSI colors
@@ -119,7 +143,7 @@ impl LoggerBuilder {
         )
             .expect("Failed to parse time format");
         let time_offset = UtcOffset::current_local_offset()
-            .unwrap_or_else(|_| UtcOffset::UTC);
+            .unwrap_or(UtcOffset::UTC);
         let timer = fmt::time::OffsetTime::new(time_offset, timer_fmt);
 
         // Try to get filter from env, fallback to configured level
@@ -155,11 +179,31 @@ impl LoggerBuilder {
             .with_thread_names(false)
             .with_thread_ids(false);
 
-        // Initialize global logger
-        Registry::default()
+        let registry = Registry::default()
             .with(env_filter)
             .with(file_layer)
             .with(console_layer)
-            .init();
+            .with(SpanTimingLayer);
+
+        #[cfg(feature = "otlp")]
+        let (registry, otlp_error) = {
+            let started = self.otlp_endpoint.as_deref().map(super::OtlpExporter::start);
+            let (traces, metrics) = match &started {
+                Some(Ok((tracer, histogram))) => (
+                    Some(tracing_opentelemetry::layer().with_tracer(tracer.clone())),
+                    Some(super::OtlpMetricsLayer::new(histogram.clone())),
+                ),
+                _ => (None, None),
+            };
+            (registry.with(traces).with(metrics), started.and_then(Result::err))
+        };
+
+        // Initialize global logger
+        registry.init();
+
+        #[cfg(feature = "otlp")]
+        if let Some(e) = otlp_error {
+            crate::warn_log!("[LOGGER]", format!("OpenTelemetry export disabled: {:#}", e));
+        }
     }
 }
\ No newline at end of file
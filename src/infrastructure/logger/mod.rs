@@ -5,12 +5,20 @@
 //! - Log rotation support
 //! - Builder pattern for easy configuration
 //! - Convenient macros for logging
+//! - Span timings and per-run correlation IDs
+//! - OpenTelemetry export over OTLP (`otlp` feature)
 //! 
 pub mod builder;
 pub mod rotation;
 pub mod level;
 pub mod macros;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod span_timings;
 
 pub use builder::*;
 pub use rotation::*;
-pub use level::*;
\ No newline at end of file
+pub use level::*;
+#[cfg(feature = "otlp")]
+pub use otlp::*;
+pub use span_timings::*;
\ No newline at end of file
@@ -0,0 +1,198 @@
+use std::{
+    thread,
+    time::Instant
+};
+
+use anyhow::{anyhow, Error, Result};
+use once_cell::sync::OnceCell;
+use opentelemetry::{
+    metrics::{Histogram, MeterProvider as _},
+    trace::TracerProvider as _,
+    KeyValue
+};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    metrics::SdkMeterProvider,
+    trace::{SdkTracer, SdkTracerProvider},
+    Resource
+};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// Environment variable holding the OTLP/HTTP collector base URL
+/// (e.g. `http://localhost:4318`).
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Service name reported to the collector.
+const OTLP_SERVICE_NAME: &str = "pilipili_strm";
+
+/// Name of the histogram recording span durations.
+const OTLP_SPAN_DURATION_METRIC: &str = "pilipili_strm.span.duration";
+
+/// Providers of the running exporter, kept for shutdown.
+static OTLP_PROVIDERS: OnceCell<(SdkTracerProvider, SdkMeterProvider)> = OnceCell::new();
+
+/// OpenTelemetry export of traces and span duration metrics over OTLP/HTTP.
+pub struct OtlpExporter;
+
+impl OtlpExporter {
+
+    /// Starts exporting to the collector at `endpoint`.
+    ///
+    /// The exporters are built on a dedicated thread because the blocking
+    /// HTTP client can't be created inside an async runtime.
+    ///
+    /// # Returns
+    /// The tracer for the span layer and the span duration histogram.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if an exporter can't be built or the
+    /// exporter was already started.
+    pub fn start(endpoint: &str) -> Result<(SdkTracer, Histogram<f64>), Error> {
+        let base = endpoint.trim_end_matches('/').to_string();
+        let (tracer_provider, meter_provider) = thread::spawn(move || Self::build_providers(&base))
+            .join()
+            .map_err(|_| anyhow!("OTLP exporter thread panicked"))??;
+
+        let tracer = tracer_provider.tracer(OTLP_SERVICE_NAME);
+        let histogram = meter_provider
+            .meter(OTLP_SERVICE_NAME)
+            .f64_histogram(OTLP_SPAN_DURATION_METRIC)
+            .with_unit("s")
+            .with_description("Duration of pipeline stages")
+            .build();
+
+        OTLP_PROVIDERS
+            .set((tracer_provider, meter_provider))
+            .map_err(|_| anyhow!("OTLP exporter already started"))?;
+        Ok((tracer, histogram))
+    }
+
+    /// Registers a gauge whose value is read at every export.
+    ///
+    /// # Returns
+    /// `false` if the exporter isn't started.
+    pub fn observe_gauge(
+        name: &'static str,
+        description: &'static str,
+        value: impl Fn() -> u64 + Send + Sync + 'static,
+    ) -> bool {
+        let Some((_, meter_provider)) = OTLP_PROVIDERS.get() else {
+            return false;
+        };
+        meter_provider
+            .meter(OTLP_SERVICE_NAME)
+            .u64_observable_gauge(name)
+            .with_description(description)
+            .with_callback(move |observer| observer.observe(value(), &[]))
+            .build();
+        true
+    }
+
+    /// Adds to a counter, labeled with the given attributes.
+    ///
+    /// # Returns
+    /// `false` if the exporter isn't started.
+    pub fn add_to_counter(
+        name: &'static str,
+        description: &'static str,
+        value: u64,
+        attributes: &[(&'static str, String)],
+    ) -> bool {
+        let Some((_, meter_provider)) = OTLP_PROVIDERS.get() else {
+            return false;
+        };
+        let attributes: Vec<KeyValue> = attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(*key, value.clone()))
+            .collect();
+        meter_provider
+            .meter(OTLP_SERVICE_NAME)
+            .u64_counter(name)
+            .with_description(description)
+            .build()
+            .add(value, &attributes);
+        true
+    }
+
+    /// Flushes pending telemetry and stops the exporter, if it was started.
+    pub fn shutdown() {
+        if let Some((tracer_provider, meter_provider)) = OTLP_PROVIDERS.get() {
+            let tracer_provider = tracer_provider.clone();
+            let meter_provider = meter_provider.clone();
+            // Shutting down drops the blocking HTTP client, which isn't allowed in async contexts
+            let _ = thread::spawn(move || {
+                let _ = tracer_provider.shutdown();
+                let _ = meter_provider.shutdown();
+            }).join();
+        }
+    }
+
+    /// Builds the trace and metric providers for a collector base URL.
+    fn build_providers(base: &str) -> Result<(SdkTracerProvider, SdkMeterProvider), Error> {
+        let resource = Resource::builder()
+            .with_service_name(OTLP_SERVICE_NAME)
+            .build();
+
+        let span_exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", base))
+            .build()?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", base))
+            .build()?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .with_resource(resource)
+            .build();
+
+        Ok((tracer_provider, meter_provider))
+    }
+}
+
+/// Tracing layer recording span durations into an OpenTelemetry histogram,
+/// labeled with the span name.
+pub struct OtlpMetricsLayer {
+
+    /// Histogram receiving span durations in seconds
+    histogram: Histogram<f64>,
+}
+
+impl OtlpMetricsLayer {
+
+    /// Creates a layer recording into `histogram`.
+    pub fn new(histogram: Histogram<f64>) -> Self {
+        Self { histogram }
+    }
+}
+
+/// Creation time stored in the extensions of each span.
+struct OtlpSpanStart(Instant);
+
+impl<S> Layer<S> for OtlpMetricsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(OtlpSpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(OtlpSpanStart(start)) = span.extensions().get::<OtlpSpanStart>() {
+                self.histogram.record(
+                    start.elapsed().as_secs_f64(),
+                    &[KeyValue::new("span", span.name())],
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    },
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
+
+use once_cell::sync::Lazy;
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// Timings of closed spans keyed by span name.
+static SPAN_TIMINGS: Lazy<Mutex<BTreeMap<&'static str, SpanTiming>>> = Lazy::new(|| {
+    Mutex::new(BTreeMap::new())
+});
+
+/// Counter making run IDs unique within the process.
+static RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Aggregated durations of every closed span with the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpanTiming {
+
+    /// Number of closed spans
+    pub count: u64,
+
+    /// Sum of all span durations
+    pub total: Duration,
+
+    /// Longest span duration
+    pub max: Duration,
+}
+
+impl SpanTiming {
+
+    /// Returns the mean span duration.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.count) {
+            Ok(count) if count > 0 => self.total / count,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+impl Display for SpanTiming {
+
+    /// Formats the timing as `count=3 mean=1.2s max=2.5s`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "count={} mean={:.1?} max={:.1?}", self.count, self.mean(), self.max)
+    }
+}
+
+/// Read access to the span timings collected by [`SpanTimingLayer`].
+pub struct SpanTimings;
+
+impl SpanTimings {
+
+    /// Returns the timings collected so far, keyed by span name.
+    pub fn snapshot() -> BTreeMap<String, SpanTiming> {
+        SPAN_TIMINGS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, timing)| (name.to_string(), *timing))
+            .collect()
+    }
+
+    /// Clears all collected timings.
+    pub fn reset() {
+        SPAN_TIMINGS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Adds a span duration to the timings of `name`.
+    fn record(name: &'static str, elapsed: Duration) {
+        let mut timings = SPAN_TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+        let timing = timings.entry(name).or_default();
+        timing.count += 1;
+        timing.total += elapsed;
+        timing.max = timing.max.max(elapsed);
+    }
+}
+
+/// Tracing layer that measures how long each span stays open.
+///
+/// Durations are wall-clock time from creation to close, so they include
+/// time spent waiting, e.g. for a destination lock.
+#[derive(Debug, Default)]
+pub struct SpanTimingLayer;
+
+/// Creation time stored in the extensions of each span.
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for SpanTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(SpanStart(start)) = span.extensions().get::<SpanStart>() {
+                SpanTimings::record(span.name(), start.elapsed());
+            }
+        }
+    }
+}
+
+/// Correlation ID tying together the log lines and notifications of one run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RunId(String);
+
+impl RunId {
+
+    /// Creates a new run ID, unique within the process and unlikely to
+    /// repeat across restarts.
+    pub fn new() -> Self {
+        let counter = RUN_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        Self(format!("{:06x}{:02x}", nanos >> 8, counter & 0xff))
+    }
+
+    /// Returns the run ID as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RunId {
+
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for RunId {
+
+    /// Formats the run ID.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,77 @@
+//! Defines the error types produced by the network layer.
+//!
+//! This module separates timeouts from other transport failures so callers
+//! can decide whether a request is worth retrying with a longer budget.
+
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration
+};
+
+/// Represents an error that occurred while sending a network request.
+#[derive(Debug)]
+pub enum NetworkError {
+
+    /// The request didn't complete within its timeout or deadline
+    Timeout(Duration),
+
+    /// The connection to the remote host couldn't be established
+    Connection(reqwest::Error),
+
+    /// Any other failure while building or sending the request
+    Request(reqwest::Error),
+}
+
+impl NetworkError {
+
+    /// Classifies a `reqwest::Error` into a `NetworkError`.
+    ///
+    /// # Arguments
+    /// * `error` - The underlying reqwest error
+    /// * `timeout` - The timeout that was applied to the request, if any
+    pub fn from_reqwest(error: reqwest::Error, timeout: Option<Duration>) -> Self {
+        if error.is_timeout() {
+            NetworkError::Timeout(timeout.unwrap_or_default())
+        } else if error.is_connect() {
+            NetworkError::Connection(error)
+        } else {
+            NetworkError::Request(error)
+        }
+    }
+
+    /// Returns `true` if the request failed because it timed out.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, NetworkError::Timeout(_))
+    }
+
+    /// Returns `true` if the connection to the remote host failed.
+    pub fn is_connect(&self) -> bool {
+        matches!(self, NetworkError::Connection(_))
+    }
+}
+
+impl Display for NetworkError {
+
+    /// Formats the network error for display purposes.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            NetworkError::Timeout(duration) => {
+                write!(f, "Request timed out after {:?}", duration)
+            }
+            NetworkError::Connection(e) => write!(f, "Connection failed: {}", e),
+            NetworkError::Request(e) => write!(f, "Request failed: {}", e),
+        }
+    }
+}
+
+impl StdError for NetworkError {
+
+    /// Returns the underlying reqwest error, if any.
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            NetworkError::Timeout(_) => None,
+            NetworkError::Connection(e) | NetworkError::Request(e) => Some(e),
+        }
+    }
+}
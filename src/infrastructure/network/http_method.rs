@@ -23,6 +23,12 @@ pub enum HttpMethod {
 
     /// HTTP DELETE method
     Delete,
+
+    /// HTTP HEAD method
+    Head,
+
+    /// WebDAV PROPFIND method
+    Propfind,
 }
 
 impl Display for HttpMethod {
@@ -36,6 +42,8 @@ impl Display for HttpMethod {
             HttpMethod::Post => "POST",
             HttpMethod::Put => "PUT",
             HttpMethod::Delete => "DELETE",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Propfind => "PROPFIND",
         };
         write!(f, "{}", str)
     }
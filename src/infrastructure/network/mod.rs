@@ -5,6 +5,8 @@
 //! - Plugin system for request/response processing
 //! - Curl-based implementation
 //! - Task-based request handling
+//! - Per-request timeouts and deadlines
+//! - Percent-encoding of URL paths
 //! 
 pub mod http_method;
 pub mod task;
@@ -12,7 +14,9 @@ pub mod target;
 pub mod provider;
 pub mod plugin;
 pub mod curl_plugin;
-pub mod extension;
+pub(crate) mod extension;
+pub mod error;
+pub mod url_path;
 
 pub use http_method::*;
 pub use task::*;
@@ -20,4 +24,5 @@ pub use target::*;
 pub use provider::*;
 pub use plugin::*;
 pub use curl_plugin::*;
-pub use extension::*;
\ No newline at end of file
+pub use error::*;
+pub use url_path::*;
\ No newline at end of file
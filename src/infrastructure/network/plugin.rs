@@ -15,7 +15,10 @@ use reqwest::{
 /// - Before the request is sent
 /// - After a response is received
 /// - When an error occurs
-pub trait NetworkPlugin {
+///
+/// Plugins must be thread-safe, so clients owning them can be used from
+/// background tasks.
+pub trait NetworkPlugin: Send + Sync {
 
     /// Called before a request is sent.
     /// 
@@ -3,6 +3,8 @@
 //! This module implements the core network provider that handles HTTP requests,
 //! including request building, sending, and plugin integration.
 
+use std::time::{Duration, Instant};
+
 use reqwest::{
     Client, 
     Method
@@ -10,6 +12,7 @@ use reqwest::{
 use once_cell::sync::Lazy;
 
 use super::{
+    error::NetworkError,
     http_method::HttpMethod,
     plugin::NetworkPlugin,
     task::NetworkTask,
@@ -62,9 +65,10 @@ impl NetworkProvider {
     /// 
     /// This method handles the complete request lifecycle:
     /// 1. Builds the request with the target's configuration
-    /// 2. Executes request plugins
-    /// 3. Sends the request
-    /// 4. Executes response/error plugins
+    /// 2. Applies the target's timeout and deadline
+    /// 3. Executes request plugins
+    /// 4. Sends the request
+    /// 5. Executes response/error plugins
     /// 
     /// # Arguments
     /// 
@@ -73,10 +77,18 @@ impl NetworkProvider {
     /// # Returns
     /// 
     /// A `Result` containing either the response or an error
+    ///
+    /// # Errors
+    ///
+    /// Returns `NetworkError::Timeout` if the request exceeds its timeout or
+    /// deadline, `NetworkError::Connection` if the host can't be reached, and
+    /// `NetworkError::Request` for any other failure.
     pub async fn send_request<T: NetworkTarget>(
         &self, 
         target: &T
-    ) -> Result<reqwest::Response, reqwest::Error> {
+    ) -> Result<reqwest::Response, NetworkError> {
+        let timeout = Self::effective_timeout(target)?;
+
         let url = format!(
             "{}/{}",
             target.base_url().trim_end_matches('/'),
@@ -88,6 +100,8 @@ impl NetworkProvider {
             HttpMethod::Post => Method::POST,
             HttpMethod::Put => Method::PUT,
             HttpMethod::Delete => Method::DELETE,
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Propfind => Method::from_bytes(b"PROPFIND").expect("PROPFIND is a valid method"),
         }, &url);
 
         if let Some(headers) = target.headers() {
@@ -115,6 +129,13 @@ impl NetworkProvider {
             NetworkTask::RequestMultipartWithFiles(params, files) => {
                 request = request.with_multipart_files(params.clone(), files.clone()).await;
             }
+            NetworkTask::RequestBytes(body) => {
+                request = request.body(body);
+            }
+        }
+
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
         }
 
         for plugin in &self.plugins {
@@ -139,6 +160,34 @@ impl NetworkProvider {
             }
         }
 
-        response
+        response.map_err(|e| NetworkError::from_reqwest(e, timeout))
+    }
+
+    /// Computes the timeout to apply to a request.
+    ///
+    /// Combines the target's timeout and the time remaining until its deadline,
+    /// using whichever is shorter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NetworkError::Timeout` if the deadline has already passed.
+    fn effective_timeout<T: NetworkTarget>(
+        target: &T
+    ) -> Result<Option<Duration>, NetworkError> {
+        let remaining = match target.deadline() {
+            Some(deadline) => {
+                let now = Instant::now();
+                if deadline <= now {
+                    return Err(NetworkError::Timeout(Duration::ZERO));
+                }
+                Some(deadline - now)
+            }
+            None => None,
+        };
+
+        Ok(match (target.timeout(), remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        })
     }
 }
\ No newline at end of file
@@ -3,6 +3,8 @@
 //! This module provides a trait that defines the structure of a network request target,
 //! including the base URL, path, HTTP method, and request task.
 
+use std::time::{Duration, Instant};
+
 use super::{
     http_method::HttpMethod,
     task::NetworkTask
@@ -16,6 +18,7 @@ use super::{
 /// - HTTP method
 /// - Request task (body/parameters)
 /// - Optional headers
+/// - Optional timeout and deadline
 pub trait NetworkTarget {
 
     /// Returns the base URL of the API.
@@ -37,4 +40,21 @@ pub trait NetworkTarget {
     fn headers(&self) -> Option<Vec<(&'static str, String)>> {
         None
     }
+
+    /// Returns the maximum duration allowed for the request.
+    ///
+    /// By default, returns `None`, meaning no per-request timeout is applied.
+    /// Implementors can override this method for endpoints that need a
+    /// shorter or longer budget (e.g. file uploads).
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns the absolute point in time by which the request must complete.
+    ///
+    /// By default, returns `None`. When both a timeout and a deadline are set,
+    /// the provider uses whichever expires first.
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
 }
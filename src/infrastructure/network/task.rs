@@ -19,5 +19,8 @@ pub enum NetworkTask {
     RequestMultipart(HashMap<String, String>),
 
     /// A request with form data including files (multipart/form-data)
-    RequestMultipartWithFiles(HashMap<String, String>, Vec<(String, String)>)
+    RequestMultipartWithFiles(HashMap<String, String>, Vec<(String, String)>),
+
+    /// A request with a raw binary body
+    RequestBytes(Vec<u8>)
 }
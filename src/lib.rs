@@ -1,11 +1,60 @@
+//! Generates `.strm` files for media libraries and keeps them synchronized
+//! with media server destinations.
+//!
+//! # Public API
+//!
+//! The supported surface for embedding the crate is:
+//! - [`core::library`]: [`LibrarySync`](core::library::LibrarySync) pipelines,
+//!   sync strategies, executors, plans and state switches
+//! - [`core::config`]: the TOML configuration and its sections
+//! - [`core::strm`]: [`StrmGenerator`](core::strm::StrmGenerator) and validation
+//! - [`core::notification`]: [`Notifier`](core::notification::Notifier) and templates
+//! - [`core::client`] and [`core::api`]: clients of Telegram, Emby, Alist,
+//!   WebDAV, S3 and HTTP upload servers, with their request descriptions
+//! - [`infrastructure::fs`], [`infrastructure::logger`],
+//!   [`infrastructure::network`] and [`infrastructure::error`]: the building
+//!   blocks the pipelines are made of
+//!
+//! `examples/` shows the common embeddings. Items re-exported from a
+//! module are reached through the module rather than the file defining
+//! them, e.g. `core::library::LibrarySync`.
+//!
+//! # Stability
+//!
+//! The crate follows semantic versioning; while it is `0.x`, minor
+//! releases may break the API and patch releases don't. Not covered are:
+//! - `pub(crate)` modules such as the persisted state files, which never
+//!   appear in the documentation
+//! - the `test-util` and `chaos` features, meant for tests only
+//! - fields added to configuration structs, which are read from TOML with
+//!   defaults rather than built in code
+//!
+//! Enums and structs expected to grow, such as
+//! [`SyncStrategy`](core::library::SyncStrategy) and
+//! [`NotificationKind`](core::notification::NotificationKind), are
+//! `#[non_exhaustive]`, so adding to them isn't a breaking change.
+//!
+//! Items are deprecated with `#[deprecated(since, note)]` naming their
+//! replacement, and removed no earlier than the next breaking release.
+
 pub mod infrastructure {
+    pub mod error;
     pub mod logger;
     pub mod network;
     pub mod fs;
+    #[cfg(feature = "chaos")]
+    pub mod chaos;
 }
 
 pub mod core {
     pub mod api;
+    pub mod backup;
     pub mod client;
     pub mod config;
-}
\ No newline at end of file
+    pub mod library;
+    pub mod notification;
+    pub mod strm;
+}
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
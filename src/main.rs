@@ -1,120 +1,615 @@
 use std::{
-    path::PathBuf,
+    env,
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
-use pilipili_strm::info_log;
+use pilipili_strm::{debug_log, error_log, info_log, warn_log};
+use pilipili_strm::core::{
+    api::DocumentMessage,
+    backup::{BackupArchive, BackupSummary},
+    client::{MarkdownV2Builder, TelegramClient},
+    config::{Config, DigestPeriod, EffectiveConfig, LibraryConfig, CONFIG_PATH_ENV},
+    library::{benchmark_library, simulate, EmbyRefreshQueue, HealthCheck, HealthMonitor, HealthTransition, LibrarySync, MaintenanceState, PauseState, Scenario, StateDoctor, SyncEstimate},
+    strm::{StrmImporter, StrmValidator},
+    notification::{format_duration, Digest, NotificationKind, NotificationQueue, Notifier, NOTIFICATION_QUEUE_RETRY_INTERVAL},
+};
+use pilipili_strm::infrastructure::error::{CrashReport, PanicHook};
 use pilipili_strm::infrastructure::logger::*;
 use pilipili_strm::infrastructure::fs::*;
 
+/// Command line usage
+const USAGE: &str = "Usage: pilipili_strm [watch [LIBRARY...] | sync [LIBRARY...] | plan [LIBRARY...] | pause [LIBRARY] | resume [LIBRARY] | maintenance on|off | digest [daily|weekly] | simulate LIBRARY SCENARIO | bench [LIBRARY...] | validate DIR [--check-urls] | import DIR [LIBRARY [--normalize]] | doctor [--repair] | backup [FILE] | restore FILE | config show [--effective]]";
+
 fn init_logger() {
-    LoggerBuilder::default()
-        .with_level(LogLevel::Debug)
-        .init();
+    let builder = LoggerBuilder::default().with_level(LogLevel::Debug);
+    #[cfg(feature = "otlp")]
+    let builder = builder.with_otlp_endpoint(env::var(OTLP_ENDPOINT_ENV).ok());
+    builder.init();
+}
+
+/// Directory inside the state directory that holds crash reports
+const CRASH_DIR_NAME: &str = "crash";
+
+fn install_panic_hook(config: &Config) {
+    let mut hook = PanicHook::new().with_report_dir(config.state_dir().join(CRASH_DIR_NAME));
+    if let Some(notifier) = Notifier::from_config(config) {
+        hook = hook.with_notifier(Box::new(move |report: &CrashReport| {
+            let _ = notifier.enqueue(NotificationKind::Crashed, &[("error", &report.to_string())]);
+        }));
+    }
+    hook.install();
+}
+
+/// Raises the open file limit so parallel scans, watches and transfers don't run out.
+fn raise_fd_limit() {
+    match FdLimit::raise() {
+        Ok(limit) => debug_log!(format!("Open file limit is {} (hard limit {})", limit.soft, limit.hard)),
+        Err(e) => warn_log!(format!("{:#}", e)),
+    }
 }
 
-fn ensure_test_directory(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    if !path.exists() {
-        std::fs::create_dir_all(path)?;
-        info_log!(format!("Test directory created: {}", path.display()));
+fn select_libraries(
+    config: &Config,
+    names: &[String],
+) -> Result<Vec<LibraryConfig>, Box<dyn std::error::Error>> {
+    if config.libraries.is_empty() {
+        return Err("No libraries configured".into());
+    }
+
+    Ok(config.ordered_libraries(names)?
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Kills running transfers on Ctrl+C, since they run in their own process
+/// groups and don't receive it. Watchers then stop gracefully; other
+/// commands exit right away.
+fn setup_ctrlc_handler(graceful: bool) -> Result<Arc<AtomicBool>, ctrlc::Error> {
+    let should_exit = Arc::new(AtomicBool::new(false));
+    let flag = should_exit.clone();
+    ctrlc::set_handler(move || {
+        let killed = ChildProcesses::kill_all();
+        if !graceful {
+            std::process::exit(130);
+        }
+        flag.store(true, Ordering::Relaxed);
+        info_log!(format!("Received Ctrl+C, stopped {} transfers, shutting down gracefully...", killed));
+    })?;
+    Ok(should_exit)
+}
+
+/// Syncs every library, so one failing library doesn't stop the others.
+///
+/// Paused libraries are skipped. In maintenance mode, destination writes
+/// are deferred until it ends, as they are when watching.
+fn sync_libraries(libraries: Vec<LibraryConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    let total = libraries.len();
+    let pause = PauseState::load(PauseState::default_path())?;
+    let mut failures = Vec::new();
+    for library in libraries {
+        if pause.is_paused(&library.name) {
+            info_log!(format!("Library '{}' is paused, skipping sync", library.name));
+            continue;
+        }
+        let library = LibrarySync::new(library);
+        match library.sync_with_confirmation(&confirm_deletions) {
+            Ok(report) => info_log!(format!("Library '{}' sync complete: {}", library.name(), report)),
+            Err(e) => {
+                error_log!(format!("Library '{}' sync failed: {:#}", library.name(), e));
+                failures.push(format!("'{}': {:#}", library.name(), e));
+            }
+        }
+    }
+    for (stage, timing) in SpanTimings::snapshot() {
+        debug_log!(format!("Stage '{}': {}", stage, timing));
+    }
+    if !failures.is_empty() {
+        return Err(format!("{} of {} libraries failed to sync: {}", failures.len(), total, failures.join("; ")).into());
     }
     Ok(())
 }
 
-fn configure_watcher(
-    watch_path: &PathBuf,
-    debounce_duration: Duration,
-) -> FileWatcher {
-    let watcher = FileWatcher::new(watch_path, debounce_duration);
-    watcher
+fn confirm_deletions(destination: &str, plan: &SyncPlan) -> bool {
+    let deletions = plan.deletions();
+    println!("Sync to {} would delete {} files:", destination, deletions.len());
+    for action in deletions {
+        println!("  {}", action);
+    }
+    print!("Proceed? [y/N] ");
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
-fn setup_sync_callback(
-    watcher: &mut FileWatcher,
-    watch_path: PathBuf,
-    sync_path: PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let sync_path_clone = sync_path.clone();
-    watcher.set_callback(move |_| {
-        if let Err(e) = sync_directories(&watch_path, &sync_path_clone) {
-            info_log!(format!("Sync failed: {}", e));
-        } else {
-            info_log!(format!(
-                "Synced {} => {} complete!",
-                watch_path.display(),
-                sync_path_clone.display()
-            ));
+fn plan_libraries(libraries: Vec<LibraryConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    for library in libraries {
+        let library = LibrarySync::new(library);
+        for (destination, plan) in library.plan()? {
+            println!("Library '{}' -> {}:\n{}", library.name(), destination, plan);
+            match SyncEstimate::load(library.name(), &destination) {
+                Ok(Some(estimate)) => {
+                    println!("Estimated duration: {} ({})\n", format_duration(estimate.estimate(plan.actions().len())), estimate);
+                }
+                Ok(None) => println!("Estimated duration: unknown, no past runs\n"),
+                Err(e) => println!("Estimated duration: unknown, {:#}\n", e),
+            }
         }
-    });
+    }
+    Ok(())
+}
+
+fn simulate_library(config: &Config, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let [name, scenario] = args else {
+        return Err(USAGE.into());
+    };
+    let library = config.library(name).ok_or_else(|| format!("Unknown library '{}'", name))?;
+    let scenario = Scenario::load(std::path::Path::new(scenario))?;
+
+    let syncs = simulate(library, &scenario, &PauseState::current(), &MaintenanceState::current())?;
+    if syncs.is_empty() {
+        println!("Library '{}': no events, nothing would sync", name);
+    }
+    for sync in syncs {
+        println!("Library '{}' {}\n", name, sync);
+    }
+    Ok(())
+}
+
+fn bench_libraries(libraries: Vec<LibraryConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    for library in libraries {
+        println!("Library '{}' ({}):\n{}\n", library.name, library.source, benchmark_library(&library)?);
+    }
+    Ok(())
+}
+
+async fn validate_strm(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (dir, check_urls) = match args {
+        [dir] => (dir, false),
+        [dir, flag] if flag == "--check-urls" => (dir, true),
+        _ => return Err(USAGE.into()),
+    };
+
+    let report = StrmValidator::new(dir).with_url_check(check_urls).validate().await?;
+    println!("{}", report);
+    if !report.is_valid() {
+        return Err(format!("{} invalid strm files in {}", report.issues.len(), dir).into());
+    }
+    Ok(())
+}
+
+fn import_strm(config: &Config, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (dir, library, normalize) = match args {
+        [dir] => (dir, None, false),
+        [dir, library] => (dir, Some(library), false),
+        [dir, library, flag] if flag == "--normalize" => (dir, Some(library), true),
+        _ => return Err(USAGE.into()),
+    };
+
+    let mut importer = StrmImporter::new(dir).with_normalize(normalize);
+    if let Some(name) = library {
+        let library = config.library(name).ok_or_else(|| format!("Unknown library '{}'", name))?;
+        if let Some(template) = library.strm.as_ref().and_then(|strm| strm.content_template.clone()) {
+            importer = importer.with_template(template);
+        }
+    }
+    println!("{}", importer.import()?);
     Ok(())
 }
 
-fn sync_directories(
-    source: &PathBuf,
-    destination: &PathBuf,
+fn run_doctor(config: &Config, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let doctor = StateDoctor::new(config.state_dir());
+    let report = match args {
+        [] => doctor.check()?,
+        [flag] if flag == "--repair" => doctor.repair()?,
+        _ => return Err(USAGE.into()),
+    };
+    println!("{}", report);
+    if !report.is_healthy() && !report.repaired {
+        return Err("State files are damaged, run 'doctor --repair' to set them aside".into());
+    }
+    Ok(())
+}
+
+fn show_config(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let effective = match args {
+        [command] if command == "show" => false,
+        [command, flag] if command == "show" && flag == "--effective" => true,
+        _ => return Err(USAGE.into()),
+    };
+    let path = Config::config_path();
+    let content = match &path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => String::new(),
+    };
+    match &path {
+        Some(path) if env::var(CONFIG_PATH_ENV).is_ok() => println!("# {} (from {})", path.display(), CONFIG_PATH_ENV),
+        Some(path) => println!("# {}", path.display()),
+        None => println!("# No configuration file, using defaults"),
+    }
+    if effective {
+        print!("{}", EffectiveConfig::from_toml(&content)?);
+    } else {
+        print!("{}", EffectiveConfig::mask_file(&content)?);
+    }
+    Ok(())
+}
+
+fn run_backup(config: &Config, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let archive = match args {
+        [] => BackupArchive::timestamped(config.backup.dir(&config.state_dir()))?,
+        [path] => BackupArchive::new(path),
+        _ => return Err(USAGE.into()),
+    };
+    let summary = archive.create(Config::config_path().as_deref(), &config.state_dir())?;
+    info_log!(format!("Backed up {} to {}", summary, archive.path().display()));
+    Ok(())
+}
+
+fn run_restore(config: &Config, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let [path] = args else {
+        return Err(USAGE.into());
+    };
+    let summary = BackupArchive::new(path).restore(&Config::config_path_or_default(), &config.state_dir())?;
+    info_log!(format!("Restored {} from {}", summary, path));
+    Ok(())
+}
+
+fn set_paused(
+    config: &Config,
+    names: &[String],
+    paused: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let source_owned = source.clone();
-    let dest_owned = destination.clone();
-    let config = DirSyncConfig::builder()
-        .with_source(DirLocation::new(&source.to_string_lossy(), true, None))
-        .with_destination(DirLocation::new(&destination.to_string_lossy(), true, None))
-        .with_strict_mode(false)
-        .with_include_suffixes(vec!["strm"])
-        .with_exclude_suffixes(vec!["txt"]);
-
-    info_log!(format!("Dir sync config: {}", config));
-
-    let mut sync_helper = DirSyncHelper::new(config);
-
-    sync_helper.set_progress_callback(Box::new(move |progress| {
-        info_log!(format!("Sync progress: {}", progress));
-    }));
-
-    sync_helper.set_file_sync_callback(Box::new(move |file| {
-        let message = format!(
-            "{} => {}",
-            source_owned.join(file).display(),
-            dest_owned.join(file).display()
-        );
-        info_log!(message);
-    }));
-
-    sync_helper.sync()?;
+    let library = match names {
+        [] => None,
+        [name] if config.library(name).is_some() => Some(name.as_str()),
+        [name] => return Err(format!("Unknown library '{}'", name).into()),
+        _ => return Err(USAGE.into()),
+    };
+
+    let path = PauseState::default_path();
+    let mut state = PauseState::load(&path)?;
+    if paused {
+        state.pause(library);
+    } else {
+        state.resume(library);
+    }
+    state.save(&path)?;
 
+    let target = library.map_or("All libraries".to_string(), |name| format!("Library '{}'", name));
+    info_log!(format!("{} {}", target, if paused { "paused" } else { "resumed" }));
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_logger();
+fn set_maintenance(
+    config: &Config,
+    names: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let enabled = match names {
+        [mode] if mode == "on" => true,
+        [mode] if mode == "off" => false,
+        _ => return Err(USAGE.into()),
+    };
 
-    let watch_path = PathHelper::expand_tilde(
-        PathBuf::from("~/Downloads/Tests")
-    );
-    let sync_path = PathHelper::expand_tilde(
-        PathBuf::from("~/Downloads/Sync_Tests")
-    );
+    let path = MaintenanceState::default_path();
+    MaintenanceState::set_enabled_persisted(&path, enabled)?;
+
+    if enabled {
+        info_log!("Maintenance mode enabled, destination writes are deferred");
+        return Ok(());
+    }
+    info_log!("Maintenance mode disabled, flushing deferred syncs");
+    flush_pending(config, &path)
+}
+
+fn flush_pending(
+    config: &Config,
+    path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pending = MaintenanceState::load(path)?.pending_libraries();
+    let known: Vec<String> = pending.into_iter()
+        .filter(|name| config.library(name).is_some())
+        .collect();
+    if known.is_empty() {
+        return Ok(());
+    }
+
+    let mut failed = Vec::new();
+    for library in config.ordered_libraries(&known)? {
+        let library = LibrarySync::new(library.clone());
+        match library.sync() {
+            Ok(_) => {
+                MaintenanceState::complete_persisted(path, library.name())?;
+                info_log!(format!("Deferred sync of library '{}' complete!", library.name()));
+            }
+            Err(e) => {
+                error_log!(format!("Deferred sync of library '{}' failed: {}", library.name(), e));
+                failed.push(library.name().to_string());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Deferred syncs failed for: {}", failed.join(", ")).into())
+    }
+}
+
+fn send_digest(config: &Config, period: DigestPeriod) -> Result<(), Box<dyn std::error::Error>> {
+    let digest = Digest::collect(period)?;
 
-    ensure_test_directory(&watch_path)?;
-    
-    let mut watcher = configure_watcher(
-        &watch_path,
-        Duration::from_secs(5)
+    if let Some(dir) = &config.notification.digest_report_dir {
+        let path = digest.write_report(&PathHelper::expand_tilde(dir))?;
+        info_log!(format!("Digest report written to {}", path.display()));
+    }
+
+    match Notifier::from_config(config) {
+        Some(notifier) => {
+            let vars = digest.vars();
+            let vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (*k, v.as_str())).collect();
+            notifier.notify(NotificationKind::Digest, &vars)?;
+        }
+        None => println!("{}", digest.to_markdown()),
+    }
+    Ok(())
+}
+
+fn run_digest(config: &Config, names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let period = match names {
+        [] => config.notification.digest.unwrap_or(DigestPeriod::Daily),
+        [period] if period == "daily" => DigestPeriod::Daily,
+        [period] if period == "weekly" => DigestPeriod::Weekly,
+        _ => return Err(USAGE.into()),
+    };
+    send_digest(config, period)
+}
+
+fn schedule_digest(config: &Config) {
+    let Some(period) = config.notification.digest else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period.duration());
+        // The first tick completes immediately, and a digest at startup would be empty
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let sent = tokio::task::spawn_blocking(move || {
+                send_digest(&Config::get(), period).map_err(|e| e.to_string())
+            }).await;
+            if let Ok(Err(e)) = sent {
+                error_log!(format!("Failed to send {} digest: {}", period, e));
+            }
+        }
+    });
+}
+
+fn schedule_notification_retries(config: &Config) {
+    if Notifier::from_config(config).is_none() {
+        return;
+    }
+
+    #[cfg(feature = "otlp")]
+    OtlpExporter::observe_gauge(
+        pilipili_strm::core::notification::NOTIFICATION_QUEUE_DEPTH_METRIC,
+        "Notifications waiting to be delivered",
+        || NotificationQueue::new(NotificationQueue::default_path()).depth() as u64,
     );
 
-    setup_sync_callback(&mut watcher, watch_path.clone(), sync_path.clone())?;
-    watcher.resume()?;
-    info_log!(format!("Syncing path: {}", sync_path.display()));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(NOTIFICATION_QUEUE_RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            if NotificationQueue::new(NotificationQueue::default_path()).depth() == 0 {
+                continue;
+            }
+            match tokio::task::spawn_blocking(Notifier::retry).await {
+                Ok(Ok(report)) => debug_log!(format!("Retried queued notifications: {}", report)),
+                Ok(Err(e)) => error_log!(format!("Failed to retry queued notifications: {:#}", e)),
+                Err(e) => error_log!(format!("Notification retry task failed: {}", e)),
+            }
+        }
+    });
+}
+
+fn run_health_checks(monitor: &mut HealthMonitor, checks: &[HealthCheck]) {
+    let notifier = Notifier::from_config(&Config::get());
+    for (check, transition) in monitor.run(checks) {
+        let sent = match transition {
+            HealthTransition::Failed { error } => {
+                error_log!(format!("Health check '{}' failed: {}", check, error));
+                notifier.as_ref().map(|notifier| {
+                    notifier.notify(NotificationKind::HealthCheckFailed, &[("check", &check), ("error", &error)])
+                })
+            }
+            HealthTransition::Recovered => {
+                info_log!(format!("Health check '{}' recovered", check));
+                notifier.as_ref().map(|notifier| {
+                    notifier.notify(NotificationKind::HealthCheckRecovered, &[("check", &check)])
+                })
+            }
+            HealthTransition::None => None,
+        };
+        if let Some(Err(e)) = sent {
+            warn_log!(format!("Failed to send health notification: {:#}", e));
+        }
+    }
+}
+
+fn schedule_health_checks(config: &Config) {
+    let Some(period) = config.health.interval() else {
+        return;
+    };
+    let checks = match HealthCheck::from_config(config) {
+        Ok(checks) if !checks.is_empty() => checks,
+        Ok(_) => return,
+        Err(e) => {
+            error_log!(format!("Failed to set up health checks: {:#}", e));
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        let mut monitor = HealthMonitor::new();
+        loop {
+            interval.tick().await;
+            let checks = checks.clone();
+            match tokio::task::spawn_blocking(move || {
+                run_health_checks(&mut monitor, &checks);
+                monitor
+            }).await {
+                Ok(checked) => monitor = checked,
+                Err(_) => {
+                    error_log!("Health check task panicked");
+                    monitor = HealthMonitor::new();
+                }
+            }
+        }
+    });
+}
+
+/// Writes a scheduled backup and deletes the oldest ones.
+fn write_scheduled_backup() -> Result<(BackupArchive, BackupSummary), anyhow::Error> {
+    let config = Config::get();
+    let dir = config.backup.dir(&config.state_dir());
+    let archive = BackupArchive::timestamped(&dir)?;
+    let summary = archive.create(Config::config_path().as_deref(), &config.state_dir())?;
+    for removed in BackupArchive::rotate(&dir, config.backup.keep.max(1))? {
+        debug_log!(format!("Deleted old backup {}", removed.display()));
+    }
+    Ok((archive, summary))
+}
+
+fn schedule_backups(config: &Config) {
+    let Some(period) = config.backup.interval() else {
+        return;
+    };
 
-    watcher.setup_ctrlc_handler()?;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // The first tick completes immediately, and restarts shouldn't each write a backup
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let (archive, summary) = match tokio::task::spawn_blocking(write_scheduled_backup).await {
+                Ok(Ok(backup)) => backup,
+                Ok(Err(e)) => {
+                    error_log!(format!("Failed to write scheduled backup: {:#}", e));
+                    continue;
+                }
+                Err(e) => {
+                    error_log!(format!("Scheduled backup task failed: {}", e));
+                    continue;
+                }
+            };
+            info_log!(format!("Backed up {} to {}", summary, archive.path().display()));
+
+            if !Config::get().backup.send_to_telegram {
+                continue;
+            }
+            let document = DocumentMessage::from_file(archive.path())
+                .with_caption(MarkdownV2Builder::escape(&format!("Backup: {}", summary)));
+            match TelegramClient::builder().build().send_document(document).await {
+                Ok(response) if response.ok => {}
+                Ok(response) => error_log!(format!("Failed to send backup to Telegram: {}", response)),
+                Err(e) => error_log!(format!("Failed to send backup to Telegram: {:#}", e)),
+            }
+        }
+    });
+}
+
+async fn watch_libraries(
+    config: &Config,
+    libraries: Vec<LibraryConfig>,
+    should_exit: Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut watchers = Vec::new();
+    for library in libraries {
+        let name = library.name.clone();
+        match LibrarySync::new(library).watch() {
+            Ok(watcher) => watchers.push(watcher),
+            Err(e) => error_log!(format!("Failed to watch library '{}': {}", name, e)),
+        }
+    }
+
+    if watchers.is_empty() {
+        return Err("No library could be watched".into());
+    }
+
+    if config.observer {
+        warn_log!("Observer mode: changes are planned and reported, nothing is written to destinations");
+    }
+    schedule_digest(config);
+    schedule_notification_retries(config);
+    schedule_health_checks(config);
+    schedule_backups(config);
     info_log!("Press Ctrl+C to stop watching...");
 
-    while !watcher.get_should_exit() {
+    while !should_exit.load(Ordering::Relaxed) {
         tokio::time::sleep(Duration::from_secs(1)).await;
     }
 
-    watcher.stop();
-    info_log!("Watcher stopped gracefully");
+    for watcher in watchers.iter_mut() {
+        watcher.stop();
+    }
+    info_log!("Watchers stopped gracefully");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    init_logger();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = Config::get();
+    install_panic_hook(&config);
+    #[cfg(feature = "chaos")]
+    pilipili_strm::infrastructure::chaos::FaultInjector::configure(config.chaos.clone());
+    raise_fd_limit();
+
+    let names = args.get(1..).unwrap_or_default();
+    let watching = matches!(args.first().map(String::as_str), None | Some("watch"));
+    let should_exit = setup_ctrlc_handler(watching)?;
+
+    let result = match args.first().map(String::as_str) {
+        None | Some("watch") => watch_libraries(&config, select_libraries(&config, names)?, should_exit).await,
+        Some("sync") => sync_libraries(select_libraries(&config, names)?),
+        Some("plan") => plan_libraries(select_libraries(&config, names)?),
+        Some("pause") => set_paused(&config, names, true),
+        Some("resume") => set_paused(&config, names, false),
+        Some("maintenance") => set_maintenance(&config, names),
+        Some("digest") => run_digest(&config, names),
+        Some("simulate") => simulate_library(&config, names),
+        Some("bench") => bench_libraries(select_libraries(&config, names)?),
+        Some("validate") => validate_strm(names).await,
+        Some("import") => import_strm(&config, names),
+        Some("doctor") => run_doctor(&config, names),
+        Some("backup") => run_backup(&config, names),
+        Some("restore") => run_restore(&config, names),
+        Some("config") => show_config(names),
+        Some(_) => Err(USAGE.into()),
+    };
+
+    // Transfers of syncs still running when the watchers stopped
+    ChildProcesses::kill_all();
+    // Refreshes still waiting for their destination to settle
+    EmbyRefreshQueue::flush();
+    #[cfg(feature = "otlp")]
+    OtlpExporter::shutdown();
+    result
+}
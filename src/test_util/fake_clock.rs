@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use tokio::time::{self, Instant};
+
+/// Virtual clock for tokio timers.
+///
+/// Pauses tokio's time source, so sleeps and debounce windows only pass
+/// when the test advances the clock. Must be used on a current-thread
+/// runtime, e.g. `#[tokio::test]`.
+#[derive(Debug)]
+pub struct FakeClock {
+
+    /// Virtual time at which the clock was started
+    started: Instant,
+}
+
+impl FakeClock {
+
+    /// Pauses tokio time and starts the clock at the current instant.
+    ///
+    /// # Panics
+    /// If called outside a current-thread runtime, or twice on one runtime.
+    pub fn start() -> Self {
+        time::pause();
+        Self { started: Instant::now() }
+    }
+
+    /// Moves the clock forward, firing every timer that becomes due.
+    pub async fn advance(&self, duration: Duration) {
+        time::advance(duration).await;
+        // Let tasks woken by the timers run before returning
+        tokio::task::yield_now().await;
+    }
+
+    /// Moves the clock forward to `elapsed` since the start, if it isn't past it already.
+    pub async fn advance_to(&self, elapsed: Duration) {
+        if let Some(remaining) = elapsed.checked_sub(self.elapsed()) {
+            self.advance(remaining).await;
+        }
+    }
+
+    /// Returns the virtual time passed since the clock was started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
@@ -0,0 +1,16 @@
+//! Deterministic simulation helpers for tests (`test-util` feature).
+//!
+//! This module makes the watcher and sync pipeline testable without real
+//! sleeps or external binaries, with:
+//! - Scripted virtual libraries of shows, episodes and sidecar files
+//! - Timed file arrivals replayed as watcher events
+//! - A fake clock driving tokio timers
+//! - A recording sync executor with scriptable failures
+//! 
+pub mod fake_clock;
+pub mod recording_strategy;
+pub mod virtual_library;
+
+pub use fake_clock::*;
+pub use recording_strategy::*;
+pub use virtual_library::*;
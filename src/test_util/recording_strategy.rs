@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex
+};
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::{
+    core::{
+        config::{DestinationConfig, LibraryConfig},
+        library::{ConfirmCallback, SyncExecutor, SyncStrategy}
+    },
+    infrastructure::fs::{ProgressSender, SyncReport}
+};
+
+/// A transfer requested from a [`RecordingSyncStrategy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedSync {
+
+    /// Name of the synchronized library
+    pub library: String,
+
+    /// Destination path
+    pub destination: String,
+
+    /// Strategy the pipeline selected for the destination
+    pub strategy: SyncStrategy,
+}
+
+/// In-memory sync executor that records every transfer instead of running it.
+///
+/// Destinations can be scripted to fail, so the orchestration in
+/// [`crate::core::library::LibrarySync`] can be tested without rsync or a
+/// network. Use it with `LibrarySync::with_executor`.
+#[derive(Debug, Default)]
+pub struct RecordingSyncStrategy {
+
+    /// Transfers requested so far, in order
+    calls: Mutex<Vec<RecordedSync>>,
+
+    /// Error messages returned for specific destinations
+    failures: Mutex<HashMap<String, String>>,
+
+    /// Paths reported as changed by every successful transfer
+    changed_paths: Vec<String>,
+}
+
+impl RecordingSyncStrategy {
+
+    /// Creates a recorder where every transfer succeeds without changes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the paths reported as changed by every successful transfer.
+    pub fn with_changed_paths(mut self, paths: &[&str]) -> Self {
+        self.changed_paths = paths.iter().map(|path| path.to_string()).collect();
+        self
+    }
+
+    /// Makes transfers into `destination` fail with `message`.
+    pub fn fail_destination(&self, destination: &str, message: &str) {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(destination.to_string(), message.to_string());
+    }
+
+    /// Lets transfers into `destination` succeed again.
+    pub fn recover_destination(&self, destination: &str) {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(destination);
+    }
+
+    /// Returns the transfers requested so far, in order.
+    pub fn calls(&self) -> Vec<RecordedSync> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl SyncExecutor for RecordingSyncStrategy {
+
+    fn sync_destination(
+        &self,
+        config: &LibraryConfig,
+        destination: &DestinationConfig,
+        strategy: SyncStrategy,
+        _confirm: &ConfirmCallback,
+        _progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport, Error> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).push(RecordedSync {
+            library: config.name.clone(),
+            destination: destination.path.clone(),
+            strategy,
+        });
+
+        match self.failures.lock().unwrap_or_else(|e| e.into_inner()).get(&destination.path) {
+            Some(message) => Err(anyhow!("{}", message)),
+            None => Ok(SyncReport::from_changed_paths(self.changed_paths.clone())),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration
+};
+
+use anyhow::{Error, Result};
+use notify::{event::CreateKind, Event, EventKind};
+use tokio::sync::mpsc::Sender;
+
+use super::fake_clock::FakeClock;
+
+/// Sidecar extensions that may accompany an episode.
+const SIDECAR_EXTENSIONS: [&str; 3] = ["nfo", "srt", "jpg"];
+
+/// A file of a virtual library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualFile {
+
+    /// Path relative to the library root
+    pub path: PathBuf,
+
+    /// Size of the generated content in bytes
+    pub size: usize,
+
+    /// Time after the start of the simulation at which the file appears
+    pub arrives_after: Duration,
+}
+
+/// A generated library of shows, laid out as `<Show>/Season 01/<Episode>`.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualLibrary {
+
+    /// Files ordered by arrival time
+    files: Vec<VirtualFile>,
+}
+
+impl VirtualLibrary {
+
+    /// Creates a builder for a virtual library.
+    pub fn builder() -> VirtualLibraryBuilder {
+        VirtualLibraryBuilder::default()
+    }
+
+    /// Returns every file, ordered by arrival time.
+    pub fn files(&self) -> &[VirtualFile] {
+        &self.files
+    }
+
+    /// Returns the files that arrived within `elapsed` since the start.
+    pub fn arrived_by(&self, elapsed: Duration) -> impl Iterator<Item = &VirtualFile> {
+        self.files.iter().filter(move |file| file.arrives_after <= elapsed)
+    }
+
+    /// Writes every file below `root` at once, ignoring arrival times.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file or directory can't be created.
+    pub fn write_to(&self, root: &Path) -> Result<(), Error> {
+        self.files.iter().try_for_each(|file| Self::write_file(root, file))
+    }
+
+    /// Replays the arrivals as watcher events on the fake clock.
+    ///
+    /// Arrival times count from the moment `play` is called. Advances the
+    /// clock to each arrival and sends a file creation event for it. Files
+    /// are only written when `root` is set, since real writes also produce
+    /// real, unscripted watcher events.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if a file can't be written or the event
+    /// channel is closed.
+    pub async fn play(
+        &self,
+        clock: &FakeClock,
+        events: &Sender<Event>,
+        root: Option<&Path>,
+    ) -> Result<(), Error> {
+        let start = clock.elapsed();
+        for file in &self.files {
+            clock.advance_to(start + file.arrives_after).await;
+            if let Some(root) = root {
+                Self::write_file(root, file)?;
+            }
+            let path = root.unwrap_or(Path::new("")).join(&file.path);
+            events
+                .send(Event::new(EventKind::Create(CreateKind::File)).add_path(path))
+                .await?;
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Writes a single file with generated content.
+    fn write_file(root: &Path, file: &VirtualFile) -> Result<(), Error> {
+        let path = root.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, vec![b'x'; file.size])?;
+        Ok(())
+    }
+}
+
+/// Builder for [`VirtualLibrary`].
+///
+/// Generation is seeded, so the same settings always produce the same
+/// library.
+#[derive(Debug, Clone)]
+pub struct VirtualLibraryBuilder {
+
+    /// Number of shows
+    shows: usize,
+
+    /// Number of episodes per show
+    episodes: usize,
+
+    /// Chance from 0.0 to 1.0 that an episode gets each sidecar file
+    sidecar_chance: f64,
+
+    /// Time between two episode arrivals
+    arrival_interval: Duration,
+
+    /// Seed of the pseudo-random generator
+    seed: u64,
+
+    /// Extension of episode files
+    extension: String,
+}
+
+impl Default for VirtualLibraryBuilder {
+
+    fn default() -> Self {
+        Self {
+            shows: 1,
+            episodes: 1,
+            sidecar_chance: 0.0,
+            arrival_interval: Duration::ZERO,
+            seed: 1,
+            extension: "strm".to_string(),
+        }
+    }
+}
+
+impl VirtualLibraryBuilder {
+
+    /// Sets the number of shows.
+    pub fn with_shows(mut self, shows: usize) -> Self {
+        self.shows = shows;
+        self
+    }
+
+    /// Sets the number of episodes per show.
+    pub fn with_episodes(mut self, episodes: usize) -> Self {
+        self.episodes = episodes;
+        self
+    }
+
+    /// Sets the chance, from 0.0 to 1.0, that an episode gets each sidecar file.
+    pub fn with_sidecar_chance(mut self, chance: f64) -> Self {
+        self.sidecar_chance = chance.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the time between two episode arrivals.
+    ///
+    /// Sidecars arrive together with their episode.
+    pub fn with_arrival_interval(mut self, interval: Duration) -> Self {
+        self.arrival_interval = interval;
+        self
+    }
+
+    /// Sets the seed of the pseudo-random generator.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the extension of episode files (`strm` by default).
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        self.extension = extension.trim_start_matches('.').to_string();
+        self
+    }
+
+    /// Generates the library.
+    pub fn build(self) -> VirtualLibrary {
+        let mut rng = XorShift::new(self.seed);
+        let mut files = Vec::new();
+        let mut arrival = Duration::ZERO;
+
+        for show in 1..=self.shows {
+            let show_dir = PathBuf::from(format!("Show {:02}", show)).join("Season 01");
+            for episode in 1..=self.episodes {
+                let stem = format!("Show {:02} S01E{:02}", show, episode);
+                files.push(VirtualFile {
+                    path: show_dir.join(format!("{}.{}", stem, self.extension)),
+                    size: 64 + rng.below(960),
+                    arrives_after: arrival,
+                });
+                for extension in SIDECAR_EXTENSIONS {
+                    if rng.chance(self.sidecar_chance) {
+                        files.push(VirtualFile {
+                            path: show_dir.join(format!("{}.{}", stem, extension)),
+                            size: 16 + rng.below(240),
+                            arrives_after: arrival,
+                        });
+                    }
+                }
+                arrival += self.arrival_interval;
+            }
+        }
+
+        VirtualLibrary { files }
+    }
+}
+
+/// Small deterministic pseudo-random generator (xorshift64).
+#[derive(Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+
+    /// Creates a generator; a zero seed is replaced, as xorshift can't leave zero.
+    fn new(seed: u64) -> Self {
+        Self(if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed })
+    }
+
+    /// Returns the next pseudo-random number.
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound.max(1) as u64) as usize
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use pilipili_strm::core::client::ArtworkCache;
+
+    #[tokio::test]
+    async fn test_artwork_cache_downloads_once() {
+        let mut server = mockito::Server::new_async().await;
+        let poster = server
+            .mock("GET", "/t/p/w500/poster.jpg")
+            .with_body("poster-bytes")
+            .expect(1)
+            .create_async()
+            .await;
+        let dir = tempdir().unwrap();
+        let url = format!("{}/t/p/w500/poster.jpg", server.url());
+
+        let mut cache = ArtworkCache::open(dir.path()).unwrap();
+        let first = cache.fetch(&url).await.unwrap();
+        assert_eq!(fs::read(&first).unwrap(), b"poster-bytes");
+
+        // Reopening keeps the index, so the second episode reuses the poster
+        let mut cache = ArtworkCache::open(dir.path()).unwrap();
+        assert_eq!(cache.fetch(&url).await.unwrap(), first);
+        poster.assert_async().await;
+
+        // The same content under another URL is stored once
+        let mirrored = cache.insert("https://emby/Items/1/Images/Primary", b"poster-bytes").unwrap();
+        assert_eq!(mirrored, first);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_artwork_cache_evicts_least_recently_used() {
+        let dir = tempdir().unwrap();
+        let mut cache = ArtworkCache::open(dir.path()).unwrap().with_max_bytes(10);
+
+        let a = cache.insert("https://tmdb/a.jpg", b"aaaa").unwrap();
+        cache.insert("https://tmdb/b.jpg", b"bbbb").unwrap();
+        assert!(cache.get("https://tmdb/a.jpg").is_some());
+        cache.insert("https://tmdb/c.jpg", b"cccc").unwrap();
+
+        assert!(cache.get("https://tmdb/b.jpg").is_none());
+        assert_eq!(cache.get("https://tmdb/a.jpg"), Some(a));
+        assert!(cache.get("https://tmdb/c.jpg").is_some());
+        assert_eq!(cache.total_bytes(), 8);
+
+        // A single image above the limit is still kept until the next insert
+        cache.insert("https://tmdb/big.jpg", b"0123456789ab").unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_artwork_cache_merges_concurrent_indexes() {
+        let dir = tempdir().unwrap();
+        let mut first = ArtworkCache::open(dir.path()).unwrap();
+        let mut second = ArtworkCache::open(dir.path()).unwrap();
+
+        first.insert("https://tmdb/a.jpg", b"aaaa").unwrap();
+        second.insert("https://tmdb/b.jpg", b"bbbb").unwrap();
+
+        let mut cache = ArtworkCache::open(dir.path()).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("https://tmdb/a.jpg").is_some());
+        assert!(cache.get("https://tmdb/b.jpg").is_some());
+    }
+}
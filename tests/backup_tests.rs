@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use pilipili_strm::core::backup::*;
+
+    #[test]
+    fn test_backup_create_and_restore() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        let state = dir.path().join("state");
+        fs::create_dir_all(state.join("listings")).unwrap();
+        fs::create_dir_all(state.join("artwork")).unwrap();
+        fs::write(&config, "state_dir = \"/var/lib/pilipili\"\n").unwrap();
+        fs::write(state.join("pause.json"), r#"{"global": true}"#).unwrap();
+        fs::write(state.join("history.jsonl"), "{}\n").unwrap();
+        fs::write(state.join("listings/anime.cache"), "cache").unwrap();
+        fs::write(state.join("artwork/index.json"), "{}").unwrap();
+
+        let archive = BackupArchive::new(state.join("backups/manual.tar.zst"));
+        let summary = archive.create(Some(&config), &state).unwrap();
+        assert!(summary.config);
+        assert_eq!(summary.state_files, 3);
+        assert!(summary.bytes > 0);
+
+        let restored = tempdir().unwrap();
+        let restored_config = restored.path().join("etc/config.toml");
+        let restored_state = restored.path().join("state");
+        let summary = archive.restore(&restored_config, &restored_state).unwrap();
+        assert!(summary.config);
+        assert_eq!(summary.state_files, 3);
+        assert_eq!(fs::read_to_string(restored_config).unwrap(), "state_dir = \"/var/lib/pilipili\"\n");
+        assert_eq!(fs::read_to_string(restored_state.join("pause.json")).unwrap(), r#"{"global": true}"#);
+        assert_eq!(fs::read_to_string(restored_state.join("listings/anime.cache")).unwrap(), "cache");
+        assert!(!restored_state.join("artwork").exists());
+        assert!(!restored_state.join("backups").exists());
+    }
+
+    #[test]
+    fn test_backup_rotation() {
+        let dir = tempdir().unwrap();
+        for stamp in ["20250101-000000", "20250102-000000", "20250103-000000"] {
+            fs::write(dir.path().join(format!("pilipili_strm-{}.tar.zst", stamp)), "").unwrap();
+        }
+        fs::write(dir.path().join("manual.tar.zst"), "").unwrap();
+
+        let removed = BackupArchive::rotate(dir.path(), 2).unwrap();
+        assert_eq!(removed, vec![dir.path().join("pilipili_strm-20250101-000000.tar.zst")]);
+        assert!(dir.path().join("pilipili_strm-20250103-000000.tar.zst").exists());
+        assert!(dir.path().join("manual.tar.zst").exists());
+
+        let archive = BackupArchive::timestamped(dir.path()).unwrap();
+        assert!(archive.path().to_string_lossy().ends_with(".tar.zst"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_backup_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        fs::write(&config, "").unwrap();
+
+        let archive = BackupArchive::new(dir.path().join("backup.tar.zst"));
+        archive.create(Some(&config), &dir.path().join("state")).unwrap();
+        let mode = fs::metadata(archive.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_backup_restore_skips_linked_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("linked.tar.zst");
+        let mut builder = tar::Builder::new(zstd::Encoder::new(fs::File::create(&path).unwrap(), 3).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "config.toml", "/etc/passwd").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let restored = tempdir().unwrap();
+        let restored_config = restored.path().join("config.toml");
+        let summary = BackupArchive::new(&path).restore(&restored_config, &restored.path().join("state")).unwrap();
+        assert!(!summary.config);
+        assert!(fs::symlink_metadata(restored_config).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "chaos")]
+
+#[cfg(test)]
+mod tests {
+
+    use pilipili_strm::{
+        core::config::Config,
+        infrastructure::chaos::*
+    };
+
+    #[test]
+    fn test_fault_injector_applies_configured_rates() {
+        let config = Config::from_toml(
+            "[chaos]\nstrategy_error_rate = 1.0\nslow_io_rate = 1.0\nslow_io_delay_ms = 1\ndropped_event_rate = 1.0\n"
+        ).unwrap();
+        assert!(!config.chaos.is_disabled());
+        assert!(Config::from_toml("[chaos]\ndropped_event_rate = 1.5\n").is_err());
+
+        FaultInjector::configure(config.chaos.clone());
+        assert_eq!(FaultInjector::rates(), config.chaos);
+        let error = FaultInjector::before_sync("/mnt/media").unwrap_err();
+        assert!(error.to_string().contains("/mnt/media"));
+        assert!(FaultInjector::drop_event());
+
+        FaultInjector::configure(FaultRates::default());
+        assert!(FaultInjector::before_sync("/mnt/media").is_ok());
+        assert!(!FaultInjector::drop_event());
+    }
+}
@@ -0,0 +1,723 @@
+#[cfg(test)]
+mod tests {
+
+    use std::{path::Path, time::Duration};
+
+    use pilipili_strm::{
+        core::{
+            config::*,
+            library::SyncStrategy,
+            strm::SwapMode
+        },
+        infrastructure::fs::{ByteSize, IoClass, MediaKind, MetadataPolicy, PathHelper, WatchEventKind}
+    };
+
+    #[test]
+    fn test_parse_partial_config() {
+        let config = Config::from_toml(r#"
+            [telegram]
+            bot_token = "123:abc"
+            chat_id = "42"
+        "#).unwrap();
+
+        assert_eq!(config.telegram.bot_token, "123:abc");
+        assert_eq!(config.telegram.chat_id, "42");
+        assert!(config.emby.base_url.is_empty());
+    }
+
+    #[test]
+    fn test_telegram_default_api_base() {
+        let config = Config::from_toml("").unwrap();
+
+        assert_eq!(config.telegram.api_base(), TELEGRAM_DEFAULT_API_BASE);
+        assert!(!config.telegram.is_local());
+    }
+
+    #[test]
+    fn test_telegram_local_api_base() {
+        let config = Config::from_toml(r#"
+            [telegram]
+            api_base = "http://127.0.0.1:8081/"
+            local_mode = true
+        "#).unwrap();
+
+        assert_eq!(config.telegram.api_base(), "http://127.0.0.1:8081");
+        assert!(config.telegram.is_local());
+    }
+
+    #[test]
+    fn test_telegram_local_mode_requires_api_base() {
+        let config = Config::from_toml(r#"
+            [telegram]
+            local_mode = true
+        "#).unwrap();
+
+        assert!(!config.telegram.is_local());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Config::from_toml("[telegram]\nbot_token = 1").is_err());
+    }
+
+    #[test]
+    fn test_apply_toml() {
+        Config::apply_toml(r#"
+            [emby]
+            base_url = "http://127.0.0.1:8096"
+        "#).unwrap();
+        assert_eq!(Config::get().emby.base_url, "http://127.0.0.1:8096");
+        assert!(Config::get().emby.refresh_settle().is_zero(), "Refreshes aren't delayed unless configured");
+
+        assert!(Config::apply_toml("[emby]\nbase_url = 1").is_err());
+        assert_eq!(Config::get().emby.base_url, "http://127.0.0.1:8096");
+    }
+
+    #[test]
+    fn test_telegram_authorize() {
+        let config = Config::from_toml(r#"
+            [[telegram.users]]
+            id = 1
+            role = "admin"
+
+            [[telegram.users]]
+            id = 2
+            role = "viewer"
+        "#).unwrap();
+        let telegram = &config.telegram;
+
+        assert_eq!(telegram.role_of(1), Some(TelegramRole::Admin));
+        assert!(telegram.authorize(1, TelegramRole::Admin));
+        assert!(telegram.authorize(1, TelegramRole::Viewer));
+        assert!(!telegram.authorize(2, TelegramRole::Admin));
+        assert!(telegram.authorize(2, TelegramRole::Viewer));
+        assert!(!telegram.authorize(3, TelegramRole::Viewer));
+    }
+
+    #[test]
+    fn test_telegram_invalid_role() {
+        let result = Config::from_toml(r#"
+            [[telegram.users]]
+            id = 1
+            role = "owner"
+        "#);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_libraries() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/mnt/media/movies"
+            include_suffixes = ["strm", ".nfo"]
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+
+            [[libraries.destinations]]
+            path = "/data/movies"
+            ssh = { ip = "10.0.0.2", username = "media", key_path = "~/.ssh/id_ed25519" }
+
+            [[libraries]]
+            name = "anime"
+            source = "/mnt/media/anime"
+            debounce_secs = 30
+            fallback_poll_secs = 60
+        "#).unwrap();
+
+        let movies = config.library("movies").unwrap();
+        assert_eq!(movies.debounce_secs, 5);
+        assert_eq!(movies.fallback_poll_interval(), Duration::from_secs(300));
+        assert_eq!(movies.stall_timeout_secs, 0, "The stall watchdog is opt-in");
+        assert_eq!(config.library("anime").unwrap().fallback_poll_secs, 60);
+
+        let sync_configs = movies.to_dir_sync_configs().unwrap();
+        assert_eq!(sync_configs.len(), 2);
+        assert!(sync_configs[0].get_stall_timeout().is_none());
+        assert_eq!(sync_configs[0].get_destination().get_path(), "/srv/emby/movies/");
+        assert_eq!(sync_configs[1].get_destination().get_path(), "media@10.0.0.2:/data/movies/");
+        assert_eq!(sync_configs[0].get_include_suffixes(), vec!["strm", "nfo"]);
+
+        let anime = config.library("anime").unwrap();
+        assert_eq!(anime.debounce_time().as_secs(), 30);
+        assert!(anime.to_dir_sync_configs().is_err(), "Library without destinations should fail");
+        assert!(config.library("music").is_none());
+    }
+
+    #[test]
+    fn test_library_confirmation_threshold() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/a"
+            strict_mode = true
+            confirm_deletions_above = 10
+
+            [[libraries]]
+            name = "anime"
+            source = "/b"
+            confirm_deletions_above = 0
+        "#).unwrap();
+
+        let movies = config.library("movies").unwrap();
+        assert!(!movies.requires_confirmation(10));
+        assert!(movies.requires_confirmation(11));
+
+        let anime = config.library("anime").unwrap();
+        assert!(!anime.requires_confirmation(100), "Only strict mode deletes files");
+    }
+
+    #[test]
+    fn test_library_http_destinations() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/mnt/media/movies"
+            include_suffixes = ["strm"]
+            exclude_regex = "^extras/"
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+
+            [[libraries.destinations]]
+            path = "https://media.example.com/upload"
+            authorization = "Bearer t0ken"
+        "#).unwrap();
+
+        let movies = config.library("movies").unwrap();
+        assert_eq!(movies.to_dir_sync_configs().unwrap().len(), 1);
+
+        let strategies = movies.destination_strategies().unwrap();
+        assert_eq!(strategies[0].1, SyncStrategy::Rsync);
+        assert_eq!(strategies[1].1, SyncStrategy::HttpUpload);
+        assert_eq!(strategies[1].0.to_upload_endpoint().authorization.as_deref(), Some("Bearer t0ken"));
+
+        assert!(movies.matches_filters(Path::new("show/ep1.strm")));
+        assert!(!movies.matches_filters(Path::new("show/ep1.nfo")));
+        assert!(!movies.matches_filters(Path::new("extras/trailer.strm")));
+
+        let invalid = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/mnt/media/movies"
+            exclude_regex = "(extras"
+        "#);
+        assert!(invalid.unwrap_err().to_string().contains("invalid exclude_regex"));
+    }
+
+    #[test]
+    fn test_library_native_strategy() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/mnt/media/movies"
+            min_free_space = "10GB"
+            bandwidth_limit = "2MB"
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+            strategy = "native"
+            temp_dir = "/srv/.staging"
+
+            [[libraries.destinations]]
+            path = "/srv/backup/movies"
+            strategy = "native"
+            bandwidth_limit = "512KiB"
+
+            [[libraries]]
+            name = "anime"
+            source = "/mnt/media/anime"
+
+            [[libraries.destinations]]
+            path = "root@nas:/srv/emby/anime"
+            strategy = "native"
+
+            [[libraries]]
+            name = "shows"
+            source = "/mnt/media/shows"
+
+            [[libraries.destinations]]
+            path = '\\nas\emby\shows'
+            strategy = "robocopy"
+
+            [[libraries.destinations]]
+            path = "rsync://nas/shows"
+            strategy = "robocopy"
+        "#).unwrap();
+
+        let movies = config.library("movies").unwrap();
+        assert_eq!(movies.destination_strategies().unwrap()[0].1, SyncStrategy::Native);
+        let sync_config = movies.to_dir_sync_config(&movies.destinations[0]).unwrap();
+        assert_eq!(sync_config.get_temp_dir().as_deref(), Some("/srv/.staging"));
+        assert_eq!(sync_config.get_min_free_space().map(|size| size.bytes()), Some(10_000_000_000));
+        assert_eq!(sync_config.get_bandwidth_limit(), Some(ByteSize(2_000_000)));
+        let backup_config = movies.to_dir_sync_config(&movies.destinations[1]).unwrap();
+        assert_eq!(backup_config.get_bandwidth_limit(), Some(ByteSize(512 * 1024)), "Destinations override the library");
+        assert!(movies.to_dir_sync_configs().unwrap().is_empty());
+        assert!(config.library("anime").unwrap().destination_strategies().is_err());
+        let shows = config.library("shows").unwrap();
+        assert_eq!(shows.destinations[0].strategy().unwrap(), SyncStrategy::Robocopy);
+        assert!(shows.destinations[1].strategy().is_err());
+    }
+
+    #[test]
+    fn test_library_s3_destination() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/mnt/media/movies"
+
+            [libraries.strm]
+            target = "/srv/strm/movies"
+
+            [[libraries.destinations]]
+            path = "s3://media/strm/movies/"
+
+            [libraries.destinations.s3]
+            endpoint = "http://minio:9000"
+            access_key_id = "minio"
+            secret_access_key = "minio-secret"
+
+            [[libraries.destinations]]
+            path = "s3:///movies"
+
+            [libraries.destinations.s3]
+            access_key_id = "minio"
+            secret_access_key = "minio-secret"
+        "#).unwrap();
+
+        let movies = config.library("movies").unwrap();
+        let (minio, empty) = (&movies.destinations[0], &movies.destinations[1]);
+        assert_eq!(movies.destination_strategies().unwrap()[0].1, SyncStrategy::S3);
+        let bucket = minio.to_s3_bucket().unwrap();
+        assert_eq!((bucket.endpoint.as_str(), bucket.name.as_str()), ("http://minio:9000", "media"));
+        assert_eq!(bucket.credentials.region, "us-east-1");
+        assert_eq!(bucket.credentials.access_key_id, "minio");
+        assert_eq!(minio.s3_prefix().unwrap(), "strm/movies");
+        assert!(empty.to_s3_bucket().is_err());
+        assert!(movies.to_dir_sync_configs().unwrap().is_empty());
+
+        // Media files aren't uploaded to S3, only .strm mirror trees
+        let mut media = movies.clone();
+        media.strm = None;
+        let error = media.destination_strategies().unwrap_err().to_string();
+        assert!(error.contains("needs a strm table"), "{}", error);
+    }
+
+    #[test]
+    fn test_duplicate_library_names() {
+        let result = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/a"
+
+            [[libraries]]
+            name = "movies"
+            source = "/b"
+        "#);
+
+        assert!(result.is_err());
+    }
+
+    fn library_names(libraries: Vec<&LibraryConfig>) -> Vec<&str> {
+        libraries.into_iter().map(|library| library.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ordered_libraries() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "archive"
+            source = "/a"
+            after = ["incoming"]
+
+            [[libraries]]
+            name = "movies"
+            source = "/m"
+
+            [[libraries]]
+            name = "incoming"
+            source = "/i"
+        "#).unwrap();
+
+        assert_eq!(
+            library_names(config.ordered_libraries(&[]).unwrap()),
+            vec!["incoming", "archive", "movies"]
+        );
+        assert_eq!(
+            library_names(config.ordered_libraries(&["archive".to_string(), "incoming".to_string()]).unwrap()),
+            vec!["incoming", "archive"]
+        );
+        assert!(config.ordered_libraries(&["music".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_library_dependencies() {
+        let unknown = Config::from_toml(r#"
+            [[libraries]]
+            name = "archive"
+            source = "/a"
+            after = ["incoming"]
+        "#);
+        assert!(unknown.is_err());
+
+        let circular = Config::from_toml(r#"
+            [[libraries]]
+            name = "a"
+            source = "/a"
+            after = ["b"]
+
+            [[libraries]]
+            name = "b"
+            source = "/b"
+            after = ["a"]
+        "#);
+        assert!(circular.unwrap_err().to_string().contains("circular dependency"));
+    }
+
+    #[test]
+    fn test_library_remote_watch() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "cloud"
+            source = "/mnt/alist/media"
+
+            [libraries.remote_watch]
+            kind = "alist"
+            url = "http://127.0.0.1:5244"
+            path = "/media"
+            token = "t0ken"
+        "#).unwrap();
+
+        let remote = config.library("cloud").unwrap().remote_watch.as_ref().unwrap();
+        assert_eq!(remote.kind, RemoteWatchKind::Alist);
+        assert_eq!(remote.interval(), std::time::Duration::from_secs(60));
+        assert!(!remote.refresh);
+        assert_eq!(remote.to_alist_endpoint().token.as_deref(), Some("t0ken"));
+
+        let unknown = Config::from_toml(r#"
+            [[libraries]]
+            name = "cloud"
+            source = "/mnt/cloud"
+
+            [libraries.remote_watch]
+            kind = "ftp"
+            url = "ftp://host"
+        "#);
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_library_snapshot_watch() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "nfs"
+            source = "/mnt/nfs/media"
+
+            [libraries.snapshot_watch]
+            interval_secs = 120
+            alongside_notify = true
+
+            [[libraries]]
+            name = "smb"
+            source = "/mnt/smb/media"
+            snapshot_watch = {}
+        "#).unwrap();
+
+        let nfs = config.library("nfs").unwrap().snapshot_watch.as_ref().unwrap();
+        assert_eq!(nfs.interval(), std::time::Duration::from_secs(120));
+        assert!(nfs.alongside_notify);
+        let smb = config.library("smb").unwrap().snapshot_watch.as_ref().unwrap();
+        assert_eq!((smb.interval_secs, smb.alongside_notify), (300, false));
+
+        let both = Config::from_toml(r#"
+            [[libraries]]
+            name = "cloud"
+            source = "/mnt/alist/media"
+            snapshot_watch = {}
+
+            [libraries.remote_watch]
+            kind = "alist"
+            url = "http://127.0.0.1:5244"
+        "#);
+        assert!(both.unwrap_err().to_string().contains("both remote_watch and snapshot_watch"));
+    }
+
+    #[test]
+    fn test_library_watch_events() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "nfs"
+            source = "/mnt/nfs/media"
+            watch_events = ["create", "modify", "rename", "remove"]
+
+            [[libraries]]
+            name = "local"
+            source = "/media"
+        "#).unwrap();
+
+        let nfs = config.library("nfs").unwrap();
+        assert_eq!(nfs.watch_events.len(), 4);
+        assert!(!nfs.watch_events.contains(&WatchEventKind::Access));
+        assert!(config.library("local").unwrap().watch_events.is_empty(), "Every event triggers a sync by default");
+
+        let unknown = Config::from_toml(r#"
+            [[libraries]]
+            name = "nfs"
+            source = "/mnt/nfs/media"
+            watch_events = ["open"]
+        "#);
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_library_io_priority() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [libraries.io_priority]
+            nice = 10
+            io_class = "best_effort"
+            io_level = 7
+        "#).unwrap();
+        let io_priority = config.library("anime").unwrap().io_priority;
+        assert_eq!(io_priority.nice, Some(10));
+        assert_eq!(io_priority.io_class, Some(IoClass::BestEffort));
+        assert_eq!(io_priority.io_level, Some(7));
+
+        let invalid = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [libraries.io_priority]
+            nice = -30
+        "#);
+        assert!(invalid.unwrap_err().to_string().contains("invalid io_priority"));
+    }
+
+    #[test]
+    fn test_library_dry_run() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            strict_mode = true
+            dry_run = true
+            destinations = [{ path = "/mnt/emby/anime" }]
+        "#).unwrap();
+        let sync_configs = config.library("anime").unwrap().to_dir_sync_configs().unwrap();
+        assert!(sync_configs.iter().all(|sync_config| sync_config.get_dry_run()));
+    }
+
+    #[test]
+    fn test_observer_mode_dry_runs_every_library() {
+        let config = Config::from_toml(r#"
+            observer = true
+
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            destinations = [{ path = "/mnt/emby/anime" }]
+
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+            dry_run = false
+            destinations = [{ path = "/mnt/emby/movies" }]
+        "#).unwrap();
+        assert!(config.observer);
+        assert!(config.libraries.iter().all(|library| library.dry_run));
+        let sync_configs = config.library("movies").unwrap().to_dir_sync_configs().unwrap();
+        assert!(sync_configs.iter().all(|sync_config| sync_config.get_dry_run()));
+    }
+
+    #[test]
+    fn test_library_min_media_sizes() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("sample.mkv"), vec![0u8; 10]).unwrap();
+        std::fs::write(source.path().join("movie.mkv"), vec![0u8; 2_000]).unwrap();
+        std::fs::write(source.path().join("movie.nfo"), b"").unwrap();
+        let config = Config::from_toml(&format!(r#"
+            [[libraries]]
+            name = "movies"
+            source = "{}"
+            min_video_size = "1KB"
+            min_audio_size = 512
+
+            [[libraries.destinations]]
+            path = "/srv/emby"
+        "#, source.path().display())).unwrap();
+        let library = config.library("movies").unwrap();
+
+        assert!(!library.matches_filters(Path::new("sample.mkv")));
+        assert!(library.matches_filters(Path::new("movie.mkv")));
+        assert!(library.matches_filters(Path::new("movie.nfo")));
+        assert!(library.matches_filters(Path::new("removed.mkv")));
+        let limits = library.to_dir_sync_configs().unwrap()[0].get_media_size_limits();
+        assert_eq!(limits.min_video_size.map(|size| size.bytes()), Some(1_000));
+        assert_eq!(limits.min_audio_size.map(|size| size.bytes()), Some(512));
+
+        assert!(Config::from_toml("[[libraries]]\nname = \"a\"\nsource = \"/a\"\nmin_video_size = \"big\"").is_err());
+
+        // Sources below the home directory are sized where they are
+        let Some(home) = PathHelper::home_dir().filter(|home| home.is_dir()) else {
+            return;
+        };
+        let homed = tempfile::tempdir_in(&home).unwrap();
+        std::fs::write(homed.path().join("sample.mkv"), vec![0u8; 10]).unwrap();
+        let config = Config::from_toml(&format!(r#"
+            [[libraries]]
+            name = "movies"
+            source = "~/{}"
+            min_video_size = "1KB"
+        "#, homed.path().strip_prefix(&home).unwrap().display())).unwrap();
+        assert!(!config.library("movies").unwrap().matches_filters(Path::new("sample.mkv")));
+    }
+
+    #[test]
+    fn test_library_subtitle_and_metadata_files() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/a"
+            include_suffixes = ["mkv"]
+            subtitle_extensions = [".srt", "ass"]
+            metadata_policy = "link"
+
+            [[libraries.destinations]]
+            path = "/srv/emby"
+        "#).unwrap();
+        let library = config.library("movies").unwrap();
+
+        assert!(library.matches_filters(Path::new("Heat/Heat.en.srt")));
+        assert!(!library.matches_filters(Path::new("Heat/Heat.nfo")));
+        let sync_config = &library.to_dir_sync_configs().unwrap()[0];
+        assert_eq!(sync_config.get_subtitle_extensions(), ["srt", "ass"]);
+        assert_eq!(sync_config.get_metadata_policy(), MetadataPolicy::Link);
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let effective = EffectiveConfig::from_toml(r#"
+            observer = true
+
+            [emby]
+            base_url = "http://emby:8096"
+            api_key = "secret"
+
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            dry_run = false
+            exclude_suffixes = ["nfo"]
+
+            [[libraries.destinations]]
+            path = "nas:/srv/emby/anime"
+            authorization = ""
+
+            [libraries.strm]
+            target = "/srv/strm/anime"
+            content_template = "http://nas/{relative_path_encoded}"
+        "#).unwrap();
+        let value = |key: &str| {
+            let value = effective.get(key).unwrap_or_else(|| panic!("{} missing", key));
+            (value.value.as_str(), value.source)
+        };
+
+        assert_eq!(value("emby.api_key"), ("\"********\"", ValueSource::File));
+        assert_eq!(value("libraries[0].destinations[0].authorization"), ("\"\"", ValueSource::File));
+        assert_eq!(value("libraries[0].exclude_suffixes"), ("[\"nfo\"]", ValueSource::File));
+        assert_eq!(value("libraries[0].dry_run"), ("true", ValueSource::Derived));
+        assert_eq!(value("libraries[0].debounce_secs"), ("5", ValueSource::Default));
+        assert_eq!(value("libraries[0].strm.content_template"), ("\"http://nas/{relative_path_encoded}\"", ValueSource::File));
+        assert_eq!(value("telegram.bot_token"), ("\"\"", ValueSource::Default));
+        assert!(effective.get("libraries[0].exclude_regex").is_none(), "Unset options aren't listed");
+        assert!(effective.to_string().contains("libraries[0].name = \"anime\"  # file\n"));
+
+        let masked = EffectiveConfig::mask_file(r#"
+            [emby]
+            API_Key = "secret"
+
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [[libraries.destinations]]
+            path = "nas:/srv/emby/anime"
+            Authorization = "Bearer abc"
+            password_env = ""
+        "#).unwrap();
+        assert!(!masked.contains("secret") && !masked.contains("Bearer"), "{}", masked);
+        assert!(masked.contains("API_Key = \"********\""), "{}", masked);
+        assert!(masked.contains("password_env = \"\""), "{}", masked);
+        assert!(masked.contains("name = \"anime\""), "{}", masked);
+    }
+
+    #[test]
+    fn test_strm_library_syncs_mirror() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            include_suffixes = ["mkv"]
+
+            [[libraries.destinations]]
+            path = "nas:/srv/emby/anime"
+
+            [libraries.strm]
+            target = "/srv/strm/anime"
+        "#).unwrap();
+        let library = config.library("anime").unwrap();
+        assert_eq!(library.sync_source(), "/srv/strm/anime");
+        assert!(library.matches_sync_filters(Path::new("Show/E01.strm")));
+
+        let sync_config = library.to_dir_sync_config(&library.destinations[0]).unwrap();
+        assert_eq!(sync_config.get_source().get_path(), "/srv/strm/anime/");
+        assert!(sync_config.get_include_suffixes().is_empty());
+
+        let nested = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [libraries.strm]
+            target = "/media/anime/./strm"
+        "#);
+        assert!(nested.is_err());
+
+        let strm = |settings: &str| Config::from_toml(&format!(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [libraries.strm]
+            target = "/srv/strm/anime"
+            {}
+        "#, settings));
+        assert!(strm("soft_delete_retention_days = 30").is_ok());
+        assert!(strm("soft_delete_retention_days = 213503982334601").is_err());
+        assert!(strm("soft_delete_dir = \"/srv/strm/anime/.trash\"").is_err());
+        assert!(strm("soft_delete_dir = \"/srv/strm/trash\"").is_ok());
+        assert!(strm("staging_dir = \"/srv/strm/anime/.staging\"").is_err());
+        let swapped = strm("swap = \"symlink\"").unwrap();
+        assert_eq!(swapped.library("anime").unwrap().strm.as_ref().unwrap().swap, Some(SwapMode::Symlink));
+
+        let kinds = strm(r#"
+            sniff_signatures = true
+            skipped_kinds = ["trailer", "sample"]
+
+            [[libraries.strm.kind_rules]]
+            kind = "extra"
+            pattern = '(?i)\bNC(?:OP|ED)\d*\b'
+        "#).unwrap();
+        let generator = kinds.library("anime").unwrap().to_strm_generator().unwrap();
+        assert_eq!(generator.kind_of(Path::new("Frieren/NCED2.mkv")), MediaKind::Extra);
+        assert!(strm("[[libraries.strm.kind_rules]]\nkind = \"extra\"").is_err());
+        assert!(strm("[[libraries.strm.kind_rules]]\nkind = \"extra\"\npattern = \"(\"").is_err());
+    }
+}
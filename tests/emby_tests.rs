@@ -1,11 +1,11 @@
 #[cfg(test)]
 mod tests {
     
-    use tokio;
-
     use pilipili_strm::{
         core::{
-            api::*
+            api::*,
+            config::Config,
+            library::AffectedFolders
         },
         infrastructure::{
             network::*,
@@ -35,4 +35,33 @@ mod tests {
             Err(e) => panic!("Request failed: {}", e),
         }
     }
+
+    #[test]
+    fn test_emby_refresh_reports_changed_folders() {
+        let mut server = mockito::Server::new();
+        let mut config = Config::default();
+        config.emby.base_url = server.url();
+        config.emby.api_key = "key".to_string();
+        Config::apply(config);
+
+        let updated = server.mock("POST", "/emby/Library/Media/Updated")
+            .match_header("X-Emby-Token", "key")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "Updates": [
+                    { "Path": "/media/Frieren/Season 1", "UpdateType": "Modified" },
+                    { "Path": "/media/Heat", "UpdateType": "Modified" }
+                ]
+            })))
+            .with_status(204)
+            .create();
+
+        let folders = AffectedFolders::from_paths(&[
+            "Frieren/Season 1/E01.strm".to_string(),
+            "Frieren/Season 1/E02.strm".to_string(),
+            "Heat/Heat.strm".to_string(),
+            "Heat/extras/".to_string(),
+        ]);
+        folders.refresh_emby("/media/").unwrap();
+        updated.assert();
+    }
 }
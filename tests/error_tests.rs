@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::{anyhow, Context};
+
+    use pilipili_strm::infrastructure::error::*;
+
+    #[test]
+    fn test_classify_missing_programs() {
+        let missing: anyhow::Result<()> = Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Failed to start sshpass");
+        let error = missing.unwrap_err();
+
+        assert_eq!(ErrorHint::classify(&error), Some(ErrorHint::SshpassMissing));
+        assert!(ErrorHint::describe(&error).contains("switch to key authentication"));
+        assert_eq!(
+            ErrorHint::classify(&anyhow!("Failed to start rsync: No such file or directory")),
+            Some(ErrorHint::RsyncMissing)
+        );
+    }
+
+    #[test]
+    fn test_classify_messages() {
+        assert_eq!(
+            ErrorHint::classify_message("Failed to watch path /data: No space left on device (os error 28)"),
+            Some(ErrorHint::InotifyLimit)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("rsync failed with exit status: 11: write failed: No space left on device"),
+            Some(ErrorHint::DiskFull)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("Not enough free space in '/srv/emby': 1.0 GiB available, 10.0 GB required"),
+            Some(ErrorHint::DiskFull)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("media@nas: Permission denied (publickey,password)."),
+            Some(ErrorHint::SshAuthFailed)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("ssh: connect to host nas port 22: Connection refused"),
+            Some(ErrorHint::HostUnreachable)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("Failed to list /media/anime: Too many open files (os error 24)"),
+            Some(ErrorHint::FdLimit)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("Destination '/mnt/nas' is on a read-only file system"),
+            Some(ErrorHint::ReadOnlyFilesystem)
+        );
+        assert_eq!(
+            ErrorHint::classify_message("rsync made no progress for 600s and was killed as stuck"),
+            Some(ErrorHint::TransferStuck)
+        );
+        assert_eq!(ErrorHint::classify_message("Guard file '/x' does not exist"), None);
+    }
+
+    #[test]
+    fn test_classify_retryable_errors() {
+        let network = [
+            "ssh: connect to host nas port 22: Connection refused",
+            "rsync: connection unexpectedly closed (0 bytes received so far) [sender]",
+            "rsync error: timeout in data send/receive (code 30) at io.c(197)",
+            "write: Broken pipe",
+        ];
+        for message in network {
+            assert_eq!(RetryClass::classify_message(message), Some(RetryClass::Network), "{}", message);
+        }
+        assert_eq!(
+            RetryClass::classify(&anyhow!("Uploading 'E01.strm' failed with status 503 Service Unavailable")),
+            Some(RetryClass::Server)
+        );
+        assert_eq!(RetryClass::classify_message("Uploading 'E01.strm' failed with status 429 Too Many Requests"), Some(RetryClass::Server));
+        assert_eq!(RetryClass::classify_message("Uploading 'E01.strm' failed with status 403 Forbidden"), None);
+        assert_eq!(RetryClass::classify_message("rsync failed with exit status: 11: No space left on device"), None);
+        assert_eq!(RetryClass::classify_message("media@nas: Permission denied (publickey,password)."), None);
+    }
+
+    #[test]
+    fn test_panic_hook_reports_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let notified = messages.clone();
+
+        PanicHook::new()
+            .with_report_dir(dir.path())
+            .with_notifier(Box::new(move |report| {
+                notified.lock().unwrap().push(report.message.clone());
+            }))
+            .install();
+        let result = std::panic::catch_unwind(|| panic!("boom"));
+        let _ = std::panic::take_hook();
+
+        assert!(result.is_err());
+        assert_eq!(*messages.lock().unwrap(), vec!["boom".to_string()]);
+
+        let reports: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(reports.len(), 1);
+        let content = std::fs::read_to_string(reports[0].as_ref().unwrap().path()).unwrap();
+        assert!(content.contains("panicked at tests/error_tests.rs"));
+        assert!(content.contains("Backtrace:"));
+    }
+}
@@ -1,11 +1,21 @@
 #[cfg(test)]
 mod tests {
 
-    use std::fs;
+    use std::{fs, path::{Path, PathBuf}};
     use tempfile::tempdir;
 
+    use image::{ImageFormat, RgbImage};
+
     use pilipili_strm::infrastructure::fs::{
+        byte_size::ByteSize,
         file_helper::FileHelper, 
+        image_helper::{ImageHelper, ImageLimits},
+        media_detector::{MediaDetector, MediaType},
+        media_kind::{MediaKind, MediaKindRule, MediaKindRules},
+        media_size::MediaSizeLimits,
+        metadata_file::MetadataFiles,
+        natural_order::{Collation, NaturalOrder},
+        subtitle_companion::SubtitleCompanions,
     };
 
     #[test]
@@ -66,4 +76,199 @@ mod tests {
         fs::remove_file(new_file1.unwrap()).unwrap();
         fs::remove_file(new_file2_path).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_image_helper_fits_limits() {
+        let dir = tempdir().unwrap();
+        let limits = ImageLimits {
+            max_bytes: 1024 * 1024,
+            max_dimension_sum: 100,
+            formats: &[ImageFormat::Jpeg, ImageFormat::Png],
+        };
+
+        let small = dir.path().join("small.png");
+        RgbImage::new(40, 30).save(&small).unwrap();
+        assert_eq!(ImageHelper::fit(&small, &limits, dir.path()).unwrap(), small);
+
+        // WebP fanart above the dimension limit becomes a smaller JPEG
+        let fanart = dir.path().join("fanart.webp");
+        RgbImage::from_fn(300, 200, |x, y| image::Rgb([x as u8, y as u8, 128])).save(&fanart).unwrap();
+        let output = dir.path().join("converted");
+        let fitted = ImageHelper::fit(&fanart, &limits, &output).unwrap();
+        assert!(fitted.starts_with(&output));
+        assert_eq!(image::ImageFormat::from_path(&fitted).unwrap(), ImageFormat::Jpeg);
+        let (width, height) = image::image_dimensions(&fitted).unwrap();
+        assert!(width + height <= 100);
+        assert_eq!(width * 2, height * 3);
+
+        assert!(ImageHelper::fit(&dir.path().join("missing.jpg"), &limits, &output).is_err());
+    }
+
+    #[test]
+    fn test_byte_size_parse() {
+        assert_eq!(ByteSize::parse("200MB").unwrap(), ByteSize(200_000_000));
+        assert_eq!(ByteSize::parse("1.5 GiB").unwrap(), ByteSize(1_610_612_736));
+        assert_eq!(ByteSize::parse("512k").unwrap(), ByteSize(524_288));
+        assert_eq!(ByteSize::parse("1024").unwrap(), ByteSize(1024));
+        assert!(ByteSize::parse("10 parsecs").is_err());
+        assert!(ByteSize::parse("MB").is_err());
+        assert_eq!(ByteSize(200 * 1024 * 1024).to_string(), "200.0 MiB");
+        assert_eq!(ByteSize(12).to_string(), "12 B");
+    }
+
+    #[test]
+    fn test_media_size_limits() {
+        let limits = MediaSizeLimits {
+            min_video_size: Some(ByteSize(1_000)),
+            min_audio_size: Some(ByteSize(10)),
+        };
+        let path = std::path::Path::new;
+        assert!(limits.is_undersized(path("Movie/sample.MKV"), 999));
+        assert!(!limits.is_undersized(path("Movie/movie.mkv"), 1_000));
+        assert!(limits.is_undersized(path("Album/empty.flac"), 0));
+        assert!(!limits.is_undersized(path("Movie/movie.srt"), 0));
+        assert!(!MediaSizeLimits::default().is_undersized(path("Movie/sample.mkv"), 0));
+    }
+
+    #[test]
+    fn test_media_detector_sniffs_signatures() {
+        let dir = tempdir().unwrap();
+        let matroska = dir.path().join("movie");
+        let fake = dir.path().join("fake.mkv");
+        let flac = dir.path().join("track.bin");
+        let unknown = dir.path().join("clip.rmvb");
+        fs::write(&matroska, [0x1A, 0x45, 0xDF, 0xA3, 0x01, 0x00]).unwrap();
+        fs::write(&fake, "this is not a video\n").unwrap();
+        fs::write(&flac, b"fLaC\x00\x00\x00\x22").unwrap();
+        fs::write(&unknown, [0x00, 0xFE, 0x13, 0x37]).unwrap();
+
+        let by_suffix = MediaDetector::new();
+        assert_eq!(by_suffix.detect(&fake), Some(MediaType::Video));
+        assert_eq!(by_suffix.detect(&matroska), None);
+
+        let sniffing = MediaDetector::new().with_signature_sniffing(true);
+        assert_eq!(sniffing.detect(&matroska), Some(MediaType::Video));
+        assert_eq!(sniffing.detect(&fake), None);
+        assert_eq!(sniffing.detect(&flac), Some(MediaType::Audio));
+        assert_eq!(sniffing.detect(&unknown), Some(MediaType::Video));
+        assert_eq!(sniffing.detect(&dir.path().join("missing.mkv")), None);
+
+        let video_only = sniffing.with_audio_suffixes(Vec::new());
+        assert_eq!(video_only.detect(&flac), None);
+        assert_eq!(MediaDetector::sniff(b"\x00\x00\x00\x20ftypM4A "), Some(MediaType::Audio));
+    }
+
+    #[test]
+    fn test_media_detector_sniffs_text_and_disc_images() {
+        let dir = tempdir().unwrap();
+        let utf16 = dir.path().join("notes.mp3");
+        let iso = dir.path().join("disc");
+        let mut text = vec![0xFF, 0xFE];
+        text.extend("not audio".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&utf16, &text).unwrap();
+        let mut image = vec![0u8; 0x8001];
+        image.extend_from_slice(b"CD001\x01");
+        fs::write(&iso, &image).unwrap();
+
+        assert_eq!(MediaDetector::sniff(&text), None);
+        assert_eq!(MediaDetector::sniff(&[0xFF, 0xFB, 0x90, 0x00]), Some(MediaType::Audio));
+
+        let sniffing = MediaDetector::new().with_signature_sniffing(true);
+        assert_eq!(sniffing.detect(&utf16), None);
+        assert_eq!(sniffing.detect(&iso), Some(MediaType::Video));
+    }
+
+    #[test]
+    fn test_media_detector_classifies_files() {
+        let detector = MediaDetector::new();
+        let classify = |path: &str| detector.classify(Path::new(path));
+        assert_eq!(classify("Heat (1995)/Heat (1995).mkv"), MediaKind::Feature);
+        assert_eq!(classify("Heat (1995)/Heat (1995)-trailer.mkv"), MediaKind::Trailer);
+        assert_eq!(classify("Heat (1995)/Trailers/Teaser 2.mp4"), MediaKind::Trailer);
+        assert_eq!(classify("Heat (1995)/heat.1995.sample.mkv"), MediaKind::Sample);
+        assert_eq!(classify("Heat (1995)/Sample/heat-trailer.mkv"), MediaKind::Sample);
+        assert_eq!(classify("Heat (1995)/Behind The Scenes/Making of.mkv"), MediaKind::Extra);
+        assert_eq!(classify("Trailer Park Boys/Season 1/S01E01.mkv"), MediaKind::Feature);
+        assert_eq!(classify("Heat (1995)/Heat (1995).zh.srt"), MediaKind::Subtitle);
+        assert_eq!(classify("Heat (1995)/movie.nfo"), MediaKind::Other);
+        assert_eq!(classify("The Interview (2014)/The Interview (2014).mkv"), MediaKind::Feature);
+        assert_eq!(classify("Crime Scene/Crime Scene.mkv"), MediaKind::Feature);
+        assert_eq!(classify("Heat (1995)/Heat (1995)-featurette.mkv"), MediaKind::Extra);
+        assert_eq!(classify("Heat (1995)/Interviews/Michael Mann.mkv"), MediaKind::Extra);
+
+        let rules = MediaKindRules::empty()
+            .with_pattern(MediaKind::Extra, r"(?i)\bNCOP\d*\b")
+            .unwrap()
+            .with_subtitle_suffixes(vec![".sup"]);
+        let detector = MediaDetector::new().with_kind_rules(rules);
+        assert_eq!(detector.classify(Path::new("Frieren/NCOP1.mkv")), MediaKind::Extra);
+        assert_eq!(detector.classify(Path::new("Frieren/Trailers/PV.mkv")), MediaKind::Feature);
+        assert_eq!(detector.classify(Path::new("Frieren/E01.srt")), MediaKind::Other);
+        assert!(MediaKindRules::empty().with_pattern(MediaKind::Extra, "(").is_err());
+
+        let configured = [MediaKindRule {
+            kind: MediaKind::Extra,
+            keywords: vec!["ncop".to_string()],
+            pattern: None,
+        }];
+        let detector = MediaDetector::new().with_kind_rules(MediaKindRules::with_configured(&configured).unwrap());
+        assert_eq!(detector.classify(Path::new("Frieren/Frieren - NCOP.mkv")), MediaKind::Extra);
+        assert_eq!(detector.classify(Path::new("Frieren/Trailers/PV.mkv")), MediaKind::Trailer);
+    }
+
+    #[test]
+    fn test_subtitle_companions() {
+        let files: Vec<PathBuf> = [
+            "Heat/Heat.mkv", "Heat/Heat.srt", "Heat/Heat.en.forced.srt", "Heat/Heat_zh-Hans.ASS",
+            "Heat/Heated.srt", "Heat/Subs/Heat.srt", "Up/Up.srt", "Up/Up.nfo",
+        ].iter().map(PathBuf::from).collect();
+        let extensions = vec!["srt".to_string(), ".ass".to_string()];
+        let is_media = |path: &Path| path.extension().is_some_and(|extension| extension == "mkv");
+
+        assert_eq!(
+            SubtitleCompanions::find(&files, &extensions, is_media),
+            [Path::new("Heat/Heat.srt"), Path::new("Heat/Heat.en.forced.srt"), Path::new("Heat/Heat_zh-Hans.ASS")]
+        );
+        assert!(SubtitleCompanions::belongs_to(Path::new("Up/Up.pt-BR.srt"), Path::new("Up/Up.mp4")));
+        assert!(!SubtitleCompanions::belongs_to(Path::new("Up.srt"), Path::new("Up/Up.mp4")));
+    }
+
+    #[test]
+    fn test_metadata_files() {
+        for path in ["Heat/movie.nfo", "Heat/poster.jpg", "Heat/Heat-fanart.JPG", "Show/season01-poster.png", "Show/S01E01-thumb.jpg"] {
+            assert!(MetadataFiles::is_metadata(Path::new(path)), "{}", path);
+        }
+        for path in ["Heat/Heat.mkv", "Heat/poster.mkv", "Heat/Heat.en.srt", "Heat/screenshot.jpg", "Heat/Heatposter.jpg"] {
+            assert!(!MetadataFiles::is_metadata(Path::new(path)), "{}", path);
+        }
+        assert!(MetadataFiles::matches_rsync_patterns(Path::new("Show/S01E01-thumb.jpg")));
+        assert!(!MetadataFiles::matches_rsync_patterns(Path::new("Heat/Heat-fanart.JPG")), "rsync patterns are case-sensitive");
+        let patterns = MetadataFiles::rsync_patterns();
+        assert!(patterns.contains(&"*.nfo".to_string()));
+        assert!(patterns.contains(&"*-thumb.jpg".to_string()));
+        assert!(patterns.contains(&"season*.png".to_string()));
+    }
+
+    #[test]
+    fn test_natural_order() {
+        let mut names = vec!["Show/E10.strm", "Show/e2.strm", "Show/E1.strm", "Show/E02.strm", "Show 2/E1.strm", "Show/Extras"];
+        names.sort_by(|a, b| NaturalOrder::compare_with(a, b, Collation::Natural));
+        assert_eq!(
+            names,
+            vec!["Show 2/E1.strm", "Show/E1.strm", "Show/E02.strm", "Show/e2.strm", "Show/E10.strm", "Show/Extras"]
+        );
+
+        let mut episodes = vec!["第十一集", "第二集", "第十集", "第一百零五集", "第九集"];
+        episodes.sort_by(|a, b| NaturalOrder::compare_with(a, b, Collation::Chinese));
+        assert_eq!(episodes, vec!["第二集", "第九集", "第十集", "第十一集", "第一百零五集"]);
+        assert_eq!(
+            NaturalOrder::compare_with("二〇二四", "二〇二三", Collation::Chinese),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(
+            NaturalOrder::compare_with("第十集", "第二集", Collation::Natural),
+            "第十集".cmp("第二集"),
+            "Chinese numerals are text unless enabled"
+        );
+    }
+}
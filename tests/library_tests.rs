@@ -0,0 +1,662 @@
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use tempfile::tempdir;
+
+    use pilipili_strm::{
+        core::{
+            config::Config,
+            library::*
+        },
+        infrastructure::fs::{ByteSize, DirLocation, FdUsage, SyncTimeout}
+    };
+
+    #[test]
+    fn test_pause_library() {
+        let mut state = PauseState::default();
+        state.pause(Some("anime"));
+
+        assert!(state.is_paused("anime"));
+        assert!(!state.is_paused("movies"));
+        assert!(!state.is_globally_paused());
+
+        state.resume(Some("anime"));
+        assert!(!state.is_paused("anime"));
+    }
+
+    #[test]
+    fn test_pause_globally() {
+        let mut state = PauseState::default();
+        state.pause(Some("anime"));
+        state.pause(None);
+
+        assert!(state.is_paused("movies"));
+        assert!(state.is_globally_paused());
+
+        state.resume(None);
+        assert!(!state.is_paused("movies"));
+        assert!(state.is_paused("anime"), "Individual pauses survive lifting the global pause");
+    }
+
+    #[test]
+    fn test_pause_state_persistence() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state").join("pause.json");
+
+        let missing = PauseState::load(&path).unwrap();
+        assert_eq!(missing, PauseState::default());
+
+        let mut state = PauseState::default();
+        state.pause(Some("movies"));
+        state.save(&path).unwrap();
+
+        let loaded = PauseState::load(&path).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.paused_libraries(), vec!["movies".to_string()]);
+    }
+
+    #[test]
+    fn test_pause_state_corrupted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pause.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        assert!(PauseState::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_maintenance_defers_and_flushes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("maintenance.json");
+
+        let mut state = MaintenanceState::load(&path).unwrap();
+        assert!(!state.is_enabled());
+        state.set_enabled(true);
+        state.save(&path).unwrap();
+
+        MaintenanceState::defer_persisted(&path, "movies").unwrap();
+        MaintenanceState::defer_persisted(&path, "anime").unwrap();
+        MaintenanceState::defer_persisted(&path, "movies").unwrap();
+
+        let mut loaded = MaintenanceState::load(&path).unwrap();
+        assert!(loaded.is_enabled());
+        assert_eq!(loaded.pending_libraries(), vec!["anime".to_string(), "movies".to_string()]);
+
+        loaded.set_enabled(false);
+        assert_eq!(loaded.pending_libraries().len(), 2, "Disabling keeps the queue for flushing");
+
+        loaded.complete("anime");
+        assert_eq!(loaded.pending_libraries(), vec!["movies".to_string()]);
+
+        MaintenanceState::complete_persisted(&path, "movies").unwrap();
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || MaintenanceState::defer_persisted(&path, &format!("library-{}", i)).unwrap())
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let loaded = MaintenanceState::load(&path).unwrap();
+        assert_eq!(loaded.pending_libraries().len(), 9, "Concurrent deferrals are all kept");
+
+        MaintenanceState::set_enabled_persisted(&path, false).unwrap();
+        let loaded = MaintenanceState::load(&path).unwrap();
+        assert!(!loaded.is_enabled());
+        assert_eq!(loaded.pending_libraries().len(), 9);
+    }
+
+    #[test]
+    fn test_sync_strategy_selection() {
+        assert_eq!(SyncStrategy::for_destination("/srv/emby").unwrap(), SyncStrategy::Rsync);
+        assert_eq!(SyncStrategy::for_destination(r"\\nas\media").unwrap(), SyncStrategy::Rsync);
+        assert_eq!(SyncStrategy::for_destination("rsync://nas/media").unwrap(), SyncStrategy::Rsync);
+        assert_eq!(
+            SyncStrategy::for_destination("HTTPS://media.example.com/upload").unwrap(),
+            SyncStrategy::HttpUpload
+        );
+        assert_eq!(SyncStrategy::for_destination("s3://media/movies").unwrap(), SyncStrategy::S3);
+        assert_eq!(SyncStrategy::for_destination("webdav://nas/media").unwrap(), SyncStrategy::Rclone);
+        assert_eq!(SyncStrategy::for_destination("WebDAVs://nas/media").unwrap(), SyncStrategy::Rclone);
+        assert_eq!(SyncStrategy::for_destination("rclone:remote:media").unwrap(), SyncStrategy::Rclone);
+        assert!(SyncStrategy::for_destination("ftp://nas/media").is_err());
+
+        assert!(SyncStrategy::Rsync.capabilities().supports_delete);
+        assert!(!SyncStrategy::HttpUpload.capabilities().supports_plan);
+        assert!(SyncStrategy::Rsync.capabilities().supports_verify);
+        assert!(!SyncStrategy::HttpUpload.capabilities().supports_verify);
+        assert!(SyncStrategy::Native.capabilities().supports_delete);
+        assert!(SyncStrategy::Native.capabilities().supports_progress);
+        assert!(SyncStrategy::HttpUpload.capabilities().supports_progress);
+        assert!(!SyncStrategy::Robocopy.capabilities().supports_progress);
+        assert_eq!(SyncStrategy::Native.to_string(), "native");
+        assert!(SyncStrategy::S3.capabilities().supports_plan);
+        assert!(!SyncStrategy::S3.capabilities().preserves_mtime);
+        assert_eq!(SyncStrategy::S3.to_string(), "s3");
+        assert!(SyncStrategy::Robocopy.capabilities().supports_plan);
+        assert_eq!(SyncStrategy::Robocopy.to_string(), "robocopy");
+        assert!(SyncStrategy::Rclone.capabilities().supports_delete);
+        assert_eq!(SyncStrategy::Rclone.to_string(), "rclone");
+    }
+
+    #[test]
+    fn test_hook_context_render() {
+        let context = HookContext {
+            library: "movies".to_string(),
+            destination: "/srv/emby".to_string(),
+            changed_paths: vec!["a.strm".to_string(), "it's.strm".to_string()],
+            error: None,
+        };
+
+        assert_eq!(
+            context.render("notify {library} {destination} {changed_count} {changed_paths} {error}"),
+            r"notify 'movies' '/srv/emby' 2 'a.strm' 'it'\''s.strm' ''"
+        );
+
+        // Values are quoted and never expanded again
+        let context = HookContext {
+            library: "{changed_paths}".to_string(),
+            changed_paths: vec!["a.strm".to_string()],
+            error: Some("$(rm -rf ~).mkv".to_string()),
+            ..HookContext::default()
+        };
+        assert_eq!(
+            context.render("{library} {error} {unknown} {changed_paths"),
+            "'{changed_paths}' '$(rm -rf ~).mkv' {unknown} {changed_paths"
+        );
+    }
+
+    #[test]
+    fn test_affected_folders_group_changes_by_show_and_season() {
+        let folders = AffectedFolders::from_paths(&[
+            "Frieren/Season 1/E01.strm".to_string(),
+            "Frieren/Season 1/E02.strm".to_string(),
+            "Frieren/Season 2/E01.strm".to_string(),
+            "Dune/Dune.strm".to_string(),
+            "Dune/extras/trailer.strm".to_string(),
+            "Dune/".to_string(),
+        ]);
+        assert_eq!(folders.folders(), ["Dune", "Frieren/Season 1", "Frieren/Season 2"]);
+        assert_eq!(folders.resolve("/media"), ["/media/Dune", "/media/Frieren/Season 1", "/media/Frieren/Season 2"]);
+
+        let root = AffectedFolders::from_paths(&["poster.jpg".to_string(), "Dune/Dune.strm".to_string()]);
+        assert_eq!(root.folders(), [""]);
+        assert_eq!(root.to_string(), "/");
+
+        let context = HookContext { changed_paths: vec!["Up/Up.strm".to_string()], ..HookContext::default() };
+        assert_eq!(context.render("{changed_folders}"), "'Up'");
+    }
+
+    #[test]
+    fn test_pending_refreshes_wait_for_destination_to_settle() {
+        let settle = Duration::from_secs(30);
+        let start = Instant::now();
+        let mut pending = PendingRefreshes::default();
+
+        let episodes = AffectedFolders::from_paths(&["Frieren/Season 1/E01.strm".to_string()]);
+        pending.add("/media/", &episodes, settle, start);
+        let movie = AffectedFolders::from_paths(&["Dune/Dune.strm".to_string()]);
+        pending.add("/media", &movie, settle, start + Duration::from_secs(20));
+        let show = AffectedFolders::from_paths(&["Frieren/Frieren.nfo".to_string()]);
+        pending.add("/media", &show, settle, start + Duration::from_secs(40));
+        pending.add("/other", &movie, settle, start + Duration::from_secs(10));
+        assert_eq!(pending.len(), 2);
+
+        // Each write pushes the refresh of its destination back
+        assert!(pending.take_settled(start + Duration::from_secs(35)).is_empty());
+        let settled = pending.take_settled(start + Duration::from_secs(45));
+        assert_eq!(settled.len(), 1);
+        assert_eq!(settled[0].0, "/other");
+        assert_eq!(pending.next_deadline(), Some(start + Duration::from_secs(70)));
+
+        let settled = pending.take_settled(start + Duration::from_secs(70));
+        assert_eq!(settled[0].0, "/media");
+        assert_eq!(settled[0].1.folders(), ["Dune", "Frieren"]);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_run_local_sync_hooks() {
+        let dir = tempdir().unwrap();
+        let output = dir.path().join("hook.txt");
+        let config = Config::from_toml(&format!(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/a"
+
+            [[libraries.destinations]]
+            path = "/srv/emby"
+            hooks = {{ local_on_success = ["echo {{library}} {{changed_count}} > {}"], local_on_failure = ["exit 3"] }}
+        "#, output.display())).unwrap();
+        let destination = &config.library("movies").unwrap().destinations[0];
+
+        let mut context = HookContext {
+            library: "movies".to_string(),
+            changed_paths: vec!["a.strm".to_string()],
+            ..HookContext::default()
+        };
+        run_sync_hooks(destination, &context).unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap().trim(), "movies 1");
+
+        context.error = Some("rsync failed".to_string());
+        assert!(run_sync_hooks(destination, &context).is_err());
+    }
+
+    #[test]
+    fn test_sync_history_append_and_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert!(SyncHistory::load_since(&path, 0).unwrap().is_empty());
+
+        let old = SyncRecord { timestamp: 100, ..SyncRecord::new("anime", 1, None) };
+        let recent = SyncRecord::new("movies", 0, Some("timeout".to_string()));
+        SyncHistory::append(&path, &old).unwrap();
+        SyncHistory::append(&path, &recent).unwrap();
+
+        assert_eq!(SyncHistory::load_since(&path, 0).unwrap(), vec![old, recent.clone()]);
+        assert_eq!(SyncHistory::load_since(&path, 101).unwrap(), vec![recent]);
+    }
+
+    #[test]
+    fn test_sync_estimate_from_history() {
+        let run = |destination: &str, changed: usize, duration_ms: u64, failed: bool| DestinationRun {
+            destination: destination.to_string(),
+            changed,
+            duration_ms,
+            failed,
+            resources: None,
+            report: None,
+        };
+        let records = vec![
+            SyncRecord { destinations: vec![run("/srv/a", 10, 3_000, false), run("/srv/b", 10, 60_000, false)], ..SyncRecord::new("anime", 20, None) },
+            SyncRecord { destinations: vec![run("/srv/a", 30, 7_000, false)], ..SyncRecord::new("anime", 30, None) },
+            SyncRecord { destinations: vec![run("/srv/a", 0, 90_000, true)], ..SyncRecord::new("anime", 0, Some("timeout".to_string())) },
+            SyncRecord { destinations: vec![run("/srv/a", 5, 90_000, false)], ..SyncRecord::new("movies", 5, None) },
+        ];
+
+        // 1s to connect and compare, 200ms per changed path
+        let estimate = SyncEstimate::from_history(&records, "anime", "/srv/a").unwrap();
+        assert_eq!(estimate.samples(), 2);
+        assert_eq!(estimate.estimate(0), Duration::from_secs(1));
+        assert_eq!(estimate.estimate(100), Duration::from_secs(21));
+        assert_eq!(estimate.longest(), Duration::from_secs(7));
+
+        let single = SyncEstimate::from_history(&records, "anime", "/srv/b").unwrap();
+        assert_eq!(single.estimate(500), Duration::from_secs(60));
+        assert!(SyncEstimate::from_history(&records, "anime", "/srv/c").is_none());
+
+        let legacy: SyncRecord = serde_json::from_str(r#"{"library":"anime","timestamp":1,"changed":2}"#).unwrap();
+        assert!(legacy.destinations.is_empty());
+    }
+
+    #[test]
+    fn test_simulate_scenario() {
+        let dir = tempdir().unwrap();
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            debounce_secs = 5
+            include_suffixes = ["strm"]
+
+            [[libraries.destinations]]
+            path = "/srv/emby/anime"
+        "#).unwrap();
+        let library = config.library("anime").unwrap();
+
+        let toml_path = dir.path().join("scenario.toml");
+        std::fs::write(&toml_path, r#"
+            [[events]]
+            at = 12
+            path = "Show/S01E03.strm"
+
+            [[events]]
+            at = 0
+            path = "/media/anime/Show/S01E01.strm"
+
+            [[events]]
+            at = 3.5
+            path = "Show/S01E01.nfo"
+            kind = "modify"
+        "#).unwrap();
+        let scenario = Scenario::load(&toml_path).unwrap();
+
+        let syncs = simulate(library, &scenario, &PauseState::default(), &MaintenanceState::default()).unwrap();
+        assert_eq!(syncs.len(), 2);
+        assert_eq!(syncs[0].at, std::time::Duration::from_secs_f64(8.5));
+        assert_eq!(syncs[0].paths, vec!["create Show/S01E01.strm"]);
+        assert_eq!(syncs[0].filtered, vec!["modify Show/S01E01.nfo"]);
+        assert_eq!(syncs[1].outcome, SimulatedOutcome::Sync);
+        assert_eq!(syncs[1].destinations, vec![("/srv/emby/anime".to_string(), SyncStrategy::Rsync)]);
+
+        let journal_path = dir.path().join("journal.jsonl");
+        std::fs::write(&journal_path, "{\"at\": 1, \"path\": \"a.strm\", \"kind\": \"remove\"}\n").unwrap();
+        let journal = Scenario::load(&journal_path).unwrap();
+
+        let mut paused = PauseState::default();
+        paused.pause(Some("anime"));
+        let syncs = simulate(library, &journal, &paused, &MaintenanceState::default()).unwrap();
+        assert_eq!(syncs[0].outcome, SimulatedOutcome::Paused);
+        assert_eq!(syncs[0].to_string(), "[6.0s] skip (paused)\n  -> /srv/emby/anime (rsync)\n  + remove a.strm");
+    }
+
+    #[test]
+    fn test_auto_tune_resolves_concurrency() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+            auto_tune = true
+
+            [[libraries.destinations]]
+            path = "/srv/emby/anime"
+
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+            transfer_concurrency = 2
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+        "#).unwrap();
+        let anime = config.library("anime").unwrap();
+        let movies = config.library("movies").unwrap();
+
+        let mut state = TuningState::default();
+        assert_eq!(Concurrency::resolve(anime, &state), Concurrency::default());
+
+        let tuning = state.library_mut("anime");
+        tuning.add_sample(Duration::from_millis(20), Duration::from_millis(40), 3);
+        tuning.add_sample(Duration::from_millis(2), Duration::from_millis(60), 3);
+        assert!(!tuning.is_tuned());
+        tuning.add_sample(Duration::from_millis(25), Duration::from_millis(50), 3);
+        assert!(tuning.is_tuned());
+        assert_eq!(
+            Concurrency::resolve(anime, &state),
+            Concurrency { scan_parallelism: 8, transfer_concurrency: 3 }
+        );
+
+        state.library_mut("movies").add_sample(Duration::ZERO, Duration::ZERO, 1);
+        assert_eq!(
+            Concurrency::resolve(movies, &state),
+            Concurrency { scan_parallelism: 1, transfer_concurrency: 2 }
+        );
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tuning.json");
+        state.save(&path).unwrap();
+        assert_eq!(TuningState::load(&path).unwrap(), state);
+        assert!(LatencyProbe::local(dir.path()).is_some());
+        assert!(LatencyProbe::local(dir.path().join("missing")).is_none());
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_recovers() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [[libraries.destinations]]
+            path = "/mnt/nas/anime"
+
+            [libraries.destinations.circuit_breaker]
+            failure_threshold = 2
+            cooldown_secs = 60
+        "#).unwrap();
+        let destination = &config.library("anime").unwrap().destinations[0];
+        let mut breaker = CircuitBreaker::new(&destination.circuit_breaker);
+        let now = Instant::now();
+
+        assert_eq!(breaker.record_failure(now), CircuitTransition::None);
+        assert!(breaker.allows(now));
+        assert_eq!(breaker.record_failure(now), CircuitTransition::Opened { failures: 2 });
+        assert_eq!(
+            breaker.state(now + Duration::from_secs(20)),
+            CircuitState::Open { remaining: Duration::from_secs(40) }
+        );
+        assert!(!breaker.allows(now + Duration::from_secs(59)));
+
+        // A failed probe re-opens the circuit without alerting again
+        let probe = now + Duration::from_secs(60);
+        assert_eq!(breaker.state(probe), CircuitState::HalfOpen);
+        assert_eq!(breaker.record_failure(probe), CircuitTransition::None);
+        assert!(!breaker.allows(probe + Duration::from_secs(30)));
+
+        assert_eq!(breaker.record_success(), CircuitTransition::Recovered);
+        assert_eq!(breaker.state(probe), CircuitState::Closed);
+        assert_eq!(breaker.record_success(), CircuitTransition::None);
+
+        // Read-only destinations open the circuit on the first failure
+        assert_eq!(breaker.trip(probe), CircuitTransition::Opened { failures: 1 });
+        assert!(!breaker.allows(probe));
+
+        // Syncs skipped while open are owed a re-sync once the cool-down ends
+        breaker.defer("anime");
+        breaker.defer("movies");
+        assert!(!breaker.take_resync("anime", probe + Duration::from_secs(59)));
+        let resync = probe + Duration::from_secs(60);
+        assert!(breaker.take_resync("anime", resync));
+        assert!(!breaker.take_resync("anime", resync), "A re-sync is claimed once");
+        assert_eq!(breaker.record_success(), CircuitTransition::Recovered);
+        assert!(breaker.take_resync("movies", resync), "Other libraries still re-sync after recovery");
+        breaker.defer("anime");
+        assert!(!breaker.take_resync("anime", resync), "Nothing is owed while closed");
+
+        let defaults = &Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+        "#).unwrap().libraries[0].destinations[0].circuit_breaker;
+        assert_eq!(defaults.failure_threshold, 5);
+        assert_eq!(defaults.cooldown(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let config = Config::from_toml(r#"
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [[libraries.destinations]]
+            path = "nas:/srv/anime"
+
+            [libraries.destinations.retry]
+            max_retries = 5
+            initial_backoff_secs = 30
+            max_backoff_secs = 100
+            jitter = 0.5
+            retry_on = ["network"]
+        "#).unwrap();
+        let retry = &config.library("anime").unwrap().destinations[0].retry;
+
+        let backoffs: Vec<_> = (1..=4).map(|retry_number| retry.backoff(retry_number).as_secs()).collect();
+        assert_eq!(backoffs, vec![30, 60, 100, 100]);
+        for _ in 0..20 {
+            let delay = retry.delay(2);
+            assert!(delay >= Duration::from_secs(30) && delay <= Duration::from_secs(90), "{:?}", delay);
+        }
+        assert!(retry.is_retryable(&anyhow::anyhow!("ssh: connect to host nas port 22: Connection timed out")));
+        assert!(!retry.is_retryable(&anyhow::anyhow!("Uploading 'a' failed with status 502 Bad Gateway")));
+        assert!(!retry.is_retryable(&anyhow::anyhow!("rsync made no progress for 600s and was killed as stuck")));
+        assert!(!retry.is_retryable(&anyhow::anyhow!("rsync error: error in rsync protocol data stream (code 12)")));
+        let timeout = SyncTimeout { program: "rsync".to_string(), elapsed: Duration::from_secs(3600) };
+        assert!(!retry.is_retryable(&anyhow::Error::new(timeout).context("Sync failed")));
+
+        let defaults = &Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+
+            [[libraries.destinations]]
+            path = "/srv/emby/movies"
+        "#).unwrap().libraries[0].destinations[0].retry;
+        assert_eq!(defaults.max_retries, 0, "Retries are opt-in");
+        assert_eq!(defaults.backoff(2), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_concurrency_fits_fd_budget() {
+        let concurrency = Concurrency { scan_parallelism: 8, transfer_concurrency: 4 };
+
+        let roomy = FdUsage { open: 100, limit: 65536 };
+        assert!(!roomy.is_near_exhaustion());
+        assert_eq!(concurrency.budgeted(&roomy), concurrency);
+
+        // 4 transfers of 16 descriptors plus 4 per scan worker need 192
+        let tight = FdUsage { open: 50, limit: 306 };
+        assert_eq!(tight.available(), 192);
+        assert_eq!(
+            concurrency.budgeted(&tight),
+            Concurrency { scan_parallelism: 8, transfer_concurrency: 4 }
+        );
+        let tighter = FdUsage { open: 50, limit: 250 };
+        assert_eq!(
+            concurrency.budgeted(&tighter),
+            Concurrency { scan_parallelism: 4, transfer_concurrency: 4 }
+        );
+
+        let exhausted = FdUsage { open: 1000, limit: 1024 };
+        assert!(exhausted.is_near_exhaustion());
+        assert_eq!(exhausted.to_string(), "1000 of 1024 file descriptors open (97%)");
+        assert_eq!(concurrency.budgeted(&exhausted), Concurrency::default());
+    }
+
+    #[test]
+    fn test_health_checks_alert_once() {
+        let source = tempdir().unwrap();
+        let destination = tempdir().unwrap();
+        let config = Config::from_toml(&format!(r#"
+            [health]
+            interval_secs = 300
+
+            [[libraries]]
+            name = "anime"
+            source = "{}"
+
+            [[libraries.destinations]]
+            path = "{}"
+        "#, source.path().display(), destination.path().display())).unwrap();
+        assert_eq!(config.health.interval(), Some(Duration::from_secs(300)));
+
+        let checks = HealthCheck::from_config(&config).unwrap();
+        assert_eq!(checks.len(), 1);
+        assert!(matches!(checks[0].probe, HealthProbe::WriteAccess(_)));
+        let mut monitor = HealthMonitor::new();
+        assert!(monitor.run(&checks).is_empty());
+        assert!(monitor.is_healthy());
+
+        let name = "ssh media@nas";
+        let failed = monitor.record(name, &Err(anyhow::anyhow!("connection refused")));
+        assert_eq!(failed, HealthTransition::Failed { error: "connection refused".to_string() });
+        assert_eq!(monitor.record(name, &Err(anyhow::anyhow!("connection refused"))), HealthTransition::None);
+        assert!(!monitor.is_healthy());
+        assert_eq!(monitor.to_string(), "unhealthy (ssh media@nas: connection refused)");
+
+        assert_eq!(monitor.record(name, &Ok(())), HealthTransition::Recovered);
+        assert_eq!(monitor.record(name, &Ok(())), HealthTransition::None);
+        assert!(monitor.is_healthy());
+    }
+
+    #[test]
+    fn test_host_condition_checks() {
+        let source = tempdir().unwrap();
+        let destination = tempdir().unwrap();
+        let state = tempdir().unwrap();
+        std::fs::write(state.path().join("history.jsonl"), vec![b'x'; 2048]).unwrap();
+        let config = Config::from_toml(&format!(r#"
+            state_dir = "{}"
+
+            [health]
+            interval_secs = 300
+            min_free_space = "1B"
+            check_sources = true
+            max_state_size = "1KiB"
+
+            [[libraries]]
+            name = "anime"
+            source = "{}"
+            dry_run = true
+
+            [[libraries.destinations]]
+            path = "{}"
+        "#, state.path().display(), source.path().display(), destination.path().display())).unwrap();
+
+        let checks = HealthCheck::from_config(&config).unwrap();
+        let names: Vec<&str> = checks.iter().map(|check| check.name.as_str()).collect();
+        let free_space = format!("free space {}", destination.path().display());
+        assert_eq!(names, vec![free_space.as_str(), "source anime", "state size"]);
+        assert!(checks[0].run().is_ok());
+
+        let error = checks[1].run().unwrap_err().to_string();
+        assert!(error.contains("is empty, is its volume mounted?"), "{}", error);
+        std::fs::write(source.path().join("S01E01.strm"), "http://media/S01E01").unwrap();
+        assert!(checks[1].run().is_ok());
+        std::fs::remove_dir_all(source.path()).unwrap();
+        assert!(checks[1].run().unwrap_err().to_string().contains("is unavailable"));
+
+        let error = checks[2].run().unwrap_err().to_string();
+        assert!(error.contains("holds 2.0 KiB, more than 1.0 KiB"), "{}", error);
+        // The size is measured once an hour, not on every round
+        std::fs::write(state.path().join("queue.json"), vec![b'x'; 2048]).unwrap();
+        let error = checks[2].run().unwrap_err().to_string();
+        assert!(error.contains("holds 2.0 KiB"), "{}", error);
+
+        let remote = Config::from_toml(r#"
+            [health]
+            check_sources = true
+
+            [[libraries]]
+            name = "anime"
+            source = "media@nas:/srv/anime"
+
+            [[libraries.destinations]]
+            path = "/mnt/emby/anime"
+        "#).unwrap();
+        let checks = HealthCheck::from_config(&remote).unwrap();
+        let source = checks.iter().find(|check| check.name == "source anime").unwrap();
+        assert!(matches!(&source.probe, HealthProbe::RemoteSourceMounted(_, path) if path == "/srv/anime"));
+
+        let full = HealthCheck {
+            name: "free space".to_string(),
+            probe: HealthProbe::FreeSpace(DirLocation::new(destination.path().to_str().unwrap(), true, None), ByteSize(u64::MAX)),
+        };
+        assert!(full.run().unwrap_err().to_string().contains("Not enough free space"));
+    }
+
+    #[test]
+    fn test_state_doctor_repairs_damaged_files() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("artwork")).unwrap();
+        std::fs::write(dir.path().join("pause.json"), r#"{"global": true}"#).unwrap();
+        std::fs::write(dir.path().join("tuning.json"), r#"{"anime": {"#).unwrap();
+        std::fs::write(dir.path().join("artwork/index.json"), "").unwrap();
+        std::fs::write(dir.path().join("history.jsonl"), "{\"ok\":true}\n{\"ok\":\n{\"ok\":false}\n").unwrap();
+        std::fs::write(dir.path().join("listings.cache"), "not json").unwrap();
+
+        let doctor = StateDoctor::new(dir.path());
+        let report = doctor.check().unwrap();
+        assert_eq!(report.checked, 4);
+        let damaged: Vec<String> = report.issues.iter().map(|issue| issue.path.display().to_string()).collect();
+        assert_eq!(damaged, ["artwork/index.json", "history.jsonl", "tuning.json"]);
+        assert_eq!(report.issues[1].problem, StateProblem::BadLines(vec![2]));
+        assert!(!report.is_healthy());
+
+        let report = doctor.repair().unwrap();
+        assert!(report.repaired);
+        assert!(!dir.path().join("tuning.json").exists());
+        assert!(dir.path().join("tuning.json.corrupt").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("history.jsonl")).unwrap(),
+            "{\"ok\":true}\n{\"ok\":false}\n"
+        );
+        assert!(doctor.check().unwrap().is_healthy());
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use tracing::info_span;
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use pilipili_strm::infrastructure::logger::*;
+
+    #[test]
+    fn test_span_timings_are_recorded() {
+        let subscriber = Registry::default().with(SpanTimingLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            info_span!("timing_test_library").in_scope(|| {
+                for _ in 0..2 {
+                    info_span!("timing_test_sync").in_scope(|| std::thread::sleep(std::time::Duration::from_millis(5)));
+                }
+            });
+        });
+
+        let timings = SpanTimings::snapshot();
+        let sync = timings["timing_test_sync"];
+        assert_eq!(sync.count, 2);
+        assert!(sync.max >= std::time::Duration::from_millis(5));
+        assert!(timings["timing_test_library"].total >= sync.total);
+    }
+
+    #[test]
+    fn test_run_ids_are_unique() {
+        let ids: HashSet<RunId> = (0..100).map(|_| RunId::new()).collect();
+        assert_eq!(ids.len(), 100);
+        assert!(ids.iter().all(|id| id.as_str().len() == 8));
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use tokio::net::TcpListener;
+
+    use pilipili_strm::infrastructure::network::*;
+
+    struct MockTarget {
+        base_url: String,
+        timeout: Option<Duration>,
+        deadline: Option<Instant>,
+    }
+
+    impl NetworkTarget for MockTarget {
+
+        fn base_url(&self) -> String {
+            self.base_url.clone()
+        }
+
+        fn path(&self) -> String {
+            "ping".to_string()
+        }
+
+        fn method(&self) -> HttpMethod {
+            HttpMethod::Get
+        }
+
+        fn task(&self) -> NetworkTask {
+            NetworkTask::RequestPlain
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn deadline(&self) -> Option<Instant> {
+            self.deadline
+        }
+    }
+
+    async fn silent_server() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        (listener, url)
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let (_listener, base_url) = silent_server().await;
+        let target = MockTarget {
+            base_url,
+            timeout: Some(Duration::from_millis(200)),
+            deadline: None,
+        };
+
+        let provider = NetworkProvider::new(vec![]);
+        let result = provider.send_request(&target).await;
+
+        match result {
+            Err(NetworkError::Timeout(duration)) => {
+                assert_eq!(duration, Duration::from_millis(200));
+            }
+            other => panic!("Expected timeout error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_deadline() {
+        let (_listener, base_url) = silent_server().await;
+        let target = MockTarget {
+            base_url,
+            timeout: Some(Duration::from_secs(60)),
+            deadline: Some(Instant::now() + Duration::from_millis(200)),
+        };
+
+        let provider = NetworkProvider::new(vec![]);
+        let started = Instant::now();
+        let result = provider.send_request(&target).await;
+
+        assert!(result.as_ref().is_err_and(|e| e.is_timeout()), "Expected timeout: {:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(5), "Deadline should take precedence");
+    }
+
+    #[tokio::test]
+    async fn test_request_deadline_already_passed() {
+        let target = MockTarget {
+            base_url: "http://127.0.0.1:1".to_string(),
+            timeout: None,
+            deadline: Some(Instant::now()),
+        };
+
+        let provider = NetworkProvider::new(vec![]);
+        let result = provider.send_request(&target).await;
+
+        assert!(result.as_ref().is_err_and(|e| e.is_timeout()), "Expected timeout: {:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_request_connection_error() {
+        let (listener, base_url) = silent_server().await;
+        drop(listener);
+        let target = MockTarget {
+            base_url,
+            timeout: Some(Duration::from_secs(5)),
+            deadline: None,
+        };
+
+        let provider = NetworkProvider::new(vec![]);
+        let result = provider.send_request(&target).await;
+
+        assert!(result.as_ref().is_err_and(|e| e.is_connect()), "Expected connection error: {:?}", result);
+    }
+}
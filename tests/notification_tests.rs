@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        fs,
+        time::{Duration, Instant}
+    };
+
+    use pilipili_strm::core::{
+        client::MarkdownV2Builder,
+        config::{Config, DigestPeriod, NotificationConfig, NotificationLanguage},
+        library::SyncRecord,
+        notification::*,
+    };
+
+    #[test]
+    fn test_builtin_templates() {
+        let vars = [("library", "Anime"), ("error", "timeout")];
+
+        let english = NotificationTemplates::builtin(NotificationLanguage::En);
+        assert_eq!(
+            english.render(NotificationKind::SyncFailed, &vars),
+            "Library 'Anime' failed to sync: timeout"
+        );
+
+        let chinese = NotificationTemplates::builtin(NotificationLanguage::Zh);
+        assert_eq!(
+            chinese.render(NotificationKind::SyncFailed, &vars),
+            "媒体库「Anime」同步失败：timeout"
+        );
+    }
+
+    #[test]
+    fn test_render_template_keeps_unknown_placeholders() {
+        assert_eq!(
+            render_template("{library}: {count} {missing} {", &[("library", "Movies"), ("count", "3")]),
+            "Movies: 3 {missing} {"
+        );
+    }
+
+    #[test]
+    fn test_template_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("templates.toml");
+        fs::write(&path, "sync_completed = \"{library} +{count}\"\n").unwrap();
+
+        let config: Config = Config::from_toml(&format!(
+            "[notification]\nlanguage = \"zh\"\ntemplates_file = \"{}\"\n",
+            path.display()
+        )).unwrap();
+        assert_eq!(config.notification.language, NotificationLanguage::Zh);
+
+        let templates = NotificationTemplates::from_config(&config.notification).unwrap();
+        assert_eq!(
+            templates.render(NotificationKind::SyncCompleted, &[("library", "TV"), ("count", "2")]),
+            "TV +2"
+        );
+        assert_eq!(templates.template(NotificationKind::Crashed), "pilipili_strm 发生崩溃：{error}");
+
+        fs::write(&path, "sync_done = \"x\"\n").unwrap();
+        let broken = NotificationConfig {
+            templates_file: Some(path.display().to_string()),
+            ..NotificationConfig::default()
+        };
+        assert!(NotificationTemplates::from_config(&broken).is_err());
+    }
+
+    #[test]
+    fn test_markdown_formats_escape_values() {
+        let config = Config::from_toml(r#"
+[notification.formats]
+sync_completed = "*{title}* S{season}: {count} new in {duration}"
+"#).unwrap();
+        let templates = NotificationTemplates::from_config(&config.notification).unwrap();
+        assert_eq!(templates.syntax(NotificationKind::SyncCompleted), TemplateSyntax::MarkdownV2);
+
+        let vars = [("title", "Mr. Robot (2015)"), ("season", "2"), ("count", "3"), ("duration", "1m 5s")];
+        assert_eq!(
+            templates.render_markdown(NotificationKind::SyncCompleted, &vars),
+            r"*Mr\. Robot \(2015\)* S2: 3 new in 1m 5s"
+        );
+        assert_eq!(
+            templates.render_markdown(NotificationKind::SyncFailed, &[("library", "a_b"), ("error", "x.")]),
+            r"Library 'a\_b' failed to sync: x\."
+        );
+        assert_eq!(MarkdownV2Builder::new().bold("a.b").build(), r"*a\.b*");
+
+        let unknown = Config::from_toml("[notification.formats]\nsync_done = \"x\"\n").unwrap();
+        assert!(NotificationTemplates::from_config(&unknown.notification).is_err());
+    }
+
+    #[test]
+    fn test_media_info_from_paths() {
+        let paths = vec![
+            "Mr. Robot/Season 02/Mr. Robot S02E01.mkv".to_string(),
+            "Mr. Robot/Season 02/Mr. Robot S02E02.mkv".to_string(),
+        ];
+        let media = MediaInfo::from_paths(&paths);
+        assert_eq!(media.title.as_deref(), Some("Mr. Robot"));
+        assert_eq!(media.season, Some(2));
+
+        let mixed = vec!["A/S01E01.mkv".to_string(), "B/S02E01.mkv".to_string()];
+        assert_eq!(MediaInfo::from_paths(&mixed), MediaInfo::default());
+
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 2m 5s");
+    }
+
+    #[test]
+    fn test_digest_from_records() {
+        let records = vec![
+            SyncRecord { timestamp: 1, ..SyncRecord::new("Anime", 12, None) },
+            SyncRecord { timestamp: 2, ..SyncRecord::new("Movies", 3, None) },
+            SyncRecord { timestamp: 3, ..SyncRecord::new("Anime", 0, Some("timeout".to_string())) },
+            SyncRecord { timestamp: 4, ..SyncRecord::new("Movies", 0, Some("timeout".to_string())) },
+            SyncRecord { timestamp: 5, ..SyncRecord::new("Movies", 0, Some("denied".to_string())) },
+        ];
+        let digest = Digest::from_records(DigestPeriod::Weekly, 1_760_486_400, &records);
+        assert_eq!((digest.syncs, digest.added, digest.failures), (5, 15, 3));
+        assert_eq!(digest.top_errors, vec![("timeout".to_string(), 2), ("denied".to_string(), 1)]);
+
+        let vars = digest.vars();
+        let vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        assert_eq!(
+            NotificationTemplates::builtin(NotificationLanguage::En).render(NotificationKind::Digest, &vars),
+            "Sync digest (weekly): 5 syncs, 15 items added, 3 failures\nLibraries: Anime +12, Movies +3\nTop errors: 2x timeout; 1x denied"
+        );
+
+        let OutboundEvent::Digest(event) = EventEnvelope::from_digest(&digest).event else {
+            panic!("digest event expected");
+        };
+        assert_eq!(
+            event.libraries,
+            vec![
+                DigestLibraryEvent { library: "Anime".to_string(), added: 12 },
+                DigestLibraryEvent { library: "Movies".to_string(), added: 3 },
+            ]
+        );
+        assert_eq!(event.top_errors[0], DigestErrorEvent { error: "timeout".to_string(), count: 2 });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = digest.write_report(dir.path()).unwrap();
+        assert!(path.ends_with("digest-weekly-2025-10-15.md"));
+        assert!(fs::read_to_string(path).unwrap().contains("- Anime: +12"));
+    }
+
+    #[test]
+    fn test_error_throttle_escalates() {
+        let minute = Duration::from_secs(60);
+        let mut throttle = ErrorThrottle::new().with_intervals(vec![minute * 5, minute * 60]);
+        let start = Instant::now();
+
+        assert_eq!(throttle.record("anime", "unreachable", start), ThrottleDecision::Notify);
+        assert_eq!(throttle.record("anime", "unreachable", start + minute), ThrottleDecision::Suppress);
+        assert_eq!(throttle.record("movies", "unreachable", start + minute), ThrottleDecision::Notify);
+        assert_eq!(
+            throttle.record("anime", "unreachable", start + minute * 5),
+            ThrottleDecision::NotifyRepeated { occurrences: 2, window: minute * 5 }
+        );
+        assert_eq!(throttle.record("anime", "unreachable", start + minute * 30), ThrottleDecision::Suppress);
+        assert_eq!(
+            throttle.record("anime", "unreachable", start + minute * 65),
+            ThrottleDecision::NotifyRepeated { occurrences: 2, window: minute * 60 }
+        );
+        assert_eq!(throttle.record("anime", "disk full", start + minute * 66), ThrottleDecision::Notify);
+
+        assert!(throttle.clear("anime"));
+        assert!(!throttle.clear("anime"));
+        assert_eq!(throttle.record("anime", "disk full", start + minute * 67), ThrottleDecision::Notify);
+
+        let mut throttle = ErrorThrottle::new();
+        assert_eq!(
+            throttle.record("anime", "rsync: open \"/srv/a.strm\" failed: Permission denied (13)", start),
+            ThrottleDecision::Notify
+        );
+        assert_eq!(
+            throttle.record("anime", "rsync: open \"/srv/b.strm\" failed: Permission denied (13)", start + minute),
+            ThrottleDecision::Suppress
+        );
+        assert_eq!(throttle.record("movies", "3 of 10 files failed", start), ThrottleDecision::Notify);
+        assert_eq!(throttle.record("movies", "4 of 12 files failed", start + minute), ThrottleDecision::Suppress);
+        assert_eq!(throttle.record("movies", "3 of 10 files skipped", start + minute), ThrottleDecision::Notify);
+
+        let templates = NotificationTemplates::builtin(NotificationLanguage::En);
+        assert_eq!(
+            templates.render(NotificationKind::SyncStillFailing, &[
+                ("library", "anime"), ("occurrences", "37"), ("window", "1h 0m 0s"), ("error", "unreachable")
+            ]),
+            "Library 'anime' is still failing, 37 occurrences in the last 1h 0m 0s: unreachable"
+        );
+    }
+
+    #[test]
+    fn test_event_schema_round_trip() {
+        let vars = [
+            ("library", "anime"),
+            ("count", "3"),
+            ("title", "Frieren"),
+            ("season", "1"),
+            ("error", "disk full"),
+            ("destination", "nas:/media"),
+            ("check", "disk"),
+            ("period", "daily"),
+        ];
+        for kind in NotificationKind::ALL {
+            let envelope = EventEnvelope::from_notification(kind, &vars);
+            assert_eq!(envelope.schema_version, EVENT_SCHEMA_VERSION);
+            let json = envelope.to_json().unwrap();
+            assert!(json.contains(&format!("\"type\":\"{}\"", kind.key())), "{}", json);
+            assert_eq!(EventEnvelope::from_json(&json).unwrap(), envelope, "{} round trip", kind.key());
+        }
+
+        let envelope = EventEnvelope::from_notification(NotificationKind::SyncCompleted, &vars);
+        assert_eq!(
+            envelope.to_json().unwrap(),
+            format!(
+                r#"{{"schema_version":"1.0.0","id":"{}","timestamp":"{}","type":"sync_completed","data":{{"library":"anime","count":3,"title":"Frieren","season":1}}}}"#,
+                envelope.id,
+                envelope.timestamp
+            )
+        );
+        assert_ne!(EventEnvelope::from_notification(NotificationKind::SyncCompleted, &vars).id, envelope.id);
+        assert!(envelope.timestamp.ends_with('Z'));
+
+        // Later minor versions may add fields and types
+        let newer = r#"{"schema_version":"1.4.0","id":"a-1-0","timestamp":"2025-10-15T08:16:37Z","type":"sync_failed","data":{"library":"anime","error":"x","retry_in":"5m"}}"#;
+        let event = EventEnvelope::from_json(newer).unwrap().event;
+        assert!(matches!(event, OutboundEvent::SyncFailed(ref failure) if failure.library == "anime"));
+        let unknown = r#"{"schema_version":"1.4.0","id":"a-1-1","timestamp":"2025-10-15T08:16:37Z","type":"library_added","data":{"library":"anime"}}"#;
+        assert_eq!(EventEnvelope::from_json(unknown).unwrap().event, OutboundEvent::Unknown);
+
+        let incompatible = r#"{"schema_version":"2.0.0","type":"crashed","data":{"error":"x"}}"#;
+        assert!(EventEnvelope::from_json(incompatible).unwrap_err().to_string().contains("incompatible"));
+    }
+}
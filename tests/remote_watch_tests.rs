@@ -0,0 +1,368 @@
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc
+        },
+        time::Duration
+    };
+
+    use mockito::Matcher;
+    use notify::EventKind;
+    use serde_json::json;
+
+    use pilipili_strm::{
+        core::{api::*, client::*, strm::StrmGenerator},
+        infrastructure::fs::*
+    };
+
+    fn listing(entries: serde_json::Value) -> String {
+        json!({ "code": 200, "message": "success", "data": { "content": entries } }).to_string()
+    }
+
+    #[test]
+    fn test_remote_snapshot_diffs_subtrees() {
+        let mut snapshot = RemoteSnapshot::default();
+        let files = |entries: &[(&str, &str)]| -> BTreeMap<PathBuf, String> {
+            entries.iter().map(|(path, version)| (PathBuf::from(path), version.to_string())).collect()
+        };
+
+        assert!(snapshot.update(Path::new(""), files(&[("Show/E01.strm", "1"), ("Movie/M.strm", "1")])).is_empty());
+        assert!(snapshot.has_baseline());
+
+        let events = snapshot.update(Path::new("Show"), files(&[("Show/E01.strm", "2"), ("Show/E02.strm", "1")]));
+        let kinds: Vec<(EventKind, PathBuf)> = events.iter().map(|event| (event.kind, event.paths[0].clone())).collect();
+        assert_eq!(kinds.len(), 2);
+        assert!(matches!(kinds[0].0, EventKind::Modify(_)) && kinds[0].1 == Path::new("Show/E01.strm"));
+        assert!(matches!(kinds[1].0, EventKind::Create(_)) && kinds[1].1 == Path::new("Show/E02.strm"));
+
+        let events = snapshot.update(Path::new("Movie"), BTreeMap::new());
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, EventKind::Remove(_)));
+    }
+
+    #[tokio::test]
+    async fn test_alist_poller_reports_changes() {
+        let mut server = mockito::Server::new_async().await;
+        let root = server.mock("POST", "/api/fs/list")
+            .match_header("authorization", "t0ken")
+            .match_body(Matcher::PartialJson(json!({ "path": "/media" })))
+            .with_body(listing(json!([
+                { "name": "Show", "size": 0, "is_dir": true, "modified": "2024-01-01T00:00:00Z" },
+                { "name": "a.strm", "size": 3, "is_dir": false, "modified": "2024-01-01T00:00:00Z" }
+            ])))
+            .create_async()
+            .await;
+        let show = server.mock("POST", "/api/fs/list")
+            .match_body(Matcher::PartialJson(json!({ "path": "/media/Show" })))
+            .with_body(listing(json!(null)))
+            .create_async()
+            .await;
+
+        let endpoint = AlistEndpoint::new(server.url()).with_token("t0ken");
+        let mut poller = AlistPoller::new(AlistClient::builder(endpoint).build(), "/media/");
+        assert!(poller.poll().await.unwrap().is_empty());
+
+        show.remove_async().await;
+        server.mock("POST", "/api/fs/list")
+            .match_body(Matcher::PartialJson(json!({ "path": "/media/Show" })))
+            .with_body(listing(json!([
+                { "name": "E01.strm", "size": 5, "is_dir": false, "modified": "2024-01-02T00:00:00Z" }
+            ])))
+            .create_async()
+            .await;
+
+        let events = poller.poll().await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, EventKind::Create(_)));
+        assert_eq!(events[0].paths, vec![PathBuf::from("Show/E01.strm")]);
+        root.expect(2).assert_async().await;
+
+        server.mock("POST", "/api/fs/list")
+            .match_body(Matcher::PartialJson(json!({ "path": "/missing" })))
+            .with_body(json!({ "code": 500, "message": "object not found", "data": null }).to_string())
+            .create_async()
+            .await;
+        let client = AlistClient::builder(AlistEndpoint::new(server.url())).build();
+        let error = client.list("/missing").await.unwrap_err();
+        assert!(error.to_string().contains("object not found"));
+    }
+
+    fn multistatus(responses: &[(&str, bool, &str)]) -> String {
+        let responses: String = responses
+            .iter()
+            .map(|(href, collection, etag)| {
+                let resource_type = if *collection { "<D:collection/>" } else { "" };
+                format!(
+                    "<D:response><D:href>{}</D:href><D:propstat><D:prop>\
+                     <D:resourcetype>{}</D:resourcetype><D:getetag>\"{}\"</D:getetag>\
+                     </D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+                    href, resource_type, etag
+                )
+            })
+            .collect();
+        format!("<?xml version=\"1.0\"?><D:multistatus xmlns:D=\"DAV:\">{}</D:multistatus>", responses)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_poller_reports_changes() {
+        let source = tempfile::tempdir().unwrap();
+        let state = tempfile::tempdir().unwrap();
+        let state_path = state.path().join("snapshots/anime.cache");
+        let kinds = |events: Vec<notify::Event>| -> Vec<(EventKind, PathBuf)> {
+            events.into_iter().map(|event| (event.kind, event.paths[0].clone())).collect()
+        };
+        std::fs::create_dir(source.path().join("Show")).unwrap();
+        std::fs::write(source.path().join("Show/E01.strm"), "http://media/E01").unwrap();
+
+        let mut poller = SnapshotPoller::new(source.path()).with_state_path(&state_path);
+        assert!(poller.poll().await.unwrap().is_empty(), "The first scan is the baseline");
+        assert!(state_path.exists());
+
+        std::fs::write(source.path().join("Show/E02.strm"), "http://media/E02").unwrap();
+        std::fs::write(source.path().join("Show/E01.strm"), "http://media/E01?v=2").unwrap();
+        let events = kinds(poller.poll().await.unwrap());
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], (EventKind::Create(_), ref path) if path == Path::new("Show/E02.strm")));
+        assert!(matches!(events[1], (EventKind::Modify(_), ref path) if path == Path::new("Show/E01.strm")));
+        assert!(poller.poll().await.unwrap().is_empty());
+
+        // Changes whose sync didn't succeed are reported again after a restart
+        let mut unsynced = SnapshotPoller::new(source.path()).with_state_path(&state_path);
+        assert_eq!(kinds(unsynced.poll().await.unwrap()).len(), 2);
+        SnapshotPoller::take_pending(&state_path).unwrap().commit().unwrap();
+        assert!(SnapshotPoller::take_pending(&state_path).is_none());
+
+        // Changes made while stopped are found against the persisted scan
+        std::fs::remove_file(source.path().join("Show/E02.strm")).unwrap();
+        let mut restarted = SnapshotPoller::new(source.path()).with_state_path(&state_path);
+        let events = kinds(restarted.poll().await.unwrap());
+        assert!(matches!(events[..], [(EventKind::Remove(_), ref path)] if path == Path::new("Show/E02.strm")));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_watcher_polls_local_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut watcher = FileWatcher::new(dir.path(), Duration::from_secs(2))
+            .with_local_polling(Duration::from_millis(100));
+        let counter = calls.clone();
+        watcher.set_callback(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert!(!watcher.is_polling());
+        watcher.resume().unwrap();
+        assert!(watcher.is_polling());
+
+        std::fs::write(dir.path().join("E01.strm"), "http://media/E01").unwrap();
+        for _ in 0..50 {
+            if calls.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "Scans report new files like notifications do");
+        watcher.stop();
+    }
+
+    #[test]
+    fn test_rename_tracker_replays_moves() {
+        use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let root = source.path();
+        for (path, content) in [("Show/E01.mkv", "episode 1"), ("Show/E02.mkv", "episode 2"), ("New.mkv", "new")] {
+            std::fs::create_dir_all(destination.path().join(path).parent().unwrap()).unwrap();
+            std::fs::write(destination.path().join(path), content).unwrap();
+        }
+        std::fs::create_dir_all(root.join("Season 1")).unwrap();
+        std::fs::write(root.join("Season 1/E02.mkv"), "episode 2").unwrap();
+        std::fs::write(root.join("Other.mkv"), "different").unwrap();
+
+        let tracker = RenameTracker::new(root);
+        let event = |kind: EventKind, paths: &[&str]| {
+            paths.iter().fold(notify::Event::new(kind), |event, path| event.add_path(root.join(path)))
+        };
+        tracker.record(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["Show/E01.mkv", "Show/Ep01.mkv"]));
+        tracker.record(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &["Show/Ep01.mkv", "Show/S01E01.mkv"]));
+        tracker.record(&event(EventKind::Remove(RemoveKind::File), &["Show/E02.mkv"]));
+        tracker.record(&event(EventKind::Create(CreateKind::File), &["Season 1/E02.mkv"]));
+        tracker.record(&event(EventKind::Remove(RemoveKind::File), &["New.mkv"]));
+        tracker.record(&event(EventKind::Create(CreateKind::File), &["Other.mkv"]));
+        tracker.record(&notify::Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from("/elsewhere/E03.mkv")));
+
+        let location = DirLocation::new(&destination.path().to_string_lossy(), true, None);
+        let renames = tracker.take_renames(|relative| MoveReplay::file_stamp(&location, relative));
+        let pairs: Vec<String> = renames.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            pairs,
+            ["Show/E01.mkv -> Show/S01E01.mkv", "Show/E02.mkv -> Season 1/E02.mkv"],
+            "Consecutive renames fold, and files of another size aren't moves"
+        );
+        assert!(tracker.take_renames(|_| None).is_empty(), "Taking the renames clears the tracker");
+
+        let applied = MoveReplay::apply(&location, &renames).unwrap();
+        assert_eq!(applied, renames);
+        assert_eq!(std::fs::read_to_string(destination.path().join("Show/S01E01.mkv")).unwrap(), "episode 1");
+        assert_eq!(std::fs::read_to_string(destination.path().join("Season 1/E02.mkv")).unwrap(), "episode 2");
+        assert!(!destination.path().join("Show/E01.mkv").exists());
+        assert!(MoveReplay::apply(&location, &renames).unwrap().is_empty(), "Moves already replayed are skipped");
+    }
+
+    #[test]
+    fn test_rename_tracker_pairs_by_stamp() {
+        use notify::event::{ModifyKind, RenameMode};
+
+        let source = tempfile::tempdir().unwrap();
+        let root = source.path();
+        std::fs::write(root.join("Moved.mkv"), "episode").unwrap();
+        std::fs::write(root.join("Edited.mkv"), "episode").unwrap();
+        let stamp = FileStamp::of(&std::fs::metadata(root.join("Moved.mkv")).unwrap());
+
+        let tracker = RenameTracker::new(root);
+        let renamed = |path: &str| notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Any))).add_path(root.join(path));
+        for path in ["Old.mkv", "Moved.mkv", "Older.mkv", "Edited.mkv"] {
+            tracker.record(&renamed(path));
+        }
+        let renames = tracker.take_renames(|relative| match relative.to_str() {
+            Some("Old.mkv") => Some(stamp),
+            Some("Older.mkv") => Some(FileStamp::new(stamp.size, Some(0))),
+            _ => None,
+        });
+        let pairs: Vec<String> = renames.iter().map(ToString::to_string).collect();
+        assert_eq!(pairs, ["Old.mkv -> Moved.mkv"], "A file of the same size but another mtime isn't a move");
+    }
+
+    #[test]
+    fn test_parse_webdav_multistatus() {
+        let body = r#"<?xml version="1.0"?>
+            <d:multistatus xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+              <d:response>
+                <d:href>https://cloud.example.com/dav/My%20Shows/</d:href>
+                <d:propstat><d:prop>
+                  <d:resourcetype><d:collection/></d:resourcetype>
+                  <cs:getctag>42</cs:getctag>
+                </d:prop></d:propstat>
+              </d:response>
+              <d:response>
+                <d:href>/dav/My%20Shows/E01.strm</d:href>
+                <d:propstat><d:prop>
+                  <d:resourcetype/>
+                  <d:getlastmodified>Mon, 01 Jan 2024 00:00:00 GMT</d:getlastmodified>
+                  <d:getcontentlength>12</d:getcontentlength>
+                </d:prop></d:propstat>
+              </d:response>
+            </d:multistatus>"#;
+
+        let entries = WebDavEntry::parse_multistatus(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].href, "/dav/My Shows/");
+        assert!(entries[0].is_collection);
+        assert_eq!(entries[0].ctag.as_deref(), Some("42"));
+        assert_eq!(entries[1].href, "/dav/My Shows/E01.strm");
+        assert!(!entries[1].is_collection);
+        assert_eq!(entries[1].content_length, Some(12));
+        assert_eq!(entries[1].version(), "||Mon, 01 Jan 2024 00:00:00 GMT|12");
+        assert!(WebDavEntry::parse_multistatus("<d:multistatus><d:response>").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_webdav_poller_descends_into_changed_collections() {
+        let mut server = mockito::Server::new_async().await;
+        let root = server.mock("PROPFIND", "/dav/media/")
+            .match_header("depth", "1")
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .with_status(207)
+            .with_body(multistatus(&[
+                ("/dav/media/", true, "r1"),
+                ("/dav/media/Show/", true, "s1"),
+                ("/dav/media/a.strm", false, "a1"),
+            ]))
+            .expect(2)
+            .create_async()
+            .await;
+        let show = server.mock("PROPFIND", "/dav/media/Show/")
+            .with_status(207)
+            .with_body(multistatus(&[
+                ("/dav/media/Show/", true, "s1"),
+                ("/dav/media/Show/E01.strm", false, "e1"),
+            ]))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let endpoint = WebDavEndpoint::new(format!("{}/dav", server.url()))
+            .with_authorization("Basic dXNlcjpwYXNz");
+        let mut poller = WebDavPoller::new(WebDavClient::builder(endpoint).build(), "/media");
+        assert!(poller.poll().await.unwrap().is_empty());
+        assert!(poller.poll().await.unwrap().is_empty());
+        root.assert_async().await;
+
+        root.remove_async().await;
+        server.mock("PROPFIND", "/dav/media/")
+            .with_status(207)
+            .with_body(multistatus(&[
+                ("/dav/media/", true, "r2"),
+                ("/dav/media/Show/", true, "s1"),
+            ]))
+            .create_async()
+            .await;
+
+        let events = poller.poll().await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, EventKind::Remove(_)));
+        assert_eq!(events[0].paths, vec![PathBuf::from("a.strm")]);
+        show.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_generate_strm_from_alist_and_webdav() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/api/fs/list")
+            .match_body(Matcher::PartialJson(json!({ "path": "/media" })))
+            .with_body(listing(json!([
+                { "name": "Frieren", "size": 0, "is_dir": true, "modified": "2024-01-01T00:00:00Z" },
+                { "name": "cover.jpg", "size": 3, "is_dir": false, "modified": "2024-01-01T00:00:00Z" }
+            ])))
+            .create_async()
+            .await;
+        server.mock("POST", "/api/fs/list")
+            .match_body(Matcher::PartialJson(json!({ "path": "/media/Frieren" })))
+            .with_body(listing(json!([
+                { "name": "E01 1080p.mkv", "size": 5, "is_dir": false, "modified": "2024-01-02T00:00:00Z", "sign": "abc=:0" }
+            ])))
+            .create_async()
+            .await;
+        server.mock("PROPFIND", "/dav/media/")
+            .with_status(207)
+            .with_body(multistatus(&[
+                ("/dav/media/", true, "r1"),
+                ("/dav/media/Up.mp4", false, "u1"),
+            ]))
+            .create_async()
+            .await;
+
+        let target = tempfile::tempdir().unwrap();
+        let generator = StrmGenerator::new("/unused", target.path());
+        let alist = AlistClient::builder(AlistEndpoint::new(server.url())).build();
+        let written = generator.generate_from_alist(&alist, "/media").await.unwrap();
+        assert_eq!(written, vec![PathBuf::from("Frieren/E01 1080p.strm")]);
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("Frieren/E01 1080p.strm")).unwrap(),
+            format!("{}/d/media/Frieren/E01%201080p.mkv?sign=abc=:0", server.url())
+        );
+
+        let webdav = WebDavClient::builder(WebDavEndpoint::new(format!("{}/dav", server.url()))).build();
+        generator.generate_from_webdav(&webdav, Path::new("/media")).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("Up.strm")).unwrap(),
+            format!("{}/dav/media/Up.mp4", server.url())
+        );
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc
+        },
+        time::Duration
+    };
+
+    use notify::{
+        event::{AccessKind, CreateKind, MetadataKind, ModifyKind},
+        Event,
+        EventKind
+    };
+
+    use pilipili_strm::{
+        core::{
+            config::Config,
+            library::{LibrarySync, SyncHistory, SyncStrategy}
+        },
+        infrastructure::fs::*,
+        test_util::*
+    };
+
+    #[test]
+    fn test_virtual_library_is_deterministic() {
+        let build = || VirtualLibrary::builder()
+            .with_shows(3)
+            .with_episodes(4)
+            .with_sidecar_chance(0.5)
+            .with_arrival_interval(Duration::from_secs(10))
+            .with_seed(42)
+            .build();
+        let library = build();
+
+        assert_eq!(library.files(), build().files());
+        let episodes: Vec<_> = library.files()
+            .iter()
+            .filter(|file| file.path.extension().is_some_and(|ext| ext == "strm"))
+            .collect();
+        assert_eq!(episodes.len(), 12);
+        assert!(library.files().len() > 12);
+        assert_eq!(episodes[11].arrives_after, Duration::from_secs(110));
+        assert!(episodes[0].path.ends_with("Show 01/Season 01/Show 01 S01E01.strm"));
+        assert_eq!(library.arrived_by(Duration::from_secs(5)).filter(|file| file.path.extension().is_some_and(|ext| ext == "strm")).count(), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        library.write_to(dir.path()).unwrap();
+        assert!(dir.path().join(&episodes[5].path).is_file());
+    }
+
+    #[tokio::test]
+    async fn test_watcher_debounces_simulated_arrivals() {
+        let clock = FakeClock::start();
+        let dir = tempfile::tempdir().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut watcher = FileWatcher::new(dir.path(), Duration::from_secs(3));
+        let counter = calls.clone();
+        watcher.set_callback(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        watcher.resume().unwrap();
+        let events = watcher.event_sender();
+
+        // Arrivals closer together than the debounce window settle into one callback
+        let burst = VirtualLibrary::builder()
+            .with_episodes(5)
+            .with_arrival_interval(Duration::from_secs(1))
+            .build();
+        burst.play(&clock, &events, None).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        clock.advance(Duration::from_secs(4)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Arrivals further apart each get their own callback
+        let spread = VirtualLibrary::builder()
+            .with_episodes(3)
+            .with_arrival_interval(Duration::from_secs(5))
+            .build();
+        spread.play(&clock, &events, None).await.unwrap();
+        clock.advance(Duration::from_secs(4)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        watcher.stop();
+    }
+
+    #[tokio::test]
+    async fn test_watcher_ignores_filtered_event_kinds() {
+        let clock = FakeClock::start();
+        let dir = tempfile::tempdir().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let filter = EventFilter::new(vec![WatchEventKind::Create, WatchEventKind::Modify]);
+        assert!(!filter.matches(&EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any))));
+        assert!(filter.matches(&EventKind::Modify(ModifyKind::Metadata(MetadataKind::WriteTime))));
+        assert!(EventFilter::default().matches(&EventKind::Access(AccessKind::Any)));
+        let mut watcher = FileWatcher::new(dir.path(), Duration::from_secs(3)).with_event_filter(filter);
+        let counter = calls.clone();
+        watcher.set_callback(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        watcher.resume().unwrap();
+        let events = watcher.event_sender();
+
+        events.send(Event::new(EventKind::Access(AccessKind::Any))).await.unwrap();
+        events.send(Event::new(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)))).await.unwrap();
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(4)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 0, "Access and metadata events are ignored");
+
+        // A stream of ignored events doesn't hold back the sync of a new file
+        events.send(Event::new(EventKind::Create(CreateKind::File))).await.unwrap();
+        tokio::task::yield_now().await;
+        for _ in 0..4 {
+            clock.advance(Duration::from_secs(1)).await;
+            events.send(Event::new(EventKind::Access(AccessKind::Any))).await.unwrap();
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        watcher.stop();
+    }
+
+    #[test]
+    fn test_recording_strategy_drives_library_sync() {
+        let state = tempfile::tempdir().unwrap();
+        let config = Config::from_toml(&format!(r#"
+            state_dir = "{}"
+
+            [[libraries]]
+            name = "anime"
+            source = "/media/anime"
+
+            [[libraries.destinations]]
+            path = "/srv/emby/anime"
+
+            [[libraries.destinations]]
+            path = "https://nas.local/upload"
+
+            [libraries.destinations.retry]
+            max_retries = 2
+            initial_backoff_secs = 0
+        "#, state.path().display())).unwrap();
+        let library = config.library("anime").unwrap().clone();
+        Config::apply(config);
+
+        let recorder = Arc::new(RecordingSyncStrategy::new().with_changed_paths(&["Show/S01E01.strm"]));
+        let sync = LibrarySync::new(library).with_executor(recorder.clone());
+
+        recorder.fail_destination("/srv/emby/anime", "disk full");
+        let error = sync.sync().unwrap_err().to_string();
+        assert!(error.contains("/srv/emby/anime: disk full"));
+
+        // A failing destination doesn't stop the others
+        let calls = recorder.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].strategy, calls[1].strategy), (SyncStrategy::Rsync, SyncStrategy::HttpUpload));
+        assert_eq!(calls[1].destination, "https://nas.local/upload");
+
+        recorder.recover_destination("/srv/emby/anime");
+        let report = sync.sync().unwrap();
+        assert_eq!(recorder.calls().len(), 4);
+        assert_eq!(report.files_transferred, 2, "Added up across destinations");
+        assert_eq!(report.changed_paths, vec!["Show/S01E01.strm".to_string()]);
+
+        let history = SyncHistory::load_since(SyncHistory::default_path(), 0).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].is_failure());
+        assert_eq!(history[1].changed, 1);
+        let reports: Vec<_> = history[1].destinations.iter().filter_map(|run| run.report.as_ref()).collect();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].files_transferred, 1);
+
+        // Transient failures are retried, others fail right away
+        recorder.fail_destination("https://nas.local/upload", "Uploading 'a.strm' failed with status 503 Service Unavailable");
+        assert!(sync.sync().is_err());
+        let upload_calls = recorder.calls().iter().filter(|call| call.destination == "https://nas.local/upload").count();
+        assert_eq!(upload_calls, 2 + 3, "The first attempt and two retries");
+    }
+}
@@ -0,0 +1,680 @@
+#[cfg(test)]
+mod tests {
+
+    use std::{fs, path::Path, time::Duration};
+
+    use tempfile::tempdir;
+
+    use pilipili_strm::{
+        core::{config::Config, library::{LibrarySync, MaintenanceState}, strm::*},
+        infrastructure::fs::{FileRename, MediaKind, OverwritePolicy}
+    };
+
+    #[test]
+    fn test_strm_content_template() {
+        let template = StrmContentTemplate::parse("http://nas:8096/media/{relative_path_encoded}").unwrap();
+        assert_eq!(
+            template.render(Path::new("Anime/Frieren S01/Frieren E01.mkv")),
+            "http://nas:8096/media/Anime/Frieren%20S01/Frieren%20E01.mkv"
+        );
+
+        let template = StrmContentTemplate::parse("{parent}|{file_name}|{file_stem}|{extension}|{file_stem_encoded}").unwrap();
+        assert_eq!(
+            template.render(Path::new("Movies/Up (2009).mp4")),
+            "Movies|Up (2009).mp4|Up (2009)|mp4|Up%20%282009%29"
+        );
+
+        let unknown = StrmContentTemplate::parse("http://nas/{path}");
+        assert!(unknown.unwrap_err().to_string().contains("'{path}'"));
+    }
+
+    #[test]
+    fn test_strm_generator_writes_changed_files() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::create_dir_all(source.path().join("Show/Season 1")).unwrap();
+        fs::write(source.path().join("Show/Season 1/E01.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Show/Season 1/E01.nfo"), b"<episodedetails/>").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path());
+        let written = generator.generate().unwrap();
+        assert_eq!(written, vec![Path::new("Show/Season 1/E01.strm").to_path_buf()]);
+        let strm = target.path().join("Show/Season 1/E01.strm");
+        assert_eq!(
+            fs::read_to_string(&strm).unwrap(),
+            source.path().join("Show/Season 1/E01.mkv").to_string_lossy()
+        );
+        assert!(generator.generate().unwrap().is_empty());
+
+        let template = StrmContentTemplate::parse("http://nas/{relative_path_encoded}").unwrap();
+        let generator = generator.with_content_template(template);
+        assert_eq!(generator.generate().unwrap().len(), 1);
+        assert_eq!(fs::read_to_string(&strm).unwrap(), "http://nas/Show/Season%201/E01.mkv");
+    }
+
+    #[test]
+    fn test_path_mappings() {
+        let mappings = PathMappings::default()
+            .with_mapping("/mnt/media", "smb://nas/media")
+            .with_mapping("/mnt/media/anime/", "/data/anime");
+        assert_eq!(mappings.apply("/mnt/media/movies/Up.mkv"), "smb://nas/media/movies/Up.mkv");
+        assert_eq!(mappings.apply("/mnt/media/anime/Frieren/E01.mkv"), "/data/anime/Frieren/E01.mkv");
+        assert_eq!(mappings.apply("/mnt/media2/Up.mkv"), "/mnt/media2/Up.mkv");
+        assert_eq!(mappings.apply("/mnt/media/movies/Up #2?.mkv"), "smb://nas/media/movies/Up%20%232%3F.mkv");
+        assert_eq!(mappings.apply("/mnt/media/anime/Frieren #1/E01.mkv"), "/data/anime/Frieren #1/E01.mkv");
+
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_path_mappings(PathMappings::new(vec![
+                PathMapping::new(source.path().to_string_lossy(), "http://emby/media/"),
+            ]));
+        generator.generate().unwrap();
+        assert_eq!(
+            fs::read_to_string(target.path().join("Up.strm")).unwrap(),
+            "http://emby/media/Up.mkv"
+        );
+    }
+
+    #[test]
+    fn test_strm_generator_mirrors_companions() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let season = source.path().join("Show/Season 1");
+        fs::create_dir_all(&season).unwrap();
+        fs::write(season.join("E01.mkv"), b"video").unwrap();
+        fs::write(season.join("E01.nfo"), b"<episodedetails/>").unwrap();
+        fs::write(season.join("E01.zh.srt"), b"1").unwrap();
+        fs::write(source.path().join("Show/poster.jpg"), b"jpg").unwrap();
+        fs::write(source.path().join("Show/notes.txt"), b"txt").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path()).with_layout(StrmLayout::Mirror);
+        let mut written = generator.generate_strm_for_dir(&source.path().join("Show")).unwrap();
+        written.sort();
+        assert_eq!(written, vec![
+            Path::new("Show/Season 1/E01.nfo").to_path_buf(),
+            Path::new("Show/Season 1/E01.strm").to_path_buf(),
+            Path::new("Show/Season 1/E01.zh.srt").to_path_buf(),
+            Path::new("Show/poster.jpg").to_path_buf(),
+        ]);
+        assert_eq!(fs::read(target.path().join("Show/poster.jpg")).unwrap(), b"jpg");
+        assert!(!target.path().join("Show/notes.txt").exists());
+        assert!(generator.generate_strm_for_dir(target.path()).is_err(), "Directories outside the source are refused");
+        assert!(generator.generate_strm_for_dir(Path::new("../Show")).is_err());
+        assert!(generator.generate().unwrap().is_empty());
+
+        // Writing next to the originals never copies a file onto itself
+        let in_place = StrmGenerator::new(source.path(), source.path()).with_layout(StrmLayout::Mirror);
+        assert_eq!(in_place.generate().unwrap(), vec![Path::new("Show/Season 1/E01.strm").to_path_buf()]);
+    }
+
+    #[test]
+    fn test_strm_generator_overwrite_policy() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+        let strm = target.path().join("Up.strm");
+        fs::write(&strm, "/old/mount/Up.mkv").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_overwrite_policy(OverwritePolicy::Skip);
+        assert!(generator.generate().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(&strm).unwrap(), "/old/mount/Up.mkv");
+
+        // Stale content pointing at moved media is refreshed
+        let generator = generator.with_overwrite_policy(OverwritePolicy::OverwriteIfChanged);
+        assert_eq!(generator.generate().unwrap().len(), 1);
+        assert!(generator.generate().unwrap().is_empty());
+
+        let generator = generator.with_overwrite_policy(OverwritePolicy::Overwrite);
+        assert_eq!(generator.generate().unwrap().len(), 1);
+        assert_eq!(
+            fs::read_to_string(&strm).unwrap(),
+            source.path().join("Up.mkv").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_strm_generator_skips_recorded_contents() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let state = tempdir().unwrap();
+        let checksum_file = state.path().join("checksums.json");
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Heat.mkv"), b"video").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path()).with_checksum_file(&checksum_file);
+        assert_eq!(generator.generate().unwrap().len(), 2);
+        assert_eq!(StrmChecksums::load(&checksum_file).unwrap().len(), 2);
+
+        // A recorded content is trusted while the file is untouched
+        let generator = StrmGenerator::new(source.path(), target.path()).with_checksum_file(&checksum_file);
+        assert!(generator.generate().unwrap().is_empty());
+
+        // A file edited since is read again and repaired
+        let strm = target.path().join("Up.strm");
+        let content = fs::read_to_string(&strm).unwrap();
+        fs::write(&strm, "edited").unwrap();
+        assert_eq!(generator.generate().unwrap(), vec![Path::new("Up.strm").to_path_buf()]);
+        assert_eq!(fs::read_to_string(&strm).unwrap(), content);
+
+        fs::remove_file(&strm).unwrap();
+        assert_eq!(generator.generate().unwrap(), vec![Path::new("Up.strm").to_path_buf()]);
+
+        let template = StrmContentTemplate::parse("http://nas/{relative_path_encoded}").unwrap();
+        let generator = generator.with_content_template(template);
+        assert_eq!(generator.generate().unwrap().len(), 2);
+        let checksums = StrmChecksums::load(&checksum_file).unwrap();
+        assert!(checksums.matches(Path::new("Heat.strm"), "http://nas/Heat.mkv"));
+
+        fs::remove_file(source.path().join("Heat.mkv")).unwrap();
+        assert_eq!(generator.prune_orphans(Path::new("")).unwrap().removed.len(), 1);
+        assert_eq!(StrmChecksums::load(&checksum_file).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_strm_generator_generates_incrementally() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let state = tempdir().unwrap();
+        let index_file = state.path().join("index.json");
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Heat.mkv"), b"video").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path()).with_index_file(&index_file);
+        assert_eq!(generator.generate_incremental().unwrap().len(), 2);
+        assert_eq!(FileIndex::load(&index_file).unwrap().len(), 2);
+        assert!(generator.generate_incremental().unwrap().is_empty());
+
+        // A file only touched keeps its hash and isn't processed again
+        let up = source.path().join("Up.mkv");
+        let touched = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::options().write(true).open(&up).unwrap().set_modified(touched).unwrap();
+        let generator = StrmGenerator::new(source.path(), target.path()).with_index_file(&index_file);
+        assert!(generator.generate_incremental().unwrap().is_empty());
+        let index = FileIndex::load(&index_file).unwrap();
+        let touched_ms = touched.duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64;
+        assert_eq!(index.get(Path::new("Up.mkv")).unwrap().modified_ms, Some(touched_ms));
+
+        fs::write(&up, b"remastered video").unwrap();
+        fs::write(source.path().join("Alien.mkv"), b"video").unwrap();
+        fs::remove_file(source.path().join("Heat.mkv")).unwrap();
+        // The new content of Up.mkv is indexed, its .strm file stays the same
+        assert_eq!(generator.generate_incremental().unwrap(), vec![Path::new("Alien.strm").to_path_buf()]);
+        assert!(!target.path().join("Heat.strm").exists());
+        let index = FileIndex::load(&index_file).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get(Path::new("Up.mkv")).unwrap().size, 16);
+
+        // A missing .strm file is written again
+        fs::remove_file(target.path().join("Up.strm")).unwrap();
+        assert_eq!(generator.generate_incremental().unwrap(), vec![Path::new("Up.strm").to_path_buf()]);
+
+        // Other settings process every file again
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_path_mappings(PathMappings::default().with_mapping(source.path().to_str().unwrap(), "/media"))
+            .with_index_file(&index_file);
+        assert_eq!(generator.generate_incremental().unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(target.path().join("Up.strm")).unwrap(), "/media/Up.mkv");
+        assert!(generator.generate_incremental().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_strm_generator_prunes_orphans() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let trash = tempdir().unwrap();
+        fs::create_dir_all(source.path().join("Show")).unwrap();
+        fs::write(source.path().join("Show/E01.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Show/E02.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Up.mp4"), b"video").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path());
+        assert_eq!(generator.generate().unwrap().len(), 3);
+        fs::remove_file(source.path().join("Show/E02.mkv")).unwrap();
+        fs::remove_file(source.path().join("Up.mp4")).unwrap();
+
+        // Only the requested directory is pruned
+        let report = generator.prune_orphans(&target.path().join("Show")).unwrap();
+        assert_eq!(report.removed, vec![Path::new("Show/E02.strm").to_path_buf()]);
+        assert!(target.path().join("Show/E01.strm").exists());
+        assert!(target.path().join("Up.strm").exists());
+        assert!(generator.prune_orphans(&source.path().join("Show")).is_err(), "Directories outside the target are refused");
+
+        let generator = generator.with_soft_delete_dir(trash.path());
+        let report = generator.prune_orphans(Path::new("")).unwrap();
+        assert_eq!(report.removed, vec![Path::new("Up.strm").to_path_buf()]);
+        assert!(!target.path().join("Up.strm").exists());
+        let days: Vec<_> = fs::read_dir(trash.path()).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(days.len(), 1, "Soft-deleted files are grouped by day");
+        assert!(days[0].join("Up.strm").exists());
+        assert!(generator.prune_orphans(Path::new("")).unwrap().is_empty());
+
+        // A file removed again the same day doesn't replace the earlier copy
+        fs::write(target.path().join("Up.strm"), "/media/Up.mp4").unwrap();
+        assert_eq!(generator.prune_orphans(Path::new("")).unwrap().removed.len(), 1);
+        assert!(days[0].join("Up.strm").exists());
+        assert_eq!(fs::read_to_string(days[0].join("Up.1.strm")).unwrap(), "/media/Up.mp4");
+    }
+
+    #[test]
+    fn test_strm_generator_purges_soft_deleted_days() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let trash = tempdir().unwrap();
+        fs::create_dir_all(trash.path().join("2020-01-01/Show")).unwrap();
+        fs::write(trash.path().join("2020-01-01/Show/E01.strm"), "/media/Show/E01.mkv").unwrap();
+        fs::write(trash.path().join("Legacy.strm"), "/media/Legacy.mkv").unwrap();
+        fs::create_dir_all(target.path().join("Show")).unwrap();
+        fs::write(target.path().join("Show/E02.strm"), "/media/Show/E02.mkv").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_soft_delete_dir(trash.path())
+            .with_soft_delete_retention(Duration::from_secs(30 * 24 * 60 * 60));
+        let report = generator.prune_orphans(Path::new("")).unwrap();
+        assert_eq!(report.removed, vec![Path::new("Show/E02.strm").to_path_buf()]);
+
+        assert!(!trash.path().join("2020-01-01").exists(), "Days past the retention are purged");
+        assert!(trash.path().join("Legacy.strm").exists(), "Entries not named after a day are kept");
+        let kept: Vec<_> = fs::read_dir(trash.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert!(kept[0].join("Show/E02.strm").exists(), "Files keep their relative path");
+
+        assert!(generator.purge_soft_deleted(Duration::ZERO).unwrap().is_empty(), "Today isn't over yet");
+        assert!(generator.purge_soft_deleted(Duration::MAX).unwrap().is_empty());
+
+        // Generating purges expired days too
+        fs::create_dir_all(trash.path().join("2020-01-02")).unwrap();
+        generator.generate().unwrap();
+        assert!(!trash.path().join("2020-01-02").exists());
+    }
+
+    #[test]
+    fn test_strm_generator_dry_run() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Up.nfo"), b"<movie/>").unwrap();
+        fs::write(target.path().join("Gone.strm"), "/media/Gone.mkv").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_layout(StrmLayout::Mirror)
+            .with_dry_run(true);
+        let mut written = generator.generate().unwrap();
+        written.sort();
+        assert_eq!(written, vec![Path::new("Up.nfo").to_path_buf(), Path::new("Up.strm").to_path_buf()]);
+        let report = generator.prune_orphans(Path::new("")).unwrap();
+        assert_eq!(report.removed, vec![Path::new("Gone.strm").to_path_buf()]);
+
+        let mut left: Vec<_> = fs::read_dir(target.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        left.sort();
+        assert_eq!(left, ["Gone.strm"]);
+    }
+
+    #[tokio::test]
+    async fn test_strm_validator() {
+        let mut server = mockito::Server::new_async().await;
+        let served = server.mock("HEAD", "/media/served.mkv").with_status(200).create_async().await;
+        let gone = server.mock("HEAD", "/media/gone.mkv").with_status(404).create_async().await;
+
+        let media = tempdir().unwrap();
+        let library = tempdir().unwrap();
+        fs::write(media.path().join("Up.mkv"), b"media").unwrap();
+        fs::write(library.path().join("local.strm"), "/mnt/media/Up.mkv\n").unwrap();
+        fs::write(library.path().join("missing.strm"), "/mnt/media/Gone.mkv").unwrap();
+        fs::write(library.path().join("empty.strm"), " \n").unwrap();
+        fs::write(library.path().join("latin1.strm"), b"/mnt/media/Caf\xe9.mkv").unwrap();
+        fs::write(library.path().join("served.strm"), format!("{}/media/served.mkv", server.url())).unwrap();
+        fs::write(library.path().join("gone.strm"), format!("{}/media/gone.mkv", server.url())).unwrap();
+        fs::write(library.path().join("notes.txt"), "").unwrap();
+
+        let validator = StrmValidator::new(library.path())
+            .with_path_mappings(PathMappings::default().with_mapping("/mnt/media", media.path().to_string_lossy()));
+        let report = validator.validate().await.unwrap();
+        assert_eq!(report.checked, 6);
+        assert_eq!(
+            report.issues,
+            vec![
+                StrmIssue { path: "empty.strm".into(), problem: StrmProblem::Empty },
+                StrmIssue { path: "latin1.strm".into(), problem: StrmProblem::InvalidEncoding },
+                StrmIssue {
+                    path: "missing.strm".into(),
+                    problem: StrmProblem::MissingTarget(media.path().join("Gone.mkv")),
+                },
+            ]
+        );
+
+        let report = validator.with_url_check(true).validate().await.unwrap();
+        assert_eq!(report.issues.len(), 4);
+        assert_eq!(report.issues[1].path, Path::new("gone.strm"));
+        assert!(matches!(
+            &report.issues[1].problem,
+            StrmProblem::UnreachableUrl { error, .. } if error.contains("404")
+        ));
+        assert!(!report.is_valid());
+        served.assert_async().await;
+        gone.assert_async().await;
+    }
+
+    #[test]
+    fn test_strm_generator_from_remote_listing() {
+        let files = RcloneListing::parse(r#"[
+            {"Path":"Frieren/E02.mkv","Name":"E02.mkv","Size":1024,"IsDir":false},
+            {"Path":"Frieren","Name":"Frieren","Size":-1,"IsDir":true},
+            {"Path":"Frieren/E01.mkv","Name":"E01.mkv","Size":2048,"IsDir":false},
+            {"Path":"Frieren/poster.jpg","Name":"poster.jpg","Size":512,"IsDir":false}
+        ]"#).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0], RemoteFile { relative: "Frieren/E01.mkv".into(), size: 2048, url: None });
+        assert_eq!(RcloneListing::remote_path("gdrive:", "/Media/Anime/"), "gdrive:Media/Anime");
+
+        let target = tempdir().unwrap();
+        let generator = StrmGenerator::new("/unused", target.path())
+            .with_path_mappings(PathMappings::default().with_mapping("gdrive:Media", "/mnt/gdrive/Media"));
+        let written = generator.generate_from_files("gdrive:Media/Anime", &files).unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(
+            fs::read_to_string(target.path().join("Frieren/E01.strm")).unwrap(),
+            "/mnt/gdrive/Media/Anime/Frieren/E01.mkv"
+        );
+        assert!(!target.path().join("Frieren/poster.jpg").exists());
+
+        let streamed = StrmGenerator::new("/unused", target.path())
+            .with_content_template(StrmContentTemplate::parse("http://rclone:8080/{relative_path_encoded}").unwrap());
+        streamed.generate_from_files("gdrive:", &files).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.path().join("Frieren/E02.strm")).unwrap(),
+            "http://rclone:8080/Frieren/E02.mkv"
+        );
+    }
+
+    #[test]
+    fn test_strm_generator_applies_renames() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        for file in ["Show/E01.mkv", "Show/E01.nfo", "Movie/Movie.mkv"] {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"video").unwrap();
+        }
+        let generator = StrmGenerator::new(source.path(), target.path()).with_layout(StrmLayout::Mirror);
+        generator.generate().unwrap();
+
+        fs::create_dir_all(source.path().join("Season 1")).unwrap();
+        fs::rename(source.path().join("Show/E01.mkv"), source.path().join("Season 1/S01E01.mkv")).unwrap();
+        fs::rename(source.path().join("Show/E01.nfo"), source.path().join("Season 1/S01E01.nfo")).unwrap();
+        fs::rename(source.path().join("Movie"), source.path().join("Film")).unwrap();
+        let rename = |from: &str, to: &str| FileRename { from: from.into(), to: to.into() };
+        let renames = [
+            rename("Show/E01.mkv", "Season 1/S01E01.mkv"),
+            rename("Show/E01.nfo", "Season 1/S01E01.nfo"),
+            rename("Movie", "Film"),
+            rename("Gone.mkv", "Season 1/Gone.mkv"),
+        ];
+
+        let moved = generator.apply_renames(&renames).unwrap();
+        assert_eq!(
+            moved,
+            [Path::new("Season 1/S01E01.strm"), Path::new("Season 1/S01E01.nfo"), Path::new("Film")]
+        );
+        assert!(target.path().join("Season 1/S01E01.strm").is_file());
+        assert!(target.path().join("Film/Movie.strm").is_file());
+        assert!(!target.path().join("Show/E01.strm").exists());
+        assert!(generator.apply_renames(&renames).unwrap().is_empty(), "Moves already applied are skipped");
+    }
+
+    #[test]
+    fn test_strm_generator_groups_disc_and_multi_part_titles() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let files = [
+            "Avatar (2009)/BDMV/index.bdmv",
+            "Avatar (2009)/BDMV/STREAM/00001.m2ts",
+            "Avatar (2009)/BDMV/STREAM/00002.m2ts",
+            "Heat/Heat.cd1.avi",
+            "Heat/Heat.cd2.avi",
+            "Alien/CD1/alien.avi",
+            "Alien/CD2/alien.avi",
+            "Up/Up.part1.mkv",
+        ];
+        for file in files {
+            let path = source.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"video").unwrap();
+        }
+
+        let template = StrmContentTemplate::parse("http://nas/{relative_path}").unwrap();
+        let generator = StrmGenerator::new(source.path(), target.path())
+            .with_content_template(template)
+            .with_title_grouping(true);
+        assert_eq!(
+            generator.generate().unwrap(),
+            vec![
+                Path::new("Alien/Alien.strm").to_path_buf(),
+                Path::new("Avatar (2009)/Avatar (2009).strm").to_path_buf(),
+                Path::new("Heat/Heat.strm").to_path_buf(),
+                Path::new("Up/Up.part1.strm").to_path_buf(),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(target.path().join("Avatar (2009)/Avatar (2009).strm")).unwrap(),
+            "http://nas/Avatar (2009)/BDMV/index.bdmv"
+        );
+        assert_eq!(
+            fs::read_to_string(target.path().join("Heat/Heat.strm")).unwrap(),
+            "stack://http://nas/Heat/Heat.cd1.avi , http://nas/Heat/Heat.cd2.avi"
+        );
+        assert!(generator.prune_orphans(Path::new("")).unwrap().removed.is_empty());
+
+        let titles = MediaTitle::group(
+            &[Path::new("Show/Disc 1/E01.mkv").to_path_buf(), Path::new("Show/Disc 1/E02.mkv").to_path_buf()],
+            |_| true,
+        );
+        assert!(titles.iter().all(|title| title.kind == MediaTitleKind::Single));
+
+        assert_eq!(MediaTitle::part_of(Path::new("Heat/Heat - Disc 2.mkv")), Some((Path::new("Heat/Heat.strm").to_path_buf(), 2)));
+        assert_eq!(MediaTitle::part_of(Path::new("Films/Apt2.mkv")), None);
+        assert_eq!(MediaTitle::part_of(Path::new("Films/Concept 2.mkv")), None);
+    }
+
+    #[test]
+    fn test_strm_generator_sniffs_signatures() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::write(source.path().join("Heat"), [0x1A, 0x45, 0xDF, 0xA3, 0x01]).unwrap();
+        fs::write(source.path().join("notes.mkv"), "not a video").unwrap();
+        fs::write(source.path().join("Up.mkv"), [0x1A, 0x45, 0xDF, 0xA3, 0x01]).unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path());
+        assert_eq!(generator.generate().unwrap().len(), 2);
+
+        let target = tempdir().unwrap();
+        let generator = StrmGenerator::new(source.path(), target.path()).with_signature_sniffing(true);
+        assert_eq!(
+            generator.generate().unwrap(),
+            vec![Path::new("Heat.strm").to_path_buf(), Path::new("Up.strm").to_path_buf()]
+        );
+    }
+
+    #[test]
+    fn test_strm_generator_skips_media_kinds() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        fs::create_dir_all(source.path().join("Up/Trailers")).unwrap();
+        fs::write(source.path().join("Up/Up.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Up/Up-sample.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Up/Trailers/Teaser.mkv"), b"video").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), target.path());
+        assert_eq!(generator.generate().unwrap().len(), 3);
+        assert_eq!(generator.kind_of(Path::new("Up/Trailers/Teaser.mkv")), MediaKind::Trailer);
+
+        let generator = generator.with_skipped_kinds(vec![MediaKind::Trailer, MediaKind::Sample]);
+        let report = generator.prune_orphans(Path::new("")).unwrap();
+        assert_eq!(report.removed.len(), 2);
+        assert!(target.path().join("Up/Up.strm").exists());
+        assert!(generator.generate().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_strm_generator_swaps_staged_regeneration() {
+        let source = tempdir().unwrap();
+        let library = tempdir().unwrap();
+        let target = library.path().join("strm");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("Gone.strm"), "/media/Gone.mkv").unwrap();
+        fs::write(source.path().join("Up.mkv"), b"video").unwrap();
+
+        let generator = StrmGenerator::new(source.path(), &target);
+        assert_eq!(generator.generate_staged(SwapMode::Rename).unwrap().len(), 1);
+        assert!(target.join("Up.strm").exists());
+        assert!(!target.join("Gone.strm").exists());
+        assert_eq!(fs::read_dir(library.path()).unwrap().count(), 1);
+
+        // Staging in a configured directory
+        let staging = library.path().join("staging");
+        let staged = generator.clone().with_staging_dir(&staging);
+        assert_eq!(staged.generate_staged(SwapMode::Rename).unwrap().len(), 1);
+        assert!(target.join("Up.strm").exists());
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+        fs::remove_dir(&staging).unwrap();
+
+        #[cfg(unix)]
+        {
+            fs::write(source.path().join("Heat.mkv"), b"video").unwrap();
+            assert_eq!(generator.generate_staged(SwapMode::Symlink).unwrap().len(), 2);
+            assert!(fs::symlink_metadata(&target).unwrap().file_type().is_symlink());
+            assert!(target.join("Heat.strm").exists());
+
+            generator.generate_staged(SwapMode::Symlink).unwrap();
+            // The symlink and the release it points at
+            assert_eq!(fs::read_dir(library.path()).unwrap().count(), 2);
+
+            // A directory the symlink was pointed at by hand is kept
+            let manual = tempdir().unwrap();
+            fs::write(manual.path().join("Keep.strm"), "/media/Keep.mkv").unwrap();
+            let release = fs::read_link(&target).unwrap();
+            fs::remove_file(&target).unwrap();
+            fs::remove_dir_all(library.path().join(release)).unwrap();
+            std::os::unix::fs::symlink(manual.path(), &target).unwrap();
+            generator.generate_staged(SwapMode::Symlink).unwrap();
+            assert!(manual.path().join("Keep.strm").exists());
+            assert!(target.join("Up.strm").exists());
+        }
+    }
+
+    #[test]
+    fn test_generate_only_library() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        let state = tempdir().unwrap();
+        fs::create_dir_all(source.path().join("Up")).unwrap();
+        fs::write(source.path().join("Up/Up.mkv"), b"video").unwrap();
+        fs::write(source.path().join("Up/Up.nfo"), b"<movie/>").unwrap();
+
+        let config = Config::from_toml(&format!(r#"
+            state_dir = "{}"
+
+            [[libraries]]
+            name = "movies"
+            source = "{}"
+
+            [libraries.strm]
+            target = "{}"
+            mirror = true
+            content_template = "http://nas/{{relative_path_encoded}}"
+        "#, state.path().display(), source.path().display(), target.path().display())).unwrap();
+        let library = config.library("movies").unwrap().clone();
+        assert!(library.is_generate_only());
+        assert!(library.destination_strategies().unwrap().is_empty());
+        Config::apply(config);
+
+        let sync = LibrarySync::new(library);
+        sync.sync().unwrap();
+        assert_eq!(fs::read_to_string(target.path().join("Up/Up.strm")).unwrap(), "http://nas/Up/Up.mkv");
+        assert!(target.path().join("Up/Up.nfo").exists());
+        assert!(sync.plan().unwrap().is_empty());
+
+        // Removed media is pruned on the next sync
+        fs::remove_file(source.path().join("Up/Up.mkv")).unwrap();
+        sync.sync().unwrap();
+        assert!(!target.path().join("Up/Up.strm").exists());
+
+        // Maintenance mode keeps generating the mirror and only defers destinations
+        let maintenance = MaintenanceState::default_path();
+        MaintenanceState::set_enabled_persisted(&maintenance, true).unwrap();
+        fs::write(source.path().join("Up/Up.mkv"), b"video").unwrap();
+        sync.sync().unwrap();
+        assert!(target.path().join("Up/Up.strm").exists());
+        assert!(MaintenanceState::load(&maintenance).unwrap().pending_libraries().is_empty());
+
+        let destination = tempdir().unwrap();
+        let mut library = sync.config().clone();
+        library.destinations = Config::from_toml(&format!(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+
+            [[libraries.destinations]]
+            path = "{}"
+        "#, destination.path().display())).unwrap().library("movies").unwrap().destinations.clone();
+        fs::write(source.path().join("Up/Up.en.srt"), b"subtitle").unwrap();
+        LibrarySync::new(library).sync().unwrap();
+        assert!(target.path().join("Up/Up.en.srt").exists());
+        assert_eq!(fs::read_dir(destination.path()).unwrap().count(), 0);
+        assert_eq!(MaintenanceState::load(&maintenance).unwrap().pending_libraries(), vec!["movies".to_string()]);
+
+        let error = Config::from_toml(r#"
+            [[libraries]]
+            name = "movies"
+            source = "/media/movies"
+        "#).unwrap().library("movies").unwrap().destination_strategies().unwrap_err();
+        assert!(error.to_string().contains("no destinations or strm target"));
+    }
+
+    #[test]
+    fn test_strm_import_detects_styles() {
+        assert_eq!(StrmStyle::detect("/mnt/media/Up.mp4\n"), StrmStyle::LocalPath);
+        assert_eq!(StrmStyle::detect("http://nas:8096/media/Up.mp4"), StrmStyle::HttpUrl);
+        assert_eq!(StrmStyle::detect("https://alist/d/Movies/Up.mp4?sign=abc=:0"), StrmStyle::AlistSign);
+        assert_eq!(StrmStyle::detect("rtsp://camera/stream"), StrmStyle::Other);
+        assert_eq!(StrmStyle::detect("  \n"), StrmStyle::Other);
+
+        let dir = tempdir().unwrap();
+        let files = [
+            ("Anime/Frieren E01.strm", "http://nas:8096/media/Anime/Frieren%20E01.mkv"),
+            ("Anime/Frieren E02.strm", "http://nas:8096/media/Anime/Frieren%20E02.mkv"),
+            ("Movies/Up.strm", "https://alist/d/Movies/Up.mp4?sign=abc=:0"),
+            ("Movies/Heat.strm", "/mnt/media/Movies/Heat.mkv"),
+            ("Live.strm", "rtsp://camera/stream"),
+        ];
+        for (path, content) in files {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+
+        let report = StrmImporter::new(dir.path()).import().unwrap();
+        assert_eq!(report.scanned, 5);
+        let styles: Vec<(StrmStyle, usize)> = report.styles.iter().map(|(style, count)| (*style, *count)).collect();
+        assert_eq!(
+            styles,
+            [(StrmStyle::LocalPath, 1), (StrmStyle::HttpUrl, 2), (StrmStyle::AlistSign, 1), (StrmStyle::Other, 1)]
+        );
+        assert_eq!(report.suggested_template.as_deref(), Some("http://nas:8096/media/{relative_path_encoded}"));
+        assert_eq!(report.matching, 2);
+        assert!(report.to_string().contains("HTTP URL: 2"));
+
+        assert!(StrmImporter::new(dir.path()).with_normalize(true).import().is_err(), "Normalizing needs a template");
+        let template = StrmContentTemplate::parse("http://emby/{relative_path_encoded}").unwrap();
+        let report = StrmImporter::new(dir.path()).with_template(template).with_normalize(true).import().unwrap();
+        assert_eq!(report.normalized, 4, "Files whose media path is unknown are left alone");
+        assert_eq!(fs::read_to_string(dir.path().join("Movies/Up.strm")).unwrap(), "http://emby/Movies/Up.mp4");
+        assert_eq!(fs::read_to_string(dir.path().join("Movies/Heat.strm")).unwrap(), "http://emby/Movies/Heat.mkv");
+        assert_eq!(fs::read_to_string(dir.path().join("Live.strm")).unwrap(), "rtsp://camera/stream");
+    }
+}
@@ -0,0 +1,275 @@
+#[cfg(test)]
+mod tests {
+
+    use tokio::sync::Mutex;
+
+    use pilipili_strm::core::{
+        api::*,
+        client::*,
+        config::*,
+        notification::{NotificationQueue, FlushReport}
+    };
+
+    /// Serializes tests since each one points the global config at its own mock server
+    static CONFIG_LOCK: Mutex<()> = Mutex::const_new(());
+
+    fn apply_config(api_base: &str) {
+        let mut config = Config::default();
+        config.telegram.bot_token = "123:abc".to_string();
+        config.telegram.api_base = Some(api_base.to_string());
+        Config::apply(config);
+    }
+
+    #[tokio::test]
+    async fn test_download_file_to() {
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        apply_config(&server.url());
+
+        let get_file = server.mock("POST", "/bot123:abc/getFile")
+            .match_body(mockito::Matcher::Json(serde_json::json!({ "file_id": "doc-1" })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":{"file_id":"doc-1","file_unique_id":"u1","file_size":9,"file_path":"documents/file_0.toml"}}"#)
+            .create_async()
+            .await;
+        let download = server.mock("GET", "/file/bot123:abc/documents/file_0.toml")
+            .with_body("[emby]\n")
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("config.toml");
+        let client = TelegramClient::builder().build();
+        let file = client.download_file_to("doc-1", &destination).await.unwrap();
+
+        get_file.assert_async().await;
+        download.assert_async().await;
+        assert_eq!(file.file_path.as_deref(), Some("documents/file_0.toml"));
+        assert_eq!(std::fs::read_to_string(&destination).unwrap(), "[emby]\n");
+    }
+
+    #[tokio::test]
+    async fn test_set_webhook() {
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        apply_config(&server.url());
+
+        let set_webhook = server.mock("POST", "/bot123:abc/setWebhook")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "url": "https://example.com/telegram",
+                "secret_token": "s3cret",
+                "allowed_updates": ["message"],
+                "drop_pending_updates": true
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":true,"description":"Webhook was set"}"#)
+            .create_async()
+            .await;
+
+        let client = TelegramClient::builder().build();
+        let webhook = WebhookConfig::new("https://example.com/telegram")
+            .with_secret_token("s3cret")
+            .with_allowed_updates(vec!["message"])
+            .with_drop_pending_updates(true);
+        let response = client.set_webhook(webhook).await.unwrap();
+
+        set_webhook.assert_async().await;
+        assert!(response.ok);
+        assert_eq!(response.result, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_get_webhook_info() {
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        apply_config(&server.url());
+
+        server.mock("POST", "/bot123:abc/getWebhookInfo")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":{"url":"https://example.com/telegram","has_custom_certificate":false,"pending_update_count":3,"last_error_message":"Connection refused"}}"#)
+            .create_async()
+            .await;
+
+        let client = TelegramClient::builder().build();
+        let info = client.get_webhook_info().await.unwrap().result.unwrap();
+
+        assert_eq!(info.url, "https://example.com/telegram");
+        assert_eq!(info.pending_update_count, 3);
+        assert_eq!(info.last_error_message.as_deref(), Some("Connection refused"));
+    }
+
+    #[test]
+    fn test_parse_update() {
+        let update: Update = serde_json::from_str(r#"{
+            "update_id": 1001,
+            "message": {
+                "message_id": 7,
+                "from": { "id": 42, "is_bot": false, "first_name": "Admin", "username": "admin" },
+                "chat": { "id": 42, "type": "private", "first_name": "Admin" },
+                "text": "/sync"
+            }
+        }"#).unwrap();
+
+        let message = update.message.unwrap();
+        assert_eq!(update.update_id, 1001);
+        assert_eq!(message.from.unwrap().id, 42);
+        assert_eq!(message.text.as_deref(), Some("/sync"));
+    }
+
+    #[test]
+    fn test_verify_webhook_secret_token() {
+        let webhook = WebhookConfig::new("https://example.com/telegram")
+            .with_secret_token("s3cret");
+
+        assert!(webhook.verify_secret_token(Some("s3cret")));
+        assert!(!webhook.verify_secret_token(Some("wrong")));
+        assert!(!webhook.verify_secret_token(Some("s3creT")));
+        assert!(!webhook.verify_secret_token(Some("s3cret2")));
+        assert!(!webhook.verify_secret_token(None));
+        assert!(WebhookConfig::new("https://example.com").verify_secret_token(None));
+    }
+
+    #[tokio::test]
+    async fn test_send_photo_caption_overflow() {
+        let caption = format!("*Frieren*\n{}", "Episode 01\\.\n".repeat(100));
+        let (truncated, follow_up) = PhotoMessage::from_url("https://tmdb/poster.jpg")
+            .with_caption(caption.clone())
+            .split_caption();
+        let truncated = truncated.caption.unwrap();
+        assert!(follow_up.is_none());
+        assert!(truncated.encode_utf16().count() <= TELEGRAM_CAPTION_MAX_LEN);
+        assert!(truncated.starts_with("*Frieren*\n") && truncated.ends_with("Episode 01\\.…"));
+
+        let linked = format!("*Frieren [{}](https://tmdb/1\\)) after*", "S01\\.E01\\-".repeat(200));
+        let (truncated, _) = PhotoMessage::from_url("https://tmdb/poster.jpg")
+            .with_caption(linked)
+            .split_caption();
+        let truncated = truncated.caption.unwrap();
+        let (kept, closing) = truncated.split_once('…').unwrap();
+        assert!(truncated.encode_utf16().count() <= TELEGRAM_CAPTION_MAX_LEN);
+        assert!(kept.starts_with("*Frieren [S01\\.E01\\-"));
+        assert_eq!((kept.len() - kept.trim_end_matches('\\').len()) % 2, 0);
+        assert_eq!(closing, "](https://tmdb/1\\))*");
+
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        apply_config(&server.url());
+
+        let send_photo = server.mock("POST", "/bot123:abc/sendPhoto")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":{"message_id":7,"chat":{"id":42,"type":"private"}}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let send_caption = server.mock("POST", "/bot123:abc/sendMessage")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "text": caption,
+                "reply_parameters": { "message_id": 7 }
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":{"message_id":8,"chat":{"id":42,"type":"private"}}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = TelegramClient::builder().build();
+        let photo = PhotoMessage::from_url("https://tmdb/poster.jpg")
+            .with_caption(caption.clone())
+            .with_caption_overflow(CaptionOverflow::FollowUp);
+        let response = client.send_photo(photo).await.unwrap();
+
+        send_photo.assert_async().await;
+        send_caption.assert_async().await;
+        assert_eq!(response.result.unwrap().message_id, 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_photo_url_through_artwork_cache() {
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.telegram.bot_token = "123:abc".to_string();
+        config.telegram.api_base = Some(server.url());
+        config.state_dir = Some(dir.path().to_string_lossy().into_owned());
+        Config::apply(config);
+
+        let poster = server.mock("GET", "/Items/1/Images/Primary")
+            .with_body("poster-bytes")
+            .expect(1)
+            .create_async()
+            .await;
+        let send_photo = server.mock("POST", "/bot123:abc/sendPhoto")
+            .match_body(mockito::Matcher::Regex("poster-bytes".to_string()))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ok":true,"result":{"message_id":7,"chat":{"id":42,"type":"private"}}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        // The poster is downloaded once and uploaded from the cache both times
+        let client = TelegramClient::builder().build();
+        let url = format!("{}/Items/1/Images/Primary", server.url());
+        client.send_photo(PhotoMessage::from_url(url.as_str())).await.unwrap();
+        client.send_photo(PhotoMessage::from_url(url.as_str())).await.unwrap();
+
+        poster.assert_async().await;
+        send_photo.assert_async().await;
+        assert_eq!(ArtworkCache::open(ArtworkCache::default_dir(&Config::get())).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notification_queue_keeps_order_per_chat() {
+        let _guard = CONFIG_LOCK.lock().await;
+        let mut server = mockito::Server::new_async().await;
+        apply_config(&server.url());
+
+        let ok = r#"{"ok":true,"result":{"message_id":1,"chat":{"id":1,"type":"private"}}}"#;
+        let mut send = |chat_id: &str, text: &str| server.mock("POST", "/bot123:abc/sendMessage")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "chat_id": chat_id, "text": text })))
+            .with_header("content-type", "application/json");
+        let unavailable = send("1", "first")
+            .with_status(502)
+            .with_body(r#"{"ok":false,"error_code":502,"description":"Bad Gateway"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let other_chat = send("2", "other").with_body(ok).expect(1).create_async().await;
+        let rejected = send("2", "bad *markdown")
+            .with_status(400)
+            .with_body(r#"{"ok":false,"error_code":400,"description":"Bad Request: can't parse entities"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let queue = NotificationQueue::new(dir.path().join("queue.json"));
+        queue.enqueue("1", "first").unwrap();
+        let second = queue.enqueue("1", "second").unwrap();
+        queue.enqueue("2", "other").unwrap();
+        queue.enqueue("2", "bad *markdown").unwrap();
+
+        // The second message of chat 1 waits behind the failed first one
+        let client = TelegramClient::builder().build();
+        let report = queue.flush(&client).await.unwrap();
+        assert_eq!(report, FlushReport { sent: 1, dropped: 1, pending: 2 });
+        unavailable.assert_async().await;
+        other_chat.assert_async().await;
+        rejected.assert_async().await;
+
+        // Unsent messages survive a restart and go out in order
+        let queue = NotificationQueue::new(queue.path());
+        let pending = queue.pending().unwrap();
+        assert_eq!(pending.iter().map(|message| message.text.as_str()).collect::<Vec<_>>(), ["first", "second"]);
+        assert_eq!(pending[0].attempts, 1);
+        assert!(queue.contains(second));
+
+        unavailable.remove_async().await;
+        let first = send("1", "first").with_body(ok).create_async().await;
+        let second_sent = send("1", "second").with_body(ok).create_async().await;
+        assert_eq!(queue.flush(&client).await.unwrap().sent, 2);
+        first.assert_async().await;
+        second_sent.assert_async().await;
+        assert_eq!(queue.depth(), 0);
+    }
+}
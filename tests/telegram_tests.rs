@@ -3,8 +3,6 @@ mod tests {
 
     use std::path::PathBuf;
 
-    use tokio;
-
     use pilipili_strm::{
         core::{ 
             api::*,
@@ -12,7 +10,7 @@ mod tests {
         },
         infrastructure::{ 
             logger::{builder::LoggerBuilder, LogLevel},
-            network::{curl_plugin::CurlPlugin}
+            network::{curl_plugin::CurlPlugin, NetworkTask}
         },
         info_log,
         error_log
@@ -34,6 +32,8 @@ mod tests {
         let text_msg = TextMessage {
             text: "Test message".to_string(),
             reply_markup: None,
+            chat_id: None,
+            reply_parameters: None,
         };
         let response = client.send_message(text_msg).await;
         match response {
@@ -55,7 +55,8 @@ mod tests {
             .build();
         let photo_msg = PhotoMessage {
             photo: PhotoInput::Url("https://cdn.pixabay.com/photo/2023/12/07/11/11/girl-8435340_1280.png".to_string()),
-            caption: Some("description of photo".to_string())
+            caption: Some("description of photo".to_string()),
+            caption_overflow: CaptionOverflow::default()
         };
         let response = client.send_photo(photo_msg).await;
         match response {
@@ -79,7 +80,8 @@ mod tests {
             .join("tests/telegram_photo.png");
         let photo_msg = PhotoMessage {
             photo: PhotoInput::FilePath(photo_path),
-            caption: Some("description of photo".to_string())
+            caption: Some("description of photo".to_string()),
+            caption_overflow: CaptionOverflow::default()
         };
         let response = client.send_photo(photo_msg).await;
         match response {
@@ -91,4 +93,49 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_photo_message_local_task() {
+        let photo_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/telegram_photo.png");
+        let photo_msg = PhotoMessage::from_file(&photo_path)
+            .with_caption("description of photo");
+
+        match photo_msg.into_local_task("42".to_string()) {
+            NetworkTask::RequestMultipart(fields) => {
+                let expected = format!("file://{}", photo_path.canonicalize().unwrap().display());
+                assert_eq!(fields.get("photo"), Some(&expected));
+                assert_eq!(fields.get("chat_id"), Some(&"42".to_string()));
+            }
+            task => panic!("Expected multipart task without files, got {:?}", task),
+        }
+    }
+
+    #[test]
+    fn test_photo_message_converts_lazily() {
+        let dir = tempfile::tempdir().unwrap();
+        let fanart = dir.path().join("fanart.png");
+        image::RgbImage::new(10_000, 50).save(&fanart).unwrap();
+
+        let photo_msg = PhotoMessage::from_file(&fanart);
+        assert!(matches!(&photo_msg.photo, PhotoInput::FilePath(path) if *path == fanart), "Nothing is converted yet");
+
+        let converted = |task: NetworkTask| match task {
+            NetworkTask::RequestMultipartWithFiles(_, files) => PathBuf::from(&files[0].0),
+            task => panic!("Expected multipart task with files, got {:?}", task),
+        };
+        let first = converted(photo_msg.clone().into_task("42".to_string()));
+        assert_ne!(first, fanart);
+        let (width, height) = image::image_dimensions(&first).unwrap();
+        assert!(width + height <= 10_000);
+        assert_eq!(converted(photo_msg.clone().into_task("42".to_string())), first, "The conversion is reused");
+        // Converted photos are uploaded, as a local server can't read the private directory
+        assert_eq!(converted(photo_msg.into_local_task("42".to_string())), first);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(first.parent().unwrap()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+
+    use pilipili_strm::{
+        core::{
+            api::*,
+            client::*
+        },
+        infrastructure::fs::ProgressEvent
+    };
+
+    #[tokio::test]
+    async fn test_upload_resumes_only_own_uploads() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("journal.json");
+        let file = dir.path().join("Big Buck.strm");
+        std::fs::write(&file, "0123456789").unwrap();
+        let client = |server: &mockito::Server| {
+            let endpoint = UploadEndpoint::new(server.url()).with_authorization("Bearer t0ken");
+            UploadClient::builder(endpoint).with_chunk_size(4).with_journal(&journal).build()
+        };
+
+        // A remote file of another origin is overwritten, not resumed
+        let mut server = mockito::Server::new_async().await;
+        let query = server.mock("HEAD", "/movies/Big%20Buck.strm")
+            .match_header("authorization", "Bearer t0ken")
+            .with_header("upload-offset", "4")
+            .create_async()
+            .await;
+        let first = server.mock("PUT", "/movies/Big%20Buck.strm")
+            .match_header("content-range", "bytes 0-3/10")
+            .create_async()
+            .await;
+        let interrupted = server.mock("PUT", "/movies/Big%20Buck.strm")
+            .match_header("content-range", "bytes 4-7/10")
+            .with_status(500)
+            .create_async()
+            .await;
+        assert!(client(&server).upload_file(&file, "movies/Big Buck.strm").await.is_err());
+        query.assert_async().await;
+        first.assert_async().await;
+        interrupted.assert_async().await;
+
+        // The interrupted upload resumes
+        let mut server = mockito::Server::new_async().await;
+        server.mock("HEAD", "/movies/Big%20Buck.strm").with_header("upload-offset", "4").create_async().await;
+        let second = server.mock("PUT", "/movies/Big%20Buck.strm")
+            .match_header("content-range", "bytes 4-7/10")
+            .match_body("4567")
+            .create_async()
+            .await;
+        let third = server.mock("PUT", "/movies/Big%20Buck.strm")
+            .match_header("content-range", "bytes 8-9/10")
+            .match_body("89")
+            .create_async()
+            .await;
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+        let endpoint = UploadEndpoint::new(server.url());
+        let resuming = UploadClient::builder(endpoint)
+            .with_chunk_size(4)
+            .with_journal(&journal)
+            .with_progress_sender(sender)
+            .build();
+        assert_eq!(resuming.upload_file(&file, "movies/Big Buck.strm").await.unwrap(), Some(6));
+        second.assert_async().await;
+        third.assert_async().await;
+
+        // Progress is reported after each chunk
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        let percents: Vec<u8> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::FileProgress { percent, .. } => Some(*percent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(events.first().unwrap().to_string(), "movies/Big Buck.strm started");
+        assert_eq!(percents, vec![80, 100]);
+        assert_eq!(events.last().unwrap().to_string(), "movies/Big Buck.strm done");
+    }
+
+    #[tokio::test]
+    async fn test_upload_dir_skips_complete_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::create_dir_all(source.join("show")).unwrap();
+        std::fs::write(source.join("a.strm"), "aaa").unwrap();
+        std::fs::write(source.join("show").join("b.strm"), "bbb").unwrap();
+        std::fs::write(source.join("show").join("b.nfo"), "ignored").unwrap();
+        let journal = dir.path().join("journal.json");
+        let upload_dir = |server: &mockito::Server| {
+            let client = UploadClient::builder(UploadEndpoint::new(server.url())).with_journal(&journal).build();
+            let source = source.clone();
+            async move {
+                client
+                    .upload_dir(&source, |path| path.extension().is_some_and(|ext| ext == "strm"))
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let mut server = mockito::Server::new_async().await;
+        for path in ["/a.strm", "/show/b.strm"] {
+            server.mock("HEAD", path).with_status(404).create_async().await;
+            server.mock("PUT", path).match_header("content-range", "bytes 0-2/3").create_async().await;
+        }
+        assert_eq!(upload_dir(&server).await, vec!["a.strm".to_string(), "show/b.strm".to_string()]);
+
+        // Complete uploads of unchanged files are skipped
+        let mut server = mockito::Server::new_async().await;
+        for path in ["/a.strm", "/show/b.strm"] {
+            server.mock("HEAD", path).with_header("upload-offset", "3").create_async().await;
+        }
+        assert!(upload_dir(&server).await.is_empty());
+
+        // A file replaced with one of the same size is uploaded again
+        let replaced = std::fs::File::options().write(true).open(source.join("a.strm")).unwrap();
+        replaced.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        let mut server = mockito::Server::new_async().await;
+        for path in ["/a.strm", "/show/b.strm"] {
+            server.mock("HEAD", path).with_header("upload-offset", "3").create_async().await;
+        }
+        let upload = server.mock("PUT", "/a.strm").match_header("content-range", "bytes 0-2/3").create_async().await;
+        assert_eq!(upload_dir(&server).await, vec!["a.strm".to_string()]);
+        upload.assert_async().await;
+    }
+}